@@ -2,9 +2,11 @@ use criterion::{criterion_group, criterion_main, Criterion};
 use eidolon::{
     camera::Camera,
     character::Character,
+    converter,
     renderer::Renderer,
     OutputFormat,
 };
+use image::{imageops::FilterType, DynamicImage};
 use std::fs;
 
 fn performance_benchmark(c: &mut Criterion) {
@@ -48,5 +50,23 @@ fn performance_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, performance_benchmark);
+fn conversion_benchmark(c: &mut Criterion) {
+    let legacy = image::open("resources/SSSSSteven.png").expect("Failed to load legacy skin");
+    let hd = DynamicImage::ImageRgba8(image::imageops::resize(
+        &legacy,
+        1024,
+        512,
+        FilterType::Nearest,
+    ));
+    let batch = vec![hd.clone(); 64];
+
+    c.bench_function("convert_hd_skin", |b| {
+        b.iter(|| converter::single2double(&hd).unwrap())
+    });
+    c.bench_function("convert_64_hd_skins", |b| {
+        b.iter(|| converter::single2double_batch(&batch))
+    });
+}
+
+criterion_group!(benches, performance_benchmark, conversion_benchmark);
 criterion_main!(benches);
@@ -0,0 +1,1204 @@
+//! Animated renders: an [`Animation`] samples a keyframed [`CameraPath`] (orbits, zoom-in
+//! reveals, crane shots) at a frame rate, optionally layers procedural [`Idle`] motion over the
+//! character's pose and changes facial expressions over time ([`ExpressionKey`]), and the rendered
+//! frames are encoded as a looping GIF ([`encode_gif`], or [`encode_gif_with`] to choose the
+//! palette, dithering and transparency).
+//!
+//! Camera paths only move the camera, so a path combines with any pose.
+
+use std::f32::consts::TAU;
+
+use image::RgbaImage;
+
+use crate::camera::Camera;
+use crate::cancel::CancelToken;
+use crate::character::Character;
+use crate::error::EidolonError;
+use crate::expression::Expression;
+use crate::palette::{Dither, Palette, ALPHA_CUTOFF};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{
+    renderer::{RenderOptions, Renderer},
+    texture::{SkinImage, Texture},
+};
+
+/// Seconds per breath of [`Idle`].
+const BREATH_SECONDS: f32 = 3.5;
+/// Seconds per arm sway of [`Idle`].
+const SWAY_SECONDS: f32 = 5.0;
+/// [`Idle`] turns the head about once per this many seconds.
+const HEAD_TURN_SECONDS: f32 = 4.0;
+/// Seconds to turn the head, hold, and turn back.
+const HEAD_TURN_PHASES: [f32; 3] = [0.5, 1.2, 0.5];
+/// Shortest and longest gap between [`ExpressionKey::blinks`], and how long a blink lasts.
+const BLINK_GAP: (f32, f32) = (2.5, 5.0);
+const BLINK_SECONDS: f32 = 0.15;
+
+/// How a segment of a [`CameraPath`] speeds up and slows down.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Easing {
+    /// Constant speed; orbits with it loop seamlessly.
+    #[default]
+    Linear,
+    /// Starts slowly, ends at full speed.
+    EaseIn,
+    /// Starts at full speed, settles into the keyframe.
+    EaseOut,
+    /// Slow at both ends.
+    EaseInOut,
+}
+
+impl Easing {
+    /// Eased progress for linear progress `t` in 0–1 (cubic curves).
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::EaseInOut => 1.0 - (2.0 - 2.0 * t).powi(3) / 2.0,
+        }
+    }
+}
+
+/// Camera at a point in time of a [`CameraPath`].
+#[derive(Debug, Clone, Copy)]
+pub struct CameraKeyframe {
+    /// Seconds from the start of the animation.
+    pub time: f32,
+    pub camera: Camera,
+    /// Easing of the segment from the previous keyframe into this one.
+    pub easing: Easing,
+}
+
+/// Keyframed camera moves. Between keyframes yaw and pitch are interpolated in degrees (so a yaw
+/// of 180 to 540 is a full turn) and zoom geometrically, which reads as a steady dolly.
+#[derive(Debug, Clone)]
+pub struct CameraPath {
+    /// Sorted by time; never empty.
+    keyframes: Vec<CameraKeyframe>,
+}
+
+impl CameraPath {
+    /// A path that starts at `camera` at time 0.
+    pub fn new(camera: Camera) -> Self {
+        Self {
+            keyframes: vec![CameraKeyframe {
+                time: 0.0,
+                camera,
+                easing: Easing::Linear,
+            }],
+        }
+    }
+
+    /// Add a keyframe at `time` seconds, replacing any keyframe already at that time.
+    pub fn key(mut self, time: f32, camera: Camera, easing: Easing) -> Self {
+        let keyframe = CameraKeyframe {
+            time: time.max(0.0),
+            camera,
+            easing,
+        };
+        match self
+            .keyframes
+            .binary_search_by(|k| k.time.total_cmp(&keyframe.time))
+        {
+            Ok(i) => self.keyframes[i] = keyframe,
+            Err(i) => self.keyframes.insert(i, keyframe),
+        }
+        self
+    }
+
+    /// `turns` full orbits (negative turns go the other way) around the character from `camera`
+    /// over `duration` seconds.
+    pub fn orbit(camera: Camera, turns: f32, duration: f32, easing: Easing) -> Self {
+        let end = Camera {
+            yaw: camera.yaw + 360.0 * turns,
+            ..camera
+        };
+        Self::new(camera).key(duration, end, easing)
+    }
+
+    /// Zoom-in reveal: starts with the character at a third of its final size and settles on
+    /// `camera`.
+    pub fn zoom_reveal(camera: Camera, duration: f32) -> Self {
+        // On-screen size goes with the square of the zoom, which also scales the model.
+        let start = Camera {
+            scale: camera.scale / 3f32.sqrt(),
+            ..camera
+        };
+        Self::new(start).key(duration, camera, Easing::EaseOut)
+    }
+
+    /// Crane shot: rises from near the ground, drifting sideways, to end on `camera`.
+    pub fn crane(camera: Camera, duration: f32) -> Self {
+        let start = Camera {
+            yaw: camera.yaw - 30.0,
+            pitch: camera.pitch - 35.0,
+            scale: camera.scale * 0.8,
+        };
+        Self::new(start).key(duration, camera, Easing::EaseInOut)
+    }
+
+    pub fn keyframes(&self) -> &[CameraKeyframe] {
+        &self.keyframes
+    }
+
+    /// Time of the last keyframe.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    /// The camera at `time` seconds; before the first and after the last keyframe the camera
+    /// holds still.
+    pub fn camera_at(&self, time: f32) -> Camera {
+        let next = self.keyframes.partition_point(|k| k.time <= time);
+        if next == 0 {
+            return self.keyframes[0].camera;
+        }
+        let Some(to) = self.keyframes.get(next) else {
+            return self.keyframes[next - 1].camera;
+        };
+        let from = &self.keyframes[next - 1];
+        let t = to.easing.apply((time - from.time) / (to.time - from.time));
+        let (a, b) = (from.camera, to.camera);
+        Camera {
+            yaw: a.yaw + (b.yaw - a.yaw) * t,
+            pitch: a.pitch + (b.pitch - a.pitch) * t,
+            scale: a.scale * (b.scale / a.scale).powf(t),
+        }
+    }
+
+    /// One camera per frame at `fps`, starting at time 0. The last keyframe's time is left out,
+    /// so a full orbit loops without showing its first frame twice.
+    pub fn frames(&self, fps: u32) -> Vec<Camera> {
+        let fps = fps.max(1);
+        let count = ((self.duration() * fps as f32).round() as u32).max(1);
+        (0..count)
+            .map(|i| self.camera_at(i as f32 / fps as f32))
+            .collect()
+    }
+}
+
+/// Subtle procedural idle motion over any base pose: breathing (chest expansion and a slight
+/// nod), a small arm sway, and now and then a head turn. Head turns are placed by `seed`, so the
+/// same seed always moves the same way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Idle {
+    pub seed: u64,
+    /// Scales all motion; 1 is subtle, 0 keeps the base pose.
+    pub strength: f32,
+}
+
+impl Default for Idle {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            strength: 1.0,
+        }
+    }
+}
+
+impl Idle {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            ..Self::default()
+        }
+    }
+
+    /// `base` moved to `time` seconds. Every motion repeats exactly every `loop_duration` seconds
+    /// (breath and sway periods are stretched to fit a whole number of cycles), so looping clips
+    /// have no seam.
+    pub fn apply(&self, base: &Character, time: f32, loop_duration: f32) -> Character {
+        let loop_duration = loop_duration.max(0.1);
+        let time = time.rem_euclid(loop_duration);
+        // Phase in turns of a motion that repeats about every `period` seconds.
+        let phase = |period: f32| time / loop_duration * (loop_duration / period).round().max(1.0);
+        let breath = (1.0 - (TAU * phase(BREATH_SECONDS)).cos()) / 2.0;
+        let sway = (TAU * phase(SWAY_SECONDS)).sin();
+        let s = self.strength;
+
+        let mut character = base.clone();
+        character.chest_expansion += 0.03 * s * breath;
+        let posture = &mut character.posture;
+        posture.head_pitch += 1.5 * s * breath;
+        posture.head_yaw += s * self.head_turn(time, loop_duration);
+        posture.right_arm_roll += s * (1.0 + 1.5 * breath);
+        posture.left_arm_roll += s * (1.0 + 1.5 * breath);
+        posture.right_arm_pitch += 2.0 * s * sway;
+        posture.left_arm_pitch -= 2.0 * s * sway;
+        character
+    }
+
+    /// Head yaw in degrees at `time`: each [`HEAD_TURN_SECONDS`] slot of the loop holds one turn
+    /// at a seeded start, angle and direction.
+    fn head_turn(&self, time: f32, loop_duration: f32) -> f32 {
+        let turn_length: f32 = HEAD_TURN_PHASES.iter().sum();
+        let slots = (loop_duration / HEAD_TURN_SECONDS).floor() as u64;
+        if slots == 0 || loop_duration / (slots as f32) < turn_length {
+            return 0.0;
+        }
+        let slot_length = loop_duration / slots as f32;
+        let slot = ((time / slot_length) as u64).min(slots - 1);
+        let random = splitmix64(self.seed ^ slot.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        let unit = |bits: u64| (bits & 0xffff) as f32 / 65535.0;
+        let start = unit(random) * (slot_length - turn_length);
+        let angle = 8.0 + 12.0 * unit(random >> 16);
+        let direction = if random >> 32 & 1 == 0 { 1.0 } else { -1.0 };
+
+        let [rise, hold, fall] = HEAD_TURN_PHASES;
+        let t = time - slot as f32 * slot_length - start;
+        let amount = if t < 0.0 || t > turn_length {
+            0.0
+        } else if t < rise {
+            Easing::EaseInOut.apply(t / rise)
+        } else if t < rise + hold {
+            1.0
+        } else {
+            1.0 - Easing::EaseInOut.apply((t - rise - hold) / fall)
+        };
+        direction * angle * amount
+    }
+}
+
+/// SplitMix64: a small, well-mixed hash of `x`.
+pub(crate) fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// An expression shown for part of an [`Animation`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpressionKey {
+    /// Seconds from the start.
+    pub start: f32,
+    /// Seconds the expression stays on.
+    pub duration: f32,
+    pub expression: Expression,
+}
+
+impl ExpressionKey {
+    /// Natural blinking over `duration` seconds: short blinks a few seconds apart, spaced by
+    /// `seed`.
+    pub fn blinks(seed: u64, duration: f32) -> Vec<Self> {
+        let mut keys = Vec::new();
+        let mut random = splitmix64(seed);
+        let gap = |bits: u64| {
+            BLINK_GAP.0 + (BLINK_GAP.1 - BLINK_GAP.0) * (bits & 0xffff) as f32 / 65535.0
+        };
+        let mut start = gap(random) / 2.0;
+        while start + BLINK_SECONDS <= duration {
+            keys.push(Self {
+                start,
+                duration: BLINK_SECONDS,
+                expression: Expression::Blink,
+            });
+            random = splitmix64(random);
+            start += gap(random);
+        }
+        keys
+    }
+
+    fn covers(&self, time: f32) -> bool {
+        (self.start..self.start + self.duration).contains(&time)
+    }
+}
+
+/// A clip: the camera path, optional idle motion, expressions, frame rate and length.
+#[derive(Debug, Clone)]
+pub struct Animation {
+    pub camera: CameraPath,
+    pub idle: Option<Idle>,
+    /// Expressions over time; where keys overlap, the later one in the list shows.
+    pub expressions: Vec<ExpressionKey>,
+    pub fps: u32,
+    /// Length in seconds; the camera holds after its last keyframe. Idle motion loops over it.
+    pub duration: f32,
+}
+
+impl Animation {
+    /// `camera` at `fps`, as long as the path, without idle motion.
+    pub fn new(camera: CameraPath, fps: u32) -> Self {
+        Self {
+            duration: camera.duration(),
+            camera,
+            idle: None,
+            expressions: Vec::new(),
+            fps: fps.max(1),
+        }
+    }
+
+    /// Number of frames; the end time is left out, like [`CameraPath::frames`].
+    pub fn frame_count(&self) -> usize {
+        ((self.duration * self.fps.max(1) as f32).round() as usize).max(1)
+    }
+
+    /// Seconds from the start to frame `index`.
+    pub fn frame_time(&self, index: usize) -> f32 {
+        index as f32 / self.fps.max(1) as f32
+    }
+
+    /// Index in [`Animation::expressions`] of the expression showing at `time`.
+    pub fn expression_at(&self, time: f32) -> Option<usize> {
+        self.expressions.iter().rposition(|key| key.covers(time))
+    }
+
+    /// `base` with idle motion at `time`, or `base` itself without idle motion.
+    pub fn character_at(&self, base: &Character, time: f32) -> Character {
+        match &self.idle {
+            Some(idle) => idle.apply(base, time, self.duration),
+            None => base.clone(),
+        }
+    }
+}
+
+/// Render every frame of `animation` with `character` as the base pose. Each expression is
+/// composited onto `skin` and uploaded once, the first time it shows.
+///
+/// Status-effect sprites ([`crate::renderer::StatusEffects`]), particles
+/// ([`crate::renderer::Particles`]) and the enchantment glint move with the frame time, added to
+/// the renderer's own effect times; the renderer's options are restored afterwards.
+///
+/// All frames are held in memory; [`stream_animation`] renders one at a time.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn render_animation(
+    renderer: &mut Renderer,
+    character: &Character,
+    skin: &SkinImage,
+    animation: &Animation,
+    size: (u32, u32),
+) -> Result<Vec<RgbaImage>, EidolonError> {
+    if animation.idle.is_none()
+        && animation.expressions.is_empty()
+        && !renderer.options().changes_over_time()
+    {
+        // One pose and face: every frame shares the uploaded uniforms and readback buffers.
+        let cameras: Vec<_> = (0..animation.frame_count())
+            .map(|i| animation.camera.camera_at(animation.frame_time(i)))
+            .collect();
+        let plain = renderer.upload_skin(skin);
+        return renderer.render_views(character, &plain, &cameras, size);
+    }
+    stream_animation(renderer, character, skin, animation, size)
+        .map(|frame| frame.map(|frame| frame.image))
+        .collect()
+}
+
+/// The frames of `animation` as an iterator that renders each one when asked for, as
+/// [`render_animation`] would, so a server can send frames as they are ready (MJPEG, multipart)
+/// or feed a live encoder without holding the whole clip.
+///
+/// The renderer's cancel token is checked before each frame. The stream ends after the first
+/// error, and the renderer's options are restored when it is dropped.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn stream_animation<'a>(
+    renderer: &'a mut Renderer,
+    character: &'a Character,
+    skin: &'a SkinImage,
+    animation: &'a Animation,
+    size: (u32, u32),
+) -> FrameStream<'a> {
+    let options = renderer.options().clone();
+    FrameStream {
+        renderer,
+        character,
+        skin,
+        animation,
+        size,
+        options,
+        next: 0,
+        looping: false,
+        failed: false,
+        plain: None,
+        expression_textures: animation.expressions.iter().map(|_| None).collect(),
+    }
+}
+
+/// A rendered frame of a [`FrameStream`].
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    /// Position in the animation, counting on across loops of [`FrameStream::looping`].
+    pub index: usize,
+    /// Seconds from the start of the animation (within the current loop).
+    pub time: f32,
+    pub image: RgbaImage,
+}
+
+impl AnimationFrame {
+    /// The frame encoded as `format`, e.g. [`image::ImageFormat::Jpeg`] for MJPEG or
+    /// [`image::ImageFormat::Png`]. Formats without alpha, such as JPEG, drop it; transparent
+    /// pixels of a default render are black.
+    pub fn encode(&self, format: image::ImageFormat) -> Result<Vec<u8>, EidolonError> {
+        let mut bytes = Vec::new();
+        let mut cursor = std::io::Cursor::new(&mut bytes);
+        let written = match format {
+            image::ImageFormat::Jpeg => image::DynamicImage::ImageRgba8(self.image.clone())
+                .to_rgb8()
+                .write_to(&mut cursor, format),
+            _ => self.image.write_to(&mut cursor, format),
+        };
+        written.map_err(|e| {
+            EidolonError::texture(format!("failed to encode frame {}: {e}", self.index))
+        })?;
+        Ok(bytes)
+    }
+}
+
+/// Iterator over the frames of an animation, rendered one at a time ([`stream_animation`]).
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FrameStream<'a> {
+    renderer: &'a mut Renderer,
+    character: &'a Character,
+    skin: &'a SkinImage,
+    animation: &'a Animation,
+    size: (u32, u32),
+    /// The renderer's options before streaming; timed effects are advanced from them.
+    options: RenderOptions,
+    next: usize,
+    looping: bool,
+    failed: bool,
+    /// The skin as uploaded, and each expression composited onto it, once first shown.
+    plain: Option<Texture>,
+    expression_textures: Vec<Option<Texture>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FrameStream<'_> {
+    /// Start over after the last frame instead of ending, for live previews; stop it with the
+    /// renderer's cancel token or by dropping the stream.
+    pub fn looping(mut self) -> Self {
+        self.looping = true;
+        self
+    }
+
+    /// Seconds each frame shows for.
+    pub fn frame_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs_f32(1.0 / self.animation.fps.max(1) as f32)
+    }
+
+    fn render(&mut self, index: usize) -> Result<AnimationFrame, EidolonError> {
+        self.renderer.check_cancelled()?;
+        let time = self
+            .animation
+            .frame_time(index % self.animation.frame_count());
+        if self.options.changes_over_time() {
+            self.renderer.set_options(self.options.advanced(time));
+        }
+        let posed = self.animation.character_at(self.character, time);
+        let camera = self.animation.camera.camera_at(time);
+        let texture = match self.animation.expression_at(time) {
+            Some(i) => match &mut self.expression_textures[i] {
+                Some(texture) => &*texture,
+                slot @ None => {
+                    let face = self.animation.expressions[i].expression.apply(self.skin)?;
+                    &*slot.insert(self.renderer.upload_skin(&face))
+                }
+            },
+            None => self
+                .plain
+                .get_or_insert_with(|| self.renderer.upload_skin(self.skin)),
+        };
+        let image = self
+            .renderer
+            .render(&posed, texture, &camera, self.size.0, self.size.1)?;
+        Ok(AnimationFrame { index, time, image })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Iterator for FrameStream<'_> {
+    type Item = Result<AnimationFrame, EidolonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || (!self.looping && self.next >= self.animation.frame_count()) {
+            return None;
+        }
+        let frame = self.render(self.next);
+        self.next += 1;
+        self.failed = frame.is_err();
+        Some(frame)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match (self.failed, self.looping) {
+            (true, _) => (0, Some(0)),
+            (false, true) => (usize::MAX, None),
+            (false, false) => {
+                let left = self.animation.frame_count().saturating_sub(self.next);
+                (left, Some(left))
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for FrameStream<'_> {
+    fn drop(&mut self) {
+        self.renderer.set_options(self.options.clone());
+    }
+}
+
+/// Which frames share a GIF color table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GifPalette {
+    /// Each frame gets its own palette: the best colors per frame, but a color can shift slightly
+    /// between frames.
+    #[default]
+    PerFrame,
+    /// One palette for the whole clip: colors hold still, and the file is a little smaller.
+    Global,
+}
+
+/// Palette and transparency settings of [`encode_gif_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GifOptions {
+    /// Colors per palette, 2–256, including the transparent one. Frames with no more colors than
+    /// this keep them exactly, which is usual for skins without shading or effects. Default 256.
+    pub palette_size: u16,
+    /// Per-frame or one global palette. Default per frame.
+    pub palette: GifPalette,
+    /// How colors missing from the palette are approximated. Default none.
+    pub dither: Dither,
+    /// Keep a transparent background: pixels with alpha below half become transparent. Without it
+    /// every pixel is flattened onto [`GifOptions::matte`]. Default on.
+    pub transparent: bool,
+    /// Color that partly transparent pixels are blended onto, since GIF pixels are either opaque
+    /// or transparent. Default black, the renderer's clear color.
+    pub matte: [u8; 3],
+    /// Store only the rectangle that changed since the previous frame, leaving unchanged pixels
+    /// inside it to the previous frame, and merge unchanged frames. Turntables over a still
+    /// background shrink a lot. Default on.
+    pub delta_frames: bool,
+}
+
+impl Default for GifOptions {
+    fn default() -> Self {
+        Self {
+            palette_size: 256,
+            palette: GifPalette::PerFrame,
+            dither: Dither::None,
+            transparent: true,
+            matte: [0, 0, 0],
+            delta_frames: true,
+        }
+    }
+}
+
+impl GifOptions {
+    /// `image` with translucent pixels blended onto the matte: opaque, or fully transparent
+    /// below [`ALPHA_CUTOFF`] if the background stays transparent.
+    fn flatten(&self, image: &RgbaImage) -> RgbaImage {
+        let mut image = image.clone();
+        for pixel in image.pixels_mut() {
+            let alpha = pixel[3];
+            if self.transparent && alpha < ALPHA_CUTOFF {
+                pixel.0 = [0, 0, 0, 0];
+                continue;
+            }
+            for c in 0..3 {
+                let blended = u32::from(pixel[c]) * u32::from(alpha)
+                    + u32::from(self.matte[c]) * u32::from(255 - alpha);
+                pixel[c] = ((blended + 127) / 255) as u8;
+            }
+            pixel[3] = 255;
+        }
+        image
+    }
+}
+
+/// Encode `frames` as a GIF that loops forever at `fps`, with the default [`GifOptions`]: pixels
+/// with alpha below half become transparent, and frames get their own palette of up to 256
+/// colors.
+pub fn encode_gif(frames: &[RgbaImage], fps: u32) -> Result<Vec<u8>, EidolonError> {
+    encode_gif_with(frames, fps, &GifOptions::default(), &CancelToken::new())
+}
+
+/// [`encode_gif`], checking `cancel` before each frame; quantizing frames to 256 colors is the
+/// slow part of encoding long clips.
+pub fn encode_gif_cancellable(
+    frames: &[RgbaImage],
+    fps: u32,
+    cancel: &CancelToken,
+) -> Result<Vec<u8>, EidolonError> {
+    encode_gif_with(frames, fps, &GifOptions::default(), cancel)
+}
+
+/// Encode `frames` (all the same size) as a GIF that loops forever at `fps`, with the palette,
+/// dithering and transparency of `options`, checking `cancel` before each frame.
+pub fn encode_gif_with(
+    frames: &[RgbaImage],
+    fps: u32,
+    options: &GifOptions,
+    cancel: &CancelToken,
+) -> Result<Vec<u8>, EidolonError> {
+    let (full_width, full_height) = frame_size(frames, "GIF")?;
+    let (Ok(width), Ok(height)) = (u16::try_from(full_width), u16::try_from(full_height)) else {
+        return Err(EidolonError::texture(format!(
+            "failed to encode GIF: {full_width}x{full_height} is larger than GIF allows \
+             ({max}x{max})",
+            max = u16::MAX
+        )));
+    };
+    let gif_error =
+        |e: gif::EncodingError| EidolonError::texture(format!("failed to encode GIF: {e}"));
+    // Hundredths of a second, as GIF stores delays.
+    let delay = (1000 / fps.max(1) / 10) as u16;
+    let colors = usize::from(options.palette_size.clamp(2, 256)) - usize::from(options.transparent);
+    // The transparent entry goes after the palette's colors.
+    let color_table = |palette: &Palette| {
+        let mut table = palette.to_rgb_bytes();
+        if options.transparent {
+            table.extend_from_slice(&[0, 0, 0]);
+        }
+        table
+    };
+
+    cancel.check()?;
+    let flattened: Vec<_> = frames.iter().map(|frame| options.flatten(frame)).collect();
+    let global = match options.palette {
+        GifPalette::Global => Some(Palette::build(&flattened, colors)),
+        GifPalette::PerFrame => None,
+    };
+    let mut indexed = Vec::with_capacity(flattened.len());
+    for image in &flattened {
+        cancel.check()?;
+        let local = global.is_none().then(|| Palette::build([image], colors));
+        let palette = local.as_ref().or(global.as_ref()).expect("one palette");
+        let transparent = options.transparent.then(|| palette.colors().len() as u8);
+        let indices = palette.index(image, options.dither, transparent);
+        let shown = indices
+            .iter()
+            .map(|&i| match palette.colors().get(usize::from(i)) {
+                Some(&[r, g, b]) => [r, g, b, 255],
+                None => [0; 4],
+            })
+            .collect();
+        indexed.push(IndexedFrame {
+            local,
+            transparent,
+            indices,
+            shown,
+        });
+    }
+
+    // What a viewer shows before each frame is drawn: the previous frames, after disposal.
+    let mut canvas = vec![[0u8; 4]; indexed[0].shown.len()];
+    let mut steps: Vec<GifStep> = Vec::with_capacity(indexed.len());
+    for (i, frame) in indexed.iter().enumerate() {
+        cancel.check()?;
+        // GIF can only clear pixels by disposing a frame's rectangle to the background, so
+        // pixels turning transparent in the next frame (the first one after the last) must be
+        // part of this frame's rectangle.
+        let next = &indexed[(i + 1) % indexed.len()].shown;
+        let clears = |p: usize| frame.shown[p][3] != 0 && next[p][3] == 0;
+        let full = [0, 0, full_width, full_height];
+        let (rect, dispose) = if !options.delta_frames {
+            (Some(full), gif::DisposalMethod::Background)
+        } else {
+            let rect = match i {
+                0 => Some(full),
+                _ => changed_rect(full_width, full_height, |p| {
+                    frame.shown[p] != canvas[p] || clears(p)
+                }),
+            };
+            match (0..canvas.len()).any(clears) {
+                true => (rect, gif::DisposalMethod::Background),
+                false => (rect, gif::DisposalMethod::Keep),
+            }
+        };
+        let rect = match rect {
+            Some(rect) => rect,
+            None => {
+                let previous = steps.last_mut().expect("the first frame is always drawn");
+                if previous.dispose == gif::DisposalMethod::Keep {
+                    // Nothing changed: show the previous frame for longer.
+                    previous.delay = previous.delay.saturating_add(delay);
+                    continue;
+                }
+                // The previous frame's disposal still has to happen now.
+                [0, 0, 1, 1]
+            }
+        };
+
+        let [x, y, rect_width, rect_height] = rect;
+        let mut pixels = Vec::with_capacity((rect_width * rect_height) as usize);
+        for row in y..y + rect_height {
+            for column in x..x + rect_width {
+                let p = (row * full_width + column) as usize;
+                // Pixels already on the canvas are left to it, which compresses better.
+                let keep =
+                    options.delta_frames && frame.shown[p][3] != 0 && frame.shown[p] == canvas[p];
+                pixels.push(match frame.transparent {
+                    Some(transparent) if keep => transparent,
+                    _ => frame.indices[p],
+                });
+            }
+        }
+        canvas.copy_from_slice(&frame.shown);
+        if dispose == gif::DisposalMethod::Background {
+            for row in y..y + rect_height {
+                let start = (row * full_width + x) as usize;
+                canvas[start..start + rect_width as usize].fill([0; 4]);
+            }
+        }
+        steps.push(GifStep {
+            frame: i,
+            rect,
+            pixels,
+            dispose,
+            delay,
+        });
+    }
+
+    let mut bytes = Vec::new();
+    {
+        let global_table = global.as_ref().map(color_table).unwrap_or_default();
+        let mut encoder =
+            gif::Encoder::new(&mut bytes, width, height, &global_table).map_err(gif_error)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(gif_error)?;
+        for step in steps {
+            cancel.check()?;
+            let frame = &indexed[step.frame];
+            let [x, y, rect_width, rect_height] = step.rect.map(|v| v as u16);
+            let mut encoded = match &frame.local {
+                Some(palette) => gif::Frame::from_palette_pixels(
+                    rect_width,
+                    rect_height,
+                    step.pixels,
+                    color_table(palette),
+                    frame.transparent,
+                ),
+                None => gif::Frame::from_indexed_pixels(
+                    rect_width,
+                    rect_height,
+                    step.pixels,
+                    frame.transparent,
+                ),
+            };
+            encoded.left = x;
+            encoded.top = y;
+            encoded.delay = step.delay;
+            encoded.dispose = step.dispose;
+            encoder.write_frame(&encoded).map_err(gif_error)?;
+        }
+    }
+    Ok(bytes)
+}
+
+/// A GIF frame indexed against its palette.
+struct IndexedFrame {
+    /// The frame's own palette, or `None` with a global one.
+    local: Option<Palette>,
+    transparent: Option<u8>,
+    indices: Vec<u8>,
+    /// The RGBA each pixel displays.
+    shown: Vec<[u8; 4]>,
+}
+
+/// A frame as written: the rectangle drawn, its indices, and how it is disposed of.
+struct GifStep {
+    frame: usize,
+    rect: [u32; 4],
+    pixels: Vec<u8>,
+    dispose: gif::DisposalMethod,
+    delay: u16,
+}
+
+/// Encode `frames` (all the same size) as an animated PNG that loops forever at `fps`, checking
+/// `cancel` before each frame.
+///
+/// Unlike GIF, APNG keeps every color and partial transparency. Frames after the first store only
+/// the rectangle that changed, and unchanged frames lengthen the previous one.
+pub fn encode_apng(
+    frames: &[RgbaImage],
+    fps: u32,
+    cancel: &CancelToken,
+) -> Result<Vec<u8>, EidolonError> {
+    let (width, height) = frame_size(frames, "APNG")?;
+    let png_error =
+        |e: png::EncodingError| EidolonError::texture(format!("failed to encode APNG: {e}"));
+    let same = |a: &[u8], b: &[u8]| a == b || (a[3] == 0 && b[3] == 0);
+
+    // Rectangles to draw, each with the number of frames it stays up for.
+    let mut steps: Vec<(usize, [u32; 4], u16)> = vec![(0, [0, 0, width, height], 1)];
+    for (i, pair) in frames.windows(2).enumerate() {
+        cancel.check()?;
+        let (previous, current) = (pair[0].as_raw(), pair[1].as_raw());
+        let changed = |p: usize| !same(&previous[4 * p..4 * p + 4], &current[4 * p..4 * p + 4]);
+        match changed_rect(width, height, changed) {
+            Some(rect) => steps.push((i + 1, rect, 1)),
+            None => {
+                let last = steps.last_mut().expect("the first frame is always drawn");
+                last.2 = last.2.saturating_add(1);
+            }
+        }
+    }
+
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(steps.len() as u32, 0)
+        .map_err(png_error)?;
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    let fps = fps.clamp(1, u32::from(u16::MAX)) as u16;
+    for (frame, [x, y, rect_width, rect_height], length) in steps {
+        cancel.check()?;
+        writer.set_frame_delay(length, fps).map_err(png_error)?;
+        // Position and size are checked against each other: move to the corner first.
+        writer.reset_frame_position().map_err(png_error)?;
+        writer
+            .set_frame_dimension(rect_width, rect_height)
+            .map_err(png_error)?;
+        writer.set_frame_position(x, y).map_err(png_error)?;
+        writer
+            .set_blend_op(png::BlendOp::Source)
+            .map_err(png_error)?;
+        writer
+            .set_dispose_op(png::DisposeOp::None)
+            .map_err(png_error)?;
+        let rect = image::imageops::crop_imm(&frames[frame], x, y, rect_width, rect_height);
+        writer
+            .write_image_data(rect.to_image().as_raw())
+            .map_err(png_error)?;
+    }
+    writer.finish().map_err(png_error)?;
+    Ok(bytes)
+}
+
+/// The size shared by all `frames`; no frames, or frames of different sizes, are a
+/// [`EidolonError::Texture`] naming `format`.
+fn frame_size(frames: &[RgbaImage], format: &str) -> Result<(u32, u32), EidolonError> {
+    let Some(first) = frames.first() else {
+        return Err(EidolonError::texture(format!(
+            "failed to encode {format}: no frames"
+        )));
+    };
+    if frames
+        .iter()
+        .any(|frame| frame.dimensions() != first.dimensions())
+    {
+        return Err(EidolonError::texture(format!(
+            "failed to encode {format}: frames differ in size"
+        )));
+    }
+    Ok(first.dimensions())
+}
+
+/// The smallest rectangle (`[x, y, width, height]`) of a `width`×`height` frame holding every
+/// pixel, by row-major index, that `changed`; `None` if none did.
+fn changed_rect(width: u32, height: u32, changed: impl Fn(usize) -> bool) -> Option<[u32; 4]> {
+    let (mut left, mut top, mut right, mut bottom) = (u32::MAX, u32::MAX, 0, 0);
+    for y in 0..height {
+        for x in 0..width {
+            if changed((y * width + x) as usize) {
+                left = left.min(x);
+                top = top.min(y);
+                right = right.max(x);
+                bottom = bottom.max(y);
+            }
+        }
+    }
+    (left != u32::MAX).then(|| [left, top, right - left + 1, bottom - top + 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{AnimationDecoder, Rgba};
+
+    fn camera(yaw: f32, pitch: f32, scale: f32) -> Camera {
+        Camera { yaw, pitch, scale }
+    }
+
+    #[test]
+    fn easing_curves_keep_their_ends() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+        }
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+    }
+
+    #[test]
+    fn paths_interpolate_between_sorted_keyframes() {
+        let path = CameraPath::new(camera(0.0, 90.0, 1.0))
+            .key(2.0, camera(180.0, 110.0, 4.0), Easing::Linear)
+            .key(1.0, camera(90.0, 90.0, 1.0), Easing::Linear);
+        assert_eq!(path.duration(), 2.0);
+        let times: Vec<_> = path.keyframes().iter().map(|k| k.time).collect();
+        assert_eq!(times, [0.0, 1.0, 2.0]);
+
+        let half = path.camera_at(1.5);
+        assert_eq!((half.yaw, half.pitch), (135.0, 100.0));
+        // Zoom is geometric: halfway from 1 to 4 is 2.
+        assert!((half.scale - 2.0).abs() < 1e-5);
+        // Outside the keyframes the camera holds.
+        assert_eq!(path.camera_at(-1.0).yaw, 0.0);
+        assert_eq!(path.camera_at(9.0).yaw, 180.0);
+    }
+
+    #[test]
+    fn orbit_frames_loop_without_repeating() {
+        let start = camera(180.0, 90.0, 1.0);
+        let frames = CameraPath::orbit(start, 1.0, 2.0, Easing::Linear).frames(4);
+        let yaws: Vec<_> = frames.iter().map(|c| c.yaw).collect();
+        assert_eq!(
+            yaws,
+            [180.0, 225.0, 270.0, 315.0, 360.0, 405.0, 450.0, 495.0]
+        );
+        // A still path still has a frame.
+        assert_eq!(CameraPath::new(start).frames(30).len(), 1);
+    }
+
+    #[test]
+    fn presets_end_on_the_given_camera() {
+        let end = camera(200.0, 100.0, 1.5);
+        for path in [
+            CameraPath::zoom_reveal(end, 3.0),
+            CameraPath::crane(end, 3.0),
+        ] {
+            let last = path.camera_at(3.0);
+            assert_eq!((last.yaw, last.pitch, last.scale), (200.0, 100.0, 1.5));
+        }
+        let start = CameraPath::zoom_reveal(end, 3.0).camera_at(0.0);
+        assert!((start.scale.powi(2) * 3.0 - end.scale.powi(2)).abs() < 1e-5);
+        assert!(CameraPath::crane(end, 3.0).camera_at(0.0).pitch < end.pitch);
+    }
+
+    #[test]
+    fn idle_motion_loops_and_is_seeded() {
+        let base = Character::new();
+        let idle = Idle::new(7);
+        let start = idle.apply(&base, 0.0, 8.0);
+        let end = idle.apply(&base, 8.0, 8.0);
+        assert_eq!(format!("{start:?}"), format!("{end:?}"));
+        // Two breaths fit an 8 s loop, so the first peaks 2 s in.
+        let peak = idle.apply(&base, 2.0, 8.0).chest_expansion;
+        assert!((peak - 0.03).abs() < 1e-6);
+        assert_eq!(start.chest_expansion, 0.0);
+
+        let turns = |seed| {
+            (0..80)
+                .map(|i| {
+                    Idle::new(seed)
+                        .apply(&base, i as f32 / 10.0, 8.0)
+                        .posture
+                        .head_yaw
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(turns(7), turns(7));
+        assert_ne!(turns(7), turns(8));
+        assert!(turns(7).iter().any(|yaw| yaw.abs() >= 8.0));
+        assert!(turns(7).iter().all(|yaw| yaw.abs() <= 20.0));
+
+        let still = Idle {
+            strength: 0.0,
+            ..idle
+        };
+        assert_eq!(
+            format!("{:?}", still.apply(&base, 3.3, 8.0)),
+            format!("{base:?}")
+        );
+    }
+
+    #[test]
+    fn animations_sample_the_camera_over_their_duration() {
+        let mut animation = Animation::new(CameraPath::new(Camera::new()), 10);
+        assert_eq!(animation.frame_count(), 1);
+        animation.duration = 2.0;
+        assert_eq!(animation.frame_count(), 20);
+        assert_eq!(animation.frame_time(5), 0.5);
+        let base = Character::new();
+        assert_eq!(animation.character_at(&base, 1.0).chest_expansion, 0.0);
+        animation.idle = Some(Idle::default());
+        assert!(animation.character_at(&base, 1.0).chest_expansion > 0.0);
+    }
+
+    #[test]
+    fn expression_keys_pick_the_latest_covering_key() {
+        let mut animation = Animation::new(CameraPath::new(Camera::new()), 10);
+        animation.duration = 10.0;
+        animation.expressions = vec![
+            ExpressionKey {
+                start: 1.0,
+                duration: 3.0,
+                expression: Expression::Wink,
+            },
+            ExpressionKey {
+                start: 2.0,
+                duration: 0.5,
+                expression: Expression::Blink,
+            },
+        ];
+        assert_eq!(animation.expression_at(0.5), None);
+        assert_eq!(animation.expression_at(1.0), Some(0));
+        assert_eq!(animation.expression_at(2.2), Some(1));
+        assert_eq!(animation.expression_at(3.0), Some(0));
+        assert_eq!(animation.expression_at(4.0), None);
+
+        let blinks = ExpressionKey::blinks(5, 20.0);
+        assert_eq!(blinks, ExpressionKey::blinks(5, 20.0));
+        assert!((4..=8).contains(&blinks.len()), "{}", blinks.len());
+        assert!(blinks
+            .iter()
+            .all(|k| k.expression == Expression::Blink && k.start + k.duration <= 20.0));
+        assert!(blinks
+            .windows(2)
+            .all(|w| w[1].start - w[0].start >= BLINK_GAP.0));
+    }
+
+    #[test]
+    fn gifs_loop_with_the_frame_delay() {
+        let frames: Vec<_> = [[255, 0, 0, 255], [0, 0, 0, 0]]
+            .into_iter()
+            .map(|color| RgbaImage::from_pixel(4, 4, Rgba(color)))
+            .collect();
+        let gif = encode_gif(&frames, 20).unwrap();
+        let decoder = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(&gif)).unwrap();
+        let decoded = decoder.into_frames().collect_frames().unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].delay().numer_denom_ms(), (50, 1));
+        assert_eq!(decoded[0].buffer().get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(decoded[1].buffer().get_pixel(0, 0)[3], 0);
+
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        assert!(matches!(
+            encode_gif_cancellable(&frames, 20, &cancel),
+            Err(EidolonError::Cancelled)
+        ));
+    }
+
+    #[test]
+    fn gif_options_choose_palette_and_background() {
+        let decode = |gif: &[u8]| {
+            image::codecs::gif::GifDecoder::new(std::io::Cursor::new(gif))
+                .unwrap()
+                .into_frames()
+                .collect_frames()
+                .unwrap()
+        };
+        // Half-transparent red over a transparent background.
+        let mut image = RgbaImage::from_pixel(8, 8, Rgba([0, 0, 0, 0]));
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 128]));
+        image.put_pixel(1, 0, Rgba([10, 200, 30, 255]));
+        let frames = [image.clone(), image];
+
+        let opaque = GifOptions {
+            palette: GifPalette::Global,
+            transparent: false,
+            matte: [255, 255, 255],
+            ..GifOptions::default()
+        };
+        let gif = encode_gif_with(&frames, 10, &opaque, &CancelToken::new()).unwrap();
+        let decoded = decode(&gif);
+        // The unchanged second frame only lengthens the first.
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].delay().numer_denom_ms(), (200, 1));
+        let first = decoded[0].buffer();
+        assert_eq!(first.get_pixel(0, 0).0, [255, 127, 127, 255]);
+        assert_eq!(first.get_pixel(1, 0).0, [10, 200, 30, 255]);
+        assert_eq!(first.get_pixel(5, 5).0, [255, 255, 255, 255]);
+
+        // Two colors plus transparency: the flat colors survive exactly.
+        let small = GifOptions {
+            palette_size: 3,
+            dither: Dither::FloydSteinberg,
+            ..GifOptions::default()
+        };
+        let gif = encode_gif_with(&frames, 10, &small, &CancelToken::new()).unwrap();
+        let first = decode(&gif)[0].buffer().clone();
+        assert_eq!(first.get_pixel(0, 0).0, [128, 0, 0, 255]);
+        assert_eq!(first.get_pixel(1, 0).0, [10, 200, 30, 255]);
+        assert_eq!(first.get_pixel(5, 5)[3], 0);
+
+        assert!(encode_gif_with(&[], 10, &small, &CancelToken::new()).is_err());
+    }
+
+    /// A 3×3 square moving over a transparent background, next to a pixel that never changes.
+    fn moving_square() -> Vec<RgbaImage> {
+        (0..6)
+            .map(|i| {
+                let mut image = RgbaImage::from_pixel(24, 16, Rgba([0, 0, 0, 0]));
+                image.put_pixel(20, 2, Rgba([10, 200, 30, 255]));
+                for (x, y) in (0..3).flat_map(|x| (0..3).map(move |y| (x, y))) {
+                    image.put_pixel(2 * i + x, 5 + y, Rgba([250, 40, 40, 100 + 30 * i as u8]));
+                }
+                image
+            })
+            .collect()
+    }
+
+    #[test]
+    fn delta_frames_draw_only_what_changed() {
+        let frames = moving_square();
+        let encode = |delta_frames| {
+            let options = GifOptions {
+                delta_frames,
+                ..GifOptions::default()
+            };
+            encode_gif_with(&frames, 10, &options, &CancelToken::new()).unwrap()
+        };
+        let decode = |gif: &[u8]| {
+            image::codecs::gif::GifDecoder::new(std::io::Cursor::new(gif))
+                .unwrap()
+                .into_frames()
+                .collect_frames()
+                .unwrap()
+        };
+        let (delta, full) = (encode(true), encode(false));
+        assert!(
+            delta.len() < full.len(),
+            "{} >= {}",
+            delta.len(),
+            full.len()
+        );
+        let (delta, full) = (decode(&delta), decode(&full));
+        assert_eq!(delta.len(), frames.len());
+        for (a, b) in delta.iter().zip(&full) {
+            assert_eq!(a.buffer(), b.buffer());
+        }
+        // Frames of a turntable keep the still parts; squares leave no trail.
+        assert_eq!(delta[3].buffer().get_pixel(20, 2).0, [10, 200, 30, 255]);
+        assert_eq!(delta[3].buffer().get_pixel(0, 5)[3], 0);
+    }
+
+    #[test]
+    fn apng_frames_keep_alpha_and_store_changes() {
+        let mut frames = moving_square();
+        frames.insert(1, frames[0].clone());
+        let apng = encode_apng(&frames, 10, &CancelToken::new()).unwrap();
+        let decoder = image::codecs::png::PngDecoder::new(std::io::Cursor::new(&apng)).unwrap();
+        let decoded = decoder
+            .apng()
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        // The repeated first frame stays up twice as long instead.
+        assert_eq!(decoded.len(), frames.len() - 1);
+        assert_eq!(
+            std::time::Duration::from(decoded[0].delay()),
+            std::time::Duration::from_millis(200)
+        );
+        for (decoded, frame) in decoded.iter().zip(frames.iter().skip(1)) {
+            assert_eq!(decoded.buffer(), frame);
+        }
+        assert!(encode_apng(&[], 10, &CancelToken::new()).is_err());
+    }
+}
@@ -0,0 +1,454 @@
+//! Attachments: small models worn on a bone, such as glasses, hats, wings, ears or held items.
+//!
+//! An [`AttachmentModel`] is loaded on the CPU from an OBJ file or a Blockbench `.bbmodel`,
+//! uploaded once with [`Renderer::upload_attachment`] and shared by any number of characters
+//! through the [`Attachment`]s in [`Character::attachments`]. Each attachment draws the mesh with
+//! its own texture, placed by an [`AttachmentTransform`], and moves with its bone.
+//!
+//! Models are in the units of the player model: one unit is a block, the feet are at the origin,
+//! the player faces -Z and their right is +X. A model built on a player in Blockbench therefore
+//! sits where it was modeled with the identity transform; a model built around the origin is moved
+//! onto its bone with [`AttachmentTransform::offset`], e.g. to [`Bone::center`].
+//!
+//! [`Renderer::upload_attachment`]: crate::renderer::Renderer::upload_attachment
+//! [`Character::attachments`]: crate::character::Character::attachments
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use cgmath::{Matrix4, Rad, Vector3};
+use serde_json::Value;
+use tobj::GPU_LOAD_OPTIONS;
+
+use crate::bedrock::{self, BedrockGeometry, Cube, CubeUv, Face, FaceUv};
+use crate::character::Bone;
+use crate::error::EidolonError;
+use crate::model::{tobj_vertices, Bounds, ModelPart, TexturedVertex};
+use crate::profile::decode_base64;
+use crate::texture::{SkinId, Texture};
+
+/// Most attachments one character can wear in a render.
+pub const MAX_ATTACHMENTS: usize = 16;
+
+/// Name of the bone that elements outside any `.bbmodel` group hang from.
+const ROOT_BONE: &str = "root";
+
+/// Triangles of an attachment, before upload.
+#[derive(Debug, Clone)]
+pub struct AttachmentModel {
+    vertices: Vec<TexturedVertex>,
+    texture: Option<Vec<u8>>,
+}
+
+impl AttachmentModel {
+    /// Every object of an OBJ file, as one mesh. Material files are not read: the texture comes
+    /// with the [`Attachment`].
+    pub fn from_obj(data: &[u8]) -> Result<Self, EidolonError> {
+        let (models, _materials) =
+            tobj::load_obj_buf(&mut std::io::Cursor::new(data), &GPU_LOAD_OPTIONS, |_| {
+                Err(tobj::LoadError::OpenFileFailed)
+            })
+            .map_err(|e| EidolonError::model(format!("failed to parse attachment OBJ: {e}")))?;
+        let vertices: Vec<_> = models
+            .iter()
+            .flat_map(|model| tobj_vertices(&model.mesh))
+            .collect();
+        Self::new(vertices, None)
+    }
+
+    /// The cubes of a Blockbench project (`.bbmodel`), posed by their groups. Blockbench pixels
+    /// are sixteenths of a unit, and UVs are in the project's texture resolution.
+    ///
+    /// Elements that are hidden or are not cubes (meshes, locators) are skipped, as are faces
+    /// without a texture. Every face samples the one texture of the [`Attachment`], and face UV
+    /// rotations are ignored. The first embedded texture is kept as
+    /// [`AttachmentModel::embedded_texture`].
+    pub fn from_bbmodel(json: &[u8]) -> Result<Self, EidolonError> {
+        let file: Value = serde_json::from_slice(json)
+            .map_err(|e| EidolonError::model(format!("malformed bbmodel JSON: {e}")))?;
+        let elements = file["elements"]
+            .as_array()
+            .ok_or_else(|| EidolonError::model("bbmodel has no elements"))?;
+        let resolution = &file["resolution"];
+        let size = |value: &Value| value.as_f64().map_or(16.0, |v| v as f32);
+
+        // Groups become bones, so nested group rotations apply. Newer projects keep the group
+        // settings in `groups` and only their UUIDs in the outliner.
+        let groups: HashMap<&str, &Value> = file["groups"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|group| Some((group["uuid"].as_str()?, group)))
+            .collect();
+        let mut bones = vec![bedrock::Bone {
+            name: ROOT_BONE.to_string(),
+            parent: None,
+            pivot: [0.0; 3],
+            rotation: [0.0; 3],
+            cubes: Vec::new(),
+        }];
+        let mut owners = HashMap::new();
+        for node in file["outliner"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+        {
+            add_outliner_node(node, 0, &groups, &mut bones, &mut owners);
+        }
+
+        for element in elements {
+            let hidden = element["visibility"] == false || element["export"] == false;
+            if hidden || element["type"].as_str().is_some_and(|t| t != "cube") {
+                continue;
+            }
+            let name = element["name"].as_str().unwrap_or("?");
+            let cube = parse_element(element)
+                .map_err(|e| EidolonError::model(format!("bbmodel element '{name}': {e}")))?;
+            let owner = element["uuid"].as_str().and_then(|uuid| owners.get(uuid));
+            bones[owner.copied().unwrap_or(0)].cubes.push(cube);
+        }
+
+        let geometry = BedrockGeometry {
+            identifier: "geometry.bbmodel".to_string(),
+            texture_width: size(&resolution["width"]),
+            texture_height: size(&resolution["height"]),
+            bones,
+        };
+        let texture = file["textures"][0]["source"]
+            .as_str()
+            .and_then(|source| source.split_once("base64,"))
+            .and_then(|(_, data)| decode_base64(data));
+        Self::new(geometry.vertices(), texture)
+    }
+
+    /// Load an `.obj` or `.bbmodel` file, by its extension.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file(path: &str) -> Result<Self, EidolonError> {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        let load = match extension.as_deref() {
+            Some("obj") => Self::from_obj,
+            Some("bbmodel") => Self::from_bbmodel,
+            _ => {
+                return Err(EidolonError::model(format!(
+                    "attachment model '{path}' is not an .obj or .bbmodel file"
+                )))
+            }
+        };
+        load(&std::fs::read(path)?)
+            .map_err(|e| EidolonError::model(format!("attachment model '{path}': {e}")))
+    }
+
+    fn new(vertices: Vec<TexturedVertex>, texture: Option<Vec<u8>>) -> Result<Self, EidolonError> {
+        if vertices.is_empty() {
+            return Err(EidolonError::model("attachment model has no triangles"));
+        }
+        Ok(Self { vertices, texture })
+    }
+
+    pub fn vertices(&self) -> &[TexturedVertex] {
+        &self.vertices
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        Bounds::from_vertices(&self.vertices)
+    }
+
+    /// PNG bytes of the first texture embedded in a `.bbmodel`. Upload it with
+    /// [`crate::texture::SkinImage::from_memory_raw`], which keeps its layout.
+    pub fn embedded_texture(&self) -> Option<&[u8]> {
+        self.texture.as_deref()
+    }
+}
+
+/// Record the bone of every element under outliner `node`: the node's own bone if it is a
+/// group, else `parent`.
+fn add_outliner_node<'a>(
+    node: &'a Value,
+    parent: usize,
+    groups: &HashMap<&str, &'a Value>,
+    bones: &mut Vec<bedrock::Bone>,
+    owners: &mut HashMap<&'a str, usize>,
+) {
+    if let Some(uuid) = node.as_str() {
+        owners.insert(uuid, parent);
+        return;
+    }
+    let group = match node["uuid"].as_str().and_then(|uuid| groups.get(uuid)) {
+        Some(group) if node.get("origin").is_none() => group,
+        _ => node,
+    };
+    let origin = bedrock::vec3(&group["origin"]).unwrap_or_default();
+    let rotation = bedrock::vec3(&group["rotation"]).unwrap_or_default();
+    let index = bones.len();
+    bones.push(bedrock::Bone {
+        name: format!("group{index}"),
+        parent: Some(bones[parent].name.clone()),
+        // Blockbench mirrors X relative to Bedrock, as its own Bedrock export does.
+        pivot: [-origin[0], origin[1], origin[2]],
+        rotation: [-rotation[0], -rotation[1], rotation[2]],
+        cubes: Vec::new(),
+    });
+    for child in node["children"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+    {
+        add_outliner_node(child, index, groups, bones, owners);
+    }
+}
+
+/// A `.bbmodel` cube element as a Bedrock cube.
+fn parse_element(element: &Value) -> Result<Cube, String> {
+    let from = bedrock::vec3(&element["from"]).ok_or("cube has no 'from'")?;
+    let to = bedrock::vec3(&element["to"]).ok_or("cube has no 'to'")?;
+    let origin = bedrock::vec3(&element["origin"]).unwrap_or(from);
+    let rotation = bedrock::vec3(&element["rotation"]).unwrap_or_default();
+    let uv = if element["box_uv"] == true {
+        CubeUv::Box(bedrock::vec2(&element["uv_offset"]).unwrap_or_default())
+    } else {
+        CubeUv::PerFace(Face::ALL.map(|face| {
+            let face = element["faces"].get(face.key())?;
+            if matches!(face.get("texture"), Some(Value::Null | Value::Bool(false))) {
+                return None;
+            }
+            let uv = face["uv"].as_array()?;
+            let [u0, v0, u1, v1] = std::array::from_fn(|i| {
+                uv.get(i).and_then(Value::as_f64).unwrap_or_default() as f32
+            });
+            Some(FaceUv {
+                uv: [u0, v0],
+                size: [u1 - u0, v1 - v0],
+            })
+        }))
+    };
+    Ok(Cube {
+        origin: [-to[0], from[1], from[2]],
+        size: std::array::from_fn(|i| to[i] - from[i]),
+        pivot: [-origin[0], origin[1], origin[2]],
+        rotation: [-rotation[0], -rotation[1], rotation[2]],
+        inflate: element["inflate"].as_f64().unwrap_or(0.0) as f32,
+        mirror: element["mirror_uv"] == true,
+        uv,
+    })
+}
+
+/// An uploaded [`AttachmentModel`] ([`crate::renderer::Renderer::upload_attachment`]).
+pub struct AttachmentMesh {
+    pub(crate) part: ModelPart,
+}
+
+impl AttachmentMesh {
+    pub(crate) fn upload(device: &wgpu::Device, model: &AttachmentModel) -> Self {
+        Self {
+            part: ModelPart::upload(device, "Attachment", &model.vertices),
+        }
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.part.bounds
+    }
+}
+
+/// Where an attachment sits relative to its bone: its model is scaled, then rotated, about its
+/// origin, then moved by `offset`. Then it follows the bone's pose.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AttachmentTransform {
+    /// In model units (a block is one unit).
+    pub offset: Vector3<f32>,
+    /// Degrees (Euler X, then Y, then Z), like [`crate::character::Character::rotation`].
+    pub rotation: Vector3<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Default for AttachmentTransform {
+    fn default() -> Self {
+        Self {
+            offset: Vector3::new(0.0, 0.0, 0.0),
+            rotation: Vector3::new(0.0, 0.0, 0.0),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl AttachmentTransform {
+    /// Model space of the attachment to the bind-pose model space of the character.
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.offset)
+            * Matrix4::from_angle_x(Rad(self.rotation.x.to_radians()))
+            * Matrix4::from_angle_y(Rad(self.rotation.y.to_radians()))
+            * Matrix4::from_angle_z(Rad(self.rotation.z.to_radians()))
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+}
+
+/// How an attachment changes the render besides drawing itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttachmentFlags {
+    /// Hide the skin's overlay layer on the attachment's bone, e.g. hair that would poke through
+    /// a hat.
+    pub hide_overlay: bool,
+    /// Draw the attachment into the shadow map, when shadows are on.
+    pub cast_shadow: bool,
+}
+
+impl Default for AttachmentFlags {
+    fn default() -> Self {
+        Self {
+            hide_overlay: false,
+            cast_shadow: true,
+        }
+    }
+}
+
+/// A mesh worn on a bone with its own texture.
+#[derive(Clone)]
+pub struct Attachment {
+    /// Shown in render metadata, e.g. `"glasses"`.
+    pub name: String,
+    pub bone: Bone,
+    pub mesh: Arc<AttachmentMesh>,
+    /// Any texture the model's UVs were made for, not only skin atlases.
+    pub texture: Arc<Texture>,
+    pub transform: AttachmentTransform,
+    pub flags: AttachmentFlags,
+}
+
+impl Attachment {
+    /// `mesh` on `bone` where it was modeled.
+    pub fn new(
+        name: impl Into<String>,
+        bone: Bone,
+        mesh: Arc<AttachmentMesh>,
+        texture: Arc<Texture>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            bone,
+            mesh,
+            texture,
+            transform: AttachmentTransform::default(),
+            flags: AttachmentFlags::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Attachment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Attachment")
+            .field("name", &self.name)
+            .field("bone", &self.bone)
+            .field("vertices", &self.mesh.part.vertex_count)
+            .field("texture", &SkinId(self.texture.content_hash()).to_string())
+            .field("transform", &self.transform)
+            .field("flags", &self.flags)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUBE_OBJ: &str = "mtllib cube.mtl
+o Cube
+v -0.5 0 -0.5
+v 0.5 0 -0.5
+v 0.5 1 -0.5
+v -0.5 1 -0.5
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 -1
+usemtl skin
+f 1/1/1 2/2/1 3/3/1 4/4/1
+";
+
+    #[test]
+    fn obj_models_ignore_their_materials() {
+        let model = AttachmentModel::from_obj(CUBE_OBJ.as_bytes()).unwrap();
+        assert_eq!(model.vertices().len(), 6);
+        assert_eq!(
+            model.bounds(),
+            Bounds {
+                min: [-0.5, 0.0, -0.5],
+                max: [0.5, 1.0, -0.5]
+            }
+        );
+        assert!(model.embedded_texture().is_none());
+        assert!(AttachmentModel::from_obj(b"o Empty\n").is_err());
+    }
+
+    #[test]
+    fn bbmodel_groups_pose_their_cubes() {
+        let json = r#"{
+            "meta": {"format_version": "4.5"},
+            "resolution": {"width": 32, "height": 32},
+            "elements": [
+                {"name": "lens", "uuid": "a", "from": [1, 28, -5], "to": [3, 30, -4],
+                 "origin": [0, 0, 0],
+                 "faces": {"north": {"uv": [0, 0, 2, 2], "texture": 0},
+                           "south": {"uv": [2, 0, 4, 2], "texture": null}}},
+                {"name": "hidden", "uuid": "b", "from": [0, 0, 0], "to": [1, 1, 1],
+                 "visibility": false, "faces": {"north": {"uv": [0, 0, 1, 1], "texture": 0}}},
+                {"name": "ear", "uuid": "c", "from": [-1, 32, -1], "to": [1, 34, 1],
+                 "box_uv": true, "uv_offset": [8, 0], "faces": {}}
+            ],
+            "outliner": [
+                {"name": "glasses", "uuid": "g", "origin": [0, 24, 0], "rotation": [0, 90, 0],
+                 "children": ["a", "b"]},
+                "c"
+            ],
+            "textures": [{"source": "data:image/png;base64,iVBORw0KGgo="}]
+        }"#;
+        let model = AttachmentModel::from_bbmodel(json.as_bytes()).unwrap();
+        // Six faces of the ungrouped ear, then one of the lens.
+        assert_eq!(model.vertices().len(), 36 + 6);
+        let lens = &model.vertices()[36..];
+        // Turned a quarter about Y, the lens at the front now faces the player's side.
+        for vertex in lens {
+            assert!(vertex.normal[0].abs() > 0.99, "{vertex:?}");
+            assert!(vertex.position[1] >= 28.0 / 16.0 - 1e-5);
+            assert!(vertex
+                .texture
+                .iter()
+                .all(|&t| (0.0..=2.0 / 32.0).contains(&t)));
+        }
+        let ear = Bounds::from_vertices(&model.vertices()[..36]);
+        assert_eq!(ear.min, [-1.0 / 16.0, 2.0, -1.0 / 16.0]);
+        assert_eq!(model.embedded_texture(), Some(&b"\x89PNG\r\n\x1a\n"[..]));
+    }
+
+    #[test]
+    fn bbmodel_x_matches_the_player_model() {
+        let json = r#"{"elements": [
+            {"uuid": "a", "from": [4, 0, 0], "to": [6, 2, 2],
+             "faces": {"north": {"uv": [0, 0, 2, 2], "texture": 0}}}
+        ]}"#;
+        let bounds = AttachmentModel::from_bbmodel(json.as_bytes())
+            .unwrap()
+            .bounds();
+        assert_eq!(bounds.min[0], 4.0 / 16.0);
+        assert_eq!(bounds.max[0], 6.0 / 16.0);
+        assert!(AttachmentModel::from_bbmodel(br#"{"elements": []}"#).is_err());
+        assert!(AttachmentModel::from_bbmodel(b"{").is_err());
+    }
+
+    #[test]
+    fn transforms_scale_then_rotate_then_move() {
+        let transform = AttachmentTransform {
+            offset: Vector3::new(0.0, 1.75, 0.0),
+            rotation: Vector3::new(0.0, 90.0, 0.0),
+            scale: Vector3::new(2.0, 1.0, 1.0),
+        };
+        let p = transform.matrix() * cgmath::Vector4::new(1.0, 0.0, 0.0, 1.0);
+        assert!((p.x - 0.0).abs() < 1e-5 && (p.y - 1.75).abs() < 1e-5 && (p.z + 2.0).abs() < 1e-5);
+        assert_eq!(
+            AttachmentTransform::default().matrix(),
+            Matrix4::from_scale(1.0)
+        );
+    }
+}
@@ -0,0 +1,1078 @@
+//! Bedrock Edition skins: `geometry.json` models and skin packs (`.mcpack` archives or their
+//! extracted folders).
+//!
+//! Geometry is turned into the six-part player mesh of [`crate::model::Model`]: every bone is
+//! assigned to the body part it hangs from, so custom geometry follows the posture of the limb it
+//! is attached to. Bones that are not part of the player's skin (`cape`, held items) are skipped.
+//!
+//! [`ClientSkin`] reads the skin a Bedrock client sends on login (as forwarded by Geyser),
+//! including persona skins, whose pieces are reported in [`ClientSkin::warnings`] rather than drawn.
+
+use std::collections::HashMap;
+use std::io::Read;
+
+use cgmath::{Deg, Matrix3, Vector3};
+use serde_json::Value;
+
+use crate::character::SkinType;
+use crate::error::EidolonError;
+use crate::model::TexturedVertex;
+use crate::profile::decode_base64;
+use crate::texture::SkinImage;
+
+/// Geometry identifiers that mean "the standard player model".
+const CLASSIC_GEOMETRY: &str = "geometry.humanoid.custom";
+const SLIM_GEOMETRY: &str = "geometry.humanoid.customSlim";
+
+/// One model from a `geometry.json` file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BedrockGeometry {
+    /// e.g. `geometry.skinpack.example.custom`.
+    pub identifier: String,
+    /// Texture size the UVs are given in, in pixels.
+    pub texture_width: f32,
+    pub texture_height: f32,
+    pub bones: Vec<Bone>,
+}
+
+/// A named group of cubes rotating around a pivot, optionally attached to a parent bone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bone {
+    pub name: String,
+    pub parent: Option<String>,
+    pub pivot: [f32; 3],
+    /// Degrees around X, then Y, then Z.
+    pub rotation: [f32; 3],
+    pub cubes: Vec<Cube>,
+}
+
+/// A textured box, in model pixels (Y up, 16 pixels to a block).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cube {
+    /// Minimum corner.
+    pub origin: [f32; 3],
+    pub size: [f32; 3],
+    pub pivot: [f32; 3],
+    /// Degrees around X, then Y, then Z, about `pivot`.
+    pub rotation: [f32; 3],
+    /// Grows the box on every side without changing its UVs (overlay layers).
+    pub inflate: f32,
+    /// Flip the texture horizontally (box UV only).
+    pub mirror: bool,
+    pub uv: CubeUv,
+}
+
+/// How a cube's faces map onto the texture.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CubeUv {
+    /// The standard box unwrap from this top-left corner, as on Java skins.
+    Box([f32; 2]),
+    /// A rectangle per face, in [`Face`] order; `None` faces are not drawn.
+    PerFace([Option<FaceUv>; 6]),
+}
+
+/// Texture rectangle of one face; a negative size flips the face.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaceUv {
+    pub uv: [f32; 2],
+    pub size: [f32; 2],
+}
+
+/// Cube faces as named in `geometry.json`. `North` is the front of the player and `East` their
+/// right side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Face {
+    North,
+    East,
+    South,
+    West,
+    Up,
+    Down,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [
+        Face::North,
+        Face::East,
+        Face::South,
+        Face::West,
+        Face::Up,
+        Face::Down,
+    ];
+
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Face::North => "north",
+            Face::East => "east",
+            Face::South => "south",
+            Face::West => "west",
+            Face::Up => "up",
+            Face::Down => "down",
+        }
+    }
+}
+
+impl BedrockGeometry {
+    /// Parse every model in a `geometry.json` file, in both the `1.12.0+` layout
+    /// (`"minecraft:geometry": [...]`) and the older `1.8.0` layout (`"geometry.<name>": {...}`).
+    /// Models without bones, which only name a parent to inherit from, are skipped.
+    pub fn parse_file(json: &[u8]) -> Result<Vec<Self>, EidolonError> {
+        let file: Value = serde_json::from_slice(json)
+            .map_err(|e| EidolonError::model(format!("malformed geometry JSON: {e}")))?;
+        let mut geometries = Vec::new();
+        if let Some(models) = file["minecraft:geometry"].as_array() {
+            for model in models {
+                let description = &model["description"];
+                let identifier = description["identifier"].as_str().unwrap_or_default();
+                geometries.push(Self::parse_model(
+                    identifier,
+                    model,
+                    &description["texture_width"],
+                    &description["texture_height"],
+                )?);
+            }
+        }
+        if let Some(file) = file.as_object() {
+            for (key, model) in file.iter().filter(|(key, _)| key.starts_with("geometry.")) {
+                // `geometry.child:geometry.parent`
+                let identifier = key.split(':').next().unwrap_or(key);
+                geometries.push(Self::parse_model(
+                    identifier,
+                    model,
+                    &model["texturewidth"],
+                    &model["textureheight"],
+                )?);
+            }
+        }
+        geometries.retain(|geometry| !geometry.bones.is_empty());
+        if geometries.is_empty() {
+            return Err(EidolonError::model(
+                "geometry file has no models with bones",
+            ));
+        }
+        Ok(geometries)
+    }
+
+    /// The model called `identifier` in a `geometry.json` file.
+    pub fn find(json: &[u8], identifier: &str) -> Result<Self, EidolonError> {
+        Self::parse_file(json)?
+            .into_iter()
+            .find(|geometry| geometry.identifier == identifier)
+            .ok_or_else(|| EidolonError::model(format!("no geometry '{identifier}' in file")))
+    }
+
+    fn parse_model(
+        identifier: &str,
+        model: &Value,
+        width: &Value,
+        height: &Value,
+    ) -> Result<Self, EidolonError> {
+        let bones = model["bones"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter(|bone| bone["neverRender"] != true)
+            .map(parse_bone)
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            identifier: identifier.to_string(),
+            texture_width: width.as_f64().unwrap_or(64.0) as f32,
+            texture_height: height.as_f64().unwrap_or(64.0) as f32,
+            bones,
+        })
+    }
+
+    /// Triangles of every cube, grouped by the [`crate::model::Model`] part they belong to
+    /// (`"Head"`, `"Hat Layer"`, ...). Every part is present, possibly empty.
+    pub(crate) fn part_vertices(&self) -> HashMap<&'static str, Vec<TexturedVertex>> {
+        let mut parts: HashMap<_, _> = PART_NAMES.iter().map(|&name| (name, Vec::new())).collect();
+        let bones: HashMap<&str, &Bone> = self.bones.iter().map(|b| (b.name.as_str(), b)).collect();
+        for bone in &self.bones {
+            let Some(part) = self.part_of(bone, &bones) else {
+                continue;
+            };
+            let vertices = parts.get_mut(part).expect("every part is present");
+            self.push_bone_vertices(bone, &bones, vertices);
+        }
+        parts
+    }
+
+    /// Triangles of every cube of every bone as one mesh, whatever the bones are called.
+    pub(crate) fn vertices(&self) -> Vec<TexturedVertex> {
+        let bones: HashMap<&str, &Bone> = self.bones.iter().map(|b| (b.name.as_str(), b)).collect();
+        let mut vertices = Vec::new();
+        for bone in &self.bones {
+            self.push_bone_vertices(bone, &bones, &mut vertices);
+        }
+        vertices
+    }
+
+    /// Append the triangles of `bone`'s cubes, posed by the bone and its parents.
+    fn push_bone_vertices(
+        &self,
+        bone: &Bone,
+        bones: &HashMap<&str, &Bone>,
+        vertices: &mut Vec<TexturedVertex>,
+    ) {
+        for cube in &bone.cubes {
+            for (face, corners, normal) in cube.faces() {
+                let Some(rect) = cube.face_uv(face) else {
+                    continue;
+                };
+                let uvs = rect_corners(rect, cube.mirror && matches!(cube.uv, CubeUv::Box(_)));
+                let transform = |p: Vector3<f32>| {
+                    let p = rotate_about(p, cube.pivot, cube.rotation);
+                    self.ancestors(bone, bones)
+                        .fold(p, |p, bone| rotate_about(p, bone.pivot, bone.rotation))
+                };
+                let normal = self
+                    .ancestors(bone, bones)
+                    .fold(rotation(cube.rotation) * normal, |n, bone| {
+                        rotation(bone.rotation) * n
+                    });
+                let vertex = |i: usize| {
+                    let p = transform(corners[i]);
+                    let [u, v] = uvs[i];
+                    // Bedrock geometry is mirrored in X relative to the OBJ model, which
+                    // has the player's right at +X; one unit is a block.
+                    TexturedVertex {
+                        position: [-p.x / 16.0, p.y / 16.0, p.z / 16.0],
+                        normal: [-normal.x, normal.y, normal.z],
+                        texture: [u / self.texture_width, v / self.texture_height],
+                    }
+                };
+                // Corners run top-left, top-right, bottom-right, bottom-left on the texture.
+                vertices.extend([0, 3, 2, 0, 2, 1].map(vertex));
+            }
+        }
+    }
+
+    /// `bone` and its parents, innermost first.
+    fn ancestors<'a>(
+        &'a self,
+        bone: &'a Bone,
+        bones: &'a HashMap<&str, &'a Bone>,
+    ) -> impl Iterator<Item = &'a Bone> + 'a {
+        // Bounded so a parent cycle in a broken file cannot loop forever.
+        std::iter::successors(Some(bone), |bone| {
+            bones.get(bone.parent.as_deref()?).copied()
+        })
+        .take(self.bones.len())
+    }
+
+    /// The model part a bone's cubes belong to: its own name if it is a player bone, otherwise
+    /// the nearest player bone it hangs from; [`None`] for skipped bones.
+    fn part_of(&self, bone: &Bone, bones: &HashMap<&str, &Bone>) -> Option<&'static str> {
+        for bone in self.ancestors(bone, bones) {
+            match bone.name.to_ascii_lowercase().as_str() {
+                "head" => return Some("Head"),
+                "hat" | "helmet" => return Some("Hat Layer"),
+                "body" | "waist" => return Some("Body"),
+                "jacket" => return Some("Body Layer"),
+                "rightarm" => return Some("Right Arm"),
+                "rightsleeve" => return Some("Right Arm Layer"),
+                "leftarm" => return Some("Left Arm"),
+                "leftsleeve" => return Some("Left Arm Layer"),
+                "rightleg" => return Some("Right Leg"),
+                "rightpants" => return Some("Right Leg Layer"),
+                "leftleg" => return Some("Left Leg"),
+                "leftpants" => return Some("Left Leg Layer"),
+                "cape" | "rightitem" | "leftitem" | "elytra" => return None,
+                _ => {}
+            }
+        }
+        Some("Body")
+    }
+}
+
+/// Object names of [`crate::model::Model`] parts.
+const PART_NAMES: [&str; 12] = [
+    "Head",
+    "Hat Layer",
+    "Body",
+    "Body Layer",
+    "Right Arm",
+    "Right Arm Layer",
+    "Left Arm",
+    "Left Arm Layer",
+    "Right Leg",
+    "Right Leg Layer",
+    "Left Leg",
+    "Left Leg Layer",
+];
+
+impl Cube {
+    /// Each face with its corners (top-left, top-right, bottom-right, bottom-left as seen on the
+    /// texture) and outward normal, in Bedrock coordinates. The player's front is -Z and their
+    /// right side -X.
+    fn faces(&self) -> [(Face, [Vector3<f32>; 4], Vector3<f32>); 6] {
+        let [x0, y0, z0] = self.origin.map(|c| c - self.inflate);
+        let [x1, y1, z1] = std::array::from_fn(|i| self.origin[i] + self.size[i] + self.inflate);
+        let p = Vector3::new;
+        [
+            (
+                Face::North,
+                [p(x0, y1, z0), p(x1, y1, z0), p(x1, y0, z0), p(x0, y0, z0)],
+                p(0.0, 0.0, -1.0),
+            ),
+            (
+                Face::East,
+                [p(x0, y1, z1), p(x0, y1, z0), p(x0, y0, z0), p(x0, y0, z1)],
+                p(-1.0, 0.0, 0.0),
+            ),
+            (
+                Face::South,
+                [p(x1, y1, z1), p(x0, y1, z1), p(x0, y0, z1), p(x1, y0, z1)],
+                p(0.0, 0.0, 1.0),
+            ),
+            (
+                Face::West,
+                [p(x1, y1, z0), p(x1, y1, z1), p(x1, y0, z1), p(x1, y0, z0)],
+                p(1.0, 0.0, 0.0),
+            ),
+            (
+                Face::Up,
+                [p(x0, y1, z1), p(x1, y1, z1), p(x1, y1, z0), p(x0, y1, z0)],
+                p(0.0, 1.0, 0.0),
+            ),
+            (
+                Face::Down,
+                [p(x0, y0, z1), p(x1, y0, z1), p(x1, y0, z0), p(x0, y0, z0)],
+                p(0.0, -1.0, 0.0),
+            ),
+        ]
+    }
+
+    /// Texture rectangle of `face`, in pixels.
+    fn face_uv(&self, face: Face) -> Option<FaceUv> {
+        let [u, v] = match &self.uv {
+            CubeUv::PerFace(faces) => return faces[face as usize],
+            CubeUv::Box(uv) => *uv,
+        };
+        // The size is not inflated: overlay layers stretch the same pixels.
+        let [w, h, d] = self.size.map(f32::floor);
+        // Mirrored cubes swap the textures of their two sides.
+        let face = match (face, self.mirror) {
+            (Face::East, true) => Face::West,
+            (Face::West, true) => Face::East,
+            (face, _) => face,
+        };
+        let (uv, size) = match face {
+            Face::North => ([u + d, v + d], [w, h]),
+            Face::East => ([u, v + d], [d, h]),
+            Face::South => ([u + d + w + d, v + d], [w, h]),
+            Face::West => ([u + d + w, v + d], [d, h]),
+            Face::Up => ([u + d, v], [w, d]),
+            Face::Down => ([u + d + w, v], [w, d]),
+        };
+        Some(FaceUv { uv, size })
+    }
+}
+
+/// Texture coordinates of a rectangle's corners: top-left, top-right, bottom-right, bottom-left.
+fn rect_corners(rect: FaceUv, mirror: bool) -> [[f32; 2]; 4] {
+    let [u, v] = rect.uv;
+    let [w, h] = rect.size;
+    let (left, right) = if mirror { (u + w, u) } else { (u, u + w) };
+    [[left, v], [right, v], [right, v + h], [left, v + h]]
+}
+
+fn rotation(degrees: [f32; 3]) -> Matrix3<f32> {
+    Matrix3::from_angle_z(Deg(degrees[2]))
+        * Matrix3::from_angle_y(Deg(degrees[1]))
+        * Matrix3::from_angle_x(Deg(degrees[0]))
+}
+
+fn rotate_about(p: Vector3<f32>, pivot: [f32; 3], degrees: [f32; 3]) -> Vector3<f32> {
+    if degrees == [0.0; 3] {
+        return p;
+    }
+    let pivot = Vector3::from(pivot);
+    rotation(degrees) * (p - pivot) + pivot
+}
+
+fn parse_bone(bone: &Value) -> Result<Bone, EidolonError> {
+    let name = bone["name"]
+        .as_str()
+        .ok_or_else(|| EidolonError::model("geometry bone has no name"))?;
+    let mirror = bone["mirror"] == true;
+    let inflate = bone["inflate"].as_f64().unwrap_or(0.0) as f32;
+    let cubes = bone["cubes"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|cube| parse_cube(cube, mirror, inflate))
+        .collect::<Result<_, _>>()
+        .map_err(|e| EidolonError::model(format!("bone '{name}': {e}")))?;
+    let pivot = vec3(&bone["pivot"]).unwrap_or_default();
+    Ok(Bone {
+        name: name.to_string(),
+        parent: bone["parent"].as_str().map(str::to_string),
+        pivot,
+        rotation: vec3(&bone["rotation"]).unwrap_or_default(),
+        cubes,
+    })
+}
+
+fn parse_cube(cube: &Value, mirror: bool, inflate: f32) -> Result<Cube, String> {
+    let origin = vec3(&cube["origin"]).ok_or("cube has no origin")?;
+    let size = vec3(&cube["size"]).ok_or("cube has no size")?;
+    let uv = match &cube["uv"] {
+        Value::Object(faces) => CubeUv::PerFace(Face::ALL.map(|face| {
+            let face = faces.get(face.key())?;
+            Some(FaceUv {
+                uv: vec2(&face["uv"])?,
+                size: vec2(&face["uv_size"])?,
+            })
+        })),
+        uv => CubeUv::Box(vec2(uv).unwrap_or_default()),
+    };
+    Ok(Cube {
+        origin,
+        size,
+        // Cubes rotate about their own pivot, or their origin when none is given.
+        pivot: vec3(&cube["pivot"]).unwrap_or(origin),
+        rotation: vec3(&cube["rotation"]).unwrap_or_default(),
+        inflate: cube["inflate"].as_f64().map_or(inflate, |i| i as f32),
+        mirror: cube["mirror"].as_bool().unwrap_or(mirror),
+        uv,
+    })
+}
+
+pub(crate) fn vec3(value: &Value) -> Option<[f32; 3]> {
+    let array = value.as_array()?;
+    let component = |i: usize| array.get(i)?.as_f64().map(|c| c as f32);
+    Some([component(0)?, component(1)?, component(2)?])
+}
+
+pub(crate) fn vec2(value: &Value) -> Option<[f32; 2]> {
+    let array = value.as_array()?;
+    let component = |i: usize| array.get(i)?.as_f64().map(|c| c as f32);
+    Some([component(0)?, component(1)?])
+}
+
+/// Player model a pack skin is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PackModel<'a> {
+    /// The standard player model.
+    Standard(SkinType),
+    /// Custom geometry from the pack's `geometry.json`.
+    Custom(&'a BedrockGeometry),
+}
+
+/// One skin of a [`SkinPack`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackSkin {
+    /// `localization_name` from `skins.json`.
+    pub name: String,
+    /// Texture PNG bytes.
+    pub texture: Vec<u8>,
+    /// Geometry identifier the skin uses.
+    pub geometry: String,
+}
+
+/// A Bedrock skin pack: `skins.json`, the skin textures and an optional `geometry.json`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkinPack {
+    pub skins: Vec<PackSkin>,
+    pub geometries: Vec<BedrockGeometry>,
+}
+
+impl SkinPack {
+    /// Read a pack from `.mcpack` (zip) bytes. The pack may sit in a folder inside the archive.
+    pub fn from_mcpack(bytes: &[u8]) -> Result<Self, EidolonError> {
+        let files = read_zip(bytes)?;
+        let manifest = files
+            .keys()
+            .filter(|name| name.rsplit('/').next() == Some("skins.json"))
+            .min_by_key(|name| name.len())
+            .ok_or_else(|| EidolonError::model("skin pack has no skins.json"))?;
+        let root = &manifest[..manifest.len() - "skins.json".len()];
+        Self::from_files(|name| files.get(&format!("{root}{name}")).cloned())
+    }
+
+    /// Read an extracted pack from the folder containing `skins.json`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(dir: impl AsRef<std::path::Path>) -> Result<Self, EidolonError> {
+        let dir = dir.as_ref();
+        Self::from_files(|name| std::fs::read(dir.join(name)).ok())
+    }
+
+    fn from_files(read: impl Fn(&str) -> Option<Vec<u8>>) -> Result<Self, EidolonError> {
+        let manifest =
+            read("skins.json").ok_or_else(|| EidolonError::model("skin pack has no skins.json"))?;
+        let manifest: Value = serde_json::from_slice(&manifest)
+            .map_err(|e| EidolonError::model(format!("malformed skins.json: {e}")))?;
+        let skins = manifest["skins"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|skin| {
+                let texture = skin["texture"]
+                    .as_str()
+                    .ok_or_else(|| EidolonError::model("pack skin has no texture"))?;
+                if texture.split(['/', '\\']).any(|c| c == "..") {
+                    return Err(EidolonError::invalid_path(format!(
+                        "pack texture '{texture}' leaves the pack"
+                    )));
+                }
+                Ok(PackSkin {
+                    name: skin["localization_name"]
+                        .as_str()
+                        .unwrap_or(texture)
+                        .to_string(),
+                    texture: read(texture).ok_or_else(|| {
+                        EidolonError::model(format!("pack texture '{texture}' is missing"))
+                    })?,
+                    geometry: skin["geometry"]
+                        .as_str()
+                        .unwrap_or(CLASSIC_GEOMETRY)
+                        .to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, EidolonError>>()?;
+        let geometries = match read("geometry.json") {
+            Some(json) => BedrockGeometry::parse_file(&json)?,
+            None => Vec::new(),
+        };
+        Ok(Self { skins, geometries })
+    }
+
+    /// The model `skin` is drawn with.
+    pub fn model(&self, skin: &PackSkin) -> Result<PackModel<'_>, EidolonError> {
+        match skin.geometry.as_str() {
+            CLASSIC_GEOMETRY => Ok(PackModel::Standard(SkinType::Classic)),
+            SLIM_GEOMETRY => Ok(PackModel::Standard(SkinType::Slim)),
+            identifier => self
+                .geometries
+                .iter()
+                .find(|geometry| geometry.identifier == identifier)
+                .map(PackModel::Custom)
+                .ok_or_else(|| {
+                    EidolonError::model(format!(
+                        "skin '{}' uses geometry '{identifier}', which the pack does not define",
+                        skin.name
+                    ))
+                }),
+        }
+    }
+}
+
+/// A skin as a Bedrock client sends it in its login data (the `ClientData` fields Geyser
+/// forwards): raw RGBA pixels, a resource patch naming the geometry, the geometry JSON, and for
+/// persona skins the list of persona pieces.
+#[derive(Debug, Clone)]
+pub struct ClientSkin {
+    pub skin: SkinImage,
+    pub model: ClientModel,
+    /// Made in the character creator rather than from a skin file.
+    pub persona: bool,
+    pub persona_pieces: Vec<PersonaPiece>,
+    /// What could not be drawn as the client would; empty when the skin renders faithfully.
+    pub warnings: Vec<SkinWarning>,
+}
+
+/// Player model a [`ClientSkin`] is drawn with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientModel {
+    Standard(SkinType),
+    Custom(BedrockGeometry),
+}
+
+/// A persona piece (hair, eyes, clothing, ...) from `PersonaPieces`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PersonaPiece {
+    pub piece_id: String,
+    /// e.g. `persona_hair`, `persona_top`.
+    pub piece_type: String,
+    pub pack_id: String,
+    pub product_id: String,
+    /// Part of the default persona rather than chosen by the player.
+    pub is_default: bool,
+}
+
+/// Why a [`ClientSkin`] may not look as it does in game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkinWarning {
+    /// Persona pieces whose geometry is not available were left out; only the base body is drawn.
+    PersonaPiecesNotDrawn { piece_types: Vec<String> },
+    /// The skin's geometry could not be used, so the standard model is drawn instead.
+    GeometryFallback { reason: String },
+}
+
+/// Geometry for persona pieces, which clients download from the marketplace rather than send.
+/// Pieces the source resolves are drawn as extra bones of the skin's custom geometry, with UVs
+/// into the skin texture.
+pub trait PersonaPieceSource {
+    fn bones(&self, piece: &PersonaPiece) -> Option<Vec<Bone>>;
+}
+
+impl ClientSkin {
+    /// Parse client skin JSON. Persona pieces are not drawn; see [`ClientSkin::from_json_with`].
+    pub fn from_json(json: &[u8]) -> Result<Self, EidolonError> {
+        struct NoPieces;
+        impl PersonaPieceSource for NoPieces {
+            fn bones(&self, _piece: &PersonaPiece) -> Option<Vec<Bone>> {
+                None
+            }
+        }
+        Self::from_json_with(json, &NoPieces)
+    }
+
+    /// Parse client skin JSON, drawing the persona pieces `pieces` has geometry for.
+    ///
+    /// Only unreadable pixels fail; geometry problems fall back to the standard model with a
+    /// [`SkinWarning`].
+    pub fn from_json_with(
+        json: &[u8],
+        pieces: &dyn PersonaPieceSource,
+    ) -> Result<Self, EidolonError> {
+        let data: Value = serde_json::from_slice(json)
+            .map_err(|e| EidolonError::texture(format!("malformed client skin JSON: {e}")))?;
+        let skin = client_skin_image(&data)?;
+        let mut warnings = Vec::new();
+
+        let standard = if data["ArmSize"] == "slim" {
+            SkinType::Slim
+        } else {
+            SkinType::Classic
+        };
+        let patch = decode_base64_json(&data["SkinResourcePatch"]);
+        let identifier = patch
+            .as_ref()
+            .and_then(|patch| patch["geometry"]["default"].as_str())
+            .unwrap_or(CLASSIC_GEOMETRY);
+        let mut model = match identifier {
+            CLASSIC_GEOMETRY => ClientModel::Standard(SkinType::Classic),
+            SLIM_GEOMETRY => ClientModel::Standard(SkinType::Slim),
+            identifier => match client_geometry(&data, identifier) {
+                Ok(geometry) => ClientModel::Custom(geometry),
+                Err(reason) => {
+                    warnings.push(SkinWarning::GeometryFallback { reason });
+                    ClientModel::Standard(standard)
+                }
+            },
+        };
+
+        let persona = data["PersonaSkin"] == true;
+        let persona_pieces: Vec<PersonaPiece> = data["PersonaPieces"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|piece| {
+                let field = |key: &str| piece[key].as_str().unwrap_or_default().to_string();
+                PersonaPiece {
+                    piece_id: field("PieceId"),
+                    piece_type: field("PieceType"),
+                    pack_id: field("PackId"),
+                    product_id: field("ProductId"),
+                    is_default: piece["IsDefault"] == true,
+                }
+            })
+            .collect();
+        let mut missing = Vec::new();
+        for piece in &persona_pieces {
+            match (pieces.bones(piece), &mut model) {
+                (Some(bones), ClientModel::Custom(geometry)) => geometry.bones.extend(bones),
+                _ => missing.push(piece.piece_type.clone()),
+            }
+        }
+        if persona && !missing.is_empty() {
+            warnings.push(SkinWarning::PersonaPiecesNotDrawn {
+                piece_types: missing,
+            });
+        }
+
+        Ok(Self {
+            skin,
+            model,
+            persona,
+            persona_pieces,
+            warnings,
+        })
+    }
+}
+
+/// `SkinData`: base64 RGBA pixels, `SkinImageWidth` × `SkinImageHeight`.
+fn client_skin_image(data: &Value) -> Result<SkinImage, EidolonError> {
+    let dimension = |key: &str| {
+        data[key]
+            .as_u64()
+            .and_then(|d| u32::try_from(d).ok())
+            .ok_or_else(|| EidolonError::texture(format!("client skin has no '{key}'")))
+    };
+    let (width, height) = (dimension("SkinImageWidth")?, dimension("SkinImageHeight")?);
+    let pixels = data["SkinData"]
+        .as_str()
+        .and_then(decode_base64)
+        .ok_or_else(|| EidolonError::texture("client skin 'SkinData' is not base64"))?;
+    let image = image::RgbaImage::from_raw(width, height, pixels).ok_or_else(|| {
+        EidolonError::texture(format!(
+            "client skin pixels do not match {width}x{height} RGBA"
+        ))
+    })?;
+    SkinImage::from_image(&image::DynamicImage::ImageRgba8(image))
+}
+
+/// `SkinGeometryData` (base64 `geometry.json`), model `identifier`.
+fn client_geometry(data: &Value, identifier: &str) -> Result<BedrockGeometry, String> {
+    let json = data["SkinGeometryData"]
+        .as_str()
+        .and_then(decode_base64)
+        .ok_or_else(|| format!("no geometry data for '{identifier}'"))?;
+    BedrockGeometry::find(&json, identifier).map_err(|e| e.to_string())
+}
+
+fn decode_base64_json(value: &Value) -> Option<Value> {
+    serde_json::from_slice(&decode_base64(value.as_str()?)?).ok()
+}
+
+/// Files of a zip archive by path. Only stored and deflated entries are supported, which is all
+/// that pack tools produce.
+fn read_zip(bytes: &[u8]) -> Result<HashMap<String, Vec<u8>>, EidolonError> {
+    let invalid = || EidolonError::model("not a valid .mcpack (zip) archive");
+    let u16_at = |at: usize| -> Option<usize> {
+        Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?).into())
+    };
+    let u32_at = |at: usize| -> Option<usize> {
+        Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?) as usize)
+    };
+    // End of central directory record, 22 bytes plus a trailing comment.
+    let end = (0..=bytes.len().saturating_sub(22))
+        .rev()
+        .find(|&at| u32_at(at) == Some(0x0605_4b50))
+        .ok_or_else(invalid)?;
+    let count = u16_at(end + 10).ok_or_else(invalid)?;
+    let mut entry = u32_at(end + 16).ok_or_else(invalid)?;
+
+    let mut files = HashMap::new();
+    for _ in 0..count {
+        if u32_at(entry) != Some(0x0201_4b50) {
+            return Err(invalid());
+        }
+        let method = u16_at(entry + 10).ok_or_else(invalid)?;
+        let compressed = u32_at(entry + 20).ok_or_else(invalid)?;
+        let name_len = u16_at(entry + 28).ok_or_else(invalid)?;
+        let extra_len = u16_at(entry + 30).ok_or_else(invalid)?;
+        let comment_len = u16_at(entry + 32).ok_or_else(invalid)?;
+        let local = u32_at(entry + 42).ok_or_else(invalid)?;
+        let name = bytes
+            .get(entry + 46..entry + 46 + name_len)
+            .ok_or_else(invalid)?;
+        let name = String::from_utf8_lossy(name).replace('\\', "/");
+        entry += 46 + name_len + extra_len + comment_len;
+        if name.ends_with('/') {
+            continue;
+        }
+
+        let data_start = local
+            + 30
+            + u16_at(local + 26).ok_or_else(invalid)?
+            + u16_at(local + 28).ok_or_else(invalid)?;
+        let data = bytes
+            .get(data_start..data_start + compressed)
+            .ok_or_else(invalid)?;
+        let data = match method {
+            0 => data.to_vec(),
+            8 => {
+                let mut out = Vec::new();
+                flate2::read::DeflateDecoder::new(data)
+                    .read_to_end(&mut out)
+                    .map_err(|e| {
+                        EidolonError::model(format!("corrupt pack entry '{name}': {e}"))
+                    })?;
+                out
+            }
+            _ => {
+                return Err(EidolonError::model(format!(
+                    "pack entry '{name}' uses unsupported compression method {method}"
+                )))
+            }
+        };
+        files.insert(name, data);
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The standard player body and jacket, in the `1.12.0` layout.
+    const GEOMETRY: &str = r#"{
+        "format_version": "1.12.0",
+        "minecraft:geometry": [{
+            "description": {"identifier": "geometry.test", "texture_width": 64, "texture_height": 64},
+            "bones": [
+                {"name": "body", "pivot": [0, 24, 0],
+                 "cubes": [{"origin": [-4, 12, -2], "size": [8, 12, 4], "uv": [16, 16]}]},
+                {"name": "jacket", "parent": "body", "pivot": [0, 24, 0],
+                 "cubes": [{"origin": [-4, 12, -2], "size": [8, 12, 4], "uv": [16, 32], "inflate": 0.25}]},
+                {"name": "horn", "parent": "head", "pivot": [0, 32, 0],
+                 "cubes": [{"origin": [-1, 32, -1], "size": [2, 4, 2],
+                            "uv": {"north": {"uv": [0, 0], "uv_size": [2, 4]}}}]},
+                {"name": "head", "pivot": [0, 24, 0]},
+                {"name": "cape", "parent": "body", "pivot": [0, 24, 3],
+                 "cubes": [{"origin": [-5, 8, 3], "size": [10, 16, 1], "uv": [0, 0]}]}
+            ]
+        }]
+    }"#;
+
+    #[test]
+    fn box_uv_matches_the_bundled_model() {
+        let geometry = BedrockGeometry::find(GEOMETRY.as_bytes(), "geometry.test").unwrap();
+        let parts = geometry.part_vertices();
+        let body = &parts["Body"];
+        assert_eq!(body.len(), 36);
+
+        let (models, _) = tobj::load_obj("resources/classic.obj", &tobj::GPU_LOAD_OPTIONS).unwrap();
+        let mesh = &models.iter().find(|m| m.name == "Body").unwrap().mesh;
+        let obj_vertices: Vec<([f32; 3], [f32; 2])> = mesh
+            .indices
+            .iter()
+            .map(|&i| {
+                let i = i as usize;
+                let p = &mesh.positions[i * 3..i * 3 + 3];
+                let t = &mesh.texcoords[i * 2..i * 2 + 2];
+                ([p[0], p[1], p[2]], [t[0], 1.0 - t[1]])
+            })
+            .collect();
+        let close = |a: &[f32], b: &[f32]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-4);
+        for vertex in body {
+            assert!(
+                obj_vertices
+                    .iter()
+                    .any(|(p, t)| close(p, &vertex.position) && close(t, &vertex.texture)),
+                "{vertex:?} is not a vertex of the bundled body"
+            );
+        }
+    }
+
+    #[test]
+    fn bones_follow_their_body_part() {
+        let geometry = BedrockGeometry::find(GEOMETRY.as_bytes(), "geometry.test").unwrap();
+        let parts = geometry.part_vertices();
+        assert_eq!(parts.len(), 12);
+        assert_eq!(parts["Body Layer"].len(), 36);
+        // The horn hangs from the head; only its north face has UVs. The cape is skipped.
+        assert_eq!(parts["Head"].len(), 6);
+        assert!(parts["Head"].iter().all(|v| v.position[1] >= 2.0));
+        assert!(parts["Right Arm"].is_empty());
+        assert_eq!(parts.values().map(Vec::len).sum::<usize>(), 78);
+
+        let jacket_top = parts["Body Layer"]
+            .iter()
+            .map(|v| v.position[1])
+            .fold(f32::MIN, f32::max);
+        assert!((jacket_top - (24.25 / 16.0)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn legacy_geometry_files_parse() {
+        let legacy = r#"{
+            "format_version": "1.8.0",
+            "geometry.legacy:geometry.humanoid": {
+                "texturewidth": 128, "textureheight": 128,
+                "bones": [{"name": "rightArm", "pivot": [-5, 22, 0], "rotation": [0, 0, 90],
+                           "cubes": [{"origin": [-8, 12, -2], "size": [4, 12, 4], "uv": [40, 16]}]}]
+            },
+            "geometry.inherited:geometry.humanoid.custom": {}
+        }"#;
+        let geometries = BedrockGeometry::parse_file(legacy.as_bytes()).unwrap();
+        assert_eq!(geometries.len(), 1);
+        assert_eq!(geometries[0].identifier, "geometry.legacy");
+        assert_eq!(geometries[0].texture_width, 128.0);
+        let arm = &geometries[0].part_vertices()["Right Arm"];
+        assert_eq!(arm.len(), 36);
+        // Rotated 90° about the shoulder, the arm sticks out sideways instead of hanging down.
+        let height = |f: fn(f32, f32) -> f32| {
+            arm.iter()
+                .map(|v| v.position[1])
+                .fold(arm[0].position[1], f)
+        };
+        assert!(height(f32::max) - height(f32::min) < 0.26);
+
+        assert!(BedrockGeometry::parse_file(b"{}").is_err());
+        assert!(BedrockGeometry::find(legacy.as_bytes(), "geometry.other").is_err());
+    }
+
+    /// A zip archive of stored (uncompressed) entries.
+    fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let (mut out, mut directory) = (Vec::new(), Vec::new());
+        for (name, data) in files {
+            let offset = out.len() as u32;
+            let sizes = [(data.len() as u32).to_le_bytes(); 2].concat();
+            out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+            out.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            out.extend_from_slice(&sizes);
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&[0, 0]);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(data);
+
+            directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            directory.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            directory.extend_from_slice(&sizes);
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let directory_offset = out.len() as u32;
+        out.extend_from_slice(&directory);
+        out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(&[(files.len() as u16).to_le_bytes(); 2].concat());
+        out.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        out.extend_from_slice(&directory_offset.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out
+    }
+
+    #[test]
+    fn mcpack_skins_resolve_their_models() {
+        let skins = br#"{"skins": [
+            {"localization_name": "steve", "geometry": "geometry.humanoid.custom", "texture": "steve.png"},
+            {"localization_name": "alex", "geometry": "geometry.humanoid.customSlim", "texture": "alex.png"},
+            {"localization_name": "custom", "geometry": "geometry.test", "texture": "steve.png"},
+            {"localization_name": "broken", "geometry": "geometry.missing", "texture": "alex.png"}
+        ]}"#;
+        let archive = zip(&[
+            ("pack/", b""),
+            ("pack/skins.json", skins),
+            ("pack/steve.png", b"steve"),
+            ("pack/alex.png", b"alex"),
+            ("pack/geometry.json", GEOMETRY.as_bytes()),
+        ]);
+        let pack = SkinPack::from_mcpack(&archive).unwrap();
+        assert_eq!(pack.skins.len(), 4);
+        assert_eq!(pack.skins[1].texture, b"alex");
+        assert_eq!(
+            pack.model(&pack.skins[1]).unwrap(),
+            PackModel::Standard(SkinType::Slim)
+        );
+        assert!(matches!(
+            pack.model(&pack.skins[2]).unwrap(),
+            PackModel::Custom(geometry) if geometry.identifier == "geometry.test"
+        ));
+        assert!(pack.model(&pack.skins[3]).is_err());
+
+        assert!(SkinPack::from_mcpack(b"not a zip").is_err());
+        let escaping = br#"{"skins": [{"texture": "../secret.png"}]}"#;
+        assert!(matches!(
+            SkinPack::from_mcpack(&zip(&[("skins.json", escaping)])),
+            Err(EidolonError::InvalidPath(_))
+        ));
+    }
+
+    fn base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        bytes
+            .chunks(3)
+            .flat_map(|chunk| {
+                let n = chunk.iter().fold(0u32, |n, &b| n << 8 | u32::from(b))
+                    << (8 * (3 - chunk.len()));
+                (0..=chunk.len()).map(move |i| ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char)
+            })
+            .collect()
+    }
+
+    fn client_json(geometry: &str, extra: serde_json::Value) -> Vec<u8> {
+        let mut data = serde_json::json!({
+            "SkinImageWidth": 64,
+            "SkinImageHeight": 64,
+            "SkinData": base64(&[255; 64 * 64 * 4]),
+            "SkinResourcePatch": base64(format!(r#"{{"geometry":{{"default":"{geometry}"}}}}"#).as_bytes()),
+            "SkinGeometryData": base64(GEOMETRY.as_bytes()),
+        });
+        data.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        data.to_string().into_bytes()
+    }
+
+    #[test]
+    fn client_skins_pick_their_model() {
+        let slim =
+            ClientSkin::from_json(&client_json(SLIM_GEOMETRY, serde_json::json!({}))).unwrap();
+        assert_eq!(slim.model, ClientModel::Standard(SkinType::Slim));
+        assert_eq!(slim.skin.rgba().dimensions(), (64, 64));
+        assert!(slim.warnings.is_empty());
+
+        let custom =
+            ClientSkin::from_json(&client_json("geometry.test", serde_json::json!({}))).unwrap();
+        assert!(
+            matches!(custom.model, ClientModel::Custom(ref g) if g.identifier == "geometry.test")
+        );
+
+        let broken = ClientSkin::from_json(&client_json(
+            "geometry.missing",
+            serde_json::json!({ "ArmSize": "slim" }),
+        ))
+        .unwrap();
+        assert_eq!(broken.model, ClientModel::Standard(SkinType::Slim));
+        assert!(matches!(
+            broken.warnings[..],
+            [SkinWarning::GeometryFallback { .. }]
+        ));
+
+        let short = client_json(SLIM_GEOMETRY, serde_json::json!({ "SkinImageWidth": 128 }));
+        assert!(matches!(
+            ClientSkin::from_json(&short),
+            Err(EidolonError::Texture(_))
+        ));
+    }
+
+    #[test]
+    fn persona_pieces_are_reported_or_drawn() {
+        let persona = client_json(
+            "geometry.test",
+            serde_json::json!({
+                "PersonaSkin": true,
+                "PersonaPieces": [
+                    {"PieceId": "a", "PieceType": "persona_hair", "PackId": "p", "ProductId": "", "IsDefault": false},
+                    {"PieceId": "b", "PieceType": "persona_eyes", "PackId": "p", "ProductId": "", "IsDefault": true}
+                ]
+            }),
+        );
+        let skin = ClientSkin::from_json(&persona).unwrap();
+        assert!(skin.persona);
+        assert_eq!(skin.persona_pieces.len(), 2);
+        assert_eq!(
+            skin.warnings,
+            [SkinWarning::PersonaPiecesNotDrawn {
+                piece_types: vec!["persona_hair".into(), "persona_eyes".into()]
+            }]
+        );
+
+        struct Hair;
+        impl PersonaPieceSource for Hair {
+            fn bones(&self, piece: &PersonaPiece) -> Option<Vec<Bone>> {
+                (piece.piece_type == "persona_hair").then(|| {
+                    vec![Bone {
+                        name: "hair".into(),
+                        parent: Some("head".into()),
+                        pivot: [0.0; 3],
+                        rotation: [0.0; 3],
+                        cubes: Vec::new(),
+                    }]
+                })
+            }
+        }
+        let skin = ClientSkin::from_json_with(&persona, &Hair).unwrap();
+        let ClientModel::Custom(geometry) = &skin.model else {
+            panic!("persona geometry should be used");
+        };
+        assert!(geometry.bones.iter().any(|bone| bone.name == "hair"));
+        assert_eq!(
+            skin.warnings,
+            [SkinWarning::PersonaPiecesNotDrawn {
+                piece_types: vec!["persona_eyes".into()]
+            }]
+        );
+    }
+}
@@ -0,0 +1,544 @@
+//! Shared skin caches: [`StoreCache`] keeps another provider's skins in any [`CacheStore`], so
+//! horizontally scaled render workers can share one cache (e.g. Redis through [`RedisStore`])
+//! instead of each warming its own [`crate::provider::DiskCache`].
+//!
+//! Like HTTP, the crate ships no Redis client: [`RedisStore`] takes a [`RedisCommands`], which a
+//! few lines around any client implement.
+//!
+//! [`ResultCache`] caches finished renders instead: encoded images on disk under a key such as
+//! [`crate::thumbnail::ThumbnailSpec::cache_key`], evicting the least recently used when the
+//! directory outgrows its size limit.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use tracing::{debug, warn};
+
+use crate::error::EidolonError;
+use crate::provider::{describe, parse_description, PlayerId, SkinData, SkinProvider};
+use crate::throttle::{Clock, SystemClock};
+
+/// Key-value storage with expiry for cached skins. Stores that cannot expire entries themselves
+/// may ignore `ttl`, at the cost of serving stale skins.
+pub trait CacheStore: Send + Sync {
+    /// The value under `key`, or `None` if missing or expired.
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, EidolonError>;
+    /// Store `value` under `key`, expiring after `ttl` (`None` keeps it).
+    fn put(&self, key: &str, value: &[u8], ttl: Option<Duration>) -> Result<(), EidolonError>;
+}
+
+/// One store behind several caches, e.g. per-provider caches in one process.
+impl<S: CacheStore> CacheStore for Arc<S> {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, EidolonError> {
+        (**self).get(key)
+    }
+
+    fn put(&self, key: &str, value: &[u8], ttl: Option<Duration>) -> Result<(), EidolonError> {
+        (**self).put(key, value, ttl)
+    }
+}
+
+/// Value and expiry time of a [`MemoryStore`] entry.
+type MemoryEntry = (Vec<u8>, Option<Duration>);
+
+/// An in-process store, for a single worker or for tests.
+pub struct MemoryStore<C = SystemClock> {
+    clock: C,
+    entries: Mutex<HashMap<String, MemoryEntry>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock::default())
+    }
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Clock> MemoryStore<C> {
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            clock,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<C: Clock> CacheStore for MemoryStore<C> {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, EidolonError> {
+        let now = self.clock.now();
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some((_, Some(expires))) if *expires <= now => {
+                entries.remove(key);
+                Ok(None)
+            }
+            entry => Ok(entry.map(|(value, _)| value.clone())),
+        }
+    }
+
+    fn put(&self, key: &str, value: &[u8], ttl: Option<Duration>) -> Result<(), EidolonError> {
+        let expires = ttl.map(|ttl| self.clock.now() + ttl);
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (value.to_vec(), expires));
+        Ok(())
+    }
+}
+
+/// The two Redis commands [`RedisStore`] needs: `GET key`, and `SET key value` with `EX seconds`
+/// when `expire_seconds` is set.
+pub trait RedisCommands: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, EidolonError>;
+    fn set(&self, key: &str, value: &[u8], expire_seconds: Option<u64>)
+        -> Result<(), EidolonError>;
+}
+
+/// A [`CacheStore`] in Redis. Keys are prefixed (`eidolon:` by default) so the cache can share a
+/// database; TTLs are rounded up to whole seconds, the resolution of `EX`.
+pub struct RedisStore<R> {
+    redis: R,
+    prefix: String,
+}
+
+impl<R: RedisCommands> RedisStore<R> {
+    pub fn new(redis: R) -> Self {
+        Self {
+            redis,
+            prefix: "eidolon:".to_string(),
+        }
+    }
+
+    /// Use `prefix` in front of every key instead of `eidolon:`.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+impl<R: RedisCommands> CacheStore for RedisStore<R> {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, EidolonError> {
+        self.redis.get(&format!("{}{key}", self.prefix))
+    }
+
+    fn put(&self, key: &str, value: &[u8], ttl: Option<Duration>) -> Result<(), EidolonError> {
+        let seconds = ttl.map(|ttl| ttl.as_secs() + u64::from(ttl.subsec_nanos() > 0));
+        self.redis.set(
+            &format!("{}{key}", self.prefix),
+            value,
+            seconds.map(|s| s.max(1)),
+        )
+    }
+}
+
+/// Caches another provider's skins in a [`CacheStore`] under `skin:<key>` ([`PlayerId::key`]),
+/// with the arm model and signature status. Entries expire after `ttl`; `None` keeps them.
+///
+/// A store that fails is logged and skipped: the skin is fetched from the inner provider, so an
+/// unreachable Redis slows workers down instead of failing renders.
+pub struct StoreCache<P, S> {
+    inner: P,
+    store: S,
+    ttl: Option<Duration>,
+}
+
+impl<P: SkinProvider, S: CacheStore> StoreCache<P, S> {
+    pub fn new(inner: P, store: S, ttl: Option<Duration>) -> Self {
+        Self { inner, store, ttl }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+}
+
+impl<P: SkinProvider, S: CacheStore> SkinProvider for StoreCache<P, S> {
+    fn fetch(&self, id: &PlayerId) -> Result<SkinData, EidolonError> {
+        let key = format!("skin:{}", id.key()?);
+        match self.store.get(&key) {
+            Ok(Some(entry)) => match decode_entry(&entry) {
+                Some(data) => {
+                    debug!(%id, "skin cache hit");
+                    return Ok(data);
+                }
+                None => warn!(%id, "ignoring malformed cache entry"),
+            },
+            Ok(None) => {}
+            Err(e) => warn!(%id, "skin cache lookup failed: {e}"),
+        }
+        let data = self.inner.fetch(id)?;
+        if let Err(e) = self.store.put(&key, &encode_entry(&data), self.ttl) {
+            warn!(%id, "failed to cache skin: {e}");
+        }
+        Ok(data)
+    }
+}
+
+/// Encoded render results on disk, one file per key, bounded by total size.
+///
+/// Reads refresh an entry's modification time, and writes that push the directory past
+/// `max_bytes` delete the least recently used entries first. Entries are written to a temporary
+/// file and renamed into place, so concurrent readers (and other processes sharing the
+/// directory) never see half an image.
+pub struct ResultCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    /// Bytes in the directory as of the last scan plus this process's writes.
+    used: Mutex<u64>,
+}
+
+impl ResultCache {
+    /// Open (creating if needed) the cache in `dir`.
+    pub fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Result<Self, EidolonError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        let used = entries(&dir)?.iter().map(|(_, len, _)| len).sum();
+        Ok(Self {
+            dir,
+            max_bytes,
+            used: Mutex::new(used),
+        })
+    }
+
+    /// Total size of the cached entries in bytes.
+    pub fn size(&self) -> u64 {
+        *self.used.lock().unwrap()
+    }
+
+    /// Cached bytes under `key`, if any. Keys are ASCII letters, digits, `_` and `-`.
+    pub fn get(&self, key: &str) -> Result<Option<Vec<u8>>, EidolonError> {
+        let path = self.path(key)?;
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                if let Err(e) = File::options()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|file| file.set_modified(SystemTime::now()))
+                {
+                    warn!("failed to refresh {}: {e}", path.display());
+                }
+                Ok(Some(bytes))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Store `bytes` under `key`, then evict down to the size limit. Entries larger than the
+    /// whole limit are not stored.
+    pub fn put(&self, key: &str, bytes: &[u8]) -> Result<(), EidolonError> {
+        let path = self.path(key)?;
+        let len = bytes.len() as u64;
+        if len > self.max_bytes {
+            debug!(key, len, "result larger than the cache, not stored");
+            return Ok(());
+        }
+        // Unique per call, so concurrent stores of one key never share a temp file.
+        static STORES: AtomicU64 = AtomicU64::new(0);
+        let store = STORES.fetch_add(1, Ordering::Relaxed);
+        let temp = self
+            .dir
+            .join(format!(".{key}.{}.{store}.tmp", std::process::id()));
+        std::fs::write(&temp, bytes)?;
+        let replaced = std::fs::metadata(&path).map_or(0, |m| m.len());
+        std::fs::rename(&temp, &path)?;
+
+        let mut used = self.used.lock().unwrap();
+        *used = (*used + len).saturating_sub(replaced);
+        if *used > self.max_bytes {
+            *used = self.evict()?;
+        }
+        Ok(())
+    }
+
+    /// The cached bytes under `key`, or `render`'s output, stored for next time.
+    pub fn get_or_insert_with(
+        &self,
+        key: &str,
+        render: impl FnOnce() -> Result<Vec<u8>, EidolonError>,
+    ) -> Result<Vec<u8>, EidolonError> {
+        if let Some(bytes) = self.get(key)? {
+            debug!(key, "result cache hit");
+            return Ok(bytes);
+        }
+        let bytes = render()?;
+        self.put(key, &bytes)?;
+        Ok(bytes)
+    }
+
+    /// Delete least recently used entries until the directory fits; returns the size left.
+    fn evict(&self) -> Result<u64, EidolonError> {
+        let mut entries = entries(&self.dir)?;
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        let mut used: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        for (path, len, _) in entries {
+            if used <= self.max_bytes {
+                break;
+            }
+            match std::fs::remove_file(&path) {
+                Ok(()) => used -= len,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => used -= len,
+                Err(e) => warn!("failed to evict {}: {e}", path.display()),
+            }
+        }
+        Ok(used)
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, EidolonError> {
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(EidolonError::invalid_path(format!(
+                "cache key '{key}' may only contain letters, digits, '_' and '-'"
+            )));
+        }
+        Ok(self.dir.join(key))
+    }
+}
+
+/// Path, size and modification time of every entry in `dir` (temporary files excluded).
+fn entries(dir: &Path) -> Result<Vec<(PathBuf, u64, SystemTime)>, EidolonError> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_file() {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push((entry.path(), metadata.len(), modified));
+        }
+    }
+    Ok(entries)
+}
+
+/// Cache entry: the two description lines of [`describe`], a newline, then the PNG bytes.
+fn encode_entry(data: &SkinData) -> Vec<u8> {
+    let mut entry = describe(data).into_bytes();
+    entry.push(b'\n');
+    entry.extend_from_slice(&data.bytes);
+    entry
+}
+
+fn decode_entry(entry: &[u8]) -> Option<SkinData> {
+    let first = entry.iter().position(|&b| b == b'\n')?;
+    let second = first + 1 + entry[first + 1..].iter().position(|&b| b == b'\n')?;
+    let (skin_type, signature) = parse_description(std::str::from_utf8(&entry[..second]).ok()?);
+    Some(SkinData {
+        bytes: entry[second + 1..].to_vec(),
+        skin_type,
+        signature,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character::SkinType;
+    use crate::profile::SignatureStatus;
+    use crate::throttle::ManualClock;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Answers every lookup with the same skin and counts the calls.
+    struct Counting(AtomicUsize);
+
+    impl SkinProvider for Counting {
+        fn fetch(&self, _id: &PlayerId) -> Result<SkinData, EidolonError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(SkinData {
+                bytes: b"\x89PNG\n\nbytes".to_vec(),
+                skin_type: Some(SkinType::Slim),
+                signature: SignatureStatus::Valid,
+            })
+        }
+    }
+
+    /// Redis stand-in recording the expiry of each `SET`.
+    #[derive(Default)]
+    struct FakeRedis(Mutex<HashMap<String, MemoryEntry>>);
+
+    impl RedisCommands for FakeRedis {
+        fn get(&self, key: &str) -> Result<Option<Vec<u8>>, EidolonError> {
+            Ok(self.0.lock().unwrap().get(key).map(|(v, _)| v.clone()))
+        }
+
+        fn set(
+            &self,
+            key: &str,
+            value: &[u8],
+            expire_seconds: Option<u64>,
+        ) -> Result<(), EidolonError> {
+            self.0.lock().unwrap().insert(
+                key.to_string(),
+                (value.to_vec(), expire_seconds.map(Duration::from_secs)),
+            );
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn workers_share_one_store() {
+        let store = Arc::new(MemoryStore::new());
+        let first = StoreCache::new(Counting(AtomicUsize::new(0)), Arc::clone(&store), None);
+        let second = StoreCache::new(Counting(AtomicUsize::new(0)), Arc::clone(&store), None);
+        let id = PlayerId::parse("Notch");
+        let fetched = first.fetch(&id).unwrap();
+        let cached = second.fetch(&id).unwrap();
+        assert_eq!(cached, fetched);
+        assert_eq!(first.inner.0.load(Ordering::SeqCst), 1);
+        assert_eq!(second.inner.0.load(Ordering::SeqCst), 0);
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn memory_entries_expire() {
+        let clock = Arc::new(ManualClock::new());
+        let cache = StoreCache::new(
+            Counting(AtomicUsize::new(0)),
+            MemoryStore::with_clock(Arc::clone(&clock)),
+            Some(Duration::from_secs(60)),
+        );
+        let id = PlayerId::parse("Notch");
+        cache.fetch(&id).unwrap();
+        clock.advance(Duration::from_secs(59));
+        cache.fetch(&id).unwrap();
+        assert_eq!(cache.inner.0.load(Ordering::SeqCst), 1);
+        clock.advance(Duration::from_secs(1));
+        cache.fetch(&id).unwrap();
+        assert_eq!(cache.inner.0.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn redis_keys_are_prefixed_and_ttls_rounded_up() {
+        let cache = StoreCache::new(
+            Counting(AtomicUsize::new(0)),
+            RedisStore::new(FakeRedis::default()).prefix("skins:"),
+            Some(Duration::from_millis(1500)),
+        );
+        cache.fetch(&PlayerId::parse("Notch")).unwrap();
+        let entries = cache.store().redis.0.lock().unwrap();
+        let (entry, expiry) = &entries["skins:skin:notch"];
+        assert_eq!(*expiry, Some(Duration::from_secs(2)));
+        assert_eq!(decode_entry(entry).unwrap().skin_type, Some(SkinType::Slim));
+    }
+
+    #[test]
+    fn failing_stores_fall_back_to_the_provider() {
+        struct Down;
+        impl CacheStore for Down {
+            fn get(&self, _: &str) -> Result<Option<Vec<u8>>, EidolonError> {
+                Err(EidolonError::fetch("connection refused"))
+            }
+            fn put(&self, _: &str, _: &[u8], _: Option<Duration>) -> Result<(), EidolonError> {
+                Err(EidolonError::fetch("connection refused"))
+            }
+        }
+        let cache = StoreCache::new(Counting(AtomicUsize::new(0)), Down, None);
+        assert_eq!(
+            cache.fetch(&PlayerId::parse("Notch")).unwrap().signature,
+            SignatureStatus::Valid
+        );
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("eidolon-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn result_cache_evicts_least_recently_used() {
+        let dir = temp_dir("results");
+        let cache = ResultCache::new(&dir, 10).unwrap();
+        cache.put("a", b"aaaa").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        cache.put("b", b"bbbb").unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        // Reading `a` makes `b` the least recently used.
+        assert_eq!(cache.get("a").unwrap().unwrap(), b"aaaa");
+        std::thread::sleep(Duration::from_millis(20));
+        cache.put("c", b"cccc").unwrap();
+        assert_eq!(cache.get("b").unwrap(), None);
+        assert!(cache.get("a").unwrap().is_some());
+        assert_eq!(cache.size(), 8);
+
+        // Too big to ever fit: skipped, nothing evicted.
+        cache.put("d", &[0; 11]).unwrap();
+        assert_eq!(cache.get("d").unwrap(), None);
+        assert_eq!(cache.size(), 8);
+
+        // A reopened cache counts what is on disk.
+        assert_eq!(ResultCache::new(&dir, 10).unwrap().size(), 8);
+        assert!(cache.get("../a").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn result_cache_renders_once() {
+        let dir = temp_dir("results-once");
+        let cache = ResultCache::new(&dir, 1 << 20).unwrap();
+        let renders = AtomicUsize::new(0);
+        let render = || {
+            renders.fetch_add(1, Ordering::SeqCst);
+            Ok(b"png".to_vec())
+        };
+        for _ in 0..3 {
+            assert_eq!(cache.get_or_insert_with("v1-key", render).unwrap(), b"png");
+        }
+        assert_eq!(renders.load(Ordering::SeqCst), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_stores_of_one_key_do_not_tear() {
+        let dir = temp_dir("results-concurrent");
+        let cache = ResultCache::new(&dir, 1 << 24).unwrap();
+        std::thread::scope(|scope| {
+            for i in 0..4u8 {
+                let cache = &cache;
+                scope.spawn(move || {
+                    for _ in 0..20 {
+                        cache.put("key", &vec![i; 1 << 16]).unwrap();
+                    }
+                });
+            }
+        });
+        let stored = cache.get("key").unwrap().unwrap();
+        assert_eq!(stored.len(), 1 << 16);
+        assert!(stored.iter().all(|&b| b == stored[0]));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn entries_round_trip_with_unknown_fields() {
+        let data = SkinData {
+            bytes: b"png\nwith\nnewlines".to_vec(),
+            skin_type: None,
+            signature: SignatureStatus::NotChecked,
+        };
+        assert_eq!(decode_entry(&encode_entry(&data)), Some(data));
+        assert_eq!(decode_entry(b"no newline"), None);
+    }
+}
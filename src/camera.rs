@@ -1,5 +1,10 @@
 use cgmath::{perspective, Deg, Matrix4, Point3, Vector3};
 
+/// Near clip plane distance of [`Camera::get_projection_matrix`].
+pub(crate) const Z_NEAR: f32 = 0.1;
+/// Far clip plane distance of [`Camera::get_projection_matrix`].
+pub(crate) const Z_FAR: f32 = 1024.0;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Camera {
     /// Orbit yaw around the look-at target (degrees). Used in [`Camera::get_view_matrix`].
@@ -10,6 +15,12 @@ pub struct Camera {
     pub scale: f32,
 }
 
+/// Default vertical field of view in degrees ([`crate::renderer::RenderOptions::fov`]).
+///
+/// The `*_with_fov` methods of [`Camera`] take another: narrower fields move the eye back so the
+/// look-at target keeps its framing, and a few degrees look nearly orthographic.
+pub const DEFAULT_FOV: f32 = 60.0;
+
 impl Default for Camera {
     fn default() -> Self {
         Self {
@@ -25,33 +36,78 @@ impl Camera {
         Self::default()
     }
 
-    /// Computes the view matrix from camera parameters.
-    pub fn get_view_matrix(&self) -> [[f32; 4]; 4] {
-        let distance = 4.0 / self.scale;
+    /// Distance from the eye to the look-at target `(0, 1, 0)`: `4.0 / scale`.
+    pub fn orbit_distance(&self) -> f32 {
+        self.orbit_distance_with_fov(DEFAULT_FOV)
+    }
+
+    /// [`Camera::orbit_distance`] at a vertical field of view of `fov` degrees: further for
+    /// fields narrower than [`DEFAULT_FOV`].
+    pub fn orbit_distance_with_fov(&self, fov: f32) -> f32 {
+        4.0 / self.scale * dolly(fov)
+    }
+
+    /// Near and far clip planes at `fov`. Both move back with the eye, keeping the depth
+    /// precision around the character at narrow fields of view.
+    pub(crate) fn clip_planes(fov: f32) -> (f32, f32) {
+        (Z_NEAR * dolly(fov), Z_FAR * dolly(fov))
+    }
+
+    /// World-space eye position on the orbit around `(0, 1, 0)` at `fov`.
+    pub(crate) fn eye_position(&self, fov: f32) -> Point3<f32> {
+        let distance = self.orbit_distance_with_fov(fov);
         let yaw_rad = self.yaw.to_radians();
         let pitch_rad = (self.pitch - 90.0).to_radians();
 
         let eye_x = distance * yaw_rad.sin() * pitch_rad.cos();
         let eye_y = 1.0 + distance * pitch_rad.sin();
         let eye_z = distance * yaw_rad.cos() * pitch_rad.cos();
+        Point3::new(eye_x, eye_y, eye_z)
+    }
+
+    /// Computes the view matrix from camera parameters.
+    pub fn get_view_matrix(&self) -> [[f32; 4]; 4] {
+        self.view_matrix_with_fov(DEFAULT_FOV)
+    }
+
+    /// Computes the projection matrix from camera parameters.
+    pub fn get_projection_matrix(&self, width: u32, height: u32) -> [[f32; 4]; 4] {
+        self.projection_matrix_with_fov(width, height, DEFAULT_FOV)
+    }
 
-        let eye = Point3::new(eye_x, eye_y, eye_z);
+    /// [`Camera::get_view_matrix`] at a vertical field of view of `fov` degrees.
+    pub fn view_matrix_with_fov(&self, fov: f32) -> [[f32; 4]; 4] {
+        let eye = self.eye_position(fov);
         let center = Point3::new(0.0, 1.0, 0.0);
         let up = Vector3::new(0.0, 1.0, 0.0);
 
         Matrix4::look_at_rh(eye, center, up).into()
     }
 
-    /// Computes the projection matrix from camera parameters.
-    pub fn get_projection_matrix(&self, width: u32, height: u32) -> [[f32; 4]; 4] {
+    /// [`Camera::get_projection_matrix`] at a vertical field of view of `fov` degrees.
+    pub fn projection_matrix_with_fov(&self, width: u32, height: u32, fov: f32) -> [[f32; 4]; 4] {
         let aspect_ratio = width as f32 / height as f32;
-        let fovy = Deg(60.0);
-        let znear = 0.1;
-        let zfar = 1024.0;
-        perspective(fovy, aspect_ratio, znear, zfar).into()
+        let (near, far) = Self::clip_planes(fov);
+        perspective(Deg(clamp_fov(fov)), aspect_ratio, near, far).into()
+    }
+}
+
+/// `fov` limited to 1°–120°; NaN falls back to [`DEFAULT_FOV`].
+pub(crate) fn clamp_fov(fov: f32) -> f32 {
+    if fov.is_nan() {
+        DEFAULT_FOV
+    } else {
+        fov.clamp(1.0, 120.0)
     }
 }
 
+/// How much further the eye is at `fov` than at [`DEFAULT_FOV`], so the target keeps its
+/// framing.
+fn dolly(fov: f32) -> f32 {
+    let half = |fov: f32| (fov / 2.0).to_radians().tan();
+    half(DEFAULT_FOV) / half(clamp_fov(fov))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,6 +148,28 @@ mod tests {
         assert_ne!(view_half, view_full);
     }
 
+    #[test]
+    fn orbit_distance_inverse_of_scale() {
+        assert_eq!(Camera::new().orbit_distance(), 4.0);
+        let zoomed = Camera {
+            scale: 2.0,
+            ..Camera::new()
+        };
+        assert_eq!(zoomed.orbit_distance(), 2.0);
+    }
+
+    #[test]
+    fn narrow_fields_of_view_keep_the_framing() {
+        let camera = Camera::new();
+        // The target's half-height at the eye's distance is the same for both.
+        let half_view =
+            |fov: f32| camera.orbit_distance_with_fov(fov) * (fov / 2.0).to_radians().tan();
+        assert!((half_view(6.0) - half_view(DEFAULT_FOV)).abs() < 1e-4);
+        assert!(camera.orbit_distance_with_fov(6.0) > 30.0);
+        let (near, far) = Camera::clip_planes(6.0);
+        assert!(near > Z_NEAR && far > camera.orbit_distance_with_fov(6.0));
+    }
+
     #[test]
     fn projection_matrix_valid_aspect() {
         let camera = Camera::new();
@@ -114,4 +192,17 @@ mod tests {
         // different aspect ratios produce different matrices
         assert_ne!(proj_square, proj_wide);
     }
+
+    #[test]
+    fn nan_fov_falls_back_to_the_default() {
+        let camera = Camera::new();
+        assert_eq!(
+            camera.projection_matrix_with_fov(800, 600, f32::NAN),
+            camera.get_projection_matrix(800, 600)
+        );
+        assert_eq!(
+            camera.orbit_distance_with_fov(f32::NAN),
+            camera.orbit_distance()
+        );
+    }
 }
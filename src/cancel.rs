@@ -0,0 +1,97 @@
+//! Cooperative cancellation for long renders.
+//!
+//! A [`CancelToken`] is shared between the code doing the work and whoever may stop it: an HTTP
+//! handler whose client disconnected, or a job queue enforcing a timeout. Work checks the token
+//! between steps (views, animation frames, lineup members, GIF frames) and stops with
+//! [`EidolonError::Cancelled`] at the next check; a step already running, such as a GPU
+//! submission, finishes first.
+//!
+//! Give a [`Renderer`](crate::renderer::Renderer) a token with
+//! [`set_cancel_token`](crate::renderer::Renderer::set_cancel_token), or a one-call render one
+//! through [`RenderRequest::cancel`](crate::request::RenderRequest::cancel).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::error::EidolonError;
+
+/// A cancellation flag, optionally with a deadline. Clones share the flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    deadline: Option<Instant>,
+}
+
+impl CancelToken {
+    /// A token that stays live until [`CancelToken::cancel`] is called.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token that also counts as cancelled once `deadline` has passed.
+    pub fn with_deadline(deadline: Instant) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                cancelled: AtomicBool::new(false),
+                deadline: Some(deadline),
+            }),
+        }
+    }
+
+    /// A token that counts as cancelled `timeout` from now.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_timeout(timeout: std::time::Duration) -> Self {
+        Self::with_deadline(Instant::now() + timeout)
+    }
+
+    /// Stop the work holding this token (or a clone) at its next check.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether [`CancelToken::cancel`] was called or the deadline has passed.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Relaxed)
+            || self
+                .inner
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// `Err(EidolonError::Cancelled)` if the token is cancelled, for use with `?` between steps.
+    pub fn check(&self) -> Result<(), EidolonError> {
+        if self.is_cancelled() {
+            Err(EidolonError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn clones_share_cancellation() {
+        let token = CancelToken::new();
+        let handle = token.clone();
+        assert!(token.check().is_ok());
+        handle.cancel();
+        assert!(token.is_cancelled());
+        assert!(matches!(token.check(), Err(EidolonError::Cancelled)));
+    }
+
+    #[test]
+    fn deadlines_cancel_once_passed() {
+        assert!(CancelToken::with_deadline(Instant::now()).is_cancelled());
+        assert!(!CancelToken::with_timeout(Duration::from_secs(3600)).is_cancelled());
+    }
+}
@@ -0,0 +1,134 @@
+//! Capes worn on the back, hanging from the shoulders.
+//!
+//! A cape is a 10×16×1 pixel slab hinged at the top of the back and swung backward by
+//! [`Cape::angle`]. Put one on with [`crate::character::Equipment::cape`]; its texture is a cape
+//! atlas from [`SkinImage::cape_from_memory`] (the game's 64×32 layout, legacy capes padded to
+//! it), uploaded with [`crate::renderer::Renderer::upload_skin`].
+//!
+//! [`SkinImage::cape_from_memory`]: crate::texture::SkinImage::cape_from_memory
+
+use std::sync::Arc;
+
+use cgmath::{Deg, Matrix4, Vector3};
+
+use crate::bedrock::{BedrockGeometry, Bone, Cube, CubeUv};
+use crate::model::TexturedVertex;
+use crate::texture::{SkinId, Texture};
+
+/// Angle in degrees a standing player's cape hangs back from the body, as in the game.
+pub const DEFAULT_CAPE_ANGLE: f32 = 6.0;
+
+/// Where the cape hangs from in model units: the top of the back, centered.
+const HINGE: [f32; 3] = [0.0, 1.5, 0.125];
+
+/// A cape worn on the back.
+#[derive(Clone)]
+pub struct Cape {
+    pub texture: Arc<Texture>,
+    /// Degrees the cape swings back from hanging straight down; negative values swing it into
+    /// the body.
+    pub angle: f32,
+}
+
+impl Cape {
+    /// A cape textured with `texture` ([`crate::texture::SkinImage::cape_from_memory`]), hanging
+    /// at [`DEFAULT_CAPE_ANGLE`].
+    pub fn new(texture: Arc<Texture>) -> Self {
+        Self {
+            texture,
+            angle: DEFAULT_CAPE_ANGLE,
+        }
+    }
+
+    /// The cape swung back by `angle` degrees.
+    pub fn with_angle(self, angle: f32) -> Self {
+        Self { angle, ..self }
+    }
+}
+
+impl std::fmt::Debug for Cape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Cape")
+            .field("texture", &SkinId(self.texture.content_hash()).to_string())
+            .field("angle", &self.angle)
+            .finish()
+    }
+}
+
+/// Swing of a cape by `angle` degrees about its hinge, in the body's model space.
+pub(crate) fn hinge_matrix(angle: f32) -> Matrix4<f32> {
+    let hinge = Vector3::from(HINGE);
+    // Turning about -X moves the hanging edge toward +Z, behind the player.
+    Matrix4::from_translation(hinge)
+        * Matrix4::from_angle_x(Deg(-angle))
+        * Matrix4::from_translation(-hinge)
+}
+
+/// Triangles of the cape in model units, hanging straight down behind the body. The outside
+/// faces +Z and shows the texture's `(1, 1)`–`(11, 17)` region, as in the game.
+pub(crate) fn vertices() -> Vec<TexturedVertex> {
+    // Bedrock pixels: built facing forward at the back of the body, then turned to face back.
+    let geometry = BedrockGeometry {
+        identifier: "geometry.eidolon.cape".to_string(),
+        texture_width: 64.0,
+        texture_height: 32.0,
+        bones: vec![Bone {
+            name: "cape".to_string(),
+            parent: None,
+            pivot: [0.0, 24.0, 0.0],
+            rotation: [0.0, 180.0, 0.0],
+            cubes: vec![Cube {
+                origin: [-5.0, 8.0, -3.0],
+                size: [10.0, 16.0, 1.0],
+                pivot: [0.0; 3],
+                rotation: [0.0; 3],
+                inflate: 0.0,
+                mirror: false,
+                uv: CubeUv::Box([0.0, 0.0]),
+            }],
+        }],
+    };
+    geometry.vertices()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Bounds;
+    use cgmath::{InnerSpace, Vector4};
+
+    #[test]
+    fn capes_hang_behind_the_body() {
+        let vertices = vertices();
+        assert_eq!(vertices.len(), 36);
+        let bounds = Bounds::from_vertices(&vertices);
+        let close = |a: [f32; 3], b: [f32; 3]| (0..3).all(|i| (a[i] - b[i]).abs() < 1e-5);
+        assert!(close(bounds.min, [-0.3125, 0.5, 0.125]), "{bounds:?}");
+        assert!(close(bounds.max, [0.3125, 1.5, 0.1875]), "{bounds:?}");
+
+        // The outside shows the cape region of the atlas.
+        let outside: Vec<_> = vertices
+            .iter()
+            .filter(|vertex| vertex.normal[2] > 0.5)
+            .collect();
+        assert_eq!(outside.len(), 6);
+        for vertex in outside {
+            let [u, v] = vertex.texture;
+            assert!((1.0..=11.0).contains(&(u * 64.0)), "{u}");
+            assert!((1.0..=17.0).contains(&(v * 32.0)), "{v}");
+        }
+    }
+
+    #[test]
+    fn positive_angles_swing_the_cape_back() {
+        let bottom = |angle| hinge_matrix(angle) * Vector4::new(0.0, 0.5, 0.125, 1.0);
+        // The hanging edge moves back and up.
+        let swung = bottom(30.0);
+        assert!(swung.z > 0.6 && swung.y > 0.6, "{swung:?}");
+        assert!(bottom(-10.0).z < 0.125);
+        assert!((bottom(0.0).truncate() - Vector3::new(0.0, 0.5, 0.125)).magnitude() < 1e-6);
+        // The hinge stays put.
+        let top = hinge_matrix(40.0) * Vector3::from(HINGE).extend(1.0);
+        assert!((top.truncate() - Vector3::from(HINGE)).magnitude() < 1e-6);
+    }
+}
@@ -0,0 +1,453 @@
+//! Profile and stat cards: a JSON [`CardTemplate`] lays out a background, a character slot and
+//! text fields, and [`CardTemplate::render`] fills it in with one player's skin and values.
+//!
+//! ```json
+//! {
+//!   "size": [600, 250],
+//!   "background": "#1e1f22",
+//!   "fonts": { "regular": "Cantarell-Regular.ttf" },
+//!   "character": { "x": 0, "y": 0, "width": 200, "height": 250, "posture": "wave", "yaw": 200 },
+//!   "texts": [
+//!     { "text": "{username}", "x": 220, "y": 40, "size": 40, "font": "regular" },
+//!     { "text": "Kills: {kills}", "x": 220, "y": 110, "size": 24, "color": "#b5bac1", "font": "regular" }
+//!   ]
+//! }
+//! ```
+//!
+//! `background` is a `#rrggbb[aa]` color or `{"image": "file"}` (scaled to cover the card). Font
+//! and image files are resolved by the caller of [`CardTemplate::from_json`], or relative to the
+//! template by [`CardTemplate::load`]. Text is drawn with its top at `y`; `{key}` placeholders are
+//! replaced with [`CardData::values`], and unknown keys with nothing.
+
+use std::collections::{BTreeMap, HashMap};
+
+use ab_glyph::{point, Font, FontArc, PxScale, ScaleFont};
+use image::{imageops, Rgba, RgbaImage};
+use serde_json::Value;
+
+use crate::camera::Camera;
+use crate::character::{Character, DefaultPostures, Posture, SkinType};
+use crate::error::EidolonError;
+use crate::renderer::Renderer;
+use crate::texture::Texture;
+
+/// A parsed card layout with its fonts and background loaded.
+#[derive(Clone)]
+pub struct CardTemplate {
+    pub width: u32,
+    pub height: u32,
+    pub background: CardBackground,
+    pub character: CharacterSlot,
+    pub texts: Vec<TextField>,
+    fonts: HashMap<String, FontArc>,
+}
+
+/// What the card is drawn on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CardBackground {
+    /// Straight RGBA; `[0, 0, 0, 0]` is transparent.
+    Color([u8; 4]),
+    /// Already scaled to the card size.
+    Image(RgbaImage),
+}
+
+/// Where and how the character is drawn.
+#[derive(Debug, Clone, Copy)]
+pub struct CharacterSlot {
+    /// Top-left corner on the card; may lie partly outside it.
+    pub x: i64,
+    pub y: i64,
+    pub width: u32,
+    pub height: u32,
+    pub posture: Posture,
+    pub camera: Camera,
+}
+
+/// A line of text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextField {
+    /// Text with `{key}` placeholders.
+    pub text: String,
+    /// Anchor: left edge, centre or right edge per `align`.
+    pub x: f32,
+    /// Top of the line.
+    pub y: f32,
+    /// Pixel height of the font.
+    pub size: f32,
+    /// Name in the template's `fonts`.
+    pub font: String,
+    /// Straight RGBA.
+    pub color: [u8; 4],
+    pub align: TextAlign,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// The per-render inputs of a card.
+pub struct CardData<'a> {
+    pub skin: &'a Texture,
+    pub skin_type: SkinType,
+    /// Placeholder values: `username`, stats, anything the template refers to.
+    pub values: BTreeMap<String, String>,
+}
+
+impl CardTemplate {
+    /// Parse a template. `resolve` returns the bytes of the font and image files it names.
+    pub fn from_json(
+        json: &[u8],
+        mut resolve: impl FnMut(&str) -> Result<Vec<u8>, EidolonError>,
+    ) -> Result<Self, EidolonError> {
+        let template: Value = serde_json::from_slice(json)
+            .map_err(|e| EidolonError::template(format!("malformed card template: {e}")))?;
+        let [width, height] = match &template["size"] {
+            Value::Array(size) if size.len() == 2 => [&size[0], &size[1]].map(|v| {
+                v.as_u64()
+                    .and_then(|v| u32::try_from(v).ok())
+                    .filter(|&v| v > 0)
+            }),
+            _ => [None, None],
+        };
+        let (Some(width), Some(height)) = (width, height) else {
+            return Err(EidolonError::template(
+                "card 'size' must be [width, height] in pixels",
+            ));
+        };
+
+        let mut fonts = HashMap::new();
+        if let Some(entries) = template["fonts"].as_object() {
+            for (name, file) in entries {
+                let file = file.as_str().ok_or_else(|| {
+                    EidolonError::template(format!("font '{name}' is not a file"))
+                })?;
+                let font = FontArc::try_from_vec(resolve(file)?)
+                    .map_err(|e| EidolonError::template(format!("font '{name}' ({file}): {e}")))?;
+                fonts.insert(name.clone(), font);
+            }
+        }
+
+        let background = match &template["background"] {
+            Value::Null => CardBackground::Color([0, 0, 0, 0]),
+            Value::String(color) => CardBackground::Color(parse_color(color)?),
+            Value::Object(object) => {
+                let file = object["image"].as_str().ok_or_else(|| {
+                    EidolonError::template("background must be a color or {\"image\": file}")
+                })?;
+                let image = image::load_from_memory(&resolve(file)?)
+                    .map_err(|e| EidolonError::template(format!("background {file}: {e}")))?;
+                CardBackground::Image(
+                    image
+                        .resize_to_fill(width, height, imageops::FilterType::Triangle)
+                        .to_rgba8(),
+                )
+            }
+            _ => {
+                return Err(EidolonError::template(
+                    "background must be a color or {\"image\": file}",
+                ))
+            }
+        };
+
+        let slot = &template["character"];
+        let number = |value: &Value, default: f64| value.as_f64().unwrap_or(default);
+        let character = CharacterSlot {
+            x: number(&slot["x"], 0.0) as i64,
+            y: number(&slot["y"], 0.0) as i64,
+            width: slot["width"].as_u64().map_or(height, |w| w as u32),
+            height: slot["height"].as_u64().map_or(height, |h| h as u32),
+            posture: match slot["posture"].as_str().unwrap_or("stand") {
+                "stand" => DefaultPostures::STAND,
+                "wave" => DefaultPostures::WAVE,
+                "walking" => DefaultPostures::WALKING,
+                "running" => DefaultPostures::RUNNING,
+                other => {
+                    return Err(EidolonError::template(format!(
+                        "unknown posture '{other}' (stand, wave, walking or running)"
+                    )))
+                }
+            },
+            camera: Camera {
+                yaw: number(&slot["yaw"], 180.0) as f32,
+                pitch: number(&slot["pitch"], 90.0) as f32,
+                scale: number(&slot["zoom"], 1.0) as f32,
+            },
+        };
+        if character.width == 0 || character.height == 0 || character.camera.scale <= 0.0 {
+            return Err(EidolonError::template(
+                "character slot needs a positive size and zoom",
+            ));
+        }
+
+        let texts = template["texts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|field| {
+                let font = field["font"].as_str().unwrap_or_default().to_string();
+                if !fonts.contains_key(&font) {
+                    return Err(EidolonError::template(format!(
+                        "text font '{font}' is not in the template's fonts"
+                    )));
+                }
+                Ok(TextField {
+                    text: field["text"].as_str().unwrap_or_default().to_string(),
+                    x: number(&field["x"], 0.0) as f32,
+                    y: number(&field["y"], 0.0) as f32,
+                    size: number(&field["size"], 16.0) as f32,
+                    font,
+                    color: match field["color"].as_str() {
+                        Some(color) => parse_color(color)?,
+                        None => [255, 255, 255, 255],
+                    },
+                    align: match field["align"].as_str().unwrap_or("left") {
+                        "left" => TextAlign::Left,
+                        "center" => TextAlign::Center,
+                        "right" => TextAlign::Right,
+                        other => {
+                            return Err(EidolonError::template(format!(
+                                "unknown align '{other}' (left, center or right)"
+                            )))
+                        }
+                    },
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            width,
+            height,
+            background,
+            character,
+            texts,
+            fonts,
+        })
+    }
+
+    /// Read a template file; font and image paths in it are relative to its directory.
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, EidolonError> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or(std::path::Path::new("."));
+        Self::from_json(&std::fs::read(path)?, |file| {
+            Ok(std::fs::read(dir.join(file))?)
+        })
+    }
+
+    /// Render the card. The renderer's light rig and options apply to the character; its clear
+    /// color is drawn in the character slot only, so leave it transparent.
+    pub fn render(&self, renderer: &Renderer, data: &CardData) -> Result<RgbaImage, EidolonError> {
+        let mut card = match &self.background {
+            CardBackground::Color(color) => {
+                RgbaImage::from_pixel(self.width, self.height, Rgba(*color))
+            }
+            CardBackground::Image(image) => image.clone(),
+        };
+
+        let slot = &self.character;
+        let character = Character {
+            skin_type: data.skin_type,
+            posture: slot.posture,
+            ..Character::default()
+        };
+        let figure =
+            renderer.render(&character, data.skin, &slot.camera, slot.width, slot.height)?;
+        imageops::overlay(&mut card, &figure, slot.x, slot.y);
+
+        for field in &self.texts {
+            let text = fill_placeholders(&field.text, &data.values);
+            draw_text(&mut card, &self.fonts[&field.font], field, &text);
+        }
+        Ok(card)
+    }
+}
+
+/// `#rrggbb` or `#rrggbbaa`.
+pub(crate) fn parse_color(color: &str) -> Result<[u8; 4], EidolonError> {
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    let parsed = match hex.len() {
+        6 => (|| Some([channel(0)?, channel(2)?, channel(4)?, 255]))(),
+        8 => (|| Some([channel(0)?, channel(2)?, channel(4)?, channel(6)?]))(),
+        _ => None,
+    };
+    parsed.ok_or_else(|| EidolonError::template(format!("'{color}' is not a #rrggbb[aa] color")))
+}
+
+fn fill_placeholders(text: &str, values: &BTreeMap<String, String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        if let Some(value) = values.get(&rest[start + 1..start + len]) {
+            out.push_str(value);
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Draw one line of `text` onto `card`, blending each glyph's coverage with the field color.
+pub(crate) fn draw_text(card: &mut RgbaImage, font: &FontArc, field: &TextField, text: &str) {
+    let scaled = font.as_scaled(PxScale::from(field.size));
+    let mut glyphs = Vec::new();
+    let mut caret = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        glyphs.push(id.with_scale_and_position(field.size, point(caret, scaled.ascent())));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+    }
+    let left = match field.align {
+        TextAlign::Left => field.x,
+        TextAlign::Center => field.x - caret / 2.0,
+        TextAlign::Right => field.x - caret,
+    };
+
+    let [r, g, b, a] = field.color.map(f32::from);
+    for glyph in glyphs {
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let x = (left + bounds.min.x).round() as i64 + i64::from(gx);
+            let y = (field.y + bounds.min.y).round() as i64 + i64::from(gy);
+            let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+                return;
+            };
+            if x >= card.width() || y >= card.height() {
+                return;
+            }
+            // Straight-alpha "over".
+            let src_a = a / 255.0 * coverage.clamp(0.0, 1.0);
+            let dst = card.get_pixel_mut(x, y);
+            let dst_a = f32::from(dst[3]) / 255.0;
+            let out_a = src_a + dst_a * (1.0 - src_a);
+            if out_a <= 0.0 {
+                return;
+            }
+            let blend = |src: f32, dst: u8| {
+                ((src * src_a + f32::from(dst) * dst_a * (1.0 - src_a)) / out_a).round() as u8
+            };
+            *dst = Rgba([
+                blend(r, dst[0]),
+                blend(g, dst[1]),
+                blend(b, dst[2]),
+                (out_a * 255.0).round() as u8,
+            ]);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font_file(name: &str) -> Result<Vec<u8>, EidolonError> {
+        Ok(std::fs::read(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("resources")
+                .join(name),
+        )?)
+    }
+
+    #[test]
+    fn templates_parse_with_defaults() {
+        let template = CardTemplate::from_json(
+            br##"{
+                "size": [300, 100],
+                "background": "#10203080",
+                "fonts": {"regular": "Cantarell-Regular.ttf"},
+                "character": {"width": 80, "posture": "wave", "zoom": 2},
+                "texts": [{"text": "{username}", "x": 90, "y": 10, "font": "regular", "align": "center"}]
+            }"##,
+            font_file,
+        )
+        .unwrap();
+        assert_eq!((template.width, template.height), (300, 100));
+        assert_eq!(
+            template.background,
+            CardBackground::Color([16, 32, 48, 128])
+        );
+        let slot = template.character;
+        assert_eq!((slot.x, slot.y, slot.width, slot.height), (0, 0, 80, 100));
+        assert_eq!(
+            format!("{:?}", slot.posture),
+            format!("{:?}", DefaultPostures::WAVE)
+        );
+        assert_eq!((slot.camera.yaw, slot.camera.scale), (180.0, 2.0));
+        let text = &template.texts[0];
+        assert_eq!(
+            (text.size, text.color, text.align),
+            (16.0, [255; 4], TextAlign::Center)
+        );
+    }
+
+    #[test]
+    fn bad_templates_are_template_errors() {
+        for json in [
+            r#"{"size": [0, 100]}"#,
+            r#"{"size": [10, 10], "background": "red"}"#,
+            r#"{"size": [10, 10], "character": {"posture": "dab"}}"#,
+            r#"{"size": [10, 10], "texts": [{"text": "hi", "font": "missing"}]}"#,
+            r#"{"size": [10, 10], "fonts": {"bad": "classic.obj"}}"#,
+        ] {
+            let result = CardTemplate::from_json(json.as_bytes(), font_file);
+            assert!(matches!(result, Err(EidolonError::Template(_))), "{json}");
+        }
+    }
+
+    #[test]
+    fn placeholders_fill_from_values() {
+        let values = BTreeMap::from([
+            ("username".to_string(), "Notch".to_string()),
+            ("kills".to_string(), "42".to_string()),
+        ]);
+        assert_eq!(
+            fill_placeholders("{username}: {kills} kills{missing}", &values),
+            "Notch: 42 kills"
+        );
+        assert_eq!(fill_placeholders("open { brace", &values), "open { brace");
+    }
+
+    #[test]
+    fn text_is_drawn_in_its_color_and_alignment() {
+        let font = FontArc::try_from_vec(font_file("Cantarell-Regular.ttf").unwrap()).unwrap();
+        let field = |align| TextField {
+            text: String::new(),
+            x: 50.0,
+            y: 0.0,
+            size: 20.0,
+            font: String::new(),
+            color: [255, 0, 0, 255],
+            align,
+        };
+        let columns = |align| {
+            let mut card = RgbaImage::new(100, 24);
+            draw_text(&mut card, &font, &field(align), "Hello");
+            assert!(card.pixels().any(|p| p.0 == [255, 0, 0, 255]));
+            let covered: Vec<u32> = (0..100)
+                .filter(|&x| (0..24).any(|y| card.get_pixel(x, y)[3] > 0))
+                .collect();
+            (covered[0], *covered.last().unwrap())
+        };
+        let (left, _) = columns(TextAlign::Left);
+        let (_, right) = columns(TextAlign::Right);
+        let (center_start, center_end) = columns(TextAlign::Center);
+        assert!((49..=51).contains(&left), "{left}");
+        assert!((48..=51).contains(&right), "{right}");
+        assert!(center_start < 50 && center_end > 50);
+    }
+}
@@ -1,4 +1,13 @@
-use cgmath::Vector3;
+use std::sync::Arc;
+
+use cgmath::{InnerSpace, Matrix, Matrix3, Rad, Vector3};
+
+use crate::attachment::Attachment;
+use crate::camera::{clamp_fov, Camera, DEFAULT_FOV};
+use crate::cape::Cape;
+use crate::headwear::Headwear;
+use crate::skin_layout::Layer;
+use crate::texture::{SkinId, Texture};
 
 /// Arm width variant: classic (4×4 arms) vs slim (3×4).
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -9,12 +18,51 @@ pub enum SkinType {
     Slim,
 }
 
+/// A posable part of the player model; things attached to a bone follow its joint rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bone {
+    Head,
+    Body,
+    RightArm,
+    LeftArm,
+    RightLeg,
+    LeftLeg,
+}
+
+impl Bone {
+    /// Center of the bone's cube at rest, in model units (Y-up, feet at 0, head top at 2; classic
+    /// arms).
+    pub fn center(self) -> [f32; 3] {
+        match self {
+            Bone::Head => [0.0, 1.75, 0.0],
+            Bone::Body => [0.0, 1.125, 0.0],
+            Bone::RightArm => [0.375, 1.125, 0.0],
+            Bone::LeftArm => [-0.375, 1.125, 0.0],
+            Bone::RightLeg => [0.125, 0.375, 0.0],
+            Bone::LeftLeg => [-0.125, 0.375, 0.0],
+        }
+    }
+
+    /// The joint the bone rotates about, in model units: neck, shoulders and hips. The body does
+    /// not rotate; its joint is the model origin.
+    pub const fn joint(self) -> [f32; 3] {
+        match self {
+            Bone::Head => [0.0, 1.5, 0.0],
+            Bone::Body => [0.0, 0.0, 0.0],
+            Bone::RightArm => [0.3125, 1.375, 0.0],
+            Bone::LeftArm => [-0.3125, 1.375, 0.0],
+            Bone::RightLeg => [0.125, 0.75, 0.0],
+            Bone::LeftLeg => [-0.125, 0.75, 0.0],
+        }
+    }
+}
+
 /// Joint angles in degrees. 0° = neutral (no rotation from bind pose).
 ///
 /// Positive yaw turns right, positive pitch looks up.
 /// The renderer applies per-joint bind-pose offsets internally;
 /// library consumers work with intuitive 0°-is-neutral angles.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Posture {
     /// Head yaw around Y (degrees). 0° = facing forward, positive = turn right.
     pub head_yaw: f32,
@@ -34,6 +82,135 @@ pub struct Posture {
     pub right_leg_pitch: f32,
 }
 
+impl Posture {
+    /// Largest head yaw [`Posture::head_look_at`] turns to, either way (degrees).
+    pub const MAX_HEAD_YAW: f32 = 75.0;
+    /// Largest head pitch [`Posture::head_look_at`] tilts to, up or down (degrees).
+    pub const MAX_HEAD_PITCH: f32 = 60.0;
+
+    /// The mirror image of this posture: left and right limbs swap angles and the head turns the
+    /// other way. Mirroring the first half of a walk cycle gives the second half.
+    pub fn mirrored(&self) -> Posture {
+        Posture {
+            head_yaw: -self.head_yaw,
+            head_pitch: self.head_pitch,
+            left_arm_roll: self.right_arm_roll,
+            left_arm_pitch: self.right_arm_pitch,
+            right_arm_roll: self.left_arm_roll,
+            right_arm_pitch: self.left_arm_pitch,
+            left_leg_pitch: self.right_leg_pitch,
+            right_leg_pitch: self.left_leg_pitch,
+        }
+    }
+
+    /// Joint-wise linear blend in degrees: `a` at `t = 0`, `b` at `t = 1`. `t` outside `0–1`
+    /// extrapolates.
+    pub fn lerp(a: &Posture, b: &Posture, t: f32) -> Posture {
+        let mix = |x: f32, y: f32| x + (y - x) * t;
+        Posture {
+            head_yaw: mix(a.head_yaw, b.head_yaw),
+            head_pitch: mix(a.head_pitch, b.head_pitch),
+            left_arm_roll: mix(a.left_arm_roll, b.left_arm_roll),
+            left_arm_pitch: mix(a.left_arm_pitch, b.left_arm_pitch),
+            right_arm_roll: mix(a.right_arm_roll, b.right_arm_roll),
+            right_arm_pitch: mix(a.right_arm_pitch, b.right_arm_pitch),
+            left_leg_pitch: mix(a.left_leg_pitch, b.left_leg_pitch),
+            right_leg_pitch: mix(a.right_leg_pitch, b.right_leg_pitch),
+        }
+    }
+
+    /// Roll and pitch (degrees) that point `arm` from its shoulder towards `target`, a point in
+    /// model units (see [`Character::model_point`]). `None` when `arm` is not an arm or `target`
+    /// is the shoulder itself.
+    ///
+    /// Arms are one rigid piece, so the hand lies on the line to the target rather than on the
+    /// target; of the two angle pairs that give the same direction, the one with roll within ±90°
+    /// is returned, so a raised arm swings up in front of the body rather than over the side.
+    pub fn arm_angles_towards(arm: Bone, target: Vector3<f32>) -> Option<(f32, f32)> {
+        let side = match arm {
+            Bone::RightArm => 1.0,
+            Bone::LeftArm => -1.0,
+            _ => return None,
+        };
+        let to_target = target - Vector3::from(arm.joint());
+        if to_target.magnitude2() < 1e-8 {
+            return None;
+        }
+        // A resting arm hangs along -Y; roll(Z) × pitch(X) turns it to
+        // (cos p · sin r, -cos p · cos r, -sin p), with roll mirrored for the left arm.
+        let d = to_target.normalize();
+        let mut pitch = (-d.z).clamp(-1.0, 1.0).asin().to_degrees();
+        let mut roll = (side * d.x).atan2(-d.y).to_degrees();
+        if roll.abs() > 90.0 {
+            roll -= 180.0 * roll.signum();
+            pitch = 180.0 - pitch;
+            if pitch > 180.0 {
+                pitch -= 360.0;
+            }
+        }
+        Some((roll, pitch))
+    }
+
+    /// Point `arm` at `target` (model units) with [`Posture::arm_angles_towards`]. Returns
+    /// whether the posture changed.
+    pub fn point_arm_at(&mut self, arm: Bone, target: Vector3<f32>) -> bool {
+        let Some((roll, pitch)) = Self::arm_angles_towards(arm, target) else {
+            return false;
+        };
+        if arm == Bone::RightArm {
+            (self.right_arm_roll, self.right_arm_pitch) = (roll, pitch);
+        } else {
+            (self.left_arm_roll, self.left_arm_pitch) = (roll, pitch);
+        }
+        true
+    }
+
+    /// Turn the head so the character looks at `target`, a point in model units (see
+    /// [`Character::model_point`]). Angles are clamped to [`Posture::MAX_HEAD_YAW`] and
+    /// [`Posture::MAX_HEAD_PITCH`], so a target behind the character gets a look over the
+    /// shoulder. Returns whether the head reaches the target unclamped.
+    pub fn head_look_at(&mut self, target: Vector3<f32>) -> bool {
+        let to_target = target - Vector3::from(Bone::Head.center());
+        if to_target.magnitude2() < 1e-8 {
+            return true;
+        }
+        // The face looks along -Z; yaw(Y) × pitch(X) turns it to
+        // (-sin y · cos p, sin p, -cos y · cos p).
+        let d = to_target.normalize();
+        let yaw = (-d.x).atan2(-d.z).to_degrees();
+        let pitch = d.y.clamp(-1.0, 1.0).asin().to_degrees();
+        self.head_yaw = yaw.clamp(-Self::MAX_HEAD_YAW, Self::MAX_HEAD_YAW);
+        self.head_pitch = pitch.clamp(-Self::MAX_HEAD_PITCH, Self::MAX_HEAD_PITCH);
+        self.head_yaw == yaw && self.head_pitch == pitch
+    }
+
+    /// `base` with the joints of `bones` taken from `overlay`, e.g. a walking body with a waving
+    /// left arm. [`Bone::Body`] has no joint and is ignored.
+    pub fn compose(base: &Posture, overlay: &Posture, bones: &[Bone]) -> Posture {
+        let mut posture = *base;
+        for bone in bones {
+            match bone {
+                Bone::Head => {
+                    posture.head_yaw = overlay.head_yaw;
+                    posture.head_pitch = overlay.head_pitch;
+                }
+                Bone::Body => {}
+                Bone::RightArm => {
+                    posture.right_arm_roll = overlay.right_arm_roll;
+                    posture.right_arm_pitch = overlay.right_arm_pitch;
+                }
+                Bone::LeftArm => {
+                    posture.left_arm_roll = overlay.left_arm_roll;
+                    posture.left_arm_pitch = overlay.left_arm_pitch;
+                }
+                Bone::RightLeg => posture.right_leg_pitch = overlay.right_leg_pitch,
+                Bone::LeftLeg => posture.left_leg_pitch = overlay.left_leg_pitch,
+            }
+        }
+        posture
+    }
+}
+
 /// Default posture presets.
 pub struct DefaultPostures;
 
@@ -84,6 +261,76 @@ impl DefaultPostures {
     };
 }
 
+/// A texture drawn on one body part instead of the character's skin, e.g. another player's head
+/// or a team-colored jacket.
+///
+/// The texture is a whole skin atlas ([`crate::renderer::Renderer::upload_skin`]); the part keeps
+/// its UVs, so only the part's region of it is seen.
+#[derive(Clone)]
+pub struct PartTextureOverride {
+    pub bone: Bone,
+    /// The layer replaced; `None` replaces both.
+    pub layer: Option<Layer>,
+    pub texture: Arc<Texture>,
+}
+
+impl std::fmt::Debug for PartTextureOverride {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PartTextureOverride")
+            .field("bone", &self.bone)
+            .field("layer", &self.layer)
+            .field("texture", &SkinId(self.texture.content_hash()).to_string())
+            .finish()
+    }
+}
+
+/// Which layers of which parts are drawn. The default shows everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartVisibility {
+    /// Base and overlay of each part, in [`Bone`] order.
+    layers: [[bool; 2]; 6],
+}
+
+impl Default for PartVisibility {
+    fn default() -> Self {
+        Self {
+            layers: [[true; 2]; 6],
+        }
+    }
+}
+
+impl PartVisibility {
+    /// Nothing drawn.
+    pub const NONE: Self = Self {
+        layers: [[false; 2]; 6],
+    };
+
+    /// Only the head's base layer and hat: the skin's hair and hat on an invisible body.
+    pub fn hair_only() -> Self {
+        let mut visibility = Self::NONE;
+        visibility.set(Bone::Head, Layer::Base, true);
+        visibility.set(Bone::Head, Layer::Overlay, true);
+        visibility
+    }
+
+    pub fn is_shown(&self, bone: Bone, layer: Layer) -> bool {
+        self.layers[bone as usize][layer as usize]
+    }
+
+    pub fn set(&mut self, bone: Bone, layer: Layer, shown: bool) {
+        self.layers[bone as usize][layer as usize] = shown;
+    }
+}
+
+/// What a character wears besides its skin.
+#[derive(Debug, Clone, Default)]
+pub struct Equipment {
+    /// A skull or block in the helmet slot, drawn over the head. Crowd renders ignore it.
+    pub head: Option<Headwear>,
+    /// A cape hanging from the back, moving with the body. Crowd renders ignore it.
+    pub cape: Option<Cape>,
+}
+
 /// Scene object: pose, skin type, and world-space transform.
 ///
 /// Skin texture is managed separately — pass it to [`crate::renderer::Renderer::render`]
@@ -97,6 +344,19 @@ pub struct Character {
     pub position: Vector3<f32>,
     /// World-space rotation in degrees (Euler X, then Y, then Z) applied before joint matrices.
     pub rotation: Vector3<f32>,
+    /// Breathing: the torso is widened and deepened by this fraction (0 = at rest) and the arms
+    /// move out with it. See [`crate::animation::Idle`].
+    pub chest_expansion: f32,
+    /// Textures that replace the skin on single parts; when several match, the last wins. Crowd
+    /// renders ignore them.
+    pub part_texture_overrides: Vec<PartTextureOverride>,
+    pub equipment: Equipment,
+    /// Models worn on bones, at most [`crate::attachment::MAX_ATTACHMENTS`]. Crowd renders
+    /// ignore them.
+    pub attachments: Vec<Attachment>,
+    /// Layers drawn; hidden ones cast no shadow either. Headwear, capes and attachments are
+    /// drawn regardless, and crowd renders ignore it.
+    pub visibility: PartVisibility,
 }
 
 impl Default for Character {
@@ -106,6 +366,11 @@ impl Default for Character {
             posture: DefaultPostures::STAND,
             position: Vector3::new(0.0, 0.0, 0.0),
             rotation: Vector3::new(0.0, 0.0, 0.0),
+            chest_expansion: 0.0,
+            part_texture_overrides: Vec::new(),
+            equipment: Equipment::default(),
+            attachments: Vec::new(),
+            visibility: PartVisibility::default(),
         }
     }
 }
@@ -114,12 +379,109 @@ impl Character {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// `world`, a point in the units of [`Character::position`], in this character's model
+    /// space: [`Character::position`] and [`Character::rotation`] undone. Camera zoom is not part
+    /// of either.
+    pub fn model_point(&self, world: Vector3<f32>) -> Vector3<f32> {
+        let rotation = Matrix3::from_angle_x(Rad(self.rotation.x.to_radians()))
+            * Matrix3::from_angle_y(Rad(self.rotation.y.to_radians()))
+            * Matrix3::from_angle_z(Rad(self.rotation.z.to_radians()));
+        // Rotations are orthonormal: the transpose is the inverse.
+        rotation.transpose() * (world - self.position)
+    }
+
+    /// The texture `layer` of `bone` is drawn with: the last matching
+    /// [`Character::part_texture_overrides`] entry, else `skin`.
+    pub(crate) fn part_texture<'a>(
+        &'a self,
+        skin: &'a Texture,
+        bone: Bone,
+        layer: Layer,
+    ) -> &'a Texture {
+        self.part_texture_overrides
+            .iter()
+            .rev()
+            .find(|o| o.bone == bone && o.layer.is_none_or(|l| l == layer))
+            .map_or(skin, |o| &o.texture)
+    }
+
+    /// [`Posture::head_look_at`] the eye of `camera`, so the character looks into the render.
+    /// The eye is taken at [`DEFAULT_FOV`]; other fields of view only move it along the same
+    /// line through the look-at target.
+    pub fn look_at_camera(&mut self, camera: &Camera) -> bool {
+        let eye = camera.eye_position(DEFAULT_FOV);
+        let eye = Vector3::new(eye.x, eye.y, eye.z);
+        // The renderer scales the model by the camera zoom after placing it.
+        let target = self.model_point(eye) / camera.scale;
+        self.posture.head_look_at(target)
+    }
+
+    /// Hair-only preview for hair and hat designers: show only the head's base layer and hat
+    /// ([`PartVisibility::hair_only`]) and frame the head in a `width`×`height` render. Returns
+    /// `camera` zoomed so the head fits from any angle; the character is moved so the head sits
+    /// on the camera's look-at target.
+    pub fn frame_hair_only(&mut self, camera: &Camera, width: u32, height: u32) -> Camera {
+        self.frame_hair_only_with_fov(camera, DEFAULT_FOV, width, height)
+    }
+
+    /// [`Character::frame_hair_only`] for a vertical field of view of `fov` degrees
+    /// ([`crate::renderer::RenderOptions::fov`]).
+    pub fn frame_hair_only_with_fov(
+        &mut self,
+        camera: &Camera,
+        fov: f32,
+        width: u32,
+        height: u32,
+    ) -> Camera {
+        self.visibility = PartVisibility::hair_only();
+        // Half the narrower field of view.
+        let aspect = width as f32 / height.max(1) as f32;
+        let half_vertical = (clamp_fov(fov) / 2.0).to_radians();
+        let half_fov = half_vertical.min((aspect * half_vertical.tan()).atan());
+        // The zoom scales the model and divides the orbit distance, so the head's apparent
+        // size grows with its square. Fit the sphere around the hat cube with a small margin.
+        let radius = HAT_HALF_EXTENT * 3f32.sqrt();
+        let unit_distance = Camera { scale: 1.0, ..*camera }.orbit_distance_with_fov(fov);
+        let scale = (unit_distance * 0.95 * half_fov.sin() / radius).sqrt();
+        let rotation = Matrix3::from_angle_x(Rad(self.rotation.x.to_radians()))
+            * Matrix3::from_angle_y(Rad(self.rotation.y.to_radians()))
+            * Matrix3::from_angle_z(Rad(self.rotation.z.to_radians()));
+        let head = Vector3::from(Bone::Head.center()) * scale;
+        self.position = Vector3::new(0.0, 1.0, 0.0) - rotation * head;
+        Camera { scale, ..*camera }
+    }
 }
 
+/// Half the edge of the head's hat layer in model units: 4 pixels plus its 0.5 pixel inflation.
+const HAT_HALF_EXTENT: f32 = 4.5 / 16.0;
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn hair_only_frames_the_head_on_the_target() {
+        let mut character = Character {
+            rotation: Vector3::new(0.0, 90.0, 0.0),
+            ..Character::new()
+        };
+        let camera = character.frame_hair_only(&Camera::new(), 256, 256);
+        let visibility = character.visibility;
+        assert!(visibility.is_shown(Bone::Head, Layer::Base));
+        assert!(visibility.is_shown(Bone::Head, Layer::Overlay));
+        assert!(!visibility.is_shown(Bone::Body, Layer::Base));
+        assert!(!visibility.is_shown(Bone::LeftLeg, Layer::Overlay));
+        // The head center lands on the look-at target.
+        let head = Vector3::from(Bone::Head.center()) * camera.scale;
+        let world = character.position + Matrix3::from_angle_y(Rad(90f32.to_radians())) * head;
+        assert!((world - Vector3::new(0.0, 1.0, 0.0)).magnitude() < 1e-5, "{world:?}");
+        // Zoomed in, but less so for a tall image.
+        assert!(camera.scale > 1.5);
+        let tall = character.frame_hair_only(&Camera::new(), 100, 300);
+        assert!(tall.scale < camera.scale);
+    }
+
     #[test]
     fn skin_type_eq() {
         assert_eq!(SkinType::Classic, SkinType::Classic);
@@ -196,6 +558,146 @@ mod tests {
         assert!(r.left_leg_pitch.abs() > w.left_leg_pitch.abs(), "running: legs swing wider");
     }
 
+    #[test]
+    fn mirrored_swaps_sides() {
+        let walking = DefaultPostures::WALKING;
+        let mirrored = walking.mirrored();
+        assert_eq!(mirrored.left_arm_pitch, walking.right_arm_pitch);
+        assert_eq!(mirrored.right_leg_pitch, walking.left_leg_pitch);
+        assert_eq!(mirrored.left_arm_roll, walking.right_arm_roll);
+        assert_eq!(mirrored.mirrored(), walking);
+        let turned = Posture {
+            head_yaw: 30.0,
+            head_pitch: 10.0,
+            ..DefaultPostures::STAND
+        };
+        assert_eq!((turned.mirrored().head_yaw, turned.mirrored().head_pitch), (-30.0, 10.0));
+    }
+
+    #[test]
+    fn lerp_blends_every_joint() {
+        let (a, b) = (DefaultPostures::STAND, DefaultPostures::RUNNING);
+        assert_eq!(Posture::lerp(&a, &b, 0.0), a);
+        assert_eq!(Posture::lerp(&a, &b, 1.0), b);
+        let half = Posture::lerp(&a, &b, 0.5);
+        assert_eq!(half.left_leg_pitch, b.left_leg_pitch / 2.0);
+        assert_eq!(half.right_arm_roll, b.right_arm_roll / 2.0);
+    }
+
+    #[test]
+    fn compose_takes_masked_joints_from_overlay() {
+        let walking = DefaultPostures::WALKING;
+        let wave = DefaultPostures::WAVE;
+        let composed = Posture::compose(&walking, &wave, &[Bone::LeftArm, Bone::Body]);
+        assert_eq!(composed.left_arm_pitch, wave.left_arm_pitch);
+        assert_eq!(composed.left_arm_roll, wave.left_arm_roll);
+        assert_eq!(composed.right_arm_pitch, walking.right_arm_pitch);
+        assert_eq!(composed.left_leg_pitch, walking.left_leg_pitch);
+        assert_eq!(Posture::compose(&walking, &wave, &[]), walking);
+    }
+
+    /// The direction a posed arm points, from the renderer's joint matrices.
+    fn arm_direction(posture: &Posture, arm: Bone) -> Vector3<f32> {
+        let (roll, pitch) = match arm {
+            Bone::RightArm => (posture.right_arm_roll, posture.right_arm_pitch),
+            _ => (-posture.left_arm_roll, posture.left_arm_pitch),
+        };
+        Matrix3::from_angle_z(Rad(roll.to_radians()))
+            * Matrix3::from_angle_x(Rad(pitch.to_radians()))
+            * Vector3::new(0.0, -1.0, 0.0)
+    }
+
+    #[test]
+    fn arms_point_at_targets() {
+        for arm in [Bone::RightArm, Bone::LeftArm] {
+            let shoulder = Vector3::from(arm.joint());
+            for offset in [
+                Vector3::new(0.0, 0.0, 1.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(-0.4, 0.8, 0.3),
+                Vector3::new(0.2, 1.0, -0.5),
+                Vector3::new(0.3, -1.0, 0.1),
+            ] {
+                let mut posture = DefaultPostures::STAND;
+                assert!(posture.point_arm_at(arm, shoulder + offset * 2.0));
+                let error = (arm_direction(&posture, arm) - offset.normalize()).magnitude();
+                assert!(error < 1e-4, "{arm:?} {offset:?}: {posture:?}");
+                let (roll, pitch) = Posture::arm_angles_towards(arm, shoulder + offset).unwrap();
+                assert!(roll.abs() <= 90.0 && pitch.abs() <= 180.0);
+            }
+        }
+        // Straight up is the wave's raised arm.
+        let up = Vector3::from(Bone::LeftArm.joint()) + Vector3::new(0.0, 1.0, 0.0);
+        let (roll, pitch) = Posture::arm_angles_towards(Bone::LeftArm, up).unwrap();
+        assert!(roll.abs() < 1e-4 && (pitch.abs() - 180.0).abs() < 1e-4);
+        assert_eq!(Posture::arm_angles_towards(Bone::Head, up), None);
+        let shoulder = Vector3::from(Bone::RightArm.joint());
+        assert_eq!(Posture::arm_angles_towards(Bone::RightArm, shoulder), None);
+    }
+
+    #[test]
+    fn model_point_undoes_the_character_transform() {
+        let mut character = Character::new();
+        character.position = Vector3::new(3.0, 0.0, -2.0);
+        character.rotation = Vector3::new(0.0, 90.0, 0.0);
+        // Turned 90° about Y, the character's +Z (back) points along world +X.
+        let front = character.model_point(Vector3::new(4.0, 1.0, -2.0));
+        assert!((front - Vector3::new(0.0, 1.0, 1.0)).magnitude() < 1e-5, "{front:?}");
+
+        let target = Vector3::new(5.0, 1.5, -2.0);
+        let local = character.model_point(target);
+        character.posture.point_arm_at(Bone::RightArm, local);
+        let direction = arm_direction(&character.posture, Bone::RightArm);
+        let expected = (local - Vector3::from(Bone::RightArm.joint())).normalize();
+        assert!((direction - expected).magnitude() < 1e-4);
+    }
+
+    /// The direction the posed face looks, from the renderer's joint matrices.
+    fn face_direction(posture: &Posture) -> Vector3<f32> {
+        Matrix3::from_angle_y(Rad(posture.head_yaw.to_radians()))
+            * Matrix3::from_angle_x(Rad(posture.head_pitch.to_radians()))
+            * Vector3::new(0.0, 0.0, -1.0)
+    }
+
+    #[test]
+    fn head_looks_at_targets_within_limits() {
+        let head = Vector3::from(Bone::Head.center());
+        for offset in [
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::new(1.0, 0.5, -1.0),
+            Vector3::new(-0.8, -0.6, -0.4),
+        ] {
+            let mut posture = DefaultPostures::STAND;
+            assert!(posture.head_look_at(head + offset * 3.0), "{offset:?}");
+            let error = (face_direction(&posture) - offset.normalize()).magnitude();
+            assert!(error < 1e-4, "{offset:?}: {posture:?}");
+        }
+
+        let mut posture = DefaultPostures::WALKING;
+        assert!(!posture.head_look_at(head + Vector3::new(0.2, 0.0, 1.0)));
+        assert_eq!(posture.head_yaw, -Posture::MAX_HEAD_YAW);
+        assert!(!posture.head_look_at(head + Vector3::new(0.0, 5.0, -0.1)));
+        assert_eq!(posture.head_pitch, Posture::MAX_HEAD_PITCH);
+        assert_eq!(posture.left_leg_pitch, DefaultPostures::WALKING.left_leg_pitch);
+    }
+
+    #[test]
+    fn characters_look_at_the_camera() {
+        let mut character = Character::new();
+        let camera = Camera {
+            yaw: 150.0,
+            ..Camera::new()
+        };
+        assert!(character.look_at_camera(&camera));
+        // The camera orbits around the feet-to-head axis, so the head follows its yaw.
+        assert!((character.posture.head_yaw - (camera.yaw - 180.0)).abs() < 1e-3);
+        assert!(character.posture.head_pitch < 0.0, "the eye is below the head");
+
+        character.rotation = Vector3::new(0.0, 20.0, 0.0);
+        character.look_at_camera(&camera);
+        assert!((character.posture.head_yaw - (camera.yaw - 200.0)).abs() < 1e-3);
+    }
+
     #[test]
     fn character_new_defaults() {
         let c = Character::new();
@@ -0,0 +1,506 @@
+//! Color management of skins: PNGs tagged with a color space other than sRGB are converted to
+//! sRGB when decoded, and PNG outputs can be tagged as sRGB.
+//!
+//! Decoders hand over the stored values as they are, so a skin saved with, say, a Display P3 or
+//! Adobe RGB profile would render with the colors shifted from what the artist saw in their
+//! editor. [`ColorTransform::from_png`] reads the PNG's color chunks, in the PNG specification's
+//! order of precedence:
+//!
+//! - `sRGB`: already sRGB, nothing to do.
+//! - `iCCP`: RGB and gray ICC profiles of the matrix/TRC kind (v2 or v4, `curv` or `para` curves),
+//!   which is what image editors embed. LUT-based profiles are left unconverted with a warning.
+//! - `gAMA` and `cHRM`: the transfer exponent and primaries. A gamma within 1% of 1/2.2 without
+//!   other primaries is taken as sRGB, as browsers do.
+//!
+//! Conversions that would change no color by a full 8-bit step, such as the many slightly
+//! different sRGB profiles in circulation, are skipped.
+//!
+//! [`SkinImage`](crate::texture::SkinImage) and [`crate::upload::process_upload`] apply the
+//! conversion on decode; [`tag_srgb_png`] marks outputs for color-managed viewers.
+
+use image::RgbaImage;
+use tracing::{debug, warn};
+
+use crate::error::EidolonError;
+
+type Matrix = [[f32; 3]; 3];
+
+/// White points as XYZ with Y = 1.
+const D50: [f32; 3] = [0.9642, 1.0, 0.8249];
+const D65: [f32; 3] = [0.95047, 1.0, 1.08883];
+
+/// Linear sRGB from XYZ relative to D65.
+const XYZ_TO_SRGB: Matrix = [
+    [3.2404542, -1.5371385, -0.4985314],
+    [-0.969266, 1.8760108, 0.041556],
+    [0.0556434, -0.2040259, 1.0572252],
+];
+
+/// Cone response matrix of the Bradford chromatic adaptation.
+const BRADFORD: Matrix = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296],
+];
+
+/// Conversion of 8-bit RGB in some color space to sRGB: per-channel linearization, then a
+/// matrix into linear sRGB. Alpha is left alone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorTransform {
+    /// Linear value of each 8-bit code, per channel.
+    curves: [[f32; 256]; 3],
+    /// Linear source RGB to linear sRGB.
+    matrix: Matrix,
+}
+
+impl ColorTransform {
+    /// The conversion to sRGB that `png`'s color chunks call for, or `None` when the image is
+    /// sRGB (or untagged, which means sRGB), its tag is not supported, or `png` cannot be read.
+    pub fn from_png(png: &[u8]) -> Option<Self> {
+        let reader = png::Decoder::new(std::io::Cursor::new(png))
+            .read_info()
+            .ok()?;
+        let info = reader.info();
+        if info.srgb.is_some() {
+            return None;
+        }
+        if let Some(profile) = &info.icc_profile {
+            return match Self::from_icc(profile) {
+                Ok(transform) => transform,
+                Err(e) => {
+                    warn!("Ignoring the PNG's ICC profile: {e}");
+                    None
+                }
+            };
+        }
+        let gamma = info.source_gamma.map(|gamma| gamma.into_value());
+        let primaries = info.source_chromaticities.map(|c| {
+            [c.white, c.red, c.green, c.blue].map(|(x, y)| [x.into_value(), y.into_value()])
+        });
+        Self::from_gamma_and_primaries(gamma, primaries)
+    }
+
+    /// The conversion from an ICC profile's color space to sRGB; `Ok(None)` when it is sRGB to
+    /// within a step. Profiles other than RGB or gray matrix/TRC ones are
+    /// [`EidolonError::Texture`].
+    pub fn from_icc(profile: &[u8]) -> Result<Option<Self>, EidolonError> {
+        let icc = Icc::parse(profile)?;
+        let transform = match icc.color_space {
+            b"RGB " => {
+                let colorant = |sig| icc.xyz(sig);
+                let [r, g, b] = [colorant(b"rXYZ")?, colorant(b"gXYZ")?, colorant(b"bXYZ")?];
+                let to_pcs = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+                Self {
+                    curves: [
+                        icc.curve(b"rTRC")?,
+                        icc.curve(b"gTRC")?,
+                        icc.curve(b"bTRC")?,
+                    ],
+                    // Colorants are adapted to the D50 connection space.
+                    matrix: mul(&mul(&XYZ_TO_SRGB, &adapt(D50, D65)), &to_pcs),
+                }
+            }
+            b"GRAY" => {
+                let curve = icc.curve(b"kTRC")?;
+                Self {
+                    curves: [curve; 3],
+                    matrix: IDENTITY,
+                }
+            }
+            other => {
+                return Err(EidolonError::texture(format!(
+                    "unsupported ICC color space '{}'",
+                    String::from_utf8_lossy(other).trim_end()
+                )))
+            }
+        };
+        Ok(transform.unless_srgb())
+    }
+
+    /// The conversion from values encoded with `gamma` (the PNG `gAMA` exponent, e.g. `0.45455`;
+    /// the sRGB curve when `None`) and `primaries` (CIE xy of white, red, green and blue, as in
+    /// `cHRM`; sRGB's when `None`), or `None` when that is sRGB to within a step.
+    pub fn from_gamma_and_primaries(
+        gamma: Option<f32>,
+        primaries: Option<[[f32; 2]; 4]>,
+    ) -> Option<Self> {
+        let curve = match gamma {
+            Some(gamma) if gamma > 0.0 && (gamma * 2.2 - 1.0).abs() > 0.01 => {
+                let exponent = 1.0 / gamma;
+                std::array::from_fn(|i| (i as f32 / 255.0).powf(exponent))
+            }
+            _ => std::array::from_fn(|i| srgb_to_linear(i as f32 / 255.0)),
+        };
+        let matrix = match primaries {
+            Some([white, red, green, blue]) => {
+                let to_xyz = primaries_to_xyz(white, [red, green, blue])?;
+                mul(&mul(&XYZ_TO_SRGB, &adapt(xy_to_xyz(white)?, D65)), &to_xyz)
+            }
+            None => IDENTITY,
+        };
+        Self {
+            curves: [curve; 3],
+            matrix,
+        }
+        .unless_srgb()
+    }
+
+    /// Convert the RGB of every pixel of `image` to sRGB.
+    pub fn apply(&self, image: &mut RgbaImage) {
+        for pixel in image.pixels_mut() {
+            let [r, g, b] = self.convert([pixel[0], pixel[1], pixel[2]]);
+            pixel.0[..3].copy_from_slice(&[r, g, b]);
+        }
+    }
+
+    fn convert_exact(&self, rgb: [u8; 3]) -> [f32; 3] {
+        let linear = std::array::from_fn(|c| self.curves[c][rgb[c] as usize]);
+        mul_vec(&self.matrix, linear).map(|v| linear_to_srgb(v.clamp(0.0, 1.0)) * 255.0)
+    }
+
+    fn convert(&self, rgb: [u8; 3]) -> [u8; 3] {
+        self.convert_exact(rgb).map(|v| v.round() as u8)
+    }
+
+    /// `None` if no color on a grid through the RGB cube moves by a full 8-bit step.
+    fn unless_srgb(self) -> Option<Self> {
+        let steps = (0..=255u8).step_by(15);
+        let moves = steps.clone().any(|r| {
+            steps.clone().any(|g| {
+                steps.clone().any(|b| {
+                    let out = self.convert_exact([r, g, b]);
+                    out.iter()
+                        .zip([r, g, b])
+                        .any(|(&o, i)| (o - f32::from(i)).abs() >= 1.0)
+                })
+            })
+        });
+        if moves {
+            Some(self)
+        } else {
+            debug!("Color profile is sRGB to within a step; not converting");
+            None
+        }
+    }
+}
+
+/// Convert `image`, decoded from the image file `bytes`, to sRGB if it is a PNG tagged otherwise.
+pub(crate) fn normalize_png(bytes: &[u8], image: &mut RgbaImage) {
+    if let Some(transform) = ColorTransform::from_png(bytes) {
+        debug!("Converting the PNG's colors to sRGB");
+        transform.apply(image);
+    }
+}
+
+/// `png` with `sRGB` (perceptual intent), `gAMA` and `cHRM` chunks after the header, so
+/// color-managed viewers show it as rendered. Existing color chunks (`sRGB`, `gAMA`, `cHRM`,
+/// `iCCP`) are replaced. Bytes that are not a PNG are a [`EidolonError::Texture`].
+pub fn tag_srgb_png(png: &[u8]) -> Result<Vec<u8>, EidolonError> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    let not_png = || EidolonError::texture("cannot tag as sRGB: not a PNG");
+    let mut chunks = png.strip_prefix(SIGNATURE).ok_or_else(not_png)?;
+    let mut out = Vec::with_capacity(png.len() + 3 * 12 + 45);
+    out.extend_from_slice(SIGNATURE);
+    while !chunks.is_empty() {
+        let length = chunks
+            .get(..4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or_else(not_png)?;
+        let end = length.checked_add(12).ok_or_else(not_png)?;
+        let chunk = chunks.get(..end).ok_or_else(not_png)?;
+        let kind = &chunk[4..8];
+        if !matches!(kind, b"sRGB" | b"gAMA" | b"cHRM" | b"iCCP") {
+            out.extend_from_slice(chunk);
+        }
+        if kind == b"IHDR" {
+            write_chunk(&mut out, b"sRGB", &[0]);
+            write_chunk(&mut out, b"gAMA", &45455u32.to_be_bytes());
+            // White, red, green and blue xy of sRGB, times 100000.
+            let primaries: [u32; 8] = [31270, 32900, 64000, 33000, 30000, 60000, 15000, 6000];
+            let chrm: Vec<u8> = primaries.iter().flat_map(|v| v.to_be_bytes()).collect();
+            write_chunk(&mut out, b"cHRM", &chrm);
+        }
+        chunks = &chunks[end..];
+    }
+    Ok(out)
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    let mut crc = flate2::Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    out.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+/// The header and tag table of an ICC profile.
+struct Icc<'a> {
+    data: &'a [u8],
+    color_space: &'a [u8; 4],
+}
+
+impl<'a> Icc<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self, EidolonError> {
+        if data.len() < 132 || &data[36..40] != b"acsp" {
+            return Err(EidolonError::texture("not an ICC profile"));
+        }
+        Ok(Self {
+            data,
+            color_space: data[16..20].try_into().expect("four bytes"),
+        })
+    }
+
+    fn tag(&self, signature: &[u8; 4]) -> Result<&'a [u8], EidolonError> {
+        // The count comes from the file; only entries that fit in the profile are read.
+        let count = (be_u32(self.data, 128)? as usize).min((self.data.len() - 132) / 12);
+        (0..count)
+            .map(|i| 132 + 12 * i)
+            .find(|&entry| self.data.get(entry..entry + 4) == Some(signature))
+            .and_then(|entry| {
+                let offset = be_u32(self.data, entry + 4).ok()? as usize;
+                let size = be_u32(self.data, entry + 8).ok()? as usize;
+                self.data.get(offset..offset.checked_add(size)?)
+            })
+            .ok_or_else(|| {
+                EidolonError::texture(format!(
+                    "ICC profile has no usable '{}' tag (only matrix/TRC profiles are supported)",
+                    String::from_utf8_lossy(signature)
+                ))
+            })
+    }
+
+    fn xyz(&self, signature: &[u8; 4]) -> Result<[f32; 3], EidolonError> {
+        let tag = self.tag(signature)?;
+        if !tag.starts_with(b"XYZ ") {
+            return Err(EidolonError::texture("malformed ICC XYZ tag"));
+        }
+        Ok([
+            s15_fixed16(tag, 8)?,
+            s15_fixed16(tag, 12)?,
+            s15_fixed16(tag, 16)?,
+        ])
+    }
+
+    /// The tone curve `signature` sampled at the 256 8-bit codes.
+    fn curve(&self, signature: &[u8; 4]) -> Result<[f32; 256], EidolonError> {
+        let tag = self.tag(signature)?;
+        let sample = |f: &dyn Fn(f32) -> f32| std::array::from_fn(|i| f(i as f32 / 255.0));
+        match &tag[..4.min(tag.len())] {
+            b"curv" => {
+                let count = be_u32(tag, 8)? as usize;
+                match count {
+                    0 => Ok(sample(&|x| x)),
+                    1 => {
+                        let gamma = f32::from(be_u16(tag, 12)?) / 256.0;
+                        Ok(sample(&|x| x.powf(gamma)))
+                    }
+                    _ => {
+                        let table = (0..count)
+                            .map(|i| be_u16(tag, 12 + 2 * i).map(|v| f32::from(v) / 65535.0))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(sample(&|x| {
+                            let position = x * (count - 1) as f32;
+                            let i = (position as usize).min(count - 2);
+                            let t = position - i as f32;
+                            table[i] + (table[i + 1] - table[i]) * t
+                        }))
+                    }
+                }
+            }
+            b"para" => {
+                let kind = be_u16(tag, 8)?;
+                let arity = match kind {
+                    0 => 1,
+                    1 => 3,
+                    2 => 4,
+                    3 => 5,
+                    4 => 7,
+                    _ => return Err(EidolonError::texture("unknown ICC parametric curve")),
+                };
+                let mut p = [0.0; 7];
+                for (i, value) in p.iter_mut().take(arity).enumerate() {
+                    *value = s15_fixed16(tag, 12 + 4 * i)?;
+                }
+                let [g, a, b, c, d, e, f] = p;
+                Ok(sample(&|x| match kind {
+                    0 => x.powf(g),
+                    1 if x >= -b / a => (a * x + b).powf(g),
+                    1 => 0.0,
+                    2 if x >= -b / a => (a * x + b).powf(g) + c,
+                    2 => c,
+                    3 if x >= d => (a * x + b).powf(g),
+                    3 => c * x,
+                    _ if x >= d => (a * x + b).powf(g) + e,
+                    _ => c * x + f,
+                }))
+            }
+            _ => Err(EidolonError::texture("unknown ICC curve type")),
+        }
+    }
+}
+
+fn be_u32(data: &[u8], at: usize) -> Result<u32, EidolonError> {
+    data.get(at..at + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or_else(|| EidolonError::texture("truncated ICC profile"))
+}
+
+fn be_u16(data: &[u8], at: usize) -> Result<u16, EidolonError> {
+    data.get(at..at + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+        .ok_or_else(|| EidolonError::texture("truncated ICC profile"))
+}
+
+fn s15_fixed16(data: &[u8], at: usize) -> Result<f32, EidolonError> {
+    Ok(be_u32(data, at)? as i32 as f32 / 65536.0)
+}
+
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+const IDENTITY: Matrix = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+fn mul(a: &Matrix, b: &Matrix) -> Matrix {
+    std::array::from_fn(|r| std::array::from_fn(|c| (0..3).map(|k| a[r][k] * b[k][c]).sum()))
+}
+
+fn mul_vec(m: &Matrix, v: [f32; 3]) -> [f32; 3] {
+    std::array::from_fn(|r| (0..3).map(|k| m[r][k] * v[k]).sum())
+}
+
+fn diagonal(v: [f32; 3]) -> Matrix {
+    [[v[0], 0.0, 0.0], [0.0, v[1], 0.0], [0.0, 0.0, v[2]]]
+}
+
+fn invert(m: &Matrix) -> Option<Matrix> {
+    let cofactor = |r: usize, c: usize| {
+        let (r1, r2) = ((r + 1) % 3, (r + 2) % 3);
+        let (c1, c2) = ((c + 1) % 3, (c + 2) % 3);
+        m[r1][c1] * m[r2][c2] - m[r1][c2] * m[r2][c1]
+    };
+    let determinant: f32 = (0..3).map(|c| m[0][c] * cofactor(0, c)).sum();
+    if determinant.abs() < 1e-9 {
+        return None;
+    }
+    Some(std::array::from_fn(|r| {
+        std::array::from_fn(|c| cofactor(c, r) / determinant)
+    }))
+}
+
+/// Bradford adaptation of XYZ colors from white point `from` to `to`.
+fn adapt(from: [f32; 3], to: [f32; 3]) -> Matrix {
+    let (cone_from, cone_to) = (mul_vec(&BRADFORD, from), mul_vec(&BRADFORD, to));
+    let scale = diagonal(std::array::from_fn(|i| cone_to[i] / cone_from[i]));
+    let inverse = invert(&BRADFORD).expect("Bradford matrix is invertible");
+    mul(&mul(&inverse, &scale), &BRADFORD)
+}
+
+/// XYZ with Y = 1 of the CIE xy chromaticity `xy`; `None` for y = 0.
+fn xy_to_xyz([x, y]: [f32; 2]) -> Option<[f32; 3]> {
+    (y > 0.0).then(|| [x / y, 1.0, (1.0 - x - y) / y])
+}
+
+/// Linear RGB to XYZ for primaries `rgb` with white `white`, all as xy chromaticities.
+fn primaries_to_xyz(white: [f32; 2], rgb: [[f32; 2]; 3]) -> Option<Matrix> {
+    let [r, g, b] = [xy_to_xyz(rgb[0])?, xy_to_xyz(rgb[1])?, xy_to_xyz(rgb[2])?];
+    let primaries = [[r[0], g[0], b[0]], [r[1], g[1], b[1]], [r[2], g[2], b[2]]];
+    let scale = mul_vec(&invert(&primaries)?, xy_to_xyz(white)?);
+    Some(mul(&primaries, &diagonal(scale)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const SRGB_PRIMARIES: [[f32; 2]; 4] = [[0.3127, 0.329], [0.64, 0.33], [0.3, 0.6], [0.15, 0.06]];
+    const DISPLAY_P3: [[f32; 2]; 4] = [[0.3127, 0.329], [0.68, 0.32], [0.265, 0.69], [0.15, 0.06]];
+
+    #[test]
+    fn srgb_tags_need_no_conversion() {
+        assert_eq!(ColorTransform::from_gamma_and_primaries(None, None), None);
+        assert_eq!(
+            ColorTransform::from_gamma_and_primaries(Some(0.45455), Some(SRGB_PRIMARIES)),
+            None
+        );
+        let png = std::fs::read("resources/SSSSSteven.png").unwrap();
+        // Photoshop's copy of the HP sRGB profile.
+        assert_eq!(ColorTransform::from_png(&png), None);
+    }
+
+    #[test]
+    fn wide_gamut_red_becomes_out_of_gamut_red() {
+        let p3 = ColorTransform::from_gamma_and_primaries(None, Some(DISPLAY_P3)).unwrap();
+        let [r, g, b] = p3.convert([255, 0, 0]);
+        assert_eq!(r, 255);
+        assert!(g == 0 && b == 0);
+        // Mid tones are more saturated than the same values read as sRGB.
+        let [r, g, b] = p3.convert([200, 100, 100]);
+        assert!(r > 200 && g < 100 && b < 100, "{r} {g} {b}");
+        let mut image = RgbaImage::from_pixel(1, 1, Rgba([128, 128, 128, 7]));
+        p3.apply(&mut image);
+        assert_eq!(image.get_pixel(0, 0), &Rgba([128, 128, 128, 7]));
+    }
+
+    #[test]
+    fn linear_gamma_brightens() {
+        let linear = ColorTransform::from_gamma_and_primaries(Some(1.0), None).unwrap();
+        assert_eq!(linear.convert([0, 0, 0]), [0, 0, 0]);
+        assert_eq!(linear.convert([255, 255, 255]), [255, 255, 255]);
+        // Linear 0.5 is sRGB 188.
+        assert_eq!(linear.convert([128, 128, 128]), [188; 3]);
+    }
+
+    #[test]
+    fn tagging_replaces_color_chunks() {
+        let png = std::fs::read("resources/SSSSSteven.png").unwrap();
+        let tagged = tag_srgb_png(&png).unwrap();
+        let reader = png::Decoder::new(std::io::Cursor::new(&tagged))
+            .read_info()
+            .unwrap();
+        assert!(reader.info().srgb.is_some());
+        assert!(reader.info().icc_profile.is_none());
+        let decode = |bytes: &[u8]| image::load_from_memory(bytes).unwrap().to_rgba8();
+        assert_eq!(decode(&tagged), decode(&png));
+        assert!(tag_srgb_png(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn unsupported_profiles_are_errors() {
+        assert!(ColorTransform::from_icc(b"not a profile").is_err());
+        let mut cmyk = vec![0; 132];
+        cmyk[16..20].copy_from_slice(b"CMYK");
+        cmyk[36..40].copy_from_slice(b"acsp");
+        assert!(ColorTransform::from_icc(&cmyk).is_err());
+    }
+
+    #[test]
+    fn hostile_profiles_are_errors() {
+        // Four billion tags claimed, none present.
+        let mut rgb = vec![0; 132];
+        rgb[16..20].copy_from_slice(b"RGB ");
+        rgb[36..40].copy_from_slice(b"acsp");
+        rgb[128..132].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(ColorTransform::from_icc(&rgb).is_err());
+        // A chunk length that overflows the chunk size.
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend_from_slice(&u32::MAX.to_be_bytes());
+        png.extend_from_slice(b"IHDR");
+        assert!(tag_srgb_png(&png).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! Skin comparison for reviewing revisions: two skins rendered with one pose and camera, placed
+//! side by side or alternated as a blink sequence, and a heatmap of the texels that changed.
+
+use image::{imageops, Rgba, RgbaImage};
+
+use crate::error::EidolonError;
+use crate::texture::SkinImage;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{camera::Camera, character::Character, renderer::Renderer, texture::Texture};
+
+/// Render `before` and `after` with the same `character` and `camera`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn render_pair(
+    renderer: &Renderer,
+    character: &Character,
+    before: &Texture,
+    after: &Texture,
+    camera: &Camera,
+    width: u32,
+    height: u32,
+) -> Result<[RgbaImage; 2], EidolonError> {
+    Ok([
+        renderer.render(character, before, camera, width, height)?,
+        renderer.render(character, after, camera, width, height)?,
+    ])
+}
+
+/// `left` and `right` next to each other, top-aligned, in one image.
+pub fn side_by_side(left: &RgbaImage, right: &RgbaImage) -> RgbaImage {
+    let mut image = RgbaImage::new(
+        left.width() + right.width(),
+        left.height().max(right.height()),
+    );
+    imageops::replace(&mut image, left, 0, 0);
+    imageops::replace(&mut image, right, i64::from(left.width()), 0);
+    image
+}
+
+/// Two renders alternated forever, each shown for `1 / fps` seconds, as a GIF
+/// ([`crate::animation::encode_gif`]). Changes stand out as flicker.
+pub fn blink_gif(before: &RgbaImage, after: &RgbaImage, fps: u32) -> Result<Vec<u8>, EidolonError> {
+    crate::animation::encode_gif(&[before.clone(), after.clone()], fps)
+}
+
+/// Texel-level difference between two skins of the same size.
+#[derive(Debug, Clone)]
+pub struct TextureDiff {
+    /// The skin's size: unchanged texels are a faint gray of `after`, changed ones opaque, from
+    /// yellow for slight changes to red for a full-range one.
+    pub heatmap: RgbaImage,
+    /// Number of texels that differ.
+    pub changed: u32,
+    /// Largest per-channel difference of any texel (0–255).
+    pub max_difference: u8,
+}
+
+impl TextureDiff {
+    /// Compare the double-layer pixels of `before` and `after`. Texels transparent in both count
+    /// as equal whatever their color. Skins of different sizes are a [`EidolonError::Texture`].
+    pub fn between(before: &SkinImage, after: &SkinImage) -> Result<Self, EidolonError> {
+        let (before, after) = (before.rgba(), after.rgba());
+        if before.dimensions() != after.dimensions() {
+            return Err(EidolonError::texture(format!(
+                "cannot compare a {}x{} skin with a {}x{} skin",
+                before.width(),
+                before.height(),
+                after.width(),
+                after.height()
+            )));
+        }
+        let mut heatmap = RgbaImage::new(after.width(), after.height());
+        let (mut changed, mut max_difference) = (0, 0);
+        for ((old, new), out) in before
+            .pixels()
+            .zip(after.pixels())
+            .zip(heatmap.pixels_mut())
+        {
+            let difference = texel_difference(old, new);
+            *out = if difference == 0 {
+                let [r, g, b, a] = new.0;
+                let luma = ((u32::from(r) * 3 + u32::from(g) * 6 + u32::from(b)) / 10) as u8;
+                Rgba([luma, luma, luma, a / 4])
+            } else {
+                changed += 1;
+                max_difference = max_difference.max(difference);
+                // Yellow at the smallest change to red at the largest.
+                Rgba([255, 255 - difference, 0, 255])
+            };
+        }
+        Ok(Self {
+            heatmap,
+            changed,
+            max_difference,
+        })
+    }
+}
+
+/// Largest channel difference between two texels; `0` when both are fully transparent.
+fn texel_difference(a: &Rgba<u8>, b: &Rgba<u8>) -> u8 {
+    if a[3] == 0 && b[3] == 0 {
+        return 0;
+    }
+    a.0.iter()
+        .zip(b.0)
+        .map(|(&x, y)| x.abs_diff(y))
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skin(pixels: &[(u32, u32, [u8; 4])]) -> SkinImage {
+        let mut rgba = RgbaImage::new(64, 64);
+        for &(x, y, color) in pixels {
+            rgba.put_pixel(x, y, Rgba(color));
+        }
+        SkinImage::from_rgba(rgba).unwrap()
+    }
+
+    #[test]
+    fn heatmap_marks_changed_texels() {
+        let before = skin(&[(8, 8, [10, 20, 30, 255]), (9, 8, [1, 2, 3, 0])]);
+        let after = skin(&[
+            (8, 8, [10, 20, 130, 255]),
+            (9, 8, [9, 9, 9, 0]),
+            (0, 0, [0, 0, 0, 1]),
+        ]);
+        let diff = TextureDiff::between(&before, &after).unwrap();
+        // Recoloring a hidden texel is not a change; a nearly invisible new one is.
+        assert_eq!(diff.changed, 2);
+        assert_eq!(diff.max_difference, 100);
+        assert_eq!(diff.heatmap.get_pixel(8, 8), &Rgba([255, 155, 0, 255]));
+        assert_eq!(diff.heatmap.get_pixel(9, 8)[3], 0);
+
+        let hd = SkinImage::from_rgba(RgbaImage::new(128, 128)).unwrap();
+        assert!(matches!(
+            TextureDiff::between(&before, &hd),
+            Err(EidolonError::Texture(_))
+        ));
+    }
+
+    #[test]
+    fn side_by_side_places_left_first() {
+        let left = RgbaImage::from_pixel(2, 3, Rgba([255, 0, 0, 255]));
+        let right = RgbaImage::from_pixel(4, 2, Rgba([0, 0, 255, 255]));
+        let image = side_by_side(&left, &right);
+        assert_eq!(image.dimensions(), (6, 3));
+        assert_eq!(image.get_pixel(1, 2), &Rgba([255, 0, 0, 255]));
+        assert_eq!(image.get_pixel(2, 0), &Rgba([0, 0, 255, 255]));
+        assert_eq!(image.get_pixel(2, 2)[3], 0);
+    }
+}
@@ -5,29 +5,182 @@
 /// Vertex stage: applies `uniforms.perspective`, `view`, `model`, and displaces vertices along
 /// the normal by `uniforms.offset` (small positive values push the overlay layer outward).
 ///
-/// Fragment stage: nearest-neighbor sampling via `s_skin`, discards near-transparent texels,
-/// then two directional lights plus ambient on the shaded normal.
+/// Fragment stage: nearest-neighbor sampling via `s_skin`, discards texels with alpha below
+/// `uniforms.alpha_cutoff` (and makes the rest opaque when `uniforms.cutout` is set), then the key + fill directional lights plus ambient from `lights` on the shaded normal.
+/// When `lights.rim.a > 0`, a rim term (`lights.rim.rgb`) is added along silhouette edges; the
+/// eye position is recovered from the inverse of the view matrix. When `occlusion.params.x > 0`,
+/// the lit color is darkened by analytic sphere occlusion from the *other* body parts
+/// (contact shadows under the arms, between the legs, under the chin). When `shadow.params.x > 0`,
+/// the key light is attenuated by a 3×3 PCF lookup into the key-light shadow map. When
+/// `adjust.params.w > 0`, exposure, saturation and gamma are applied to the lit color. When
+/// `adjust.hurt.a > 0`, the texel is mixed towards `adjust.hurt.rgb` before lighting (the hurt
+/// flash). When `uniforms.glint > 0`, the enchantment glint (scrolling noise in
+/// `adjust.glint.rgb`) is added to the lit color. The output is premultiplied by the texel alpha
+/// (the pipelines blend with premultiplied `over`).
+///
+/// The rim, occlusion, shadow, adjustment, hurt and glint terms are also gated by the
+/// pipeline-overridable constants `RIM_LIGHT`, `AMBIENT_OCCLUSION`, `SHADOWS`, `COLOR_ADJUST`,
+/// `HURT_TINT` and `GLINT` (all `true` by default). Skin pipelines are compiled per enabled feature set with the unused ones set to
+/// `false`, so a plain render does not carry the branches of every effect.
+///
+/// `vs_instanced`/`fs_crowd` draw crowd renders: the model matrix comes from per-instance vertex
+/// attributes (locations 3–6) instead of `uniforms.model`, and the skin from layer `layer`
+/// (location 7) of `t_skins`, so one draw covers a body part of many characters in many skins.
+///
+/// Extra entry points: `vs_shadow`/`fs_shadow` render the alpha-tested depth-only shadow map, and
+/// `fs_ground` draws the shadow-catcher ground quad (black, alpha = shadow × opacity, faded out
+/// towards the quad edge). `fs_emissive` writes the unlit skin color of the overlay layer for the
+/// emissive/bloom pass. `fs_segment` writes `uniforms.segment / 255` to red for segmentation maps.
 ///
 /// # Bind groups
 ///
-/// - Group 0, binding 0: uniform buffer (`Uniforms`: projection, view, model, offset).
+/// - Group 0, binding 0: uniform buffer (`Uniforms`: projection, view, model, offset, part,
+///   glint, alpha cutoff, cutout, segment).
+/// - Group 0, binding 1: uniform buffer (`Lights`: key/fill direction and color, ambient, rim).
+/// - Group 0, binding 2: uniform buffer (`Occlusion`: 12 posed occluder spheres, strength).
+/// - Group 0, binding 3: uniform buffer (`Shadow`: light view-projection, enabled/bias/texel/opacity).
+/// - Group 0, binding 4: uniform buffer (`Adjust`: exposure, 1/gamma, saturation, enabled; hurt
+///   tint color and amount; glint color and time).
 /// - Group 1, binding 0: skin `texture_2d`.
-/// - Group 1, binding 1: sampler (configured as nearest in the render pipeline).
+/// - Group 1, binding 1: sampler (nearest by default; see `RenderOptions::texture_filter`).
+/// - Group 1, binding 2: crowd skins `texture_2d_array` (crowd pipeline only, instead of binding 0).
+/// - Group 2, binding 0: shadow map `texture_depth_2d` (1×1 placeholder while shadows are off).
+/// - Group 2, binding 1: comparison sampler for the shadow map.
 pub const SHADER: &str = r#"
+override SHADOWS: bool = true;
+override AMBIENT_OCCLUSION: bool = true;
+override RIM_LIGHT: bool = true;
+override COLOR_ADJUST: bool = true;
+override HURT_TINT: bool = true;
+override GLINT: bool = true;
+
 struct Uniforms {
     perspective: mat4x4<f32>,
     view: mat4x4<f32>,
     model: mat4x4<f32>,
     offset: f32,
+    part: u32,
+    glint: f32,
+    alpha_cutoff: f32,
+    cutout: u32,
+    segment: u32,
 }
 
 @group(0) @binding(0)
 var<uniform> uniforms: Uniforms;
 
+struct Lights {
+    key_direction: vec4<f32>,
+    key_color: vec4<f32>,
+    fill_direction: vec4<f32>,
+    fill_color: vec4<f32>,
+    ambient: vec4<f32>,
+    rim: vec4<f32>,
+}
+
+@group(0) @binding(1)
+var<uniform> lights: Lights;
+
+struct Occlusion {
+    spheres: array<vec4<f32>, 12>,
+    params: vec4<f32>,
+}
+
+@group(0) @binding(2)
+var<uniform> occlusion: Occlusion;
+
+// Approximate occlusion of a sphere (xyz center, w radius) over the hemisphere around `n`.
+fn sphere_occlusion(p: vec3<f32>, n: vec3<f32>, sphere: vec4<f32>) -> f32 {
+    let d = sphere.xyz - p;
+    let l = max(length(d), 1e-4);
+    return max(dot(n, d / l), 0.0) * (sphere.w * sphere.w) / (l * l);
+}
+
+struct Shadow {
+    light_view_proj: mat4x4<f32>,
+    params: vec4<f32>,
+}
+
+@group(0) @binding(3)
+var<uniform> shadow: Shadow;
+
+struct Adjust {
+    params: vec4<f32>,
+    hurt: vec4<f32>,
+    glint: vec4<f32>,
+}
+
+@group(0) @binding(4)
+var<uniform> adjust: Adjust;
+
+// Value noise whose cells repeat every `period` cells along both axes.
+fn periodic_noise(p: vec2<f32>, period: f32) -> f32 {
+    let cell = floor(p);
+    let f = fract(p);
+    let s = f * f * (3.0 - 2.0 * f);
+    var corners: array<f32, 4>;
+    for (var i = 0u; i < 4u; i = i + 1u) {
+        let c = cell + vec2<f32>(f32(i & 1u), f32(i >> 1u));
+        let wrapped = c - period * floor(c / period);
+        corners[i] = fract(sin(dot(wrapped, vec2<f32>(127.1, 311.7))) * 43758.5453);
+    }
+    return mix(mix(corners[0], corners[1], s.x), mix(corners[2], corners[3], s.x), s.y);
+}
+
+// Enchantment glint at skin coordinate `uv`: two layers of streaky noise scrolling at different
+// angles, like the game's glint texture. `adjust.glint.a` is the time, repeating every 4 s.
+fn glint(uv: vec2<f32>) -> f32 {
+    let scroll = adjust.glint.a / 4.0 * 8.0;
+    let p = uv * 16.0;
+    let a = mat2x2<f32>(0.985, 0.174, -0.174, 0.985) * p;
+    let b = mat2x2<f32>(0.643, -0.766, 0.766, 0.643) * p;
+    let first = periodic_noise(vec2<f32>(a.x * 0.5 + scroll, a.y * 2.0), 8.0);
+    let second = periodic_noise(vec2<f32>(b.x * 0.5 - scroll, b.y * 2.0), 8.0);
+    return 1.2 * (pow(first, 3.0) + pow(second, 3.0));
+}
+
+// Exposure (multiplier), saturation (around Rec. 709 luminance), then gamma.
+fn adjust_color(c: vec3<f32>) -> vec3<f32> {
+    if (!COLOR_ADJUST || adjust.params.w == 0.0) {
+        return c;
+    }
+    let exposed = c * adjust.params.x;
+    let luma = dot(exposed, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let saturated = max(mix(vec3<f32>(luma), exposed, adjust.params.z), vec3<f32>(0.0));
+    return pow(saturated, vec3<f32>(adjust.params.y));
+}
+
 @group(1) @binding(0)
 var t_skin: texture_2d<f32>;
 @group(1) @binding(1)
 var s_skin: sampler;
+// Every skin of a crowd render, one per layer (the crowd pipeline binds this instead of t_skin).
+@group(1) @binding(2)
+var t_skins: texture_2d_array<f32>;
+
+@group(2) @binding(0)
+var t_shadow: texture_depth_2d;
+@group(2) @binding(1)
+var s_shadow: sampler_comparison;
+
+// Fraction of the key light reaching `world_position` (1 = fully lit), 3x3 PCF.
+fn shadow_visibility(world_position: vec3<f32>) -> f32 {
+    let clip = shadow.light_view_proj * vec4<f32>(world_position, 1.0);
+    let ndc = clip.xyz / clip.w;
+    let uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+    if (any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0)) || ndc.z > 1.0) {
+        return 1.0;
+    }
+    let depth = ndc.z - shadow.params.y;
+    var lit = 0.0;
+    for (var y = -1; y <= 1; y = y + 1) {
+        for (var x = -1; x <= 1; x = x + 1) {
+            let texel = vec2<f32>(f32(x), f32(y)) * shadow.params.z;
+            lit = lit + textureSampleCompareLevel(t_shadow, s_shadow, uv + texel, depth);
+        }
+    }
+    return lit / 9.0;
+}
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
@@ -39,41 +192,567 @@ struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) normal: vec3<f32>,
     @location(1) tex_coords: vec2<f32>,
+    @location(2) world_position: vec3<f32>,
+    // Skin layer in `t_skins` (crowd renders only).
+    @location(3) @interpolate(flat) layer: u32,
 }
 
-@vertex
-fn vs_main(in: VertexInput) -> VertexOutput {
+fn transform_vertex(in: VertexInput, model: mat4x4<f32>) -> VertexOutput {
     var out: VertexOutput;
-    let modelview = uniforms.view * uniforms.model;
+    let modelview = uniforms.view * model;
     out.tex_coords = in.tex_coords;
     let normal_matrix = mat3x3<f32>(
-        uniforms.model[0].xyz,
-        uniforms.model[1].xyz,
-        uniforms.model[2].xyz,
+        model[0].xyz,
+        model[1].xyz,
+        model[2].xyz,
     );
     out.normal = normal_matrix * in.normal;
     let offset_position = in.position + in.normal * uniforms.offset;
+    out.world_position = (model * vec4<f32>(offset_position, 1.0)).xyz;
     out.clip_position = uniforms.perspective * modelview * vec4<f32>(offset_position, 1.0);
     return out;
 }
 
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    return transform_vertex(in, uniforms.model);
+}
+
+// Per-instance part transform for crowd renders (columns of the model matrix).
+struct InstanceInput {
+    @location(3) model_0: vec4<f32>,
+    @location(4) model_1: vec4<f32>,
+    @location(5) model_2: vec4<f32>,
+    @location(6) model_3: vec4<f32>,
+    @location(7) layer: u32,
+}
+
+@vertex
+fn vs_instanced(in: VertexInput, instance: InstanceInput) -> VertexOutput {
+    let model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    var out = transform_vertex(in, model);
+    out.layer = instance.layer;
+    return out;
+}
+
+// Discard texels below the layer's alpha cutoff; cutout layers draw the rest opaque.
+fn alpha_test(tex_color: vec4<f32>) -> vec4<f32> {
+    if (tex_color.a < uniforms.alpha_cutoff) {
+        discard;
+    }
+    return select(tex_color, vec4<f32>(tex_color.rgb, 1.0), uniforms.cutout != 0u);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let tex_color = textureSample(t_skin, s_skin, in.tex_coords);
+    let tex_color = alpha_test(textureSample(t_skin, s_skin, in.tex_coords));
+    return shade(in, tex_color);
+}
+
+@fragment
+fn fs_crowd(in: VertexOutput) -> @location(0) vec4<f32> {
+    let tex_color = alpha_test(textureSample(t_skins, s_skin, in.tex_coords, in.layer));
+    return shade(in, tex_color);
+}
+
+// Lit, premultiplied color of the skin texel `tex_color`.
+fn shade(in: VertexOutput, tex_color: vec4<f32>) -> vec4<f32> {
+    let light_dir1 = normalize(lights.key_direction.xyz);
+    let light_dir2 = normalize(lights.fill_direction.xyz);
 
-    if (tex_color.a < 0.01) {
+    let diff1 = max(dot(normalize(in.normal), light_dir1), 0.0);
+    let diff2 = max(dot(normalize(in.normal), light_dir2), 0.0);
+
+    var key = diff1 * lights.key_color.rgb;
+    if (SHADOWS && shadow.params.x > 0.0) {
+        key = key * shadow_visibility(in.world_position);
+    }
+
+    let diffuse = lights.ambient.rgb + key + diff2 * lights.fill_color.rgb;
+
+    var albedo = tex_color.rgb;
+    if (HURT_TINT && adjust.hurt.a > 0.0) {
+        albedo = mix(albedo, adjust.hurt.rgb, adjust.hurt.a);
+    }
+    var color = albedo * diffuse;
+    if (AMBIENT_OCCLUSION && occlusion.params.x > 0.0) {
+        let n = normalize(in.normal);
+        var occ = 0.0;
+        for (var i = 0u; i < 12u; i = i + 1u) {
+            if (i / 2u != uniforms.part) {
+                occ = occ + sphere_occlusion(in.world_position, n, occlusion.spheres[i]);
+            }
+        }
+        color = color * (1.0 - occlusion.params.x * clamp(occ, 0.0, 1.0));
+    }
+    if (RIM_LIGHT && lights.rim.a > 0.0) {
+        let view_rot = mat3x3<f32>(
+            uniforms.view[0].xyz,
+            uniforms.view[1].xyz,
+            uniforms.view[2].xyz,
+        );
+        let eye = -(transpose(view_rot) * uniforms.view[3].xyz);
+        let to_eye = normalize(eye - in.world_position);
+        let facing = max(dot(normalize(in.normal), to_eye), 0.0);
+        color = color + lights.rim.rgb * pow(1.0 - facing, 3.0) * lights.rim.a;
+    }
+    if (GLINT && uniforms.glint > 0.0) {
+        color = color + adjust.glint.rgb * glint(in.tex_coords);
+    }
+    color = adjust_color(color);
+
+    return vec4<f32>(color * tex_color.a, tex_color.a);
+}
+
+struct ShadowOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+}
+
+@vertex
+fn vs_shadow(in: VertexInput) -> ShadowOutput {
+    var out: ShadowOutput;
+    let offset_position = in.position + in.normal * uniforms.offset;
+    out.clip_position = shadow.light_view_proj * uniforms.model * vec4<f32>(offset_position, 1.0);
+    out.tex_coords = in.tex_coords;
+    return out;
+}
+
+@fragment
+fn fs_shadow(in: ShadowOutput) {
+    if (textureSample(t_skin, s_skin, in.tex_coords).a < 0.5) {
         discard;
     }
+}
 
-    let light_dir1 = normalize(vec3<f32>(1.0, 1.0, 1.0));
-    let light_dir2 = normalize(vec3<f32>(-1.0, 0.5, -0.5));
+@fragment
+fn fs_emissive(in: VertexOutput) -> @location(0) vec4<f32> {
+    let tex_color = alpha_test(textureSample(t_skin, s_skin, in.tex_coords));
+    return vec4<f32>(adjust_color(tex_color.rgb), tex_color.a);
+}
 
-    let ambient = 0.5;
-    let diff1 = max(dot(normalize(in.normal), light_dir1), 0.0);
-    let diff2 = max(dot(normalize(in.normal), light_dir2), 0.0) * 0.3;
+@fragment
+fn fs_segment(in: VertexOutput) -> @location(0) vec4<f32> {
+    _ = alpha_test(textureSample(t_skin, s_skin, in.tex_coords));
+    return vec4<f32>(f32(uniforms.segment) / 255.0, 0.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_ground(in: VertexOutput) -> @location(0) vec4<f32> {
+    let edge = length(in.tex_coords * 2.0 - vec2<f32>(1.0));
+    let fade = 1.0 - smoothstep(0.6, 1.0, edge);
+    let darkness = (1.0 - shadow_visibility(in.world_position)) * shadow.params.w * fade;
+    return vec4<f32>(0.0, 0.0, 0.0, darkness);
+}
+"#;
+
+/// Fullscreen post-processing shader, run after the skin pass when a post effect is enabled.
+///
+/// Vertex stage: one oversized triangle covering the viewport (no vertex buffer).
+///
+/// Fragment stage (`fs_composite`): loads the scene color and, when `post.dof.x > 0`, gathers a
+/// golden-angle disc whose radius is the circle of confusion derived from the linearized depth
+/// buffer. Samples behind the shaded pixel only count up to the pixel's own blur radius, so a
+/// sharp subject does not pick up the blurred background. The scene and all accumulation are
+/// premultiplied; the result is written straight or premultiplied (`post.output.z > 0`). When `post.bloom.x > 0`, visible overlay texels are
+/// replaced by their unlit color from `t_emissive` before any blur, and the blurred bloom
+/// texture is added on top (also raising alpha, so the glow shows on transparent backgrounds).
+/// The scene target is floating point, so the final color can exceed 1.0 until the tone mapper
+/// selected by `post.output.x` (0 = clamp, 1 = Reinhard, 2 = ACES filmic) maps it to `0..1`;
+/// `post.output.y > 0` skips tone mapping for float outputs.
+///
+/// `fs_depth` writes the eye distance of each pixel (`-1` where nothing was drawn) for depth
+/// outputs.
+///
+/// # Bind groups
+///
+/// - Group 0, binding 0: uniform buffer (`Post`: DOF enabled/focus/radius, near/far planes,
+///   emissive enabled/bloom intensity, tone mapper/raw/premultiplied output).
+/// - Group 0, binding 1: premultiplied scene color `texture_2d` (loaded, not sampled).
+/// - Group 0, binding 2: scene depth, bound as an unfilterable `texture_2d` (`textureLoad` on
+///   `texture_depth_2d` is unavailable on the GL backend).
+/// - Group 0, binding 3: unlit overlay color `texture_2d` (1×1 placeholder when emissive is off).
+/// - Group 0, binding 4: blurred bloom `texture_2d`, premultiplied (1×1 placeholder when off).
+pub const POST_SHADER: &str = r#"
+struct Post {
+    dof: vec4<f32>,
+    projection: vec4<f32>,
+    bloom: vec4<f32>,
+    output: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> post: Post;
+@group(0) @binding(1)
+var t_color: texture_2d<f32>;
+@group(0) @binding(2)
+var t_depth: texture_2d<f32>;
+@group(0) @binding(3)
+var t_emissive: texture_2d<f32>;
+@group(0) @binding(4)
+var t_bloom: texture_2d<f32>;
+
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+fn clamp_texel(p: vec2<i32>) -> vec2<i32> {
+    let size = vec2<i32>(textureDimensions(t_color));
+    return clamp(p, vec2<i32>(0), size - vec2<i32>(1));
+}
+
+fn load_premultiplied(p: vec2<i32>) -> vec4<f32> {
+    let q = clamp_texel(p);
+    let c = textureLoad(t_color, q, 0);
+    var rgb = c.rgb;
+    if (post.bloom.x > 0.0) {
+        let e = textureLoad(t_emissive, q, 0);
+        rgb = mix(rgb, e.rgb * c.a, e.a);
+    }
+    return vec4<f32>(rgb, c.a);
+}
+
+// Eye distance from a depth-buffer value (OpenGL-style projection, depth = clip z / w).
+fn linear_depth(p: vec2<i32>) -> f32 {
+    let d = textureLoad(t_depth, clamp_texel(p), 0).r;
+    let n = post.projection.x;
+    let f = post.projection.y;
+    return 2.0 * n * f / ((f + n) - d * (f - n));
+}
+
+fn circle_of_confusion(z: f32) -> f32 {
+    let focus = post.dof.y;
+    return post.dof.z * clamp(abs(z - focus) * 2.0 / focus, 0.0, 1.0);
+}
+
+const DOF_TAPS: u32 = 48u;
+const GOLDEN_ANGLE: f32 = 2.39996323;
+
+fn depth_of_field(p: vec2<i32>) -> vec4<f32> {
+    let center_z = linear_depth(p);
+    let center_coc = circle_of_confusion(center_z);
+    var sum = load_premultiplied(p);
+    var weight = 1.0;
+    for (var i = 1u; i < DOF_TAPS; i = i + 1u) {
+        let r = post.dof.z * sqrt(f32(i) / f32(DOF_TAPS));
+        let a = f32(i) * GOLDEN_ANGLE;
+        let q = p + vec2<i32>(round(vec2<f32>(cos(a), sin(a)) * r));
+        let z = linear_depth(q);
+        var coc = circle_of_confusion(z);
+        if (z > center_z) {
+            coc = min(coc, center_coc);
+        }
+        let w = clamp(coc - r + 1.0, 0.0, 1.0);
+        sum = sum + load_premultiplied(q) * w;
+        weight = weight + w;
+    }
+    return sum / weight;
+}
+
+fn tone_map(c: vec3<f32>) -> vec3<f32> {
+    let x = max(c, vec3<f32>(0.0));
+    if (post.output.x == 1.0) {
+        return x / (vec3<f32>(1.0) + x);
+    }
+    if (post.output.x == 2.0) {
+        return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3<f32>(0.0), vec3<f32>(1.0));
+    }
+    return min(x, vec3<f32>(1.0));
+}
+
+@fragment
+fn fs_composite(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let p = vec2<i32>(position.xy);
+    var color = load_premultiplied(p);
+    if (post.dof.x > 0.0) {
+        color = depth_of_field(p);
+    }
+    if (post.bloom.x > 0.0) {
+        let glow = textureLoad(t_bloom, clamp_texel(p), 0).rgb * post.bloom.y;
+        color = vec4<f32>(color.rgb + glow, min(color.a + max(glow.r, max(glow.g, glow.b)), 1.0));
+    }
+    if (color.a <= 0.0) {
+        return vec4<f32>(0.0);
+    }
+    var rgb = color.rgb / color.a;
+    if (post.output.y == 0.0) {
+        rgb = tone_map(rgb);
+    }
+    if (post.output.z > 0.0) {
+        rgb = rgb * color.a;
+    }
+    return vec4<f32>(rgb, color.a);
+}
+
+@fragment
+fn fs_depth(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let p = vec2<i32>(position.xy);
+    if (textureLoad(t_depth, clamp_texel(p), 0).r >= 1.0) {
+        return vec4<f32>(-1.0);
+    }
+    return vec4<f32>(linear_depth(p));
+}
+"#;
+
+/// Separable Gaussian blur for bloom, run twice (horizontal, then vertical) over the emissive
+/// texture.
+///
+/// The first pass (`blur.params.w = 1`) also applies the bright-pass: texels are premultiplied
+/// and scaled by how far their luminance exceeds `blur.params.z`. The kernel spans ±3σ in
+/// `BLUR_TAPS` steps of `blur.params.xy` texels.
+///
+/// # Bind groups
+///
+/// - Group 0, binding 0: uniform buffer (`Blur`: tap step, threshold, bright-pass flag).
+/// - Group 0, binding 1: source `texture_2d` (loaded, not sampled).
+pub const BLOOM_SHADER: &str = r#"
+struct Blur {
+    params: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> blur: Blur;
+@group(0) @binding(1)
+var t_source: texture_2d<f32>;
+
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+fn bright_pass(c: vec4<f32>) -> vec4<f32> {
+    if (blur.params.w == 0.0) {
+        return c;
+    }
+    let rgb = c.rgb * c.a;
+    let luminance = dot(rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let t = blur.params.z;
+    let k = clamp((luminance - t) / max(1.0 - t, 1e-3), 0.0, 1.0);
+    return vec4<f32>(rgb, c.a) * k;
+}
+
+const BLUR_TAPS: i32 = 12;
 
-    let diffuse = (ambient + diff1 * 0.5 + diff2) * vec3<f32>(1.0, 1.0, 1.0);
+@fragment
+fn fs_blur(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let p = vec2<i32>(position.xy);
+    let size = vec2<i32>(textureDimensions(t_source));
+    var sum = vec4<f32>(0.0);
+    var total = 0.0;
+    for (var i = -BLUR_TAPS; i <= BLUR_TAPS; i = i + 1) {
+        let x = f32(i) / f32(BLUR_TAPS);
+        let w = exp(-4.5 * x * x);
+        let q = clamp(p + vec2<i32>(round(blur.params.xy * f32(i))), vec2<i32>(0), size - vec2<i32>(1));
+        sum = sum + bright_pass(textureLoad(t_source, q, 0)) * w;
+        total = total + w;
+    }
+    return sum / total;
+}
+"#;
+
+/// Billboarded sprite shader for status effects (fire, potion swirls) and particles.
+///
+/// Vertex stage (`vs_sprite`): six vertices per instance span a quad of `center.w` world units
+/// around `center.xyz`, along the camera's right and up axes.
+///
+/// Fragment stage (`fs_sprite`): draws the pixel-art pattern selected by `params.x` — 0 = flame
+/// (16×16 texels scrolling upwards, repeating every second of `sprites.right.w`), 1 = swirl
+/// (an 8×8 ring that shrinks, turns and fades with the age in `params.z`), and the particles
+/// 2 = heart, 3 = spark, 4 = glyph (picked by the seed in `params.y`), which fade in and out with
+/// their age — tinted by `color`, and writes premultiplied color. Patterns are bitmaps or come
+/// from a hash of the texel, so they need no texture.
+///
+/// # Bind groups
+///
+/// - Group 0, binding 0: uniform buffer (`Sprites`: view-projection, camera right + time, up).
+pub const SPRITE_SHADER: &str = r#"
+struct Sprites {
+    view_proj: mat4x4<f32>,
+    right: vec4<f32>,
+    up: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> sprites: Sprites;
 
-    return vec4<f32>(tex_color.rgb * diffuse, tex_color.a);
+struct SpriteInput {
+    @location(0) center: vec4<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) params: vec4<f32>,
+}
+
+struct SpriteOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) @interpolate(flat) params: vec4<f32>,
+}
+
+@vertex
+fn vs_sprite(@builtin(vertex_index) index: u32, in: SpriteInput) -> SpriteOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 1.0),
+    );
+    let corner = corners[index];
+    let offset = (corner - vec2<f32>(0.5)) * in.center.w;
+    let world = in.center.xyz + sprites.right.xyz * offset.x + sprites.up.xyz * offset.y;
+    var out: SpriteOutput;
+    out.clip_position = sprites.view_proj * vec4<f32>(world, 1.0);
+    out.uv = corner;
+    out.color = in.color;
+    out.params = in.params;
+    return out;
+}
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+// 16x16 fire scrolling upwards; `uv.y` = 0 at the bottom.
+fn flame(uv: vec2<f32>, seed: f32, time: f32) -> vec4<f32> {
+    let texel = min(floor(uv * 16.0), vec2<f32>(15.0));
+    let scrolled = texel.y + 32.0 - floor(fract(time) * 32.0);
+    let row = scrolled - 32.0 * floor(scrolled / 32.0);
+    let coarse = hash(vec2<f32>(floor(texel.x / 2.0) + seed * 13.0, floor(row / 3.0)));
+    let fine = hash(vec2<f32>(texel.x + seed * 7.0, row));
+    let height = texel.y / 15.0;
+    let side = abs(texel.x - 7.5) / 8.0;
+    let heat = 1.0 - height * 1.2 - side * side * 0.6 + (coarse - 0.5) * 1.1 + (fine - 0.5) * 0.3;
+    if (heat < 0.3) {
+        return vec4<f32>(0.0);
+    }
+    var color = vec3<f32>(0.8, 0.2, 0.05);
+    if (heat > 0.5) {
+        color = vec3<f32>(1.0, 0.55, 0.1);
+    }
+    if (heat > 0.8) {
+        color = vec3<f32>(1.0, 0.9, 0.45);
+    }
+    return vec4<f32>(color, 1.0);
+}
+
+// 8x8 ring with a gap, shrinking, turning and fading as the particle ages.
+fn swirl(uv: vec2<f32>, age: f32) -> vec4<f32> {
+    let texel = min(floor(uv * 8.0), vec2<f32>(7.0));
+    let c = texel - vec2<f32>(3.5);
+    let radius = 3.2 - 2.0 * age;
+    if (abs(length(c) - radius) > 0.75) {
+        return vec4<f32>(0.0);
+    }
+    if (fract(atan2(c.y, c.x) / 6.2831853 + age) < 0.2) {
+        return vec4<f32>(0.0);
+    }
+    return vec4<f32>(1.0, 1.0, 1.0, 1.0 - age * age);
+}
+
+// Particles fade in quickly and out over the last quarter of their life.
+fn particle_fade(age: f32) -> f32 {
+    return min(age * 10.0, 1.0) * (1.0 - smoothstep(0.75, 1.0, age));
+}
+
+// 8x8 heart; rows from the bottom, bit x set where the heart covers column x.
+fn heart(uv: vec2<f32>, age: f32) -> vec4<f32> {
+    var rows = array<u32, 8>(0x00u, 0x18u, 0x3cu, 0x7eu, 0xffu, 0xffu, 0xffu, 0x66u);
+    let texel = vec2<u32>(min(floor(uv * 8.0), vec2<f32>(7.0)));
+    if (((rows[texel.y] >> texel.x) & 1u) == 0u) {
+        return vec4<f32>(0.0);
+    }
+    var shade = vec3<f32>(1.0);
+    if (texel.y == 6u && texel.x == 1u) {
+        shade = vec3<f32>(2.5);
+    } else if (texel.y <= 2u || texel.x == 7u) {
+        shade = vec3<f32>(0.7);
+    }
+    return vec4<f32>(shade, particle_fade(age));
+}
+
+// 5x5 plus-shaped spark whose arms shrink away with age, leaving the bright center.
+fn spark(uv: vec2<f32>, age: f32) -> vec4<f32> {
+    let c = abs(min(floor(uv * 5.0), vec2<f32>(4.0)) - vec2<f32>(2.0));
+    let reach = select(2.0, select(1.0, 0.0, age > 0.7), age > 0.35);
+    if (min(c.x, c.y) > 0.0 || max(c.x, c.y) > reach) {
+        return vec4<f32>(0.0);
+    }
+    let shade = select(0.8, 1.3, max(c.x, c.y) == 0.0);
+    return vec4<f32>(vec3<f32>(shade), particle_fade(age));
+}
+
+// 6x6 glyph: a random 4x5 rune picked by the seed, with a dark edge.
+fn glyph(uv: vec2<f32>, seed: f32, age: f32) -> vec4<f32> {
+    let texel = min(floor(uv * 6.0), vec2<f32>(5.0));
+    if (texel.x < 1.0 || texel.x > 4.0 || texel.y > 4.0) {
+        return vec4<f32>(0.0);
+    }
+    // Mirrored halves keep the runes readable as symbols rather than noise.
+    let column = min(texel.x, 5.0 - texel.x);
+    if (hash(vec2<f32>(column + seed * 3.1, texel.y + seed * 0.7)) < 0.45) {
+        return vec4<f32>(0.0);
+    }
+    let shade = select(1.0, 0.6, texel.y == 0.0);
+    return vec4<f32>(vec3<f32>(shade), particle_fade(age));
+}
+
+@fragment
+fn fs_sprite(in: SpriteOutput) -> @location(0) vec4<f32> {
+    var pattern = vec4<f32>(0.0);
+    switch u32(in.params.x) {
+        case 0u: {
+            pattern = flame(in.uv, in.params.y, sprites.right.w);
+        }
+        case 1u: {
+            pattern = swirl(in.uv, in.params.z);
+        }
+        case 2u: {
+            pattern = heart(in.uv, in.params.z);
+        }
+        case 3u: {
+            pattern = spark(in.uv, in.params.z);
+        }
+        case 4u: {
+            pattern = glyph(in.uv, in.params.y, in.params.z);
+        }
+        default: {}
+    }
+    let color = pattern * in.color;
+    if (color.a < 0.01) {
+        discard;
+    }
+    return vec4<f32>(color.rgb * color.a, color.a);
+}
+"#;
+
+/// Depth resolve for multisampled renders: a fullscreen pass that writes the nearest sample of
+/// each pixel of the multisampled depth buffer to `frag_depth` of a single-sampled one, which the
+/// post-processing and emissive passes read.
+///
+/// # Bind groups
+///
+/// - Group 0, binding 0: multisampled depth buffer `texture_depth_multisampled_2d` (loaded).
+pub const DEPTH_RESOLVE_SHADER: &str = r#"
+@group(0) @binding(0)
+var t_depth: texture_depth_multisampled_2d;
+
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+@fragment
+fn fs_resolve(@builtin(position) position: vec4<f32>) -> @builtin(frag_depth) f32 {
+    let p = vec2<i32>(position.xy);
+    var depth = 1.0;
+    for (var i = 0; i < i32(textureNumSamples(t_depth)); i = i + 1) {
+        depth = min(depth, textureLoad(t_depth, p, i));
+    }
+    return depth;
 }
 "#;
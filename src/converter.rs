@@ -1,5 +1,10 @@
 use crate::error::EidolonError;
-use image::{imageops, DynamicImage, GenericImageView, ImageBuffer};
+use image::{DynamicImage, Pixel, Rgba, RgbaImage};
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
+
+/// Pixel rectangle `(x0, y0, x1, y1)` in 64px reference coordinates.
+type Rect = (u32, u32, u32, u32);
 
 // 64×32 right leg: source rects on the upper half (OFIB face order) in pixel coords (x0, y0, x1, y1).
 const RIGHT_LEG_OUTSIDE_RANGE: (u32, u32, u32, u32) = (0, 20, 4, 32);
@@ -42,31 +47,69 @@ fn scale_rect(rect: (u32, u32, u32, u32), hd_ratio: f32) -> (u32, u32, u32, u32)
 
 /// Expand a legacy single-layer skin (`width == 2 * height`) to a square double-layer atlas by
 /// copying the top half and synthesizing mirrored left limbs in the bottom half.
+///
+/// Allocates the output; [`single2double_in_place`] and [`single2double_into`] reuse buffers.
 pub fn single2double(img: &DynamicImage) -> Result<DynamicImage, EidolonError> {
+    check_single_layer(img.width(), img.height())?;
+    let mut output = img.to_rgba8();
+    single2double_in_place(&mut output)?;
+    Ok(DynamicImage::ImageRgba8(output))
+}
+
+/// [`single2double`] without a second image: the single-layer skin's own buffer is grown to the
+/// square atlas, whose top half it already is. On error `img` is left unchanged.
+pub fn single2double_in_place(img: &mut RgbaImage) -> Result<(), EidolonError> {
+    let (width, height) = img.dimensions();
+    check_single_layer(width, height)?;
+    let mut buffer = std::mem::take(img).into_raw();
+    buffer.resize(width as usize * width as usize * 4, 0);
+    mirror_limbs(&mut buffer, width);
+    *img = RgbaImage::from_raw(width, width, buffer).ok_or_else(buffer_size_error)?;
+    Ok(())
+}
+
+/// [`single2double`] into `output`, whose allocation is reused: services converting one skin
+/// after another keep one output image (or one per worker) instead of allocating per skin.
+/// `output` is resized to the square atlas; its previous contents do not matter.
+pub fn single2double_into(img: &RgbaImage, output: &mut RgbaImage) -> Result<(), EidolonError> {
+    let (width, height) = img.dimensions();
+    check_single_layer(width, height)?;
+    let mut buffer = std::mem::take(output).into_raw();
+    buffer.clear();
+    buffer.extend_from_slice(img.as_raw());
+    buffer.resize(width as usize * width as usize * 4, 0);
+    mirror_limbs(&mut buffer, width);
+    *output = RgbaImage::from_raw(width, width, buffer).ok_or_else(buffer_size_error)?;
+    Ok(())
+}
+
+fn check_single_layer(width: u32, height: u32) -> Result<(), EidolonError> {
     // Check if the image is single-layer (width is twice the height)
-    if img.width() != img.height() * 2 {
+    if width != height * 2 {
         return Err(EidolonError::conversion(
             "Input image is not a single-layer skin (width must be twice the height)",
         ));
     }
+    Ok(())
+}
 
-    let hd_ratio = img.width() as f32 / 64.0;
-    let scale = |rect: (u32, u32, u32, u32)| scale_rect(rect, hd_ratio);
-
-    // Create a new square image buffer for the double-layer skin
-    let mut output_img = ImageBuffer::new(img.width(), img.width());
+fn buffer_size_error() -> EidolonError {
+    EidolonError::conversion("output buffer does not match the skin size")
+}
 
-    // Copy the original single-layer skin to the top half of the new image
-    for y in 0..img.height() {
-        for x in 0..img.width() {
-            let pixel = img.get_pixel(x, y);
-            output_img.put_pixel(x, y, pixel);
-        }
-    }
+/// Fill the empty bottom half of a `width`×`width` RGBA `buffer`, whose top half is a
+/// single-layer skin, with its right limbs mirrored into the left-limb regions.
+fn mirror_limbs(buffer: &mut [u8], width: u32) {
+    let hd_ratio = width as f32 / 64.0;
+    let scale = |rect: (u32, u32, u32, u32)| scale_rect(rect, hd_ratio);
+    let half = width / 2;
+    // Sources lie in the top half and destinations in the bottom half.
+    let (top, bottom) = buffer.split_at_mut((half * width) as usize * 4);
+    let width = width as usize;
 
     // Map each right-side source region (top half) to its left-side
-    // destination (bottom half): crop → flip horizontally → overlay
-    const REGION_PAIRS: &[((u32, u32, u32, u32), (u32, u32, u32, u32))] = &[
+    // destination (bottom half), flipped horizontally
+    const REGION_PAIRS: &[(Rect, Rect)] = &[
         // Right leg parts → left leg positions
         (RIGHT_LEG_OUTSIDE_RANGE, LEFT_LEG_OUTSIDE_RANGE),
         (RIGHT_LEG_TOP_FRONT_RANGE, LEFT_LEG_TOP_FRONT_RANGE),
@@ -83,22 +126,39 @@ pub fn single2double(img: &DynamicImage) -> Result<DynamicImage, EidolonError> {
     ];
 
     for (src_rect, dst_rect) in REGION_PAIRS {
-        let src = scale(*src_rect);
-        let dst = scale(*dst_rect);
-        let cropped = img
-            .view(src.0, src.1, src.2 - src.0, src.3 - src.1)
-            .to_image();
-        let flipped = imageops::flip_horizontal(&cropped);
-        imageops::overlay(&mut output_img, &flipped, dst.0 as i64, dst.1 as i64);
+        let (sx0, sy0, sx1, sy1) = scale(*src_rect);
+        let (dx0, dy0, ..) = scale(*dst_rect);
+        // Odd HD ratios can round a destination past the edge; it is clipped, as by
+        // `imageops::overlay`.
+        let region_width = (sx1 - sx0).min(width as u32 - dx0) as usize;
+        let region_height = (sy1 - sy0).min(width as u32 - dy0) as usize;
+        for row in 0..region_height {
+            let from = ((sy0 as usize + row) * width + sx0 as usize) * 4;
+            let to = (((dy0 - half) as usize + row) * width + dx0 as usize) * 4;
+            let source = &top[from..from + region_width * 4];
+            let target = &mut bottom[to..to + region_width * 4];
+            for (target, source) in target.chunks_exact_mut(4).zip(source.chunks_exact(4).rev()) {
+                // Blended onto the empty bottom half, as `imageops::overlay` did: fully
+                // transparent texels stay zero.
+                let mut pixel = Rgba([0; 4]);
+                pixel.blend(Rgba::from_slice(source));
+                target.copy_from_slice(&pixel.0);
+            }
+        }
     }
+}
 
-    Ok(DynamicImage::ImageRgba8(output_img))
+/// [`single2double`] for many skins at once, converted in parallel. Results are in the order of
+/// `images`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn single2double_batch(images: &[DynamicImage]) -> Vec<Result<DynamicImage, EidolonError>> {
+    images.par_iter().map(single2double).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use image::{DynamicImage, Rgba};
+    use image::{DynamicImage, GenericImageView, Rgba};
 
     #[test]
     fn test_single2double_success() {
@@ -143,6 +203,97 @@ mod tests {
         assert!(single2double(&img2).is_err());
     }
 
+    /// The crop, flip and overlay conversion the raw-buffer one replaced.
+    fn reference_single2double(img: &DynamicImage) -> RgbaImage {
+        use image::imageops;
+        let scale = |rect| scale_rect(rect, img.width() as f32 / 64.0);
+        let mut output = RgbaImage::new(img.width(), img.width());
+        imageops::replace(&mut output, &img.to_rgba8(), 0, 0);
+        for (src_rect, dst_rect) in [
+            (RIGHT_LEG_OUTSIDE_RANGE, LEFT_LEG_OUTSIDE_RANGE),
+            (RIGHT_LEG_TOP_FRONT_RANGE, LEFT_LEG_TOP_FRONT_RANGE),
+            (RIGHT_LEG_BUTTOM_RANGE, LEFT_LEG_BUTTOM_RANGE),
+            (RIGHT_LEG_INSIDE_RANGE, LEFT_LEG_INSIDE_RANGE),
+            (RIGHT_LEG_BACK_RANGE, LEFT_LEG_BACK_RANGE),
+            (RIGHT_ARM_OUTSIDE_RANGE, LEFT_ARM_OUTSIDE_RANGE),
+            (RIGHT_ARM_TOP_RANGE, LEFT_ARM_TOP_RANGE),
+            (RIGHT_ARM_FRONT_RANGE, LEFT_ARM_FRONT_RANGE),
+            (RIGHT_ARM_BUTTOM_RANGE, LEFT_ARM_BUTTOM_RANGE),
+            (RIGHT_ARM_INSIDE_RANGE, LEFT_ARM_INSIDE_RANGE),
+            (RIGHT_ARM_BACK_RANGE, LEFT_ARM_BACK_RANGE),
+        ] {
+            let src = scale(src_rect);
+            let dst = scale(dst_rect);
+            let cropped = img.view(src.0, src.1, src.2 - src.0, src.3 - src.1).to_image();
+            let flipped = imageops::flip_horizontal(&cropped);
+            imageops::overlay(&mut output, &flipped, dst.0 as i64, dst.1 as i64);
+        }
+        output
+    }
+
+    #[test]
+    fn raw_buffer_conversion_matches_overlaying() {
+        let legacy = image::open("resources/SSSSSteven.png").unwrap();
+        assert_eq!(
+            single2double(&legacy).unwrap().to_rgba8(),
+            reference_single2double(&legacy)
+        );
+        // Noise with every alpha, at standard, HD and odd sizes.
+        let mut state = 2005u32;
+        for width in [2, 6, 64, 96, 128, 200, 256] {
+            let noise = RgbaImage::from_fn(width, width / 2, |_, _| {
+                Rgba([0; 4].map(|_: u8| {
+                    state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+                    (state >> 24) as u8
+                }))
+            });
+            let noise = DynamicImage::ImageRgba8(noise);
+            assert_eq!(
+                single2double(&noise).unwrap().to_rgba8(),
+                reference_single2double(&noise),
+                "{width}"
+            );
+        }
+        // Other pixel formats are converted to RGBA first.
+        let rgb = DynamicImage::ImageRgb8(legacy.to_rgb8());
+        assert_eq!(single2double(&rgb).unwrap().to_rgba8(), reference_single2double(&rgb));
+    }
+
+    #[test]
+    fn in_place_and_into_match_single2double() {
+        let legacy = image::open("resources/SSSSSteven.png").unwrap();
+        let expected = single2double(&legacy).unwrap().to_rgba8();
+
+        let mut in_place = legacy.to_rgba8();
+        single2double_in_place(&mut in_place).unwrap();
+        assert_eq!(in_place, expected);
+
+        // A reused output keeps its allocation, whatever it held before.
+        let mut output = RgbaImage::from_pixel(128, 128, Rgba([9; 4]));
+        let allocation = output.as_raw().as_ptr();
+        single2double_into(&legacy.to_rgba8(), &mut output).unwrap();
+        assert_eq!(output, expected);
+        assert_eq!(output.as_raw().as_ptr(), allocation);
+
+        // Rejected input leaves the image alone.
+        let mut square = RgbaImage::from_pixel(64, 64, Rgba([1; 4]));
+        assert!(single2double_in_place(&mut square).is_err());
+        assert_eq!(square, RgbaImage::from_pixel(64, 64, Rgba([1; 4])));
+        assert!(single2double_into(&square, &mut output).is_err());
+    }
+
+    #[test]
+    fn batches_keep_their_order() {
+        let legacy = image::open("resources/SSSSSteven.png").unwrap();
+        let square = DynamicImage::ImageRgba8(RgbaImage::new(64, 64));
+        let results = single2double_batch(&[legacy.clone(), square, legacy.clone()]);
+        assert_eq!(results.len(), 3);
+        let expected = single2double(&legacy).unwrap();
+        assert_eq!(results[0].as_ref().unwrap(), &expected);
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap(), &expected);
+    }
+
     #[test]
     fn test_scale_rect_identity() {
         // hd_ratio=1.0 → no scaling
@@ -0,0 +1,393 @@
+//! Cosmetics manifests: a JSON file listing the attachments of an accessory pack, so a cosmetics
+//! service can load its whole catalog at once and render any item on any skin.
+//!
+//! ```json
+//! {
+//!   "name": "Summer pack",
+//!   "attachments": {
+//!     "round_glasses": { "model": "glasses/round.bbmodel", "bone": "head" },
+//!     "straw_hat": {
+//!       "model": "hats/straw.obj",
+//!       "texture": "hats/straw.png",
+//!       "bone": "head",
+//!       "offset": [0, 0.05, 0],
+//!       "rotation": [-5, 0, 0],
+//!       "scale": 1.1,
+//!       "render": { "hide_overlay": true, "cast_shadow": false }
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! `model` (`.obj` or `.bbmodel`) and `bone` (`head`, `body`, `right-arm`, `left-arm`,
+//! `right-leg`, `left-leg`) are required. `texture` is a PNG and may be left out for `.bbmodel`
+//! files that embed theirs. The transform fields default to the identity ([`AttachmentTransform`];
+//! `scale` is one number or three) and `render` to [`AttachmentFlags::default`]. Paths are
+//! relative to the manifest and may not leave its directory. Unknown fields and values fail with
+//! [`EidolonError::Model`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use cgmath::Vector3;
+use serde_json::Value;
+
+use crate::attachment::{
+    Attachment, AttachmentFlags, AttachmentMesh, AttachmentModel, AttachmentTransform,
+};
+use crate::camera::Camera;
+use crate::character::{Bone, Character};
+use crate::error::EidolonError;
+use crate::renderer::Renderer;
+use crate::texture::{SkinImage, Texture};
+
+/// One attachment of a manifest, before its files are loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CosmeticEntry {
+    /// Model file, relative to the manifest.
+    pub model: String,
+    /// Texture PNG, relative to the manifest; `None` uses the texture embedded in the model.
+    pub texture: Option<String>,
+    pub bone: Bone,
+    pub transform: AttachmentTransform,
+    pub flags: AttachmentFlags,
+}
+
+/// A parsed cosmetics manifest; see the [module docs](self).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CosmeticsManifest {
+    /// Display name of the pack.
+    pub name: Option<String>,
+    /// Entries by attachment name, in sorted order.
+    pub entries: BTreeMap<String, CosmeticEntry>,
+}
+
+impl CosmeticsManifest {
+    pub fn from_json(json: &[u8]) -> Result<Self, EidolonError> {
+        let root: Value = serde_json::from_slice(json)
+            .map_err(|e| EidolonError::model(format!("malformed cosmetics manifest: {e}")))?;
+        let root = root
+            .as_object()
+            .ok_or_else(|| EidolonError::model("cosmetics manifest must be an object"))?;
+        let mut manifest = Self::default();
+        for (key, value) in root {
+            match key.as_str() {
+                "name" => {
+                    let name = value
+                        .as_str()
+                        .ok_or_else(|| EidolonError::model("manifest 'name' must be a string"))?;
+                    manifest.name = Some(name.to_string());
+                }
+                "attachments" => {
+                    let entries = value.as_object().ok_or_else(|| {
+                        EidolonError::model("manifest 'attachments' must be an object")
+                    })?;
+                    for (name, fields) in entries {
+                        let entry = parse_entry(fields).map_err(|e| {
+                            EidolonError::model(format!("attachment '{name}': {e}"))
+                        })?;
+                        manifest.entries.insert(name.clone(), entry);
+                    }
+                }
+                _ => {
+                    return Err(EidolonError::model(format!(
+                        "unknown manifest field '{key}'"
+                    )))
+                }
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+fn parse_entry(fields: &Value) -> Result<CosmeticEntry, String> {
+    let fields = fields.as_object().ok_or("must be an object")?;
+    let mut model = None;
+    let mut texture = None;
+    let mut bone = None;
+    let mut transform = AttachmentTransform::default();
+    let mut flags = AttachmentFlags::default();
+    for (key, value) in fields {
+        let text = || {
+            value
+                .as_str()
+                .ok_or_else(|| format!("'{key}' must be a string"))
+        };
+        let vector = || {
+            let array = value.as_array().filter(|a| a.len() == 3);
+            let components =
+                array.and_then(|a| a.iter().map(Value::as_f64).collect::<Option<Vec<_>>>());
+            components
+                .map(|c| Vector3::new(c[0] as f32, c[1] as f32, c[2] as f32))
+                .ok_or_else(|| format!("'{key}' must be three numbers"))
+        };
+        match key.as_str() {
+            "model" => model = Some(text()?.to_string()),
+            "texture" => texture = Some(text()?.to_string()),
+            "bone" => {
+                bone = Some(parse_bone(text()?).ok_or_else(|| format!("'{value}' is not a bone"))?)
+            }
+            "offset" => transform.offset = vector()?,
+            "rotation" => transform.rotation = vector()?,
+            "scale" => {
+                transform.scale = match value.as_f64() {
+                    Some(scale) => Vector3::new(scale as f32, scale as f32, scale as f32),
+                    None => {
+                        vector().map_err(|_| "'scale' must be a number or three".to_string())?
+                    }
+                }
+            }
+            "render" => flags = parse_flags(value)?,
+            _ => return Err(format!("unknown field '{key}'")),
+        }
+    }
+    Ok(CosmeticEntry {
+        model: model.ok_or("'model' is missing")?,
+        texture,
+        bone: bone.ok_or("'bone' is missing")?,
+        transform,
+        flags,
+    })
+}
+
+fn parse_flags(value: &Value) -> Result<AttachmentFlags, String> {
+    let fields = value.as_object().ok_or("'render' must be an object")?;
+    let mut flags = AttachmentFlags::default();
+    for (key, value) in fields {
+        let flag = match key.as_str() {
+            "hide_overlay" => &mut flags.hide_overlay,
+            "cast_shadow" => &mut flags.cast_shadow,
+            _ => return Err(format!("unknown render flag '{key}'")),
+        };
+        *flag = value
+            .as_bool()
+            .ok_or_else(|| format!("render flag '{key}' must be true or false"))?;
+    }
+    Ok(flags)
+}
+
+/// Bone names as on the command line.
+fn parse_bone(name: &str) -> Option<Bone> {
+    Some(match name {
+        "head" => Bone::Head,
+        "body" => Bone::Body,
+        "right-arm" => Bone::RightArm,
+        "left-arm" => Bone::LeftArm,
+        "right-leg" => Bone::RightLeg,
+        "left-leg" => Bone::LeftLeg,
+        _ => return None,
+    })
+}
+
+/// The attachments of a manifest, uploaded and ready to wear.
+#[derive(Debug, Clone, Default)]
+pub struct CosmeticsCatalog {
+    attachments: BTreeMap<String, Attachment>,
+}
+
+impl CosmeticsCatalog {
+    /// Load the manifest at `path` and everything it lists.
+    pub fn load(renderer: &Renderer, path: &str) -> Result<Self, EidolonError> {
+        let manifest = CosmeticsManifest::from_json(&std::fs::read(path)?)
+            .map_err(|e| EidolonError::model(format!("{path}: {e}")))?;
+        let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+        Self::from_manifest(renderer, &manifest, dir)
+    }
+
+    /// Load and upload the files of `manifest` from `dir`. Entries sharing a model or texture file
+    /// share its upload.
+    pub fn from_manifest(
+        renderer: &Renderer,
+        manifest: &CosmeticsManifest,
+        dir: &Path,
+    ) -> Result<Self, EidolonError> {
+        let mut models: HashMap<&str, (Arc<AttachmentMesh>, Option<Arc<Texture>>)> = HashMap::new();
+        let mut textures: HashMap<&str, Arc<Texture>> = HashMap::new();
+        let mut attachments = BTreeMap::new();
+        for (name, entry) in &manifest.entries {
+            let in_entry =
+                |e: EidolonError| EidolonError::model(format!("attachment '{name}': {e}"));
+            if !models.contains_key(entry.model.as_str()) {
+                let path = pack_path(dir, &entry.model).map_err(in_entry)?;
+                let model =
+                    AttachmentModel::from_file(&path.to_string_lossy()).map_err(in_entry)?;
+                let embedded = match model.embedded_texture() {
+                    Some(png) => Some(Arc::new(
+                        renderer.upload_skin(&SkinImage::from_memory_raw(png).map_err(in_entry)?),
+                    )),
+                    None => None,
+                };
+                let mesh = Arc::new(renderer.upload_attachment(&model));
+                models.insert(&entry.model, (mesh, embedded));
+            }
+            let (mesh, embedded) = &models[entry.model.as_str()];
+            let texture = match &entry.texture {
+                Some(file) => match textures.get(file.as_str()) {
+                    Some(texture) => Arc::clone(texture),
+                    None => {
+                        let path = pack_path(dir, file).map_err(in_entry)?;
+                        let image =
+                            SkinImage::from_file_raw(&path.to_string_lossy()).map_err(in_entry)?;
+                        let texture = Arc::new(renderer.upload_skin(&image));
+                        textures.insert(file, Arc::clone(&texture));
+                        texture
+                    }
+                },
+                None => Arc::clone(embedded.as_ref().ok_or_else(|| {
+                    in_entry(EidolonError::model(
+                        "no 'texture' given and the model embeds none",
+                    ))
+                })?),
+            };
+            let mut attachment =
+                Attachment::new(name.clone(), entry.bone, Arc::clone(mesh), texture);
+            attachment.transform = entry.transform;
+            attachment.flags = entry.flags;
+            attachments.insert(name.clone(), attachment);
+        }
+        Ok(Self { attachments })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Attachment> {
+        self.attachments.get(name)
+    }
+
+    /// Attachment names in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.attachments.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.attachments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.attachments.is_empty()
+    }
+
+    /// Put attachment `name` on `character`. Unknown names are [`EidolonError::NotFound`].
+    pub fn wear(&self, character: &mut Character, name: &str) -> Result<(), EidolonError> {
+        let attachment = self
+            .get(name)
+            .ok_or_else(|| EidolonError::not_found(format!("no attachment named '{name}'")))?;
+        character.attachments.push(attachment.clone());
+        Ok(())
+    }
+
+    /// Render `character` wearing each attachment in turn, on top of what it already wears: one
+    /// preview per catalog entry, in name order.
+    pub fn render_each(
+        &self,
+        renderer: &Renderer,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        size: (u32, u32),
+    ) -> Result<Vec<(String, image::RgbaImage)>, EidolonError> {
+        let mut character = character.clone();
+        let mut previews = Vec::with_capacity(self.len());
+        for (name, attachment) in &self.attachments {
+            character.attachments.push(attachment.clone());
+            let image = renderer.render(&character, skin, camera, size.0, size.1)?;
+            character.attachments.pop();
+            previews.push((name.clone(), image));
+        }
+        Ok(previews)
+    }
+}
+
+/// `file` under `dir`, if it is a relative path that stays inside it.
+fn pack_path(dir: &Path, file: &str) -> Result<PathBuf, EidolonError> {
+    let relative = Path::new(file);
+    if file.is_empty()
+        || !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(EidolonError::invalid_path(format!(
+            "'{file}' must be a relative path without '..'"
+        )));
+    }
+    Ok(dir.join(relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"{
+        "name": "Summer pack",
+        "attachments": {
+            "round_glasses": { "model": "glasses/round.bbmodel", "bone": "head" },
+            "straw_hat": {
+                "model": "hats/straw.obj",
+                "texture": "hats/straw.png",
+                "bone": "head",
+                "offset": [0, 0.05, 0],
+                "rotation": [-5, 0, 0],
+                "scale": 1.5,
+                "render": { "hide_overlay": true, "cast_shadow": false }
+            },
+            "cape": { "model": "cape.obj", "texture": "cape.png", "bone": "body", "scale": [1, 2, 1] }
+        }
+    }"#;
+
+    #[test]
+    fn manifests_parse_with_defaults() {
+        let manifest = CosmeticsManifest::from_json(MANIFEST.as_bytes()).unwrap();
+        assert_eq!(manifest.name.as_deref(), Some("Summer pack"));
+        assert_eq!(
+            manifest.entries.keys().collect::<Vec<_>>(),
+            ["cape", "round_glasses", "straw_hat"]
+        );
+        let glasses = &manifest.entries["round_glasses"];
+        assert_eq!(glasses.texture, None);
+        assert_eq!(glasses.transform, AttachmentTransform::default());
+        assert_eq!(glasses.flags, AttachmentFlags::default());
+        let hat = &manifest.entries["straw_hat"];
+        assert_eq!(hat.transform.scale, Vector3::new(1.5, 1.5, 1.5));
+        assert_eq!(hat.transform.rotation, Vector3::new(-5.0, 0.0, 0.0));
+        assert!(hat.flags.hide_overlay && !hat.flags.cast_shadow);
+        let cape = &manifest.entries["cape"];
+        assert_eq!((cape.bone, cape.transform.scale.y), (Bone::Body, 2.0));
+    }
+
+    #[test]
+    fn bad_manifests_are_rejected() {
+        for json in [
+            r#"[]"#,
+            r#"{"attachments": []}"#,
+            r#"{"packs": {}}"#,
+            r#"{"attachments": {"a": {"bone": "head"}}}"#,
+            r#"{"attachments": {"a": {"model": "a.obj"}}}"#,
+            r#"{"attachments": {"a": {"model": "a.obj", "bone": "tail"}}}"#,
+            r#"{"attachments": {"a": {"model": "a.obj", "bone": "head", "offset": [1, 2]}}}"#,
+            r#"{"attachments": {"a": {"model": "a.obj", "bone": "head", "colour": "red"}}}"#,
+            r#"{"attachments": {"a": {"model": "a.obj", "bone": "head", "render": {"glow": true}}}}"#,
+        ] {
+            assert!(
+                matches!(
+                    CosmeticsManifest::from_json(json.as_bytes()),
+                    Err(EidolonError::Model(_))
+                ),
+                "{json}"
+            );
+        }
+    }
+
+    #[test]
+    fn pack_paths_stay_in_the_pack() {
+        let dir = Path::new("packs/summer");
+        assert_eq!(
+            pack_path(dir, "hats/straw.obj").unwrap(),
+            dir.join("hats/straw.obj")
+        );
+        for file in ["", "../other/hat.obj", "/etc/passwd", "hats/../../x.obj"] {
+            assert!(
+                matches!(pack_path(dir, file), Err(EidolonError::InvalidPath(_))),
+                "{file}"
+            );
+        }
+    }
+}
@@ -1,6 +1,7 @@
 //! Typed error for the public API.
 
 use std::fmt;
+use std::time::Duration;
 
 /// All errors the library can surface.
 #[derive(Debug)]
@@ -17,6 +18,65 @@ pub enum EidolonError {
     Conversion(String),
     /// Path contains null bytes or is otherwise invalid.
     InvalidPath(String),
+    /// Requested output size is zero or larger than the device can render and read back.
+    InvalidDimensions {
+        width: u32,
+        height: u32,
+        /// Largest accepted width and height ([`crate::renderer::Renderer::max_output_size`]).
+        max: u32,
+    },
+    /// A skin source has no skin for the requested player.
+    NotFound(String),
+    /// A skin source failed to answer (transport error, malformed response).
+    Fetch(String),
+    /// A skin source asked the caller to slow down (HTTP 429), optionally saying for how long
+    /// (`Retry-After`).
+    RateLimited { retry_after: Option<Duration> },
+    /// A destination (e.g. a webhook) refused a rendered image or could not be reached.
+    Publish(String),
+    /// A card template or preset file is malformed, or names a missing font or image.
+    Template(String),
+    /// Work stopped early because its [`crate::cancel::CancelToken`] was cancelled or timed out.
+    Cancelled,
+    /// The GPU did not finish a render within the renderer's GPU timeout
+    /// ([`crate::renderer::Renderer::set_gpu_timeout`]).
+    Timeout { after: Duration },
+    /// Untrusted skin bytes were rejected by [`crate::fuzz::parse_skin`] or
+    /// [`crate::fuzz::convert`].
+    InvalidSkin(SkinDefect),
+    /// Code inside [`catch_panic`] panicked; holds the panic message.
+    Panicked(String),
+}
+
+/// What is wrong with rejected skin bytes ([`EidolonError::InvalidSkin`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkinDefect {
+    /// The bytes do not start with the PNG signature.
+    NotPng,
+    /// The data ends before the image does.
+    Truncated,
+    /// Malformed chunks, checksums or image data.
+    Corrupt(String),
+    /// The size is not a skin layout the entrypoint accepts.
+    Layout { width: u32, height: u32 },
+    /// Wider or taller than `max`; rejected before any pixel is decoded.
+    TooLarge { width: u32, height: u32, max: u32 },
+}
+
+impl fmt::Display for SkinDefect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotPng => write!(f, "not a PNG"),
+            Self::Truncated => write!(f, "truncated PNG"),
+            Self::Corrupt(msg) => write!(f, "corrupt PNG: {msg}"),
+            Self::Layout { width, height } => {
+                write!(f, "{width}x{height} is not a skin layout")
+            }
+            Self::TooLarge { width, height, max } => {
+                write!(f, "{width}x{height} is larger than {max}x{max}")
+            }
+        }
+    }
 }
 
 impl fmt::Display for EidolonError {
@@ -28,6 +88,24 @@ impl fmt::Display for EidolonError {
             Self::Texture(msg) => write!(f, "Texture error: {msg}"),
             Self::Conversion(msg) => write!(f, "Conversion error: {msg}"),
             Self::InvalidPath(msg) => write!(f, "Invalid path: {msg}"),
+            Self::InvalidDimensions { width, height, max } => write!(
+                f,
+                "Invalid dimensions: {width}x{height} (width and height must be 1 to {max})"
+            ),
+            Self::NotFound(msg) => write!(f, "Not found: {msg}"),
+            Self::Fetch(msg) => write!(f, "Fetch error: {msg}"),
+            Self::RateLimited {
+                retry_after: Some(after),
+            } => write!(f, "Rate limited: retry after {}s", after.as_secs_f32()),
+            Self::RateLimited { retry_after: None } => write!(f, "Rate limited"),
+            Self::Publish(msg) => write!(f, "Publish error: {msg}"),
+            Self::Template(msg) => write!(f, "Template error: {msg}"),
+            Self::Cancelled => write!(f, "Cancelled"),
+            Self::Timeout { after } => {
+                write!(f, "Timed out: the GPU took over {}s", after.as_secs_f32())
+            }
+            Self::InvalidSkin(defect) => write!(f, "Invalid skin: {defect}"),
+            Self::Panicked(msg) => write!(f, "Panicked: {msg}"),
         }
     }
 }
@@ -68,6 +146,45 @@ impl EidolonError {
     pub fn invalid_path(msg: impl Into<String>) -> Self {
         Self::InvalidPath(msg.into())
     }
+
+    pub fn not_found(msg: impl Into<String>) -> Self {
+        Self::NotFound(msg.into())
+    }
+
+    pub fn fetch(msg: impl Into<String>) -> Self {
+        Self::Fetch(msg.into())
+    }
+
+    pub fn rate_limited(retry_after: Option<Duration>) -> Self {
+        Self::RateLimited { retry_after }
+    }
+
+    pub fn publish(msg: impl Into<String>) -> Self {
+        Self::Publish(msg.into())
+    }
+
+    pub fn template(msg: impl Into<String>) -> Self {
+        Self::Template(msg.into())
+    }
+}
+
+/// Run `f`, turning a panic into [`EidolonError::Panicked`] instead of unwinding into the caller.
+///
+/// Bindings (Python extension modules, C FFI) call the library through this, so one malformed
+/// input that hits a bug fails that call rather than the host process. The one-call APIs
+/// ([`crate::request`]) already do. Anything `f` borrowed mutably (a
+/// [`crate::renderer::Renderer`], say) may be left half-updated and should be discarded after a
+/// panic. The panic hook still runs, so the message is logged to stderr as usual. On `wasm32`
+/// panics abort and cannot be caught.
+pub fn catch_panic<T>(f: impl FnOnce() -> Result<T, EidolonError>) -> Result<T, EidolonError> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(|msg| msg.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic payload".to_string());
+        Err(EidolonError::Panicked(msg))
+    })
 }
 
 #[cfg(test)]
@@ -109,6 +226,63 @@ mod tests {
         assert_eq!(e.to_string(), "Invalid path: null byte in path");
     }
 
+    #[test]
+    fn invalid_dimensions_display() {
+        let e = EidolonError::InvalidDimensions {
+            width: 0,
+            height: 600,
+            max: 8192,
+        };
+        assert_eq!(
+            e.to_string(),
+            "Invalid dimensions: 0x600 (width and height must be 1 to 8192)"
+        );
+    }
+
+    #[test]
+    fn cancelled_display() {
+        assert_eq!(EidolonError::Cancelled.to_string(), "Cancelled");
+    }
+
+    #[test]
+    fn timeout_display() {
+        let e = EidolonError::Timeout {
+            after: Duration::from_millis(2500),
+        };
+        assert_eq!(e.to_string(), "Timed out: the GPU took over 2.5s");
+    }
+
+    #[test]
+    fn invalid_skin_display() {
+        let e = EidolonError::InvalidSkin(SkinDefect::TooLarge {
+            width: 65536,
+            height: 65536,
+            max: 4096,
+        });
+        assert_eq!(e.to_string(), "Invalid skin: 65536x65536 is larger than 4096x4096");
+        let e = EidolonError::InvalidSkin(SkinDefect::Layout {
+            width: 64,
+            height: 48,
+        });
+        assert_eq!(e.to_string(), "Invalid skin: 64x48 is not a skin layout");
+    }
+
+    #[test]
+    fn catch_panic_turns_panics_into_errors() {
+        let e = catch_panic::<()>(|| panic!("bad part {}", 7)).unwrap_err();
+        assert!(matches!(&e, EidolonError::Panicked(msg) if msg == "bad part 7"));
+        assert_eq!(e.to_string(), "Panicked: bad part 7");
+        assert!(matches!(
+            catch_panic::<()>(|| panic!("static")),
+            Err(EidolonError::Panicked(msg)) if msg == "static"
+        ));
+        assert_eq!(catch_panic(|| Ok(3)).unwrap(), 3);
+        assert!(matches!(
+            catch_panic::<()>(|| Err(EidolonError::Cancelled)),
+            Err(EidolonError::Cancelled)
+        ));
+    }
+
     #[test]
     fn from_io_error() {
         let io = std::io::Error::new(std::io::ErrorKind::NotFound, "file missing");
@@ -149,7 +323,7 @@ mod tests {
 
     #[test]
     fn display_all_variants() {
-        let io = std::io::Error::new(std::io::ErrorKind::Other, "oops");
+        let io = std::io::Error::other("oops");
         assert_eq!(
             EidolonError::Io(io).to_string(),
             "I/O error: oops"
@@ -174,6 +348,30 @@ mod tests {
             EidolonError::InvalidPath("nul".into()).to_string(),
             "Invalid path: nul"
         );
+        assert_eq!(
+            EidolonError::not_found("no skin for steve").to_string(),
+            "Not found: no skin for steve"
+        );
+        assert_eq!(
+            EidolonError::fetch("HTTP 500").to_string(),
+            "Fetch error: HTTP 500"
+        );
+        assert_eq!(
+            EidolonError::rate_limited(Some(Duration::from_secs(30))).to_string(),
+            "Rate limited: retry after 30s"
+        );
+        assert_eq!(
+            EidolonError::rate_limited(None).to_string(),
+            "Rate limited"
+        );
+        assert_eq!(
+            EidolonError::publish("HTTP 413").to_string(),
+            "Publish error: HTTP 413"
+        );
+        assert_eq!(
+            EidolonError::template("no size").to_string(),
+            "Template error: no size"
+        );
     }
 
     #[test]
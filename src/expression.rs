@@ -0,0 +1,301 @@
+//! Facial expressions composited onto the skin's face before upload: built-in blinks and winks
+//! found from the face itself, and faces cut from a user-provided [`ExpressionSheet`].
+//!
+//! Expressions edit a copy of the [`SkinImage`]; the original is untouched, so one decoded skin can
+//! be shown with several expressions (see [`crate::animation::ExpressionKey`]).
+
+use image::{imageops, Rgba, RgbaImage};
+
+use crate::error::EidolonError;
+use crate::texture::SkinImage;
+
+/// Top-left of the head's front face (base layer) in 64px atlas coordinates.
+const FACE: (u32, u32) = (8, 8);
+/// Top-left of the hat's front face.
+const HAT_FACE: (u32, u32) = (40, 8);
+/// Face rows the built-in expressions look for eyes in.
+const EYE_ROWS: std::ops::Range<usize> = 4..7;
+/// Face rows sampled for the skin tone (the cheeks, below the eyes).
+const CHEEK_ROWS: std::ops::Range<usize> = 6..8;
+/// Sum of channel differences above which two colors count as different.
+const EYE_CONTRAST: u32 = 60;
+
+/// A change to the face.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    /// Both eyes closed: each eye is painted over with the skin tone, with a darker lid line along
+    /// its bottom row.
+    Blink,
+    /// The player's right eye (on the viewer's left) closed.
+    Wink,
+    /// An 8×8 face (scaled like the skin) drawn over the face; transparent pixels keep the
+    /// skin's own. The hat layer is cleared wherever it is drawn, so it shows.
+    Face(RgbaImage),
+}
+
+impl Expression {
+    /// The built-in expression called `name`: `blink` or `wink`.
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "blink" => Some(Expression::Blink),
+            "wink" => Some(Expression::Wink),
+            _ => None,
+        }
+    }
+
+    /// `skin` with this expression applied.
+    ///
+    /// Blinks and winks find the eyes on rows 4–6 of the face (hat layer included), as mirrored
+    /// pairs of pixels that stand out from the cheeks' most common color; faces without such pairs
+    /// are unchanged. The hat layer is cleared over the eyes.
+    pub fn apply(&self, skin: &SkinImage) -> Result<SkinImage, EidolonError> {
+        let mut rgba = skin.rgba().clone();
+        let scale = (rgba.width() / 64).max(1);
+        match self {
+            Expression::Blink | Expression::Wink => {
+                close_eyes(&mut rgba, scale, *self == Expression::Blink);
+            }
+            Expression::Face(face) => {
+                if face.dimensions() != (8 * scale, 8 * scale) {
+                    return Err(EidolonError::texture(format!(
+                        "expression face is {}x{}, expected {s}x{s} for this skin",
+                        face.width(),
+                        face.height(),
+                        s = 8 * scale
+                    )));
+                }
+                for (x, y, pixel) in face.enumerate_pixels() {
+                    if pixel[3] == 0 {
+                        continue;
+                    }
+                    let base = rgba.get_pixel_mut(FACE.0 * scale + x, FACE.1 * scale + y);
+                    *base = blend(*base, *pixel);
+                    rgba.put_pixel(HAT_FACE.0 * scale + x, HAT_FACE.1 * scale + y, Rgba([0; 4]));
+                }
+            }
+        }
+        SkinImage::from_rgba(rgba)
+    }
+}
+
+/// Close the player's right eye (on the viewer's left), and the left one too when `both`.
+fn close_eyes(rgba: &mut RgbaImage, scale: u32, both: bool) {
+    // The face as seen: hat over base, one sample per 64px texel.
+    let face: Vec<Vec<Rgba<u8>>> = (0..8)
+        .map(|y| {
+            (0..8)
+                .map(|x| {
+                    let base = *rgba.get_pixel((FACE.0 + x) * scale, (FACE.1 + y) * scale);
+                    let hat = *rgba.get_pixel((HAT_FACE.0 + x) * scale, (HAT_FACE.1 + y) * scale);
+                    blend(base, hat)
+                })
+                .collect()
+        })
+        .collect();
+    let Some(skin) = most_common(face[CHEEK_ROWS].iter().flatten()) else {
+        return;
+    };
+    // Eyes are drawn mirrored, so a pixel that stands out from the skin and matches its mirror
+    // image is part of an eye; hair falling over one side of the face is not. The eye is the box
+    // around those pixels, which also takes in unmatched highlights.
+    let mut eye: Option<(usize, usize, usize, usize)> = None;
+    for y in EYE_ROWS {
+        for x in 1..4 {
+            let (left, right) = (face[y][x], face[y][7 - x]);
+            if contrast(left, skin) > EYE_CONTRAST && contrast(left, right) <= EYE_CONTRAST {
+                eye = Some(match eye {
+                    None => (x, x, y, y),
+                    Some((x0, x1, y0, y1)) => (x0.min(x), x1.max(x), y0.min(y), y1.max(y)),
+                });
+            }
+        }
+    }
+    let Some((x0, x1, y0, y1)) = eye else {
+        return;
+    };
+    let lid = Rgba([
+        (u32::from(skin[0]) * 70 / 100) as u8,
+        (u32::from(skin[1]) * 70 / 100) as u8,
+        (u32::from(skin[2]) * 70 / 100) as u8,
+        255,
+    ]);
+    let mut columns: Vec<usize> = (x0..=x1).collect();
+    if both {
+        columns.extend((x0..=x1).map(|x| 7 - x));
+    }
+    for x in columns {
+        for y in y0..=y1 {
+            let color = if y == y1 { lid } else { skin };
+            for (dx, dy) in (0..scale).flat_map(|dx| (0..scale).map(move |dy| (dx, dy))) {
+                let (x, y) = (x as u32 * scale + dx, y as u32 * scale + dy);
+                rgba.put_pixel(FACE.0 * scale + x, FACE.1 * scale + y, color);
+                rgba.put_pixel(HAT_FACE.0 * scale + x, HAT_FACE.1 * scale + y, Rgba([0; 4]));
+            }
+        }
+    }
+}
+
+/// Sum of the RGB channel differences.
+fn contrast(a: Rgba<u8>, b: Rgba<u8>) -> u32 {
+    (0..3).map(|c| u32::from(a[c].abs_diff(b[c]))).sum()
+}
+
+/// The most common opaque color.
+fn most_common<'a>(pixels: impl Iterator<Item = &'a Rgba<u8>>) -> Option<Rgba<u8>> {
+    let mut counts: Vec<(Rgba<u8>, u32)> = Vec::new();
+    for &pixel in pixels.filter(|p| p[3] == 255) {
+        match counts.iter_mut().find(|(color, _)| *color == pixel) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((pixel, 1)),
+        }
+    }
+    // Ties go to the first color seen.
+    counts
+        .iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|&(color, _)| color)
+}
+
+/// `top` over `bottom` ("source over").
+fn blend(bottom: Rgba<u8>, top: Rgba<u8>) -> Rgba<u8> {
+    let mut out = RgbaImage::from_pixel(1, 1, bottom);
+    imageops::overlay(&mut out, &RgbaImage::from_pixel(1, 1, top), 0, 0);
+    *out.get_pixel(0, 0)
+}
+
+/// Faces for [`Expression::Face`], laid out left to right in one image: a strip of 8×8 cells
+/// (16×16 for 128px skins, and so on), each a full face or just the pixels it changes.
+#[derive(Debug, Clone)]
+pub struct ExpressionSheet {
+    faces: Vec<RgbaImage>,
+}
+
+impl ExpressionSheet {
+    /// Split `sheet` into faces `cell` pixels square.
+    pub fn from_image(sheet: &RgbaImage, cell: u32) -> Result<Self, EidolonError> {
+        if cell == 0
+            || sheet.height() != cell
+            || !sheet.width().is_multiple_of(cell)
+            || sheet.width() == 0
+        {
+            return Err(EidolonError::texture(format!(
+                "expression sheet must be one row of {cell}x{cell} faces, got {}x{}",
+                sheet.width(),
+                sheet.height()
+            )));
+        }
+        let faces = (0..sheet.width() / cell)
+            .map(|i| imageops::crop_imm(sheet, i * cell, 0, cell, cell).to_image())
+            .collect();
+        Ok(Self { faces })
+    }
+
+    /// Decode a sheet PNG; its height is the face size.
+    pub fn from_memory(bytes: &[u8]) -> Result<Self, EidolonError> {
+        let image = image::load_from_memory(bytes)
+            .map_err(|e| EidolonError::texture(format!("failed to decode expression sheet: {e}")))?
+            .to_rgba8();
+        Self::from_image(&image, image.height())
+    }
+
+    pub fn len(&self) -> usize {
+        self.faces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.faces.is_empty()
+    }
+
+    /// Face `index` (0-based, left to right).
+    pub fn get(&self, index: usize) -> Option<Expression> {
+        self.faces.get(index).cloned().map(Expression::Face)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SKIN: Rgba<u8> = Rgba([200, 150, 120, 255]);
+    const EYE: Rgba<u8> = Rgba([20, 40, 200, 255]);
+    const HAIR: Rgba<u8> = Rgba([60, 40, 30, 255]);
+
+    /// A skin-colored face with two-row eyes in columns 1–2 and 5–6 of rows 4–5, a highlight
+    /// on one side only, and a lock of hair next to the left eye.
+    fn face_skin() -> SkinImage {
+        let mut rgba = RgbaImage::new(64, 64);
+        for y in 0..8 {
+            for x in 0..8 {
+                rgba.put_pixel(FACE.0 + x, FACE.1 + y, SKIN);
+            }
+        }
+        for (x, y) in [(1, 4), (2, 4), (5, 4), (6, 4), (2, 5), (5, 5)] {
+            rgba.put_pixel(FACE.0 + x, FACE.1 + y, EYE);
+        }
+        rgba.put_pixel(FACE.0 + 1, FACE.1 + 5, Rgba([255; 4]));
+        rgba.put_pixel(FACE.0 + 3, FACE.1 + 4, HAIR);
+        SkinImage::from_rgba(rgba).unwrap()
+    }
+
+    fn face_pixel(skin: &SkinImage, x: u32, y: u32) -> Rgba<u8> {
+        *skin.rgba().get_pixel(FACE.0 + x, FACE.1 + y)
+    }
+
+    #[test]
+    fn blinks_close_both_eyes_and_winks_one() {
+        let skin = face_skin();
+        let lid = Rgba([140, 105, 84, 255]);
+
+        let blink = Expression::Blink.apply(&skin).unwrap();
+        for x in [1, 2, 5, 6] {
+            assert_eq!(face_pixel(&blink, x, 4), SKIN);
+            assert_eq!(face_pixel(&blink, x, 5), lid);
+        }
+        // Hair on one side only is not an eye.
+        assert_eq!(face_pixel(&blink, 3, 4), HAIR);
+
+        let wink = Expression::Wink.apply(&skin).unwrap();
+        assert_eq!(face_pixel(&wink, 1, 5), lid);
+        assert_eq!(face_pixel(&wink, 5, 5), EYE);
+        // The source skin is untouched.
+        assert_eq!(face_pixel(&skin, 1, 4), EYE);
+        assert_ne!(blink.content_hash(), skin.content_hash());
+    }
+
+    #[test]
+    fn eyes_on_the_hat_layer_close_too() {
+        let mut rgba = face_skin().rgba().clone();
+        rgba.put_pixel(FACE.0 + 2, FACE.1 + 4, SKIN);
+        rgba.put_pixel(HAT_FACE.0 + 2, HAT_FACE.1 + 4, EYE);
+        let blink = Expression::Blink
+            .apply(&SkinImage::from_rgba(rgba).unwrap())
+            .unwrap();
+        assert_eq!(blink.rgba().get_pixel(HAT_FACE.0 + 2, HAT_FACE.1 + 4)[3], 0);
+        assert_ne!(face_pixel(&blink, 2, 5), EYE);
+    }
+
+    #[test]
+    fn sheet_faces_draw_over_the_face_and_clear_the_hat() {
+        let mut sheet = RgbaImage::new(16, 8);
+        let mouth = Rgba([90, 20, 20, 255]);
+        sheet.put_pixel(8 + 3, 6, mouth);
+        let sheet = ExpressionSheet::from_image(&sheet, 8).unwrap();
+        assert_eq!(sheet.len(), 2);
+        assert!(sheet.get(2).is_none());
+
+        let mut rgba = face_skin().rgba().clone();
+        rgba.put_pixel(HAT_FACE.0 + 3, HAT_FACE.1 + 6, SKIN);
+        let skin = SkinImage::from_rgba(rgba).unwrap();
+        let smile = sheet.get(1).unwrap().apply(&skin).unwrap();
+        assert_eq!(*smile.rgba().get_pixel(FACE.0 + 3, FACE.1 + 6), mouth);
+        assert_eq!(smile.rgba().get_pixel(HAT_FACE.0 + 3, HAT_FACE.1 + 6)[3], 0);
+        // The empty first face changes nothing.
+        let same = sheet.get(0).unwrap().apply(&skin).unwrap();
+        assert_eq!(same.content_hash(), skin.content_hash());
+
+        assert!(ExpressionSheet::from_image(&RgbaImage::new(12, 8), 8).is_err());
+        let hd = SkinImage::from_rgba(RgbaImage::new(128, 128)).unwrap();
+        assert!(sheet.get(1).unwrap().apply(&hd).is_err());
+    }
+}
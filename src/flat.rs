@@ -0,0 +1,188 @@
+//! Flat 2D skin views cut straight from the atlas, without the GPU: the face ([`face`]) and a
+//! front-view paper doll ([`paper_doll`]), plus an SVG export ([`to_svg`]) that keeps them crisp
+//! at any zoom.
+//!
+//! Views are at the skin's own resolution (one pixel per texel; HD skins give larger images).
+
+use std::fmt::Write;
+
+use image::{imageops, RgbaImage};
+
+use crate::character::SkinType;
+use crate::texture::SkinImage;
+
+/// Front face of a body-part box in 64px atlas coordinates: `(x, y, width, height)`.
+type FrontRect = (u32, u32, u32, u32);
+
+/// Head front, and the hat in front of it.
+const HEAD: [FrontRect; 2] = [(8, 8, 8, 8), (40, 8, 8, 8)];
+
+/// Paper-doll parts: base and overlay fronts at their position in the 16×32 doll. The player's
+/// right side is on the viewer's left. Arms are 4 wide here; see [`doll_parts`] for slim.
+const DOLL: [(FrontRect, FrontRect, (u32, u32)); 6] = [
+    ((8, 8, 8, 8), (40, 8, 8, 8), (4, 0)),       // head, hat
+    ((20, 20, 8, 12), (20, 36, 8, 12), (4, 8)),  // body, jacket
+    ((44, 20, 4, 12), (44, 36, 4, 12), (0, 8)),  // right arm, sleeve
+    ((36, 52, 4, 12), (52, 52, 4, 12), (12, 8)), // left arm, sleeve
+    ((4, 20, 4, 12), (4, 36, 4, 12), (4, 20)),   // right leg, pants
+    ((20, 52, 4, 12), (4, 52, 4, 12), (8, 20)),  // left leg, pants
+];
+
+/// Width and height of [`paper_doll`] images of a 64px skin.
+pub const DOLL_SIZE: (u32, u32) = (16, 32);
+
+/// The 8×8 face (scaled for HD skins), with the hat layer on top when `overlay` is set.
+pub fn face(skin: &SkinImage, overlay: bool) -> RgbaImage {
+    let scale = (skin.rgba().width() / 64).max(1);
+    let mut out = RgbaImage::new(8 * scale, 8 * scale);
+    let layers = if overlay { &HEAD[..] } else { &HEAD[..1] };
+    for &rect in layers {
+        blit(&mut out, skin, rect, (0, 0), scale);
+    }
+    out
+}
+
+/// The front of the whole character, laid out flat in [`DOLL_SIZE`] (scaled for HD skins). Slim
+/// arms are 3 wide and sit against the body.
+pub fn paper_doll(skin: &SkinImage, skin_type: SkinType, overlay: bool) -> RgbaImage {
+    let scale = (skin.rgba().width() / 64).max(1);
+    let mut out = RgbaImage::new(DOLL_SIZE.0 * scale, DOLL_SIZE.1 * scale);
+    let parts = doll_parts(skin_type);
+    for (base, _, at) in parts {
+        blit(&mut out, skin, base, at, scale);
+    }
+    if overlay {
+        for (_, layer, at) in parts {
+            blit(&mut out, skin, layer, at, scale);
+        }
+    }
+    out
+}
+
+fn doll_parts(skin_type: SkinType) -> [(FrontRect, FrontRect, (u32, u32)); 6] {
+    let mut parts = DOLL;
+    if skin_type == SkinType::Slim {
+        for (base, layer, at) in &mut parts[2..4] {
+            base.2 = 3;
+            layer.2 = 3;
+            // The right arm moves one pixel towards the body.
+            if at.0 == 0 {
+                at.0 = 1;
+            }
+        }
+    }
+    parts
+}
+
+/// Draw atlas rect `(x, y, w, h)` (64px coordinates) at `at` (doll coordinates), alpha-blended.
+fn blit(
+    out: &mut RgbaImage,
+    skin: &SkinImage,
+    (x, y, w, h): FrontRect,
+    at: (u32, u32),
+    scale: u32,
+) {
+    let part = imageops::crop_imm(skin.rgba(), x * scale, y * scale, w * scale, h * scale);
+    imageops::overlay(
+        out,
+        &*part,
+        i64::from(at.0 * scale),
+        i64::from(at.1 * scale),
+    );
+}
+
+/// An SVG with one `rect` per run of equal pixels in a row; transparent pixels are left out.
+/// `pixel_size` sets the `width`/`height` attributes (the `viewBox` stays in pixels), and
+/// `crispEdges` keeps browsers from blurring the texels.
+pub fn to_svg(image: &RgbaImage, pixel_size: u32) -> String {
+    let (width, height) = image.dimensions();
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         viewBox=\"0 0 {width} {height}\" shape-rendering=\"crispEdges\">\n",
+        width * pixel_size,
+        height * pixel_size
+    );
+    for y in 0..height {
+        let mut x = 0;
+        while x < width {
+            let pixel = image.get_pixel(x, y).0;
+            let run = (x..width)
+                .take_while(|&end| image.get_pixel(end, y).0 == pixel)
+                .count() as u32;
+            let [r, g, b, a] = pixel;
+            if a > 0 {
+                let _ = write!(
+                    svg,
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{run}\" height=\"1\" fill=\"#{r:02x}{g:02x}{b:02x}\""
+                );
+                if a < 255 {
+                    let _ = write!(svg, " fill-opacity=\"{:.3}\"", f32::from(a) / 255.0);
+                }
+                svg.push_str("/>\n");
+            }
+            x += run;
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// A 64px skin where every texel encodes its own coordinates.
+    fn coordinate_skin(scale: u32) -> SkinImage {
+        SkinImage::from_rgba(RgbaImage::from_fn(64 * scale, 64 * scale, |x, y| {
+            Rgba([(x / scale) as u8, (y / scale) as u8, 0, 255])
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn face_is_the_head_front_under_the_hat() {
+        let skin = coordinate_skin(1);
+        let base = face(&skin, false);
+        assert_eq!(base.dimensions(), (8, 8));
+        assert_eq!(base.get_pixel(0, 0).0, [8, 8, 0, 255]);
+        // The opaque hat covers the face.
+        assert_eq!(face(&skin, true).get_pixel(7, 7).0, [47, 15, 0, 255]);
+        assert_eq!(face(&coordinate_skin(2), false).dimensions(), (16, 16));
+    }
+
+    #[test]
+    fn paper_doll_places_fronts_mirrored() {
+        let skin = coordinate_skin(1);
+        let doll = paper_doll(&skin, SkinType::Classic, false);
+        assert_eq!(doll.dimensions(), DOLL_SIZE);
+        // The player's right arm on the viewer's left, left leg on the right.
+        assert_eq!(doll.get_pixel(0, 8).0, [44, 20, 0, 255]);
+        assert_eq!(doll.get_pixel(15, 8).0, [39, 52, 0, 255]);
+        assert_eq!(doll.get_pixel(8, 20).0, [20, 52, 0, 255]);
+
+        let slim = paper_doll(&skin, SkinType::Slim, false);
+        assert_eq!(slim.get_pixel(0, 8)[3], 0);
+        assert_eq!(slim.get_pixel(1, 8).0, [44, 20, 0, 255]);
+        assert_eq!(slim.get_pixel(14, 8).0, [38, 52, 0, 255]);
+        assert_eq!(slim.get_pixel(15, 8)[3], 0);
+    }
+
+    #[test]
+    fn svg_merges_runs_and_skips_transparency() {
+        let mut image = RgbaImage::new(4, 2);
+        for x in 0..3 {
+            image.put_pixel(x, 0, Rgba([255, 0, 0, 255]));
+        }
+        image.put_pixel(3, 1, Rgba([0, 0, 255, 128]));
+        let svg = to_svg(&image, 10);
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\" height=\"20\" viewBox=\"0 0 4 2\""
+        ));
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"3\" height=\"1\" fill=\"#ff0000\"/>"));
+        assert!(svg.contains(
+            "<rect x=\"3\" y=\"1\" width=\"1\" height=\"1\" fill=\"#0000ff\" fill-opacity=\"0.502\"/>"
+        ));
+        assert_eq!(svg.matches("<rect").count(), 2);
+    }
+}
@@ -0,0 +1,197 @@
+//! Entrypoints for untrusted skin bytes: [`parse_skin`] and [`convert`] need no GPU, reject
+//! anything malformed with an [`EidolonError::InvalidSkin`] instead of panicking, and take one
+//! byte slice each, so they double as `cargo fuzz` targets.
+//!
+//! The PNG header is read before any pixel: sizes other than 64·k × 64·k or 64·k × 32·k, and
+//! skins wider than [`MAX_UPLOAD_WIDTH`], are rejected without decoding, so a small file claiming
+//! a huge image costs nothing. Decoded pixels are converted to sRGB ([`crate::color`]).
+
+use std::io::ErrorKind;
+
+use image::{DynamicImage, ImageFormat, RgbaImage};
+
+use crate::color;
+use crate::converter::single2double_in_place;
+use crate::error::{EidolonError, SkinDefect};
+use crate::texture::SkinImage;
+use crate::upload::{encode, MAX_UPLOAD_WIDTH};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Decode skin PNG bytes into a double-layer [`SkinImage`], converting single-layer skins, like
+/// [`SkinImage::from_memory`] but hardened for untrusted input: only PNGs of a skin layout up to
+/// [`MAX_UPLOAD_WIDTH`] wide are accepted.
+pub fn parse_skin(bytes: &[u8]) -> Result<SkinImage, EidolonError> {
+    let (width, height) = read_header(bytes)?;
+    if height != width && height * 2 != width {
+        return Err(invalid(SkinDefect::Layout { width, height }));
+    }
+    let rgba = decode(bytes)?;
+    SkinImage::from_image(&DynamicImage::ImageRgba8(rgba))
+}
+
+/// Convert a single-layer (64·k × 32·k) skin PNG to a double-layer one
+/// ([`single2double_in_place`]), returned as plain RGBA8 PNG bytes. Square skins are a
+/// [`SkinDefect::Layout`].
+pub fn convert(bytes: &[u8]) -> Result<Vec<u8>, EidolonError> {
+    let (width, height) = read_header(bytes)?;
+    if height * 2 != width {
+        return Err(invalid(SkinDefect::Layout { width, height }));
+    }
+    let mut rgba = decode(bytes)?;
+    single2double_in_place(&mut rgba)?;
+    encode(&rgba, &[])
+}
+
+fn invalid(defect: SkinDefect) -> EidolonError {
+    EidolonError::InvalidSkin(defect)
+}
+
+/// The size in the `IHDR` chunk of `bytes`, if it is at most [`MAX_UPLOAD_WIDTH`] and a multiple
+/// of 64 wide; the height is checked by the caller. Nothing past the size is read.
+fn read_header(bytes: &[u8]) -> Result<(u32, u32), EidolonError> {
+    if !bytes.starts_with(PNG_SIGNATURE) {
+        return Err(invalid(SkinDefect::NotPng));
+    }
+    // Signature, then the IHDR chunk's length, type, width and height.
+    let header = bytes.get(8..24).ok_or(invalid(SkinDefect::Truncated))?;
+    if &header[4..8] != b"IHDR" {
+        return Err(invalid(SkinDefect::Corrupt("the first chunk is not IHDR".into())));
+    }
+    let be_u32 = |at: usize| {
+        u32::from_be_bytes([header[at], header[at + 1], header[at + 2], header[at + 3]])
+    };
+    let (width, height) = (be_u32(8), be_u32(12));
+    if width > MAX_UPLOAD_WIDTH || height > MAX_UPLOAD_WIDTH {
+        return Err(invalid(SkinDefect::TooLarge {
+            width,
+            height,
+            max: MAX_UPLOAD_WIDTH,
+        }));
+    }
+    if width == 0 || !width.is_multiple_of(64) {
+        return Err(invalid(SkinDefect::Layout { width, height }));
+    }
+    Ok((width, height))
+}
+
+/// The pixels of `bytes`, whose header [`read_header`] accepted, in sRGB.
+fn decode(bytes: &[u8]) -> Result<RgbaImage, EidolonError> {
+    let image = image::load_from_memory_with_format(bytes, ImageFormat::Png).map_err(|e| match e {
+        image::ImageError::IoError(e) if e.kind() == ErrorKind::UnexpectedEof => {
+            invalid(SkinDefect::Truncated)
+        }
+        e => invalid(SkinDefect::Corrupt(e.to_string())),
+    })?;
+    let mut rgba = image.to_rgba8();
+    color::normalize_png(bytes, &mut rgba);
+    Ok(rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random numbers (64-bit LCG) for mutations.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> u64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            self.0 >> 33
+        }
+    }
+
+    fn defect(result: Result<impl Sized, EidolonError>) -> SkinDefect {
+        match result {
+            Err(EidolonError::InvalidSkin(defect)) => defect,
+            Err(e) => panic!("unstructured error: {e}"),
+            Ok(_) => panic!("accepted"),
+        }
+    }
+
+    /// A PNG whose header claims `width`×`height`, with no image data.
+    fn header_only(width: u32, height: u32) -> Vec<u8> {
+        let mut ihdr = b"IHDR".to_vec();
+        ihdr.extend_from_slice(&width.to_be_bytes());
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+        let mut crc = flate2::Crc::new();
+        crc.update(&ihdr);
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend_from_slice(&13u32.to_be_bytes());
+        png.extend_from_slice(&ihdr);
+        png.extend_from_slice(&crc.sum().to_be_bytes());
+        png
+    }
+
+    #[test]
+    fn skins_parse_and_legacy_skins_convert() {
+        let skin = std::fs::read("resources/bingling_sama.png").unwrap();
+        assert_eq!(parse_skin(&skin).unwrap().rgba().dimensions(), (64, 64));
+        assert_eq!(defect(convert(&skin)), SkinDefect::Layout { width: 64, height: 64 });
+
+        let legacy = std::fs::read("resources/SSSSSteven.png").unwrap();
+        assert_eq!(parse_skin(&legacy).unwrap().rgba().dimensions(), (64, 64));
+        let converted = convert(&legacy).unwrap();
+        let parsed = parse_skin(&converted).unwrap();
+        assert_eq!(parsed.rgba(), parse_skin(&legacy).unwrap().rgba());
+    }
+
+    #[test]
+    fn absurd_sizes_are_rejected_before_decoding() {
+        assert_eq!(
+            defect(parse_skin(&header_only(1 << 20, 1 << 20))),
+            SkinDefect::TooLarge {
+                width: 1 << 20,
+                height: 1 << 20,
+                max: MAX_UPLOAD_WIDTH
+            }
+        );
+        assert!(matches!(
+            defect(convert(&header_only(64, u32::MAX))),
+            SkinDefect::TooLarge { .. }
+        ));
+        for (width, height) in [(64, 48), (100, 100), (0, 0), (64, 128)] {
+            assert_eq!(
+                defect(parse_skin(&header_only(width, height))),
+                SkinDefect::Layout { width, height }
+            );
+        }
+        // The header passes; the missing image data does not.
+        assert_eq!(defect(parse_skin(&header_only(64, 64))), SkinDefect::Truncated);
+        assert_eq!(defect(parse_skin(b"GIF89a")), SkinDefect::NotPng);
+        assert_eq!(defect(parse_skin(&[])), SkinDefect::NotPng);
+    }
+
+    #[test]
+    fn truncated_and_mutated_pngs_never_panic() {
+        let skin = std::fs::read("resources/bingling_sama.png").unwrap();
+        for len in 0..skin.len() {
+            // Decoders accept a PNG cut off after its image data, so only errors are checked.
+            if let Err(e) = parse_skin(&skin[..len]) {
+                assert!(matches!(e, EidolonError::InvalidSkin(_)), "{len} bytes: {e}");
+            }
+        }
+        assert_eq!(defect(parse_skin(&skin[..skin.len() / 2])), SkinDefect::Truncated);
+        assert_eq!(defect(parse_skin(&skin[..20])), SkinDefect::Truncated);
+
+        let legacy = std::fs::read("resources/SSSSSteven.png").unwrap();
+        let mut rng = Lcg(2002);
+        for round in 0..600 {
+            let mut mutated = if round % 2 == 0 { skin.clone() } else { legacy.clone() };
+            for _ in 0..1 + rng.next() % 4 {
+                let at = rng.next() as usize % mutated.len();
+                mutated[at] = rng.next() as u8;
+            }
+            for result in [parse_skin(&mutated).map(|_| ()), convert(&mutated).map(|_| ())] {
+                if let Err(e) = result {
+                    assert!(matches!(e, EidolonError::InvalidSkin(_)), "round {round}: {e}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,304 @@
+//! Player-head items: the skin of a head is a base64 `textures` value stored in the item's NBT
+//! (`SkullOwner.Properties.textures[0].Value` before 1.20.5, the `minecraft:profile` component
+//! after). [`PlayerHead`] pulls it out of whatever form a plugin stored: SNBT text, binary NBT
+//! (optionally gzipped), component JSON or the bare base64 value.
+
+use std::io::Read;
+
+use crate::error::EidolonError;
+use crate::profile::PlayerTextures;
+
+/// Host that serves every skin the game itself assigns to heads.
+#[cfg(not(target_arch = "wasm32"))]
+const MOJANG_TEXTURES: &str = "textures.minecraft.net";
+
+/// Largest inflated size of gzipped head NBT; item data is a few kilobytes at most.
+const MAX_NBT_BYTES: u64 = 1 << 20;
+
+/// The textures of a player head.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerHead {
+    /// The base64 `textures` value as stored.
+    pub value: String,
+    pub textures: PlayerTextures,
+}
+
+impl PlayerHead {
+    /// Find the `textures` value in head data: SNBT (`{SkullOwner:{...}}`,
+    /// `[minecraft:profile=...]`), binary NBT, JSON, or the base64 value itself. The first string that decodes to textures
+    /// with a skin URL wins.
+    pub fn from_nbt(data: &[u8]) -> Result<Self, EidolonError> {
+        let strings = match data {
+            // gzip
+            [0x1f, 0x8b, ..] => {
+                let mut nbt = Vec::new();
+                flate2::read::GzDecoder::new(data)
+                    .take(MAX_NBT_BYTES + 1)
+                    .read_to_end(&mut nbt)
+                    .map_err(|e| EidolonError::texture(format!("corrupt gzipped NBT: {e}")))?;
+                if nbt.len() as u64 > MAX_NBT_BYTES {
+                    return Err(EidolonError::texture(format!(
+                        "gzipped NBT inflates past {MAX_NBT_BYTES} bytes"
+                    )));
+                }
+                nbt_strings(&nbt)
+            }
+            // A binary compound tag.
+            [10, 0, ..] => nbt_strings(data),
+            text => text_strings(&String::from_utf8_lossy(text)),
+        };
+        strings
+            .into_iter()
+            .find_map(|value| {
+                let textures = PlayerTextures::from_property(&value).ok()?;
+                textures
+                    .skin_url
+                    .is_some()
+                    .then_some(Self { value, textures })
+            })
+            .ok_or_else(|| EidolonError::not_found("no head textures in NBT data"))
+    }
+
+    /// The skin URL; always present on heads from [`PlayerHead::from_nbt`].
+    pub fn skin_url(&self) -> Option<&str> {
+        self.textures.skin_url.as_deref()
+    }
+
+    /// Download the skin. Head data is unsigned and any plugin can write it, so only URLs on
+    /// Mojang's texture server are fetched; check [`PlayerHead::skin_url`] yourself to allow
+    /// others.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn fetch(
+        &self,
+        http: &impl crate::provider::HttpGet,
+    ) -> Result<crate::provider::SkinData, EidolonError> {
+        let url = self
+            .skin_url()
+            .ok_or_else(|| EidolonError::not_found("head has no skin"))?;
+        let host = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .and_then(|rest| rest.split('/').next());
+        if host != Some(MOJANG_TEXTURES) {
+            return Err(EidolonError::fetch(format!(
+                "head skin {url} is not on {MOJANG_TEXTURES}"
+            )));
+        }
+        let bytes = http
+            .get(url)?
+            .ok_or_else(|| EidolonError::not_found(format!("head skin {url} is gone")))?;
+        Ok(crate::provider::SkinData {
+            bytes,
+            skin_type: Some(self.textures.skin_type),
+            signature: crate::profile::SignatureStatus::NotChecked,
+        })
+    }
+}
+
+/// Quoted strings in SNBT or JSON (single or double quotes, backslash escapes), or the whole text
+/// when it is a bare value.
+fn text_strings(text: &str) -> Vec<String> {
+    let mut strings = vec![text.trim().to_string()];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' && c != '\'' {
+            continue;
+        }
+        let mut string = String::new();
+        while let Some(next) = chars.next() {
+            match next {
+                '\\' => string.extend(chars.next()),
+                quote if quote == c => break,
+                other => string.push(other),
+            }
+        }
+        strings.push(string);
+    }
+    strings
+}
+
+/// Every string tag in binary NBT, in file order. Stops quietly at malformed data.
+fn nbt_strings(nbt: &[u8]) -> Vec<String> {
+    struct Reader<'a> {
+        data: &'a [u8],
+        strings: Vec<String>,
+    }
+
+    impl Reader<'_> {
+        fn take(&mut self, n: usize) -> Option<&[u8]> {
+            let (taken, rest) = (self.data.len() >= n).then(|| self.data.split_at(n))?;
+            self.data = rest;
+            Some(taken)
+        }
+
+        fn int(&mut self, n: usize) -> Option<usize> {
+            let bytes = self.take(n)?;
+            let value = bytes.iter().fold(0u64, |v, &b| v << 8 | u64::from(b));
+            // Negative lengths read as huge and fail on the next `take`.
+            usize::try_from(value).ok()
+        }
+
+        fn string(&mut self) -> Option<String> {
+            let len = self.int(2)?;
+            Some(String::from_utf8_lossy(self.take(len)?).into_owned())
+        }
+
+        fn payload(&mut self, tag: u8, depth: usize) -> Option<()> {
+            if depth > 64 {
+                return None;
+            }
+            match tag {
+                1 => drop(self.take(1)?),
+                2 => drop(self.take(2)?),
+                3 | 5 => drop(self.take(4)?),
+                4 | 6 => drop(self.take(8)?),
+                7 => {
+                    let len = self.int(4)?;
+                    self.take(len)?;
+                }
+                8 => {
+                    let string = self.string()?;
+                    self.strings.push(string);
+                }
+                9 => {
+                    let element = self.take(1)?[0];
+                    for _ in 0..self.int(4)? {
+                        self.payload(element, depth + 1)?;
+                    }
+                }
+                10 => loop {
+                    let tag = self.take(1)?[0];
+                    if tag == 0 {
+                        break;
+                    }
+                    self.string()?;
+                    self.payload(tag, depth + 1)?;
+                },
+                11 => {
+                    let len = self.int(4)?;
+                    self.take(len.checked_mul(4)?)?;
+                }
+                12 => {
+                    let len = self.int(4)?;
+                    self.take(len.checked_mul(8)?)?;
+                }
+                _ => return None,
+            }
+            Some(())
+        }
+    }
+
+    let mut reader = Reader {
+        data: nbt,
+        strings: Vec::new(),
+    };
+    // Root: tag id, name, payload.
+    let _ = (|| {
+        let tag = reader.take(1)?[0];
+        reader.string()?;
+        reader.payload(tag, 0)
+    })();
+    reader.strings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character::SkinType;
+
+    /// A `textures` value with a slim skin at `http://textures.example/skin`.
+    const TEXTURES: &str = "eyJ0aW1lc3RhbXAiOjAsInByb2ZpbGVJZCI6IjA2OWE3OWY0NDRlOTQ3MjZhNWJlZmNhOTBlMzhhYWY1IiwicHJvZmlsZU5hbWUiOiJOb3RjaCIsInRleHR1cmVzIjp7IlNLSU4iOnsidXJsIjoiaHR0cDovL3RleHR1cmVzLmV4YW1wbGUvc2tpbiIsIm1ldGFkYXRhIjp7Im1vZGVsIjoic2xpbSJ9fX19";
+
+    #[test]
+    fn snbt_components_and_bare_values_decode() {
+        let legacy = format!(
+            r#"{{SkullOwner:{{Id:[I;1,2,3,4],Name:"Notch",Properties:{{textures:[{{Value:"{TEXTURES}"}}]}}}}}}"#
+        );
+        let component = format!(
+            r#"[minecraft:profile={{name:"Notch",properties:[{{name:'textures',value:'{TEXTURES}'}}]}}]"#
+        );
+        for data in [legacy.as_str(), component.as_str(), TEXTURES] {
+            let head = PlayerHead::from_nbt(data.as_bytes()).expect(data);
+            assert_eq!(head.value, TEXTURES);
+            assert_eq!(head.skin_url(), Some("http://textures.example/skin"));
+            assert_eq!(head.textures.skin_type, SkinType::Slim);
+        }
+        assert!(matches!(
+            PlayerHead::from_nbt(br#"{SkullOwner:{Name:"Notch"}}"#),
+            Err(EidolonError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn binary_nbt_decodes_plain_and_gzipped() {
+        let string = |s: &str| [&(s.len() as u16).to_be_bytes()[..], s.as_bytes()].concat();
+        let mut nbt = vec![10];
+        nbt.extend(string(""));
+        nbt.push(10);
+        nbt.extend(string("SkullOwner"));
+        nbt.push(11);
+        nbt.extend(string("Id"));
+        nbt.extend([0, 0, 0, 1, 0, 0, 0, 7]);
+        nbt.push(10);
+        nbt.extend(string("Properties"));
+        nbt.push(9);
+        nbt.extend(string("textures"));
+        nbt.extend([10, 0, 0, 0, 1]);
+        nbt.push(8);
+        nbt.extend(string("Value"));
+        nbt.extend(string(TEXTURES));
+        nbt.extend([0, 0, 0, 0]);
+        assert_eq!(PlayerHead::from_nbt(&nbt).unwrap().value, TEXTURES);
+
+        let mut gzipped = Vec::new();
+        let mut encoder =
+            flate2::write::GzEncoder::new(&mut gzipped, flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, &nbt).unwrap();
+        encoder.finish().unwrap();
+        assert_eq!(PlayerHead::from_nbt(&gzipped).unwrap().value, TEXTURES);
+
+        assert!(PlayerHead::from_nbt(&nbt[..nbt.len() - 20]).is_err());
+        assert!(matches!(
+            PlayerHead::from_nbt(&[0x1f, 0x8b, 0, 0]),
+            Err(EidolonError::Texture(_))
+        ));
+    }
+
+    #[test]
+    fn gzip_bombs_are_refused() {
+        let mut gzipped = Vec::new();
+        let mut encoder = flate2::write::GzEncoder::new(&mut gzipped, flate2::Compression::best());
+        std::io::Write::write_all(&mut encoder, &vec![0; 2 * MAX_NBT_BYTES as usize]).unwrap();
+        encoder.finish().unwrap();
+        assert!(matches!(
+            PlayerHead::from_nbt(&gzipped),
+            Err(EidolonError::Texture(_))
+        ));
+    }
+
+    #[test]
+    fn only_mojang_texture_urls_are_fetched() {
+        let http = |_: &str| -> Result<Option<Vec<u8>>, EidolonError> { Ok(Some(b"png".to_vec())) };
+        let head = PlayerHead::from_nbt(TEXTURES.as_bytes()).unwrap();
+        assert!(matches!(head.fetch(&http), Err(EidolonError::Fetch(_))));
+        let bare = PlayerHead {
+            textures: PlayerTextures {
+                skin_url: None,
+                ..head.textures.clone()
+            },
+            ..head.clone()
+        };
+        assert!(matches!(bare.fetch(&http), Err(EidolonError::NotFound(_))));
+
+        let mojang = PlayerHead {
+            textures: PlayerTextures {
+                skin_url: Some("http://textures.minecraft.net/texture/abc".into()),
+                ..head.textures.clone()
+            },
+            ..head
+        };
+        let skin = mojang.fetch(&http).unwrap();
+        assert_eq!(skin.bytes, b"png");
+        assert_eq!(skin.skin_type, Some(SkinType::Slim));
+    }
+}
@@ -0,0 +1,220 @@
+//! Heads worn in the helmet slot: mob and player skulls, and blocks such as carved pumpkins.
+//!
+//! Headwear follows the head bone and is sized as the game draws it: skulls at 19/16 of the head
+//! about the neck, enclosing the hat layer, and blocks at 10/16 of a block around the middle of
+//! the head. Put one on with [`crate::character::Equipment::head`]; take it off with `None`.
+//!
+//! Skull textures use the skin layout: a player's skin, or a mob texture such as the creeper's
+//! (single-layer 64×32 textures are expanded like skins). Block textures are assembled from the
+//! block's face textures with [`block_texture`].
+
+use std::sync::Arc;
+
+use image::{imageops, RgbaImage};
+
+use crate::bedrock::{BedrockGeometry, Bone, Cube, CubeUv};
+use crate::error::EidolonError;
+use crate::model::TexturedVertex;
+use crate::texture::{SkinId, SkinImage, Texture};
+
+/// Scale of a skull worn on the head, about the neck.
+pub const SKULL_SCALE: f32 = 1.1875;
+
+/// Scale of a block worn on the head, about the middle of the head.
+pub const BLOCK_SCALE: f32 = 0.625;
+
+/// The model a [`Headwear`] is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HeadwearShape {
+    /// A head cube and its hat layer, textured like a skin's head. Mob textures without a hat
+    /// region leave it transparent.
+    Skull,
+    /// A full block, textured by [`block_texture`].
+    Block,
+}
+
+impl HeadwearShape {
+    pub const ALL: [HeadwearShape; 2] = [HeadwearShape::Skull, HeadwearShape::Block];
+}
+
+/// A skull or block worn over the head.
+#[derive(Clone)]
+pub struct Headwear {
+    pub shape: HeadwearShape,
+    pub texture: Arc<Texture>,
+}
+
+impl Headwear {
+    /// A skull textured with `texture`, a skin or skin-layout mob texture
+    /// ([`crate::renderer::Renderer::upload_skin`]).
+    pub fn skull(texture: Arc<Texture>) -> Self {
+        Self {
+            shape: HeadwearShape::Skull,
+            texture,
+        }
+    }
+
+    /// A block textured with the [`block_texture`] atlas in `texture`.
+    pub fn block(texture: Arc<Texture>) -> Self {
+        Self {
+            shape: HeadwearShape::Block,
+            texture,
+        }
+    }
+}
+
+impl std::fmt::Debug for Headwear {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Headwear")
+            .field("shape", &self.shape)
+            .field("texture", &SkinId(self.texture.content_hash()).to_string())
+            .finish()
+    }
+}
+
+/// The texture of a [`HeadwearShape::Block`] from a block's face textures, e.g.
+/// `carved_pumpkin.png` for `front`, `pumpkin_side.png` for `side` (the back and both sides) and
+/// `pumpkin_top.png` for `top` and `bottom`.
+///
+/// Faces are square; all are resized to the size of `front`, so resource-pack resolutions work.
+pub fn block_texture(
+    front: &RgbaImage,
+    side: &RgbaImage,
+    top: &RgbaImage,
+    bottom: &RgbaImage,
+) -> Result<SkinImage, EidolonError> {
+    let size = front.width();
+    if size == 0 || front.height() != size {
+        return Err(EidolonError::texture(format!(
+            "block faces must be square, got {}x{}",
+            front.width(),
+            front.height()
+        )));
+    }
+    // The box unwrap of a cube of edge `size`: top and bottom in the first row; east, north,
+    // west and south in the second. The atlas is square like a skin.
+    let mut atlas = RgbaImage::new(4 * size, 4 * size);
+    let faces = [
+        (top, 1, 0),
+        (bottom, 2, 0),
+        (side, 0, 1),
+        (front, 1, 1),
+        (side, 2, 1),
+        (side, 3, 1),
+    ];
+    for (face, column, row) in faces {
+        let face = if face.dimensions() == (size, size) {
+            face.clone()
+        } else {
+            imageops::resize(face, size, size, imageops::FilterType::Nearest)
+        };
+        imageops::replace(
+            &mut atlas,
+            &face,
+            i64::from(column * size),
+            i64::from(row * size),
+        );
+    }
+    SkinImage::from_rgba(atlas)
+}
+
+/// Triangles of `shape` in model units, placed on the head in its rest pose.
+pub(crate) fn vertices(shape: HeadwearShape) -> Vec<TexturedVertex> {
+    let cube = |origin, size, inflate, uv| Cube {
+        origin,
+        size,
+        pivot: [0.0; 3],
+        rotation: [0.0; 3],
+        inflate,
+        mirror: false,
+        uv: CubeUv::Box(uv),
+    };
+    // Bedrock pixels: the neck is at y = 24, the head spans 24 to 32.
+    let (cubes, anchor, scale) = match shape {
+        HeadwearShape::Skull => (
+            vec![
+                cube([-4.0, 24.0, -4.0], [8.0; 3], 0.0, [0.0, 0.0]),
+                cube([-4.0, 24.0, -4.0], [8.0; 3], 0.25, [32.0, 0.0]),
+            ],
+            [0.0, 24.0, 0.0],
+            SKULL_SCALE,
+        ),
+        HeadwearShape::Block => (
+            vec![cube([-8.0, 20.0, -8.0], [16.0; 3], 0.0, [0.0, 0.0])],
+            [0.0, 28.0, 0.0],
+            BLOCK_SCALE,
+        ),
+    };
+    let geometry = BedrockGeometry {
+        identifier: "geometry.eidolon.headwear".to_string(),
+        // Skull textures are skins and block textures are built as 4×4 faces; both are square.
+        texture_width: 64.0,
+        texture_height: 64.0,
+        bones: vec![Bone {
+            name: "head".to_string(),
+            parent: None,
+            pivot: [0.0, 24.0, 0.0],
+            rotation: [0.0; 3],
+            cubes,
+        }],
+    };
+    let anchor = anchor.map(|c| c / 16.0);
+    let mut vertices = geometry
+        .part_vertices()
+        .remove("Head")
+        .expect("every part is present");
+    for vertex in &mut vertices {
+        vertex.position = std::array::from_fn(|i| {
+            anchor[i] + (vertex.position[i] - anchor[i]) * scale
+        });
+    }
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Bounds;
+
+    #[test]
+    fn skulls_enclose_the_hat_layer_from_the_neck() {
+        let bounds = Bounds::from_vertices(&vertices(HeadwearShape::Skull));
+        // The skull's own hat layer is outermost: 4.25 pixels, scaled with the skull.
+        let half = 4.25 * SKULL_SCALE / 16.0;
+        assert!((bounds.max[0] - half).abs() < 1e-5, "{bounds:?}");
+        assert!((bounds.min[2] + half).abs() < 1e-5, "{bounds:?}");
+        assert!(bounds.max[1] > 2.0 + 0.25 / 16.0);
+        assert!(bounds.min[1] < 1.5, "{bounds:?}");
+    }
+
+    #[test]
+    fn blocks_are_ten_pixels_around_the_head() {
+        let bounds = Bounds::from_vertices(&vertices(HeadwearShape::Block));
+        for axis in 0..3 {
+            let extent = bounds.max[axis] - bounds.min[axis];
+            assert!((extent - 10.0 / 16.0).abs() < 1e-5, "{bounds:?}");
+        }
+        assert!((bounds.min[1] + bounds.max[1] - 3.5).abs() < 1e-5);
+        assert_eq!(vertices(HeadwearShape::Block).len(), 36);
+    }
+
+    #[test]
+    fn block_textures_unwrap_the_faces() {
+        let face = |value| RgbaImage::from_pixel(16, 16, image::Rgba([value, 0, 0, 255]));
+        let texture = block_texture(&face(1), &face(2), &face(3), &face(4)).unwrap();
+        let rgba = texture.rgba();
+        assert_eq!(rgba.dimensions(), (64, 64));
+        assert_eq!(rgba.get_pixel(20, 20)[0], 1);
+        assert_eq!(rgba.get_pixel(4, 20)[0], 2);
+        assert_eq!(rgba.get_pixel(52, 20)[0], 2);
+        assert_eq!(rgba.get_pixel(20, 4)[0], 3);
+        assert_eq!(rgba.get_pixel(36, 4)[0], 4);
+        assert_eq!(rgba.get_pixel(4, 4)[3], 0);
+
+        // Larger side textures are scaled to the front.
+        let big = RgbaImage::from_pixel(32, 32, image::Rgba([5, 0, 0, 255]));
+        let texture = block_texture(&face(1), &big, &face(3), &face(4)).unwrap();
+        assert_eq!(texture.rgba().get_pixel(4, 20)[0], 5);
+        assert!(block_texture(&RgbaImage::new(16, 8), &big, &big, &big).is_err());
+    }
+}
@@ -3,17 +3,112 @@ Eidolon: Minecraft skin rendering library.
 
 Provides:
 - Loading and rendering Minecraft skins (PNG; single-layer skins are expanded to double-layer when needed)
-- Configurable character posture and camera
-- Headless image output (PNG / WebP) and windowed preview
+- Color management: skins tagged with ICC profiles or gamma/primaries are converted to sRGB on
+  load, and PNG output can be tagged as sRGB (`color`)
+- Bedrock custom geometry and skin packs (`bedrock`)
+- Flat face and paper-doll views with SVG export (`flat`)
+- Facial expression overlays: blinks, winks and faces from expression sheets (`expression`)
+- Status effects: hurt tint, fire and potion swirls (`renderer::StatusEffects`)
+- Enchantment glint on enchanted gear, per bone (`renderer::EnchantmentGlint`)
+- Particle emitters for scene dressing: hearts, crit sparks, enchantment glyphs (`renderer::Particles`)
+- Configurable character posture, camera, and lighting presets
+- Shot presets matching familiar framings: the in-game inventory model and a back view for capes
+  (`shot`)
+- Cooperative cancellation of long renders, animations and lineups (`cancel::CancelToken`)
+- Side-by-side and blink comparisons of two skins, with texel heatmaps of what changed (`compare`)
+- Animated GIFs along keyframed camera paths: orbits, zoom-in reveals, crane shots (`animation`),
+  with exact palettes for flat colors, global or per-frame palettes and optional dithering
+  (`palette`); APNGs; frames streamed one at a time (`animation::stream_animation`), e.g. as live
+  MJPEG turntables over HTTP (`mjpeg`)
+- One-call rendering from skin PNG bytes (`render_skin_image`), traced per request with W3C
+  `traceparent` IDs (`trace`)
+- Theme palettes (primary, secondary, accent) from a skin for styling web pages (`theme`), and the
+  atlas layout of the body parts with per-region color statistics (`skin_layout`)
+- Profile and stat cards from JSON templates (`card::CardTemplate`), and skin history strips with
+  date labels (`timeline::TimelineStrip`)
+- One-call, versioned thumbnails for skin databases (`thumbnail::thumbnail`), re-rendered only
+  when a player's skin changed (`thumbnail::thumbnail_if_changed`), and named presets of them for
+  public endpoints (`presets::PresetRegistry`)
+- Pluggable skin sources by player name or UUID (`provider::SkinProvider`), including Mojang and
+  custom Yggdrasil servers, with optional textures signature checks, and a rate-limited,
+  coalescing HTTP client for them (`throttle::ThrottledHttp`); skin caches on disk or in any shared
+  `cache::CacheStore` such as Redis
+- Player-head skins from item NBT (`head::PlayerHead`)
+- Skulls and blocks worn over the head, such as pumpkins and creeper heads (`headwear`), and
+  capes at a configurable angle (`cape`)
+- Accessories from OBJ or Blockbench models worn on a bone: glasses, hats, wings (`attachment`),
+  loaded a pack at a time from JSON manifests (`cosmetics::CosmeticsCatalog`)
+- Server list ping and lineup banners of the players online (`server`)
+- Sanitizing user-submitted skins before storing them (`upload::process_upload`), with pluggable
+  moderation hooks over region crops (`moderation`), and panic-free parsing and conversion of
+  untrusted skin bytes that double as fuzz targets (`fuzz`)
+- Posting renders to Discord webhooks (`webhook::DiscordWebhook`)
+- Pluggable output destinations for encoded renders: files, stdout, memory, webhooks or caller
+  closures (`sink::OutputSink`)
+- Headless image output (PNG / WebP, 16-bit PNG and OpenEXR for compositing), terminal previews
+  (`terminal`) and windowed preview
 */
 
+pub mod animation;
+pub mod attachment;
+pub mod bedrock;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cache;
 pub mod camera;
+pub mod cancel;
+pub mod cape;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod card;
 pub mod character;
+pub mod color;
+pub mod compare;
 pub mod constants;
 pub mod converter;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cosmetics;
 pub mod error;
+pub mod expression;
+pub mod flat;
+pub mod fuzz;
+pub mod head;
+pub mod headwear;
+pub mod lighting;
+pub mod metadata;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod mjpeg;
 pub mod model;
+pub mod moderation;
+pub mod palette;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod presets;
+pub mod profile;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod provider;
 pub mod renderer;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod request;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
+pub mod shot;
+pub mod signature;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sink;
+pub mod skin_layout;
+pub mod terminal;
 pub mod texture;
+pub mod theme;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod throttle;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod timeline;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod thumbnail;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod trace;
+pub mod upload;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod webhook;
 
 pub use renderer::OutputFormat;
+#[cfg(not(target_arch = "wasm32"))]
+pub use request::{render_skin_image, render_skin_image_with_theme, RenderRequest};
@@ -0,0 +1,172 @@
+//! Scene lighting rig: a key light, a fill light, an ambient term, and an optional rim light.
+//!
+//! [`Lighting::default`] reproduces the renderer's original hardcoded rig. [`LightingPreset`]
+//! bundles a rig with a matching background tint so a render can be set to a mood in one call
+//! (see [`crate::renderer::Renderer::apply_lighting_preset`]).
+
+/// Directional light rig consumed by the fragment shader.
+///
+/// Colors are linear RGB multipliers and already include intensity; directions point *towards*
+/// the light and do not need to be normalized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lighting {
+    /// Direction towards the primary (key) light.
+    pub key_direction: [f32; 3],
+    /// Key light color × intensity.
+    pub key_color: [f32; 3],
+    /// Direction towards the secondary (fill) light.
+    pub fill_direction: [f32; 3],
+    /// Fill light color × intensity.
+    pub fill_color: [f32; 3],
+    /// Constant ambient term added to every lit texel.
+    pub ambient: [f32; 3],
+    /// Rim (back-light) color, added along silhouette edges facing away from the camera.
+    pub rim_color: [f32; 3],
+    /// Rim light strength. `0.0` disables the rim term entirely.
+    pub rim_strength: f32,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self {
+            key_direction: [1.0, 1.0, 1.0],
+            key_color: [0.5, 0.5, 0.5],
+            fill_direction: [-1.0, 0.5, -0.5],
+            fill_color: [0.3, 0.3, 0.3],
+            ambient: [0.5, 0.5, 0.5],
+            rim_color: [1.0, 1.0, 1.0],
+            rim_strength: 0.0,
+        }
+    }
+}
+
+/// Named mood presets: each sets the light rig and the background clear color together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightingPreset {
+    /// High warm sun, blue sky fill.
+    Noon,
+    /// Low orange sun from the side, purple fill.
+    Sunset,
+    /// Cool dim light from above, night-sky background.
+    Moonlight,
+    /// Lava glow from below, dark red background.
+    Nether,
+    /// Soft violet light on a near-black background.
+    End,
+}
+
+impl LightingPreset {
+    /// Light rig for this preset.
+    pub fn lighting(&self) -> Lighting {
+        match self {
+            LightingPreset::Noon => Lighting {
+                key_direction: [0.3, 1.0, 0.4],
+                key_color: [0.6, 0.59, 0.55],
+                fill_direction: [-1.0, 0.3, -0.5],
+                fill_color: [0.14, 0.16, 0.2],
+                ambient: [0.5, 0.52, 0.55],
+                ..Lighting::default()
+            },
+            LightingPreset::Sunset => Lighting {
+                key_direction: [1.0, 0.25, 0.4],
+                key_color: [0.7, 0.42, 0.25],
+                fill_direction: [-1.0, 0.4, -0.3],
+                fill_color: [0.14, 0.1, 0.18],
+                ambient: [0.35, 0.28, 0.3],
+                ..Lighting::default()
+            },
+            LightingPreset::Moonlight => Lighting {
+                key_direction: [-0.4, 1.0, 0.6],
+                key_color: [0.25, 0.29, 0.4],
+                fill_direction: [0.8, 0.2, -0.6],
+                fill_color: [0.04, 0.05, 0.07],
+                ambient: [0.18, 0.2, 0.3],
+                ..Lighting::default()
+            },
+            LightingPreset::Nether => Lighting {
+                key_direction: [0.2, -0.6, 0.6],
+                key_color: [0.6, 0.27, 0.12],
+                fill_direction: [-0.5, 1.0, -0.2],
+                fill_color: [0.18, 0.05, 0.03],
+                ambient: [0.4, 0.22, 0.18],
+                ..Lighting::default()
+            },
+            LightingPreset::End => Lighting {
+                key_direction: [0.0, 1.0, 0.3],
+                key_color: [0.34, 0.32, 0.4],
+                fill_direction: [-0.6, 0.2, -0.8],
+                fill_color: [0.18, 0.12, 0.24],
+                ambient: [0.35, 0.3, 0.42],
+                ..Lighting::default()
+            },
+        }
+    }
+
+    /// Opaque background clear color (RGBA, 0.0–1.0) matching this preset.
+    pub fn background(&self) -> [f64; 4] {
+        match self {
+            LightingPreset::Noon => [0.53, 0.75, 0.95, 1.0],
+            LightingPreset::Sunset => [0.98, 0.55, 0.35, 1.0],
+            LightingPreset::Moonlight => [0.04, 0.05, 0.12, 1.0],
+            LightingPreset::Nether => [0.35, 0.05, 0.04, 1.0],
+            LightingPreset::End => [0.08, 0.05, 0.12, 1.0],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_PRESETS: [LightingPreset; 5] = [
+        LightingPreset::Noon,
+        LightingPreset::Sunset,
+        LightingPreset::Moonlight,
+        LightingPreset::Nether,
+        LightingPreset::End,
+    ];
+
+    #[test]
+    fn default_matches_legacy_rig() {
+        // The shader used to hardcode: ambient 0.5, key (1,1,1) × 0.5, fill (-1,0.5,-0.5) × 0.3.
+        let l = Lighting::default();
+        assert_eq!(l.ambient, [0.5; 3]);
+        assert_eq!(l.key_direction, [1.0, 1.0, 1.0]);
+        assert_eq!(l.key_color, [0.5; 3]);
+        assert_eq!(l.fill_direction, [-1.0, 0.5, -0.5]);
+        assert_eq!(l.fill_color, [0.3; 3]);
+    }
+
+    #[test]
+    fn rim_light_off_by_default() {
+        assert_eq!(Lighting::default().rim_strength, 0.0);
+        for preset in ALL_PRESETS {
+            assert_eq!(preset.lighting().rim_strength, 0.0, "{:?} enables rim light", preset);
+        }
+    }
+
+    #[test]
+    fn preset_backgrounds_are_opaque() {
+        for preset in ALL_PRESETS {
+            assert_eq!(preset.background()[3], 1.0, "{:?} background not opaque", preset);
+        }
+    }
+
+    #[test]
+    fn presets_differ_from_default_and_each_other() {
+        for (i, a) in ALL_PRESETS.iter().enumerate() {
+            assert_ne!(a.lighting(), Lighting::default(), "{:?} equals default rig", a);
+            for b in &ALL_PRESETS[i + 1..] {
+                assert_ne!(a.lighting(), b.lighting(), "{:?} and {:?} share a rig", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn preset_key_directions_non_zero() {
+        for preset in ALL_PRESETS {
+            let d = preset.lighting().key_direction;
+            assert!(d.iter().any(|c| *c != 0.0), "{:?} has a zero key direction", preset);
+        }
+    }
+}
@@ -1,12 +1,36 @@
+use std::io::Write;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
 use clap::{Parser, Subcommand, ValueEnum};
 use eidolon::{
-    camera::Camera,
-    character::{Character, DefaultPostures, Posture, SkinType},
+    animation::{Animation, CameraPath, Easing, ExpressionKey, GifOptions, GifPalette, Idle},
+    bedrock::BedrockGeometry,
+    camera::{Camera, DEFAULT_FOV},
+    cancel::CancelToken,
+    cape::{Cape, DEFAULT_CAPE_ANGLE},
+    compare::{self, TextureDiff},
+    character::{Bone, Character, DefaultPostures, Posture, SkinType},
+    color,
     converter,
-    renderer::{OutputFormat, Renderer},
+    expression::{Expression, ExpressionSheet},
+    lighting::{Lighting, LightingPreset},
+    metadata::RenderMetadata,
+    mjpeg::{self, FrameBroadcast},
+    palette::Dither,
+    provider::{DirectoryProvider, PlayerId, ProviderChain, SkinProvider},
+    renderer::{
+        AlphaMode, AlphaTest, ColorAdjustments, DepthOfField, EmissiveOverlay, EnchantmentGlint, ExrChannels,
+        LayerAlpha, Msaa, OutputFormat, ParticleEmitter, ParticlePreset, Particles, RenderOptions, Renderer,
+        ShadowSettings, StatusEffects, StereoLayout, StereoSettings, TextureFilter, ToneMapping,
+    },
+    shot::ShotPreset,
+    sink::{FileSink, Output, OutputSink, StdoutSink},
+    texture::SkinImage,
+    theme::ThemePalette,
+    thumbnail::{ThumbnailPose, ThumbnailView},
+    timeline::{date_label, TimelineEntry, TimelineStrip, TimelineStyle},
 };
 use winit::application::ApplicationHandler;
 use winit::dpi::PhysicalSize;
@@ -22,6 +46,9 @@ use winit::window::{Window, WindowId};
                   eidolon render skin.png\n  \
                   eidolon render skin.png out.webp --slim --posture wave\n  \
                   eidolon preview skin.png --cam-zoom 2.0\n  \
+                  eidolon animate skin.png spin.gif --width 256 --height 256\n  \
+                  eidolon live skin.png --listen 0.0.0.0:8080\n  \
+                  eidolon banner --server mc.example.org --skin-dir skins\n  \
                   eidolon convert old_skin.png new_skin.png"
 )]
 struct Args {
@@ -48,6 +75,274 @@ impl From<PostureCli> for Posture {
     }
 }
 
+impl From<PostureCli> for ThumbnailPose {
+    fn from(value: PostureCli) -> Self {
+        match value {
+            PostureCli::Stand => ThumbnailPose::Stand,
+            PostureCli::Wave => ThumbnailPose::Wave,
+            PostureCli::Walking => ThumbnailPose::Walking,
+            PostureCli::Running => ThumbnailPose::Running,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq, Eq)]
+enum FlatViewCli {
+    /// The 8×8 face.
+    Face,
+    /// The whole character from the front, 16×32.
+    PaperDoll,
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug)]
+enum LightingCli {
+    Noon,
+    Sunset,
+    Moonlight,
+    Nether,
+    End,
+}
+
+impl From<LightingCli> for LightingPreset {
+    fn from(value: LightingCli) -> Self {
+        match value {
+            LightingCli::Noon => LightingPreset::Noon,
+            LightingCli::Sunset => LightingPreset::Sunset,
+            LightingCli::Moonlight => LightingPreset::Moonlight,
+            LightingCli::Nether => LightingPreset::Nether,
+            LightingCli::End => LightingPreset::End,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug)]
+enum ToneMappingCli {
+    Linear,
+    Reinhard,
+    Aces,
+}
+
+impl From<ToneMappingCli> for ToneMapping {
+    fn from(value: ToneMappingCli) -> Self {
+        match value {
+            ToneMappingCli::Linear => ToneMapping::Linear,
+            ToneMappingCli::Reinhard => ToneMapping::Reinhard,
+            ToneMappingCli::Aces => ToneMapping::Aces,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug)]
+enum TextureFilterCli {
+    /// Crisp full-size texels.
+    Nearest,
+    /// Crisp texels from the mipmap level that fits the output size (HD skins).
+    NearestMipmapped,
+    /// Smooth bilinear texels between mipmap levels.
+    Linear,
+}
+
+impl From<TextureFilterCli> for TextureFilter {
+    fn from(value: TextureFilterCli) -> Self {
+        match value {
+            TextureFilterCli::Nearest => TextureFilter::Nearest,
+            TextureFilterCli::NearestMipmapped => TextureFilter::NearestMipmapped,
+            TextureFilterCli::Linear => TextureFilter::Linear,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug)]
+enum MsaaCli {
+    /// One sample per pixel.
+    Off,
+    #[value(name = "2")]
+    X2,
+    #[value(name = "4")]
+    X4,
+    #[value(name = "8")]
+    X8,
+}
+
+impl From<MsaaCli> for Msaa {
+    fn from(value: MsaaCli) -> Self {
+        match value {
+            MsaaCli::Off => Msaa::Off,
+            MsaaCli::X2 => Msaa::X2,
+            MsaaCli::X4 => Msaa::X4,
+            MsaaCli::X8 => Msaa::X8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+enum StereoCli {
+    SideBySide,
+    Anaglyph,
+}
+
+impl From<StereoCli> for StereoLayout {
+    fn from(value: StereoCli) -> Self {
+        match value {
+            StereoCli::SideBySide => StereoLayout::SideBySide,
+            StereoCli::Anaglyph => StereoLayout::Anaglyph,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+enum ShotCli {
+    Inventory,
+    CapeBack,
+}
+
+impl ShotCli {
+    /// The preset, with the mouse at `cursor` for shots that follow it.
+    fn preset(self, cursor: [f32; 2]) -> ShotPreset {
+        match self {
+            ShotCli::Inventory => ShotPreset::Inventory { cursor },
+            ShotCli::CapeBack => ShotPreset::CapeBack,
+        }
+    }
+}
+
+/// Parse an `X,Y` cursor position.
+fn parse_cursor(s: &str) -> Result<[f32; 2], String> {
+    let invalid = || format!("'{}' is not an X,Y position", s);
+    let (x, y) = s.split_once(',').ok_or_else(invalid)?;
+    match (x.trim().parse::<f32>(), y.trim().parse::<f32>()) {
+        (Ok(x), Ok(y)) if x.is_finite() && y.is_finite() => Ok([x, y]),
+        _ => Err(invalid()),
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+enum ViewCli {
+    Front,
+    Back,
+    Isometric,
+}
+
+impl From<ViewCli> for ThumbnailView {
+    fn from(value: ViewCli) -> Self {
+        match value {
+            ViewCli::Front => ThumbnailView::Front,
+            ViewCli::Back => ThumbnailView::Back,
+            ViewCli::Isometric => ThumbnailView::Isometric,
+        }
+    }
+}
+
+/// Parse a timeline entry `[LABEL=]FILE`; a label `@SECONDS` becomes the UTC date of that Unix
+/// time, and a missing one the file name without its extension.
+fn parse_timeline_entry(s: &str) -> Result<(String, String), String> {
+    let (label, file) = match s.split_once('=') {
+        Some((label, file)) => (label.to_string(), file),
+        None => {
+            let stem = std::path::Path::new(s).file_stem().and_then(|stem| stem.to_str());
+            (stem.unwrap_or(s).to_string(), s)
+        }
+    };
+    if file.is_empty() {
+        return Err(format!("'{}' names no skin file", s));
+    }
+    let label = match label.strip_prefix('@') {
+        Some(seconds) => date_label(
+            seconds
+                .parse()
+                .map_err(|_| format!("'{}' is not a Unix time in seconds", seconds))?,
+        ),
+        None => label,
+    };
+    Ok((label, file.to_string()))
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+enum BitDepthCli {
+    #[value(name = "8")]
+    Eight,
+    #[value(name = "16")]
+    Sixteen,
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+enum CameraPathCli {
+    /// Full turns around the character, starting at the scene camera.
+    Orbit,
+    /// Zoom in from far away, ending at the scene camera.
+    Reveal,
+    /// Rise from near the ground, ending at the scene camera.
+    Crane,
+    /// The scene camera, not moving (for --idle loops).
+    Still,
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+enum EasingCli {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl From<EasingCli> for Easing {
+    fn from(value: EasingCli) -> Self {
+        match value {
+            EasingCli::Linear => Easing::Linear,
+            EasingCli::EaseIn => Easing::EaseIn,
+            EasingCli::EaseOut => Easing::EaseOut,
+            EasingCli::EaseInOut => Easing::EaseInOut,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+enum DitherCli {
+    None,
+    Ordered,
+    FloydSteinberg,
+}
+
+impl From<DitherCli> for Dither {
+    fn from(value: DitherCli) -> Self {
+        match value {
+            DitherCli::None => Dither::None,
+            DitherCli::Ordered => Dither::Ordered,
+            DitherCli::FloydSteinberg => Dither::FloydSteinberg,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+enum TerminalFormatCli {
+    /// 24-bit color half blocks; works in most terminals.
+    Ansi,
+    /// Full-resolution image through the Kitty graphics protocol.
+    Kitty,
+    /// Full-resolution image as Sixel graphics.
+    Sixel,
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug)]
+enum ExrChannelsCli {
+    Rgb,
+    Rgba,
+    Rgbz,
+    Rgbaz,
+}
+
+impl From<ExrChannelsCli> for ExrChannels {
+    fn from(value: ExrChannelsCli) -> Self {
+        let (alpha, depth) = match value {
+            ExrChannelsCli::Rgb => (false, false),
+            ExrChannelsCli::Rgba => (true, false),
+            ExrChannelsCli::Rgbz => (false, true),
+            ExrChannelsCli::Rgbaz => (true, true),
+        };
+        ExrChannels { alpha, depth }
+    }
+}
+
 fn parse_positive_scale(s: &str) -> Result<f32, String> {
     let value: f32 = s
         .parse()
@@ -59,6 +354,119 @@ fn parse_positive_scale(s: &str) -> Result<f32, String> {
     }
 }
 
+/// Parse a strength in `0.0..=1.0`.
+fn parse_unit_interval(s: &str) -> Result<f32, String> {
+    let value: f32 = s
+        .parse()
+        .map_err(|_| format!("'{}' is not a valid number", s))?;
+    if (0.0..=1.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err(format!("value must be between 0 and 1, got {}", value))
+    }
+}
+
+/// Parse a value `>= 0.0`.
+fn parse_non_negative(s: &str) -> Result<f32, String> {
+    let value: f32 = s
+        .parse()
+        .map_err(|_| format!("'{}' is not a valid number", s))?;
+    if value >= 0.0 {
+        Ok(value)
+    } else {
+        Err(format!("value must be at least 0, got {}", value))
+    }
+}
+
+/// Parse a field of view in degrees: 1 to 120.
+fn parse_fov(s: &str) -> Result<f32, String> {
+    let value: f32 = s
+        .parse()
+        .map_err(|_| format!("'{}' is not a valid number", s))?;
+    if (1.0..=120.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err(format!("field of view must be between 1 and 120 degrees, got {}", value))
+    }
+}
+
+/// Parse `rrggbb` or `#rrggbb` into linear 0.0–1.0 RGB.
+fn parse_hex_color(s: &str) -> Result<[f32; 3], String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' is not a hex color (expected rrggbb)", s));
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap() as f32 / 255.0;
+    Ok([channel(0), channel(2), channel(4)])
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+enum ParticlesCli {
+    Hearts,
+    CritSparks,
+    EnchantGlyphs,
+}
+
+impl From<ParticlesCli> for ParticlePreset {
+    fn from(value: ParticlesCli) -> Self {
+        match value {
+            ParticlesCli::Hearts => ParticlePreset::Hearts,
+            ParticlesCli::CritSparks => ParticlePreset::CritSparks,
+            ParticlesCli::EnchantGlyphs => ParticlePreset::EnchantGlyphs,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq)]
+enum LayerCli {
+    Base,
+    Overlay,
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug)]
+enum BoneCli {
+    Head,
+    Body,
+    RightArm,
+    LeftArm,
+    RightLeg,
+    LeftLeg,
+}
+
+impl From<BoneCli> for Bone {
+    fn from(value: BoneCli) -> Self {
+        match value {
+            BoneCli::Head => Bone::Head,
+            BoneCli::Body => Bone::Body,
+            BoneCli::RightArm => Bone::RightArm,
+            BoneCli::LeftArm => Bone::LeftArm,
+            BoneCli::RightLeg => Bone::RightLeg,
+            BoneCli::LeftLeg => Bone::LeftLeg,
+        }
+    }
+}
+
+/// Parse a `preset[@bone]` particle emitter, e.g. `hearts` or `crit-sparks@right-arm`.
+fn parse_particles(s: &str) -> Result<ParticleEmitter, String> {
+    let (preset, bone) = match s.split_once('@') {
+        Some((preset, bone)) => (preset, Some(bone)),
+        None => (s, None),
+    };
+    let emitter = ParticleEmitter::preset(ParticlesCli::from_str(preset, true)?.into());
+    Ok(match bone {
+        Some(bone) => emitter.attached_to(BoneCli::from_str(bone, true)?.into()),
+        None => emitter,
+    })
+}
+
+/// Parse a `key=value` card placeholder.
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("'{}' is not key=value", s)),
+    }
+}
+
 #[derive(Parser, Debug)]
 struct ViewportArgs {
     /// Output image or window width in pixels.
@@ -69,6 +477,127 @@ struct ViewportArgs {
     height: u32,
 }
 
+/// Asset override files of preview, reloaded whenever they change (`dev` feature).
+#[cfg(feature = "dev")]
+#[derive(Parser, Debug)]
+struct DevArgs {
+    /// WGSL file replacing the built-in skin shader.
+    #[arg(long)]
+    dev_shader: Option<PathBuf>,
+    /// OBJ file replacing the bundled classic model.
+    #[arg(long)]
+    dev_classic_model: Option<PathBuf>,
+    /// OBJ file replacing the bundled slim model.
+    #[arg(long)]
+    dev_slim_model: Option<PathBuf>,
+}
+
+/// Output encoding options for render.
+#[derive(Parser, Debug)]
+struct OutputArgs {
+    /// PNG bits per channel; 16 renders through the float pipeline.
+    #[arg(long, value_enum, default_value = "8")]
+    bit_depth: BitDepthCli,
+
+    /// Channels written to .exr outputs (linear light, Z = eye distance).
+    #[arg(long, value_enum, default_value = "rgba")]
+    exr_channels: ExrChannelsCli,
+
+    /// Print the render to the terminal instead of writing a file: `ansi` works everywhere,
+    /// `kitty` and `sixel` show full resolution where supported.
+    #[arg(long, value_enum, conflicts_with = "stereo")]
+    format: Option<TerminalFormatCli>,
+
+    /// Terminal width in characters for --format ansi (default: $COLUMNS, else 80).
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    columns: Option<u32>,
+}
+
+/// Facial expression options of render and animate.
+#[derive(Parser, Debug)]
+struct ExpressionArgs {
+    /// Facial expression: `blink`, `wink`, or the 0-based index of a face in --expression-sheet.
+    #[arg(long, value_name = "NAME")]
+    expression: Option<String>,
+
+    /// PNG strip of 8×8 faces (16×16 for 128px skins, ...) drawn over the skin's face; transparent
+    /// pixels keep the skin's own.
+    #[arg(long, value_name = "FILE")]
+    expression_sheet: Option<PathBuf>,
+}
+
+/// GIF palette options of animate.
+#[derive(Parser, Debug)]
+struct GifArgs {
+    /// Colors per frame palette (2–256). Frames with fewer colors keep them exactly.
+    #[arg(long, default_value_t = 256, value_parser = clap::value_parser!(u16).range(2..=256))]
+    colors: u16,
+
+    /// One palette for the whole GIF instead of one per frame, so colors do not shift.
+    #[arg(long)]
+    global_palette: bool,
+
+    /// How colors missing from the palette are approximated.
+    #[arg(long, value_enum, default_value = "none")]
+    dither: DitherCli,
+
+    /// Flatten the background onto --matte instead of keeping it transparent.
+    #[arg(long)]
+    opaque: bool,
+
+    /// Color (rrggbb) that translucent pixels, or with --opaque the background, are blended onto.
+    #[arg(long, default_value = "000000", value_parser = parse_hex_color)]
+    matte: [f32; 3],
+
+    /// Store every frame whole instead of only the rectangle that changed.
+    #[arg(long)]
+    full_frames: bool,
+}
+
+impl GifArgs {
+    fn options(&self) -> GifOptions {
+        GifOptions {
+            palette_size: self.colors,
+            palette: if self.global_palette {
+                GifPalette::Global
+            } else {
+                GifPalette::PerFrame
+            },
+            dither: self.dither.into(),
+            transparent: !self.opaque,
+            matte: self.matte.map(|c| (c * 255.0).round() as u8),
+            delta_frames: !self.full_frames,
+        }
+    }
+}
+
+impl ExpressionArgs {
+    fn expression(&self) -> Result<Option<Expression>, Box<dyn std::error::Error>> {
+        let Some(name) = &self.expression else {
+            return Ok(None);
+        };
+        if let Some(builtin) = Expression::builtin(name) {
+            return Ok(Some(builtin));
+        }
+        let index: usize = name.parse().map_err(|_| {
+            format!("unknown expression '{name}' (expected blink, wink or a sheet index)")
+        })?;
+        let path = self
+            .expression_sheet
+            .as_ref()
+            .ok_or("a numbered --expression needs --expression-sheet")?;
+        let sheet = ExpressionSheet::from_memory(&std::fs::read(path)?)?;
+        let face = sheet.get(index).ok_or_else(|| {
+            format!(
+                "{} has {} faces; there is no face {index}",
+                path.display(),
+                sheet.len()
+            )
+        })?;
+        Ok(Some(face))
+    }
+}
+
 /// Shared scene parameters for render and preview.
 #[derive(Parser, Debug)]
 struct SceneArgs {
@@ -88,10 +617,152 @@ struct SceneArgs {
     #[arg(long, default_value_t = 1.0, value_parser = parse_positive_scale)]
     cam_zoom: f32,
 
+    /// Vertical field of view in degrees; narrower keeps the framing and flattens the
+    /// perspective (a few degrees look nearly orthographic).
+    #[arg(long, default_value_t = DEFAULT_FOV, value_parser = parse_fov)]
+    cam_fov: f32,
+
     /// Posture preset: stand, wave, walking, running.
     #[arg(long, value_enum, default_value_t = PostureCli::Stand)]
     posture: PostureCli,
 
+    /// Lighting preset (also sets an opaque background): noon, sunset, moonlight, nether, end.
+    /// Default is the neutral studio rig on a transparent background.
+    #[arg(long, value_enum)]
+    lighting: Option<LightingCli>,
+
+    /// Rim (back-light) strength; 0 disables it.
+    #[arg(long, default_value_t = 0.0)]
+    rim_strength: f32,
+
+    /// Rim light color as hex `rrggbb`.
+    #[arg(long, default_value = "ffffff", value_parser = parse_hex_color)]
+    rim_color: [f32; 3],
+
+    /// Contact ambient-occlusion strength between body parts (0–1); 0 disables it.
+    #[arg(long, default_value_t = 0.0, value_parser = parse_unit_interval)]
+    ambient_occlusion: f32,
+
+    /// Cast key-light shadows (arms onto the torso, character onto the ground).
+    #[arg(long)]
+    shadows: bool,
+
+    /// Shadow-map resolution in texels (with --shadows).
+    #[arg(long, default_value_t = ShadowSettings::default().resolution)]
+    shadow_resolution: u32,
+
+    /// Shadow depth bias; raise if lit faces show stripes (with --shadows).
+    #[arg(long, default_value_t = ShadowSettings::default().bias)]
+    shadow_bias: f32,
+
+    /// Ground shadow opacity (0–1); 0 hides the ground shadow (with --shadows).
+    #[arg(long, default_value_t = ShadowSettings::default().ground_opacity, value_parser = parse_unit_interval)]
+    ground_shadow: f32,
+
+    /// Blur out-of-focus areas (depth of field post effect).
+    #[arg(long)]
+    depth_of_field: bool,
+
+    /// Distance from the camera that stays sharp (with --depth-of-field).
+    /// Default: the orbit target, 4 / cam-zoom.
+    #[arg(long, value_parser = parse_positive_scale)]
+    dof_focus: Option<f32>,
+
+    /// Largest blur radius in pixels (with --depth-of-field).
+    #[arg(long, default_value_t = DepthOfField::default().blur_strength)]
+    dof_blur: f32,
+
+    /// Render the overlay layer unlit with a bloom glow (for glowing eyes, runes, visors).
+    #[arg(long)]
+    emissive_overlay: bool,
+
+    /// Luminance (0–1) an emissive texel must exceed to glow (with --emissive-overlay).
+    #[arg(long, default_value_t = EmissiveOverlay::default().bloom_threshold, value_parser = parse_unit_interval)]
+    bloom_threshold: f32,
+
+    /// Glow strength; 0 keeps the overlay full-bright without bloom (with --emissive-overlay).
+    #[arg(long, default_value_t = EmissiveOverlay::default().bloom_intensity)]
+    bloom_intensity: f32,
+
+    /// Tone mapper for the HDR scene; anything but linear renders through the post pass.
+    #[arg(long, value_enum, default_value = "linear")]
+    tone_mapping: ToneMappingCli,
+
+    /// Write color premultiplied by alpha instead of straight alpha.
+    #[arg(long)]
+    premultiplied_alpha: bool,
+
+    /// Skin texels with alpha below this (0–1) are not drawn.
+    #[arg(long, default_value_t = AlphaTest::default().cutoff, value_parser = parse_unit_interval)]
+    alpha_cutoff: f32,
+
+    /// Draw texels of these layers that pass --alpha-cutoff opaque instead of blending them,
+    /// removing gray fringes from antialiased skins; bare --cutout covers both, e.g.
+    /// --cutout=overlay --alpha-cutoff 0.5.
+    #[arg(long, value_name = "LAYERS", num_args = 0..=1, require_equals = true, value_delimiter = ',', default_missing_values = ["base", "overlay"])]
+    cutout: Option<Vec<LayerCli>>,
+
+    /// Texture sampling; nearest-mipmapped keeps HD skins from shimmering at small sizes.
+    #[arg(long, value_enum, default_value = "nearest")]
+    texture_filter: TextureFilterCli,
+
+    /// Multisample anti-aliasing: samples per pixel for smooth silhouette edges (off, 2, 4 or 8;
+    /// unsupported counts fall back to fewer).
+    #[arg(long, value_enum, value_name = "SAMPLES", default_value = "off")]
+    msaa: MsaaCli,
+
+    /// Pull each face's texture coordinates in by this many skin texels (e.g. 0.01), so samples
+    /// never land on a face boundary; 0 keeps exact UVs.
+    #[arg(long, value_name = "TEXELS", default_value_t = 0.0, value_parser = parse_non_negative)]
+    uv_inset: f32,
+
+    /// Exposure adjustment in stops (+1 doubles brightness, -1 halves it).
+    #[arg(long, default_value_t = ColorAdjustments::default().exposure, allow_hyphen_values = true)]
+    exposure: f32,
+
+    /// Gamma adjustment; above 1 lifts mid-tones, below 1 darkens them.
+    #[arg(long, default_value_t = ColorAdjustments::default().gamma, value_parser = parse_positive_scale)]
+    gamma: f32,
+
+    /// Saturation; 0 is grayscale, 1 unchanged, above 1 more vivid.
+    #[arg(long, default_value_t = ColorAdjustments::default().saturation, value_parser = parse_non_negative)]
+    saturation: f32,
+
+    /// Red hurt flash as when taking damage; optional strength, e.g. --hurt=0.5.
+    #[arg(long, value_name = "STRENGTH", num_args = 0..=1, require_equals = true, default_missing_value = "1", value_parser = parse_non_negative)]
+    hurt: Option<f32>,
+
+    /// Set the character on fire (flames in front of the model).
+    #[arg(long)]
+    on_fire: bool,
+
+    /// Potion swirl particles around the character in this hex color `rrggbb`.
+    #[arg(long, value_name = "COLOR", value_parser = parse_hex_color)]
+    potion: Option<[f32; 3]>,
+
+    /// Particle emitter `preset[@bone]` (hearts, crit-sparks, enchant-glyphs), optionally moved
+    /// onto another bone, e.g. crit-sparks@right-arm. Repeatable.
+    #[arg(long = "particles", value_name = "PRESET[@BONE]", value_parser = parse_particles)]
+    particles: Vec<ParticleEmitter>,
+
+    /// Enchantment glint over the gear on these bones; bare --glint covers every bone, e.g.
+    /// --glint=head,body for an enchanted helmet and chestplate.
+    #[arg(long, value_name = "BONES", num_args = 0..=1, require_equals = true, value_delimiter = ',', default_missing_values = ["head", "body", "right-arm", "left-arm", "right-leg", "left-leg"])]
+    glint: Option<Vec<BoneCli>>,
+
+    /// Enchantment glint color as hex `rrggbb`.
+    #[arg(long, value_name = "COLOR", requires = "glint", value_parser = parse_hex_color)]
+    glint_color: Option<[f32; 3]>,
+
+    /// Seconds into the fire, potion, particle and glint effects (picks the frame); animations
+    /// advance it per frame.
+    #[arg(long, value_name = "SECS", default_value_t = 0.0, value_parser = parse_non_negative)]
+    effect_time: f32,
+
+    /// Draw parts the skin leaves fully transparent as flat gray mannequin parts.
+    #[arg(long)]
+    mannequin: bool,
+
     // ── per-joint overrides (power-user; shown in --help --long) ──
     #[arg(long, hide_short_help = true)]
     head_yaw: Option<f32>,
@@ -157,6 +828,81 @@ fn character_and_camera_from_scene(scene: &SceneArgs) -> (Character, Camera) {
     (character, camera)
 }
 
+/// Light rig from the scene flags, plus the preset background color (if a preset was chosen).
+fn lighting_from_scene(scene: &SceneArgs) -> (Lighting, Option<[f64; 4]>) {
+    let preset = scene.lighting.map(LightingPreset::from);
+    let mut lighting = preset.map(|p| p.lighting()).unwrap_or_default();
+    lighting.rim_strength = scene.rim_strength;
+    lighting.rim_color = scene.rim_color;
+    (lighting, preset.map(|p| p.background()))
+}
+
+/// Optional render features from the scene flags.
+fn options_from_scene(scene: &SceneArgs) -> RenderOptions {
+    RenderOptions {
+        ambient_occlusion: scene.ambient_occlusion,
+        shadow: scene.shadows.then_some(ShadowSettings {
+            resolution: scene.shadow_resolution,
+            bias: scene.shadow_bias,
+            ground_opacity: scene.ground_shadow,
+        }),
+        depth_of_field: scene.depth_of_field.then_some(DepthOfField {
+            focus_distance: scene.dof_focus,
+            blur_strength: scene.dof_blur,
+        }),
+        emissive_overlay: scene.emissive_overlay.then_some(EmissiveOverlay {
+            bloom_threshold: scene.bloom_threshold,
+            bloom_intensity: scene.bloom_intensity,
+        }),
+        tone_mapping: scene.tone_mapping.into(),
+        alpha_mode: if scene.premultiplied_alpha {
+            AlphaMode::Premultiplied
+        } else {
+            AlphaMode::Straight
+        },
+        alpha_test: {
+            let cutout = scene.cutout.as_deref().unwrap_or_default();
+            let mode = |layer| {
+                if cutout.contains(&layer) {
+                    LayerAlpha::Cutout
+                } else {
+                    LayerAlpha::Blend
+                }
+            };
+            AlphaTest {
+                cutoff: scene.alpha_cutoff,
+                base: mode(LayerCli::Base),
+                overlay: mode(LayerCli::Overlay),
+            }
+        },
+        texture_filter: scene.texture_filter.into(),
+        msaa: scene.msaa.into(),
+        fov: scene.cam_fov,
+        uv_inset: scene.uv_inset,
+        adjustments: ColorAdjustments {
+            exposure: scene.exposure,
+            gamma: scene.gamma,
+            saturation: scene.saturation,
+        },
+        status: StatusEffects {
+            hurt: scene.hurt.unwrap_or(0.0),
+            on_fire: scene.on_fire,
+            potion: scene.potion,
+            time: scene.effect_time,
+        },
+        particles: Particles {
+            emitters: scene.particles.clone(),
+            time: scene.effect_time,
+        },
+        glint: scene.glint.as_ref().map(|bones| EnchantmentGlint {
+            bones: bones.iter().map(|&bone| bone.into()).collect(),
+            color: scene.glint_color.unwrap_or(EnchantmentGlint::PURPLE),
+            time: scene.effect_time,
+        }),
+        mannequin_fallback: scene.mannequin,
+    }
+}
+
 /// Infer OutputFormat from filename extension. Unknown / missing → Png.
 fn format_from_filename(filename: &str) -> OutputFormat {
     match std::path::Path::new(filename)
@@ -164,41 +910,534 @@ fn format_from_filename(filename: &str) -> OutputFormat {
         .and_then(|e| e.to_str())
     {
         Some("webp") => OutputFormat::WebP,
+        Some("exr") => OutputFormat::Exr(ExrChannels::default()),
         _ => OutputFormat::Png,
     }
 }
 
-#[derive(Subcommand, Debug)]
-enum Command {
-    /// Render the skin to an image file (headless).
-    ///
-    /// Format is inferred from the output filename extension
-    /// (.png or .webp). Defaults to PNG.
-    Render {
-        /// Path to the skin PNG file.
-        skin: String,
+/// [`format_from_filename`] refined by the output flags (bit depth, EXR channels).
+fn output_format(filename: &str, args: &OutputArgs) -> OutputFormat {
+    match format_from_filename(filename) {
+        OutputFormat::Png if args.bit_depth == BitDepthCli::Sixteen => OutputFormat::Png16,
+        OutputFormat::Exr(_) => OutputFormat::Exr(args.exr_channels.into()),
+        format => format,
+    }
+}
 
-        /// Output image path. Extension determines format (.png or .webp).
-        #[arg(default_value = "output.png")]
-        output: String,
+/// Where the image for output `path` goes: stdout for `-`, otherwise the file.
+fn output_sink(path: &str) -> Box<dyn OutputSink> {
+    if path == "-" {
+        Box::new(StdoutSink)
+    } else {
+        Box::new(FileSink::default())
+    }
+}
 
-        #[command(flatten)]
-        viewport: ViewportArgs,
+/// One `--out` target: an output path plus per-view overrides of the shared scene.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct OutputSpec {
+    path: String,
+    yaw: Option<f32>,
+    pitch: Option<f32>,
+    zoom: Option<f32>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
 
-        #[command(flatten)]
-        scene: SceneArgs,
-    },
-    /// Open a live preview window.
-    Preview {
-        /// Path to the skin PNG file.
-        skin: String,
+impl OutputSpec {
+    fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            ..Self::default()
+        }
+    }
 
-        #[command(flatten)]
-        viewport: ViewportArgs,
+    /// `base` with this view's camera overrides applied.
+    fn camera(&self, base: &Camera) -> Camera {
+        Camera {
+            yaw: self.yaw.unwrap_or(base.yaw),
+            pitch: self.pitch.unwrap_or(base.pitch),
+            scale: self.zoom.unwrap_or(base.scale),
+        }
+    }
 
-        #[command(flatten)]
+    /// Output size, falling back to the shared viewport.
+    fn size(&self, viewport: &ViewportArgs) -> (u32, u32) {
+        (
+            self.width.unwrap_or(viewport.width),
+            self.height.unwrap_or(viewport.height),
+        )
+    }
+}
+
+/// Parse `path[:key=value,...]` (keys: `yaw`, `pitch`, `zoom`, `width`, `height`). The path is
+/// split at the last `:` only when overrides follow, so `C:\out.png` stays a plain path.
+fn parse_output_spec(s: &str) -> Result<OutputSpec, String> {
+    let (path, overrides) = match s.rsplit_once(':') {
+        Some((path, rest)) if rest.contains('=') => (path, rest),
+        _ => (s, ""),
+    };
+    if path.is_empty() {
+        return Err(format!("'{}' has no output path", s));
+    }
+    let mut spec = OutputSpec::new(path);
+    for pair in overrides.split(',').filter(|p| !p.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("'{}' is not key=value", pair))?;
+        let number = || {
+            value
+                .parse::<f32>()
+                .map_err(|_| format!("'{}' is not a valid number", value))
+        };
+        let pixels = || {
+            value
+                .parse::<u32>()
+                .ok()
+                .filter(|&v| v > 0)
+                .ok_or_else(|| format!("'{}' is not a positive pixel count", value))
+        };
+        match key {
+            "yaw" => spec.yaw = Some(number()?),
+            "pitch" => spec.pitch = Some(number()?),
+            "zoom" => spec.zoom = Some(parse_positive_scale(value)?),
+            "width" => spec.width = Some(pixels()?),
+            "height" => spec.height = Some(pixels()?),
+            _ => {
+                return Err(format!(
+                    "unknown view key '{}' (expected yaw, pitch, zoom, width, height)",
+                    key
+                ))
+            }
+        }
+    }
+    Ok(spec)
+}
+
+/// One `--key` of `animate`: a camera keyframe; unset fields keep the scene camera's values.
+#[derive(Clone, Debug, PartialEq)]
+struct CameraKeyArg {
+    time: f32,
+    yaw: Option<f32>,
+    pitch: Option<f32>,
+    zoom: Option<f32>,
+    easing: Easing,
+}
+
+impl CameraKeyArg {
+    fn camera(&self, scene: &Camera) -> Camera {
+        Camera {
+            yaw: self.yaw.unwrap_or(scene.yaw),
+            pitch: self.pitch.unwrap_or(scene.pitch),
+            scale: self.zoom.unwrap_or(scene.scale),
+        }
+    }
+}
+
+/// Parses `TIME[:yaw=..,pitch=..,zoom=..,ease=..]`.
+fn parse_camera_key(s: &str) -> Result<CameraKeyArg, String> {
+    let (time, fields) = s.split_once(':').unwrap_or((s, ""));
+    let mut key = CameraKeyArg {
+        time: parse_non_negative(time)?,
+        yaw: None,
+        pitch: None,
+        zoom: None,
+        easing: Easing::Linear,
+    };
+    for pair in fields.split(',').filter(|p| !p.is_empty()) {
+        let (name, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("'{}' is not key=value", pair))?;
+        let number = || {
+            value
+                .parse::<f32>()
+                .map_err(|_| format!("'{}' is not a valid number", value))
+        };
+        match name {
+            "yaw" => key.yaw = Some(number()?),
+            "pitch" => key.pitch = Some(number()?),
+            "zoom" => key.zoom = Some(parse_positive_scale(value)?),
+            "ease" => key.easing = EasingCli::from_str(value, true)?.into(),
+            _ => {
+                return Err(format!(
+                    "unknown keyframe key '{}' (expected yaw, pitch, zoom, ease)",
+                    name
+                ))
+            }
+        }
+    }
+    Ok(key)
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Render the skin to an image file (headless).
+    ///
+    /// Format is inferred from the output filename extension
+    /// (.png, .webp or .exr). Defaults to PNG.
+    Render {
+        /// Path to the skin PNG file, or a player name or UUID with --skin-dir.
+        skin: String,
+
+        /// Look SKIN up as `<name or uuid>.png` in this directory when it is not a file; repeat to
+        /// search several directories in order.
+        #[arg(long = "skin-dir", value_name = "DIR")]
+        skin_dirs: Vec<PathBuf>,
+
+        /// Draw the character with this Bedrock `geometry.json` instead of the built-in model.
+        #[arg(long, value_name = "FILE")]
+        geometry: Option<PathBuf>,
+
+        /// Model in the --geometry file to use (default: the first one).
+        #[arg(long, value_name = "ID", requires = "geometry")]
+        geometry_id: Option<String>,
+
+        /// Output image path, or `-` for a PNG on stdout. Extension determines format (.png,
+        /// .webp or .exr).
+        #[arg(default_value = "output.png")]
+        output: String,
+
+        /// Extra output as `path[:yaw=..,pitch=..,zoom=..,width=..,height=..]`; repeat to render
+        /// several views with one loaded skin (replaces OUTPUT).
+        #[arg(long = "out", value_name = "SPEC", value_parser = parse_output_spec, conflicts_with = "output")]
+        outs: Vec<OutputSpec>,
+
+        #[command(flatten)]
+        viewport: ViewportArgs,
+
+        #[command(flatten)]
+        encoding: OutputArgs,
+
+        #[command(flatten)]
+        expression: ExpressionArgs,
+
+        /// Normalize exposure so the visible skin reaches a consistent mean luminance
+        /// (overrides --exposure). Optional target in 0–1, e.g. --auto-exposure=0.5.
+        // The bare-flag value is `renderer::DEFAULT_TARGET_LUMINANCE`.
+        #[arg(long, value_name = "TARGET", num_args = 0..=1, require_equals = true, default_missing_value = "0.45", value_parser = parse_unit_interval)]
+        auto_exposure: Option<f32>,
+
+        /// Render a left- and right-eye view: side by side (twice as wide) or as a red–cyan
+        /// anaglyph. PNG and WebP only.
+        #[arg(long, value_enum)]
+        stereo: Option<StereoCli>,
+
+        /// Distance between the eyes in world units (with --stereo); larger exaggerates depth.
+        #[arg(long, default_value_t = StereoSettings::default().eye_separation, value_parser = parse_non_negative)]
+        eye_separation: f32,
+
+        /// Do not embed render metadata (version, skin hash, pose, camera) in PNG/WebP output.
+        #[arg(long)]
+        no_metadata: bool,
+
+        /// Tag PNG output as sRGB (sRGB, gAMA and cHRM chunks), so color-managed viewers and
+        /// editors show the rendered colors unchanged.
+        #[arg(long)]
+        tag_srgb: bool,
+
+        /// Write the skin's theme palette (primary, secondary, accent) as JSON to this file, or
+        /// to stdout with `-`.
+        #[arg(long, value_name = "PATH")]
+        theme: Option<PathBuf>,
+
+        /// Cape PNG to hang from the back (64x32 layout; legacy 22x17 and 46x22 capes work too).
+        #[arg(long, value_name = "PATH")]
+        cape: Option<String>,
+
+        /// Degrees the --cape swings back from hanging straight down.
+        #[arg(long, value_name = "DEG", default_value_t = DEFAULT_CAPE_ANGLE, requires = "cape", allow_hyphen_values = true)]
+        cape_angle: f32,
+
+        /// Also write a segmentation map of the first view to this PNG: every body part layer,
+        /// the headwear, each attachment and the cape in its own flat color.
+        #[arg(long, value_name = "PATH")]
+        segmentation: Option<PathBuf>,
+
+        /// Bit-identical output for identical inputs: render on the software (CPU) adapter and
+        /// encode single-threaded. Fails if no software adapter (llvmpipe, WARP) is installed.
+        #[arg(long)]
+        deterministic: bool,
+
+        /// Render only the head and its hat layer on an invisible body, zoomed onto the head
+        /// (replaces --cam-zoom and --pos-*).
+        #[arg(long)]
+        hair_only: bool,
+
+        /// Shot preset setting the camera, head turn, lights and background together: `inventory`
+        /// matches the game's inventory player model (render at 49x70 times the GUI scale),
+        /// `cape-back` a three-quarter back view for showing off capes.
+        /// Replaces --cam-*, --pos-*, --rot-*, --head-* and --lighting.
+        #[arg(long, value_enum, conflicts_with = "hair_only")]
+        shot: Option<ShotCli>,
+
+        /// Mouse position for --shot inventory, in GUI pixels from the middle of the player box
+        /// (x right, y down); the player turns towards it.
+        #[arg(long, value_name = "X,Y", requires = "shot", value_parser = parse_cursor, allow_hyphen_values = true)]
+        cursor: Option<[f32; 2]>,
+
+        #[command(flatten)]
+        scene: SceneArgs,
+    },
+    /// Open a live preview window.
+    Preview {
+        /// Path to the skin PNG file.
+        skin: String,
+
+        #[command(flatten)]
+        viewport: ViewportArgs,
+
+        #[command(flatten)]
+        scene: SceneArgs,
+
+        #[cfg(feature = "dev")]
+        #[command(flatten)]
+        dev: DevArgs,
+    },
+    /// Render an animated GIF with the camera moving along a path.
+    ///
+    /// Orbits start at the scene camera (--cam-yaw, --cam-pitch, --cam-zoom); reveals and crane
+    /// shots end on it.
+    Animate {
+        /// Path to the skin PNG file.
+        skin: String,
+
+        /// Output GIF path; a `.png` path writes an animated PNG (full color and alpha, no
+        /// palette options).
+        #[arg(default_value = "animation.gif")]
+        output: PathBuf,
+
+        /// Camera move.
+        #[arg(long, value_enum, default_value = "orbit")]
+        camera_path: CameraPathCli,
+
+        /// Camera keyframe `TIME[:yaw=..,pitch=..,zoom=..,ease=..]` (seconds; unset fields keep
+        /// the scene camera); repeat to build a custom path (replaces --camera-path).
+        #[arg(long = "key", value_name = "KEYFRAME", value_parser = parse_camera_key)]
+        keys: Vec<CameraKeyArg>,
+
+        /// Length of the --camera-path move in seconds.
+        #[arg(long, default_value_t = 3.0, value_parser = parse_positive_scale)]
+        duration: f32,
+
+        /// Frames per second.
+        #[arg(long, default_value_t = 20, value_parser = clap::value_parser!(u32).range(1..=50))]
+        fps: u32,
+
+        /// Number of turns of --camera-path orbit; negative turns the other way.
+        #[arg(long, default_value_t = 1.0, allow_hyphen_values = true)]
+        turns: f32,
+
+        /// Easing of --camera-path orbit; linear loops seamlessly.
+        #[arg(long, value_enum, default_value = "linear")]
+        easing: EasingCli,
+
+        /// Layer subtle idle motion over the pose: breathing, arm sway, occasional head turns.
+        #[arg(long)]
+        idle: bool,
+
+        /// Blink now and then (every few seconds).
+        #[arg(long)]
+        blink: bool,
+
+        /// Seed for the timing and direction of --idle head turns and --blink.
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
+        #[command(flatten)]
+        expression: ExpressionArgs,
+
+        #[command(flatten)]
+        gif: GifArgs,
+
+        #[command(flatten)]
+        viewport: ViewportArgs,
+
+        #[command(flatten)]
+        scene: SceneArgs,
+    },
+    /// Serve a live turntable of a skin as MJPEG over HTTP, for dashboards:
+    /// `<img src="http://127.0.0.1:8080/">`. Any path shows the stream.
+    ///
+    /// Frames are rendered only while someone is watching. JPEG has no transparency: the
+    /// background is black unless a --lighting preset sets one.
+    Live {
+        /// Path to the skin PNG file.
+        skin: String,
+
+        /// Address to listen on.
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+
+        /// Frames per second.
+        #[arg(long, default_value_t = 15, value_parser = clap::value_parser!(u32).range(1..=30))]
+        fps: u32,
+
+        /// Seconds per turn of the turntable.
+        #[arg(long, default_value_t = 6.0, value_parser = parse_positive_scale)]
+        turn_seconds: f32,
+
+        /// Layer subtle idle motion over the pose: breathing, arm sway, occasional head turns.
+        #[arg(long)]
+        idle: bool,
+
+        /// Most connections served at once; further ones are answered with 503.
+        #[arg(long, default_value_t = 16, value_parser = clap::value_parser!(u32).range(1..))]
+        max_viewers: u32,
+
+        #[command(flatten)]
+        viewport: ViewportArgs,
+
+        #[command(flatten)]
+        scene: SceneArgs,
+    },
+    /// Render two skins with the same pose and camera for reviewing a revision: side by side
+    /// (before on the left) or, with --blink, as a GIF alternating between them.
+    Compare {
+        /// Path to the earlier skin PNG file.
+        before: String,
+
+        /// Path to the revised skin PNG file.
+        after: String,
+
+        /// Output path: an image side by side (format from the extension), a GIF with --blink.
+        #[arg(default_value = "compare.png")]
+        output: PathBuf,
+
+        /// Alternate the two renders in a looping GIF instead of placing them side by side.
+        #[arg(long)]
+        blink: bool,
+
+        /// Renders per second of the --blink GIF.
+        #[arg(long, default_value_t = 2, requires = "blink", value_parser = clap::value_parser!(u32).range(1..=50))]
+        fps: u32,
+
+        /// Also write a heatmap of the texels that changed (PNG, at the skin's size); the skins
+        /// must have the same size.
+        #[arg(long, value_name = "PATH")]
+        heatmap: Option<PathBuf>,
+
+        #[command(flatten)]
+        viewport: ViewportArgs,
+
+        #[command(flatten)]
         scene: SceneArgs,
     },
+    /// Render a player's skin history: each skin with the same pose and camera, left to right,
+    /// above a time axis with a label under each.
+    Timeline {
+        /// Skins in order, as `LABEL=FILE` (`@SECONDS=FILE` labels with the UTC date of a Unix
+        /// time) or `FILE` (labelled with the file name).
+        #[arg(required = true, value_name = "ENTRY", value_parser = parse_timeline_entry)]
+        entries: Vec<(String, String)>,
+
+        /// Output PNG path.
+        #[arg(short, long, default_value = "timeline.png")]
+        output: PathBuf,
+
+        /// Camera of every render.
+        #[arg(long, value_enum, default_value = "isometric")]
+        view: ViewCli,
+
+        /// Posture of every render.
+        #[arg(long, value_enum, default_value = "stand")]
+        posture: PostureCli,
+
+        /// Use slim arm geometry (Alex-style, 3px arms) for every skin.
+        #[arg(long)]
+        slim: bool,
+
+        /// Width of each render in pixels.
+        #[arg(long, default_value_t = TimelineStyle::default().cell_width, value_parser = clap::value_parser!(u32).range(1..=2048))]
+        cell_width: u32,
+
+        /// Height of each render in pixels.
+        #[arg(long, default_value_t = TimelineStyle::default().cell_height, value_parser = clap::value_parser!(u32).range(1..=2048))]
+        cell_height: u32,
+
+        /// Pixel height of the labels.
+        #[arg(long, default_value_t = TimelineStyle::default().label_size, value_parser = parse_positive_scale)]
+        label_size: f32,
+
+        /// TrueType or OpenType font for the labels (Cantarell by default).
+        #[arg(long, value_name = "FILE")]
+        font: Option<PathBuf>,
+
+        /// Opaque background color as hex `rrggbb` (transparent by default).
+        #[arg(long, value_parser = parse_hex_color)]
+        background: Option<[f32; 3]>,
+    },
+    /// Ping a server and render the players it lists online as a lineup banner.
+    ///
+    /// Servers list a sample of at most about 12 players; skins are looked up by UUID, then name,
+    /// in the --skin-dir directories.
+    Banner {
+        /// Server address, `host[:port]` (port 25565 by default; SRV records are not looked up).
+        #[arg(long)]
+        server: String,
+
+        /// Look players up as `<uuid or name>.png` in this directory; repeat to search several
+        /// directories in order.
+        #[arg(long = "skin-dir", value_name = "DIR")]
+        skin_dirs: Vec<PathBuf>,
+
+        /// Skin for players not found in any --skin-dir (they are left out otherwise).
+        #[arg(long, value_name = "FILE")]
+        fallback_skin: Option<PathBuf>,
+
+        /// Output PNG path.
+        #[arg(default_value = "banner.png")]
+        output: PathBuf,
+
+        /// Banner width in pixels.
+        #[arg(long, default_value_t = 1200, value_parser = clap::value_parser!(u32).range(1..))]
+        width: u32,
+        /// Banner height in pixels.
+        #[arg(long, default_value_t = 400, value_parser = clap::value_parser!(u32).range(1..))]
+        height: u32,
+
+        /// Seconds to wait for the server.
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+        timeout: u64,
+    },
+    /// Render a profile or stat card from a JSON template.
+    Card {
+        /// Card template JSON; font and image paths in it are relative to its directory.
+        template: PathBuf,
+        /// Path to the skin PNG file.
+        skin: String,
+        /// Output PNG path.
+        #[arg(default_value = "card.png")]
+        output: PathBuf,
+        /// Use slim arm geometry (Alex-style, 3px arms).
+        #[arg(long)]
+        slim: bool,
+        /// Placeholder value for the template's texts; repeat for each, e.g.
+        /// --set username=Notch --set kills=42.
+        #[arg(long = "set", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+        values: Vec<(String, String)>,
+    },
+    /// Cut a flat face or paper-doll view from the skin, without rendering.
+    ///
+    /// Writes SVG (one rect per pixel run, crisp at any zoom) when OUTPUT ends in .svg, PNG
+    /// otherwise.
+    Flat {
+        /// Path to the skin PNG file.
+        skin: String,
+        /// Output .svg or .png path.
+        #[arg(default_value = "face.svg")]
+        output: PathBuf,
+        #[arg(long, value_enum, default_value = "face")]
+        view: FlatViewCli,
+        /// Use slim arm geometry (paper doll only).
+        #[arg(long)]
+        slim: bool,
+        /// Leave out the overlay layer (hat, jacket, sleeves, pants).
+        #[arg(long)]
+        no_overlay: bool,
+        /// Output pixels per skin pixel: the SVG's width/height, or the PNG upscale factor.
+        #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..=64))]
+        pixel_size: u32,
+    },
     /// Convert a legacy single-layer skin atlas to a square double-layer atlas.
     Convert {
         /// Input PNG (width must be twice the height).
@@ -207,16 +1446,28 @@ enum Command {
         #[arg(default_value = "output.png")]
         output: PathBuf,
     },
+    /// Print the render metadata embedded in a PNG or WebP written by `render`.
+    Inspect {
+        /// Rendered PNG or WebP file.
+        image: PathBuf,
+    },
+    /// Render a tiny test image and check it; exits non-zero if the GPU pipeline is broken.
+    SelfTest,
 }
 
 struct PreviewApp {
     renderer: Option<Renderer>,
     window: Option<Arc<Window>>,
+    lighting: Lighting,
+    background: Option<[f64; 4]>,
+    options: RenderOptions,
     character: Character,
     skin: Option<eidolon::texture::Texture>,
     camera: Camera,
     texture_path: String,
     initial_size: PhysicalSize<u32>,
+    #[cfg(feature = "dev")]
+    overrides: eidolon::renderer::AssetOverrides,
 }
 
 impl ApplicationHandler for PreviewApp {
@@ -232,6 +1483,15 @@ impl ApplicationHandler for PreviewApp {
 
         let mut renderer =
             Renderer::new_windowed(window.clone()).expect("Failed to create windowed renderer");
+        renderer.set_lighting(self.lighting);
+        renderer.set_options(self.options.clone());
+        if let Some([r, g, b, a]) = self.background {
+            renderer.set_clear_color(r, g, b, a);
+        }
+        #[cfg(feature = "dev")]
+        if let Err(e) = renderer.watch_assets(&self.overrides) {
+            tracing::error!("Asset override error: {e}");
+        }
         self.skin = Some(
             renderer
                 .load_texture(&self.texture_path)
@@ -263,6 +1523,11 @@ impl ApplicationHandler for PreviewApp {
                 }
             }
             WindowEvent::RedrawRequested => {
+                // Failures are logged by the renderer; the previous assets stay in use.
+                #[cfg(feature = "dev")]
+                if let Some(renderer) = &mut self.renderer {
+                    let _ = renderer.reload_changed_assets();
+                }
                 if let (Some(renderer), Some(skin)) = (&self.renderer, &self.skin) {
                     match renderer.render_frame(&self.character, skin, &self.camera) {
                         Ok(()) => {}
@@ -277,7 +1542,7 @@ impl ApplicationHandler for PreviewApp {
                         Err(wgpu::SurfaceError::OutOfMemory) => {
                             event_loop.exit();
                         }
-                        Err(e) => log::error!("Render error: {:?}", e),
+                        Err(e) => tracing::error!("Render error: {:?}", e),
                     }
                 }
                 if let Some(window) = &self.window {
@@ -289,74 +1554,813 @@ impl ApplicationHandler for PreviewApp {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use clap::Parser;
-
-    // ── parse_positive_scale ──
-
-    #[test]
-    fn parse_scale_valid() {
-        assert_eq!(parse_positive_scale("1.0").unwrap(), 1.0);
-        assert_eq!(parse_positive_scale("0.5").unwrap(), 0.5);
-        assert_eq!(parse_positive_scale("42").unwrap(), 42.0);
-    }
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::init();
+    let args = Args::parse();
 
-    #[test]
-    fn parse_scale_zero_fails() {
-        assert!(parse_positive_scale("0").is_err());
-        assert!(parse_positive_scale("0.0").is_err());
-    }
+    use tracing::{error, info};
+    match args.command {
+        Command::Render {
+            skin,
+            skin_dirs,
+            geometry,
+            geometry_id,
+            output,
+            outs,
+            viewport,
+            encoding,
+            expression,
+            auto_exposure,
+            stereo,
+            eye_separation,
+            no_metadata,
+            tag_srgb,
+            theme,
+            cape,
+            cape_angle,
+            segmentation,
+            deterministic,
+            hair_only,
+            shot,
+            cursor,
+            scene,
+        } => {
+            let views = if outs.is_empty() {
+                vec![OutputSpec::new(&output)]
+            } else {
+                outs
+            };
+            // Reject output paths that attempt directory traversal.
+            if views.iter().any(|view| {
+                std::path::Path::new(&view.path)
+                    .components()
+                    .any(|c| matches!(c, std::path::Component::ParentDir))
+            }) {
+                error!("Output path must not contain '..' components");
+                return Err(Box::from(
+                    "output path must not contain '..' (directory traversal)",
+                ));
+            }
 
-    #[test]
-    fn parse_scale_negative_fails() {
-        assert!(parse_positive_scale("-1.0").is_err());
-    }
+            info!("Minecraft skin renderer");
+            info!("Skin: {}", skin);
+            for view in &views {
+                let (width, height) = view.size(&viewport);
+                info!("Output: {} ({}x{})", view.path, width, height);
+            }
 
-    #[test]
-    fn parse_scale_not_a_number_fails() {
-        assert!(parse_positive_scale("abc").is_err());
-        assert!(parse_positive_scale("").is_err());
-    }
+            info!("Creating renderer...");
+            let mut renderer = if deterministic {
+                Renderer::new_deterministic()?
+            } else {
+                Renderer::new()?
+            };
+            let (lighting, background) = lighting_from_scene(&scene);
+            renderer.set_lighting(lighting);
+            if let Some([r, g, b, a]) = background {
+                renderer.set_clear_color(r, g, b, a);
+            }
+            renderer.set_options(options_from_scene(&scene));
+            renderer.set_embed_metadata(!no_metadata);
+            renderer.set_embed_srgb(tag_srgb);
+            info!("Renderer ready");
 
-    // ── format_from_filename ──
+            let (mut character, camera) = character_and_camera_from_scene(&scene);
+            let preset = shot.map(|shot| shot.preset(cursor.unwrap_or_default()));
+            if let Some(preset) = preset {
+                renderer.set_options(RenderOptions {
+                    fov: preset.fov(),
+                    ..renderer.options().clone()
+                });
+                renderer.set_lighting(preset.lighting());
+                if let Some([r, g, b, a]) = preset.background() {
+                    renderer.set_clear_color(r, g, b, a);
+                }
+            }
+            if let Some(path) = geometry {
+                let json = std::fs::read(&path)?;
+                let geometry = match geometry_id {
+                    Some(id) => BedrockGeometry::find(&json, &id)?,
+                    None => BedrockGeometry::parse_file(&json)?.remove(0),
+                };
+                info!("Using geometry {} from {}", geometry.identifier, path.display());
+                renderer.set_model_from_bedrock(character.skin_type, &geometry)?;
+            }
 
-    #[test]
-    fn format_from_filename_webp() {
-        assert!(matches!(format_from_filename("out.webp"), OutputFormat::WebP));
-        assert!(matches!(format_from_filename("/tmp/x.webp"), OutputFormat::WebP));
-    }
+            info!("Loading skin: {}", skin);
+            let skin_image = if skin_dirs.is_empty() || std::path::Path::new(&skin).is_file() {
+                SkinImage::from_file(&skin)?
+            } else {
+                let mut chain = ProviderChain::new();
+                for dir in skin_dirs {
+                    chain.push(DirectoryProvider::new(dir));
+                }
+                let data = chain.fetch(&PlayerId::parse(&skin))?;
+                SkinImage::from_memory(&data.bytes)?
+            };
+            let skin_texture = match expression.expression()? {
+                Some(expression) => renderer.upload_skin(&expression.apply(&skin_image)?),
+                None => renderer.upload_skin(&skin_image),
+            };
+            info!("Skin loaded");
+            if let Some(path) = cape {
+                let texture = renderer.upload_skin(&SkinImage::cape_from_file(&path)?);
+                let cape = Cape::new(Arc::new(texture)).with_angle(cape_angle);
+                character.equipment.cape = Some(cape);
+                info!("Cape: {}", path);
+            }
+            // Character and camera of one output; --hair-only and --shot fit them to its size.
+            let frame_view = |view: &OutputSpec| {
+                let (width, height) = view.size(&viewport);
+                let mut character = character.clone();
+                let camera = if let Some(preset) = preset {
+                    preset.frame(&mut character, width, height)
+                } else if hair_only {
+                    character.frame_hair_only_with_fov(&camera, scene.cam_fov, width, height)
+                } else {
+                    camera
+                };
+                (character, view.camera(&camera))
+            };
 
-    #[test]
-    fn format_from_filename_png() {
-        assert!(matches!(format_from_filename("out.png"), OutputFormat::Png));
-    }
+            if let Some(path) = theme {
+                let json = ThemePalette::from_skin(&skin_image, character.skin_type).to_json();
+                if path.as_os_str() == "-" {
+                    println!("{json}");
+                } else {
+                    std::fs::write(&path, json + "\n")?;
+                    info!("Theme palette: {}", path.display());
+                }
+            }
 
-    #[test]
-    fn format_from_filename_no_extension_defaults_png() {
-        assert!(matches!(format_from_filename("output"), OutputFormat::Png));
-        assert!(matches!(format_from_filename("/tmp/noext"), OutputFormat::Png));
-        assert!(matches!(format_from_filename(""), OutputFormat::Png));
-    }
+            if let Some(target) = auto_exposure {
+                let (character, camera) = frame_view(&views[0]);
+                match renderer.normalize_brightness(&character, &skin_texture, &camera, target)? {
+                    Some(stats) => info!(
+                        "Skin luminance {:.3} (contrast {:.3}); exposure set to {:+.2} stops",
+                        stats.mean_luminance,
+                        stats.contrast,
+                        renderer.options().adjustments.exposure
+                    ),
+                    None => info!("Skin not visible; exposure left unchanged"),
+                }
+            }
 
-    #[test]
-    fn format_from_filename_unknown_extension_defaults_png() {
-        assert!(matches!(format_from_filename("out.jpg"), OutputFormat::Png));
-        assert!(matches!(format_from_filename("out.bmp"), OutputFormat::Png));
-    }
+            if let Some(path) = segmentation {
+                let (width, height) = views[0].size(&viewport);
+                let (character, view_camera) = frame_view(&views[0]);
+                renderer
+                    .render_segmentation(&character, &skin_texture, &view_camera, width, height)?
+                    .to_image()
+                    .save_with_format(&path, image::ImageFormat::Png)?;
+                info!("Segmentation map: {}", path.display());
+            }
 
-    // ── PostureCli → Posture ──
+            for view in &views {
+                let (width, height) = view.size(&viewport);
+                let (character, view_camera) = frame_view(view);
+                if let Some(terminal_format) = encoding.format {
+                    let image =
+                        renderer.render(&character, &skin_texture, &view_camera, width, height)?;
+                    let text = match terminal_format {
+                        TerminalFormatCli::Ansi => {
+                            let columns = encoding.columns.unwrap_or_else(|| {
+                                std::env::var("COLUMNS")
+                                    .ok()
+                                    .and_then(|c| c.parse().ok())
+                                    .unwrap_or(80)
+                            });
+                            eidolon::terminal::to_ansi(&image, columns)
+                        }
+                        TerminalFormatCli::Kitty => eidolon::terminal::to_kitty(&image),
+                        TerminalFormatCli::Sixel => eidolon::terminal::to_sixel(&image),
+                    };
+                    print!("{text}");
+                    continue;
+                }
+                info!("Rendering {}...", view.path);
+                let format = output_format(&view.path, &encoding);
+                let sink = output_sink(&view.path);
+                match stereo {
+                    Some(layout) => {
+                        if !matches!(format, OutputFormat::Png | OutputFormat::WebP) {
+                            return Err(Box::from("--stereo supports 8-bit PNG and WebP only"));
+                        }
+                        let settings = StereoSettings {
+                            eye_separation,
+                            layout: layout.into(),
+                        };
+                        let image = renderer.render_stereo(
+                            &character,
+                            &skin_texture,
+                            &view_camera,
+                            &settings,
+                            width,
+                            height,
+                        )?;
+                        let path = format.output_path(&view.path)?;
+                        let bytes = if no_metadata {
+                            let mut bytes = Vec::new();
+                            image.write_to(
+                                &mut std::io::Cursor::new(&mut bytes),
+                                format.as_image_format(),
+                            )?;
+                            bytes
+                        } else {
+                            let mut meta = renderer.render_metadata(
+                                &character,
+                                &skin_texture,
+                                &view_camera,
+                                (width, height),
+                            );
+                            meta.insert("stereo", format!("{:?},{}", settings.layout, eye_separation));
+                            match format {
+                                OutputFormat::WebP => meta.encode_webp(&image)?,
+                                _ => meta.encode_png(&image)?,
+                            }
+                        };
+                        let bytes = if tag_srgb && format == OutputFormat::Png {
+                            color::tag_srgb_png(&bytes)?
+                        } else {
+                            bytes
+                        };
+                        sink.write(&Output::new(path, bytes))?;
+                    }
+                    None => renderer.render_to_sink(
+                        &character,
+                        &skin_texture,
+                        &view_camera,
+                        &view.path,
+                        (width, height),
+                        format,
+                        &*sink,
+                    )?,
+                }
+                info!("Done. Saved: {}", view.path);
+            }
 
-    #[test]
-    fn posture_cli_to_posture() {
-        let p: Posture = PostureCli::Stand.into();
-        assert_eq!(p.head_yaw, 0.0);
-        assert_eq!(p.left_leg_pitch, 0.0);
-    }
+            Ok(())
+        }
+        Command::Preview {
+            skin,
+            viewport,
+            scene,
+            #[cfg(feature = "dev")]
+            dev,
+        } => {
+            let (character, camera) = character_and_camera_from_scene(&scene);
+            let (lighting, background) = lighting_from_scene(&scene);
 
-    #[test]
-    fn posture_cli_wave_arms_raised() {
+            let event_loop = EventLoop::new()?;
+            let mut app = PreviewApp {
+                renderer: None,
+                window: None,
+                lighting,
+                background,
+                options: options_from_scene(&scene),
+                character,
+                skin: None,
+                camera,
+                texture_path: skin,
+                initial_size: PhysicalSize::new(viewport.width, viewport.height),
+                #[cfg(feature = "dev")]
+                overrides: eidolon::renderer::AssetOverrides {
+                    shader: dev.dev_shader,
+                    classic_model: dev.dev_classic_model,
+                    slim_model: dev.dev_slim_model,
+                },
+            };
+            event_loop.run_app(&mut app)?;
+
+            Ok(())
+        }
+        Command::Animate {
+            skin,
+            output,
+            camera_path,
+            keys,
+            duration,
+            fps,
+            turns,
+            easing,
+            idle,
+            blink,
+            seed,
+            expression,
+            gif,
+            viewport,
+            scene,
+        } => {
+            let (character, camera) = character_and_camera_from_scene(&scene);
+            let mut animation = if keys.is_empty() {
+                let path = match camera_path {
+                    CameraPathCli::Orbit => {
+                        CameraPath::orbit(camera, turns, duration, easing.into())
+                    }
+                    CameraPathCli::Reveal => CameraPath::zoom_reveal(camera, duration),
+                    CameraPathCli::Crane => CameraPath::crane(camera, duration),
+                    CameraPathCli::Still => CameraPath::new(camera),
+                };
+                Animation {
+                    duration,
+                    ..Animation::new(path, fps)
+                }
+            } else {
+                let path = keys.iter().fold(CameraPath::new(camera), |path, key| {
+                    path.key(key.time, key.camera(&camera), key.easing)
+                });
+                Animation::new(path, fps)
+            };
+            animation.idle = idle.then(|| Idle::new(seed));
+            if let Some(expression) = expression.expression()? {
+                animation.expressions.push(ExpressionKey {
+                    start: 0.0,
+                    duration: animation.duration.max(1.0),
+                    expression,
+                });
+            }
+            if blink {
+                animation
+                    .expressions
+                    .extend(ExpressionKey::blinks(seed, animation.duration));
+            }
+
+            let mut renderer = Renderer::new()?;
+            let (lighting, background) = lighting_from_scene(&scene);
+            renderer.set_lighting(lighting);
+            if let Some([r, g, b, a]) = background {
+                renderer.set_clear_color(r, g, b, a);
+            }
+            renderer.set_options(options_from_scene(&scene));
+            let skin_image = SkinImage::from_file(&skin)?;
+
+            let size = (viewport.width, viewport.height);
+            info!(
+                "Rendering {} frames ({}x{}) to {}",
+                animation.frame_count(),
+                size.0,
+                size.1,
+                output.display()
+            );
+            let frames = eidolon::animation::render_animation(
+                &mut renderer,
+                &character,
+                &skin_image,
+                &animation,
+                size,
+            )?;
+            let apng = output
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+            let encoded = if apng {
+                eidolon::animation::encode_apng(&frames, fps, &CancelToken::new())?
+            } else {
+                eidolon::animation::encode_gif_with(
+                    &frames,
+                    fps,
+                    &gif.options(),
+                    &CancelToken::new(),
+                )?
+            };
+            std::fs::write(&output, encoded)?;
+            Ok(())
+        }
+        Command::Live {
+            skin,
+            listen,
+            fps,
+            turn_seconds,
+            idle,
+            max_viewers,
+            viewport,
+            scene,
+        } => {
+            let (character, camera) = character_and_camera_from_scene(&scene);
+            let animation = Animation {
+                idle: idle.then(|| Idle::new(0)),
+                ..Animation::new(
+                    CameraPath::orbit(camera, 1.0, turn_seconds, Easing::Linear),
+                    fps,
+                )
+            };
+            let mut renderer = Renderer::new()?;
+            let (lighting, background) = lighting_from_scene(&scene);
+            renderer.set_lighting(lighting);
+            if let Some([r, g, b, a]) = background {
+                renderer.set_clear_color(r, g, b, a);
+            }
+            renderer.set_options(options_from_scene(&scene));
+            let skin_image = SkinImage::from_file(&skin)?;
+
+            let listener = std::net::TcpListener::bind(&listen)?;
+            info!("Serving {} at http://{}/", skin, listener.local_addr()?);
+            let broadcast = Arc::new(FrameBroadcast::new());
+            // Cancelled when the render loop stops, which ends every connection.
+            let shutdown = CancelToken::new();
+            {
+                let (broadcast, shutdown) = (Arc::clone(&broadcast), shutdown.clone());
+                let connections = Arc::new(AtomicU32::new(0));
+                std::thread::spawn(move || {
+                    for mut stream in listener.incoming().flatten() {
+                        if connections.fetch_add(1, Ordering::SeqCst) >= max_viewers {
+                            connections.fetch_sub(1, Ordering::SeqCst);
+                            let _ = stream.write_all(
+                                b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\
+                                  Connection: close\r\n\r\n",
+                            );
+                            continue;
+                        }
+                        let (broadcast, shutdown, connections) = (
+                            Arc::clone(&broadcast),
+                            shutdown.clone(),
+                            Arc::clone(&connections),
+                        );
+                        std::thread::spawn(move || {
+                            if let Err(e) = mjpeg::serve_connection(stream, &broadcast, &shutdown)
+                            {
+                                info!("Viewer disconnected: {e}");
+                            }
+                            connections.fetch_sub(1, Ordering::SeqCst);
+                        });
+                    }
+                });
+            }
+
+            let size = (viewport.width, viewport.height);
+            let mut frames = eidolon::animation::stream_animation(
+                &mut renderer,
+                &character,
+                &skin_image,
+                &animation,
+                size,
+            )
+            .looping();
+            let frame_duration = frames.frame_duration();
+            let mut publish_next = || -> Result<(), Box<dyn std::error::Error>> {
+                let started = std::time::Instant::now();
+                let frame = frames.next().expect("looping streams do not end")?;
+                broadcast.publish(frame.encode(image::ImageFormat::Jpeg)?);
+                if let Some(rest) = frame_duration.checked_sub(started.elapsed()) {
+                    std::thread::sleep(rest);
+                }
+                Ok(())
+            };
+            let result = loop {
+                if !broadcast.wait_for_viewers(std::time::Duration::from_secs(1)) {
+                    continue;
+                }
+                if let Err(e) = publish_next() {
+                    break Err(e);
+                }
+            };
+            shutdown.cancel();
+            result
+        }
+        Command::Compare {
+            before,
+            after,
+            output,
+            blink,
+            fps,
+            heatmap,
+            viewport,
+            scene,
+        } => {
+            let (character, camera) = character_and_camera_from_scene(&scene);
+            let mut renderer = Renderer::new()?;
+            let (lighting, background) = lighting_from_scene(&scene);
+            renderer.set_lighting(lighting);
+            if let Some([r, g, b, a]) = background {
+                renderer.set_clear_color(r, g, b, a);
+            }
+            renderer.set_options(options_from_scene(&scene));
+            let before = SkinImage::from_file(&before)?;
+            let after = SkinImage::from_file(&after)?;
+
+            if let Some(path) = heatmap {
+                let diff = TextureDiff::between(&before, &after)?;
+                info!(
+                    "{} texels changed (largest difference {})",
+                    diff.changed, diff.max_difference
+                );
+                diff.heatmap
+                    .save(&path)
+                    .map_err(|e| format!("Failed to save heatmap: {e}"))?;
+            }
+
+            let [before, after] = compare::render_pair(
+                &renderer,
+                &character,
+                &renderer.upload_skin(&before),
+                &renderer.upload_skin(&after),
+                &camera,
+                viewport.width,
+                viewport.height,
+            )?;
+            if blink {
+                std::fs::write(&output, compare::blink_gif(&before, &after, fps)?)?;
+            } else {
+                compare::side_by_side(&before, &after)
+                    .save(&output)
+                    .map_err(|e| format!("Failed to save comparison: {e}"))?;
+            }
+            info!("Comparison: {}", output.display());
+            Ok(())
+        }
+        Command::Timeline {
+            entries,
+            output,
+            view,
+            posture,
+            slim,
+            cell_width,
+            cell_height,
+            label_size,
+            font,
+            background,
+        } => {
+            let style = TimelineStyle {
+                pose: posture.into(),
+                view: view.into(),
+                cell_width,
+                cell_height,
+                label_size,
+                background: match background {
+                    Some([r, g, b]) => [r, g, b, 1.0].map(|c| (c * 255.0).round() as u8),
+                    None => TimelineStyle::default().background,
+                },
+                ..TimelineStyle::default()
+            };
+            let strip = match font {
+                Some(path) => TimelineStrip::with_font(style, std::fs::read(path)?)?,
+                None => TimelineStrip::new(style),
+            };
+            let entries = entries
+                .iter()
+                .map(|(label, file)| {
+                    let mut entry = TimelineEntry::new(label, SkinImage::from_file(file)?);
+                    if slim {
+                        entry.skin_type = SkinType::Slim;
+                    }
+                    Ok(entry)
+                })
+                .collect::<Result<Vec<_>, eidolon::error::EidolonError>>()?;
+
+            info!("Rendering {} skins to {}", entries.len(), output.display());
+            strip
+                .render(&Renderer::new()?, &entries)?
+                .save(&output)
+                .map_err(|e| format!("Failed to save timeline: {e}"))?;
+            Ok(())
+        }
+        Command::Banner {
+            server,
+            skin_dirs,
+            fallback_skin,
+            output,
+            width,
+            height,
+            timeout,
+        } => {
+            let status = eidolon::server::ping(&server, std::time::Duration::from_secs(timeout))?;
+            info!(
+                "{server}: {} ({}/{} online, {} listed)",
+                status.version,
+                status.online,
+                status.max,
+                status.players().count()
+            );
+            let mut chain = ProviderChain::new();
+            for dir in skin_dirs {
+                chain.push(DirectoryProvider::new(dir));
+            }
+            let fallback = fallback_skin
+                .map(|path| -> Result<_, std::io::Error> {
+                    Ok(eidolon::provider::SkinData {
+                        bytes: std::fs::read(path)?,
+                        skin_type: None,
+                        signature: Default::default(),
+                    })
+                })
+                .transpose()?;
+
+            let renderer = Renderer::new()?;
+            let skins = eidolon::server::fetch_lineup(
+                &renderer,
+                status.players(),
+                &chain,
+                fallback.as_ref(),
+            )?;
+            info!("Rendering {} players to {}", skins.len(), output.display());
+            eidolon::server::render_lineup(&renderer, &skins, width, height)?
+                .save(&output)
+                .map_err(|e| format!("Failed to save banner: {e}"))?;
+            Ok(())
+        }
+        Command::Card {
+            template,
+            skin,
+            output,
+            slim,
+            values,
+        } => {
+            let template = eidolon::card::CardTemplate::load(&template)?;
+            let renderer = Renderer::new()?;
+            let skin = renderer.load_texture(&skin)?;
+            let data = eidolon::card::CardData {
+                skin: &skin,
+                skin_type: if slim {
+                    SkinType::Slim
+                } else {
+                    SkinType::Classic
+                },
+                values: values.into_iter().collect(),
+            };
+            info!("Rendering card to {}", output.display());
+            template
+                .render(&renderer, &data)?
+                .save(&output)
+                .map_err(|e| format!("Failed to save card: {e}"))?;
+            Ok(())
+        }
+        Command::Flat {
+            skin,
+            output,
+            view,
+            slim,
+            no_overlay,
+            pixel_size,
+        } => {
+            let skin = SkinImage::from_file(&skin)?;
+            let image = match view {
+                FlatViewCli::Face => eidolon::flat::face(&skin, !no_overlay),
+                FlatViewCli::PaperDoll => {
+                    let skin_type = if slim {
+                        SkinType::Slim
+                    } else {
+                        SkinType::Classic
+                    };
+                    eidolon::flat::paper_doll(&skin, skin_type, !no_overlay)
+                }
+            };
+            let svg = output
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+            if svg {
+                std::fs::write(&output, eidolon::flat::to_svg(&image, pixel_size))?;
+            } else {
+                image::imageops::resize(
+                    &image,
+                    image.width() * pixel_size,
+                    image.height() * pixel_size,
+                    image::imageops::FilterType::Nearest,
+                )
+                .save(&output)
+                .map_err(|e| format!("Failed to save image: {e}"))?;
+            }
+            info!("Flat view saved to {}", output.display());
+            Ok(())
+        }
+        Command::Convert { input, output } => {
+            let img =
+                image::open(&input).map_err(|e| format!("Failed to open input image: {}", e))?;
+
+            match converter::single2double(&img) {
+                Ok(result) => {
+                    info!("Conversion OK. Double-layer skin saved to: {:?}", output);
+                    result
+                        .save(output)
+                        .map_err(|e| format!("Failed to save output image: {}", e))?;
+                    Ok(())
+                }
+                Err(e) => {
+                    error!("Conversion failed: {}", e);
+                    Err(Box::new(std::io::Error::other(e.to_string())))
+                }
+            }
+        }
+        Command::Inspect { image } => {
+            match RenderMetadata::read_file(&image)? {
+                Some(meta) => {
+                    for (key, value) in &meta.entries {
+                        println!("{key}: {value}");
+                    }
+                }
+                None => println!("No eidolon metadata in {}", image.display()),
+            }
+            Ok(())
+        }
+        Command::SelfTest => {
+            let elapsed = eidolon::request::self_test()?;
+            println!("ok ({} ms)", elapsed.as_millis());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    // ── parse_positive_scale ──
+
+    #[test]
+    fn parse_scale_valid() {
+        assert_eq!(parse_positive_scale("1.0").unwrap(), 1.0);
+        assert_eq!(parse_positive_scale("0.5").unwrap(), 0.5);
+        assert_eq!(parse_positive_scale("42").unwrap(), 42.0);
+    }
+
+    #[test]
+    fn parse_scale_zero_fails() {
+        assert!(parse_positive_scale("0").is_err());
+        assert!(parse_positive_scale("0.0").is_err());
+    }
+
+    #[test]
+    fn parse_scale_negative_fails() {
+        assert!(parse_positive_scale("-1.0").is_err());
+    }
+
+    #[test]
+    fn parse_scale_not_a_number_fails() {
+        assert!(parse_positive_scale("abc").is_err());
+        assert!(parse_positive_scale("").is_err());
+    }
+
+    // ── format_from_filename ──
+
+    #[test]
+    fn format_from_filename_webp() {
+        assert!(matches!(format_from_filename("out.webp"), OutputFormat::WebP));
+        assert!(matches!(format_from_filename("/tmp/x.webp"), OutputFormat::WebP));
+    }
+
+    #[test]
+    fn format_from_filename_png() {
+        assert!(matches!(format_from_filename("out.png"), OutputFormat::Png));
+    }
+
+    #[test]
+    fn format_from_filename_no_extension_defaults_png() {
+        assert!(matches!(format_from_filename("output"), OutputFormat::Png));
+        assert!(matches!(format_from_filename("/tmp/noext"), OutputFormat::Png));
+        assert!(matches!(format_from_filename(""), OutputFormat::Png));
+    }
+
+    #[test]
+    fn format_from_filename_unknown_extension_defaults_png() {
+        assert!(matches!(format_from_filename("out.jpg"), OutputFormat::Png));
+        assert!(matches!(format_from_filename("out.bmp"), OutputFormat::Png));
+    }
+
+    #[test]
+    fn format_from_filename_exr() {
+        assert!(matches!(format_from_filename("out.exr"), OutputFormat::Exr(_)));
+    }
+
+    #[test]
+    fn cli_render_float_outputs() {
+        let args = Args::try_parse_from([
+            "eidolon",
+            "render",
+            "skin.png",
+            "out.exr",
+            "--exr-channels",
+            "rgbaz",
+        ])
+        .expect("render exr parse");
+        match args.command {
+            Command::Render { output, encoding, .. } => match output_format(&output, &encoding) {
+                OutputFormat::Exr(channels) => assert!(channels.alpha && channels.depth),
+                other => panic!("Expected Exr, got {:?}", other),
+            },
+            _ => panic!("Expected Render"),
+        }
+
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "--bit-depth", "16"])
+            .expect("render 16-bit parse");
+        match args.command {
+            Command::Render { output, encoding, .. } => {
+                assert!(matches!(output_format(&output, &encoding), OutputFormat::Png16))
+            }
+            _ => panic!("Expected Render"),
+        }
+        assert!(Args::try_parse_from(["eidolon", "render", "skin.png", "--bit-depth", "12"]).is_err());
+    }
+
+    // ── PostureCli → Posture ──
+
+    #[test]
+    fn posture_cli_to_posture() {
+        let p: Posture = PostureCli::Stand.into();
+        assert_eq!(p.head_yaw, 0.0);
+        assert_eq!(p.left_leg_pitch, 0.0);
+    }
+
+    #[test]
+    fn posture_cli_wave_arms_raised() {
         let p: Posture = PostureCli::Wave.into();
         assert!(p.left_arm_pitch > 0.0);
     }
@@ -378,6 +2382,83 @@ mod tests {
         assert!(r.left_leg_pitch.abs() > w.left_leg_pitch.abs());
     }
 
+    // ── LightingCli → LightingPreset ──
+
+    #[test]
+    fn lighting_cli_to_preset() {
+        assert_eq!(LightingPreset::from(LightingCli::Noon), LightingPreset::Noon);
+        assert_eq!(LightingPreset::from(LightingCli::Sunset), LightingPreset::Sunset);
+        assert_eq!(LightingPreset::from(LightingCli::Moonlight), LightingPreset::Moonlight);
+        assert_eq!(LightingPreset::from(LightingCli::Nether), LightingPreset::Nether);
+        assert_eq!(LightingPreset::from(LightingCli::End), LightingPreset::End);
+    }
+
+    // ── parse_hex_color ──
+
+    #[test]
+    fn parse_hex_color_valid() {
+        assert_eq!(parse_hex_color("ffffff").unwrap(), [1.0, 1.0, 1.0]);
+        assert_eq!(parse_hex_color("#000000").unwrap(), [0.0, 0.0, 0.0]);
+        let c = parse_hex_color("ff8000").unwrap();
+        assert_eq!(c[0], 1.0);
+        assert!((c[1] - 128.0 / 255.0).abs() < 1e-6);
+        assert_eq!(c[2], 0.0);
+    }
+
+    #[test]
+    fn parse_hex_color_invalid() {
+        assert!(parse_hex_color("fff").is_err());
+        assert!(parse_hex_color("gggggg").is_err());
+        assert!(parse_hex_color("").is_err());
+    }
+
+    // ── lighting_from_scene ──
+
+    #[test]
+    fn lighting_defaults_to_neutral_rig_without_background() {
+        let (lighting, background) = lighting_from_scene(&default_scene());
+        assert_eq!(lighting, Lighting::default());
+        assert!(background.is_none());
+    }
+
+    #[test]
+    fn lighting_preset_with_rim_override() {
+        let mut scene = default_scene();
+        scene.lighting = Some(LightingCli::Moonlight);
+        scene.rim_strength = 0.8;
+        scene.rim_color = [0.5, 0.6, 1.0];
+        let (lighting, background) = lighting_from_scene(&scene);
+        assert_eq!(lighting.ambient, LightingPreset::Moonlight.lighting().ambient);
+        assert_eq!(lighting.rim_strength, 0.8);
+        assert_eq!(lighting.rim_color, [0.5, 0.6, 1.0]);
+        assert_eq!(background, Some(LightingPreset::Moonlight.background()));
+    }
+
+    // ── options_from_scene ──
+
+    #[test]
+    fn options_from_default_scene_match_defaults() {
+        assert_eq!(options_from_scene(&default_scene()), RenderOptions::default());
+    }
+
+    #[test]
+    fn options_from_scene_carries_ambient_occlusion() {
+        let mut scene = default_scene();
+        scene.ambient_occlusion = 0.7;
+        assert_eq!(options_from_scene(&scene).ambient_occlusion, 0.7);
+    }
+
+    #[test]
+    fn options_from_scene_shadows_only_when_enabled() {
+        let mut scene = default_scene();
+        scene.shadow_resolution = 512;
+        assert!(options_from_scene(&scene).shadow.is_none());
+        scene.shadows = true;
+        let shadow = options_from_scene(&scene).shadow.expect("shadows enabled");
+        assert_eq!(shadow.resolution, 512);
+        assert_eq!(shadow.ground_opacity, 0.5);
+    }
+
     // ── character_and_camera_from_scene ──
 
     fn default_scene() -> SceneArgs {
@@ -386,7 +2467,40 @@ mod tests {
             cam_yaw: 180.0,
             cam_pitch: 90.0,
             cam_zoom: 1.0,
+            cam_fov: DEFAULT_FOV,
             posture: PostureCli::Stand,
+            lighting: None,
+            rim_strength: 0.0,
+            rim_color: [1.0, 1.0, 1.0],
+            ambient_occlusion: 0.0,
+            shadows: false,
+            shadow_resolution: 1024,
+            shadow_bias: 0.003,
+            ground_shadow: 0.5,
+            depth_of_field: false,
+            dof_focus: None,
+            dof_blur: 8.0,
+            emissive_overlay: false,
+            bloom_threshold: 0.6,
+            bloom_intensity: 1.0,
+            tone_mapping: ToneMappingCli::Linear,
+            premultiplied_alpha: false,
+            alpha_cutoff: 0.01,
+            cutout: None,
+            texture_filter: TextureFilterCli::Nearest,
+            msaa: MsaaCli::Off,
+            uv_inset: 0.0,
+            exposure: 0.0,
+            gamma: 1.0,
+            saturation: 1.0,
+            hurt: None,
+            on_fire: false,
+            potion: None,
+            particles: Vec::new(),
+            glint: None,
+            glint_color: None,
+            effect_time: 0.0,
+            mannequin: false,
             head_yaw: None,
             head_pitch: None,
             left_arm_roll: None,
@@ -481,7 +2595,7 @@ mod tests {
         let args = Args::try_parse_from(["eidolon", "render", "skin.png"])
             .expect("minimal render parse");
         match args.command {
-            Command::Render { skin, output, viewport, scene } => {
+            Command::Render { skin, output, viewport, scene, .. } => {
                 assert_eq!(skin, "skin.png");
                 assert_eq!(output, "output.png");
                 assert_eq!(viewport.width, 800);
@@ -493,6 +2607,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cli_render_to_stdout() {
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "-"])
+            .expect("stdout render parse");
+        match args.command {
+            Command::Render { output, .. } => {
+                assert_eq!(output, "-");
+                assert_eq!(format_from_filename(&output), OutputFormat::Png);
+            }
+            _ => panic!("Expected Render"),
+        }
+    }
+
     #[test]
     fn cli_render_all_options() {
         let args = Args::try_parse_from([
@@ -505,7 +2632,7 @@ mod tests {
         ])
         .expect("full render parse");
         match args.command {
-            Command::Render { skin, output, viewport, scene } => {
+            Command::Render { skin, output, viewport, scene, .. } => {
                 assert_eq!(skin, "skin.png");
                 assert_eq!(output, "out.webp");
                 assert_eq!(viewport.width, 400);
@@ -525,15 +2652,571 @@ mod tests {
     }
 
     #[test]
-    fn cli_render_invalid_width_rejected() {
-        assert!(Args::try_parse_from(["eidolon", "render", "skin.png", "--width", "0"]).is_err());
+    fn cli_render_lighting_preset() {
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "--lighting", "sunset"])
+            .expect("render with lighting parse");
+        match args.command {
+            Command::Render { scene, .. } => {
+                assert!(matches!(scene.lighting, Some(LightingCli::Sunset)));
+            }
+            _ => panic!("Expected Render"),
+        }
     }
 
     #[test]
-    fn cli_render_invalid_zoom_zero_rejected() {
-        assert!(Args::try_parse_from(["eidolon", "render", "skin.png", "--cam-zoom", "0"]).is_err());
-    }
-
+    fn cli_render_lighting_defaults_to_none() {
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png"]).unwrap();
+        match args.command {
+            Command::Render { scene, .. } => assert!(scene.lighting.is_none()),
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_rim_light() {
+        let args = Args::try_parse_from([
+            "eidolon", "render", "skin.png", "--rim-strength", "0.6", "--rim-color", "#80c0ff",
+        ])
+        .expect("render with rim light parse");
+        match args.command {
+            Command::Render { scene, .. } => {
+                assert_eq!(scene.rim_strength, 0.6);
+                assert_eq!(scene.rim_color[0], 128.0 / 255.0);
+            }
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_ambient_occlusion() {
+        let args =
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--ambient-occlusion", "0.5"])
+                .expect("render with ambient occlusion parse");
+        match args.command {
+            Command::Render { scene, .. } => assert_eq!(scene.ambient_occlusion, 0.5),
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_shadow_flags() {
+        let args = Args::try_parse_from([
+            "eidolon",
+            "render",
+            "skin.png",
+            "--shadows",
+            "--shadow-resolution",
+            "2048",
+            "--shadow-bias",
+            "0.01",
+            "--ground-shadow",
+            "0.8",
+        ])
+        .expect("render with shadows parse");
+        match args.command {
+            Command::Render { scene, .. } => {
+                assert!(scene.shadows);
+                assert_eq!(scene.shadow_resolution, 2048);
+                assert_eq!(scene.shadow_bias, 0.01);
+                assert_eq!(scene.ground_shadow, 0.8);
+            }
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_shadow_defaults_match_library() {
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "--shadows"]).unwrap();
+        match args.command {
+            Command::Render { scene, .. } => {
+                assert_eq!(options_from_scene(&scene).shadow, Some(ShadowSettings::default()));
+            }
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_depth_of_field() {
+        let args = Args::try_parse_from([
+            "eidolon",
+            "render",
+            "skin.png",
+            "--depth-of-field",
+            "--dof-focus",
+            "2.5",
+            "--dof-blur",
+            "12",
+        ])
+        .expect("render with depth of field parse");
+        match args.command {
+            Command::Render { scene, .. } => {
+                let dof = options_from_scene(&scene).depth_of_field.expect("dof enabled");
+                assert_eq!(dof.focus_distance, Some(2.5));
+                assert_eq!(dof.blur_strength, 12.0);
+            }
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_depth_of_field_off_by_default() {
+        let args =
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--dof-blur", "4"]).unwrap();
+        match args.command {
+            Command::Render { scene, .. } => {
+                assert!(options_from_scene(&scene).depth_of_field.is_none())
+            }
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_emissive_overlay() {
+        let args = Args::try_parse_from([
+            "eidolon",
+            "render",
+            "skin.png",
+            "--emissive-overlay",
+            "--bloom-threshold",
+            "0.3",
+            "--bloom-intensity",
+            "2",
+        ])
+        .expect("render with emissive overlay parse");
+        match args.command {
+            Command::Render { scene, .. } => {
+                let emissive = options_from_scene(&scene).emissive_overlay.expect("enabled");
+                assert_eq!(emissive.bloom_threshold, 0.3);
+                assert_eq!(emissive.bloom_intensity, 2.0);
+            }
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_tone_mapping() {
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "--tone-mapping", "aces"])
+            .expect("render with tone mapping parse");
+        match args.command {
+            Command::Render { scene, .. } => {
+                assert_eq!(options_from_scene(&scene).tone_mapping, ToneMapping::Aces)
+            }
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_premultiplied_alpha() {
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "--premultiplied-alpha"])
+            .expect("render with premultiplied alpha parse");
+        match args.command {
+            Command::Render { scene, .. } => {
+                assert_eq!(options_from_scene(&scene).alpha_mode, AlphaMode::Premultiplied)
+            }
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_alpha_test() {
+        let alpha_test = |args: &[&str]| match Args::try_parse_from(args).unwrap().command {
+            Command::Render { scene, .. } => options_from_scene(&scene).alpha_test,
+            _ => panic!("Expected Render"),
+        };
+        assert_eq!(alpha_test(&["eidolon", "render", "skin.png"]), AlphaTest::default());
+        assert_eq!(
+            alpha_test(&["eidolon", "render", "skin.png", "--cutout", "--alpha-cutoff", "0.5"]),
+            AlphaTest::cutout(0.5)
+        );
+        assert_eq!(
+            alpha_test(&["eidolon", "render", "skin.png", "--cutout=overlay"]),
+            AlphaTest {
+                overlay: LayerAlpha::Cutout,
+                ..AlphaTest::default()
+            }
+        );
+        assert!(
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--alpha-cutoff", "2"]).is_err()
+        );
+    }
+
+    #[test]
+    fn cli_render_texture_filter() {
+        let args = Args::try_parse_from([
+            "eidolon",
+            "render",
+            "skin.png",
+            "--texture-filter",
+            "nearest-mipmapped",
+        ])
+        .unwrap();
+        match args.command {
+            Command::Render { scene, .. } => assert_eq!(
+                options_from_scene(&scene).texture_filter,
+                TextureFilter::NearestMipmapped
+            ),
+            _ => panic!("Expected Render"),
+        }
+        assert!(
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--texture-filter", "cubic"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn cli_render_msaa() {
+        let args =
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--msaa", "4"]).unwrap();
+        match args.command {
+            Command::Render { scene, .. } => {
+                assert_eq!(options_from_scene(&scene).msaa, Msaa::X4)
+            }
+            _ => panic!("Expected Render"),
+        }
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png"]).unwrap();
+        match args.command {
+            Command::Render { scene, .. } => {
+                assert_eq!(options_from_scene(&scene).msaa, Msaa::Off)
+            }
+            _ => panic!("Expected Render"),
+        }
+        assert!(Args::try_parse_from(["eidolon", "render", "skin.png", "--msaa", "3"]).is_err());
+    }
+
+    #[test]
+    fn cli_render_uv_inset() {
+        let args =
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--uv-inset", "0.01"]).unwrap();
+        match args.command {
+            Command::Render { scene, .. } => {
+                assert_eq!(options_from_scene(&scene).uv_inset, 0.01)
+            }
+            _ => panic!("Expected Render"),
+        }
+        assert!(
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--uv-inset=-1"]).is_err()
+        );
+    }
+
+    #[test]
+    fn cli_render_stereo() {
+        let args = Args::try_parse_from([
+            "eidolon",
+            "render",
+            "skin.png",
+            "--stereo",
+            "anaglyph",
+            "--eye-separation",
+            "0.2",
+        ])
+        .expect("render with stereo parse");
+        match args.command {
+            Command::Render {
+                stereo,
+                eye_separation,
+                ..
+            } => {
+                assert_eq!(stereo.map(StereoLayout::from), Some(StereoLayout::Anaglyph));
+                assert_eq!(eye_separation, 0.2);
+            }
+            _ => panic!("Expected Render"),
+        }
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png"]).unwrap();
+        match args.command {
+            Command::Render { stereo, .. } => assert!(stereo.is_none()),
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_multiple_outputs() {
+        let args = Args::try_parse_from([
+            "eidolon",
+            "render",
+            "skin.png",
+            "--out",
+            "front.png:yaw=0",
+            "--out",
+            "iso.webp:yaw=225,pitch=60,width=256",
+            "--out",
+            "plain.png",
+        ])
+        .expect("render with --out parse");
+        match args.command {
+            Command::Render { outs, viewport, .. } => {
+                assert_eq!(outs.len(), 3);
+                assert_eq!(outs[0].path, "front.png");
+                assert_eq!(outs[0].yaw, Some(0.0));
+                assert_eq!(outs[1].camera(&Camera::new()).pitch, 60.0);
+                assert_eq!(outs[1].size(&viewport), (256, 600));
+                assert_eq!(outs[2], OutputSpec::new("plain.png"));
+            }
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_out_conflicts_with_positional_output() {
+        assert!(Args::try_parse_from([
+            "eidolon",
+            "render",
+            "skin.png",
+            "out.png",
+            "--out",
+            "front.png"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn output_spec_parsing() {
+        assert_eq!(parse_output_spec(r"C:\out.png").unwrap().path, r"C:\out.png");
+        let spec = parse_output_spec(r"C:\renders\a.png:zoom=2").unwrap();
+        assert_eq!(spec.path, r"C:\renders\a.png");
+        assert_eq!(spec.zoom, Some(2.0));
+        assert!(parse_output_spec("a.png:zoom=0").is_err());
+        assert!(parse_output_spec("a.png:width=0").is_err());
+        assert!(parse_output_spec("a.png:roll=3").is_err());
+        assert!(parse_output_spec("a.png:yaw=x").is_err());
+        assert!(parse_output_spec(":yaw=1").is_err());
+    }
+
+    #[test]
+    fn cli_live_parses() {
+        let args = Args::try_parse_from(["eidolon", "live", "skin.png"]).unwrap();
+        match args.command {
+            Command::Live {
+                skin,
+                listen,
+                fps,
+                turn_seconds,
+                idle,
+                max_viewers,
+                ..
+            } => {
+                assert_eq!((skin.as_str(), listen.as_str()), ("skin.png", "127.0.0.1:8080"));
+                assert_eq!((fps, turn_seconds, idle, max_viewers), (15, 6.0, false, 16));
+            }
+            _ => panic!("Expected Live"),
+        }
+        let args = Args::try_parse_from([
+            "eidolon", "live", "skin.png", "--listen", "0.0.0.0:9000", "--fps", "30",
+            "--turn-seconds", "4", "--idle",
+        ])
+        .expect("live parse");
+        match args.command {
+            Command::Live {
+                listen,
+                fps,
+                turn_seconds,
+                idle,
+                ..
+            } => assert_eq!((listen.as_str(), fps, turn_seconds, idle), ("0.0.0.0:9000", 30, 4.0, true)),
+            _ => panic!("Expected Live"),
+        }
+        assert!(Args::try_parse_from(["eidolon", "live", "skin.png", "--fps", "60"]).is_err());
+        assert!(Args::try_parse_from(["eidolon", "live", "skin.png", "--max-viewers", "0"]).is_err());
+    }
+
+    #[test]
+    fn cli_animate_camera_paths() {
+        let args = Args::try_parse_from(["eidolon", "animate", "skin.png"]).unwrap();
+        match args.command {
+            Command::Animate {
+                output,
+                camera_path,
+                keys,
+                fps,
+                easing,
+                gif,
+                ..
+            } => {
+                assert_eq!(gif.options(), GifOptions::default());
+                assert_eq!(output, PathBuf::from("animation.gif"));
+                assert_eq!(camera_path, CameraPathCli::Orbit);
+                assert!(keys.is_empty());
+                assert_eq!((fps, easing), (20, EasingCli::Linear));
+            }
+            _ => panic!("Expected Animate"),
+        }
+        let args = Args::try_parse_from([
+            "eidolon", "animate", "skin.png", "spin.gif", "--camera-path", "crane", "--turns", "-2",
+            "--key", "0.5:yaw=90,ease=ease-out", "--key", "2",
+        ])
+        .expect("animate parse");
+        match args.command {
+            Command::Animate {
+                camera_path,
+                keys,
+                turns,
+                ..
+            } => {
+                assert_eq!((camera_path, turns), (CameraPathCli::Crane, -2.0));
+                assert_eq!(keys.len(), 2);
+                assert_eq!(keys[0].yaw, Some(90.0));
+                assert_eq!(keys[0].easing, Easing::EaseOut);
+                assert_eq!(keys[1].time, 2.0);
+            }
+            _ => panic!("Expected Animate"),
+        }
+        assert!(Args::try_parse_from(["eidolon", "animate", "skin.png", "--fps", "0"]).is_err());
+        let args = Args::try_parse_from([
+            "eidolon", "animate", "skin.png", "--colors", "64", "--global-palette", "--dither",
+            "floyd-steinberg", "--opaque", "--matte", "ffffff", "--full-frames",
+        ])
+        .expect("gif options parse");
+        match args.command {
+            Command::Animate { gif, .. } => assert_eq!(
+                gif.options(),
+                GifOptions {
+                    palette_size: 64,
+                    palette: GifPalette::Global,
+                    dither: Dither::FloydSteinberg,
+                    transparent: false,
+                    matte: [255, 255, 255],
+                    delta_frames: false,
+                }
+            ),
+            _ => panic!("Expected Animate"),
+        }
+        assert!(Args::try_parse_from(["eidolon", "animate", "skin.png", "--colors", "1"]).is_err());
+        let args = Args::try_parse_from([
+            "eidolon", "animate", "skin.png", "--camera-path", "still", "--idle", "--seed", "9",
+        ])
+        .expect("idle parse");
+        match args.command {
+            Command::Animate {
+                camera_path,
+                idle,
+                seed,
+                ..
+            } => assert_eq!((camera_path, idle, seed), (CameraPathCli::Still, true, 9)),
+            _ => panic!("Expected Animate"),
+        }
+        let args = Args::try_parse_from([
+            "eidolon", "animate", "skin.png", "--blink", "--expression", "wink",
+        ])
+        .expect("expression parse");
+        match args.command {
+            Command::Animate {
+                blink, expression, ..
+            } => {
+                assert!(blink);
+                assert_eq!(expression.expression().unwrap(), Some(Expression::Wink));
+            }
+            _ => panic!("Expected Animate"),
+        }
+    }
+
+    #[test]
+    fn camera_key_parsing() {
+        let key = parse_camera_key("1.5:pitch=60,zoom=2").unwrap();
+        assert_eq!((key.time, key.yaw, key.easing), (1.5, None, Easing::Linear));
+        let camera = key.camera(&Camera::new());
+        assert_eq!((camera.yaw, camera.pitch, camera.scale), (180.0, 60.0, 2.0));
+        assert!(parse_camera_key("-1").is_err());
+        assert!(parse_camera_key("1:zoom=0").is_err());
+        assert!(parse_camera_key("1:ease=bounce").is_err());
+        assert!(parse_camera_key("1:roll=3").is_err());
+    }
+
+    #[test]
+    fn cli_render_auto_exposure() {
+        use eidolon::renderer::DEFAULT_TARGET_LUMINANCE;
+
+        let args = Args::try_parse_from(["eidolon", "render", "--auto-exposure", "skin.png"])
+            .expect("render with auto exposure parse");
+        match args.command {
+            Command::Render { skin, auto_exposure, .. } => {
+                assert_eq!(skin, "skin.png");
+                assert_eq!(auto_exposure, Some(DEFAULT_TARGET_LUMINANCE));
+            }
+            _ => panic!("Expected Render"),
+        }
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "--auto-exposure=0.6"])
+            .expect("render with auto exposure target parse");
+        match args.command {
+            Command::Render { auto_exposure, .. } => assert_eq!(auto_exposure, Some(0.6)),
+            _ => panic!("Expected Render"),
+        }
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png"]).unwrap();
+        match args.command {
+            Command::Render { auto_exposure, .. } => assert_eq!(auto_exposure, None),
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_color_adjustments() {
+        let args = Args::try_parse_from([
+            "eidolon",
+            "render",
+            "skin.png",
+            "--exposure",
+            "-0.5",
+            "--gamma",
+            "1.2",
+            "--saturation",
+            "0",
+        ])
+        .expect("render with adjustments parse");
+        match args.command {
+            Command::Render { scene, .. } => {
+                let adjustments = options_from_scene(&scene).adjustments;
+                assert_eq!(adjustments.exposure, -0.5);
+                assert_eq!(adjustments.gamma, 1.2);
+                assert_eq!(adjustments.saturation, 0.0);
+            }
+            _ => panic!("Expected Render"),
+        }
+        assert!(Args::try_parse_from(["eidolon", "render", "skin.png", "--gamma", "0"]).is_err());
+        assert!(
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--saturation", "-1"]).is_err()
+        );
+    }
+
+    #[test]
+    fn cli_render_bloom_threshold_out_of_range_rejected() {
+        assert!(Args::try_parse_from([
+            "eidolon",
+            "render",
+            "skin.png",
+            "--emissive-overlay",
+            "--bloom-threshold",
+            "1.5",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn cli_render_ambient_occlusion_out_of_range_rejected() {
+        for value in ["-0.1", "1.5", "abc"] {
+            assert!(
+                Args::try_parse_from(["eidolon", "render", "skin.png", "--ambient-occlusion", value])
+                    .is_err(),
+                "{} accepted",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn cli_render_invalid_lighting_rejected() {
+        assert!(
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--lighting", "dusk"]).is_err()
+        );
+    }
+
+    #[test]
+    fn cli_render_invalid_width_rejected() {
+        assert!(Args::try_parse_from(["eidolon", "render", "skin.png", "--width", "0"]).is_err());
+    }
+
+    #[test]
+    fn cli_render_invalid_zoom_zero_rejected() {
+        assert!(Args::try_parse_from(["eidolon", "render", "skin.png", "--cam-zoom", "0"]).is_err());
+    }
+
     #[test]
     fn cli_render_invalid_zoom_negative_rejected() {
         assert!(Args::try_parse_from(["eidolon", "render", "skin.png", "--cam-zoom", "-1"]).is_err());
@@ -546,12 +3229,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cli_render_skin_dirs() {
+        let args = Args::try_parse_from([
+            "eidolon", "render", "Notch", "--skin-dir", "skins", "--skin-dir", "fallback",
+        ])
+        .unwrap();
+        match args.command {
+            Command::Render { skin, skin_dirs, .. } => {
+                assert_eq!(skin, "Notch");
+                assert_eq!(skin_dirs, [PathBuf::from("skins"), PathBuf::from("fallback")]);
+            }
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_geometry() {
+        let args = Args::try_parse_from([
+            "eidolon", "render", "skin.png", "--geometry", "geometry.json", "--geometry-id",
+            "geometry.custom",
+        ])
+        .unwrap();
+        match args.command {
+            Command::Render { geometry, geometry_id, .. } => {
+                assert_eq!(geometry, Some(PathBuf::from("geometry.json")));
+                assert_eq!(geometry_id.as_deref(), Some("geometry.custom"));
+            }
+            _ => panic!("Expected Render"),
+        }
+        assert!(
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--geometry-id", "x"]).is_err(),
+            "--geometry-id requires --geometry"
+        );
+    }
+
     #[test]
     fn cli_preview_minimal() {
         let args = Args::try_parse_from(["eidolon", "preview", "skin.png"])
             .expect("minimal preview parse");
         match args.command {
-            Command::Preview { skin, viewport, scene } => {
+            Command::Preview {
+                skin,
+                viewport,
+                scene,
+                ..
+            } => {
                 assert_eq!(skin, "skin.png");
                 assert_eq!(viewport.width, 800);
                 assert_eq!(viewport.height, 600);
@@ -561,6 +3284,29 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "dev")]
+    #[test]
+    fn cli_preview_dev_overrides() {
+        let args = Args::try_parse_from([
+            "eidolon",
+            "preview",
+            "skin.png",
+            "--dev-shader",
+            "skin.wgsl",
+            "--dev-slim-model",
+            "slim.obj",
+        ])
+        .expect("dev preview parse");
+        match args.command {
+            Command::Preview { dev, .. } => {
+                assert_eq!(dev.dev_shader, Some(PathBuf::from("skin.wgsl")));
+                assert_eq!(dev.dev_classic_model, None);
+                assert_eq!(dev.dev_slim_model, Some(PathBuf::from("slim.obj")));
+            }
+            _ => panic!("Expected Preview"),
+        }
+    }
+
     #[test]
     fn cli_preview_with_options() {
         let args = Args::try_parse_from([
@@ -570,7 +3316,12 @@ mod tests {
         ])
         .expect("preview with options parse");
         match args.command {
-            Command::Preview { skin, viewport, scene } => {
+            Command::Preview {
+                skin,
+                viewport,
+                scene,
+                ..
+            } => {
                 assert_eq!(skin, "skin.png");
                 assert_eq!(viewport.width, 1024);
                 assert_eq!(viewport.height, 768);
@@ -609,112 +3360,462 @@ mod tests {
     }
 
     #[test]
-    fn cli_missing_subcommand_rejected() {
-        assert!(Args::try_parse_from(["eidolon"]).is_err());
+    fn cli_compare_parses() {
+        let args = Args::try_parse_from(["eidolon", "compare", "old.png", "new.png"]).unwrap();
+        match args.command {
+            Command::Compare {
+                before,
+                after,
+                output,
+                blink,
+                heatmap,
+                ..
+            } => {
+                assert_eq!((before.as_str(), after.as_str()), ("old.png", "new.png"));
+                assert_eq!(output, PathBuf::from("compare.png"));
+                assert!(!blink);
+                assert_eq!(heatmap, None);
+            }
+            _ => panic!("Expected Compare"),
+        }
+        let args = Args::try_parse_from([
+            "eidolon", "compare", "old.png", "new.png", "review.gif", "--blink", "--fps", "4",
+            "--heatmap", "texels.png", "--posture", "wave",
+        ])
+        .unwrap();
+        match args.command {
+            Command::Compare {
+                blink, fps, heatmap, ..
+            } => {
+                assert!(blink);
+                assert_eq!(fps, 4);
+                assert_eq!(heatmap, Some(PathBuf::from("texels.png")));
+            }
+            _ => panic!("Expected Compare"),
+        }
+        // The frame rate only applies to the blink GIF.
+        assert!(Args::try_parse_from(["eidolon", "compare", "a.png", "b.png", "--fps", "4"]).is_err());
     }
 
     #[test]
-    fn cli_render_missing_skin_rejected() {
-        assert!(Args::try_parse_from(["eidolon", "render"]).is_err());
+    fn cli_timeline_parses() {
+        let args = Args::try_parse_from([
+            "eidolon",
+            "timeline",
+            "@1409598000=skins/a.png",
+            "Summer=b.png",
+            "skins/latest.png",
+            "--view",
+            "front",
+            "--background",
+            "ffffff",
+        ])
+        .unwrap();
+        match args.command {
+            Command::Timeline {
+                entries,
+                output,
+                view,
+                background,
+                ..
+            } => {
+                let labels: Vec<_> = entries.iter().map(|(label, _)| label.as_str()).collect();
+                assert_eq!(labels, ["2014-09-01", "Summer", "latest"]);
+                assert_eq!(entries[0].1, "skins/a.png");
+                assert_eq!(output, PathBuf::from("timeline.png"));
+                assert_eq!(view, ViewCli::Front);
+                assert_eq!(background, Some([1.0, 1.0, 1.0]));
+            }
+            _ => panic!("Expected Timeline"),
+        }
+        assert!(Args::try_parse_from(["eidolon", "timeline"]).is_err());
+        assert!(Args::try_parse_from(["eidolon", "timeline", "@soon=a.png"]).is_err());
+        assert!(Args::try_parse_from(["eidolon", "timeline", "Empty="]).is_err());
     }
 
     #[test]
-    fn cli_render_help_accepted() {
-        // --help should print and exit
-        let result = Args::try_parse_from(["eidolon", "render", "--help"]);
-        assert!(result.is_err()); // clap exits on help by default
+    fn cli_banner_parses() {
+        let args = Args::try_parse_from([
+            "eidolon", "banner", "--server", "mc.example.org", "--skin-dir", "skins", "--skin-dir",
+            "more", "lineup.png",
+        ])
+        .expect("banner parse");
+        match args.command {
+            Command::Banner {
+                server,
+                skin_dirs,
+                fallback_skin,
+                output,
+                width,
+                height,
+                timeout,
+            } => {
+                assert_eq!(server, "mc.example.org");
+                assert_eq!(skin_dirs, [PathBuf::from("skins"), PathBuf::from("more")]);
+                assert_eq!(fallback_skin, None);
+                assert_eq!(output, PathBuf::from("lineup.png"));
+                assert_eq!((width, height, timeout), (1200, 400, 5));
+            }
+            _ => panic!("Expected Banner"),
+        }
+        assert!(Args::try_parse_from(["eidolon", "banner", "banner.png"]).is_err());
     }
-}
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::init();
-    let args = Args::parse();
+    #[test]
+    fn cli_card_parses() {
+        let args = Args::try_parse_from([
+            "eidolon", "card", "card.json", "skin.png", "--slim", "--set", "username=Notch", "--set",
+            "motto=a=b",
+        ])
+        .expect("card parse");
+        match args.command {
+            Command::Card {
+                template,
+                skin,
+                output,
+                slim,
+                values,
+            } => {
+                assert_eq!(template, PathBuf::from("card.json"));
+                assert_eq!(skin, "skin.png");
+                assert_eq!(output, PathBuf::from("card.png"));
+                assert!(slim);
+                assert_eq!(
+                    values,
+                    [
+                        ("username".to_string(), "Notch".to_string()),
+                        ("motto".to_string(), "a=b".to_string())
+                    ]
+                );
+            }
+            _ => panic!("Expected Card"),
+        }
+        assert!(
+            Args::try_parse_from(["eidolon", "card", "card.json", "skin.png", "--set", "=x"])
+                .is_err()
+        );
+    }
 
-    use log::{error, info};
-    match args.command {
-        Command::Render {
-            skin,
-            output,
-            viewport,
-            scene,
-        } => {
-            // Reject output paths that attempt directory traversal.
-            if std::path::Path::new(&output)
-                .components()
-                .any(|c| matches!(c, std::path::Component::ParentDir))
-            {
-                error!("Output path must not contain '..' components");
-                return Err(Box::from(
-                    "output path must not contain '..' (directory traversal)",
-                ));
+    #[test]
+    fn cli_flat_parses() {
+        let args = Args::try_parse_from([
+            "eidolon", "flat", "skin.png", "doll.png", "--view", "paper-doll", "--slim",
+            "--no-overlay", "--pixel-size", "4",
+        ])
+        .expect("flat parse");
+        match args.command {
+            Command::Flat {
+                skin,
+                output,
+                view,
+                slim,
+                no_overlay,
+                pixel_size,
+            } => {
+                assert_eq!(skin, "skin.png");
+                assert_eq!(output, PathBuf::from("doll.png"));
+                assert_eq!(view, FlatViewCli::PaperDoll);
+                assert!(slim && no_overlay);
+                assert_eq!(pixel_size, 4);
+            }
+            _ => panic!("Expected Flat"),
+        }
+        let args = Args::try_parse_from(["eidolon", "flat", "skin.png"]).unwrap();
+        match args.command {
+            Command::Flat {
+                output, view, pixel_size, ..
+            } => {
+                assert_eq!(output, PathBuf::from("face.svg"));
+                assert_eq!((view, pixel_size), (FlatViewCli::Face, 8));
             }
+            _ => panic!("Expected Flat"),
+        }
+    }
 
-            info!("Minecraft skin renderer");
-            info!("Skin: {}", skin);
-            info!("Output: {} ({}x{})", output, viewport.width, viewport.height);
+    #[test]
+    fn cli_render_terminal_format() {
+        let args = Args::try_parse_from([
+            "eidolon", "render", "skin.png", "--format", "ansi", "--columns", "60",
+        ])
+        .expect("ansi parse");
+        match args.command {
+            Command::Render { encoding, .. } => {
+                assert_eq!(encoding.format, Some(TerminalFormatCli::Ansi));
+                assert_eq!(encoding.columns, Some(60));
+            }
+            _ => panic!("Expected Render"),
+        }
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png"]).unwrap();
+        match args.command {
+            Command::Render { encoding, .. } => assert_eq!(encoding.format, None),
+            _ => panic!("Expected Render"),
+        }
+        for (name, format) in [
+            ("kitty", TerminalFormatCli::Kitty),
+            ("sixel", TerminalFormatCli::Sixel),
+        ] {
+            let args =
+                Args::try_parse_from(["eidolon", "render", "skin.png", "--format", name]).unwrap();
+            match args.command {
+                Command::Render { encoding, .. } => assert_eq!(encoding.format, Some(format)),
+                _ => panic!("Expected Render"),
+            }
+        }
+        assert!(Args::try_parse_from([
+            "eidolon", "render", "skin.png", "--format", "ansi", "--stereo", "anaglyph",
+        ])
+        .is_err());
+    }
 
-            info!("Creating renderer...");
-            let renderer = Renderer::new()?;
-            info!("Renderer ready");
+    #[test]
+    fn cli_render_hair_only() {
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "--hair-only"]).unwrap();
+        match args.command {
+            Command::Render { hair_only, .. } => assert!(hair_only),
+            _ => panic!("Expected Render"),
+        }
+    }
 
-            let (character, camera) = character_and_camera_from_scene(&scene);
+    #[test]
+    fn cli_render_inventory_shot() {
+        let args = Args::try_parse_from([
+            "eidolon", "render", "skin.png", "--shot", "inventory", "--cursor", "-12,8.5",
+        ])
+        .unwrap();
+        match args.command {
+            Command::Render { shot, cursor, .. } => {
+                assert_eq!(shot, Some(ShotCli::Inventory));
+                assert_eq!(cursor, Some([-12.0, 8.5]));
+            }
+            _ => panic!("Expected Render"),
+        }
+        assert!(parse_cursor("12").is_err());
+        assert!(parse_cursor("a,1").is_err());
+        let args =
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--shot", "cape-back"]).unwrap();
+        match args.command {
+            Command::Render { shot, .. } => assert_eq!(shot, Some(ShotCli::CapeBack)),
+            _ => panic!("Expected Render"),
+        }
+        // The cursor only steers a shot.
+        assert!(Args::try_parse_from(["eidolon", "render", "skin.png", "--cursor", "1,2"]).is_err());
+    }
 
-            info!("Loading skin: {}", skin);
-            let skin_texture = renderer.load_texture(&skin)?;
-            info!("Skin loaded");
+    #[test]
+    fn cli_render_cape() {
+        let args = Args::try_parse_from([
+            "eidolon", "render", "skin.png", "--cape", "cape.png", "--cape-angle", "-4",
+        ])
+        .unwrap();
+        match args.command {
+            Command::Render {
+                cape, cape_angle, ..
+            } => {
+                assert_eq!(cape.as_deref(), Some("cape.png"));
+                assert_eq!(cape_angle, -4.0);
+            }
+            _ => panic!("Expected Render"),
+        }
+        let args =
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--cape", "c.png"]).unwrap();
+        match args.command {
+            Command::Render { cape_angle, .. } => assert_eq!(cape_angle, DEFAULT_CAPE_ANGLE),
+            _ => panic!("Expected Render"),
+        }
+        // The angle only swings a cape.
+        assert!(
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--cape-angle", "20"]).is_err()
+        );
+    }
 
-            info!("Rendering...");
-            let output_format = format_from_filename(&output);
-            renderer.render_to_image(
-                &character,
-                &skin_texture,
-                &camera,
-                &output,
-                (viewport.width, viewport.height),
-                output_format,
-            )?;
-            info!("Done. Saved: {}", output);
+    #[test]
+    fn cli_render_mannequin() {
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "--mannequin"]).unwrap();
+        match args.command {
+            Command::Render { scene, .. } => assert!(options_from_scene(&scene).mannequin_fallback),
+            _ => panic!("Expected Render"),
+        }
+    }
 
-            Ok(())
+    #[test]
+    fn cli_render_status_effects() {
+        let args = Args::try_parse_from([
+            "eidolon", "render", "skin.png", "--hurt", "--on-fire", "--potion", "8833cc",
+            "--effect-time", "0.5",
+        ])
+        .expect("status parse");
+        match args.command {
+            Command::Render { scene, .. } => {
+                let status = options_from_scene(&scene).status;
+                assert_eq!(status.hurt, 1.0);
+                assert!(status.on_fire);
+                assert_eq!(status.potion, Some(parse_hex_color("8833cc").unwrap()));
+                assert_eq!(status.time, 0.5);
+            }
+            _ => panic!("Expected Render"),
         }
-        Command::Preview {
-            skin,
-            viewport,
-            scene,
-        } => {
-            let (character, camera) = character_and_camera_from_scene(&scene);
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "--hurt=0.5"]).unwrap();
+        match args.command {
+            Command::Render { scene, .. } => assert_eq!(scene.hurt, Some(0.5)),
+            _ => panic!("Expected Render"),
+        }
+        assert!(Args::try_parse_from(["eidolon", "render", "skin.png", "--potion", "purple"]).is_err());
+    }
 
-            let event_loop = EventLoop::new()?;
-            let mut app = PreviewApp {
-                renderer: None,
-                window: None,
-                character,
-                skin: None,
-                camera,
-                texture_path: skin,
-                initial_size: PhysicalSize::new(viewport.width, viewport.height),
-            };
-            event_loop.run_app(&mut app)?;
+    #[test]
+    fn cli_render_glint() {
+        let glint_of = |args: &[&str]| {
+            let args = Args::try_parse_from(args).expect("glint parse");
+            match args.command {
+                Command::Render { scene, .. } => options_from_scene(&scene).glint,
+                _ => panic!("Expected Render"),
+            }
+        };
+        assert_eq!(glint_of(&["eidolon", "render", "skin.png"]), None);
+        assert_eq!(
+            glint_of(&["eidolon", "render", "skin.png", "--glint"]),
+            Some(EnchantmentGlint::all())
+        );
+        let some = glint_of(&[
+            "eidolon", "render", "skin.png", "--glint=head,left-leg", "--glint-color", "3366ff",
+        ])
+        .unwrap();
+        assert_eq!(some.bones, [Bone::Head, Bone::LeftLeg]);
+        assert_eq!(some.color, parse_hex_color("3366ff").unwrap());
+        assert!(Args::try_parse_from(["eidolon", "render", "skin.png", "--glint-color", "3366ff"]).is_err());
+        assert!(Args::try_parse_from(["eidolon", "render", "skin.png", "--glint=tail"]).is_err());
+    }
 
-            Ok(())
+    #[test]
+    fn cli_render_particles() {
+        let args = Args::try_parse_from([
+            "eidolon", "render", "skin.png", "--particles", "hearts", "--particles",
+            "crit-sparks@right-arm", "--effect-time", "0.25",
+        ])
+        .expect("particles parse");
+        match args.command {
+            Command::Render { scene, .. } => {
+                let particles = options_from_scene(&scene).particles;
+                assert_eq!(particles.time, 0.25);
+                assert_eq!(
+                    particles.emitters,
+                    vec![
+                        ParticleEmitter::preset(ParticlePreset::Hearts),
+                        ParticleEmitter::preset(ParticlePreset::CritSparks)
+                            .attached_to(Bone::RightArm),
+                    ]
+                );
+            }
+            _ => panic!("Expected Render"),
         }
-        Command::Convert { input, output } => {
-            let img =
-                image::open(&input).map_err(|e| format!("Failed to open input image: {}", e))?;
+        for bad in ["confetti", "hearts@tail", "hearts@"] {
+            assert!(
+                Args::try_parse_from(["eidolon", "render", "skin.png", "--particles", bad]).is_err(),
+                "{bad}"
+            );
+        }
+    }
 
-            match converter::single2double(&img) {
-                Ok(result) => {
-                    info!("Conversion OK. Double-layer skin saved to: {:?}", output);
-                    result
-                        .save(output)
-                        .map_err(|e| format!("Failed to save output image: {}", e))?;
-                    Ok(())
-                }
-                Err(e) => {
-                    error!("Conversion failed: {}", e);
-                    Err(Box::new(std::io::Error::other(e.to_string())))
-                }
+    #[test]
+    fn cli_self_test_parses() {
+        let args = Args::try_parse_from(["eidolon", "self-test"]).expect("self-test parse");
+        assert!(matches!(args.command, Command::SelfTest));
+    }
+
+    #[test]
+    fn cli_inspect_parses() {
+        let args = Args::try_parse_from(["eidolon", "inspect", "out.png"]).expect("inspect parse");
+        match args.command {
+            Command::Inspect { image } => assert_eq!(image, PathBuf::from("out.png")),
+            _ => panic!("Expected Inspect"),
+        }
+    }
+
+    #[test]
+    fn cli_render_theme() {
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "--theme", "theme.json"])
+            .unwrap();
+        match args.command {
+            Command::Render { theme, .. } => assert_eq!(theme, Some(PathBuf::from("theme.json"))),
+            _ => panic!("Expected Render"),
+        }
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png"]).unwrap();
+        match args.command {
+            Command::Render { theme, .. } => assert_eq!(theme, None),
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_segmentation() {
+        let args =
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--segmentation", "parts.png"])
+                .unwrap();
+        match args.command {
+            Command::Render { segmentation, .. } => {
+                assert_eq!(segmentation, Some(PathBuf::from("parts.png")))
             }
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_metadata_on_by_default() {
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png"]).unwrap();
+        match args.command {
+            Command::Render { no_metadata, .. } => assert!(!no_metadata),
+            _ => panic!("Expected Render"),
+        }
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "--no-metadata"]).unwrap();
+        match args.command {
+            Command::Render { no_metadata, .. } => assert!(no_metadata),
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_tag_srgb() {
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png"]).unwrap();
+        match args.command {
+            Command::Render { tag_srgb, .. } => assert!(!tag_srgb),
+            _ => panic!("Expected Render"),
+        }
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png", "--tag-srgb"]).unwrap();
+        match args.command {
+            Command::Render { tag_srgb, .. } => assert!(tag_srgb),
+            _ => panic!("Expected Render"),
+        }
+    }
+
+    #[test]
+    fn cli_render_deterministic() {
+        let args = Args::try_parse_from(["eidolon", "render", "skin.png"]).unwrap();
+        match args.command {
+            Command::Render { deterministic, .. } => assert!(!deterministic),
+            _ => panic!("Expected Render"),
+        }
+        let args =
+            Args::try_parse_from(["eidolon", "render", "skin.png", "--deterministic"]).unwrap();
+        match args.command {
+            Command::Render { deterministic, .. } => assert!(deterministic),
+            _ => panic!("Expected Render"),
         }
     }
+
+    #[test]
+    fn cli_missing_subcommand_rejected() {
+        assert!(Args::try_parse_from(["eidolon"]).is_err());
+    }
+
+    #[test]
+    fn cli_render_missing_skin_rejected() {
+        assert!(Args::try_parse_from(["eidolon", "render"]).is_err());
+    }
+
+    #[test]
+    fn cli_render_help_accepted() {
+        // --help should print and exit
+        let result = Args::try_parse_from(["eidolon", "render", "--help"]);
+        assert!(result.is_err()); // clap exits on help by default
+    }
 }
@@ -0,0 +1,359 @@
+//! Render metadata embedded in output images, so cached avatars can be invalidated when render
+//! parameters or the renderer version change.
+//!
+//! PNG files carry one `iTXt` chunk per entry (keyword `eidolon:<key>`) plus a `Software` `tEXt`
+//! chunk; WebP files carry an XMP packet with the entries as `eidolon:<key>` attributes.
+
+use std::collections::BTreeMap;
+use std::io::Cursor;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use image::{ImageBuffer, Rgba, RgbaImage};
+
+use crate::camera::Camera;
+use crate::character::{Character, SkinType};
+use crate::error::EidolonError;
+use crate::texture::SkinId;
+
+/// Prefix of the PNG keywords and XMP attributes written by this module.
+const PREFIX: &str = "eidolon:";
+/// XMP namespace of the `eidolon:` attributes.
+const XMP_NAMESPACE: &str = "https://github.com/bingling-sama/Eidolon/ns/render/1.0/";
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// 64-bit FNV-1a, continuing from `hash` (start with [`fnv1a_start`]). Stable across platforms
+/// and Rust versions, unlike `std`'s hasher.
+pub(crate) fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+pub(crate) const fn fnv1a_start() -> u64 {
+    FNV_OFFSET
+}
+
+/// Key/value render parameters, sorted by key. Keys are lowercase ASCII words joined by `-`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderMetadata {
+    pub entries: BTreeMap<String, String>,
+}
+
+impl RenderMetadata {
+    /// Describe a render: library version, skin hash and type, posture, camera, output size and
+    /// a hash of the remaining settings (`settings`, e.g. the `Debug` form of the light rig and
+    /// render options).
+    pub fn describe(
+        character: &Character,
+        skin_hash: u64,
+        camera: &Camera,
+        size: (u32, u32),
+        settings: &str,
+    ) -> Self {
+        let p = &character.posture;
+        let mut meta = Self::default();
+        meta.insert("version", env!("CARGO_PKG_VERSION"));
+        meta.insert("skin-hash", SkinId(skin_hash).to_string());
+        meta.insert(
+            "skin-type",
+            match character.skin_type {
+                SkinType::Classic => "classic",
+                SkinType::Slim => "slim",
+            },
+        );
+        meta.insert(
+            "posture",
+            [
+                p.head_yaw,
+                p.head_pitch,
+                p.left_arm_roll,
+                p.left_arm_pitch,
+                p.right_arm_roll,
+                p.right_arm_pitch,
+                p.left_leg_pitch,
+                p.right_leg_pitch,
+            ]
+            .map(|a| a.to_string())
+            .join(","),
+        );
+        meta.insert(
+            "camera",
+            format!("yaw={},pitch={},zoom={}", camera.yaw, camera.pitch, camera.scale),
+        );
+        meta.insert("size", format!("{}x{}", size.0, size.1));
+        if !character.part_texture_overrides.is_empty() {
+            let overrides: Vec<_> = character
+                .part_texture_overrides
+                .iter()
+                .map(|o| {
+                    let layer = match o.layer {
+                        Some(layer) => format!("/{layer:?}"),
+                        None => String::new(),
+                    };
+                    let part = format!("{:?}{layer}", o.bone).to_lowercase();
+                    format!("{part}={}", SkinId(o.texture.content_hash()))
+                })
+                .collect();
+            meta.insert("part-textures", overrides.join(","));
+        }
+        if let Some(headwear) = &character.equipment.head {
+            let shape = format!("{:?}", headwear.shape).to_lowercase();
+            meta.insert(
+                "headwear",
+                format!("{shape}={}", SkinId(headwear.texture.content_hash())),
+            );
+        }
+        if let Some(cape) = &character.equipment.cape {
+            meta.insert(
+                "cape",
+                format!("{},angle={}", SkinId(cape.texture.content_hash()), cape.angle),
+            );
+        }
+        if !character.attachments.is_empty() {
+            let attachments: Vec<_> = character
+                .attachments
+                .iter()
+                .map(|a| {
+                    let bone = format!("{:?}", a.bone).to_lowercase();
+                    format!("{}@{bone}", a.name)
+                })
+                .collect();
+            meta.insert("attachments", attachments.join(","));
+        }
+        meta.insert(
+            "settings-hash",
+            format!("{:016x}", fnv1a(fnv1a_start(), settings.as_bytes())),
+        );
+        meta
+    }
+
+    /// Set `key` (without the `eidolon:` prefix) to `value`.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.entries.insert(key.into(), value.into());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(String::as_str)
+    }
+
+    /// Encode an 8-bit RGBA image as PNG with these entries.
+    pub fn encode_png(&self, image: &RgbaImage) -> Result<Vec<u8>, EidolonError> {
+        self.encode_png_raw(image.width(), image.height(), png::BitDepth::Eight, image.as_raw())
+    }
+
+    /// Encode a 16-bit RGBA image as PNG with these entries.
+    pub fn encode_png16(
+        &self,
+        image: &ImageBuffer<Rgba<u16>, Vec<u16>>,
+    ) -> Result<Vec<u8>, EidolonError> {
+        let bytes: Vec<u8> = image.as_raw().iter().flat_map(|v| v.to_be_bytes()).collect();
+        self.encode_png_raw(image.width(), image.height(), png::BitDepth::Sixteen, &bytes)
+    }
+
+    fn encode_png_raw(
+        &self,
+        width: u32,
+        height: u32,
+        depth: png::BitDepth,
+        data: &[u8],
+    ) -> Result<Vec<u8>, EidolonError> {
+        let err = |e: png::EncodingError| EidolonError::texture(format!("failed to encode PNG: {e}"));
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(depth);
+        encoder
+            .add_text_chunk(
+                "Software".to_string(),
+                format!("eidolon {}", env!("CARGO_PKG_VERSION")),
+            )
+            .map_err(err)?;
+        for (key, value) in &self.entries {
+            encoder
+                .add_itxt_chunk(format!("{PREFIX}{key}"), value.clone())
+                .map_err(err)?;
+        }
+        let mut writer = encoder.write_header().map_err(err)?;
+        writer.write_image_data(data).map_err(err)?;
+        writer.finish().map_err(err)?;
+        Ok(out)
+    }
+
+    /// Encode an RGBA image as lossless WebP with these entries as XMP.
+    pub fn encode_webp(&self, image: &RgbaImage) -> Result<Vec<u8>, EidolonError> {
+        let mut out = Vec::new();
+        let mut encoder = image_webp::WebPEncoder::new(&mut out);
+        encoder.set_xmp_metadata(self.to_xmp().into_bytes());
+        encoder
+            .encode(
+                image.as_raw(),
+                image.width(),
+                image.height(),
+                image_webp::ColorType::Rgba8,
+            )
+            .map_err(|e| EidolonError::texture(format!("failed to encode WebP: {e}")))?;
+        Ok(out)
+    }
+
+    /// Read the entries of a PNG or WebP file's bytes. `Ok(None)` if the image carries none.
+    pub fn read(bytes: &[u8]) -> Result<Option<Self>, EidolonError> {
+        let meta = if bytes.starts_with(b"\x89PNG") {
+            Self::read_png(bytes)?
+        } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            let mut decoder = image_webp::WebPDecoder::new(Cursor::new(bytes))
+                .map_err(|e| EidolonError::texture(format!("failed to read WebP: {e}")))?;
+            let xmp = decoder
+                .xmp_metadata()
+                .map_err(|e| EidolonError::texture(format!("failed to read WebP XMP: {e}")))?;
+            Self::from_xmp(&String::from_utf8_lossy(&xmp.unwrap_or_default()))
+        } else {
+            return Err(EidolonError::texture("metadata can only be read from PNG or WebP"));
+        };
+        Ok((!meta.entries.is_empty()).then_some(meta))
+    }
+
+    /// [`RenderMetadata::read`] on a file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_file(path: impl AsRef<Path>) -> Result<Option<Self>, EidolonError> {
+        Self::read(&std::fs::read(path)?)
+    }
+
+    fn read_png(bytes: &[u8]) -> Result<Self, EidolonError> {
+        let reader = png::Decoder::new(Cursor::new(bytes))
+            .read_info()
+            .map_err(|e| EidolonError::texture(format!("failed to read PNG: {e}")))?;
+        let mut meta = Self::default();
+        for chunk in &reader.info().utf8_text {
+            if let (Some(key), Ok(text)) = (chunk.keyword.strip_prefix(PREFIX), chunk.get_text()) {
+                meta.insert(key, text);
+            }
+        }
+        Ok(meta)
+    }
+
+    fn to_xmp(&self) -> String {
+        let attributes: String = self
+            .entries
+            .iter()
+            .map(|(key, value)| format!(" {PREFIX}{key}=\"{}\"", xml_escape(value)))
+            .collect();
+        format!(
+            "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF \
+             xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"><rdf:Description \
+             rdf:about=\"\" xmlns:eidolon=\"{XMP_NAMESPACE}\"{attributes}/></rdf:RDF></x:xmpmeta>"
+        )
+    }
+
+    /// Parse the `eidolon:key="value"` attributes written by [`RenderMetadata::to_xmp`].
+    fn from_xmp(xmp: &str) -> Self {
+        let mut meta = Self::default();
+        let mut rest = xmp;
+        while let Some(start) = rest.find(PREFIX) {
+            rest = &rest[start + PREFIX.len()..];
+            let Some((key, after)) = rest.split_once("=\"") else {
+                break;
+            };
+            let Some((value, after)) = after.split_once('"') else {
+                break;
+            };
+            if !key.is_empty() && key.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-') {
+                meta.insert(key, xml_unescape(value));
+            }
+            rest = after;
+        }
+        meta
+    }
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&quot;", "\"")
+        .replace("&gt;", ">")
+        .replace("&lt;", "<")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> RenderMetadata {
+        RenderMetadata::describe(
+            &Character::new(),
+            0xabc,
+            &Camera::new(),
+            (800, 600),
+            "settings",
+        )
+    }
+
+    #[test]
+    fn describe_fills_standard_keys() {
+        let meta = sample();
+        assert_eq!(meta.get("version"), Some(env!("CARGO_PKG_VERSION")));
+        assert_eq!(meta.get("skin-hash"), Some("0000000000000abc"));
+        assert_eq!(meta.get("skin-type"), Some("classic"));
+        assert_eq!(meta.get("camera"), Some("yaw=180,pitch=90,zoom=1"));
+        assert_eq!(meta.get("size"), Some("800x600"));
+        assert_eq!(meta.get("posture"), Some("0,0,0,0,0,0,0,0"));
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let mut meta = sample();
+        meta.insert("note", "ünïcode & <xml>");
+        let image = RgbaImage::from_pixel(3, 2, Rgba([1, 2, 3, 4]));
+        let bytes = meta.encode_png(&image).unwrap();
+        assert_eq!(RenderMetadata::read(&bytes).unwrap(), Some(meta));
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        assert_eq!(decoded, image);
+    }
+
+    #[test]
+    fn png16_round_trip() {
+        let meta = sample();
+        let image = ImageBuffer::from_pixel(2, 2, Rgba([1u16, 2, 65535, 300]));
+        let bytes = meta.encode_png16(&image).unwrap();
+        assert_eq!(RenderMetadata::read(&bytes).unwrap(), Some(meta));
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgba16();
+        assert_eq!(decoded, image);
+    }
+
+    #[test]
+    fn webp_round_trip() {
+        let mut meta = sample();
+        meta.insert("note", "a \"quoted\" & <tagged> value");
+        let image = RgbaImage::from_pixel(3, 2, Rgba([10, 20, 30, 255]));
+        let bytes = meta.encode_webp(&image).unwrap();
+        assert_eq!(RenderMetadata::read(&bytes).unwrap(), Some(meta));
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        assert_eq!(decoded, image);
+    }
+
+    #[test]
+    fn plain_images_have_no_metadata() {
+        let image = RgbaImage::from_pixel(1, 1, Rgba([0, 0, 0, 0]));
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        assert_eq!(RenderMetadata::read(&png).unwrap(), None);
+        assert!(RenderMetadata::read(b"GIF89a").is_err());
+    }
+
+    #[test]
+    fn fnv1a_matches_reference() {
+        assert_eq!(fnv1a(fnv1a_start(), b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(fnv1a_start(), b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
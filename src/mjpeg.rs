@@ -0,0 +1,277 @@
+//! Live previews as Motion JPEG over HTTP: a `multipart/x-mixed-replace` response that browsers
+//! show as a moving `<img>`, so a dashboard can show a rotating avatar without WebGL.
+//!
+//! One render loop publishes JPEG frames to a [`FrameBroadcast`] (for example from a looping
+//! [`crate::animation::stream_animation`]); any number of viewers, each on its own thread, take
+//! the latest frame and write it to their connection ([`serve_connection`], or [`write_part`]
+//! from a handler of your own HTTP server). A viewer that falls behind skips frames rather than
+//! queueing them.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use tracing::debug;
+
+use crate::cancel::CancelToken;
+
+/// Separator between the frames of the stream.
+pub const BOUNDARY: &str = "eidolon-frame";
+
+/// `Content-Type` of an MJPEG response.
+pub const CONTENT_TYPE: &str = "multipart/x-mixed-replace; boundary=eidolon-frame";
+
+/// How long viewers wait for a frame before checking whether they should stop.
+const VIEWER_POLL: Duration = Duration::from_millis(250);
+
+/// Largest request head [`serve_connection`] reads before answering.
+const MAX_REQUEST_HEAD: usize = 8192;
+
+/// How long [`serve_connection`] waits on a client sending its request or taking a frame before
+/// dropping the connection.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Write one JPEG `frame` as a part of an MJPEG body.
+pub fn write_part(writer: &mut impl Write, frame: &[u8]) -> io::Result<()> {
+    write!(
+        writer,
+        "--{BOUNDARY}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+        frame.len()
+    )?;
+    writer.write_all(frame)?;
+    writer.write_all(b"\r\n")?;
+    writer.flush()
+}
+
+/// The latest frame of a live preview, shared between the thread rendering it and the threads
+/// sending it to viewers.
+#[derive(Debug, Default)]
+pub struct FrameBroadcast {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Number of frames published so far, and the last of them.
+    sequence: u64,
+    frame: Option<Arc<Vec<u8>>>,
+    viewers: usize,
+}
+
+impl FrameBroadcast {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the current frame and wake the viewers.
+    pub fn publish(&self, frame: Vec<u8>) {
+        let mut state = self.state.lock().expect("broadcast lock poisoned");
+        state.sequence += 1;
+        state.frame = Some(Arc::new(frame));
+        self.changed.notify_all();
+    }
+
+    /// Number of connected [`Viewer`]s.
+    pub fn viewers(&self) -> usize {
+        self.state.lock().expect("broadcast lock poisoned").viewers
+    }
+
+    /// Block until at least one viewer is connected or `timeout` passes, and return whether one
+    /// is; render loops call this to stay idle while nobody watches.
+    pub fn wait_for_viewers(&self, timeout: Duration) -> bool {
+        let state = self.state.lock().expect("broadcast lock poisoned");
+        let (state, _) = self
+            .changed
+            .wait_timeout_while(state, timeout, |state| state.viewers == 0)
+            .expect("broadcast lock poisoned");
+        state.viewers > 0
+    }
+
+    /// Register a viewer; it counts in [`FrameBroadcast::viewers`] until dropped.
+    pub fn subscribe(self: &Arc<Self>) -> Viewer {
+        let mut state = self.state.lock().expect("broadcast lock poisoned");
+        state.viewers += 1;
+        self.changed.notify_all();
+        Viewer {
+            broadcast: Arc::clone(self),
+            seen: 0,
+        }
+    }
+}
+
+/// A connection watching a [`FrameBroadcast`].
+#[derive(Debug)]
+pub struct Viewer {
+    broadcast: Arc<FrameBroadcast>,
+    /// Sequence number of the last frame taken.
+    seen: u64,
+}
+
+impl Viewer {
+    /// The newest frame this viewer has not had yet, waiting up to `timeout` for one.
+    pub fn next_frame(&mut self, timeout: Duration) -> Option<Arc<Vec<u8>>> {
+        let state = self
+            .broadcast
+            .state
+            .lock()
+            .expect("broadcast lock poisoned");
+        let (state, _) = self
+            .broadcast
+            .changed
+            .wait_timeout_while(state, timeout, |state| state.sequence == self.seen)
+            .expect("broadcast lock poisoned");
+        if state.sequence == self.seen {
+            return None;
+        }
+        self.seen = state.sequence;
+        state.frame.clone()
+    }
+}
+
+impl Drop for Viewer {
+    fn drop(&mut self) {
+        let mut state = self
+            .broadcast
+            .state
+            .lock()
+            .expect("broadcast lock poisoned");
+        state.viewers -= 1;
+    }
+}
+
+/// Answer an HTTP request on `stream` with the frames of `broadcast` as MJPEG until the client
+/// disconnects, stalls, or `cancel` is cancelled. The request itself is read but not inspected,
+/// so any path shows the stream.
+pub fn serve_connection(
+    stream: TcpStream,
+    broadcast: &Arc<FrameBroadcast>,
+    cancel: &CancelToken,
+) -> io::Result<()> {
+    let peer = stream.peer_addr().ok();
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?).take(MAX_REQUEST_HEAD as u64);
+    loop {
+        let mut line = Vec::new();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 || line == b"\r\n" || line == b"\n" {
+            break;
+        }
+    }
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: {CONTENT_TYPE}\r\nCache-Control: no-cache, no-store\r\n\
+         Connection: close\r\n\r\n"
+    )?;
+    debug!(?peer, "MJPEG viewer connected");
+    let mut viewer = broadcast.subscribe();
+    let result = loop {
+        if cancel.is_cancelled() {
+            break Ok(());
+        }
+        if let Some(frame) = viewer.next_frame(VIEWER_POLL) {
+            if let Err(e) = write_part(&mut stream, &frame) {
+                break Err(e);
+            }
+        }
+    };
+    debug!(?peer, "MJPEG viewer left");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn parts_carry_their_length() {
+        let mut body = Vec::new();
+        write_part(&mut body, b"jpeg").unwrap();
+        assert_eq!(
+            body,
+            b"--eidolon-frame\r\nContent-Type: image/jpeg\r\nContent-Length: 4\r\n\r\njpeg\r\n"
+        );
+        assert!(CONTENT_TYPE.ends_with(BOUNDARY));
+    }
+
+    #[test]
+    fn viewers_get_the_latest_frame_once() {
+        let broadcast = Arc::new(FrameBroadcast::new());
+        assert!(!broadcast.wait_for_viewers(Duration::ZERO));
+        let mut viewer = broadcast.subscribe();
+        assert_eq!(broadcast.viewers(), 1);
+        assert!(broadcast.wait_for_viewers(Duration::ZERO));
+        assert_eq!(viewer.next_frame(Duration::ZERO), None);
+        broadcast.publish(vec![1]);
+        broadcast.publish(vec![2]);
+        // A slow viewer skips to the newest frame.
+        assert_eq!(viewer.next_frame(Duration::ZERO).as_deref(), Some(&vec![2]));
+        assert_eq!(viewer.next_frame(Duration::ZERO), None);
+        drop(viewer);
+        assert_eq!(broadcast.viewers(), 0);
+    }
+
+    #[test]
+    fn connections_receive_an_mjpeg_response() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let broadcast = Arc::new(FrameBroadcast::new());
+        let cancel = CancelToken::new();
+        let server = {
+            let (broadcast, cancel) = (Arc::clone(&broadcast), cancel.clone());
+            std::thread::spawn(move || {
+                let (stream, _) = listener.accept().unwrap();
+                serve_connection(stream, &broadcast, &cancel)
+            })
+        };
+
+        let mut client = TcpStream::connect(address).unwrap();
+        client
+            .write_all(b"GET /live HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        assert!(broadcast.wait_for_viewers(Duration::from_secs(5)));
+        broadcast.publish(b"frame".to_vec());
+        let expected = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {CONTENT_TYPE}\r\nCache-Control: no-cache, \
+             no-store\r\nConnection: close\r\n\r\n--{BOUNDARY}\r\nContent-Type: image/jpeg\r\n\
+             Content-Length: 5\r\n\r\nframe\r\n"
+        );
+        let mut response = vec![0; expected.len()];
+        client.read_exact(&mut response).unwrap();
+        assert_eq!(String::from_utf8(response).unwrap(), expected);
+
+        cancel.cancel();
+        server.join().unwrap().unwrap();
+        assert_eq!(broadcast.viewers(), 0);
+    }
+
+    #[test]
+    fn endless_request_heads_are_cut_short() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let broadcast = Arc::new(FrameBroadcast::new());
+        let cancel = CancelToken::new();
+        let server = {
+            let (broadcast, cancel) = (Arc::clone(&broadcast), cancel.clone());
+            std::thread::spawn(move || {
+                let (stream, _) = listener.accept().unwrap();
+                serve_connection(stream, &broadcast, &cancel)
+            })
+        };
+
+        let mut client = TcpStream::connect(address).unwrap();
+        client.write_all(&[b'a'; MAX_REQUEST_HEAD * 2]).unwrap();
+        // The server answers once it has read the limit, without a line break ever arriving.
+        assert!(broadcast.wait_for_viewers(Duration::from_secs(5)));
+        let mut status = [0; 15];
+        client.read_exact(&mut status).unwrap();
+        assert_eq!(&status, b"HTTP/1.1 200 OK");
+
+        cancel.cancel();
+        server.join().unwrap().unwrap();
+    }
+}
@@ -1,14 +1,16 @@
 //! Loads the rigged Minecraft player mesh from OBJ assets.
 
+use crate::bedrock::BedrockGeometry;
+use crate::cape;
 use crate::error::EidolonError;
-use log::info;
+use tracing::{debug, info};
 use std::collections::HashMap;
 use tobj::{load_obj, GPU_LOAD_OPTIONS};
 use wgpu::util::DeviceExt;
 
 /// Vertex layout: position, normal, UV (matches the skin shader `VertexInput`).
 #[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct TexturedVertex {
     pub position: [f32; 3],
     pub normal: [f32; 3],
@@ -41,10 +43,168 @@ impl TexturedVertex {
     }
 }
 
+/// Axis-aligned bounding box of a mesh in model space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Bounds {
+    /// Smallest box containing every vertex position (all zeros for no vertices).
+    pub fn from_vertices(vertices: &[TexturedVertex]) -> Self {
+        let Some(first) = vertices.first() else {
+            return Self {
+                min: [0.0; 3],
+                max: [0.0; 3],
+            };
+        };
+        vertices.iter().fold(
+            Self {
+                min: first.position,
+                max: first.position,
+            },
+            |b, v| Self {
+                min: std::array::from_fn(|i| b.min[i].min(v.position[i])),
+                max: std::array::from_fn(|i| b.max[i].max(v.position[i])),
+            },
+        )
+    }
+
+    /// The eight corners of the box.
+    pub fn corners(&self) -> [[f32; 3]; 8] {
+        std::array::from_fn(|i| {
+            let pick = |axis: usize| {
+                if i & (1 << axis) == 0 {
+                    self.min[axis]
+                } else {
+                    self.max[axis]
+                }
+            };
+            [pick(0), pick(1), pick(2)]
+        })
+    }
+}
+
+/// Triangles of an OBJ mesh loaded with [`GPU_LOAD_OPTIONS`]. V is flipped: OBJ texture
+/// coordinates start at the bottom of the image.
+pub(crate) fn tobj_vertices(mesh: &tobj::Mesh) -> Vec<TexturedVertex> {
+    let mut vertices = Vec::new();
+    let positions: Vec<_> = mesh.positions.chunks(3).collect();
+    let normals: Vec<_> = mesh.normals.chunks(3).collect();
+    let texcoords: Vec<_> = mesh.texcoords.chunks(2).collect();
+
+    for i in 0..mesh.indices.len() {
+        let pos_idx = mesh.indices[i] as usize;
+        let pos = [positions[pos_idx][0], positions[pos_idx][1], positions[pos_idx][2]];
+
+        let nml_idx = if !mesh.normal_indices.is_empty() {
+            mesh.normal_indices[i] as usize
+        } else {
+            pos_idx
+        };
+        let nml = if nml_idx < normals.len() {
+            [normals[nml_idx][0], normals[nml_idx][1], normals[nml_idx][2]]
+        } else {
+            [0.0, 1.0, 0.0]
+        };
+
+        let tex_idx = if !mesh.texcoord_indices.is_empty() {
+            mesh.texcoord_indices[i] as usize
+        } else {
+            pos_idx
+        };
+        let tex = if tex_idx < texcoords.len() {
+            [texcoords[tex_idx][0], 1.0 - texcoords[tex_idx][1]]
+        } else {
+            [0.0, 0.0]
+        };
+
+        vertices.push(TexturedVertex {
+            position: pos,
+            normal: nml,
+            texture: tex,
+        });
+    }
+    vertices
+}
+
+/// Move the texture coordinates of every triangle in `vertices` `inset` (in texture coordinate
+/// units) into the triangle's UV rectangle, on each side.
+///
+/// The box unwraps of the player models, headwear and Bedrock geometry split each face into two
+/// triangles that both span the face's whole UV rectangle, so this insets every face by the same
+/// amount and sampling never lands exactly on a face boundary, where the texel picked depends on
+/// rounding. Insets are clamped to half the rectangle; faces without UV extent are left alone.
+pub fn inset_uvs(vertices: &mut [TexturedVertex], inset: f32) {
+    for triangle in vertices.chunks_exact_mut(3) {
+        for axis in 0..2 {
+            let (min, max) = triangle.iter().fold((f32::MAX, f32::MIN), |(min, max), v| {
+                (min.min(v.texture[axis]), max.max(v.texture[axis]))
+            });
+            let inset = inset.min((max - min) / 2.0);
+            if inset <= 0.0 {
+                continue;
+            }
+            for vertex in triangle.iter_mut() {
+                let uv = &mut vertex.texture[axis];
+                if *uv == min {
+                    *uv += inset;
+                } else if *uv == max {
+                    *uv -= inset;
+                }
+            }
+        }
+    }
+}
+
 /// Indexed triangle mesh uploaded as a single vertex buffer.
 pub struct ModelPart {
     pub vertex_buffer: wgpu::Buffer,
     pub vertex_count: u32,
+    /// Model-space bounds of the vertices; used to skip parts outside the view.
+    pub bounds: Bounds,
+    /// The vertices as loaded, before any [`ModelPart::set_uv_inset`].
+    pub(crate) vertices: Vec<TexturedVertex>,
+}
+
+impl ModelPart {
+    pub(crate) fn upload(device: &wgpu::Device, name: &str, vertices: &[TexturedVertex]) -> Self {
+        // Empty parts keep one unused vertex: draw loops bind every part, and a buffer cannot be
+        // bound as an empty slice.
+        let placeholder = [TexturedVertex {
+            position: [0.0; 3],
+            normal: [0.0, 1.0, 0.0],
+            texture: [0.0; 2],
+        }];
+        let contents = if vertices.is_empty() { &placeholder[..] } else { vertices };
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("Vertex Buffer: {name}")),
+            contents: bytemuck::cast_slice(contents),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        Self {
+            vertex_buffer,
+            vertex_count: vertices.len() as u32,
+            bounds: Bounds::from_vertices(vertices),
+            vertices: vertices.to_vec(),
+        }
+    }
+
+    /// Re-upload the vertices with their texture coordinates inset by `inset` ([`inset_uvs`]);
+    /// `0.0` restores them as loaded.
+    pub(crate) fn set_uv_inset(&mut self, device: &wgpu::Device, inset: f32) {
+        if self.vertices.is_empty() {
+            return;
+        }
+        let mut vertices = self.vertices.clone();
+        inset_uvs(&mut vertices, inset);
+        self.vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer: UV inset"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+    }
 }
 
 /// One body region: opaque `main` mesh plus `layer` overlay (hat/body/armor layer).
@@ -53,7 +213,7 @@ pub struct BodyPart {
     pub layer: ModelPart,
 }
 
-/// Full player model: six body parts, each with main + layer geometry.
+/// Full player model: six body parts, each with main + layer geometry, and the cape.
 pub struct Model {
     pub head: BodyPart,
     pub body: BodyPart,
@@ -61,22 +221,41 @@ pub struct Model {
     pub left_arm: BodyPart,
     pub right_leg: BodyPart,
     pub left_leg: BodyPart,
+    /// Drawn for [`crate::character::Equipment::cape`], in the body's model space hanging
+    /// straight down; textured by the cape.
+    pub cape: ModelPart,
 }
 
 impl Model {
+    /// Re-upload every part with its texture coordinates inset by `inset` ([`inset_uvs`]).
+    pub(crate) fn set_uv_inset(&mut self, device: &wgpu::Device, inset: f32) {
+        for part in [
+            &mut self.head,
+            &mut self.body,
+            &mut self.right_arm,
+            &mut self.left_arm,
+            &mut self.right_leg,
+            &mut self.left_leg,
+        ] {
+            part.main.set_uv_inset(device, inset);
+            part.layer.set_uv_inset(device, inset);
+        }
+        self.cape.set_uv_inset(device, inset);
+    }
+
     /// Load an OBJ from a file path where each object name maps to a fixed body part.
     ///
     /// Required object names: `Head`, `Hat Layer`, `Body`, `Body Layer`, `Right Arm`,
     /// `Right Arm Layer`, `Left Arm`, `Left Arm Layer`, `Right Leg`, `Right Leg Layer`,
-    /// `Left Leg`, `Left Leg Layer`.
+    /// `Left Leg`, `Left Leg Layer`. An optional `Cape` object replaces the standard cape.
     pub fn load_from_obj(
         device: &wgpu::Device,
         path: &str,
     ) -> Result<Self, EidolonError> {
-        info!("Loading OBJ file: {}", path);
+        info!(path, "loading OBJ model");
         let (models, _materials) = load_obj(path, &GPU_LOAD_OPTIONS)
             .map_err(|e| EidolonError::model(format!("failed to load OBJ '{}': {}", path, e)))?;
-        info!("OBJ file loaded with {} objects", models.len());
+        debug!(objects = models.len(), "parsed OBJ");
         Self::build_from_tobj(device, models)
     }
 
@@ -86,17 +265,32 @@ impl Model {
         data: &[u8],
         name_hint: &str,
     ) -> Result<Self, EidolonError> {
-        info!("Loading OBJ from bytes ({})", name_hint);
+        info!(name = name_hint, "loading OBJ model from memory");
         let (models, _materials) = tobj::load_obj_buf(
             &mut std::io::Cursor::new(data),
             &GPU_LOAD_OPTIONS,
-            |_| unreachable!("no material files when loading from bytes"),
+            |_| Err(tobj::LoadError::OpenFileFailed),
         )
         .map_err(|e| EidolonError::model(format!("failed to parse OBJ bytes ({}): {}", name_hint, e)))?;
-        info!("OBJ bytes loaded with {} objects", models.len());
+        debug!(objects = models.len(), "parsed OBJ");
         Self::build_from_tobj(device, models)
     }
 
+    /// Build the model from Bedrock geometry. Parts without bones (a geometry with no hat
+    /// layer, say) are empty; the cape is the standard one.
+    pub fn from_bedrock(
+        device: &wgpu::Device,
+        geometry: &BedrockGeometry,
+    ) -> Result<Self, EidolonError> {
+        info!(identifier = %geometry.identifier, "building Bedrock geometry");
+        let parts = geometry
+            .part_vertices()
+            .into_iter()
+            .map(|(name, vertices)| (name.to_string(), ModelPart::upload(device, name, &vertices)))
+            .collect();
+        Self::from_parts(device, parts)
+    }
+
     fn build_from_tobj(
         device: &wgpu::Device,
         models: Vec<tobj::Model>,
@@ -109,62 +303,24 @@ impl Model {
                 continue;
             }
 
-            let mut vertices_data = Vec::new();
-            let positions: Vec<_> = mesh.positions.chunks(3).collect();
-            let normals: Vec<_> = mesh.normals.chunks(3).collect();
-            let texcoords: Vec<_> = mesh.texcoords.chunks(2).collect();
-
-            for i in 0..mesh.indices.len() {
-                let pos_idx = mesh.indices[i] as usize;
-                let pos = [positions[pos_idx][0], positions[pos_idx][1], positions[pos_idx][2]];
-
-                let nml_idx = if !mesh.normal_indices.is_empty() {
-                    mesh.normal_indices[i] as usize
-                } else {
-                    pos_idx
-                };
-                let nml = if nml_idx < normals.len() {
-                    [normals[nml_idx][0], normals[nml_idx][1], normals[nml_idx][2]]
-                } else {
-                    [0.0, 1.0, 0.0]
-                };
-
-                let tex_idx = if !mesh.texcoord_indices.is_empty() {
-                    mesh.texcoord_indices[i] as usize
-                } else {
-                    pos_idx
-                };
-                let tex = if tex_idx < texcoords.len() {
-                    [texcoords[tex_idx][0], 1.0 - texcoords[tex_idx][1]]
-                } else {
-                    [0.0, 0.0]
-                };
-
-                vertices_data.push(TexturedVertex {
-                    position: pos,
-                    normal: nml,
-                    texture: tex,
-                });
-            }
+            let vertices_data = tobj_vertices(mesh);
 
             if vertices_data.is_empty() {
                 continue;
             }
 
-            let vertex_count = vertices_data.len() as u32;
-            let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some(&format!("Vertex Buffer: {}", model.name)),
-                contents: bytemuck::cast_slice(&vertices_data),
-                usage: wgpu::BufferUsages::VERTEX,
-            });
-            let model_part = ModelPart {
-                vertex_buffer,
-                vertex_count,
-            };
-            info!("Loaded part: {}", model.name);
+            let model_part = ModelPart::upload(device, &model.name, &vertices_data);
+            debug!(part = %model.name, "uploaded model part");
             parts.insert(model.name, model_part);
         }
 
+        Self::from_parts(device, parts)
+    }
+
+    fn from_parts(
+        device: &wgpu::Device,
+        mut parts: HashMap<String, ModelPart>,
+    ) -> Result<Self, EidolonError> {
         fn extract_part(
             parts: &mut HashMap<String, ModelPart>,
             name: &str,
@@ -199,6 +355,9 @@ impl Model {
                 main: extract_part(&mut parts, "Left Leg")?,
                 layer: extract_part(&mut parts, "Left Leg Layer")?,
             },
+            cape: parts
+                .remove("Cape")
+                .unwrap_or_else(|| ModelPart::upload(device, "Cape", &cape::vertices())),
         })
     }
 }
@@ -308,6 +467,61 @@ mod tests {
         );
     }
 
+    #[test]
+    fn bounds_enclose_all_vertices() {
+        let vertex = |position| TexturedVertex {
+            position,
+            normal: [0.0, 1.0, 0.0],
+            texture: [0.0, 0.0],
+        };
+        let bounds = Bounds::from_vertices(&[
+            vertex([1.0, -2.0, 0.5]),
+            vertex([-1.0, 3.0, 0.0]),
+            vertex([0.0, 0.0, -0.5]),
+        ]);
+        assert_eq!(bounds.min, [-1.0, -2.0, -0.5]);
+        assert_eq!(bounds.max, [1.0, 3.0, 0.5]);
+        let corners = bounds.corners();
+        assert!(corners.contains(&[-1.0, -2.0, -0.5]));
+        assert!(corners.contains(&[1.0, 3.0, 0.5]));
+        assert!(corners.contains(&[1.0, -2.0, 0.5]));
+    }
+
+    #[test]
+    fn uv_insets_pull_faces_in_on_every_side() {
+        let vertex = |u, v| TexturedVertex {
+            position: [0.0; 3],
+            normal: [0.0, 0.0, 1.0],
+            texture: [u, v],
+        };
+        // A quad over the UV rectangle (0.25, 0.5)–(0.5, 0.75) as two triangles.
+        let mut quad = [
+            vertex(0.25, 0.5),
+            vertex(0.5, 0.5),
+            vertex(0.5, 0.75),
+            vertex(0.25, 0.5),
+            vertex(0.5, 0.75),
+            vertex(0.25, 0.75),
+        ];
+        inset_uvs(&mut quad, 0.03125);
+        assert_eq!(
+            quad.map(|v| v.texture),
+            [
+                [0.28125, 0.53125],
+                [0.46875, 0.53125],
+                [0.46875, 0.71875],
+                [0.28125, 0.53125],
+                [0.46875, 0.71875],
+                [0.28125, 0.71875],
+            ]
+        );
+
+        // Insets past the middle stop there; faces without width are untouched.
+        let mut sliver = [vertex(0.0, 0.5), vertex(1.0, 0.5), vertex(1.0, 0.5)];
+        inset_uvs(&mut sliver, 0.75);
+        assert_eq!(sliver.map(|v| v.texture), [[0.5, 0.5]; 3]);
+    }
+
     #[test]
     fn textured_vertex_desc_layout_is_valid() {
         let desc = TexturedVertex::desc();
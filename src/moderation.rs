@@ -0,0 +1,244 @@
+//! Moderation hooks for uploaded skins: a [`ModerationHook`] looks at region crops of a sanitized
+//! skin (face, torso front, arms, legs, ...) and returns a [`ModerationReport`] before the skin is
+//! stored, rendered or served.
+//!
+//! Hooks are user-supplied (a classifier service, a closure) or the built-in
+//! [`SkinToneHeuristic`], a coarse check for large bare-skin areas. Eidolon never acts on a
+//! report; the caller decides what a [`Verdict`] means for the upload.
+
+use image::RgbaImage;
+
+use crate::character::SkinType;
+use crate::error::EidolonError;
+use crate::skin_layout::{region_crop, Region};
+use crate::texture::SkinImage;
+
+/// What a hook thinks should happen to a skin. Ordered from most to least permissive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Verdict {
+    Allow,
+    /// Hold for a human to look at.
+    Review,
+    Reject,
+}
+
+/// One observation behind a verdict.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    /// The region the finding is about, `None` for the whole skin.
+    pub region: Option<Region>,
+    /// Hook-defined strength, e.g. a classifier probability or a ratio.
+    pub score: f32,
+    pub note: String,
+}
+
+/// A hook's answer for one skin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModerationReport {
+    pub verdict: Verdict,
+    pub findings: Vec<Finding>,
+}
+
+impl ModerationReport {
+    /// Nothing found.
+    pub fn allow() -> Self {
+        Self {
+            verdict: Verdict::Allow,
+            findings: Vec::new(),
+        }
+    }
+
+    /// Both reports in one: the stricter verdict and every finding.
+    pub fn merge(mut self, other: ModerationReport) -> Self {
+        self.verdict = self.verdict.max(other.verdict);
+        self.findings.extend(other.findings);
+        self
+    }
+}
+
+/// A region of the skin cut from the atlas, overlay composited (see
+/// [`crate::skin_layout::region_crop`]).
+#[derive(Debug, Clone)]
+pub struct RegionCrop {
+    pub region: Region,
+    pub image: RgbaImage,
+}
+
+/// Crops of every [`Region`], in [`Region::ALL`] order.
+pub fn region_crops(skin: &SkinImage, skin_type: SkinType) -> Vec<RegionCrop> {
+    Region::ALL
+        .iter()
+        .map(|&region| RegionCrop {
+            region,
+            image: region_crop(skin, region, skin_type),
+        })
+        .collect()
+}
+
+/// Looks at a skin's region crops and reports. Errors (e.g. an unreachable classifier) are passed
+/// through to the caller of [`moderate`].
+pub trait ModerationHook: Send + Sync {
+    fn review(&self, crops: &[RegionCrop]) -> Result<ModerationReport, EidolonError>;
+}
+
+impl<F> ModerationHook for F
+where
+    F: Fn(&[RegionCrop]) -> Result<ModerationReport, EidolonError> + Send + Sync,
+{
+    fn review(&self, crops: &[RegionCrop]) -> Result<ModerationReport, EidolonError> {
+        self(crops)
+    }
+}
+
+/// Run `hook` on the region crops of `skin`.
+pub fn moderate(
+    skin: &SkinImage,
+    skin_type: SkinType,
+    hook: &impl ModerationHook,
+) -> Result<ModerationReport, EidolonError> {
+    hook.review(&region_crops(skin, skin_type))
+}
+
+/// Flags skins whose clothed regions are mostly skin-colored, for human review.
+///
+/// A texel counts as skin-colored by a fixed RGB rule (reddish, not too dark, not gray), so tanned
+/// and pale tones pass and so do orange or brown clothes: treat a [`Verdict::Review`] from this
+/// heuristic as a hint, never as a rejection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkinToneHeuristic {
+    /// Regions checked.
+    pub regions: Vec<Region>,
+    /// Share of a region's opaque texels above which it is flagged, `0.0–1.0`.
+    pub max_ratio: f32,
+}
+
+impl Default for SkinToneHeuristic {
+    /// Torso front and legs, flagged above 85% skin tone.
+    fn default() -> Self {
+        Self {
+            regions: vec![Region::TorsoFront, Region::Legs],
+            max_ratio: 0.85,
+        }
+    }
+}
+
+impl SkinToneHeuristic {
+    /// Share of the opaque texels of `image` that are skin-colored; 0 for an empty image.
+    pub fn ratio(image: &RgbaImage) -> f32 {
+        let opaque: Vec<_> = image.pixels().filter(|p| p[3] >= 128).collect();
+        if opaque.is_empty() {
+            return 0.0;
+        }
+        let skin = opaque.iter().filter(|p| is_skin_tone(p.0)).count();
+        skin as f32 / opaque.len() as f32
+    }
+}
+
+impl ModerationHook for SkinToneHeuristic {
+    fn review(&self, crops: &[RegionCrop]) -> Result<ModerationReport, EidolonError> {
+        let mut report = ModerationReport::allow();
+        for crop in crops
+            .iter()
+            .filter(|crop| self.regions.contains(&crop.region))
+        {
+            let ratio = Self::ratio(&crop.image);
+            if ratio > self.max_ratio {
+                report.verdict = Verdict::Review;
+                report.findings.push(Finding {
+                    region: Some(crop.region),
+                    score: ratio,
+                    note: format!("{:.0}% skin tone", ratio * 100.0),
+                });
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Peer et al.'s RGB skin rule for daylight: reddish, bright enough, and not gray.
+fn is_skin_tone([r, g, b, _]: [u8; 4]) -> bool {
+    let (r, g, b) = (i32::from(r), i32::from(g), i32::from(b));
+    let spread = r.max(g).max(b) - r.min(g).min(b);
+    r > 95 && g > 40 && b > 20 && spread > 15 && (r - g).abs() > 15 && r > g && r > b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character::Bone;
+    use crate::skin_layout::{part, Layer, PARTS};
+    use image::Rgba;
+
+    const TAN: [u8; 4] = [214, 160, 120, 255];
+    const BLUE: [u8; 4] = [40, 60, 200, 255];
+
+    /// A skin painted `clothes` on every base face of the body and legs, tan elsewhere.
+    fn skin(clothes: [u8; 4]) -> SkinImage {
+        let mut rgba = RgbaImage::from_pixel(64, 64, Rgba(TAN));
+        for bone in [Bone::Body, Bone::RightLeg, Bone::LeftLeg] {
+            for (x, y, w, h) in part(bone).faces(Layer::Base, SkinType::Classic) {
+                for ty in y..y + h {
+                    for tx in x..x + w {
+                        rgba.put_pixel(tx, ty, Rgba(clothes));
+                    }
+                }
+            }
+        }
+        // Clear the overlay so the base layer shows.
+        for part in &PARTS {
+            for (x, y, w, h) in part.faces(Layer::Overlay, SkinType::Classic) {
+                for ty in y..y + h {
+                    for tx in x..x + w {
+                        rgba.put_pixel(tx, ty, Rgba([0; 4]));
+                    }
+                }
+            }
+        }
+        SkinImage::from_rgba(rgba).unwrap()
+    }
+
+    #[test]
+    fn skin_tone_heuristic_flags_bare_torso_and_legs() {
+        let heuristic = SkinToneHeuristic::default();
+        let clothed = moderate(&skin(BLUE), SkinType::Classic, &heuristic).unwrap();
+        assert_eq!(clothed, ModerationReport::allow());
+
+        let bare = moderate(&skin(TAN), SkinType::Classic, &heuristic).unwrap();
+        assert_eq!(bare.verdict, Verdict::Review);
+        let regions: Vec<_> = bare.findings.iter().map(|f| f.region).collect();
+        assert_eq!(regions, [Some(Region::TorsoFront), Some(Region::Legs)]);
+        assert_eq!(bare.findings[0].score, 1.0);
+    }
+
+    #[test]
+    fn closures_see_every_region_and_reports_merge() {
+        let hook = |crops: &[RegionCrop]| {
+            let regions: Vec<_> = crops.iter().map(|crop| crop.region).collect();
+            assert_eq!(regions, Region::ALL);
+            assert_eq!(crops[0].image.dimensions(), (8, 8));
+            Ok(ModerationReport {
+                verdict: Verdict::Reject,
+                findings: vec![Finding {
+                    region: None,
+                    score: 0.9,
+                    note: "classifier".into(),
+                }],
+            })
+        };
+        let report = moderate(&skin(TAN), SkinType::Classic, &hook).unwrap();
+        let merged = moderate(&skin(TAN), SkinType::Classic, &SkinToneHeuristic::default())
+            .unwrap()
+            .merge(report);
+        assert_eq!(merged.verdict, Verdict::Reject);
+        assert_eq!(merged.findings.len(), 3);
+    }
+
+    #[test]
+    fn skin_tone_rule() {
+        assert!(is_skin_tone(TAN));
+        assert!(is_skin_tone([120, 80, 60, 255]));
+        assert!(!is_skin_tone(BLUE));
+        assert!(!is_skin_tone([128, 128, 128, 255]));
+        assert!(!is_skin_tone([60, 40, 30, 255]));
+    }
+}
@@ -0,0 +1,244 @@
+//! Color quantization for palette images ([`crate::animation::encode_gif_with`]).
+//!
+//! Skins are drawn with a few dozen flat colors, and renders of them keep most of that flatness,
+//! so a [`Palette`] first tries to hold every color exactly. Only images with more colors than
+//! the palette has room for are quantized (NeuQuant), and then [`Dither`] chooses how the
+//! difference is spread.
+
+use std::collections::HashMap;
+
+use color_quant::NeuQuant;
+use image::RgbaImage;
+
+/// Pixels with alpha below this are transparent in palette images (GIF has no partial alpha).
+pub const ALPHA_CUTOFF: u8 = 128;
+
+/// NeuQuant sampling factor: 1 learns from every pixel, 30 from every 30th.
+const NEUQUANT_SAMPLE: i32 = 10;
+
+/// 8×8 Bayer threshold matrix for [`Dither::Ordered`].
+const BAYER: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// How colors missing from a [`Palette`] are approximated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dither {
+    /// The nearest palette color: flat areas stay flat, gradients band.
+    #[default]
+    None,
+    /// A fixed 8×8 Bayer pattern. The pattern stays put between frames, so animations do not
+    /// shimmer.
+    Ordered,
+    /// Floyd–Steinberg error diffusion: the smoothest gradients, but the noise changes from frame
+    /// to frame.
+    FloydSteinberg,
+}
+
+/// Up to 256 opaque RGB colors, with the nearest-color lookups used to index images against them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    colors: Vec<[u8; 3]>,
+    /// Whether every color of the images it was built from is in the palette.
+    exact: bool,
+}
+
+impl Palette {
+    /// A palette of at most `max_colors` (clamped to 1–256) for the pixels of `images` with alpha
+    /// of at least [`ALPHA_CUTOFF`]: all of their colors if they fit, otherwise a quantized
+    /// selection.
+    pub fn build<'a>(images: impl IntoIterator<Item = &'a RgbaImage>, max_colors: usize) -> Self {
+        let max_colors = max_colors.clamp(1, 256);
+        let images: Vec<_> = images.into_iter().collect();
+        let mut distinct = HashMap::new();
+        let opaque = || {
+            images
+                .iter()
+                .flat_map(|image| image.pixels())
+                .filter(|pixel| pixel[3] >= ALPHA_CUTOFF)
+        };
+        for pixel in opaque() {
+            let next = distinct.len();
+            distinct
+                .entry([pixel[0], pixel[1], pixel[2]])
+                .or_insert(next);
+            if distinct.len() > max_colors {
+                break;
+            }
+        }
+        if distinct.len() <= max_colors {
+            let mut colors = vec![[0; 3]; distinct.len()];
+            for (color, i) in distinct {
+                colors[i] = color;
+            }
+            if colors.is_empty() {
+                colors.push([0; 3]);
+            }
+            return Self {
+                colors,
+                exact: true,
+            };
+        }
+        let samples: Vec<u8> = opaque()
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+            .collect();
+        let quantizer = NeuQuant::new(NEUQUANT_SAMPLE, max_colors, &samples);
+        Self {
+            colors: quantizer
+                .color_map_rgb()
+                .chunks_exact(3)
+                .map(|c| [c[0], c[1], c[2]])
+                .collect(),
+            exact: false,
+        }
+    }
+
+    /// The colors, in index order.
+    pub fn colors(&self) -> &[[u8; 3]] {
+        &self.colors
+    }
+
+    /// Whether the images the palette was built from fit it without any color changing.
+    pub fn is_exact(&self) -> bool {
+        self.exact
+    }
+
+    /// The colors as packed RGB triples, as GIF color tables store them.
+    pub fn to_rgb_bytes(&self) -> Vec<u8> {
+        self.colors.iter().flatten().copied().collect()
+    }
+
+    /// Index of the color closest to `rgb`.
+    pub fn nearest(&self, rgb: [u8; 3]) -> u8 {
+        let distance = |color: &[u8; 3]| -> i32 {
+            (0..3)
+                .map(|c| (i32::from(color[c]) - i32::from(rgb[c])).pow(2))
+                .sum()
+        };
+        (0..self.colors.len())
+            .min_by_key(|&i| distance(&self.colors[i]))
+            .unwrap_or(0) as u8
+    }
+
+    /// Palette indices of the pixels of `image`, row by row. Pixels with alpha below
+    /// [`ALPHA_CUTOFF`] get `transparent` (the nearest color to black without one); colors the
+    /// palette lacks are approximated with `dither`.
+    pub fn index(&self, image: &RgbaImage, dither: Dither, transparent: Option<u8>) -> Vec<u8> {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let mut cache: HashMap<[u8; 3], u8> = HashMap::new();
+        let mut lookup = |rgb: [u8; 3]| *cache.entry(rgb).or_insert_with(|| self.nearest(rgb));
+        let clear = transparent.unwrap_or_else(|| self.nearest([0; 3]));
+        let dither = if self.exact { Dither::None } else { dither };
+        let mut indices = Vec::with_capacity(width * height);
+        match dither {
+            Dither::None | Dither::Ordered => {
+                // Spread of the ordered offsets: about the gap between neighbouring colors of an
+                // evenly filled cube of this many colors.
+                let spread = 255.0 / (self.colors.len() as f32).cbrt();
+                for (x, y, pixel) in image.enumerate_pixels() {
+                    if pixel[3] < ALPHA_CUTOFF {
+                        indices.push(clear);
+                        continue;
+                    }
+                    let rgb = [pixel[0], pixel[1], pixel[2]];
+                    let rgb = if dither == Dither::Ordered {
+                        let threshold = BAYER[y as usize % 8][x as usize % 8];
+                        let offset = ((f32::from(threshold) + 0.5) / 64.0 - 0.5) * spread;
+                        rgb.map(|c| (f32::from(c) + offset).round().clamp(0.0, 255.0) as u8)
+                    } else {
+                        rgb
+                    };
+                    indices.push(lookup(rgb));
+                }
+            }
+            Dither::FloydSteinberg => {
+                let mut error = vec![[0.0f32; 3]; (width + 2) * 2];
+                for y in 0..height {
+                    let (current, next) = error.split_at_mut(width + 2);
+                    next.fill([0.0; 3]);
+                    for x in 0..width {
+                        let pixel = image.get_pixel(x as u32, y as u32);
+                        if pixel[3] < ALPHA_CUTOFF {
+                            indices.push(clear);
+                            continue;
+                        }
+                        let wanted: [f32; 3] = std::array::from_fn(|c| {
+                            (f32::from(pixel[c]) + current[x + 1][c]).clamp(0.0, 255.0)
+                        });
+                        let index = lookup(wanted.map(|c| c.round() as u8));
+                        indices.push(index);
+                        let got = self.colors[usize::from(index)];
+                        for c in 0..3 {
+                            let e = wanted[c] - f32::from(got[c]);
+                            current[x + 2][c] += e * 7.0 / 16.0;
+                            next[x][c] += e * 3.0 / 16.0;
+                            next[x + 1][c] += e * 5.0 / 16.0;
+                            next[x + 2][c] += e / 16.0;
+                        }
+                    }
+                    error.rotate_left(width + 2);
+                }
+            }
+        }
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn gradient() -> RgbaImage {
+        RgbaImage::from_fn(64, 8, |x, _| Rgba([x as u8 * 4, 0, 0, 255]))
+    }
+
+    #[test]
+    fn flat_colors_are_kept_exactly() {
+        let mut image = RgbaImage::from_pixel(4, 4, Rgba([12, 34, 56, 255]));
+        image.put_pixel(1, 1, Rgba([200, 100, 0, 255]));
+        image.put_pixel(2, 2, Rgba([9, 9, 9, 0]));
+        let palette = Palette::build([&image], 4);
+        assert!(palette.is_exact());
+        assert_eq!(palette.colors(), &[[12, 34, 56], [200, 100, 0]]);
+        let indices = palette.index(&image, Dither::FloydSteinberg, Some(2));
+        assert_eq!(indices[0], 0);
+        assert_eq!(indices[5], 1);
+        assert_eq!(indices[10], 2);
+    }
+
+    #[test]
+    fn too_many_colors_are_quantized() {
+        let palette = Palette::build([&gradient()], 4);
+        assert!(!palette.is_exact());
+        assert_eq!(palette.colors().len(), 4);
+    }
+
+    #[test]
+    fn dithering_mixes_neighbouring_colors() {
+        let image = gradient();
+        let palette = Palette {
+            colors: vec![[0, 0, 0], [252, 0, 0]],
+            exact: false,
+        };
+        let count_changes = |indices: &[u8]| indices.windows(2).filter(|w| w[0] != w[1]).count();
+        let row = |dither| palette.index(&image, dither, None)[..64].to_vec();
+        // Without dithering the row switches once, halfway.
+        assert_eq!(count_changes(&row(Dither::None)), 1);
+        assert!(count_changes(&row(Dither::Ordered)) > 4);
+        assert!(count_changes(&row(Dither::FloydSteinberg)) > 4);
+        // Error diffusion keeps the average brightness.
+        let lit = row(Dither::FloydSteinberg)
+            .iter()
+            .filter(|&&i| i == 1)
+            .count();
+        assert!((28..=36).contains(&lit), "{lit}");
+    }
+}
@@ -0,0 +1,259 @@
+//! Named render presets: a [`PresetRegistry`] maps operator-chosen names (`front_256`,
+//! `iso_webp_512`) to full [`ThumbnailSpec`]s, so a public endpoint can accept just a preset name
+//! and a skin, and callers cannot ask for expensive sizes or formats the operator did not offer.
+//!
+//! Registries are usually loaded from a JSON file:
+//!
+//! ```json
+//! {
+//!   "front_256": { "size": 256 },
+//!   "iso_webp_512": { "view": "isometric", "size": 512, "format": "webp", "background": "#202020" }
+//! }
+//! ```
+//!
+//! Every field is optional and defaults to [`ThumbnailSpec::default`]: `version` (`1`), `pose`
+//! (`stand`, `wave`, `walking`, `running`), `view` (`front`, `back`, `isometric`), `skin_type`
+//! (`classic`, `slim`), `size`, `format` (`png`, `webp`) and `background` (`#rrggbb[aa]`).
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::cache::ResultCache;
+use crate::card::parse_color;
+use crate::character::SkinType;
+use crate::error::EidolonError;
+use crate::renderer::OutputFormat;
+use crate::thumbnail::{thumbnail, ThumbnailPose, ThumbnailSpec, ThumbnailVersion, ThumbnailView};
+
+/// Largest preset size accepted by [`PresetRegistry::insert`], in pixels.
+pub const MAX_PRESET_SIZE: u32 = 2048;
+
+/// Named [`ThumbnailSpec`]s; see the [module docs](self).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PresetRegistry {
+    presets: BTreeMap<String, ThumbnailSpec>,
+}
+
+impl PresetRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a registry. Unknown fields and values fail with [`EidolonError::Template`], so a
+    /// typo does not silently fall back to a default.
+    pub fn from_json(json: &[u8]) -> Result<Self, EidolonError> {
+        let root: Value = serde_json::from_slice(json)
+            .map_err(|e| EidolonError::template(format!("malformed preset file: {e}")))?;
+        let entries = root
+            .as_object()
+            .ok_or_else(|| EidolonError::template("preset file must be an object of presets"))?;
+        let mut registry = Self::new();
+        for (name, fields) in entries {
+            let spec = parse_spec(fields)
+                .map_err(|e| EidolonError::template(format!("preset '{name}': {e}")))?;
+            registry.insert(name, spec)?;
+        }
+        Ok(registry)
+    }
+
+    /// Add or replace preset `name`. Names are ASCII letters, digits, `_` and `-`, so they are
+    /// safe in URLs and file names; sizes are 1 to [`MAX_PRESET_SIZE`]; formats PNG or WebP.
+    pub fn insert(&mut self, name: &str, spec: ThumbnailSpec) -> Result<(), EidolonError> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(EidolonError::template(format!(
+                "preset name '{name}' may only contain letters, digits, '_' and '-'"
+            )));
+        }
+        if !(1..=MAX_PRESET_SIZE).contains(&spec.size) {
+            return Err(EidolonError::template(format!(
+                "preset '{name}': size must be 1 to {MAX_PRESET_SIZE}"
+            )));
+        }
+        if !matches!(spec.format, OutputFormat::Png | OutputFormat::WebP) {
+            return Err(EidolonError::template(format!(
+                "preset '{name}': format must be PNG or WebP"
+            )));
+        }
+        self.presets.insert(name.to_string(), spec);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ThumbnailSpec> {
+        self.presets.get(name)
+    }
+
+    /// Preset names in sorted order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.presets.keys().map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.presets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.presets.is_empty()
+    }
+
+    fn spec(&self, name: &str) -> Result<&ThumbnailSpec, EidolonError> {
+        self.get(name)
+            .ok_or_else(|| EidolonError::not_found(format!("no preset named '{name}'")))
+    }
+
+    /// Render `skin_bytes` with preset `name` ([`thumbnail`]). Unknown names are
+    /// [`EidolonError::NotFound`].
+    pub fn render(&self, name: &str, skin_bytes: &[u8]) -> Result<Vec<u8>, EidolonError> {
+        thumbnail(skin_bytes, self.spec(name)?)
+    }
+
+    /// [`Self::render`] through `cache`: identical requests are served from disk without touching
+    /// the GPU. Keys are [`Self::cache_key`], so they change with the skin, the preset's spec and
+    /// its [`ThumbnailVersion`].
+    pub fn render_cached(
+        &self,
+        name: &str,
+        skin_bytes: &[u8],
+        cache: &ResultCache,
+    ) -> Result<Vec<u8>, EidolonError> {
+        let spec = self.spec(name)?;
+        cache.get_or_insert_with(&spec.cache_key(skin_bytes), || thumbnail(skin_bytes, spec))
+    }
+
+    /// [`ThumbnailSpec::cache_key`] of preset `name`. Presets with equal specs share keys.
+    pub fn cache_key(&self, name: &str, skin_bytes: &[u8]) -> Result<String, EidolonError> {
+        Ok(self.spec(name)?.cache_key(skin_bytes))
+    }
+}
+
+fn parse_spec(fields: &Value) -> Result<ThumbnailSpec, String> {
+    let fields = fields
+        .as_object()
+        .ok_or("must be an object of render settings")?;
+    let mut spec = ThumbnailSpec::default();
+    for (key, value) in fields {
+        let text = || {
+            value
+                .as_str()
+                .ok_or_else(|| format!("'{key}' must be a string"))
+        };
+        let invalid = || format!("'{value}' is not a valid '{key}'");
+        match key.as_str() {
+            "version" => {
+                spec.version = match value.as_u64() {
+                    Some(1) => ThumbnailVersion::V1,
+                    _ => return Err(invalid()),
+                }
+            }
+            "pose" => {
+                spec.pose = match text()? {
+                    "stand" => ThumbnailPose::Stand,
+                    "wave" => ThumbnailPose::Wave,
+                    "walking" => ThumbnailPose::Walking,
+                    "running" => ThumbnailPose::Running,
+                    _ => return Err(invalid()),
+                }
+            }
+            "view" => {
+                spec.view = match text()? {
+                    "front" => ThumbnailView::Front,
+                    "back" => ThumbnailView::Back,
+                    "isometric" => ThumbnailView::Isometric,
+                    _ => return Err(invalid()),
+                }
+            }
+            "skin_type" => {
+                spec.skin_type = match text()? {
+                    "classic" => SkinType::Classic,
+                    "slim" => SkinType::Slim,
+                    _ => return Err(invalid()),
+                }
+            }
+            "size" => {
+                spec.size = value
+                    .as_u64()
+                    .and_then(|size| u32::try_from(size).ok())
+                    .ok_or_else(invalid)?
+            }
+            "format" => {
+                spec.format = match text()? {
+                    "png" => OutputFormat::Png,
+                    "webp" => OutputFormat::WebP,
+                    _ => return Err(invalid()),
+                }
+            }
+            "background" => spec.background = parse_color(text()?).map_err(|_| invalid())?,
+            _ => return Err(format!("unknown setting '{key}'")),
+        }
+    }
+    Ok(spec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRESETS: &str = r##"{
+        "front_256": { "size": 256 },
+        "iso_webp_512": { "view": "isometric", "size": 512, "format": "webp", "background": "#202020" },
+        "wave_slim": { "pose": "wave", "skin_type": "slim", "version": 1 }
+    }"##;
+
+    #[test]
+    fn presets_parse_with_defaults() {
+        let registry = PresetRegistry::from_json(PRESETS.as_bytes()).unwrap();
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            ["front_256", "iso_webp_512", "wave_slim"]
+        );
+        assert_eq!(registry.get("front_256"), Some(&ThumbnailSpec::default()));
+        let iso = registry.get("iso_webp_512").unwrap();
+        assert_eq!(iso.view, ThumbnailView::Isometric);
+        assert_eq!(iso.format, OutputFormat::WebP);
+        assert_eq!(iso.background, [0x20, 0x20, 0x20, 255]);
+        let wave = registry.get("wave_slim").unwrap();
+        assert_eq!(
+            (wave.pose, wave.skin_type),
+            (ThumbnailPose::Wave, SkinType::Slim)
+        );
+    }
+
+    #[test]
+    fn bad_presets_are_rejected() {
+        for json in [
+            r#"[]"#,
+            r#"{"a": 5}"#,
+            r#"{"a": {"size": 0}}"#,
+            r#"{"a": {"size": 4096}}"#,
+            r#"{"a": {"view": "top"}}"#,
+            r#"{"a": {"sise": 64}}"#,
+            r#"{"a": {"version": 2}}"#,
+            r#"{"../a": {}}"#,
+        ] {
+            assert!(
+                matches!(
+                    PresetRegistry::from_json(json.as_bytes()),
+                    Err(EidolonError::Template(_))
+                ),
+                "{json}"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_names_are_not_found() {
+        let registry = PresetRegistry::from_json(PRESETS.as_bytes()).unwrap();
+        assert!(matches!(
+            registry.render("huge", b"png"),
+            Err(EidolonError::NotFound(_))
+        ));
+        assert_eq!(
+            registry.cache_key("front_256", b"png").unwrap(),
+            ThumbnailSpec::default().cache_key(b"png")
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Yggdrasil game profiles: the session-server profile JSON served by Mojang and compatible skin
+//! servers (authlib-injector, Blessing Skin), and the base64 `textures` property inside it.
+//!
+//! Parsing only; fetching lives in [`crate::provider`].
+
+use serde_json::Value;
+
+use crate::character::SkinType;
+use crate::error::EidolonError;
+use crate::signature::SignatureKey;
+
+/// A session-server profile (`/session/minecraft/profile/<uuid>`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameProfile {
+    /// Dashless UUID as served.
+    pub id: String,
+    pub name: String,
+    /// The `textures` property; `None` for profiles that carry no properties.
+    pub textures: Option<TexturesProperty>,
+}
+
+impl GameProfile {
+    /// Parse profile JSON: `{"id", "name", "properties": [{"name": "textures", "value",
+    /// "signature"?}]}`.
+    pub fn from_json(json: &[u8]) -> Result<Self, EidolonError> {
+        let profile: Value = serde_json::from_slice(json)
+            .map_err(|e| EidolonError::fetch(format!("malformed profile JSON: {e}")))?;
+        let field = |key: &str| {
+            profile[key]
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| EidolonError::fetch(format!("profile has no '{key}'")))
+        };
+        let textures = profile["properties"].as_array().and_then(|properties| {
+            let property = properties.iter().find(|p| p["name"] == "textures")?;
+            Some(TexturesProperty {
+                value: property["value"].as_str()?.to_string(),
+                signature: property["signature"].as_str().map(str::to_string),
+            })
+        });
+        Ok(Self {
+            id: field("id")?,
+            name: field("name")?,
+            textures,
+        })
+    }
+}
+
+/// The `textures` property of a profile, still encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TexturesProperty {
+    /// Base64 JSON payload.
+    pub value: String,
+    /// Base64 signature of `value`; only served when requested (`?unsigned=false`).
+    pub signature: Option<String>,
+}
+
+impl TexturesProperty {
+    pub fn decode(&self) -> Result<PlayerTextures, EidolonError> {
+        PlayerTextures::from_property(&self.value)
+    }
+
+    /// Check the signature against the key of the server that should have issued it.
+    pub fn verify(&self, key: &SignatureKey) -> SignatureStatus {
+        let Some(signature) = &self.signature else {
+            return SignatureStatus::Unsigned;
+        };
+        match decode_base64(signature) {
+            Some(signature) if key.verify(self.value.as_bytes(), &signature) => {
+                SignatureStatus::Valid
+            }
+            _ => SignatureStatus::Invalid,
+        }
+    }
+}
+
+/// Outcome of checking a `textures` property signature.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// No key was given, or the source has no signatures to check.
+    #[default]
+    NotChecked,
+    /// The server sent the property without a signature.
+    Unsigned,
+    /// Signed by the expected key.
+    Valid,
+    /// The signature does not match: the property was altered or issued by another server.
+    Invalid,
+}
+
+/// Skin and cape of a player, decoded from a `textures` property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerTextures {
+    /// `None` for players on a default skin.
+    pub skin_url: Option<String>,
+    /// Slim when the skin's `metadata.model` is `"slim"`.
+    pub skin_type: SkinType,
+    pub cape_url: Option<String>,
+}
+
+impl PlayerTextures {
+    /// Decode a base64 `textures` value: `{"textures": {"SKIN": {"url", "metadata": {"model"}},
+    /// "CAPE": {"url"}}}`.
+    pub fn from_property(value: &str) -> Result<Self, EidolonError> {
+        let json = decode_base64(value)
+            .ok_or_else(|| EidolonError::fetch("textures property is not base64"))?;
+        let payload: Value = serde_json::from_slice(&json)
+            .map_err(|e| EidolonError::fetch(format!("malformed textures JSON: {e}")))?;
+        let textures = &payload["textures"];
+        let skin = &textures["SKIN"];
+        Ok(Self {
+            skin_url: skin["url"].as_str().map(str::to_string),
+            skin_type: if skin["metadata"]["model"] == "slim" {
+                SkinType::Slim
+            } else {
+                SkinType::Classic
+            },
+            cape_url: textures["CAPE"]["url"].as_str().map(str::to_string),
+        })
+    }
+}
+
+/// Standard base64 (`+/`), with or without `=` padding; whitespace is ignored.
+pub(crate) fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in s.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return None,
+        };
+        buffer = buffer << 6 | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// Standard base64 with `=` padding.
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let buffer = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |buffer, (i, &b)| buffer | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(ALPHABET[(buffer >> (18 - 6 * i) & 63) as usize]));
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `textures` payload with a slim skin at `http://textures.example/skin`.
+    const TEXTURES: &str = "eyJ0aW1lc3RhbXAiOjAsInByb2ZpbGVJZCI6IjA2OWE3OWY0NDRlOTQ3MjZhNWJlZmNhOTBlMzhhYWY1IiwicHJvZmlsZU5hbWUiOiJOb3RjaCIsInRleHR1cmVzIjp7IlNLSU4iOnsidXJsIjoiaHR0cDovL3RleHR1cmVzLmV4YW1wbGUvc2tpbiIsIm1ldGFkYXRhIjp7Im1vZGVsIjoic2xpbSJ9fX19";
+
+    #[test]
+    fn base64_decodes_padded_and_unpadded() {
+        assert_eq!(decode_base64("aGk/Pg==").unwrap(), b"hi?>");
+        assert_eq!(decode_base64("aGk/Pg").unwrap(), b"hi?>");
+        assert_eq!(
+            decode_base64("YW55IGNh\ncm5hbCBwbGVhcw==").unwrap(),
+            b"any carnal pleas"
+        );
+        assert!(decode_base64("not base64!").is_none());
+        for text in [&b""[..], b"h", b"hi", b"hi?", b"hi?>"] {
+            assert_eq!(decode_base64(&encode_base64(text)).unwrap(), text);
+        }
+        assert_eq!(encode_base64(b"hi?>"), "aGk/Pg==");
+    }
+
+    #[test]
+    fn profile_and_textures_parse() {
+        let json = format!(
+            r#"{{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[{{"name":"textures","value":"{TEXTURES}","signature":"c2ln"}}]}}"#
+        );
+        let profile = GameProfile::from_json(json.as_bytes()).unwrap();
+        assert_eq!(profile.name, "Notch");
+        let property = profile.textures.unwrap();
+        assert_eq!(property.signature.as_deref(), Some("c2ln"));
+        let textures = property.decode().unwrap();
+        assert_eq!(
+            textures.skin_url.as_deref(),
+            Some("http://textures.example/skin")
+        );
+        assert_eq!(textures.skin_type, SkinType::Slim);
+        assert_eq!(textures.cape_url, None);
+    }
+
+    #[test]
+    fn malformed_profiles_are_fetch_errors() {
+        assert!(matches!(
+            GameProfile::from_json(b"{}"),
+            Err(EidolonError::Fetch(_))
+        ));
+        assert!(matches!(
+            PlayerTextures::from_property("!!"),
+            Err(EidolonError::Fetch(_))
+        ));
+        let bare = GameProfile::from_json(br#"{"id":"a","name":"b"}"#).unwrap();
+        assert_eq!(bare.textures, None);
+    }
+}
@@ -0,0 +1,615 @@
+//! Pluggable skin sources: a [`SkinProvider`] looks up a player's skin by [`PlayerId`].
+//!
+//! Built in are [`DirectoryProvider`] (skins on disk), [`UrlProvider`] (a URL template),
+//! [`YggdrasilProvider`] (Mojang's or a custom auth server's profile API), [`DiskCache`] (caches any provider) and
+//! [`ProviderChain`] (tries providers in order). Private skin systems implement the trait.
+//!
+//! The crate ships no HTTP client: downloading providers take an [`HttpGet`], which a closure
+//! around any blocking client implements.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::character::SkinType;
+use crate::error::EidolonError;
+use crate::profile::{GameProfile, SignatureStatus};
+use crate::signature::SignatureKey;
+
+/// Player to look up: a name or a UUID.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PlayerId {
+    Name(String),
+    /// With or without dashes.
+    Uuid(String),
+}
+
+impl PlayerId {
+    /// A UUID if `s` is 32 hex digits (optionally dashed 8-4-4-4-12), otherwise a name.
+    pub fn parse(s: &str) -> Self {
+        let dashed = s.len() == 36
+            && s.char_indices()
+                .all(|(i, c)| matches!(i, 8 | 13 | 18 | 23) == (c == '-'));
+        let hex = s.chars().filter(|&c| c != '-').collect::<String>();
+        if (s.len() == 32 || dashed)
+            && hex.len() == 32
+            && hex.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            Self::Uuid(s.to_string())
+        } else {
+            Self::Name(s.to_string())
+        }
+    }
+
+    /// Lowercase name or dashless lowercase UUID; used in file names and URLs. Fails for
+    /// anything but ASCII letters, digits, `_` and `-`, so it never escapes a directory.
+    pub fn key(&self) -> Result<String, EidolonError> {
+        let key = match self {
+            Self::Name(name) => name.to_ascii_lowercase(),
+            Self::Uuid(uuid) => uuid.replace('-', "").to_ascii_lowercase(),
+        };
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(EidolonError::invalid_path(format!(
+                "player id '{self}' may only contain letters, digits, '_' and '-'"
+            )));
+        }
+        Ok(key)
+    }
+}
+
+impl fmt::Display for PlayerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => f.write_str(name),
+            Self::Uuid(uuid) => f.write_str(uuid),
+        }
+    }
+}
+
+/// A fetched skin: PNG bytes as published, plus the arm model when the source knows it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkinData {
+    pub bytes: Vec<u8>,
+    pub skin_type: Option<SkinType>,
+    /// Whether the skin URL came from a correctly signed profile; only
+    /// [`YggdrasilProvider::verify_signatures`] checks.
+    pub signature: SignatureStatus,
+}
+
+/// A source of player skins.
+///
+/// Return [`EidolonError::NotFound`] for players the source does not know, so a
+/// [`ProviderChain`] moves on to the next source.
+pub trait SkinProvider: Send + Sync {
+    fn fetch(&self, id: &PlayerId) -> Result<SkinData, EidolonError>;
+}
+
+/// Blocking HTTP GET for downloading providers: the response body, or `None` for 404 and empty
+/// (204) responses. 429 responses are [`EidolonError::RateLimited`] (with `Retry-After` when
+/// sent), so [`crate::throttle::ThrottledHttp`] can back off; other failures are
+/// [`EidolonError::Fetch`].
+pub trait HttpGet: Send + Sync {
+    fn get(&self, url: &str) -> Result<Option<Vec<u8>>, EidolonError>;
+}
+
+impl<F> HttpGet for F
+where
+    F: Fn(&str) -> Result<Option<Vec<u8>>, EidolonError> + Send + Sync,
+{
+    fn get(&self, url: &str) -> Result<Option<Vec<u8>>, EidolonError> {
+        self(url)
+    }
+}
+
+/// Skins stored as `<dir>/<key>.png` ([`PlayerId::key`]).
+#[derive(Debug, Clone)]
+pub struct DirectoryProvider {
+    dir: PathBuf,
+}
+
+impl DirectoryProvider {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl SkinProvider for DirectoryProvider {
+    fn fetch(&self, id: &PlayerId) -> Result<SkinData, EidolonError> {
+        let path = self.dir.join(format!("{}.png", id.key()?));
+        match std::fs::read(&path) {
+            Ok(bytes) => Ok(SkinData {
+                bytes,
+                skin_type: None,
+                signature: SignatureStatus::NotChecked,
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(EidolonError::not_found(
+                format!("no skin for '{id}' in {}", self.dir.display()),
+            )),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Skins downloaded from a URL template; `{id}` is replaced with [`PlayerId::key`], e.g.
+/// `https://skins.example.org/{id}.png`.
+pub struct UrlProvider<H> {
+    template: String,
+    http: H,
+}
+
+impl<H: HttpGet> UrlProvider<H> {
+    pub fn new(template: impl Into<String>, http: H) -> Self {
+        Self {
+            template: template.into(),
+            http,
+        }
+    }
+}
+
+impl<H: HttpGet> SkinProvider for UrlProvider<H> {
+    fn fetch(&self, id: &PlayerId) -> Result<SkinData, EidolonError> {
+        let url = self.template.replace("{id}", &id.key()?);
+        let bytes = self
+            .http
+            .get(&url)?
+            .ok_or_else(|| EidolonError::not_found(format!("no skin at {url}")))?;
+        Ok(SkinData {
+            bytes,
+            skin_type: None,
+            signature: SignatureStatus::NotChecked,
+        })
+    }
+}
+
+/// Skins from a Yggdrasil-compatible API — Mojang's, or a custom auth server such as Blessing
+/// Skin: name → UUID lookup, session-server profile, then the skin URL from its `textures`
+/// property. Players on a default skin are [`EidolonError::NotFound`].
+///
+/// Signatures are not checked unless a key is set with [`Self::verify_signatures`] or
+/// [`Self::require_signatures`].
+pub struct YggdrasilProvider<H> {
+    /// Prefix of the name lookup, `<api_url>/users/profiles/minecraft/<name>`.
+    api_url: String,
+    /// Prefix of the profile lookup, `<session_url>/session/minecraft/profile/<uuid>`.
+    session_url: String,
+    http: H,
+    signature_key: Option<SignatureKey>,
+    /// Fail fetches whose signature is not [`SignatureStatus::Valid`].
+    require_signature: bool,
+}
+
+impl<H: HttpGet> YggdrasilProvider<H> {
+    /// A server with the given API and session-server URLs (no trailing slash).
+    pub fn new(api_url: impl Into<String>, session_url: impl Into<String>, http: H) -> Self {
+        Self {
+            api_url: api_url.into(),
+            session_url: session_url.into(),
+            http,
+            signature_key: None,
+            require_signature: false,
+        }
+    }
+
+    /// Mojang's `api.mojang.com` and `sessionserver.mojang.com`.
+    pub fn mojang(http: H) -> Self {
+        Self::new(
+            "https://api.mojang.com",
+            "https://sessionserver.mojang.com",
+            http,
+        )
+    }
+
+    /// An authlib-injector API root, e.g. `https://skin.example.org/api/yggdrasil` for Blessing
+    /// Skin: `<root>/api` and `<root>/sessionserver`. Name lookups need the server to offer the
+    /// Mojang-style `GET` endpoint; look players up by UUID otherwise.
+    pub fn authlib_injector(root: &str, http: H) -> Self {
+        let root = root.trim_end_matches('/');
+        Self::new(format!("{root}/api"), format!("{root}/sessionserver"), http)
+    }
+
+    /// Request signed profiles and report the result of checking them against `key` in
+    /// [`SkinData::signature`].
+    pub fn verify_signatures(mut self, key: SignatureKey) -> Self {
+        self.signature_key = Some(key);
+        self
+    }
+
+    /// Like [`Self::verify_signatures`], but fetches fail with [`EidolonError::Fetch`] unless the
+    /// signature is valid, so spoofed skin URLs are never downloaded.
+    pub fn require_signatures(mut self, key: SignatureKey) -> Self {
+        self.require_signature = true;
+        self.verify_signatures(key)
+    }
+
+    fn get_json(&self, url: &str) -> Result<Option<Vec<u8>>, EidolonError> {
+        Ok(self.http.get(url)?.filter(|body| !body.is_empty()))
+    }
+
+    /// The session-server profile of `id`, or `None` if the server does not know the player.
+    /// Signed when a signature key is set.
+    pub fn profile(&self, id: &PlayerId) -> Result<Option<GameProfile>, EidolonError> {
+        let key = id.key()?;
+        let uuid = match id {
+            PlayerId::Uuid(_) => key,
+            PlayerId::Name(_) => {
+                let url = format!("{}/users/profiles/minecraft/{key}", self.api_url);
+                let Some(body) = self.get_json(&url)? else {
+                    return Ok(None);
+                };
+                let found: Value = serde_json::from_slice(&body)
+                    .map_err(|e| EidolonError::fetch(format!("malformed JSON from {url}: {e}")))?;
+                let uuid = found["id"].as_str().ok_or_else(|| {
+                    EidolonError::fetch(format!("missing 'id' in response from {url}"))
+                })?;
+                PlayerId::Uuid(uuid.to_string()).key()?
+            }
+        };
+        let mut url = format!("{}/session/minecraft/profile/{uuid}", self.session_url);
+        if self.signature_key.is_some() {
+            url.push_str("?unsigned=false");
+        }
+        self.get_json(&url)?
+            .map(|body| GameProfile::from_json(&body))
+            .transpose()
+    }
+}
+
+impl<H: HttpGet> SkinProvider for YggdrasilProvider<H> {
+    fn fetch(&self, id: &PlayerId) -> Result<SkinData, EidolonError> {
+        let profile = self
+            .profile(id)?
+            .ok_or_else(|| EidolonError::not_found(format!("no profile for '{id}'")))?;
+        let property = profile
+            .textures
+            .ok_or_else(|| EidolonError::fetch(format!("profile of '{id}' has no textures")))?;
+        let signature = self
+            .signature_key
+            .as_ref()
+            .map_or(SignatureStatus::NotChecked, |key| property.verify(key));
+        if self.require_signature && signature != SignatureStatus::Valid {
+            return Err(EidolonError::fetch(format!(
+                "textures of '{id}' failed signature check: {signature:?}"
+            )));
+        }
+        let textures = property.decode()?;
+        let skin_url = textures
+            .skin_url
+            .ok_or_else(|| EidolonError::not_found(format!("'{id}' uses a default skin")))?;
+        debug!(%id, skin_url, "downloading skin");
+        let bytes = self
+            .http
+            .get(&skin_url)?
+            .ok_or_else(|| EidolonError::fetch(format!("skin {skin_url} is gone")))?;
+        Ok(SkinData {
+            bytes,
+            skin_type: Some(textures.skin_type),
+            signature,
+        })
+    }
+}
+
+/// Caches another provider's skins as `<dir>/<key>.png`, with the arm model and signature status
+/// in `<key>.model`. Entries older than `max_age` are fetched again; `None` keeps them forever.
+pub struct DiskCache<P> {
+    inner: P,
+    dir: PathBuf,
+    max_age: Option<Duration>,
+}
+
+impl<P: SkinProvider> DiskCache<P> {
+    pub fn new(inner: P, dir: impl Into<PathBuf>, max_age: Option<Duration>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+            max_age,
+        }
+    }
+
+    fn read(&self, png: &Path, model: &Path) -> Option<SkinData> {
+        let modified = std::fs::metadata(png).and_then(|m| m.modified()).ok()?;
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        if self.max_age.is_some_and(|max| age > max) {
+            return None;
+        }
+        let model = std::fs::read_to_string(model).unwrap_or_default();
+        let (skin_type, signature) = parse_description(&model);
+        Some(SkinData {
+            bytes: std::fs::read(png).ok()?,
+            skin_type,
+            signature,
+        })
+    }
+
+    fn write(&self, png: &Path, model: &Path, data: &SkinData) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        std::fs::write(png, &data.bytes)?;
+        std::fs::write(model, describe(data))
+    }
+}
+
+/// Arm model and signature status of `data` as two lines, e.g. `slim\nvalid`; unknown values are
+/// empty lines.
+pub(crate) fn describe(data: &SkinData) -> String {
+    let model_name = match data.skin_type {
+        Some(SkinType::Slim) => "slim",
+        Some(SkinType::Classic) => "classic",
+        None => "",
+    };
+    let signature = match data.signature {
+        SignatureStatus::NotChecked => "",
+        SignatureStatus::Unsigned => "unsigned",
+        SignatureStatus::Valid => "valid",
+        SignatureStatus::Invalid => "invalid",
+    };
+    format!("{model_name}\n{signature}")
+}
+
+/// Inverse of [`describe`]; anything unrecognized is unknown.
+pub(crate) fn parse_description(description: &str) -> (Option<SkinType>, SignatureStatus) {
+    let mut lines = description.lines().map(str::trim);
+    let skin_type = match lines.next() {
+        Some("slim") => Some(SkinType::Slim),
+        Some("classic") => Some(SkinType::Classic),
+        _ => None,
+    };
+    let signature = match lines.next() {
+        Some("valid") => SignatureStatus::Valid,
+        Some("unsigned") => SignatureStatus::Unsigned,
+        Some("invalid") => SignatureStatus::Invalid,
+        _ => SignatureStatus::NotChecked,
+    };
+    (skin_type, signature)
+}
+
+impl<P: SkinProvider> SkinProvider for DiskCache<P> {
+    fn fetch(&self, id: &PlayerId) -> Result<SkinData, EidolonError> {
+        let key = id.key()?;
+        let png = self.dir.join(format!("{key}.png"));
+        let model = self.dir.join(format!("{key}.model"));
+        if let Some(data) = self.read(&png, &model) {
+            debug!(%id, "skin cache hit");
+            return Ok(data);
+        }
+        let data = self.inner.fetch(id)?;
+        if let Err(e) = self.write(&png, &model, &data) {
+            warn!(%id, "failed to cache skin in {}: {e}", self.dir.display());
+        }
+        Ok(data)
+    }
+}
+
+/// Providers tried in order: the first skin found wins. When none has one, the first error that
+/// is not [`EidolonError::NotFound`] is returned, so a failing source is not mistaken for a
+/// missing player.
+#[derive(Default)]
+pub struct ProviderChain {
+    providers: Vec<Box<dyn SkinProvider>>,
+}
+
+impl ProviderChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `provider` to the chain.
+    pub fn push(&mut self, provider: impl SkinProvider + 'static) {
+        self.providers.push(Box::new(provider));
+    }
+
+    pub fn len(&self) -> usize {
+        self.providers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+}
+
+impl SkinProvider for ProviderChain {
+    fn fetch(&self, id: &PlayerId) -> Result<SkinData, EidolonError> {
+        let mut failure = None;
+        for provider in &self.providers {
+            match provider.fetch(id) {
+                Ok(data) => return Ok(data),
+                Err(EidolonError::NotFound(_)) => {}
+                Err(e) => {
+                    warn!(%id, "skin provider failed: {e}");
+                    failure.get_or_insert(e);
+                }
+            }
+        }
+        Err(failure.unwrap_or_else(|| EidolonError::not_found(format!("no skin for '{id}'"))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A `textures` payload with a slim skin at `http://textures.example/skin`.
+    const TEXTURES: &str = "eyJ0aW1lc3RhbXAiOjAsInByb2ZpbGVJZCI6IjA2OWE3OWY0NDRlOTQ3MjZhNWJlZmNhOTBlMzhhYWY1IiwicHJvZmlsZU5hbWUiOiJOb3RjaCIsInRleHR1cmVzIjp7IlNLSU4iOnsidXJsIjoiaHR0cDovL3RleHR1cmVzLmV4YW1wbGUvc2tpbiIsIm1ldGFkYXRhIjp7Im1vZGVsIjoic2xpbSJ9fX19";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("eidolon-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// Answers every lookup with the same skin and counts the calls.
+    struct Counting(AtomicUsize);
+
+    impl SkinProvider for Counting {
+        fn fetch(&self, _id: &PlayerId) -> Result<SkinData, EidolonError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(SkinData {
+                bytes: b"png".to_vec(),
+                skin_type: Some(SkinType::Slim),
+                signature: SignatureStatus::Valid,
+            })
+        }
+    }
+
+    #[test]
+    fn player_ids_parse_and_key() {
+        let dashed = PlayerId::parse("069A79F4-44E9-4726-A5BE-FCA90E38AAF5");
+        assert!(matches!(dashed, PlayerId::Uuid(_)));
+        assert_eq!(dashed.key().unwrap(), "069a79f444e94726a5befca90e38aaf5");
+        assert!(matches!(
+            PlayerId::parse("069a79f444e94726a5befca90e38aaf5"),
+            PlayerId::Uuid(_)
+        ));
+        assert_eq!(PlayerId::parse("Notch"), PlayerId::Name("Notch".into()));
+        assert_eq!(PlayerId::parse("Notch").key().unwrap(), "notch");
+        assert!(PlayerId::parse("../etc/passwd").key().is_err());
+        assert!(PlayerId::parse("").key().is_err());
+    }
+
+    #[test]
+    fn directory_provider_reads_by_key() {
+        let dir = temp_dir("skins");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("notch.png"), b"png").unwrap();
+        let provider = DirectoryProvider::new(&dir);
+        assert_eq!(
+            provider.fetch(&PlayerId::parse("Notch")).unwrap().bytes,
+            b"png"
+        );
+        assert!(matches!(
+            provider.fetch(&PlayerId::parse("jeb_")),
+            Err(EidolonError::NotFound(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn chain_falls_through_missing_players() {
+        let mut chain = ProviderChain::new();
+        chain.push(DirectoryProvider::new(temp_dir("empty")));
+        chain.push(Counting(AtomicUsize::new(0)));
+        assert_eq!(chain.len(), 2);
+        let data = chain.fetch(&PlayerId::parse("Notch")).unwrap();
+        assert_eq!(data.skin_type, Some(SkinType::Slim));
+
+        let mut failing = ProviderChain::new();
+        failing.push(UrlProvider::new("http://down/{id}", |_: &str| {
+            Err(EidolonError::fetch("connection refused"))
+        }));
+        failing.push(DirectoryProvider::new(temp_dir("empty")));
+        assert!(matches!(
+            failing.fetch(&PlayerId::parse("Notch")),
+            Err(EidolonError::Fetch(_))
+        ));
+        assert!(matches!(
+            ProviderChain::new().fetch(&PlayerId::parse("Notch")),
+            Err(EidolonError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn disk_cache_serves_repeat_lookups() {
+        let dir = temp_dir("cache");
+        let cache = DiskCache::new(Counting(AtomicUsize::new(0)), &dir, None);
+        let id = PlayerId::parse("Notch");
+        let first = cache.fetch(&id).unwrap();
+        let second = cache.fetch(&id).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(cache.inner.0.load(Ordering::SeqCst), 1);
+
+        let expired = DiskCache::new(Counting(AtomicUsize::new(0)), &dir, Some(Duration::ZERO));
+        std::thread::sleep(Duration::from_millis(10));
+        expired.fetch(&id).unwrap();
+        assert_eq!(expired.inner.0.load(Ordering::SeqCst), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn yggdrasil_provider_follows_profile_to_skin() {
+        let http = |url: &str| -> Result<Option<Vec<u8>>, EidolonError> {
+            Ok(match url {
+                "https://api.mojang.com/users/profiles/minecraft/notch" => Some(
+                    br#"{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch"}"#.to_vec(),
+                ),
+                "https://sessionserver.mojang.com/session/minecraft/profile/069a79f444e94726a5befca90e38aaf5" => {
+                    Some(format!(
+                        r#"{{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[{{"name":"textures","value":"{TEXTURES}"}}]}}"#
+                    ).into_bytes())
+                }
+                "http://textures.example/skin" => Some(b"png".to_vec()),
+                _ => None,
+            })
+        };
+        let provider = YggdrasilProvider::mojang(http);
+        let data = provider.fetch(&PlayerId::parse("Notch")).unwrap();
+        assert_eq!(data.bytes, b"png");
+        assert_eq!(data.skin_type, Some(SkinType::Slim));
+        assert!(matches!(
+            provider.fetch(&PlayerId::parse("nobody")),
+            Err(EidolonError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn authlib_injector_roots_map_to_yggdrasil_endpoints() {
+        let http = |url: &str| -> Result<Option<Vec<u8>>, EidolonError> {
+            Ok(match url {
+                "https://skin.example/api/yggdrasil/sessionserver/session/minecraft/profile/069a79f444e94726a5befca90e38aaf5" => {
+                    Some(format!(
+                        r#"{{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[{{"name":"textures","value":"{TEXTURES}"}}]}}"#
+                    ).into_bytes())
+                }
+                "http://textures.example/skin" => Some(b"png".to_vec()),
+                _ => None,
+            })
+        };
+        let provider =
+            YggdrasilProvider::authlib_injector("https://skin.example/api/yggdrasil/", http);
+        let id = PlayerId::parse("069a79f4-44e9-4726-a5be-fca90e38aaf5");
+        assert_eq!(provider.profile(&id).unwrap().unwrap().name, "Notch");
+        assert_eq!(provider.fetch(&id).unwrap().bytes, b"png");
+    }
+
+    #[test]
+    fn signatures_are_requested_and_checked() {
+        use crate::signature::tests::{PUBLIC_KEY, SIGNATURE};
+
+        let signed = |signature: &'static str| {
+            move |url: &str| -> Result<Option<Vec<u8>>, EidolonError> {
+                Ok(match url {
+                    "https://sessionserver.mojang.com/session/minecraft/profile/069a79f444e94726a5befca90e38aaf5?unsigned=false" => {
+                        Some(format!(
+                            r#"{{"id":"069a79f444e94726a5befca90e38aaf5","name":"Notch","properties":[{{"name":"textures","value":"{TEXTURES}","signature":"{signature}"}}]}}"#
+                        ).into_bytes())
+                    }
+                    "http://textures.example/skin" => Some(b"png".to_vec()),
+                    _ => None,
+                })
+            }
+        };
+        let key = SignatureKey::from_pem(PUBLIC_KEY).unwrap();
+        let id = PlayerId::parse("069a79f444e94726a5befca90e38aaf5");
+
+        let provider = YggdrasilProvider::mojang(signed(SIGNATURE)).require_signatures(key.clone());
+        assert_eq!(
+            provider.fetch(&id).unwrap().signature,
+            SignatureStatus::Valid
+        );
+
+        let forged = YggdrasilProvider::mojang(signed("c2ln")).verify_signatures(key.clone());
+        assert_eq!(
+            forged.fetch(&id).unwrap().signature,
+            SignatureStatus::Invalid
+        );
+        let forged = YggdrasilProvider::mojang(signed("c2ln")).require_signatures(key);
+        assert!(matches!(forged.fetch(&id), Err(EidolonError::Fetch(_))));
+    }
+}
@@ -0,0 +1,105 @@
+//! Brightness analysis of rendered skins, so galleries of many skins can be normalized to a
+//! consistent exposure ([`super::Renderer::normalize_brightness`]).
+//!
+//! Only visible pixels count: each pixel is weighted by its alpha, so the transparent
+//! background and hidden texels do not drag the average down.
+
+use image::RgbaImage;
+
+/// Mean luminance [`super::Renderer::normalize_brightness`] aims for by default.
+pub const DEFAULT_TARGET_LUMINANCE: f32 = 0.45;
+
+/// Largest exposure change, in stops, that auto-adjust applies in either direction.
+const MAX_EXPOSURE_STOPS: f32 = 2.0;
+
+/// Luminance statistics of the visible skin in a render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrightnessStats {
+    /// Alpha-weighted mean Rec. 709 luminance of the encoded output (`0.0–1.0`).
+    pub mean_luminance: f32,
+    /// Alpha-weighted standard deviation of the luminance (RMS contrast).
+    pub contrast: f32,
+    /// Fraction of the image covered by the skin (sum of alpha over pixel count).
+    pub coverage: f32,
+}
+
+impl BrightnessStats {
+    /// Analyze a straight-alpha render. Returns `None` when nothing is visible.
+    pub fn from_image(image: &RgbaImage) -> Option<Self> {
+        let (mut weight, mut sum, mut sum_sq) = (0.0f64, 0.0f64, 0.0f64);
+        for p in image.pixels() {
+            let a = p[3] as f64 / 255.0;
+            if a == 0.0 {
+                continue;
+            }
+            let l = (0.2126 * p[0] as f64 + 0.7152 * p[1] as f64 + 0.0722 * p[2] as f64) / 255.0;
+            weight += a;
+            sum += a * l;
+            sum_sq += a * l * l;
+        }
+        if weight == 0.0 {
+            return None;
+        }
+        let mean = sum / weight;
+        let variance = (sum_sq / weight - mean * mean).max(0.0);
+        let pixels = (image.width() as f64 * image.height() as f64).max(1.0);
+        Some(Self {
+            mean_luminance: mean as f32,
+            contrast: variance.sqrt() as f32,
+            coverage: (weight / pixels) as f32,
+        })
+    }
+
+    /// Exposure, in stops, that moves [`Self::mean_luminance`] to `target`. Clamped to ±2 stops
+    /// so near-black or near-white skins are not pushed into clipping.
+    pub fn exposure_to(&self, target: f32) -> f32 {
+        let ratio = target.max(1e-3) / self.mean_luminance.max(1e-3);
+        ratio.log2().clamp(-MAX_EXPOSURE_STOPS, MAX_EXPOSURE_STOPS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_image_has_no_stats() {
+        let img = RgbaImage::from_pixel(4, 4, image::Rgba([255, 255, 255, 0]));
+        assert!(BrightnessStats::from_image(&img).is_none());
+    }
+
+    #[test]
+    fn ignores_transparent_background() {
+        let mut img = RgbaImage::from_pixel(2, 1, image::Rgba([0, 0, 0, 0]));
+        img.put_pixel(1, 0, image::Rgba([255, 255, 255, 255]));
+        let stats = BrightnessStats::from_image(&img).unwrap();
+        assert!((stats.mean_luminance - 1.0).abs() < 1e-6);
+        assert!(stats.contrast < 1e-6);
+        assert_eq!(stats.coverage, 0.5);
+    }
+
+    #[test]
+    fn contrast_is_luminance_deviation() {
+        let mut img = RgbaImage::from_pixel(2, 1, image::Rgba([0, 0, 0, 255]));
+        img.put_pixel(1, 0, image::Rgba([255, 255, 255, 255]));
+        let stats = BrightnessStats::from_image(&img).unwrap();
+        assert!((stats.mean_luminance - 0.5).abs() < 1e-6);
+        assert!((stats.contrast - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn exposure_doubles_towards_target_and_clamps() {
+        let stats = BrightnessStats {
+            mean_luminance: 0.2,
+            contrast: 0.1,
+            coverage: 0.3,
+        };
+        assert!((stats.exposure_to(0.4) - 1.0).abs() < 1e-6);
+        assert_eq!(stats.exposure_to(100.0), 2.0);
+        let black = BrightnessStats {
+            mean_luminance: 0.0,
+            ..stats
+        };
+        assert_eq!(black.exposure_to(0.45), 2.0);
+    }
+}
@@ -0,0 +1,175 @@
+//! Emissive overlay and bloom: the overlay layer is re-drawn unlit into an emissive target, then
+//! blurred horizontally and vertically (with a bright-pass on the first blur) for the composite.
+
+use super::options::EmissiveOverlay;
+
+/// Format of the emissive and blur targets. Float so the Gaussian sums do not band.
+pub(crate) const BLOOM_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Blur standard deviation as a fraction of the output height, so the glow looks the same at any
+/// resolution.
+const SIGMA_PER_HEIGHT: f32 = 0.01;
+
+/// Taps on each side of the center in `BLOOM_SHADER`; the kernel spans ±3σ.
+const BLUR_TAPS: f32 = 12.0;
+
+/// One blur pass (group 0, binding 0 of the bloom shader).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct BlurUniforms {
+    /// `xy` = texel step between taps, `z` = threshold, `w` = apply bright-pass (0/1).
+    pub params: [f32; 4],
+}
+
+/// Uniforms for the horizontal (bright-pass) and vertical blur passes.
+pub(crate) fn compute_blur_uniforms(
+    settings: &EmissiveOverlay,
+    height: u32,
+) -> (BlurUniforms, BlurUniforms) {
+    let sigma = (height as f32 * SIGMA_PER_HEIGHT).max(1.0);
+    let step = (3.0 * sigma / BLUR_TAPS).max(1.0);
+    let threshold = settings.bloom_threshold.clamp(0.0, 1.0);
+    (
+        BlurUniforms {
+            params: [step, 0.0, threshold, 1.0],
+        },
+        BlurUniforms {
+            params: [0.0, step, threshold, 0.0],
+        },
+    )
+}
+
+pub(crate) fn create_blur_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Blur Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: wgpu::BufferSize::new(
+                        std::mem::size_of::<BlurUniforms>() as u64,
+                    ),
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                },
+                count: None,
+            },
+        ],
+    })
+}
+
+pub(crate) fn create_blur_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uniforms: &wgpu::Buffer,
+    source: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Blur Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniforms.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(source),
+            },
+        ],
+    })
+}
+
+pub(crate) fn create_bloom_texture(
+    device: &wgpu::Device,
+    label: &str,
+    width: u32,
+    height: u32,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: BLOOM_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
+}
+
+/// Emissive color plus the two blur ping-pong targets, all at output size.
+pub(crate) struct BloomTargets {
+    pub emissive: wgpu::TextureView,
+    pub blur_h: wgpu::TextureView,
+    pub blur_v: wgpu::TextureView,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl BloomTargets {
+    pub(crate) fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        let view = |label| {
+            create_bloom_texture(device, label, width, height)
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+        Self {
+            emissive: view("Emissive Texture"),
+            blur_h: view("Bloom Blur Texture (horizontal)"),
+            blur_v: view("Bloom Blur Texture (vertical)"),
+            width,
+            height,
+        }
+    }
+
+    /// Bytes of the three targets.
+    pub(crate) fn bytes(&self) -> u64 {
+        3 * super::memory::target_bytes(BLOOM_FORMAT, self.width, self.height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blur_passes_are_horizontal_then_vertical() {
+        let (h, v) = compute_blur_uniforms(&EmissiveOverlay::default(), 600);
+        assert!(h.params[0] > 0.0 && h.params[1] == 0.0);
+        assert!(v.params[0] == 0.0 && v.params[1] > 0.0);
+        assert_eq!(h.params[3], 1.0, "bright-pass runs on the first pass");
+        assert_eq!(v.params[3], 0.0);
+    }
+
+    #[test]
+    fn blur_radius_scales_with_height() {
+        let (small, _) = compute_blur_uniforms(&EmissiveOverlay::default(), 600);
+        let (large, _) = compute_blur_uniforms(&EmissiveOverlay::default(), 2400);
+        assert!(large.params[0] > small.params[0]);
+    }
+
+    #[test]
+    fn threshold_is_clamped() {
+        let settings = EmissiveOverlay {
+            bloom_threshold: 3.0,
+            ..EmissiveOverlay::default()
+        };
+        let (h, _) = compute_blur_uniforms(&settings, 100);
+        assert_eq!(h.params[2], 1.0);
+    }
+}
@@ -0,0 +1,233 @@
+//! Crowd renders: many characters in one image ([`super::Renderer::render_crowd`]).
+//!
+//! Every distinct skin is copied into a layer of a texture array, so members with the same skin
+//! type form one batch whatever they wear. Each body part (main and overlay mesh) of a batch is a
+//! single instanced draw whose instances carry the member's part transform and skin layer, so a
+//! 50-player lineup costs the same twelve draws per skin type as a single character.
+
+use crate::camera::Camera;
+use crate::character::{Character, SkinType};
+use crate::texture::Texture;
+
+use super::uniforms::{part_model_matrices, BODY_PART_COUNT};
+
+/// One character of a crowd render.
+#[derive(Clone)]
+pub struct CrowdMember<'a> {
+    /// Pose, placement and arm model; place members apart with [`Character::position`].
+    pub character: Character,
+    pub skin: &'a Texture,
+}
+
+/// Per-instance vertex data of the crowd pipeline (vertex buffer slot 1): the world-space model
+/// matrix of one body part of one member.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct InstanceData {
+    pub model: [[f32; 4]; 4],
+    /// Layer of the member's skin in its batch's skin array.
+    pub layer: u32,
+    pub _padding: [u32; 3],
+}
+
+impl InstanceData {
+    pub(crate) fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+            3 => Float32x4,
+            4 => Float32x4,
+            5 => Float32x4,
+            6 => Float32x4,
+            7 => Uint32,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceData>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// A crowd's skins packed into texture arrays: skins of one size share an array (up to the
+/// device's layer limit), one layer per distinct skin.
+pub(crate) struct SkinPacking<'a> {
+    pub arrays: Vec<SkinArray<'a>>,
+    /// Per member: index into `arrays` and layer.
+    pub slots: Vec<(usize, u32)>,
+}
+
+/// Skins of one texture array, in layer order.
+pub(crate) struct SkinArray<'a> {
+    pub width: u32,
+    pub height: u32,
+    pub skins: Vec<&'a Texture>,
+}
+
+pub(crate) fn pack_skins<'a>(members: &[CrowdMember<'a>], max_layers: u32) -> SkinPacking<'a> {
+    let skins: Vec<_> = members
+        .iter()
+        .map(|m| {
+            let size = (m.skin.texture.width(), m.skin.texture.height());
+            (m.skin as *const Texture, size)
+        })
+        .collect();
+    let (arrays, slots) = assign_layers(&skins, max_layers);
+    SkinPacking {
+        arrays: arrays
+            .into_iter()
+            .map(|((width, height), layers)| SkinArray {
+                width,
+                height,
+                skins: layers.into_iter().map(|i| members[i].skin).collect(),
+            })
+            .collect(),
+        slots,
+    }
+}
+
+type Layers = Vec<((u32, u32), Vec<usize>)>;
+
+/// Give each distinct key a layer in an array of its size. Returns, per array, its size and the
+/// index of the first entry of each layer, plus the `(array, layer)` of every entry.
+fn assign_layers<K: PartialEq + Copy>(
+    skins: &[(K, (u32, u32))],
+    max_layers: u32,
+) -> (Layers, Vec<(usize, u32)>) {
+    let mut arrays: Layers = Vec::new();
+    let mut placed: Vec<(K, (usize, u32))> = Vec::new();
+    let slots = skins
+        .iter()
+        .enumerate()
+        .map(|(i, (key, size))| {
+            if let Some((_, slot)) = placed.iter().find(|(k, _)| k == key) {
+                return *slot;
+            }
+            let array = match arrays
+                .iter()
+                .position(|(s, layers)| s == size && layers.len() < max_layers as usize)
+            {
+                Some(array) => array,
+                None => {
+                    arrays.push((*size, Vec::new()));
+                    arrays.len() - 1
+                }
+            };
+            arrays[array].1.push(i);
+            let slot = (array, arrays[array].1.len() as u32 - 1);
+            placed.push((*key, slot));
+            slot
+        })
+        .collect();
+    (arrays, slots)
+}
+
+/// Members drawn together: same skin type and skin array.
+pub(crate) struct CrowdBatch {
+    pub skin_type: SkinType,
+    /// Index into [`SkinPacking::arrays`].
+    pub array: usize,
+    /// Indices into the member slice, in input order.
+    pub members: Vec<usize>,
+}
+
+/// Group `members` into batches, in order of first appearance.
+pub(crate) fn batches(members: &[CrowdMember], packing: &SkinPacking) -> Vec<CrowdBatch> {
+    let keys: Vec<_> = members
+        .iter()
+        .zip(&packing.slots)
+        .map(|(m, &(array, _))| (m.character.skin_type, array))
+        .collect();
+    group_indices(&keys)
+        .into_iter()
+        .map(|((skin_type, array), members)| CrowdBatch {
+            skin_type,
+            array,
+            members,
+        })
+        .collect()
+}
+
+/// Indices of equal keys, grouped in order of first appearance.
+fn group_indices<K: PartialEq + Copy>(keys: &[K]) -> Vec<(K, Vec<usize>)> {
+    let mut groups: Vec<(K, Vec<usize>)> = Vec::new();
+    for (i, key) in keys.iter().enumerate() {
+        match groups.iter_mut().find(|(k, _)| k == key) {
+            Some((_, indices)) => indices.push(i),
+            None => groups.push((*key, vec![i])),
+        }
+    }
+    groups
+}
+
+/// Instance data of every batch: batch by batch, then part by part, then member by member. The
+/// instances of part `i` of a batch with `n` members starting at `base` are `base + i * n ..`.
+pub(crate) fn instance_data(
+    members: &[CrowdMember],
+    packing: &SkinPacking,
+    batches: &[CrowdBatch],
+    camera: &Camera,
+) -> Vec<InstanceData> {
+    let mut data = Vec::with_capacity(members.len() * BODY_PART_COUNT);
+    for batch in batches {
+        let matrices: Vec<_> = batch
+            .members
+            .iter()
+            .map(|&i| (part_model_matrices(&members[i].character, camera), packing.slots[i].1))
+            .collect();
+        for part in 0..BODY_PART_COUNT {
+            data.extend(matrices.iter().map(|(m, layer)| InstanceData {
+                model: m[part].into(),
+                layer: *layer,
+                _padding: [0; 3],
+            }));
+        }
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn groups_keep_first_appearance_order() {
+        let groups = group_indices(&['a', 'b', 'a', 'c', 'b']);
+        assert_eq!(
+            groups,
+            vec![('a', vec![0, 2]), ('b', vec![1, 4]), ('c', vec![3])]
+        );
+    }
+
+    #[test]
+    fn instance_layout_is_matrix_and_layer() {
+        let desc = InstanceData::desc();
+        assert_eq!(desc.step_mode, wgpu::VertexStepMode::Instance);
+        assert_eq!(desc.array_stride, 80);
+        let locations: Vec<_> = desc.attributes.iter().map(|a| a.shader_location).collect();
+        assert_eq!(locations, [3, 4, 5, 6, 7]);
+        assert_eq!(desc.attributes[4].offset, 64);
+    }
+
+    #[test]
+    fn repeated_skins_share_a_layer() {
+        let skins = [('a', (64, 64)), ('b', (64, 64)), ('a', (64, 64))];
+        let (arrays, slots) = assign_layers(&skins, 256);
+        assert_eq!(arrays, vec![((64, 64), vec![0, 1])]);
+        assert_eq!(slots, [(0, 0), (0, 1), (0, 0)]);
+    }
+
+    #[test]
+    fn arrays_split_by_size_and_layer_limit() {
+        let skins = [
+            ('a', (64, 64)),
+            ('b', (128, 128)),
+            ('c', (64, 64)),
+            ('d', (64, 64)),
+        ];
+        let (arrays, slots) = assign_layers(&skins, 2);
+        assert_eq!(
+            arrays,
+            vec![((64, 64), vec![0, 2]), ((128, 128), vec![1]), ((64, 64), vec![3])]
+        );
+        assert_eq!(slots, [(0, 0), (1, 0), (0, 1), (2, 0)]);
+    }
+}
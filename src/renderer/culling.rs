@@ -0,0 +1,99 @@
+//! View-frustum culling of body parts: tightly cropped renders (heads, busts) skip drawing the
+//! parts that are entirely off screen.
+//!
+//! Only the camera pass culls. The shadow pass draws every part, since parts outside the view
+//! still cast shadows into it.
+
+use cgmath::{Matrix4, Vector4};
+
+use crate::model::{Bounds, Model};
+
+use super::uniforms::{body_part_ref, Uniforms, BODY_PART_COUNT};
+
+/// Whether `bounds`, displaced by `uniforms.offset` and transformed by the part's
+/// projection × view × model, may touch the view frustum. Conservative: a box that is outside
+/// but straddles a frustum corner is still reported visible.
+pub(crate) fn is_visible(uniforms: &Uniforms, bounds: &Bounds) -> bool {
+    let clip = Matrix4::from(uniforms.perspective)
+        * Matrix4::from(uniforms.view)
+        * Matrix4::from(uniforms.model);
+    let pad = uniforms.offset.abs();
+    let expanded = Bounds {
+        min: bounds.min.map(|c| c - pad),
+        max: bounds.max.map(|c| c + pad),
+    };
+    let corners = expanded
+        .corners()
+        .map(|[x, y, z]| clip * Vector4::new(x, y, z, 1.0));
+    // Outside when every corner lies beyond the same clip plane.
+    let planes: [fn(&Vector4<f32>) -> bool; 6] = [
+        |p| p.x < -p.w,
+        |p| p.x > p.w,
+        |p| p.y < -p.w,
+        |p| p.y > p.w,
+        |p| p.z < -p.w,
+        |p| p.z > p.w,
+    ];
+    !planes.iter().any(|outside| corners.iter().all(outside))
+}
+
+/// Visibility of the main and overlay mesh of every body part, in
+/// [`super::uniforms::PART_CONFIGS`] order.
+pub(crate) fn visible_parts(
+    uniforms: &[Uniforms; BODY_PART_COUNT],
+    model: &Model,
+) -> [[bool; 2]; BODY_PART_COUNT] {
+    std::array::from_fn(|i| {
+        let part = body_part_ref(i, model);
+        [
+            is_visible(&uniforms[i], &part.main.bounds),
+            is_visible(&uniforms[i], &part.layer.bounds),
+        ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::{Camera, DEFAULT_FOV};
+    use crate::character::Character;
+    use crate::renderer::uniforms::compute_body_part_uniforms;
+
+    // Bind-pose boxes of the head and right leg (Blockbench model units).
+    const HEAD: Bounds = Bounds {
+        min: [-0.25, 1.5, -0.25],
+        max: [0.25, 2.0, 0.25],
+    };
+    const LEG: Bounds = Bounds {
+        min: [0.0, 0.0, -0.125],
+        max: [0.25, 0.75, 0.125],
+    };
+
+    #[test]
+    fn full_body_view_sees_every_part() {
+        let uniforms = compute_body_part_uniforms(&Character::new(), &Camera::new(), DEFAULT_FOV, 800, 600);
+        assert!(is_visible(&uniforms[0], &HEAD));
+        assert!(is_visible(&uniforms[3], &LEG));
+    }
+
+    #[test]
+    fn close_up_skips_parts_out_of_frame() {
+        // Zoomed in on the middle of the character: the head is above the frame.
+        let camera = Camera {
+            scale: 4.0,
+            ..Camera::new()
+        };
+        let uniforms = compute_body_part_uniforms(&Character::new(), &camera, DEFAULT_FOV, 100, 100);
+        assert!(!is_visible(&uniforms[0], &HEAD));
+        assert!(is_visible(&uniforms[3], &LEG));
+    }
+
+    #[test]
+    fn parts_behind_the_camera_are_culled() {
+        let mut character = Character::new();
+        // Move the character behind the eye (the default camera looks along +z from z = -4).
+        character.position.z = -10.0;
+        let uniforms = compute_body_part_uniforms(&character, &Camera::new(), DEFAULT_FOV, 800, 600);
+        assert!(!is_visible(&uniforms[0], &HEAD));
+    }
+}
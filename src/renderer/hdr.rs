@@ -0,0 +1,237 @@
+//! Float outputs for compositing: linear-light color and depth ([`HdrImage`], OpenEXR) and
+//! 16-bit PNG.
+//!
+//! Both go through the post-processing composite into a float target, so values above `1.0`
+//! from the HDR scene survive readback. The shader works in sRGB-encoded values, like the 8-bit
+//! output; [`HdrImage`] decodes them to linear light, as compositors expect from EXR.
+
+use std::path::Path;
+
+#[cfg(not(target_arch = "wasm32"))]
+use image::Rgba;
+use image::{ImageBuffer, Luma, Rgba32FImage};
+
+use crate::error::EidolonError;
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::options::AlphaMode;
+
+/// Channels written by [`HdrImage::write_exr`]. Color (`R`, `G`, `B`) is always included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExrChannels {
+    /// Straight alpha as `A`.
+    pub alpha: bool,
+    /// Eye distance in world units as `Z`; empty pixels are `+inf`.
+    pub depth: bool,
+}
+
+impl Default for ExrChannels {
+    fn default() -> Self {
+        Self {
+            alpha: true,
+            depth: false,
+        }
+    }
+}
+
+/// Float render from [`super::Renderer::render_hdr`].
+#[derive(Debug, Clone)]
+pub struct HdrImage {
+    /// Linear-light RGBA, straight or premultiplied per [`super::RenderOptions::alpha_mode`]. Not
+    /// tone mapped, so bright light and bloom may exceed `1.0`.
+    pub color: Rgba32FImage,
+    /// Eye distance in world units; `f32::INFINITY` where nothing was drawn.
+    pub depth: ImageBuffer<Luma<f32>, Vec<f32>>,
+}
+
+impl HdrImage {
+    /// Build from readback data: `color` is straight-alpha sRGB-encoded RGBA, `depth` is eye
+    /// distance with negative values marking the background. Color is linearized, then
+    /// premultiplied if `alpha_mode` asks for it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn from_readback(
+        width: u32,
+        height: u32,
+        color: Vec<f32>,
+        depth: Vec<f32>,
+        alpha_mode: AlphaMode,
+    ) -> Result<Self, EidolonError> {
+        let premultiply = alpha_mode == AlphaMode::Premultiplied;
+        let color = color
+            .chunks_exact(4)
+            .flat_map(|p| {
+                let k = if premultiply { p[3] } else { 1.0 };
+                [
+                    srgb_to_linear(p[0]) * k,
+                    srgb_to_linear(p[1]) * k,
+                    srgb_to_linear(p[2]) * k,
+                    p[3],
+                ]
+            })
+            .collect();
+        let depth = depth
+            .into_iter()
+            .map(|z| if z < 0.0 { f32::INFINITY } else { z })
+            .collect();
+        Ok(Self {
+            color: ImageBuffer::from_raw(width, height, color)
+                .ok_or_else(|| EidolonError::gpu("float readback size mismatch"))?,
+            depth: ImageBuffer::from_raw(width, height, depth)
+                .ok_or_else(|| EidolonError::gpu("depth readback size mismatch"))?,
+        })
+    }
+
+    /// Write an OpenEXR file with the selected channels (32-bit float, lossless compression).
+    pub fn write_exr(
+        &self,
+        path: impl AsRef<Path>,
+        channels: ExrChannels,
+    ) -> Result<(), EidolonError> {
+        Ok(std::fs::write(path, self.encode_exr(channels)?)?)
+    }
+
+    /// The OpenEXR file [`HdrImage::write_exr`] writes, in memory.
+    pub fn encode_exr(&self, channels: ExrChannels) -> Result<Vec<u8>, EidolonError> {
+        self.encode_exr_with(channels, true)
+    }
+
+    /// [`HdrImage::encode_exr`]; `parallel` compresses blocks on several threads, which writes
+    /// them in completion order, so the file bytes can differ between runs.
+    pub(crate) fn encode_exr_with(
+        &self,
+        channels: ExrChannels,
+        parallel: bool,
+    ) -> Result<Vec<u8>, EidolonError> {
+        use exr::prelude::*;
+
+        let plane =
+            |index: usize| -> Vec<f32> { self.color.pixels().map(|p| p.0[index]).collect() };
+        let mut list = vec![
+            AnyChannel::new("R", FlatSamples::F32(plane(0))),
+            AnyChannel::new("G", FlatSamples::F32(plane(1))),
+            AnyChannel::new("B", FlatSamples::F32(plane(2))),
+        ];
+        if channels.alpha {
+            list.push(AnyChannel::new("A", FlatSamples::F32(plane(3))));
+        }
+        if channels.depth {
+            list.push(AnyChannel::new(
+                "Z",
+                FlatSamples::F32(self.depth.as_raw().clone()),
+            ));
+        }
+
+        let size = (self.color.width() as usize, self.color.height() as usize);
+        let layer = Layer::new(
+            size,
+            LayerAttributes::named("eidolon"),
+            Encoding::SMALL_LOSSLESS,
+            AnyChannels::sort(list.into()),
+        );
+        let image = Image::from_layer(layer);
+        let write = image.write();
+        let write = if parallel { write } else { write.non_parallel() };
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        write
+            .to_buffered(&mut bytes)
+            .map_err(|e| EidolonError::texture(format!("failed to save EXR image: {e}")))?;
+        Ok(bytes.into_inner())
+    }
+}
+
+/// sRGB-encoded value to linear light. Values above `1.0` follow the same curve.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Quantize tone-mapped RGBA to 16 bits per channel.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn to_rgba16(
+    width: u32,
+    height: u32,
+    color: &[f32],
+) -> Result<ImageBuffer<Rgba<u16>, Vec<u16>>, EidolonError> {
+    let values = color
+        .iter()
+        .map(|v| (v.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16)
+        .collect();
+    ImageBuffer::from_raw(width, height, values)
+        .ok_or_else(|| EidolonError::gpu("float readback size mismatch"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srgb_to_linear_endpoints() {
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-6);
+        assert!((srgb_to_linear(0.5) - 0.214).abs() < 1e-3);
+        assert!(srgb_to_linear(2.0) > 1.0, "HDR values stay above 1");
+    }
+
+    #[test]
+    fn rgba16_quantizes_and_clamps() {
+        let img = to_rgba16(1, 1, &[0.0, 1.0, 0.5, 2.0]).unwrap();
+        assert_eq!(img.get_pixel(0, 0).0, [0, 65535, 32768, 65535]);
+    }
+
+    #[test]
+    fn readback_marks_background_depth_infinite() {
+        let img = HdrImage::from_readback(2, 1, vec![1.0; 8], vec![-1.0, 3.5], AlphaMode::Straight)
+            .unwrap();
+        assert_eq!(img.depth.get_pixel(0, 0).0[0], f32::INFINITY);
+        assert_eq!(img.depth.get_pixel(1, 0).0[0], 3.5);
+        assert_eq!(img.color.get_pixel(0, 0).0[3], 1.0);
+    }
+
+    #[test]
+    fn readback_rejects_size_mismatch() {
+        assert!(
+            HdrImage::from_readback(2, 2, vec![0.0; 4], vec![0.0; 4], AlphaMode::Straight).is_err()
+        );
+    }
+
+    #[test]
+    fn readback_premultiplies_linear_color() {
+        let img = HdrImage::from_readback(
+            1,
+            1,
+            vec![1.0, 1.0, 1.0, 0.5],
+            vec![1.0],
+            AlphaMode::Premultiplied,
+        )
+        .unwrap();
+        assert_eq!(img.color.get_pixel(0, 0).0, [0.5, 0.5, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn write_exr_includes_selected_channels() {
+        let img = HdrImage::from_readback(2, 1, vec![0.5; 8], vec![-1.0, 2.0], AlphaMode::Straight)
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("eidolon_hdr_{}.exr", std::process::id()));
+        img.write_exr(
+            &path,
+            ExrChannels {
+                alpha: true,
+                depth: true,
+            },
+        )
+        .unwrap();
+        let meta = exr::meta::MetaData::read_from_file(&path, false).unwrap();
+        let names: Vec<String> = meta.headers[0]
+            .channels
+            .list
+            .iter()
+            .map(|c| c.name.to_string())
+            .collect();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(names, ["A", "B", "G", "R", "Z"]);
+    }
+}
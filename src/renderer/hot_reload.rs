@@ -0,0 +1,120 @@
+//! Development-time asset overrides (`dev` feature): the skin shader and player models are read
+//! from files on disk and rebuilt when those files change ([`super::Renderer::watch_assets`]).
+//!
+//! Changes are found by polling modification times, so a caller decides when a reload may happen
+//! — once per frame in the preview, between jobs in a batch — and a half-written file is simply
+//! picked up again on the next save.
+
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::character::SkinType;
+
+/// Files that replace built-in assets while they are watched. Unset entries keep the built-in
+/// asset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssetOverrides {
+    /// WGSL source replacing [`crate::constants::SHADER`] for skin pipelines. It must keep the
+    /// entry points, bindings and override constants of the built-in shader.
+    pub shader: Option<PathBuf>,
+    /// OBJ model for classic (4px-arm) characters.
+    pub classic_model: Option<PathBuf>,
+    /// OBJ model for slim (3px-arm) characters.
+    pub slim_model: Option<PathBuf>,
+}
+
+/// What a watched file replaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Asset {
+    Shader,
+    Model(SkinType),
+}
+
+struct Watched {
+    asset: Asset,
+    path: PathBuf,
+    /// Modification time at the last poll; `None` until the file has been seen.
+    modified: Option<SystemTime>,
+}
+
+/// Polls the files of an [`AssetOverrides`] for changes.
+pub(crate) struct AssetWatcher {
+    files: Vec<Watched>,
+}
+
+impl AssetWatcher {
+    pub(crate) fn new(overrides: &AssetOverrides) -> Self {
+        let files = [
+            (Asset::Shader, &overrides.shader),
+            (Asset::Model(SkinType::Classic), &overrides.classic_model),
+            (Asset::Model(SkinType::Slim), &overrides.slim_model),
+        ]
+        .into_iter()
+        .filter_map(|(asset, path)| {
+            path.clone().map(|path| Watched {
+                asset,
+                path,
+                modified: None,
+            })
+        })
+        .collect();
+        Self { files }
+    }
+
+    /// Watched files whose modification time changed since the last poll; every existing file on
+    /// the first poll. Missing files are skipped until they appear.
+    pub(crate) fn poll(&mut self) -> Vec<(Asset, PathBuf)> {
+        let mut changed = Vec::new();
+        for file in &mut self.files {
+            let Ok(modified) = std::fs::metadata(&file.path).and_then(|m| m.modified()) else {
+                continue;
+            };
+            if file.modified != Some(modified) {
+                file.modified = Some(modified);
+                changed.push((file.asset, file.path.clone()));
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn unset_overrides_are_not_watched() {
+        let watcher = AssetWatcher::new(&AssetOverrides {
+            slim_model: Some("slim.obj".into()),
+            ..AssetOverrides::default()
+        });
+        assert_eq!(watcher.files.len(), 1);
+        assert_eq!(watcher.files[0].asset, Asset::Model(SkinType::Slim));
+    }
+
+    #[test]
+    fn poll_reports_new_and_modified_files_once() {
+        let dir = std::env::temp_dir().join(format!("eidolon-hot-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let shader = dir.join("shader.wgsl");
+        let _ = std::fs::remove_file(&shader);
+
+        let mut watcher = AssetWatcher::new(&AssetOverrides {
+            shader: Some(shader.clone()),
+            ..AssetOverrides::default()
+        });
+        assert!(watcher.poll().is_empty(), "missing files are skipped");
+
+        std::fs::write(&shader, "// v1").unwrap();
+        assert_eq!(watcher.poll(), [(Asset::Shader, shader.clone())]);
+        assert!(watcher.poll().is_empty());
+
+        let file = std::fs::File::options().write(true).open(&shader).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(watcher.poll(), [(Asset::Shader, shader.clone())]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+//! Accounting of the GPU memory a renderer keeps between renders ([`super::Renderer::gpu_memory`]).
+
+/// GPU memory held by a renderer, in bytes, by kind.
+///
+/// Computed from the sizes and formats of its allocations; drivers add alignment and padding, and
+/// compiled pipelines are not counted. Skins and attachments uploaded for the caller
+/// ([`Texture`](crate::texture::Texture), [`AttachmentMesh`](crate::attachment::AttachmentMesh))
+/// belong to the caller and are freed when dropped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GpuMemory {
+    /// Cached render targets sized by recent renders: depth, post-processing scene color, bloom,
+    /// multisampled scene targets and the shadow map.
+    pub framebuffers: u64,
+    /// Vertex buffers of the player models, the ground and headwear meshes.
+    pub geometry: u64,
+    /// Textures the renderer owns: the mannequin skin and the placeholders bound while effects are
+    /// off.
+    pub textures: u64,
+    /// Uniform buffers.
+    pub buffers: u64,
+}
+
+impl GpuMemory {
+    /// Sum of all kinds.
+    pub fn total(&self) -> u64 {
+        self.framebuffers + self.geometry + self.textures + self.buffers
+    }
+}
+
+/// Bytes of `texture`'s texels, all mip levels and samples.
+pub(crate) fn texture_bytes(texture: &wgpu::Texture) -> u64 {
+    let format = texture.format();
+    let size = texture.size();
+    let texels: u64 = (0..texture.mip_level_count())
+        .map(|level| {
+            format.theoretical_memory_footprint(wgpu::Extent3d {
+                width: (size.width >> level).max(1),
+                height: (size.height >> level).max(1),
+                depth_or_array_layers: size.depth_or_array_layers,
+            })
+        })
+        .sum();
+    texels * u64::from(texture.sample_count())
+}
+
+/// Bytes of a single-sampled `width`×`height` target in `format`, without mip levels.
+pub(crate) fn target_bytes(format: wgpu::TextureFormat, width: u32, height: u32) -> u64 {
+    format.theoretical_memory_footprint(wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_count_every_texel() {
+        assert_eq!(
+            target_bytes(wgpu::TextureFormat::Rgba8Unorm, 800, 600),
+            1_920_000
+        );
+        assert_eq!(target_bytes(wgpu::TextureFormat::Rgba16Float, 2, 2), 32);
+        // Depth formats have no copy size; the estimate pads 24-bit depth to 4 bytes.
+        assert_eq!(target_bytes(wgpu::TextureFormat::Depth24Plus, 10, 10), 400);
+    }
+
+    #[test]
+    fn total_adds_every_kind() {
+        let memory = GpuMemory {
+            framebuffers: 1,
+            geometry: 2,
+            textures: 3,
+            buffers: 4,
+        };
+        assert_eq!(memory.total(), 10);
+    }
+}
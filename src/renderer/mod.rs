@@ -1,101 +1,285 @@
 //! WGPU renderer: headless RGBA readback and windowed surface preview, shared skin pipeline.
 
+mod analysis;
+mod bloom;
+mod culling;
+#[cfg(not(target_arch = "wasm32"))]
+mod crowd;
+mod hdr;
+#[cfg(all(feature = "dev", not(target_arch = "wasm32")))]
+mod hot_reload;
+mod memory;
+mod models;
+mod msaa;
+mod options;
+mod particles;
+mod pick;
 mod pipeline;
+mod posed;
+mod post;
 mod readback;
+mod segmentation;
+mod shadow;
+mod sprites;
+mod stereo;
 mod uniforms;
+mod variants;
 
-use std::cell::RefCell;
+pub use analysis::{BrightnessStats, DEFAULT_TARGET_LUMINANCE};
+#[cfg(not(target_arch = "wasm32"))]
+pub use crowd::CrowdMember;
+pub use hdr::{ExrChannels, HdrImage};
+#[cfg(all(feature = "dev", not(target_arch = "wasm32")))]
+pub use hot_reload::AssetOverrides;
+pub use memory::GpuMemory;
+pub use options::{
+    AlphaMode, AlphaTest, ColorAdjustments, DepthOfField, EmissiveOverlay, LayerAlpha, Msaa,
+    RenderOptions, ShadowSettings, EnchantmentGlint, StatusEffects, TextureFilter, ToneMapping,
+};
+pub use particles::{ParticleAnchor, ParticleEmitter, ParticlePreset, Particles};
+pub use pick::Pick;
+pub use posed::{PosedMesh, PosedSource};
+pub use segmentation::{segment_color, SegmentationMap};
+pub use stereo::{StereoLayout, StereoSettings};
+
+use std::cell::{OnceCell, RefCell};
 #[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
 #[cfg(not(target_arch = "wasm32"))]
 use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
 
 use image::{ImageBuffer, ImageFormat, Rgba};
 #[cfg(not(target_arch = "wasm32"))]
 use winit::window::Window;
 
+use crate::attachment::{AttachmentMesh, AttachmentModel, MAX_ATTACHMENTS};
+use crate::bedrock::BedrockGeometry;
 use crate::camera::Camera;
-use crate::character::{Character, SkinType};
+use crate::cancel::CancelToken;
+use crate::character::{Bone, Character, SkinType};
 use crate::error::EidolonError;
-use crate::model::Model;
-use crate::texture::Texture;
+use crate::headwear::{self, HeadwearShape};
+use crate::lighting::{Lighting, LightingPreset};
+use crate::metadata::RenderMetadata;
+use crate::model::{Model, ModelPart};
+use crate::skin_layout::{self, Layer};
+use crate::texture::{SkinId, SkinImage, Texture};
+
+#[cfg(all(feature = "dev", not(target_arch = "wasm32")))]
+use hot_reload::{Asset, AssetWatcher};
+use models::ModelCache;
+use msaa::{DepthResolve, MsaaSupport, MsaaTargets};
+#[cfg(not(target_arch = "wasm32"))]
+use variants::VariantCache;
+use variants::ShaderFeatures;
+use bloom::{
+    compute_blur_uniforms, create_bloom_texture, create_blur_bind_group,
+    create_blur_bind_group_layout, BloomTargets, BlurUniforms, BLOOM_FORMAT,
+};
+use pipeline::{
+    create_bloom_shader_module, create_blur_pipeline, create_depth_output_pipeline,
+    create_emissive_pipeline, create_post_shader_module, create_scene_pipelines,
+    create_shader_module, create_shadow_pipeline, create_sprite_shader_module,
+    create_target_pipelines, PipelineSources,
+    ScenePipelines, TargetPipelines, DEPTH_FORMAT, HDR_FORMAT, RENDER_TARGET_FORMAT,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use pipeline::{
+    create_composite_pipeline, create_crowd_pipeline, create_segmentation_pipeline,
+    DEPTH_OUTPUT_FORMAT, FLOAT_TARGET_FORMAT,
+};
+use post::{
+    compute_post_uniforms, create_post_bind_group, create_post_bind_group_layout, PostUniforms,
+};
+use shadow::{
+    clamp_resolution, compute_ground_uniforms, compute_shadow_uniforms, create_ground_mesh,
+    ShadowMap, ShadowUniforms, GROUND_SLOT,
+};
+use particles::particle_sprites;
+use sprites::{
+    compute_sprite_uniforms, create_sprite_bind_group_layout, status_sprites, SpriteUniforms,
+};
+use uniforms::{
+    apply_glint, body_part_ref, bone_index, cape_model_matrix, compute_adjust_uniforms,
+    compute_attachment_uniforms, compute_body_part_uniforms, compute_cape_uniforms,
+    compute_occlusion_uniforms, part_bone, part_model_matrices, shown_layers, AdjustUniforms, LightUniforms, OcclusionUniforms,
+    Uniforms,
+    BODY_PART_COUNT, PART_CONFIGS,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use uniforms::compute_instanced_part_uniforms;
+
+/// Uniform buffer slot of the first of a character's attachments; the others follow.
+const FIRST_ATTACHMENT_SLOT: usize = GROUND_SLOT + 1;
+
+/// Uniform buffer slot of the overlay layer of the first body part; the others follow in
+/// [`PART_CONFIGS`] order. Overlays share their part's transform but have their own alpha test.
+const FIRST_OVERLAY_SLOT: usize = FIRST_ATTACHMENT_SLOT + MAX_ATTACHMENTS;
 
-use pipeline::{create_pipeline, DEPTH_FORMAT, RENDER_TARGET_FORMAT};
-use uniforms::{body_part_ref, compute_body_part_uniforms, BODY_PART_COUNT, PART_CONFIGS};
+/// Uniform buffer slot of the headwear: the head's overlay uniforms, told apart in segmentation
+/// maps.
+const HEADWEAR_SLOT: usize = FIRST_OVERLAY_SLOT + BODY_PART_COUNT;
+
+/// Uniform buffer slot of the cape.
+const CAPE_SLOT: usize = HEADWEAR_SLOT + 1;
+
+/// Base color of [`RenderOptions::mannequin_fallback`] parts.
+const MANNEQUIN_COLOR: [u8; 4] = [150, 150, 150, 255];
 
 /// Image format for [`Renderer::render_to_image`].
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputFormat {
     Png,
     WebP,
+    /// 16 bits per channel PNG, rendered through the float pipeline ([`Renderer::render_16bit`]).
+    Png16,
+    /// Linear-light OpenEXR with the selected channels ([`Renderer::render_hdr`]).
+    Exr(ExrChannels),
 }
 
 impl OutputFormat {
     pub fn as_image_format(&self) -> ImageFormat {
         match self {
-            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Png | OutputFormat::Png16 => ImageFormat::Png,
             OutputFormat::WebP => ImageFormat::WebP,
+            OutputFormat::Exr(_) => ImageFormat::OpenExr,
         }
     }
 
-    /// File extension without the dot: `"png"` or `"webp"`.
+    /// File extension without the dot: `"png"`, `"webp"` or `"exr"`.
     pub fn extension(&self) -> &'static str {
         match self {
-            OutputFormat::Png => "png",
+            OutputFormat::Png | OutputFormat::Png16 => "png",
             OutputFormat::WebP => "webp",
+            OutputFormat::Exr(_) => "exr",
         }
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn output_format_png_as_image_format() {
-        assert_eq!(OutputFormat::Png.as_image_format(), ImageFormat::Png);
-    }
-
-    #[test]
-    fn output_format_webp_as_image_format() {
-        assert_eq!(OutputFormat::WebP.as_image_format(), ImageFormat::WebP);
-    }
-
-    #[test]
-    fn output_format_png_extension() {
-        assert_eq!(OutputFormat::Png.extension(), "png");
-    }
 
-    #[test]
-    fn output_format_webp_extension() {
-        assert_eq!(OutputFormat::WebP.extension(), "webp");
+    /// MIME type of the encoded output, e.g. `"image/png"`.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Png | OutputFormat::Png16 => "image/png",
+            OutputFormat::WebP => "image/webp",
+            OutputFormat::Exr(_) => "image/x-exr",
+        }
     }
 
-    #[test]
-    fn output_format_debug() {
-        assert!(format!("{:?}", OutputFormat::Png).contains("Png"));
-        assert!(format!("{:?}", OutputFormat::WebP).contains("WebP"));
+    /// `filename` with its extension replaced to match this format (e.g. `"skin.png"` with
+    /// `WebP` becomes `"skin.webp"`). Rejects filenames containing null bytes.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn output_path(&self, filename: &str) -> Result<String, EidolonError> {
+        if filename.contains('\0') {
+            return Err(EidolonError::invalid_path("output filename contains null bytes"));
+        }
+        Ok(match Path::new(filename).extension() {
+            Some(ext) if ext == self.extension() => filename.to_string(),
+            _ => {
+                let stem = Path::new(filename)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy())
+                    .unwrap_or_else(|| filename.into());
+                format!("{}.{}", stem, self.extension())
+            }
+        })
     }
 }
 
 pub struct Renderer {
     device: wgpu::Device,
     queue: wgpu::Queue,
-    pipeline: wgpu::RenderPipeline,
+    pipeline: TargetPipelines,
+    /// Scene pipelines for the float intermediate target used by post-processing.
+    hdr_pipeline: ScenePipelines,
+    /// Composite into [`FLOAT_TARGET_FORMAT`] for float outputs.
+    #[cfg(not(target_arch = "wasm32"))]
+    float_composite_pipeline: wgpu::RenderPipeline,
+    /// Instanced skin pipeline of [`Renderer::render_crowd`].
+    #[cfg(not(target_arch = "wasm32"))]
+    crowd_pipeline: VariantCache,
+    /// Group 1 of the crowd pipeline: skin texture array and sampler.
+    #[cfg(not(target_arch = "wasm32"))]
+    skin_array_bind_group_layout: wgpu::BindGroupLayout,
+    /// Segment ids of [`Renderer::render_segmentation`].
+    #[cfg(not(target_arch = "wasm32"))]
+    segmentation_pipeline: wgpu::RenderPipeline,
+    /// Linear depth into [`DEPTH_OUTPUT_FORMAT`] alongside float outputs.
+    depth_output_pipeline: wgpu::RenderPipeline,
+    shadow_pipeline: wgpu::RenderPipeline,
     texture_bind_group_layout: wgpu::BindGroupLayout,
+    /// Skin sampler of [`TextureFilter::Nearest`], built into every [`Texture`]'s bind group.
     sampler: wgpu::Sampler,
+    /// Skin samplers of [`TextureFilter::NearestMipmapped`] and [`TextureFilter::Linear`].
+    filtered_samplers: [wgpu::Sampler; 2],
     uniform_buffer: wgpu::Buffer,
     uniform_bind_group: wgpu::BindGroup,
     uniform_aligned_size: u32,
-    slim_model: Model,
-    default_model: Model,
+    light_buffer: wgpu::Buffer,
+    occlusion_buffer: wgpu::Buffer,
+    shadow_buffer: wgpu::Buffer,
+    adjust_buffer: wgpu::Buffer,
+    shadow_bind_group_layout: wgpu::BindGroupLayout,
+    shadow_sampler: wgpu::Sampler,
+    /// 1×1 shadow map bound while shadows are off (group 2 must always be bound).
+    placeholder_shadow_map: ShadowMap,
+    /// Key-light shadow map; recreated when the requested resolution changes.
+    cached_shadow_map: RefCell<Option<ShadowMap>>,
+    ground_mesh: ModelPart,
+    /// Meshes of the [`HeadwearShape`]s, in [`HeadwearShape::ALL`] order.
+    headwear_meshes: [ModelPart; 2],
+    /// Gray skin drawn for blank parts under [`RenderOptions::mannequin_fallback`].
+    mannequin_skin: Texture,
+    /// Camera of the status-effect sprite pass.
+    sprite_buffer: wgpu::Buffer,
+    sprite_bind_group: wgpu::BindGroup,
+    post_bind_group_layout: wgpu::BindGroupLayout,
+    post_buffer: wgpu::Buffer,
+    /// Intermediate scene color target for post-processing; recreated when dimensions change.
+    cached_scene_texture: RefCell<Option<(wgpu::Texture, u32, u32)>>,
+    emissive_pipeline: wgpu::RenderPipeline,
+    blur_pipeline: wgpu::RenderPipeline,
+    blur_bind_group_layout: wgpu::BindGroupLayout,
+    /// Horizontal and vertical blur pass uniforms (separate buffers: both are used in one submit).
+    blur_buffers: [wgpu::Buffer; 2],
+    /// 1×1 transparent texture bound for the emissive/bloom inputs while the overlay is not emissive.
+    placeholder_bloom_view: wgpu::TextureView,
+    /// Emissive + blur targets; recreated when dimensions change.
+    cached_bloom_targets: RefCell<Option<BloomTargets>>,
+    /// Classic and slim models, uploaded on first use.
+    models: ModelCache,
+    /// Shader and model files replacing the built-in ones ([`Renderer::watch_assets`]).
+    #[cfg(all(feature = "dev", not(target_arch = "wasm32")))]
+    asset_watcher: Option<AssetWatcher>,
     surface: Option<wgpu::Surface<'static>>,
     surface_config: Option<wgpu::SurfaceConfiguration>,
-    surface_pipeline: Option<wgpu::RenderPipeline>,
+    surface_pipeline: Option<TargetPipelines>,
     /// Cached depth buffer; recreated when dimensions change (avoids per-frame alloc in windowed preview).
     cached_depth_texture: RefCell<Option<(wgpu::Texture, u32, u32)>>,
+    /// Multisampled scene targets of [`RenderOptions::msaa`]; recreated when the format, size or
+    /// sample count changes.
+    cached_msaa_targets: RefCell<Option<MsaaTargets>>,
+    /// Sample counts the scene's color formats can render and resolve.
+    msaa_support: MsaaSupport,
+    /// Depth resolve of multisampled renders with post-processing, created on first use.
+    depth_resolve: OnceCell<DepthResolve>,
     /// RGBA clear color for the render pass background. Default: transparent black.
     clear_color: [f64; 4],
+    /// Key/fill/ambient light rig uploaded to the shader each render pass.
+    lighting: Lighting,
+    /// Optional render features (ambient occlusion, ...).
+    options: RenderOptions,
+    /// Whether [`Renderer::render_to_image`] embeds [`RenderMetadata`] in PNG and WebP files.
+    embed_metadata: bool,
+    /// Whether [`Renderer::encode`] tags PNG output as sRGB.
+    embed_srgb: bool,
+    /// Created by [`Renderer::new_deterministic`]: software adapter, single-threaded encoding.
+    deterministic: bool,
+    /// Checked between views, frames and members ([`Renderer::set_cancel_token`]).
+    cancel: Option<CancelToken>,
+    /// Longest wait for each readback ([`Renderer::set_gpu_timeout`]).
+    #[cfg(not(target_arch = "wasm32"))]
+    gpu_timeout: Option<Duration>,
 }
 
 impl Renderer {
@@ -104,17 +288,20 @@ impl Renderer {
     fn create_wgpu_device(
         instance: &wgpu::Instance,
         compatible_surface: Option<&wgpu::Surface>,
+        force_fallback_adapter: bool,
     ) -> Result<(wgpu::Device, wgpu::Queue, wgpu::Adapter), EidolonError> {
         let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
             power_preference: wgpu::PowerPreference::HighPerformance,
             compatible_surface,
-            force_fallback_adapter: false,
+            force_fallback_adapter,
         }))
         .map_err(|e| EidolonError::gpu(format!("failed to request adapter: {e}")))?;
         let (device, queue) = pollster::block_on(adapter.request_device(
             &wgpu::DeviceDescriptor {
                 label: Some("Eidolon Device"),
-                required_features: wgpu::Features::empty(),
+                // Sample counts beyond the guaranteed 4× for `RenderOptions::msaa`.
+                required_features: adapter.features()
+                    & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES,
                 required_limits: wgpu::Limits::default(),
                 memory_hints: Default::default(),
                 trace: wgpu::Trace::Off,
@@ -131,8 +318,34 @@ impl Renderer {
             backends: wgpu::Backends::all(),
             ..Default::default()
         });
-        let (device, queue, _) = Self::create_wgpu_device(&instance, None)?;
-        Self::init_with_device(device, queue, None)
+        let (device, queue, adapter) = Self::create_wgpu_device(&instance, None, false)?;
+        Self::init_with_device(&adapter, device, queue, None)
+    }
+
+    /// Headless renderer whose output depends only on its inputs, for golden-image tests and
+    /// deduplication by output hash.
+    ///
+    /// Renders on the software (CPU) fallback adapter (llvmpipe, WARP or SwiftShader), since
+    /// hardware GPUs and drivers round differently, and encodes files single-threaded (see
+    /// [`Renderer::render_to_image`]). Output is bit-identical between runs on the same software
+    /// adapter version; fails if no software adapter is installed.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new_deterministic() -> Result<Self, EidolonError> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        let (device, queue, adapter) = Self::create_wgpu_device(&instance, None, true)
+            .map_err(|e| {
+                EidolonError::gpu(format!(
+                    "deterministic rendering needs a software adapter (llvmpipe, WARP or SwiftShader): {e}"
+                ))
+            })?;
+        let info = adapter.get_info();
+        tracing::info!("Deterministic adapter: {} ({:?})", info.name, info.backend);
+        let mut renderer = Self::init_with_device(&adapter, device, queue, None)?;
+        renderer.deterministic = true;
+        Ok(renderer)
     }
 
     /// Windowed renderer: creates a surface and optional second pipeline if the swapchain format differs.
@@ -145,7 +358,7 @@ impl Renderer {
         let surface = instance
             .create_surface(window.clone())
             .map_err(|e| EidolonError::gpu(format!("failed to create surface: {e}")))?;
-        let (device, queue, adapter) = Self::create_wgpu_device(&instance, Some(&surface))?;
+        let (device, queue, adapter) = Self::create_wgpu_device(&instance, Some(&surface), false)?;
 
         let size = window.inner_size();
         let surface_caps = surface.get_capabilities(&adapter);
@@ -180,7 +393,7 @@ impl Renderer {
         };
         surface.configure(&device, &config);
 
-        Self::init_with_device(device, queue, Some((surface, config, surface_format)))
+        Self::init_with_device(&adapter, device, queue, Some((surface, config, surface_format)))
     }
 
     /// Headless renderer for WASM: offscreen target with async adapter/device creation.
@@ -208,7 +421,7 @@ impl Renderer {
             })
             .await
             .map_err(|e| EidolonError::gpu(format!("failed to request GPU device: {e}")))?;
-        Self::init_with_device(device, queue, None)
+        Self::init_with_device(&adapter, device, queue, None)
     }
 
     /// Windowed renderer for WASM: creates a surface from a canvas and configures it.
@@ -275,10 +488,11 @@ impl Renderer {
         };
         surface.configure(&device, &config);
 
-        Self::init_with_device(device, queue, Some((surface, config, surface_format)))
+        Self::init_with_device(&adapter, device, queue, Some((surface, config, surface_format)))
     }
 
     fn init_with_device(
+        adapter: &wgpu::Adapter,
         device: wgpu::Device,
         queue: wgpu::Queue,
         surface_info: Option<(
@@ -290,24 +504,74 @@ impl Renderer {
         // Log wgpu errors (shader compilation, pipeline creation, etc.)
         // instead of letting them silently drop on the GPU error queue.
         device.on_uncaptured_error(Box::new(|error| {
-            log::error!("wgpu error: {:?}", error);
+            tracing::error!("wgpu error: {:?}", error);
         }));
 
         let uniform_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 label: Some("Uniform Bind Group Layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: true,
-                        min_binding_size: wgpu::BufferSize::new(
-                            std::mem::size_of::<uniforms::Uniforms>() as u64,
-                        ),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: wgpu::BufferSize::new(
+                                std::mem::size_of::<uniforms::Uniforms>() as u64,
+                            ),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(
+                                std::mem::size_of::<LightUniforms>() as u64,
+                            ),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(
+                                std::mem::size_of::<OcclusionUniforms>() as u64,
+                            ),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(
+                                std::mem::size_of::<ShadowUniforms>() as u64,
+                            ),
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: wgpu::BufferSize::new(
+                                std::mem::size_of::<AdjustUniforms>() as u64,
+                            ),
+                        },
+                        count: None,
                     },
-                    count: None,
-                }],
+                ],
             });
 
         let texture_bind_group_layout =
@@ -333,6 +597,29 @@ impl Renderer {
                 ],
             });
 
+        let shadow_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Shadow Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            sample_type: wgpu::TextureSampleType::Depth,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                        count: None,
+                    },
+                ],
+            });
+
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             label: Some("Skin Sampler"),
             address_mode_u: wgpu::AddressMode::Repeat,
@@ -341,33 +628,197 @@ impl Renderer {
             mag_filter: wgpu::FilterMode::Nearest,
             min_filter: wgpu::FilterMode::Nearest,
             mipmap_filter: wgpu::FilterMode::Nearest,
+            // Full-size texels only; the mipmaps of HD skins are for the other filters.
+            lod_max_clamp: 0.0,
+            ..Default::default()
+        });
+        let filtered_samplers = [
+            (wgpu::FilterMode::Nearest, wgpu::FilterMode::Nearest),
+            (wgpu::FilterMode::Linear, wgpu::FilterMode::Linear),
+        ]
+        .map(|(filter, mipmap_filter)| {
+            device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("Filtered Skin Sampler"),
+                address_mode_u: wgpu::AddressMode::Repeat,
+                address_mode_v: wgpu::AddressMode::Repeat,
+                address_mode_w: wgpu::AddressMode::Repeat,
+                mag_filter: filter,
+                min_filter: filter,
+                mipmap_filter,
+                ..Default::default()
+            })
+        });
+
+        let shadow_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
             ..Default::default()
         });
 
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Pipeline Layout"),
-            bind_group_layouts: &[&uniform_bind_group_layout, &texture_bind_group_layout],
+            bind_group_layouts: &[
+                &uniform_bind_group_layout,
+                &texture_bind_group_layout,
+                &shadow_bind_group_layout,
+            ],
+            push_constant_ranges: &[],
+        });
+
+        let shadow_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Shadow Pipeline Layout"),
+                bind_group_layouts: &[&uniform_bind_group_layout, &texture_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let post_bind_group_layout = create_post_bind_group_layout(&device);
+        let post_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Post Pipeline Layout"),
+            bind_group_layouts: &[&post_bind_group_layout],
             push_constant_ranges: &[],
         });
 
-        let pipeline = create_pipeline(&device, &pipeline_layout, RENDER_TARGET_FORMAT);
+        let sprite_bind_group_layout = create_sprite_bind_group_layout(&device);
+        let sprite_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Sprite Pipeline Layout"),
+                bind_group_layouts: &[&sprite_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let shader = create_shader_module(&device);
+        let post_shader = create_post_shader_module(&device);
+        let sprite_shader = create_sprite_shader_module(&device);
+        let sources = PipelineSources {
+            shader: &shader,
+            layout: &pipeline_layout,
+            post_shader: &post_shader,
+            post_layout: &post_pipeline_layout,
+            sprite_shader: &sprite_shader,
+            sprite_layout: &sprite_pipeline_layout,
+        };
+        let pipeline = create_target_pipelines(&device, &sources, RENDER_TARGET_FORMAT);
+        let hdr_pipeline = create_scene_pipelines(&device, &sources, HDR_FORMAT);
+        #[cfg(not(target_arch = "wasm32"))]
+        let float_composite_pipeline = create_composite_pipeline(
+            &device,
+            &post_shader,
+            &post_pipeline_layout,
+            FLOAT_TARGET_FORMAT,
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        let skin_array_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Skin Array Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2Array,
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        },
+                        count: None,
+                    },
+                ],
+            });
+        #[cfg(not(target_arch = "wasm32"))]
+        let crowd_pipeline = create_crowd_pipeline(
+            &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Crowd Pipeline Layout"),
+                bind_group_layouts: &[
+                    &uniform_bind_group_layout,
+                    &skin_array_bind_group_layout,
+                    &shadow_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            }),
+            RENDER_TARGET_FORMAT,
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        let segmentation_pipeline =
+            create_segmentation_pipeline(&device, &shader, &pipeline_layout);
+        let depth_output_pipeline =
+            create_depth_output_pipeline(&device, &post_shader, &post_pipeline_layout);
+        let shadow_pipeline = create_shadow_pipeline(&device, &shader, &shadow_pipeline_layout);
+        let emissive_pipeline = create_emissive_pipeline(&device, &shader, &pipeline_layout);
+
+        let blur_bind_group_layout = create_blur_bind_group_layout(&device);
+        let blur_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blur Pipeline Layout"),
+            bind_group_layouts: &[&blur_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let blur_pipeline = create_blur_pipeline(
+            &device,
+            &create_bloom_shader_module(&device),
+            &blur_pipeline_layout,
+        );
 
         let surface_pipeline = surface_info.as_ref().and_then(|(_, _, format)| {
             if *format != RENDER_TARGET_FORMAT {
-                Some(create_pipeline(&device, &pipeline_layout, *format))
+                Some(create_target_pipelines(&device, &sources, *format))
             } else {
                 None
             }
         });
 
+        let mut scene_formats = vec![RENDER_TARGET_FORMAT, HDR_FORMAT];
+        scene_formats.extend(surface_info.as_ref().map(|(_, _, format)| *format));
+        let msaa_support = MsaaSupport::new(adapter, &device, &scene_formats);
+
         let alignment = device.limits().min_uniform_buffer_offset_alignment;
         let uniform_size = std::mem::size_of::<uniforms::Uniforms>() as u32;
         let aligned_size = uniform_size.div_ceil(alignment) * alignment;
-        let num_body_parts = BODY_PART_COUNT as u32;
+        // One slot per body part, the ground quad, the attachments, the body part overlays, the
+        // headwear and the cape.
+        let num_slots = (CAPE_SLOT + 1) as u32;
 
         let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("Dynamic Uniform Buffer"),
-            size: (num_body_parts * aligned_size) as u64,
+            size: (num_slots * aligned_size) as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let light_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Light Uniform Buffer"),
+            size: std::mem::size_of::<LightUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let occlusion_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Occlusion Uniform Buffer"),
+            size: std::mem::size_of::<OcclusionUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let shadow_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shadow Uniform Buffer"),
+            size: std::mem::size_of::<ShadowUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let adjust_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Adjust Uniform Buffer"),
+            size: std::mem::size_of::<AdjustUniforms>() as u64,
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -375,40 +826,90 @@ impl Renderer {
         let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("Uniform Bind Group"),
             layout: &uniform_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &uniform_buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(uniform_size as u64),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: light_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: occlusion_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: shadow_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: adjust_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let placeholder_shadow_map =
+            ShadowMap::new(&device, &shadow_bind_group_layout, &shadow_sampler, 1);
+        let ground_mesh = create_ground_mesh(&device);
+        let headwear_meshes = HeadwearShape::ALL
+            .map(|shape| ModelPart::upload(&device, "Headwear", &headwear::vertices(shape)));
+        let mannequin_skin = Texture::upload(
+            &device,
+            &queue,
+            &texture_bind_group_layout,
+            &sampler,
+            &SkinImage::from_rgba(skin_layout::mannequin(MANNEQUIN_COLOR))?,
+        );
+
+        let sprite_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Sprite Uniform Buffer"),
+            size: std::mem::size_of::<SpriteUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let sprite_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Sprite Bind Group"),
+            layout: &sprite_bind_group_layout,
             entries: &[wgpu::BindGroupEntry {
                 binding: 0,
-                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                    buffer: &uniform_buffer,
-                    offset: 0,
-                    size: wgpu::BufferSize::new(uniform_size as u64),
-                }),
+                resource: sprite_buffer.as_entire_binding(),
             }],
         });
 
-        #[cfg(not(target_arch = "wasm32"))]
-        let (slim_model, default_model) = {
-            let slim = Model::load_from_obj(&device, "resources/slim.obj")?;
-            let classic = Model::load_from_obj(&device, "resources/classic.obj")?;
-            (slim, classic)
-        };
+        let post_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Post Uniform Buffer"),
+            size: std::mem::size_of::<PostUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
 
-        #[cfg(target_arch = "wasm32")]
-        let (slim_model, default_model) = {
-            let slim = Model::load_from_obj_bytes(
-                &device,
-                include_bytes!("../../resources/slim.obj"),
-                "slim.obj",
-            )?;
-            let classic = Model::load_from_obj_bytes(
-                &device,
-                include_bytes!("../../resources/classic.obj"),
-                "classic.obj",
-            )?;
-            (slim, classic)
-        };
+        let blur_buffers = ["Blur Uniform Buffer (horizontal)", "Blur Uniform Buffer (vertical)"]
+            .map(|label| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(label),
+                    size: std::mem::size_of::<BlurUniforms>() as u64,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                })
+            });
+        let placeholder_bloom_view = create_bloom_texture(&device, "Placeholder Bloom Texture", 1, 1)
+            .create_view(&wgpu::TextureViewDescriptor::default());
 
+        let models = ModelCache::new();
         let (surface, surface_config) = match surface_info {
-            Some((s, c, _)) => (Some(s), Some(c)),
+            Some((s, c, _)) => {
+                // `render_frame` only reports surface errors, so a preview loads both models up
+                // front instead of on its first frame.
+                models.get(&device, SkinType::Classic)?;
+                models.get(&device, SkinType::Slim)?;
+                (Some(s), Some(c))
+            }
             None => (None, None),
         };
 
@@ -416,18 +917,64 @@ impl Renderer {
             device,
             queue,
             pipeline,
+            hdr_pipeline,
+            #[cfg(not(target_arch = "wasm32"))]
+            float_composite_pipeline,
+            #[cfg(not(target_arch = "wasm32"))]
+            crowd_pipeline,
+            #[cfg(not(target_arch = "wasm32"))]
+            skin_array_bind_group_layout,
+            #[cfg(not(target_arch = "wasm32"))]
+            segmentation_pipeline,
+            depth_output_pipeline,
+            shadow_pipeline,
             texture_bind_group_layout,
             sampler,
+            filtered_samplers,
             uniform_buffer,
             uniform_bind_group,
             uniform_aligned_size: aligned_size,
-            slim_model,
-            default_model,
+            light_buffer,
+            occlusion_buffer,
+            shadow_buffer,
+            adjust_buffer,
+            shadow_bind_group_layout,
+            shadow_sampler,
+            placeholder_shadow_map,
+            cached_shadow_map: RefCell::new(None),
+            ground_mesh,
+            headwear_meshes,
+            mannequin_skin,
+            sprite_buffer,
+            sprite_bind_group,
+            post_bind_group_layout,
+            post_buffer,
+            cached_scene_texture: RefCell::new(None),
+            emissive_pipeline,
+            blur_pipeline,
+            blur_bind_group_layout,
+            blur_buffers,
+            placeholder_bloom_view,
+            cached_bloom_targets: RefCell::new(None),
+            models,
+            #[cfg(all(feature = "dev", not(target_arch = "wasm32")))]
+            asset_watcher: None,
             surface,
             surface_config,
             surface_pipeline,
             cached_depth_texture: RefCell::new(None),
+            cached_msaa_targets: RefCell::new(None),
+            msaa_support,
+            depth_resolve: OnceCell::new(),
             clear_color: [0.0, 0.0, 0.0, 0.0],
+            lighting: Lighting::default(),
+            options: RenderOptions::default(),
+            embed_metadata: false,
+            embed_srgb: false,
+            deterministic: false,
+            cancel: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            gpu_timeout: None,
         })
     }
 
@@ -466,72 +1013,1433 @@ impl Renderer {
         )
     }
 
-    #[allow(clippy::too_many_arguments)]
-    fn encode_render_pass(
-        &self,
-        encoder: &mut wgpu::CommandEncoder,
-        target_view: &wgpu::TextureView,
-        pipeline: &wgpu::RenderPipeline,
-        character: &Character,
-        skin: &Texture,
-        camera: &Camera,
-        width: u32,
-        height: u32,
-    ) {
-        let model = match character.skin_type {
-            SkinType::Slim => &self.slim_model,
-            SkinType::Classic => &self.default_model,
-        };
+    /// Upload a skin decoded with [`SkinImage`] (possibly on another thread).
+    pub fn upload_skin(&self, skin: &SkinImage) -> Texture {
+        Texture::upload(
+            &self.device,
+            &self.queue,
+            &self.texture_bind_group_layout,
+            &self.sampler,
+            skin,
+        )
+    }
 
-        let uniforms = compute_body_part_uniforms(character, camera, width, height);
+    /// Upload an attachment model, to be worn through [`crate::attachment::Attachment`]s.
+    pub fn upload_attachment(&self, model: &AttachmentModel) -> AttachmentMesh {
+        AttachmentMesh::upload(&self.device, model)
+    }
 
-        for (i, uniform) in uniforms.iter().enumerate() {
-            let offset = (i as u64) * (self.uniform_aligned_size as u64);
-            self.queue
-                .write_buffer(&self.uniform_buffer, offset, bytemuck::bytes_of(uniform));
-        }
+    /// Use the OBJ geometry in `data` for characters of `skin_type` instead of the bundled model.
+    /// The mesh needs the same named parts as `resources/classic.obj`.
+    pub fn set_model_from_obj_bytes(
+        &mut self,
+        skin_type: SkinType,
+        data: &[u8],
+        name_hint: &str,
+    ) -> Result<(), EidolonError> {
+        let model = Model::load_from_obj_bytes(&self.device, data, name_hint)?;
+        self.models.set(&self.device, skin_type, model);
+        Ok(())
+    }
+
+    /// Draw characters of `skin_type` with Bedrock geometry ([`crate::bedrock`]) instead of the
+    /// bundled model. Postures still rotate limbs about the standard joints.
+    pub fn set_model_from_bedrock(
+        &mut self,
+        skin_type: SkinType,
+        geometry: &BedrockGeometry,
+    ) -> Result<(), EidolonError> {
+        let model = Model::from_bedrock(&self.device, geometry)?;
+        self.models.set(&self.device, skin_type, model);
+        Ok(())
+    }
+
+    /// Replace the skin shader and player models with the files in `overrides` (`dev` feature)
+    /// and keep watching them: each [`Renderer::reload_changed_assets`] rebuilds what changed
+    /// since the previous call. Files that exist are loaded right away.
+    ///
+    /// The shader override applies to skin pipelines (single, crowd and float renders and the
+    /// window surface); ground, shadow, emissive and post passes keep the built-in shaders.
+    #[cfg(all(feature = "dev", not(target_arch = "wasm32")))]
+    pub fn watch_assets(&mut self, overrides: &AssetOverrides) -> Result<(), EidolonError> {
+        self.asset_watcher = Some(AssetWatcher::new(overrides));
+        self.reload_changed_assets().map(|_| ())
+    }
 
-        let depth_view = {
-            let mut cache = self.cached_depth_texture.borrow_mut();
-            let need_new = match cache.as_ref() {
-                None => true,
-                Some((_, w, h)) => *w != width || *h != height,
+    /// Rebuild the watched assets whose files changed since the last call and return whether any
+    /// did. Cheap when nothing changed (one `stat` per file), so a preview can call it every
+    /// frame.
+    ///
+    /// A shader that fails validation or a model that fails to load is returned as an error and
+    /// the previous one stays in use; the file is retried once it changes again.
+    #[cfg(all(feature = "dev", not(target_arch = "wasm32")))]
+    pub fn reload_changed_assets(&mut self) -> Result<bool, EidolonError> {
+        let Some(watcher) = &mut self.asset_watcher else {
+            return Ok(false);
+        };
+        let changed = watcher.poll();
+        let mut first_error = None;
+        for (asset, path) in &changed {
+            let result = match *asset {
+                Asset::Shader => self.reload_shader(path),
+                Asset::Model(skin_type) => {
+                    Model::load_from_obj(&self.device, &path.to_string_lossy())
+                        .map(|model| self.models.set(&self.device, skin_type, model))
+                }
             };
-            if need_new {
-                let texture = self.device.create_texture(&wgpu::TextureDescriptor {
-                    label: Some("Depth Texture"),
-                    size: wgpu::Extent3d {
-                        width,
-                        height,
-                        depth_or_array_layers: 1,
-                    },
-                    mip_level_count: 1,
-                    sample_count: 1,
-                    dimension: wgpu::TextureDimension::D2,
-                    format: DEPTH_FORMAT,
-                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                    view_formats: &[],
-                });
-                *cache = Some((texture, width, height));
+            match result {
+                Ok(()) => tracing::info!(path = %path.display(), "reloaded {asset:?}"),
+                Err(e) => {
+                    tracing::error!(path = %path.display(), "failed to reload {asset:?}: {e}");
+                    first_error.get_or_insert(e);
+                }
             }
-            cache
-                .as_ref()
-                .unwrap()
-                .0
-                .create_view(&wgpu::TextureViewDescriptor::default())
-        };
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(!changed.is_empty()),
+        }
+    }
+
+    /// Swap in the skin shader at `path` once the variant of the current options compiles.
+    #[cfg(all(feature = "dev", not(target_arch = "wasm32")))]
+    fn reload_shader(&mut self, path: &Path) -> Result<(), EidolonError> {
+        let source = std::fs::read_to_string(path)?;
+        let candidate = self.pipeline.scene.skin.with_source(&source);
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        candidate.get(
+            &self.device,
+            self.shader_features(),
+            self.msaa_samples(RENDER_TARGET_FORMAT),
+        );
+        if let Some(error) = pollster::block_on(self.device.pop_error_scope()) {
+            return Err(EidolonError::gpu(format!(
+                "shader '{}' does not compile: {error}",
+                path.display()
+            )));
+        }
+        self.pipeline.scene.skin = candidate;
+        self.hdr_pipeline.skin = self.hdr_pipeline.skin.with_source(&source);
+        self.crowd_pipeline = self.crowd_pipeline.with_source(&source);
+        if let Some(surface) = &mut self.surface_pipeline {
+            surface.scene.skin = surface.scene.skin.with_source(&source);
+        }
+        Ok(())
+    }
+
+    /// Number of skin pipeline variants compiled so far. Each distinct combination of shadows,
+    /// ambient occlusion, rim light and color adjustments compiles its own variant on first use.
+    pub fn shader_variant_count(&self) -> usize {
+        let mut count = self.pipeline.scene.skin.len() + self.hdr_pipeline.skin.len();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            count += self.crowd_pipeline.len();
+        }
+        if let Some(surface) = &self.surface_pipeline {
+            count += surface.scene.skin.len();
+        }
+        count
+    }
+
+    /// Largest width and height of an 8-bit render on this device: the texture size limit, further
+    /// reduced so the readback buffer of a square image fits the buffer size limit. Larger or zero
+    /// sizes fail with [`EidolonError::InvalidDimensions`]; float renders
+    /// ([`Renderer::render_hdr`], [`Renderer::render_16bit`]) read back twice the bytes per pixel
+    /// and accept somewhat less.
+    pub fn max_output_size(&self) -> u32 {
+        readback::max_output_side(&self.device.limits(), 4)
+    }
+
+    /// GPU memory this renderer holds between renders; see [`GpuMemory`] for what is counted.
+    pub fn gpu_memory(&self) -> GpuMemory {
+        let cached_texture = |cache: &RefCell<Option<(wgpu::Texture, u32, u32)>>| {
+            cache
+                .borrow()
+                .as_ref()
+                .map_or(0, |(texture, _, _)| memory::texture_bytes(texture))
+        };
+        let bloom = self.cached_bloom_targets.borrow();
+        let msaa = self.cached_msaa_targets.borrow();
+        let shadow = self.cached_shadow_map.borrow();
+        let framebuffers = cached_texture(&self.cached_depth_texture)
+            + cached_texture(&self.cached_scene_texture)
+            + bloom.as_ref().map_or(0, BloomTargets::bytes)
+            + msaa.as_ref().map_or(0, MsaaTargets::bytes)
+            + shadow.as_ref().map_or(0, ShadowMap::bytes);
+        let geometry = self.models.bytes()
+            + self.ground_mesh.vertex_buffer.size()
+            + self
+                .headwear_meshes
+                .iter()
+                .map(|mesh| mesh.vertex_buffer.size())
+                .sum::<u64>();
+        let textures = memory::texture_bytes(&self.mannequin_skin.texture)
+            + self.placeholder_shadow_map.bytes()
+            + memory::target_bytes(BLOOM_FORMAT, 1, 1);
+        let buffers = [
+            &self.uniform_buffer,
+            &self.light_buffer,
+            &self.occlusion_buffer,
+            &self.shadow_buffer,
+            &self.adjust_buffer,
+            &self.sprite_buffer,
+            &self.post_buffer,
+            &self.blur_buffers[0],
+            &self.blur_buffers[1],
+        ]
+        .iter()
+        .map(|buffer| buffer.size())
+        .sum();
+        GpuMemory {
+            framebuffers,
+            geometry,
+            textures,
+            buffers,
+        }
+    }
+
+    /// Free cached GPU memory until the renderer holds at most `to_bytes`
+    /// ([`GpuMemory::total`]) and return what it holds afterwards.
+    ///
+    /// Render targets go first (multisampled targets, bloom, scene color, depth, shadow map), then
+    /// the bundled player models; whatever is freed is recreated by the next render that needs it,
+    /// at the cost of a slower first render. Models set with
+    /// [`Renderer::set_model_from_obj_bytes`] or [`Renderer::set_model_from_bedrock`] and fixed
+    /// allocations are kept, so the result can stay above `to_bytes`; `trim(0)` frees all it can.
+    pub fn trim(&mut self, to_bytes: u64) -> GpuMemory {
+        let before = self.gpu_memory().total();
+        let evictions: [fn(&mut Self); 7] = [
+            |r| *r.cached_msaa_targets.get_mut() = None,
+            |r| *r.cached_bloom_targets.get_mut() = None,
+            |r| *r.cached_scene_texture.get_mut() = None,
+            |r| *r.cached_depth_texture.get_mut() = None,
+            |r| *r.cached_shadow_map.get_mut() = None,
+            |r| r.models.evict_bundled(SkinType::Slim),
+            |r| r.models.evict_bundled(SkinType::Classic),
+        ];
+        for evict in evictions {
+            if self.gpu_memory().total() <= to_bytes {
+                break;
+            }
+            evict(self);
+        }
+        let after = self.gpu_memory();
+        tracing::debug!(
+            freed = before - after.total(),
+            held = after.total(),
+            "Trimmed GPU memory"
+        );
+        after
+    }
+
+    /// Whether the model for `skin_type` has been uploaded. Models load on the first render that
+    /// needs them (windowed renderers load both up front).
+    pub fn is_model_loaded(&self, skin_type: SkinType) -> bool {
+        self.models.is_loaded(skin_type)
+    }
+
+    /// The meshes [`Renderer::render`] draws for `character`, posed in world space, without
+    /// rendering: downstream crates can rasterize, ray cast or export them themselves.
+    ///
+    /// Hidden layers ([`Character::visibility`]) are left out, parts outside the view are not.
+    /// `camera` only contributes its [`Camera::scale`], which renders fold into the character's
+    /// transform; UVs carry the current [`RenderOptions::uv_inset`]. Loads the model for the
+    /// character's skin type if no render has yet.
+    pub fn posed_geometry(
+        &self,
+        character: &Character,
+        camera: &Camera,
+    ) -> Result<Vec<PosedMesh>, EidolonError> {
+        let model = self.models.get(&self.device, character.skin_type)?;
+        let matrices = part_model_matrices(character, camera);
+        let uv_inset = self.models.uv_inset();
+        let shown = shown_layers(character);
+        let mut meshes = Vec::new();
+        let mut add = |source, part: &ModelPart, matrix, offset| {
+            if !part.vertices.is_empty() {
+                meshes.push(PosedMesh {
+                    source,
+                    vertices: posed::pose_vertices(&part.vertices, matrix, offset, uv_inset),
+                });
+            }
+        };
+        for (i, (matrix, shown)) in matrices.iter().zip(shown).enumerate() {
+            let bone = part_bone(i);
+            let body_part = body_part_ref(i, model);
+            let layers = [(&body_part.main, Layer::Base), (&body_part.layer, Layer::Overlay)];
+            for ((part, layer), shown) in layers.into_iter().zip(shown) {
+                if shown {
+                    add(PosedSource::Part { bone, layer }, part, *matrix, PART_CONFIGS[i].1);
+                }
+            }
+            if let (Bone::Head, Some(headwear)) = (bone, &character.equipment.head) {
+                let part = &self.headwear_meshes[headwear.shape as usize];
+                add(PosedSource::Headwear, part, *matrix, PART_CONFIGS[i].1);
+            }
+        }
+        for (i, attachment) in character.attachments.iter().enumerate() {
+            let matrix = matrices[bone_index(attachment.bone)] * attachment.transform.matrix();
+            add(PosedSource::Attachment(i), &attachment.mesh.part, matrix, 0.0);
+        }
+        if let Some(matrix) = cape_model_matrix(character, camera) {
+            add(PosedSource::Cape, &model.cape, matrix, 0.0);
+        }
+        Ok(meshes)
+    }
+
+    /// What is under pixel `(x, y)` of a `width`×`height` render of `character` with `skin`
+    /// through `camera`: the mesh, the point and the texel of the mesh's texture, or `None` over
+    /// the background and for degenerate cameras. Interactive skin editors use it to find the texel clicked in a preview.
+    ///
+    /// Casts a ray through [`Renderer::posed_geometry`] on the CPU, so it needs no render.
+    /// Texels that [`RenderOptions::alpha_test`] discards are seen through, as in the render.
+    pub fn pick(
+        &self,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+        pixel: (u32, u32),
+    ) -> Result<Option<Pick>, EidolonError> {
+        if width == 0 || height == 0 || pixel.0 >= width || pixel.1 >= height {
+            return Ok(None);
+        }
+        let meshes = self.posed_geometry(character, camera)?;
+        let fov = self.options.fov;
+        let Some((origin, direction)) = pick::pixel_ray(camera, fov, width, height, pixel) else {
+            return Ok(None);
+        };
+        let cutoff = self.options.alpha_test.cutoff;
+        let picked = pick::cast(&meshes, origin, direction).into_iter().find_map(|hit| {
+            let source = meshes[hit.mesh].source;
+            let texture = match source {
+                PosedSource::Part { bone, layer } => {
+                    let texture = character.part_texture(skin, bone, layer);
+                    if layer == Layer::Base
+                        && self.options.mannequin_fallback
+                        && texture.is_blank(bone, character.skin_type)
+                    {
+                        &self.mannequin_skin
+                    } else {
+                        texture
+                    }
+                }
+                PosedSource::Headwear => &character.equipment.head.as_ref()?.texture,
+                PosedSource::Attachment(i) => &character.attachments[i].texture,
+                PosedSource::Cape => &character.equipment.cape.as_ref()?.texture,
+            };
+            let (width, height) = texture.size();
+            let texel = pick::texel_at(hit.uv, width, height);
+            (f32::from(texture.alpha_at(texel)) / 255.0 >= cutoff).then(|| Pick {
+                source,
+                position: hit.position.into(),
+                normal: hit.normal.into(),
+                uv: hit.uv,
+                texel,
+            })
+        });
+        Ok(picked)
+    }
+
+    /// Encode one frame into `target_view`: the skin pass directly, or — when a post effect is
+    /// enabled — the skin pass into the intermediate target followed by the composite pass.
+    ///
+    /// The direct path always leaves premultiplied color; `premultiplied` selects what the
+    /// composite writes.
+    #[allow(clippy::too_many_arguments)]
+    fn encode_render_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target_view: &wgpu::TextureView,
+        pipelines: &TargetPipelines,
+        premultiplied: bool,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+    ) -> Result<(), EidolonError> {
+        if !self.options.uses_post_processing() {
+            self.encode_scene_pass(
+                encoder,
+                target_view,
+                &pipelines.scene,
+                character,
+                skin,
+                camera,
+                width,
+                height,
+                self.msaa_samples(pipelines.scene.color_format),
+            )?;
+            return Ok(());
+        }
+
+        let mut post_uniforms = compute_post_uniforms(&self.options, camera);
+        post_uniforms.output[2] = f32::from(u8::from(premultiplied));
+        self.encode_post_passes(
+            encoder,
+            target_view,
+            &pipelines.composite,
+            None,
+            post_uniforms,
+            character,
+            skin,
+            camera,
+            width,
+            height,
+        )
+    }
+
+    /// Encode the skin pass into the intermediate HDR target, the bloom passes if enabled, and
+    /// the composite into `target_view` (plus linear depth into `depth_target`, if given).
+    #[allow(clippy::too_many_arguments)]
+    fn encode_post_passes(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target_view: &wgpu::TextureView,
+        composite: &wgpu::RenderPipeline,
+        depth_target: Option<&wgpu::TextureView>,
+        post_uniforms: PostUniforms,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+    ) -> Result<(), EidolonError> {
+        let scene_view = {
+            let mut cache = self.cached_scene_texture.borrow_mut();
+            if !matches!(cache.as_ref(), Some((_, w, h)) if *w == width && *h == height) {
+                let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Scene Color Texture"),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: HDR_FORMAT,
+                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                        | wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                });
+                *cache = Some((texture, width, height));
+            }
+            cache
+                .as_ref()
+                .unwrap()
+                .0
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+        let reads_depth = reads_scene_depth(&self.options, depth_target.is_some());
+        let samples = self.hdr_scene_samples(&self.options, reads_depth);
+        self.encode_scene_pass(
+            encoder,
+            &scene_view,
+            &self.hdr_pipeline,
+            character,
+            skin,
+            camera,
+            width,
+            height,
+            samples,
+        )?;
+        if samples > 1 && reads_depth {
+            self.encode_depth_resolve(encoder, width, height);
+        }
+
+        self.queue
+            .write_buffer(&self.post_buffer, 0, bytemuck::bytes_of(&post_uniforms));
+        let depth_view = self.depth_view(width, height);
+
+        let bloom_cache = match &self.options.emissive_overlay {
+            Some(settings) => {
+                self.encode_bloom_passes(
+                    encoder,
+                    &depth_view,
+                    character,
+                    skin,
+                    settings,
+                    width,
+                    height,
+                )?;
+                Some(self.cached_bloom_targets.borrow())
+            }
+            None => None,
+        };
+        let (emissive_view, bloom_view) = match bloom_cache.as_ref().and_then(|c| c.as_ref()) {
+            Some(targets) => (&targets.emissive, &targets.blur_v),
+            None => (&self.placeholder_bloom_view, &self.placeholder_bloom_view),
+        };
+
+        let bind_group = create_post_bind_group(
+            &self.device,
+            &self.post_bind_group_layout,
+            &self.post_buffer,
+            &scene_view,
+            &depth_view,
+            emissive_view,
+            bloom_view,
+        );
+
+        let mut post_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Post Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        post_pass.set_pipeline(composite);
+        post_pass.set_bind_group(0, &bind_group, &[]);
+        post_pass.draw(0..3, 0..1);
+        drop(post_pass);
+
+        if let Some(depth_target) = depth_target {
+            let mut depth_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Depth Output Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: depth_target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            depth_pass.set_pipeline(&self.depth_output_pipeline);
+            depth_pass.set_bind_group(0, &bind_group, &[]);
+            depth_pass.draw(0..3, 0..1);
+        }
+        Ok(())
+    }
+
+    /// Encode the emissive pass (overlay layer, unlit, depth-tested against the finished scene)
+    /// and the two bloom blur passes into the cached [`BloomTargets`].
+    #[allow(clippy::too_many_arguments)]
+    fn encode_bloom_passes(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        depth_view: &wgpu::TextureView,
+        character: &Character,
+        skin: &Texture,
+        settings: &EmissiveOverlay,
+        width: u32,
+        height: u32,
+    ) -> Result<(), EidolonError> {
+        let model = self.models.get(&self.device, character.skin_type)?;
+
+        let mut cache = self.cached_bloom_targets.borrow_mut();
+        if !matches!(cache.as_ref(), Some(t) if t.width == width && t.height == height) {
+            *cache = Some(BloomTargets::new(&self.device, width, height));
+        }
+        let targets = cache.as_ref().unwrap();
+
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Emissive Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &targets.emissive,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.emissive_pipeline);
+            pass.set_bind_group(2, &self.placeholder_shadow_map.bind_group, &[]);
+            let shown = shown_layers(character);
+            for i in (0..PART_CONFIGS.len()).filter(|&i| shown[i][1]) {
+                let body_part = body_part_ref(i, model);
+                let dynamic_offset = ((FIRST_OVERLAY_SLOT + i) as u32) * self.uniform_aligned_size;
+                let texture = character.part_texture(skin, part_bone(i), Layer::Overlay);
+                pass.set_bind_group(0, &self.uniform_bind_group, &[dynamic_offset]);
+                pass.set_bind_group(1, self.texture_bind_group(texture), &[]);
+                pass.set_vertex_buffer(0, body_part.layer.vertex_buffer.slice(..));
+                pass.draw(0..body_part.layer.vertex_count, 0..1);
+            }
+        }
+
+        let (horizontal, vertical) = compute_blur_uniforms(settings, height);
+        let passes = [
+            (&self.blur_buffers[0], horizontal, &targets.emissive, &targets.blur_h),
+            (&self.blur_buffers[1], vertical, &targets.blur_h, &targets.blur_v),
+        ];
+        for (buffer, uniforms, source, target) in passes {
+            self.queue
+                .write_buffer(buffer, 0, bytemuck::bytes_of(&uniforms));
+            let bind_group = create_blur_bind_group(
+                &self.device,
+                &self.blur_bind_group_layout,
+                buffer,
+                source,
+            );
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Bloom Blur Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.blur_pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        Ok(())
+    }
+
+    /// Encode the shadow pass (if enabled) and the skin pass into `target_view`, with `samples`
+    /// samples per pixel ([`Renderer::msaa_samples`]).
+    #[allow(clippy::too_many_arguments)]
+    fn encode_scene_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target_view: &wgpu::TextureView,
+        pipelines: &ScenePipelines,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> Result<(), EidolonError> {
+        let model = self.models.get(&self.device, character.skin_type)?;
+        let visible = self.write_part_uniforms(model, character, camera, width, height)?;
+        let shown = shown_layers(character);
+        self.queue.write_buffer(
+            &self.light_buffer,
+            0,
+            bytemuck::bytes_of(&LightUniforms::from(&self.lighting)),
+        );
+        self.queue.write_buffer(
+            &self.occlusion_buffer,
+            0,
+            bytemuck::bytes_of(&compute_occlusion_uniforms(
+                character,
+                camera,
+                self.options.ambient_occlusion,
+            )),
+        );
+        self.queue.write_buffer(
+            &self.adjust_buffer,
+            0,
+            bytemuck::bytes_of(&compute_adjust_uniforms(&self.options)),
+        );
+
+        let shadow_settings = self.options.shadow.as_ref();
+        let shadow_resolution = shadow_settings.map_or(1, |s| {
+            clamp_resolution(s.resolution, self.device.limits().max_texture_dimension_2d)
+        });
+        self.queue.write_buffer(
+            &self.shadow_buffer,
+            0,
+            bytemuck::bytes_of(&compute_shadow_uniforms(
+                character,
+                camera,
+                &self.lighting,
+                shadow_settings,
+                shadow_resolution,
+            )),
+        );
+        let draw_ground = shadow_settings.is_some_and(|s| s.ground_opacity > 0.0);
+        if draw_ground {
+            let offset = (GROUND_SLOT as u64) * (self.uniform_aligned_size as u64);
+            self.queue.write_buffer(
+                &self.uniform_buffer,
+                offset,
+                bytemuck::bytes_of(&compute_ground_uniforms(
+                    character,
+                    camera,
+                    self.options.fov,
+                    width,
+                    height,
+                )),
+            );
+        }
+
+        if shadow_settings.is_some() {
+            let mut cache = self.cached_shadow_map.borrow_mut();
+            if cache.as_ref().map(|m| m.resolution) != Some(shadow_resolution) {
+                *cache = Some(ShadowMap::new(
+                    &self.device,
+                    &self.shadow_bind_group_layout,
+                    &self.shadow_sampler,
+                    shadow_resolution,
+                ));
+            }
+            let shadow_map = cache.as_ref().unwrap();
+
+            let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shadow Pass"),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &shadow_map.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            shadow_pass.set_pipeline(&self.shadow_pipeline);
+            // Parts outside the view can still shadow the visible ones.
+            self.draw_body_parts(&mut shadow_pass, model, character, skin, &shown, true);
+        }
+
+        let mut sprites =
+            status_sprites(&self.options.status, character, camera, self.options.fov);
+        sprites.extend(particle_sprites(&self.options.particles, character, camera));
+        let sprite_instances = (!sprites.is_empty()).then(|| {
+            use wgpu::util::DeviceExt;
+            self.queue.write_buffer(
+                &self.sprite_buffer,
+                0,
+                bytemuck::bytes_of(&compute_sprite_uniforms(
+                    camera,
+                    self.options.fov,
+                    width,
+                    height,
+                    self.options.status.time,
+                )),
+            );
+            self.device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("Sprite Instance Buffer"),
+                    contents: bytemuck::cast_slice(&sprites),
+                    usage: wgpu::BufferUsages::VERTEX,
+                })
+        });
+
+        let (color_view, resolve_target, depth_view) =
+            self.scene_attachments(target_view, pipelines.color_format, width, height, samples);
 
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: target_view,
-                    resolve_target: None,
+                    view: &color_view,
+                    resolve_target,
+                    ops: wgpu::Operations {
+                        // Targets hold premultiplied color.
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: self.clear_color[0] * self.clear_color[3],
+                            g: self.clear_color[1] * self.clear_color[3],
+                            b: self.clear_color[2] * self.clear_color[3],
+                            a: self.clear_color[3],
+                        }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            let cache = self.cached_shadow_map.borrow();
+            let shadow_map = match (shadow_settings, cache.as_ref()) {
+                (Some(_), Some(map)) => map,
+                _ => &self.placeholder_shadow_map,
+            };
+
+            render_pass.set_pipeline(&pipelines.skin.get(
+                &self.device,
+                self.shader_features(),
+                samples,
+            ));
+            render_pass.set_bind_group(2, &shadow_map.bind_group, &[]);
+            self.draw_body_parts(&mut render_pass, model, character, skin, &visible, false);
+
+            if draw_ground {
+                let dynamic_offset = (GROUND_SLOT as u32) * self.uniform_aligned_size;
+                render_pass.set_pipeline(&pipelines.ground.get(&self.device, samples));
+                render_pass.set_bind_group(0, &self.uniform_bind_group, &[dynamic_offset]);
+                render_pass.set_vertex_buffer(0, self.ground_mesh.vertex_buffer.slice(..));
+                render_pass.draw(0..self.ground_mesh.vertex_count, 0..1);
+            }
+
+            if let Some(instances) = &sprite_instances {
+                render_pass.set_pipeline(&pipelines.sprites.get(&self.device, samples));
+                render_pass.set_bind_group(0, &self.sprite_bind_group, &[]);
+                render_pass.set_vertex_buffer(0, instances.slice(..));
+                render_pass.draw(0..6, 0..sprites.len() as u32);
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the uniforms of the body parts, headwear, attachments and cape of `character` to
+    /// their slots, and return which layers of each part are shown and in view.
+    fn write_part_uniforms(
+        &self,
+        model: &Model,
+        character: &Character,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+    ) -> Result<[[bool; 2]; BODY_PART_COUNT], EidolonError> {
+        let fov = self.options.fov;
+        let mut uniforms = compute_body_part_uniforms(character, camera, fov, width, height);
+        if let Some(glint) = &self.options.glint {
+            apply_glint(&mut uniforms, glint);
+        }
+        let shown = shown_layers(character);
+        let mut visible = culling::visible_parts(&uniforms, model);
+        for (visible, shown) in visible.iter_mut().zip(shown) {
+            visible[0] &= shown[0];
+            visible[1] &= shown[1];
+        }
+        if character.attachments.len() > MAX_ATTACHMENTS {
+            return Err(EidolonError::model(format!(
+                "a character can wear at most {MAX_ATTACHMENTS} attachments, got {}",
+                character.attachments.len()
+            )));
+        }
+        let alpha_test = &self.options.alpha_test;
+        let write = |slot: usize, uniform: &Uniforms| {
+            let offset = (slot as u64) * (self.uniform_aligned_size as u64);
+            self.queue
+                .write_buffer(&self.uniform_buffer, offset, bytemuck::bytes_of(uniform));
+        };
+        for (i, uniform) in uniforms.iter().enumerate() {
+            let bone = part_bone(i);
+            for (slot, layer) in [(i, Layer::Base), (FIRST_OVERLAY_SLOT + i, Layer::Overlay)] {
+                let uniform = uniform
+                    .with_alpha(alpha_test.layer(layer))
+                    .with_segment(PosedSource::Part { bone, layer });
+                write(slot, &uniform);
+            }
+            if bone == Bone::Head && character.equipment.head.is_some() {
+                let headwear = uniform
+                    .with_alpha(alpha_test.layer(Layer::Overlay))
+                    .with_segment(PosedSource::Headwear);
+                write(HEADWEAR_SLOT, &headwear);
+            }
+        }
+        let attachment_uniforms = compute_attachment_uniforms(character, camera, fov, width, height);
+        for (i, uniform) in attachment_uniforms.into_iter().enumerate() {
+            let uniform = uniform
+                .with_alpha(alpha_test.layer(Layer::Overlay))
+                .with_segment(PosedSource::Attachment(i));
+            write(FIRST_ATTACHMENT_SLOT + i, &uniform);
+        }
+        if let Some(uniform) = compute_cape_uniforms(character, camera, fov, width, height) {
+            let uniform = uniform
+                .with_alpha(alpha_test.layer(Layer::Overlay))
+                .with_segment(PosedSource::Cape);
+            write(CAPE_SLOT, &uniform);
+        }
+        Ok(visible)
+    }
+
+    /// Skin shader features the current options and lighting need.
+    fn shader_features(&self) -> ShaderFeatures {
+        ShaderFeatures::for_render(&self.options, &self.lighting)
+    }
+
+    /// Samples per pixel of a scene pass into `format`: [`RenderOptions::msaa`], lowered to what
+    /// the device supports.
+    fn msaa_samples(&self, format: wgpu::TextureFormat) -> u32 {
+        self.msaa_support.samples(format, self.options.msaa.samples())
+    }
+
+    /// Samples per pixel of the HDR scene pass of a post-processed render with `options`;
+    /// `reads_depth` as [`reads_scene_depth`].
+    fn hdr_scene_samples(&self, options: &RenderOptions, reads_depth: bool) -> u32 {
+        // Effects reading depth need the multisampled depth resolved, which samples it.
+        if reads_depth && !self.msaa_support.sampled_depth {
+            1
+        } else {
+            self.msaa_support.samples(HDR_FORMAT, options.msaa.samples())
+        }
+    }
+
+    /// Color view, resolve target and depth view of a scene pass into `target_view` (of
+    /// `format`): the target itself and the cached depth buffer, or for multisampled passes the
+    /// cached [`MsaaTargets`], resolving into the target.
+    fn scene_attachments<'a>(
+        &self,
+        target_view: &'a wgpu::TextureView,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> (wgpu::TextureView, Option<&'a wgpu::TextureView>, wgpu::TextureView) {
+        if samples == 1 {
+            return (target_view.clone(), None, self.depth_view(width, height));
+        }
+        let mut cache = self.cached_msaa_targets.borrow_mut();
+        if !matches!(cache.as_ref(), Some(t) if t.matches(format, width, height, samples)) {
+            *cache = Some(MsaaTargets::new(
+                &self.device,
+                format,
+                width,
+                height,
+                samples,
+                self.msaa_support.sampled_depth,
+            ));
+        }
+        let targets = cache.as_ref().unwrap();
+        (targets.color.clone(), Some(target_view), targets.depth.clone())
+    }
+
+    /// Encode the resolve of the last multisampled scene pass's depth into the cached depth
+    /// buffer, which the emissive and post passes read.
+    fn encode_depth_resolve(&self, encoder: &mut wgpu::CommandEncoder, width: u32, height: u32) {
+        let target = self.depth_view(width, height);
+        let cache = self.cached_msaa_targets.borrow();
+        let Some(targets) = cache.as_ref() else {
+            return;
+        };
+        self.depth_resolve
+            .get_or_init(|| DepthResolve::new(&self.device))
+            .encode(&self.device, encoder, &targets.depth, &target);
+    }
+
+    /// View of the cached depth buffer, recreated when the dimensions change.
+    fn depth_view(&self, width: u32, height: u32) -> wgpu::TextureView {
+        let mut cache = self.cached_depth_texture.borrow_mut();
+        let need_new = match cache.as_ref() {
+            None => true,
+            Some((_, w, h)) => *w != width || *h != height,
+        };
+        if need_new {
+            let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some("Depth Texture"),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: DEPTH_FORMAT,
+                // Sampled by the post pass (depth of field).
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                    | wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            });
+            *cache = Some((texture, width, height));
+        }
+        cache
+            .as_ref()
+            .unwrap()
+            .0
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    /// Draw the body part meshes (main + overlay) marked in `visible` with the pipeline already
+    /// set on `pass`, each with `skin` or its [`Character::part_texture_overrides`] texture, the
+    /// character's headwear with the head, its attachments (only those that cast shadows in a
+    /// `shadow_pass`) and its cape.
+    fn draw_body_parts(
+        &self,
+        pass: &mut wgpu::RenderPass<'_>,
+        model: &Model,
+        character: &Character,
+        skin: &Texture,
+        visible: &[[bool; 2]; BODY_PART_COUNT],
+        shadow_pass: bool,
+    ) {
+        // Headwear is drawn with the head even when both of the head's layers are hidden.
+        for i in 0..PART_CONFIGS.len() {
+            let body_part = body_part_ref(i, model);
+            let meshes = [(&body_part.main, Layer::Base), (&body_part.layer, Layer::Overlay)];
+            for ((mesh, layer), visible) in meshes.into_iter().zip(visible[i]) {
+                if visible {
+                    let slot = match layer {
+                        Layer::Base => i,
+                        Layer::Overlay => FIRST_OVERLAY_SLOT + i,
+                    };
+                    let dynamic_offset = (slot as u32) * self.uniform_aligned_size;
+                    pass.set_bind_group(0, &self.uniform_bind_group, &[dynamic_offset]);
+                    let mut texture = character.part_texture(skin, part_bone(i), layer);
+                    if layer == Layer::Base
+                        && self.options.mannequin_fallback
+                        && texture.is_blank(part_bone(i), character.skin_type)
+                    {
+                        texture = &self.mannequin_skin;
+                    }
+                    pass.set_bind_group(1, self.texture_bind_group(texture), &[]);
+                    pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                    pass.draw(0..mesh.vertex_count, 0..1);
+                }
+            }
+            if let (Bone::Head, Some(headwear)) = (part_bone(i), &character.equipment.head) {
+                let mesh = &self.headwear_meshes[headwear.shape as usize];
+                let dynamic_offset = (HEADWEAR_SLOT as u32) * self.uniform_aligned_size;
+                pass.set_bind_group(0, &self.uniform_bind_group, &[dynamic_offset]);
+                pass.set_bind_group(1, self.texture_bind_group(&headwear.texture), &[]);
+                pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                pass.draw(0..mesh.vertex_count, 0..1);
+            }
+        }
+        // Attachments reach past their bone's bounds, so they are not culled.
+        for (i, attachment) in character.attachments.iter().enumerate() {
+            if shadow_pass && !attachment.flags.cast_shadow {
+                continue;
+            }
+            let mesh = &attachment.mesh.part;
+            let dynamic_offset = ((FIRST_ATTACHMENT_SLOT + i) as u32) * self.uniform_aligned_size;
+            pass.set_bind_group(0, &self.uniform_bind_group, &[dynamic_offset]);
+            pass.set_bind_group(1, self.texture_bind_group(&attachment.texture), &[]);
+            pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            pass.draw(0..mesh.vertex_count, 0..1);
+        }
+        // The cape swings out of the body's bounds, so it is not culled either.
+        if let Some(cape) = &character.equipment.cape {
+            let mesh = &model.cape;
+            let dynamic_offset = (CAPE_SLOT as u32) * self.uniform_aligned_size;
+            pass.set_bind_group(0, &self.uniform_bind_group, &[dynamic_offset]);
+            pass.set_bind_group(1, self.texture_bind_group(&cape.texture), &[]);
+            pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            pass.draw(0..mesh.vertex_count, 0..1);
+        }
+    }
+
+    /// Bind group of `texture` sampled with [`RenderOptions::texture_filter`].
+    fn texture_bind_group<'t>(&self, texture: &'t Texture) -> &'t wgpu::BindGroup {
+        texture.filtered_bind_group(
+            self.options.texture_filter,
+            &self.device,
+            &self.texture_bind_group_layout,
+            &self.filtered_samplers,
+        )
+    }
+
+    /// Pay the one-time cost of a first render up front: load both player models, compile the
+    /// skin pipeline variant of the current options and lighting, and draw a blank skin at
+    /// `width`×`height` so the depth, scene, shadow and bloom targets of that size exist.
+    /// The variants of offscreen renders with each of `variants` (under the current lighting)
+    /// are compiled too, so switching options later does not stall a render.
+    ///
+    /// Doubles as a preflight check: fails if the device cannot draw. Call after
+    /// [`Renderer::set_options`] and [`Renderer::set_lighting`]; size-dependent targets are
+    /// cached for one size at a time, so warm up with the most common output size.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn warm_up(
+        &self,
+        width: u32,
+        height: u32,
+        variants: &[RenderOptions],
+    ) -> Result<(), EidolonError> {
+        let start = std::time::Instant::now();
+        for options in variants {
+            let features = ShaderFeatures::for_render(options, &self.lighting);
+            if options.uses_post_processing() {
+                let samples = self.hdr_scene_samples(options, reads_scene_depth(options, false));
+                self.hdr_pipeline.skin.get(&self.device, features, samples);
+            } else {
+                let samples = self
+                    .msaa_support
+                    .samples(RENDER_TARGET_FORMAT, options.msaa.samples());
+                self.pipeline.scene.skin.get(&self.device, features, samples);
+            }
+        }
+        let skin = self.upload_skin(&SkinImage::from_rgba(image::RgbaImage::from_pixel(
+            64,
+            64,
+            Rgba([255; 4]),
+        ))?);
+        for skin_type in [SkinType::Classic, SkinType::Slim] {
+            let character = Character {
+                skin_type,
+                ..Character::new()
+            };
+            let image = self.render(&character, &skin, &Camera::new(), width, height)?;
+            if image.pixels().all(|p| p[3] == 0) {
+                return Err(EidolonError::gpu(
+                    "warm-up render drew nothing; the device is not rendering",
+                ));
+            }
+        }
+        tracing::info!("Renderer warmed up at {width}x{height} in {:?}", start.elapsed());
+        Ok(())
+    }
+
+    /// Render to an offscreen texture and return an RGBA [`image::ImageBuffer`] (blocking map readback).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render(
+        &self,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+    ) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, EidolonError> {
+        let mut images =
+            self.render_views(character, skin, std::slice::from_ref(camera), (width, height))?;
+        Ok(images.remove(0))
+    }
+
+    /// Render `character` once per camera, in order, like repeated [`Renderer::render`] calls.
+    ///
+    /// The uploaded skin, render target and readback buffer are shared by all views, so several
+    /// angles of one character cost little more than the draws themselves.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render_views(
+        &self,
+        character: &Character,
+        skin: &Texture,
+        cameras: &[Camera],
+        size: (u32, u32),
+    ) -> Result<Vec<image::RgbaImage>, EidolonError> {
+        let (width, height) = size;
+        let _span = tracing::info_span!(
+            "render",
+            skin = %SkinId(skin.content_hash()),
+            skin_type = ?character.skin_type,
+            width,
+            height,
+            views = cameras.len(),
+        )
+        .entered();
+        readback::validate_size(width, height, self.max_output_size())?;
+        let render_texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Render Target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: RENDER_TARGET_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let texture_view = render_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let (output_buffer, padded_bytes_per_row) =
+            readback::create_output_buffer(&self.device, width, height, 4)?;
+
+        let mut images = Vec::with_capacity(cameras.len());
+        for camera in cameras {
+            self.check_cancelled()?;
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
+
+            self.encode_render_pass(
+                &mut encoder,
+                &texture_view,
+                &self.pipeline,
+                self.options.alpha_mode == AlphaMode::Premultiplied,
+                character,
+                skin,
+                camera,
+                width,
+                height,
+            )?;
+
+            readback::copy_render_target_to_buffer(
+                &mut encoder,
+                &render_texture,
+                &output_buffer,
+                width,
+                height,
+                padded_bytes_per_row,
+            );
+
+            self.queue.submit(Some(encoder.finish()));
+
+            let mut image = readback::map_output_buffer_to_rgba(
+                &self.device,
+                &output_buffer,
+                width,
+                height,
+                padded_bytes_per_row,
+                self.gpu_timeout,
+            )?;
+            if self.needs_unpremultiply() {
+                readback::unpremultiply(&mut image);
+            }
+            images.push(image);
+        }
+        Ok(images)
+    }
+
+    /// Render which mesh covers each pixel of a `width`×`height` view of `character`: body part
+    /// layers, headwear, attachments and the cape each get their own segment, as flat ids without
+    /// lighting, blending or anti-aliasing. Hidden layers and texels the alpha test discards are left out,
+    /// as in [`Renderer::render`]; sprites and the ground are not drawn.
+    ///
+    /// Used for ML datasets, checks that a part is (or is not) visible, and picking every pixel
+    /// at once ([`Renderer::pick`] answers one pixel without rendering).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render_segmentation(
+        &self,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+    ) -> Result<SegmentationMap, EidolonError> {
+        let _span = tracing::info_span!(
+            "render_segmentation",
+            skin_type = ?character.skin_type,
+            width,
+            height,
+        )
+        .entered();
+        readback::validate_size(width, height, self.max_output_size())?;
+        self.check_cancelled()?;
+        let target = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Segmentation Target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: RENDER_TARGET_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let target_view = target.create_view(&wgpu::TextureViewDescriptor::default());
+        let (output_buffer, padded_bytes_per_row) =
+            readback::create_output_buffer(&self.device, width, height, 4)?;
+
+        let model = self.models.get(&self.device, character.skin_type)?;
+        let visible = self.write_part_uniforms(model, character, camera, width, height)?;
+        let depth_view = self.depth_view(width, height);
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Segmentation Encoder"),
+            });
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Segmentation Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            pass.set_pipeline(&self.segmentation_pipeline);
+            pass.set_bind_group(2, &self.placeholder_shadow_map.bind_group, &[]);
+            self.draw_body_parts(&mut pass, model, character, skin, &visible, false);
+        }
+        readback::copy_render_target_to_buffer(
+            &mut encoder,
+            &target,
+            &output_buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let image = readback::map_output_buffer_to_rgba(
+            &self.device,
+            &output_buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            self.gpu_timeout,
+        )?;
+        let ids = image.pixels().map(|pixel| pixel[0]).collect();
+        Ok(SegmentationMap::from_ids(width, height, ids))
+    }
+
+    /// Render a left- and right-eye view around `camera` and combine them per
+    /// [`StereoSettings::layout`]. Each eye is `width`×`height`, so side-by-side output is
+    /// `2 × width` wide.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render_stereo(
+        &self,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        settings: &StereoSettings,
+        width: u32,
+        height: u32,
+    ) -> Result<image::RgbaImage, EidolonError> {
+        let eyes = stereo::eye_cameras(camera, self.options.fov, settings.eye_separation);
+        let views = self.render_views(character, skin, &eyes, (width, height))?;
+        Ok(stereo::combine(&views[0], &views[1], settings.layout))
+    }
+
+    /// Render many characters into one image (lineups, group shots), with one instanced draw per
+    /// body part and skin type instead of one per character. The members' skins are copied into
+    /// a texture array for the render, so different skins need no rebinding between draws.
+    ///
+    /// Members share the camera, light rig, background, color adjustments and alpha mode. Ambient
+    /// occlusion, shadows and the post effects of [`RenderOptions`] are per-character and are not
+    /// applied. Give members distinct [`Character::position`]s; zoom out with [`Camera::scale`]
+    /// to fit them.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render_crowd(
+        &self,
+        members: &[CrowdMember],
+        camera: &Camera,
+        width: u32,
+        height: u32,
+    ) -> Result<image::RgbaImage, EidolonError> {
+        use wgpu::util::DeviceExt;
+
+        let _span =
+            tracing::info_span!("render_crowd", members = members.len(), width, height).entered();
+        readback::validate_size(width, height, self.max_output_size())?;
+        self.check_cancelled()?;
+        let max_layers = self.device.limits().max_texture_array_layers;
+        let packing = crowd::pack_skins(members, max_layers);
+        let batches = crowd::batches(members, &packing);
+        let instances = crowd::instance_data(members, &packing, &batches, camera);
+
+        let alpha_test = &self.options.alpha_test;
+        for (i, uniform) in compute_instanced_part_uniforms(camera, self.options.fov, width, height)
+            .iter()
+            .enumerate()
+        {
+            for (slot, layer) in [(i, Layer::Base), (FIRST_OVERLAY_SLOT + i, Layer::Overlay)] {
+                let offset = (slot as u64) * (self.uniform_aligned_size as u64);
+                let uniform = uniform.with_alpha(alpha_test.layer(layer));
+                self.queue
+                    .write_buffer(&self.uniform_buffer, offset, bytemuck::bytes_of(&uniform));
+            }
+        }
+        self.queue.write_buffer(
+            &self.light_buffer,
+            0,
+            bytemuck::bytes_of(&LightUniforms::from(&self.lighting)),
+        );
+        // Occlusion and shadow uniforms describe a single character; switch both off.
+        let unused = Character::new();
+        self.queue.write_buffer(
+            &self.occlusion_buffer,
+            0,
+            bytemuck::bytes_of(&compute_occlusion_uniforms(&unused, camera, 0.0)),
+        );
+        self.queue.write_buffer(
+            &self.shadow_buffer,
+            0,
+            bytemuck::bytes_of(&compute_shadow_uniforms(
+                &unused,
+                camera,
+                &self.lighting,
+                None,
+                1,
+            )),
+        );
+        self.queue.write_buffer(
+            &self.adjust_buffer,
+            0,
+            bytemuck::bytes_of(&compute_adjust_uniforms(&self.options)),
+        );
+
+        let render_texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Crowd Render Target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: RENDER_TARGET_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let texture_view = render_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let samples = self.msaa_samples(RENDER_TARGET_FORMAT);
+        let (color_view, resolve_target, depth_view) =
+            self.scene_attachments(&texture_view, RENDER_TARGET_FORMAT, width, height, samples);
+        let (output_buffer, padded_bytes_per_row) =
+            readback::create_output_buffer(&self.device, width, height, 4)?;
+        // Vertex buffers must not be empty.
+        let instance_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Crowd Instance Buffer"),
+                contents: if instances.is_empty() {
+                    &[0; std::mem::size_of::<crowd::InstanceData>()]
+                } else {
+                    bytemuck::cast_slice(&instances)
+                },
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Crowd Encoder"),
+            });
+        let skin_arrays: Vec<_> = packing
+            .arrays
+            .iter()
+            .map(|array| self.create_skin_array(&mut encoder, array, max_layers))
+            .collect();
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Crowd Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &color_view,
+                    resolve_target,
                     ops: wgpu::Operations {
+                        // Targets hold premultiplied color.
                         load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: self.clear_color[0],
-                            g: self.clear_color[1],
-                            b: self.clear_color[2],
+                            r: self.clear_color[0] * self.clear_color[3],
+                            g: self.clear_color[1] * self.clear_color[3],
+                            b: self.clear_color[2] * self.clear_color[3],
                             a: self.clear_color[3],
                         }),
                         store: wgpu::StoreOp::Store,
@@ -548,88 +2456,306 @@ impl Renderer {
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
-
-            render_pass.set_pipeline(pipeline);
-            render_pass.set_bind_group(1, &skin.bind_group, &[]);
-
-            for i in 0..PART_CONFIGS.len() {
-                let body_part = body_part_ref(i, model);
-                let dynamic_offset = (i as u32) * self.uniform_aligned_size;
-                render_pass.set_bind_group(0, &self.uniform_bind_group, &[dynamic_offset]);
-
-                render_pass.set_vertex_buffer(0, body_part.main.vertex_buffer.slice(..));
-                render_pass.draw(0..body_part.main.vertex_count, 0..1);
-
-                render_pass.set_vertex_buffer(0, body_part.layer.vertex_buffer.slice(..));
-                render_pass.draw(0..body_part.layer.vertex_count, 0..1);
+            pass.set_pipeline(&self.crowd_pipeline.get(
+                &self.device,
+                self.shader_features(),
+                samples,
+            ));
+            pass.set_bind_group(2, &self.placeholder_shadow_map.bind_group, &[]);
+
+            let instance_size = std::mem::size_of::<crowd::InstanceData>() as u64;
+            let mut base = 0u64;
+            for batch in &batches {
+                let model = self.models.get(&self.device, batch.skin_type)?;
+                let count = batch.members.len() as u64;
+                pass.set_bind_group(1, &skin_arrays[batch.array], &[]);
+                for i in 0..PART_CONFIGS.len() {
+                    let body_part = body_part_ref(i, model);
+                    let first = (base + i as u64 * count) * instance_size;
+                    pass.set_vertex_buffer(
+                        1,
+                        instance_buffer.slice(first..first + count * instance_size),
+                    );
+                    let meshes = [
+                        (&body_part.main, i),
+                        (&body_part.layer, FIRST_OVERLAY_SLOT + i),
+                    ];
+                    for (mesh, slot) in meshes {
+                        let dynamic_offset = (slot as u32) * self.uniform_aligned_size;
+                        pass.set_bind_group(0, &self.uniform_bind_group, &[dynamic_offset]);
+                        pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                        pass.draw(0..mesh.vertex_count, 0..count as u32);
+                    }
+                }
+                base += count * BODY_PART_COUNT as u64;
             }
         }
+        readback::copy_render_target_to_buffer(
+            &mut encoder,
+            &render_texture,
+            &output_buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let mut image = readback::map_output_buffer_to_rgba(
+            &self.device,
+            &output_buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+            self.gpu_timeout,
+        )?;
+        if self.options.alpha_mode == AlphaMode::Straight {
+            readback::unpremultiply(&mut image);
+        }
+        Ok(image)
     }
 
-    /// Render to an offscreen texture and return an RGBA [`image::ImageBuffer`] (blocking map readback).
+    /// Encode copies of `array`'s skins into the layers of a new texture array and return its
+    /// crowd bind group.
     #[cfg(not(target_arch = "wasm32"))]
-    pub fn render(
+    fn create_skin_array(
         &self,
-        character: &Character,
-        skin: &Texture,
-        camera: &Camera,
-        width: u32,
-        height: u32,
-    ) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, EidolonError> {
-        let render_texture = self.device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Render Target"),
+        encoder: &mut wgpu::CommandEncoder,
+        array: &crowd::SkinArray,
+        max_layers: u32,
+    ) -> wgpu::BindGroup {
+        // At least two layers: GL cannot view a single-layer texture as an array.
+        let layers = (array.skins.len() as u32).max(2).min(max_layers);
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Crowd Skin Array"),
             size: wgpu::Extent3d {
-                width,
-                height,
-                depth_or_array_layers: 1,
+                width: array.width,
+                height: array.height,
+                depth_or_array_layers: layers,
             },
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: RENDER_TARGET_FORMAT,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             view_formats: &[],
         });
-        let texture_view = render_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        for (layer, skin) in array.skins.iter().enumerate() {
+            encoder.copy_texture_to_texture(
+                skin.texture.as_image_copy(),
+                wgpu::TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::Extent3d {
+                    width: array.width,
+                    height: array.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        // The array holds no mipmaps, so only the linear filter differs from nearest.
+        let sampler = match self.options.texture_filter {
+            TextureFilter::Linear => &self.filtered_samplers[1],
+            TextureFilter::Nearest | TextureFilter::NearestMipmapped => &self.sampler,
+        };
+        self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Crowd Skin Array Bind Group"),
+            layout: &self.skin_array_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+            ],
+        })
+    }
 
-        let (output_buffer, padded_bytes_per_row) =
-            readback::create_output_buffer(&self.device, width, height)?;
+    /// Whether an 8-bit render came from the direct path (premultiplied) but straight alpha was
+    /// requested.
+    fn needs_unpremultiply(&self) -> bool {
+        self.options.alpha_mode == AlphaMode::Straight && !self.options.uses_post_processing()
+    }
+
+    /// Render linear-light color and depth for compositing (blocking map readback).
+    ///
+    /// Always renders through the post-processing composite into a float target, so values
+    /// above `1.0` are kept; [`RenderOptions::tone_mapping`] is not applied. See [`HdrImage`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render_hdr(
+        &self,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+    ) -> Result<HdrImage, EidolonError> {
+        let (color, depth) =
+            self.render_float(character, skin, camera, width, height, true, true)?;
+        HdrImage::from_readback(
+            width,
+            height,
+            color,
+            depth.unwrap_or_default(),
+            self.options.alpha_mode,
+        )
+    }
+
+    /// Like [`Renderer::render`], with 16 bits per channel: the float render is tone mapped and
+    /// quantized on the CPU instead of in an 8-bit target.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render_16bit(
+        &self,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+    ) -> Result<ImageBuffer<Rgba<u16>, Vec<u16>>, EidolonError> {
+        let (color, _) = self.render_float(character, skin, camera, width, height, false, false)?;
+        hdr::to_rgba16(width, height, &color)
+    }
+
+    /// Composite into a [`FLOAT_TARGET_FORMAT`] target and read back straight-alpha RGBA
+    /// (`raw` skips tone mapping), plus the linear depth when `with_depth` is set.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::too_many_arguments)]
+    fn render_float(
+        &self,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        width: u32,
+        height: u32,
+        raw: bool,
+        with_depth: bool,
+    ) -> Result<(Vec<f32>, Option<Vec<f32>>), EidolonError> {
+        let _span = tracing::info_span!(
+            "render_float",
+            skin = %SkinId(skin.content_hash()),
+            skin_type = ?character.skin_type,
+            width,
+            height,
+            raw,
+        )
+        .entered();
+        let max = readback::max_output_side(&self.device.limits(), 8);
+        readback::validate_size(width, height, max)?;
+        self.check_cancelled()?;
+        let create_target = |label, format| {
+            self.device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                view_formats: &[],
+            })
+        };
+        let color_texture = create_target("Float Render Target", FLOAT_TARGET_FORMAT);
+        let color_view = color_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let (color_buffer, color_row_bytes) =
+            readback::create_output_buffer(&self.device, width, height, 8)?;
+        let depth_output = if with_depth {
+            let texture = create_target("Depth Output Target", DEPTH_OUTPUT_FORMAT);
+            let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let (buffer, row_bytes) =
+                readback::create_output_buffer(&self.device, width, height, 8)?;
+            Some((texture, view, buffer, row_bytes))
+        } else {
+            None
+        };
+
+        let mut post_uniforms = compute_post_uniforms(&self.options, camera);
+        if raw {
+            post_uniforms.output[1] = 1.0;
+            post_uniforms.output[2] = 0.0;
+        }
 
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
+                label: Some("Float Render Encoder"),
             });
 
-        self.encode_render_pass(
+        self.encode_post_passes(
             &mut encoder,
-            &texture_view,
-            &self.pipeline,
+            &color_view,
+            &self.float_composite_pipeline,
+            depth_output.as_ref().map(|(_, view, _, _)| view),
+            post_uniforms,
             character,
             skin,
             camera,
             width,
             height,
-        );
+        )?;
 
         readback::copy_render_target_to_buffer(
             &mut encoder,
-            &render_texture,
-            &output_buffer,
+            &color_texture,
+            &color_buffer,
             width,
             height,
-            padded_bytes_per_row,
+            color_row_bytes,
         );
+        if let Some((texture, _, buffer, row_bytes)) = &depth_output {
+            readback::copy_render_target_to_buffer(
+                &mut encoder,
+                texture,
+                buffer,
+                width,
+                height,
+                *row_bytes,
+            );
+        }
 
         self.queue.submit(Some(encoder.finish()));
 
-        readback::map_output_buffer_to_rgba(
+        let color = readback::map_output_buffer_to_f32(
             &self.device,
-            &output_buffer,
+            &color_buffer,
             width,
             height,
-            padded_bytes_per_row,
-        )
+            color_row_bytes,
+            self.gpu_timeout,
+        )?;
+        let depth = match &depth_output {
+            Some((_, _, buffer, row_bytes)) => Some(
+                readback::map_output_buffer_to_f32(
+                    &self.device,
+                    buffer,
+                    width,
+                    height,
+                    *row_bytes,
+                    self.gpu_timeout,
+                )?
+                .into_iter()
+                .step_by(4)
+                .collect(),
+            ),
+            None => None,
+        };
+        Ok((color, depth))
     }
 
     /// Render to an offscreen texture and return an RGBA [`image::ImageBuffer`] (async readback).
@@ -645,6 +2771,17 @@ impl Renderer {
         width: u32,
         height: u32,
     ) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, EidolonError> {
+        use tracing::Instrument;
+
+        let span = tracing::info_span!(
+            "render",
+            skin = %SkinId(skin.content_hash()),
+            skin_type = ?character.skin_type,
+            width,
+            height,
+        );
+        let guard = span.enter();
+        readback::validate_size(width, height, self.max_output_size())?;
         let render_texture = self.device.create_texture(&wgpu::TextureDescriptor {
             label: Some("Render Target"),
             size: wgpu::Extent3d {
@@ -662,7 +2799,7 @@ impl Renderer {
         let texture_view = render_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let (output_buffer, padded_bytes_per_row) =
-            readback::create_output_buffer(&self.device, width, height)?;
+            readback::create_output_buffer(&self.device, width, height, 4)?;
 
         let mut encoder = self
             .device
@@ -674,12 +2811,13 @@ impl Renderer {
             &mut encoder,
             &texture_view,
             &self.pipeline,
+            self.options.alpha_mode == AlphaMode::Premultiplied,
             character,
             skin,
             camera,
             width,
             height,
-        );
+        )?;
 
         readback::copy_render_target_to_buffer(
             &mut encoder,
@@ -691,15 +2829,21 @@ impl Renderer {
         );
 
         self.queue.submit(Some(encoder.finish()));
+        drop(guard);
 
-        readback::map_output_buffer_to_rgba_async(
+        let mut image = readback::map_output_buffer_to_rgba_async(
             &self.device,
             &output_buffer,
             width,
             height,
             padded_bytes_per_row,
         )
-        .await
+        .instrument(span)
+        .await?;
+        if self.needs_unpremultiply() {
+            readback::unpremultiply(&mut image);
+        }
+        Ok(image)
     }
 
     /// Present one frame to the window surface (expects `new_windowed`).
@@ -725,16 +2869,23 @@ impl Renderer {
                 label: Some("Render Frame Encoder"),
             });
 
+        // Premultiplied unless the compositor expects straight color.
+        let premultiplied = config.alpha_mode != wgpu::CompositeAlphaMode::PostMultiplied;
         self.encode_render_pass(
             &mut encoder,
             &view,
             pipeline,
+            premultiplied,
             character,
             skin,
             camera,
             config.width,
             config.height,
-        );
+        )
+        .map_err(|e| {
+            tracing::error!("Failed to encode frame: {e}");
+            wgpu::SurfaceError::Other
+        })?;
 
         self.queue.submit(Some(encoder.finish()));
         output.present();
@@ -747,6 +2898,97 @@ impl Renderer {
         self.clear_color = [r, g, b, a];
     }
 
+    /// Replace the key/fill/ambient light rig. Default is [`Lighting::default`].
+    pub fn set_lighting(&mut self, lighting: Lighting) {
+        self.lighting = lighting;
+    }
+
+    /// Apply a [`LightingPreset`]: sets the light rig and the matching opaque background color.
+    pub fn apply_lighting_preset(&mut self, preset: LightingPreset) {
+        self.lighting = preset.lighting();
+        self.clear_color = preset.background();
+    }
+
+    /// Replace the optional render features. Default is [`RenderOptions::default`].
+    pub fn set_options(&mut self, options: RenderOptions) {
+        if options.uv_inset != self.options.uv_inset {
+            // Texels of the 64px atlas the player models and headwear are laid out for.
+            let inset = options.uv_inset.max(0.0) / 64.0;
+            self.models.set_uv_inset(&self.device, inset);
+            for mesh in &mut self.headwear_meshes {
+                mesh.set_uv_inset(&self.device, inset);
+            }
+        }
+        self.options = options;
+    }
+
+    /// Current render features.
+    pub fn options(&self) -> &RenderOptions {
+        &self.options
+    }
+
+    /// Stop renders with [`EidolonError::Cancelled`] once `token` is cancelled. The token is
+    /// checked before each view, crowd and float render, and by the animation and lineup helpers
+    /// between frames and players; a submitted draw still finishes. `None` (the default) never
+    /// cancels.
+    pub fn set_cancel_token(&mut self, token: Option<CancelToken>) {
+        self.cancel = token;
+    }
+
+    /// Give up waiting for a view or frame whose GPU work and readback take longer than
+    /// `timeout`, with [`EidolonError::Timeout`], instead of blocking until the driver answers. The abandoned
+    /// work may still occupy the device, so treat a renderer that timed out as suspect and replace
+    /// it; the one-call APIs do (see [`RenderRequest::gpu_timeout`](crate::request::RenderRequest::gpu_timeout)).
+    /// `None` (the default) waits indefinitely.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_gpu_timeout(&mut self, timeout: Option<Duration>) {
+        self.gpu_timeout = timeout;
+    }
+
+    /// The token set with [`Renderer::set_cancel_token`].
+    pub fn cancel_token(&self) -> Option<&CancelToken> {
+        self.cancel.as_ref()
+    }
+
+    /// `Err(EidolonError::Cancelled)` once the renderer's cancel token is cancelled.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn check_cancelled(&self) -> Result<(), EidolonError> {
+        self.cancel.as_ref().map_or(Ok(()), CancelToken::check)
+    }
+
+    /// Normalize exposure across skins: render a small probe of `character` on a transparent
+    /// background with the current options (minus [`RenderOptions::adjustments`]), measure the
+    /// visible skin, and set [`ColorAdjustments::exposure`] so its mean luminance approaches
+    /// `target_luminance` (see [`DEFAULT_TARGET_LUMINANCE`]). Gamma and saturation are kept.
+    ///
+    /// Returns the measured (unadjusted) statistics, or `None` if nothing was visible — the
+    /// options are then left unchanged.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn normalize_brightness(
+        &mut self,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        target_luminance: f32,
+    ) -> Result<Option<BrightnessStats>, EidolonError> {
+        const PROBE_SIZE: u32 = 128;
+
+        let options = self.options.clone();
+        let clear_color = self.clear_color;
+        self.options.adjustments = ColorAdjustments::default();
+        self.options.alpha_mode = AlphaMode::Straight;
+        self.clear_color = [0.0; 4];
+        let probe = self.render(character, skin, camera, PROBE_SIZE, PROBE_SIZE);
+        self.options = options;
+        self.clear_color = clear_color;
+
+        let stats = BrightnessStats::from_image(&probe?);
+        if let Some(stats) = &stats {
+            self.options.adjustments.exposure = stats.exposure_to(target_luminance);
+        }
+        Ok(stats)
+    }
+
     /// Update surface extent after a resize; no-op if not windowed or size is zero.
     pub fn resize(&mut self, width: u32, height: u32) {
         if width == 0 || height == 0 {
@@ -759,7 +3001,114 @@ impl Renderer {
         }
     }
 
-    /// Calls [`Renderer::render`], then saves using [`OutputFormat`].
+    /// Embed [`RenderMetadata`] (see [`Renderer::render_metadata`]) in PNG and WebP files written
+    /// by [`Renderer::render_to_image`]. Off by default; EXR output never carries it.
+    pub fn set_embed_metadata(&mut self, embed: bool) {
+        self.embed_metadata = embed;
+    }
+
+    /// Tag PNG files written by [`Renderer::encode`] as sRGB ([`crate::color::tag_srgb_png`]), so
+    /// color-managed viewers show the render's colors unchanged. Off by default.
+    pub fn set_embed_srgb(&mut self, embed: bool) {
+        self.embed_srgb = embed;
+    }
+
+    /// Metadata describing a render of `character` with `skin` from `camera` at `size` under the
+    /// current light rig, background and options.
+    pub fn render_metadata(
+        &self,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        size: (u32, u32),
+    ) -> RenderMetadata {
+        let settings = format!(
+            "{:?}|{:?}|{:?}",
+            self.lighting, self.clear_color, self.options
+        );
+        RenderMetadata::describe(character, skin.content_hash(), camera, size, &settings)
+    }
+
+    /// Whether this renderer was created by [`Renderer::new_deterministic`].
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Calls [`Renderer::render`] (or [`Renderer::render_16bit`] / [`Renderer::render_hdr`] for
+    /// [`OutputFormat::Png16`] / [`OutputFormat::Exr`]) and encodes the image in `format`.
+    ///
+    /// With [`Renderer::set_embed_metadata`], PNG and WebP output carries [`RenderMetadata`]; with
+    /// [`Renderer::set_embed_srgb`], PNG output is tagged as sRGB. A
+    /// [`Renderer::new_deterministic`] renderer compresses EXR on one thread, so its blocks are
+    /// always written in the same order.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn encode(
+        &self,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        size: (u32, u32),
+        format: OutputFormat,
+    ) -> Result<Vec<u8>, EidolonError> {
+        let metadata = self
+            .embed_metadata
+            .then(|| self.render_metadata(character, skin, camera, size));
+        let bytes = match (format, &metadata) {
+            (OutputFormat::Png, Some(meta)) => {
+                meta.encode_png(&self.render(character, skin, camera, size.0, size.1)?)
+            }
+            (OutputFormat::WebP, Some(meta)) => {
+                meta.encode_webp(&self.render(character, skin, camera, size.0, size.1)?)
+            }
+            (OutputFormat::Png16, Some(meta)) => {
+                meta.encode_png16(&self.render_16bit(character, skin, camera, size.0, size.1)?)
+            }
+            (OutputFormat::Png | OutputFormat::WebP, None) => encode_buffer(
+                &self.render(character, skin, camera, size.0, size.1)?,
+                format,
+            ),
+            (OutputFormat::Png16, None) => encode_buffer(
+                &self.render_16bit(character, skin, camera, size.0, size.1)?,
+                format,
+            ),
+            (OutputFormat::Exr(channels), _) => self
+                .render_hdr(character, skin, camera, size.0, size.1)?
+                .encode_exr_with(channels, !self.deterministic),
+        }?;
+        if self.embed_srgb && matches!(format, OutputFormat::Png | OutputFormat::Png16) {
+            crate::color::tag_srgb_png(&bytes)
+        } else {
+            Ok(bytes)
+        }
+    }
+
+    /// [`Renderer::encode`], then hand the result to `sink` as an
+    /// [`Output`](crate::sink::Output) named `name` with its extension adjusted to `format`
+    /// (see [`OutputFormat::output_path`]).
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_to_sink(
+        &self,
+        character: &Character,
+        skin: &Texture,
+        camera: &Camera,
+        name: &str,
+        size: (u32, u32),
+        format: OutputFormat,
+        sink: &(impl crate::sink::OutputSink + ?Sized),
+    ) -> Result<(), EidolonError> {
+        let name = format.output_path(name)?;
+        let _span = tracing::info_span!("render_to_sink", name = %name).entered();
+        let bytes = self.encode(character, skin, camera, size, format)?;
+        sink.write(&crate::sink::Output {
+            name,
+            content_type: format.content_type(),
+            bytes,
+        })
+    }
+
+    /// [`Renderer::render_to_sink`] into a [`FileSink`](crate::sink::FileSink): saves the image
+    /// at `filename`.
     ///
     /// The file extension is automatically adjusted to match the output format
     /// (e.g. `"skin.png"` with `WebP` becomes `"skin.webp"`).
@@ -774,26 +3123,70 @@ impl Renderer {
         size: (u32, u32),
         format: OutputFormat,
     ) -> Result<(), EidolonError> {
-        if filename.contains('\0') {
-            return Err(EidolonError::invalid_path("output filename contains null bytes"));
-        }
+        let sink = crate::sink::FileSink::default();
+        self.render_to_sink(character, skin, camera, filename, size, format, &sink)
+    }
+}
 
-        // Auto-adjust extension to match the requested format.
-        let adjusted = match Path::new(filename).extension() {
-            Some(ext) if ext == format.extension() => filename.to_string(),
-            _ => {
-                let stem = Path::new(filename)
-                    .file_stem()
-                    .map(|s| s.to_string_lossy())
-                    .unwrap_or_else(|| filename.into());
-                format!("{}.{}", stem, format.extension())
-            }
-        };
+/// Whether a post-processed render with `options` reads its scene depth: for depth output, depth
+/// of field or the emissive overlay.
+fn reads_scene_depth(options: &RenderOptions, depth_output: bool) -> bool {
+    depth_output || options.depth_of_field.is_some() || options.emissive_overlay.is_some()
+}
 
-        let image_buffer = self.render(character, skin, camera, size.0, size.1)?;
-        image_buffer
-            .save_with_format(&adjusted, format.as_image_format())
-            .map_err(|e| EidolonError::texture(format!("failed to save image: {e}")))?;
-        Ok(())
+/// `image` encoded as `format` (PNG or WebP), without metadata.
+#[cfg(not(target_arch = "wasm32"))]
+fn encode_buffer<P>(
+    image: &image::ImageBuffer<P, Vec<P::Subpixel>>,
+    format: OutputFormat,
+) -> Result<Vec<u8>, EidolonError>
+where
+    P: image::PixelWithColorType,
+    [P::Subpixel]: image::EncodableLayout,
+{
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut bytes), format.as_image_format())
+        .map_err(|e| EidolonError::texture(format!("failed to save image: {e}")))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_format_png_as_image_format() {
+        assert_eq!(OutputFormat::Png.as_image_format(), ImageFormat::Png);
+    }
+
+    #[test]
+    fn output_format_webp_as_image_format() {
+        assert_eq!(OutputFormat::WebP.as_image_format(), ImageFormat::WebP);
+    }
+
+    #[test]
+    fn output_format_png_extension() {
+        assert_eq!(OutputFormat::Png.extension(), "png");
+    }
+
+    #[test]
+    fn output_format_webp_extension() {
+        assert_eq!(OutputFormat::WebP.extension(), "webp");
+    }
+
+    #[test]
+    fn output_format_float_formats() {
+        assert_eq!(OutputFormat::Png16.as_image_format(), ImageFormat::Png);
+        assert_eq!(OutputFormat::Png16.extension(), "png");
+        let exr = OutputFormat::Exr(ExrChannels::default());
+        assert_eq!(exr.as_image_format(), ImageFormat::OpenExr);
+        assert_eq!(exr.extension(), "exr");
+    }
+
+    #[test]
+    fn output_format_debug() {
+        assert!(format!("{:?}", OutputFormat::Png).contains("Png"));
+        assert!(format!("{:?}", OutputFormat::WebP).contains("WebP"));
     }
 }
@@ -0,0 +1,150 @@
+//! Player models, uploaded on first use and cached per skin type.
+//!
+//! A renderer that only draws classic skins never parses or uploads the slim mesh, and creating
+//! a renderer does not touch the model files at all.
+
+use std::cell::OnceCell;
+
+use crate::character::SkinType;
+use crate::error::EidolonError;
+use crate::model::Model;
+
+/// Loaded models by skin type; empty until a render needs one.
+pub(crate) struct ModelCache {
+    classic: OnceCell<Model>,
+    slim: OnceCell<Model>,
+    /// Classic and slim: set by the caller rather than loaded from the bundle, so not evictable.
+    custom: [bool; 2],
+    /// UV inset applied to every model, in texture coordinate units ([`Model::set_uv_inset`]).
+    uv_inset: f32,
+}
+
+impl ModelCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            classic: OnceCell::new(),
+            slim: OnceCell::new(),
+            custom: [false; 2],
+            uv_inset: 0.0,
+        }
+    }
+
+    fn cell(&self, skin_type: SkinType) -> &OnceCell<Model> {
+        match skin_type {
+            SkinType::Classic => &self.classic,
+            SkinType::Slim => &self.slim,
+        }
+    }
+
+    fn cell_mut(&mut self, skin_type: SkinType) -> &mut OnceCell<Model> {
+        match skin_type {
+            SkinType::Classic => &mut self.classic,
+            SkinType::Slim => &mut self.slim,
+        }
+    }
+
+    /// The UV inset applied to the models, in texture coordinate units.
+    pub(crate) fn uv_inset(&self) -> f32 {
+        self.uv_inset
+    }
+
+    /// The model for `skin_type`, loading the bundled one if none is cached yet.
+    pub(crate) fn get(
+        &self,
+        device: &wgpu::Device,
+        skin_type: SkinType,
+    ) -> Result<&Model, EidolonError> {
+        let cell = self.cell(skin_type);
+        if let Some(model) = cell.get() {
+            return Ok(model);
+        }
+        let mut model = load_bundled(device, skin_type)?;
+        if self.uv_inset != 0.0 {
+            model.set_uv_inset(device, self.uv_inset);
+        }
+        Ok(cell.get_or_init(|| model))
+    }
+
+    pub(crate) fn is_loaded(&self, skin_type: SkinType) -> bool {
+        self.cell(skin_type).get().is_some()
+    }
+
+    /// Replace the model for `skin_type`.
+    pub(crate) fn set(&mut self, device: &wgpu::Device, skin_type: SkinType, mut model: Model) {
+        if self.uv_inset != 0.0 {
+            model.set_uv_inset(device, self.uv_inset);
+        }
+        *self.cell_mut(skin_type) = OnceCell::from(model);
+        self.custom[skin_type as usize] = true;
+    }
+
+    /// Inset the texture coordinates of the loaded and future models by `inset`.
+    pub(crate) fn set_uv_inset(&mut self, device: &wgpu::Device, inset: f32) {
+        if inset == self.uv_inset {
+            return;
+        }
+        self.uv_inset = inset;
+        for skin_type in [SkinType::Classic, SkinType::Slim] {
+            if let Some(model) = self.cell_mut(skin_type).get_mut() {
+                model.set_uv_inset(device, inset);
+            }
+        }
+    }
+
+    /// Unload the model for `skin_type` if it is the bundled one; the next render reloads it.
+    pub(crate) fn evict_bundled(&mut self, skin_type: SkinType) {
+        if !self.custom[skin_type as usize] {
+            *self.cell_mut(skin_type) = OnceCell::new();
+        }
+    }
+
+    /// Vertex buffer bytes of the loaded models.
+    pub(crate) fn bytes(&self) -> u64 {
+        [SkinType::Classic, SkinType::Slim]
+            .into_iter()
+            .filter_map(|skin_type| self.cell(skin_type).get())
+            .map(model_bytes)
+            .sum()
+    }
+}
+
+/// Vertex buffer bytes of `model`'s main and layer meshes and its cape.
+fn model_bytes(model: &Model) -> u64 {
+    [
+        &model.head,
+        &model.body,
+        &model.right_arm,
+        &model.left_arm,
+        &model.right_leg,
+        &model.left_leg,
+    ]
+    .into_iter()
+    .map(|part| part.main.vertex_buffer.size() + part.layer.vertex_buffer.size())
+    .sum::<u64>()
+        + model.cape.vertex_buffer.size()
+}
+
+/// The bundled `resources/` mesh for `skin_type`.
+#[cfg(not(target_arch = "wasm32"))]
+fn load_bundled(device: &wgpu::Device, skin_type: SkinType) -> Result<Model, EidolonError> {
+    match skin_type {
+        SkinType::Classic => Model::load_from_obj(device, "resources/classic.obj"),
+        SkinType::Slim => Model::load_from_obj(device, "resources/slim.obj"),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn load_bundled(device: &wgpu::Device, skin_type: SkinType) -> Result<Model, EidolonError> {
+    match skin_type {
+        SkinType::Classic => Model::load_from_obj_bytes(
+            device,
+            include_bytes!("../../resources/classic.obj"),
+            "classic.obj",
+        ),
+        SkinType::Slim => Model::load_from_obj_bytes(
+            device,
+            include_bytes!("../../resources/slim.obj"),
+            "slim.obj",
+        ),
+    }
+}
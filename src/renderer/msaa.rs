@@ -0,0 +1,306 @@
+//! Multisample anti-aliasing ([`super::RenderOptions::msaa`]): the scene pass draws into
+//! multisampled color and depth targets that resolve into the output, and post-processing reads
+//! a depth buffer resolved from the multisampled one.
+
+use crate::constants::DEPTH_RESOLVE_SHADER;
+
+use super::memory::target_bytes;
+use super::pipeline::DEPTH_FORMAT;
+
+/// Multisampling capabilities of the device for the scene's color formats and the depth format.
+pub(crate) struct MsaaSupport {
+    formats: Vec<(wgpu::TextureFormat, wgpu::TextureFormatFeatureFlags)>,
+    /// Whether multisampled depth buffers can be sampled, as the depth resolve does. The GL
+    /// backend cannot create sampled multisampled textures.
+    pub sampled_depth: bool,
+}
+
+impl MsaaSupport {
+    /// Capabilities of `formats` on `device`. Sample counts other than WebGPU's guaranteed 4 need
+    /// [`wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES`], requested with the device
+    /// where the adapter has it.
+    pub fn new(
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+        formats: &[wgpu::TextureFormat],
+    ) -> Self {
+        let features = device.features();
+        let flags = |format: wgpu::TextureFormat| {
+            if features.contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+                adapter.get_texture_format_features(format).flags
+            } else {
+                format.guaranteed_format_features(features).flags
+            }
+        };
+        Self {
+            formats: formats
+                .iter()
+                .chain([&DEPTH_FORMAT])
+                .map(|&format| (format, flags(format)))
+                .collect(),
+            sampled_depth: adapter.get_info().backend != wgpu::Backend::Gl,
+        }
+    }
+
+    /// Most samples up to `requested` a scene pass into `format` can take: `format` renders and
+    /// resolves them and the depth buffer renders them. 1 for formats not given to
+    /// [`MsaaSupport::new`].
+    pub fn samples(&self, format: wgpu::TextureFormat, requested: u32) -> u32 {
+        let flags = |format| {
+            self.formats
+                .iter()
+                .find(|(f, _)| *f == format)
+                .map(|(_, flags)| *flags)
+        };
+        let (Some(color), Some(depth)) = (flags(format), flags(DEPTH_FORMAT)) else {
+            return 1;
+        };
+        if !color.contains(wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE) {
+            return 1;
+        }
+        let mut samples = requested.max(1);
+        while samples > 1
+            && !(color.sample_count_supported(samples) && depth.sample_count_supported(samples))
+        {
+            samples /= 2;
+        }
+        samples
+    }
+}
+
+/// Multisampled color and depth targets of the scene pass, at output size.
+pub(crate) struct MsaaTargets {
+    pub color: wgpu::TextureView,
+    /// Read by the depth resolve when created with `sampled_depth`.
+    pub depth: wgpu::TextureView,
+    pub format: wgpu::TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    pub samples: u32,
+}
+
+impl MsaaTargets {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        samples: u32,
+        sampled_depth: bool,
+    ) -> Self {
+        let depth_usage = if sampled_depth {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        };
+        let view = |label, format, usage| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size: wgpu::Extent3d {
+                        width,
+                        height,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: samples,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+        Self {
+            color: view(
+                "Multisampled Color Texture",
+                format,
+                wgpu::TextureUsages::RENDER_ATTACHMENT,
+            ),
+            depth: view("Multisampled Depth Texture", DEPTH_FORMAT, depth_usage),
+            format,
+            width,
+            height,
+            samples,
+        }
+    }
+
+    pub fn matches(
+        &self,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+        samples: u32,
+    ) -> bool {
+        (self.format, self.width, self.height, self.samples) == (format, width, height, samples)
+    }
+
+    /// Bytes of both targets, every sample.
+    pub fn bytes(&self) -> u64 {
+        let texels = target_bytes(self.format, self.width, self.height)
+            + target_bytes(DEPTH_FORMAT, self.width, self.height);
+        texels * u64::from(self.samples)
+    }
+}
+
+/// Fullscreen pass writing the nearest sample of a multisampled depth buffer into a
+/// single-sampled one ([`DEPTH_RESOLVE_SHADER`]).
+pub(crate) struct DepthResolve {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+}
+
+impl DepthResolve {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Depth Resolve Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: true,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Depth,
+                },
+                count: None,
+            }],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Depth Resolve Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Depth Resolve Shader"),
+            source: wgpu::ShaderSource::Wgsl(DEPTH_RESOLVE_SHADER.into()),
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Depth Resolve Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_fullscreen"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_resolve"),
+                compilation_options: Default::default(),
+                targets: &[],
+            }),
+            multiview: None,
+            cache: None,
+        });
+        Self {
+            pipeline,
+            bind_group_layout,
+        }
+    }
+
+    /// Encode the resolve of the multisampled `source` into the single-sampled `target`.
+    pub fn encode(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        source: &wgpu::TextureView,
+        target: &wgpu::TextureView,
+    ) {
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Depth Resolve Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(source),
+            }],
+        });
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Depth Resolve Pass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: target,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wgpu::TextureFormatFeatureFlags as Flags;
+
+    fn support(color: Flags, depth: Flags) -> MsaaSupport {
+        MsaaSupport {
+            formats: vec![
+                (wgpu::TextureFormat::Rgba8Unorm, color),
+                (DEPTH_FORMAT, depth),
+            ],
+            sampled_depth: true,
+        }
+    }
+
+    #[test]
+    fn unsupported_sample_counts_fall_back_to_lower_ones() {
+        let x4 = Flags::MULTISAMPLE_X4;
+        let all = Flags::MULTISAMPLE_X2 | x4 | Flags::MULTISAMPLE_X8;
+        let format = wgpu::TextureFormat::Rgba8Unorm;
+
+        let support = self::support(all | Flags::MULTISAMPLE_RESOLVE, x4);
+        assert_eq!(support.samples(format, 8), 4, "limited by the depth format");
+        assert_eq!(support.samples(format, 4), 4);
+        assert_eq!(support.samples(format, 2), 1);
+        assert_eq!(support.samples(format, 1), 1);
+        assert_eq!(support.samples(wgpu::TextureFormat::Rgba16Float, 4), 1);
+
+        let support = self::support(all, all);
+        assert_eq!(
+            support.samples(format, 4),
+            1,
+            "the color format must resolve"
+        );
+    }
+
+    #[test]
+    fn depth_resolve_shader_is_valid() {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        }))
+        .expect("No wgpu adapter");
+        let (device, _queue) =
+            pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()))
+                .expect("No wgpu device");
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let _ = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(DEPTH_RESOLVE_SHADER.into()),
+        });
+        // GLSL cannot load depth texels; the resolve never runs there (`sampled_depth`).
+        if adapter.get_info().backend != wgpu::Backend::Gl {
+            DepthResolve::new(&device);
+        }
+        let error = pollster::block_on(device.pop_error_scope());
+        assert!(error.is_none(), "{error:?}");
+    }
+}
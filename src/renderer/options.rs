@@ -0,0 +1,525 @@
+//! Renderer-wide feature toggles applied to every render.
+
+use crate::camera::DEFAULT_FOV;
+use crate::character::Bone;
+use crate::skin_layout::Layer;
+
+use super::particles::Particles;
+
+/// Optional render features. [`RenderOptions::default`] renders exactly like a plain renderer.
+///
+/// Set with [`crate::renderer::Renderer::set_options`]; the options apply to every subsequent
+/// headless render and windowed frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    /// Contact darkening between body parts (under the arms, between the legs, under the chin).
+    /// `0.0` disables it; `1.0` fully darkens texels in contact with another part.
+    pub ambient_occlusion: f32,
+    /// Shadow map for the key light (self-shadowing plus a ground shadow). `None` disables it.
+    pub shadow: Option<ShadowSettings>,
+    /// Depth-of-field blur post effect. `None` disables it.
+    pub depth_of_field: Option<DepthOfField>,
+    /// Render the overlay layer unlit and full-bright, with a bloom glow. `None` disables it.
+    pub emissive_overlay: Option<EmissiveOverlay>,
+    /// Curve that maps the floating-point scene to 8-bit output. Anything but
+    /// [`ToneMapping::Linear`] renders through the HDR post-processing pass.
+    pub tone_mapping: ToneMapping,
+    /// Whether output color is straight or premultiplied by alpha.
+    pub alpha_mode: AlphaMode,
+    /// Which texels are drawn, and whether the partially transparent ones blend or are cut out.
+    pub alpha_test: AlphaTest,
+    /// How skin, headwear and attachment textures are sampled.
+    pub texture_filter: TextureFilter,
+    /// Samples per pixel of the scene pass, smoothing the jagged silhouette edges of a
+    /// single-sampled render.
+    pub msaa: Msaa,
+    /// Vertical field of view in degrees, 1–120. Narrower fields move the eye back so the
+    /// camera's look-at target keeps its framing: [`DEFAULT_FOV`] is the usual perspective, a
+    /// few degrees look nearly orthographic.
+    pub fov: f32,
+    /// Inset of every face's texture coordinates, in texels of the 64px skin atlas, so sampling
+    /// never falls exactly on a face boundary where the neighbouring face's texel can be picked
+    /// (visible as stray texel lines along box edges). Applies to the player models and headwear;
+    /// `0.0` keeps the exact UVs. Around `0.01` is enough; changing it re-uploads the models.
+    pub uv_inset: f32,
+    /// Exposure, gamma and saturation applied to the shaded skin color.
+    pub adjustments: ColorAdjustments,
+    /// In-game status effects: hurt tint, burning, potion swirls.
+    pub status: StatusEffects,
+    /// Particle emitters decorating the scene (hearts, crit sparks, enchantment glyphs).
+    pub particles: Particles,
+    /// Animated enchantment glint over enchanted gear. `None` disables it.
+    pub glint: Option<EnchantmentGlint>,
+    /// Draw parts whose base layer is fully transparent or missing from the texture as flat gray
+    /// mannequin parts instead of leaving them invisible, so broken skins still give a usable
+    /// preview. The overlay is drawn over them as usual.
+    pub mannequin_fallback: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            ambient_occlusion: 0.0,
+            shadow: None,
+            depth_of_field: None,
+            emissive_overlay: None,
+            tone_mapping: ToneMapping::Linear,
+            alpha_mode: AlphaMode::Straight,
+            alpha_test: AlphaTest::default(),
+            texture_filter: TextureFilter::Nearest,
+            msaa: Msaa::Off,
+            fov: DEFAULT_FOV,
+            uv_inset: 0.0,
+            adjustments: ColorAdjustments::default(),
+            status: StatusEffects::default(),
+            particles: Particles::default(),
+            glint: None,
+            mannequin_fallback: false,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Whether any option needs the post-processing pass (render to an intermediate target, then
+    /// composite). Without one, the scene is drawn straight into the output.
+    pub(crate) fn uses_post_processing(&self) -> bool {
+        self.depth_of_field.is_some()
+            || self.emissive_overlay.is_some()
+            || self.tone_mapping != ToneMapping::Linear
+    }
+
+    /// Whether any option animates with time (status sprites, particles, glint).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn changes_over_time(&self) -> bool {
+        self.status.has_sprites() || !self.particles.is_empty() || self.glint.is_some()
+    }
+
+    /// These options `seconds` later: every effect clock advanced by `seconds`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn advanced(&self, seconds: f32) -> Self {
+        let mut options = self.clone();
+        options.status.time += seconds;
+        options.particles.time += seconds;
+        if let Some(glint) = &mut options.glint {
+            glint.time += seconds;
+        }
+        options
+    }
+}
+
+/// Shadow-map parameters for [`RenderOptions::shadow`].
+///
+/// The key light ([`crate::lighting::Lighting::key_direction`]) renders a depth map of the
+/// character; arms and head then shadow the torso, and a shadow-catcher ground plane under the
+/// feet receives the character's shadow (only the shadow is drawn, the plane itself stays
+/// invisible).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowSettings {
+    /// Shadow-map width and height in texels. Clamped to `16..=` the device texture limit.
+    pub resolution: u32,
+    /// Depth bias in shadow-map depth units (`0.0–1.0` spans the light frustum). Raise it if
+    /// lit faces show striped "shadow acne"; lower it if shadows detach from their casters.
+    pub bias: f32,
+    /// Opacity of the ground shadow (`0.0` hides the ground plane, `1.0` is solid black).
+    pub ground_opacity: f32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 1024,
+            bias: 0.003,
+            ground_opacity: 0.5,
+        }
+    }
+}
+
+/// Depth-of-field parameters for [`RenderOptions::depth_of_field`].
+///
+/// Blur grows with the distance from the focal plane and reaches `blur_strength` at half the
+/// focus distance in front of or behind it; the empty background counts as infinitely far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthOfField {
+    /// Distance from the eye (world units) that stays sharp. `None` focuses on the orbit target,
+    /// i.e. [`crate::camera::Camera::orbit_distance_with_fov`] at [`RenderOptions::fov`].
+    pub focus_distance: Option<f32>,
+    /// Largest blur radius, in output pixels.
+    pub blur_strength: f32,
+}
+
+impl Default for DepthOfField {
+    fn default() -> Self {
+        Self {
+            focus_distance: None,
+            blur_strength: 8.0,
+        }
+    }
+}
+
+/// Emissive overlay parameters for [`RenderOptions::emissive_overlay`].
+///
+/// Overlay-layer texels (hat, jacket, sleeves, pants) ignore the light rig and show their full
+/// texture color, so glowing details painted on the second layer — eyes, runes, visors — stay
+/// bright in any lighting. Emissive texels brighter than `bloom_threshold` then bleed a soft glow
+/// into their surroundings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmissiveOverlay {
+    /// Luminance (`0.0–1.0`) an emissive texel must exceed to bloom.
+    pub bloom_threshold: f32,
+    /// Strength of the glow; `0.0` keeps the overlay full-bright without bloom.
+    pub bloom_intensity: f32,
+}
+
+impl Default for EmissiveOverlay {
+    fn default() -> Self {
+        Self {
+            bloom_threshold: 0.6,
+            bloom_intensity: 1.0,
+        }
+    }
+}
+
+/// Tone mapper for [`RenderOptions::tone_mapping`].
+///
+/// Post effects render into a floating-point target, so bright key lights, rim light and bloom
+/// can exceed `1.0` there; the tone mapper decides how that range reaches the 8-bit output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ToneMapping {
+    /// Clamp to `0.0–1.0`; identical to rendering without post effects.
+    #[default]
+    Linear,
+    /// `c / (1 + c)`: never clips, but darkens mid-tones.
+    Reinhard,
+    /// ACES filmic approximation (Narkowicz): soft highlight roll-off with added contrast.
+    Aces,
+}
+
+impl ToneMapping {
+    /// Shader selector (`post.tone.x` in `POST_SHADER`).
+    pub(crate) fn shader_index(self) -> f32 {
+        match self {
+            ToneMapping::Linear => 0.0,
+            ToneMapping::Reinhard => 1.0,
+            ToneMapping::Aces => 2.0,
+        }
+    }
+}
+
+/// Alpha convention of rendered images, for [`RenderOptions::alpha_mode`].
+///
+/// Rendering is always premultiplied internally, so partially transparent texels blend onto a
+/// transparent background without dark fringes; this only selects what the output contains.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// Color channels hold the unblended color (the usual PNG/WebP convention).
+    #[default]
+    Straight,
+    /// Color channels are already multiplied by alpha, for compositors that expect it (EXR,
+    /// most video and game engines).
+    Premultiplied,
+}
+
+/// Alpha testing of skin texels, for [`RenderOptions::alpha_test`].
+///
+/// Texels with alpha below `cutoff` are never drawn. Above it, [`LayerAlpha::Blend`] keeps the
+/// texel's alpha and [`LayerAlpha::Cutout`] draws the texel opaque. Upscaled or antialiased skins
+/// carry half-transparent, darkened texels along overlay edges; blending them leaves gray fringes
+/// around hair and sleeves, while a cutout with a cutoff around `0.5` keeps the edges crisp like
+/// the game does. Headwear, capes and attachments are tested like the overlay. The default draws
+/// everything but near-invisible texels and blends both layers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlphaTest {
+    /// Alpha (`0.0–1.0`) below which texels are discarded.
+    pub cutoff: f32,
+    /// How the base layer draws texels at or above the cutoff.
+    pub base: LayerAlpha,
+    /// How the overlay layer draws texels at or above the cutoff.
+    pub overlay: LayerAlpha,
+}
+
+impl Default for AlphaTest {
+    fn default() -> Self {
+        Self {
+            cutoff: 0.01,
+            base: LayerAlpha::Blend,
+            overlay: LayerAlpha::Blend,
+        }
+    }
+}
+
+impl AlphaTest {
+    /// Both layers cut out at `cutoff`: hard, fringe-free edges.
+    pub fn cutout(cutoff: f32) -> Self {
+        Self {
+            cutoff,
+            base: LayerAlpha::Cutout,
+            overlay: LayerAlpha::Cutout,
+        }
+    }
+
+    /// Uniform values `(cutoff, cutout)` for a mesh of `layer`.
+    pub(crate) fn layer(&self, layer: Layer) -> (f32, u32) {
+        let mode = match layer {
+            Layer::Base => self.base,
+            Layer::Overlay => self.overlay,
+        };
+        (self.cutoff, (mode == LayerAlpha::Cutout) as u32)
+    }
+}
+
+/// How a layer draws the texels that pass [`AlphaTest::cutoff`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LayerAlpha {
+    /// Blend with the texel's alpha: soft edges, but dark fringes from antialiased skins.
+    #[default]
+    Blend,
+    /// Draw the texel fully opaque.
+    Cutout,
+}
+
+/// Texture sampling for [`RenderOptions::texture_filter`].
+///
+/// HD skins (128 pixels wide and up) carry mipmaps down to 64×64, so only they look different
+/// under [`TextureFilter::NearestMipmapped`]: a 512× skin shown at thumbnail size otherwise
+/// picks scattered texels and shimmers as it moves, while standard skins keep their crisp
+/// pixels. Crowd renders sample the full-size texture only.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextureFilter {
+    /// The nearest texel of the full-size texture: crisp pixels at any size.
+    #[default]
+    Nearest,
+    /// The nearest texel of the mipmap level closest to the on-screen size: crisp pixels without
+    /// the shimmer of small HD skins.
+    NearestMipmapped,
+    /// Bilinear texels, blended between mipmap levels: smooth, for painted HD skins.
+    Linear,
+}
+
+/// Multisample anti-aliasing for [`RenderOptions::msaa`].
+///
+/// The scene is drawn into multisampled color and depth targets and resolved into the output, so
+/// edges of the character, its shadow and sprites blend with the background; texels inside faces
+/// are still shaded once per pixel and stay crisp. Sample counts the adapter cannot render fall
+/// back to the next lower one (WebGPU only guarantees [`Msaa::X4`]). Crowd renders are
+/// anti-aliased too; segmentation maps never are. The GL backend cannot read multisampled depth,
+/// so there renders with depth of field, an emissive overlay or a depth output stay
+/// single-sampled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Msaa {
+    /// One sample per pixel: hard edges.
+    #[default]
+    Off,
+    X2,
+    X4,
+    X8,
+}
+
+impl Msaa {
+    /// Samples per pixel.
+    pub fn samples(self) -> u32 {
+        match self {
+            Msaa::Off => 1,
+            Msaa::X2 => 2,
+            Msaa::X4 => 4,
+            Msaa::X8 => 8,
+        }
+    }
+}
+
+/// Output adjustments for [`RenderOptions::adjustments`].
+///
+/// Applied per fragment to the shaded skin (before tone mapping and encoding), so they need no
+/// post pass and work in the windowed preview too; the transparent background is untouched.
+/// Useful for lifting avatars that read too dark on dark-mode UIs. The default is a no-op.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorAdjustments {
+    /// Exposure in stops: each `+1.0` doubles the brightness, `-1.0` halves it.
+    pub exposure: f32,
+    /// Gamma applied after exposure: values above `1.0` lift mid-tones, below `1.0` darken them.
+    /// Clamped to at least `0.01`.
+    pub gamma: f32,
+    /// Saturation: `0.0` is grayscale, `1.0` unchanged, above `1.0` more vivid.
+    pub saturation: f32,
+}
+
+impl Default for ColorAdjustments {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            gamma: 1.0,
+            saturation: 1.0,
+        }
+    }
+}
+
+impl ColorAdjustments {
+    /// Whether these adjustments leave colors unchanged.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// In-game status effects for [`RenderOptions::status`], for combat and status scenes. The
+/// default shows none.
+///
+/// Flames and potion swirls are billboarded sprites drawn after the character, depth-tested
+/// against it; they are unlit and skip the emissive glow and crowd renders.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatusEffects {
+    /// Red hurt flash: `1.0` tints the skin like the game does when an entity takes damage
+    /// (30% towards red, before lighting); `0.0` disables it.
+    pub hurt: f32,
+    /// Burning: flames wrapped around the character, in front of it as seen by the camera.
+    pub on_fire: bool,
+    /// Potion swirl particles rising around the character, in this RGB color (`0.0–1.0`, e.g.
+    /// the effect's color). `None` disables them.
+    pub potion: Option<[f32; 3]>,
+    /// Seconds into the effects: flames flicker and swirls rise as it advances. Both repeat every
+    /// second, so animations of whole seconds loop.
+    pub time: f32,
+}
+
+impl Default for StatusEffects {
+    fn default() -> Self {
+        Self {
+            hurt: 0.0,
+            on_fire: false,
+            potion: None,
+            time: 0.0,
+        }
+    }
+}
+
+impl StatusEffects {
+    /// Whether any effect draws sprites (and so changes with [`StatusEffects::time`]).
+    pub fn has_sprites(&self) -> bool {
+        self.on_fire || self.potion.is_some()
+    }
+}
+
+/// Enchantment glint for [`RenderOptions::glint`]: the game's scrolling purple shimmer, added on
+/// top of the lit color of enchanted gear.
+///
+/// There is no separate armor or item geometry; gear is what the skin paints on a bone, so the
+/// glint is toggled per bone and covers both skin layers of it. Crowd renders draw no glint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnchantmentGlint {
+    /// Bones that glint, e.g. `[Bone::Head]` for an enchanted helmet.
+    pub bones: Vec<Bone>,
+    /// Glint color, RGB `0.0–1.0`; brighter colors shimmer more strongly.
+    pub color: [f32; 3],
+    /// Seconds into the shimmer; it repeats every [`EnchantmentGlint::PERIOD`] seconds.
+    pub time: f32,
+}
+
+impl EnchantmentGlint {
+    /// Seconds after which the shimmer repeats, so animations of this length loop.
+    pub const PERIOD: f32 = 4.0;
+    /// The game's glint purple.
+    pub const PURPLE: [f32; 3] = [0.5, 0.25, 0.8];
+
+    /// Glint over every bone: a fully enchanted set of gear.
+    pub fn all() -> Self {
+        Self {
+            bones: vec![
+                Bone::Head,
+                Bone::Body,
+                Bone::RightArm,
+                Bone::LeftArm,
+                Bone::RightLeg,
+                Bone::LeftLeg,
+            ],
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for EnchantmentGlint {
+    /// No bones; set [`EnchantmentGlint::bones`] or start from [`EnchantmentGlint::all`].
+    fn default() -> Self {
+        Self {
+            bones: Vec::new(),
+            color: Self::PURPLE,
+            time: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_disables_ambient_occlusion() {
+        assert_eq!(RenderOptions::default().ambient_occlusion, 0.0);
+    }
+
+    #[test]
+    fn default_disables_shadows() {
+        assert!(RenderOptions::default().shadow.is_none());
+    }
+
+    #[test]
+    fn default_skips_post_processing() {
+        assert!(!RenderOptions::default().uses_post_processing());
+        let dof = RenderOptions {
+            depth_of_field: Some(DepthOfField::default()),
+            ..RenderOptions::default()
+        };
+        assert!(dof.uses_post_processing());
+        let emissive = RenderOptions {
+            emissive_overlay: Some(EmissiveOverlay::default()),
+            ..RenderOptions::default()
+        };
+        assert!(emissive.uses_post_processing());
+        let tone = RenderOptions {
+            tone_mapping: ToneMapping::Aces,
+            ..RenderOptions::default()
+        };
+        assert!(tone.uses_post_processing());
+    }
+
+    #[test]
+    fn default_adjustments_are_identity() {
+        assert!(RenderOptions::default().adjustments.is_identity());
+        let lifted = ColorAdjustments {
+            exposure: 0.5,
+            ..ColorAdjustments::default()
+        };
+        assert!(!lifted.is_identity());
+    }
+
+    #[test]
+    fn advancing_moves_every_effect_clock() {
+        let options = RenderOptions {
+            glint: Some(EnchantmentGlint::all()),
+            ..RenderOptions::default()
+        };
+        assert!(options.changes_over_time());
+        assert!(!RenderOptions::default().changes_over_time());
+        let later = options.advanced(1.5);
+        assert_eq!(later.status.time, 1.5);
+        assert_eq!(later.particles.time, 1.5);
+        assert_eq!(later.glint.unwrap().time, 1.5);
+    }
+
+    #[test]
+    fn default_status_shows_nothing() {
+        let status = RenderOptions::default().status;
+        assert_eq!(status.hurt, 0.0);
+        assert!(!status.has_sprites());
+        let burning = StatusEffects {
+            on_fire: true,
+            ..StatusEffects::default()
+        };
+        assert!(burning.has_sprites());
+    }
+
+    #[test]
+    fn default_shadow_settings_are_usable() {
+        let s = ShadowSettings::default();
+        assert!(s.resolution.is_power_of_two());
+        assert!(s.bias > 0.0 && s.bias < 0.1);
+        assert!((0.0..=1.0).contains(&s.ground_opacity));
+    }
+}
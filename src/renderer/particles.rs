@@ -0,0 +1,332 @@
+//! Particle emitters for scene dressing: hearts, crit sparks and enchantment glyphs.
+//!
+//! Particles are stateless: every particle an emitter spawned in the last `lifetime` seconds is
+//! recomputed from its spawn time and seed, so any [`Particles::time`] renders on its own and the
+//! same time always gives the same picture. Emitters are warmed up, so a still at time `0` already
+//! shows a full cloud. Particles are drawn with the status-effect sprites
+//! ([`super::sprites`]).
+
+use cgmath::{Matrix4, Point3, Transform, Vector3};
+
+use crate::animation::splitmix64;
+use crate::camera::Camera;
+use crate::character::{Bone, Character};
+
+use super::sprites::{SpriteInstance, SpriteKind};
+use super::uniforms::{bone_index, part_model_matrices};
+
+/// Particle emitters for [`super::RenderOptions::particles`]. The default has none.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Particles {
+    pub emitters: Vec<ParticleEmitter>,
+    /// Seconds since the emitters started; animations advance it per frame.
+    pub time: f32,
+}
+
+impl Particles {
+    pub fn is_empty(&self) -> bool {
+        self.emitters.is_empty()
+    }
+}
+
+/// Pixel-art look of a particle, with preset emitter settings in [`ParticleEmitter::preset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticlePreset {
+    /// Love hearts floating up from above the head.
+    Hearts,
+    /// Critical-hit sparks bursting from the body and falling.
+    CritSparks,
+    /// Enchanting-table glyphs drifting up around the character.
+    EnchantGlyphs,
+}
+
+/// Where an emitter spawns its particles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParticleAnchor {
+    /// Fixed in the scene: [`ParticleEmitter::offset`] is a world-space point.
+    Scene,
+    /// On a bone: [`ParticleEmitter::offset`] is a point of the model at rest (Y-up, feet at 0,
+    /// head top at 2) that follows the bone's joint rotation and the character transform.
+    Bone(Bone),
+}
+
+/// Spawns billboarded particles at a steady rate; each moves with a fixed velocity plus gravity
+/// and fades out at the end of its life.
+///
+/// Distances are in model units (the character is 2 tall) and scaled with the character by
+/// [`Camera::scale`]; motion is in world axes, so particles keep rising when the bone turns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleEmitter {
+    pub preset: ParticlePreset,
+    pub anchor: ParticleAnchor,
+    /// Spawn point; see [`ParticleAnchor`].
+    pub offset: [f32; 3],
+    /// Half extents of the box around the spawn point that particles spawn in.
+    pub spread: [f32; 3],
+    /// Particles per second.
+    pub rate: f32,
+    /// Seconds each particle lives.
+    pub lifetime: f32,
+    /// Initial velocity in units per second.
+    pub velocity: [f32; 3],
+    /// Random velocity added per particle, up to this much along each axis.
+    pub velocity_spread: f32,
+    /// Downward acceleration in units per second squared; negative floats upwards.
+    pub gravity: f32,
+    /// Particle width (= height).
+    pub size: f32,
+    /// Tint, RGB `0.0–1.0`.
+    pub color: [f32; 3],
+    /// Picks the particles' random spawn points, velocities and glyphs.
+    pub seed: u64,
+}
+
+impl ParticleEmitter {
+    /// An emitter with `preset`'s look and its usual placement, motion and color.
+    pub fn preset(preset: ParticlePreset) -> Self {
+        match preset {
+            ParticlePreset::Hearts => Self {
+                preset,
+                anchor: ParticleAnchor::Bone(Bone::Head),
+                offset: [0.0, 2.1, 0.0],
+                spread: [0.35, 0.05, 0.35],
+                rate: 2.5,
+                lifetime: 1.6,
+                velocity: [0.0, 0.45, 0.0],
+                velocity_spread: 0.05,
+                gravity: 0.0,
+                size: 0.22,
+                color: [0.95, 0.15, 0.2],
+                seed: 0,
+            },
+            ParticlePreset::CritSparks => Self {
+                preset,
+                anchor: ParticleAnchor::Bone(Bone::Body),
+                offset: [0.0, 1.15, 0.0],
+                spread: [0.45, 0.4, 0.4],
+                rate: 30.0,
+                lifetime: 0.7,
+                velocity: [0.0, 0.6, 0.0],
+                velocity_spread: 1.4,
+                gravity: 2.5,
+                size: 0.16,
+                color: [1.0, 0.92, 0.75],
+                seed: 0,
+            },
+            ParticlePreset::EnchantGlyphs => Self {
+                preset,
+                anchor: ParticleAnchor::Bone(Bone::Body),
+                offset: [0.0, 1.0, 0.0],
+                spread: [0.8, 0.9, 0.6],
+                rate: 10.0,
+                lifetime: 2.0,
+                velocity: [0.0, 0.25, 0.0],
+                velocity_spread: 0.15,
+                gravity: 0.0,
+                size: 0.14,
+                color: [0.85, 0.85, 1.0],
+                seed: 0,
+            },
+        }
+    }
+
+    /// The emitter moved onto `bone`, keeping its spawn point's offset from the center of the
+    /// bone it was on (from the character's center for scene emitters).
+    pub fn attached_to(self, bone: Bone) -> Self {
+        let from = match self.anchor {
+            ParticleAnchor::Scene => [0.0, 1.0, 0.0],
+            ParticleAnchor::Bone(from) => from.center(),
+        };
+        let to = bone.center();
+        Self {
+            anchor: ParticleAnchor::Bone(bone),
+            offset: std::array::from_fn(|i| self.offset[i] - from[i] + to[i]),
+            ..self
+        }
+    }
+
+    /// [`ParticlePreset`] drawn by this emitter, as a sprite pattern.
+    fn kind(&self) -> SpriteKind {
+        match self.preset {
+            ParticlePreset::Hearts => SpriteKind::Heart,
+            ParticlePreset::CritSparks => SpriteKind::Spark,
+            ParticlePreset::EnchantGlyphs => SpriteKind::Glyph,
+        }
+    }
+}
+
+/// Every live particle of `particles` around `character` as seen by `camera`.
+pub(crate) fn particle_sprites(
+    particles: &Particles,
+    character: &Character,
+    camera: &Camera,
+) -> Vec<SpriteInstance> {
+    let parts = part_model_matrices(character, camera);
+    let time = particles.time;
+    let mut sprites = Vec::new();
+    for (e, emitter) in particles.emitters.iter().enumerate() {
+        if emitter.rate <= 0.0 || emitter.lifetime <= 0.0 {
+            continue;
+        }
+        let origin = match emitter.anchor {
+            ParticleAnchor::Scene => Point3::from(emitter.offset) * camera.scale,
+            ParticleAnchor::Bone(bone) => {
+                let matrix: Matrix4<f32> = parts[bone_index(bone)];
+                matrix.transform_point(Point3::from(emitter.offset))
+            }
+        };
+        let kind = emitter.kind() as u32 as f32;
+        let seed = emitter.seed ^ splitmix64(e as u64);
+        // Particle `k` spawns at `k / rate`; those spawned within the last lifetime are alive.
+        let newest = (time * emitter.rate).floor() as i64;
+        let oldest = ((time - emitter.lifetime) * emitter.rate).floor() as i64 + 1;
+        for k in oldest..=newest {
+            let age = time - k as f32 / emitter.rate;
+            if !(0.0..emitter.lifetime).contains(&age) {
+                continue;
+            }
+            let random = Randoms(splitmix64(seed.wrapping_add(k as u64)));
+            let spawn = Vector3::new(
+                random.signed(0) * emitter.spread[0],
+                random.signed(1) * emitter.spread[1],
+                random.signed(2) * emitter.spread[2],
+            );
+            let velocity = Vector3::from(emitter.velocity)
+                + Vector3::new(random.signed(3), random.signed(4), random.signed(5))
+                    * emitter.velocity_spread;
+            let travel = velocity * age - Vector3::unit_y() * (0.5 * emitter.gravity * age * age);
+            let center = origin + (spawn + travel) * camera.scale;
+            let [r, g, b] = emitter.color;
+            sprites.push(SpriteInstance {
+                center: [center.x, center.y, center.z, emitter.size * camera.scale],
+                color: [r, g, b, 1.0],
+                params: [kind, (random.0 >> 54) as f32, age / emitter.lifetime, 0.0],
+            });
+        }
+    }
+    sprites
+}
+
+/// Six 10-bit random numbers from one 64-bit hash.
+struct Randoms(u64);
+
+impl Randoms {
+    /// The `i`th number, in `-1.0..=1.0`.
+    fn signed(&self, i: u32) -> f32 {
+        ((self.0 >> (i * 10)) & 0x3ff) as f32 / 1023.0 * 2.0 - 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(emitter: ParticleEmitter, time: f32) -> Vec<SpriteInstance> {
+        let particles = Particles {
+            emitters: vec![emitter],
+            time,
+        };
+        particle_sprites(&particles, &Character::new(), &Camera::new())
+    }
+
+    #[test]
+    fn no_emitters_no_particles() {
+        let sprites = particle_sprites(&Particles::default(), &Character::new(), &Camera::new());
+        assert!(sprites.is_empty());
+    }
+
+    #[test]
+    fn emitters_start_warm() {
+        let hearts = ParticleEmitter::preset(ParticlePreset::Hearts);
+        let expected = (hearts.rate * hearts.lifetime).floor() as usize;
+        let sprites = at(hearts, 0.0);
+        assert!(sprites.len() >= expected, "{} < {expected}", sprites.len());
+        assert!(sprites.iter().all(|s| (0.0..1.0).contains(&s.params[2])));
+        assert!(sprites
+            .iter()
+            .all(|s| s.params[0] == SpriteKind::Heart as u32 as f32));
+    }
+
+    #[test]
+    fn particles_keep_their_identity_over_time() {
+        let emitter = ParticleEmitter {
+            spread: [0.0; 3],
+            velocity_spread: 0.0,
+            ..ParticleEmitter::preset(ParticlePreset::Hearts)
+        };
+        let first = at(emitter.clone(), 0.0);
+        let later = at(emitter, 0.2);
+        // The newest particle at 0.0 is 0.2 s older and has risen by 0.2 × velocity.
+        let newest = first.last().unwrap();
+        let same = later
+            .iter()
+            .find(|s| (s.params[1] - newest.params[1]).abs() < 0.5)
+            .unwrap();
+        assert!((same.center[1] - newest.center[1] - 0.2 * 0.45).abs() < 1e-4);
+    }
+
+    #[test]
+    fn gravity_pulls_sparks_down() {
+        let still = ParticleEmitter {
+            velocity: [0.0; 3],
+            velocity_spread: 0.0,
+            spread: [0.0; 3],
+            gravity: 0.0,
+            ..ParticleEmitter::preset(ParticlePreset::CritSparks)
+        };
+        let falling = ParticleEmitter {
+            gravity: 2.5,
+            ..still.clone()
+        };
+        let rest = at(still, 1.0);
+        let fallen = at(falling, 1.0);
+        for (a, b) in rest.iter().zip(&fallen) {
+            let age = a.params[2] * 0.7;
+            assert!((a.center[1] - b.center[1] - 0.5 * 2.5 * age * age).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn bone_anchors_follow_the_joint() {
+        let emitter = ParticleEmitter {
+            anchor: ParticleAnchor::Bone(Bone::RightArm),
+            offset: [0.3125, 0.75, 0.0],
+            spread: [0.0; 3],
+            velocity: [0.0; 3],
+            velocity_spread: 0.0,
+            ..ParticleEmitter::preset(ParticlePreset::CritSparks)
+        };
+        let particles = Particles {
+            emitters: vec![emitter],
+            time: 0.0,
+        };
+        let camera = Camera::new();
+        let down = particle_sprites(&particles, &Character::new(), &camera);
+        let mut raised = Character::new();
+        raised.posture.right_arm_pitch = 180.0;
+        let up = particle_sprites(&particles, &raised, &camera);
+        // Hand below the shoulder at rest, above it with the arm raised.
+        assert!(down[0].center[1] < 1.375 * camera.scale);
+        assert!(up[0].center[1] > 1.375 * camera.scale);
+    }
+
+    #[test]
+    fn scene_anchors_ignore_the_pose() {
+        let emitter = ParticleEmitter {
+            anchor: ParticleAnchor::Scene,
+            offset: [1.0, 0.5, 0.0],
+            ..ParticleEmitter::preset(ParticlePreset::EnchantGlyphs)
+        };
+        let mut moved = Character::new();
+        moved.position = Vector3::new(3.0, 0.0, 0.0);
+        let particles = Particles {
+            emitters: vec![emitter],
+            time: 0.5,
+        };
+        let camera = Camera::new();
+        assert_eq!(
+            particle_sprites(&particles, &Character::new(), &camera),
+            particle_sprites(&particles, &moved, &camera)
+        );
+    }
+}
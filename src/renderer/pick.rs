@@ -0,0 +1,177 @@
+//! Picking ([`super::Renderer::pick`]): which mesh, and which texel of its texture, is under an
+//! output pixel, found by casting a ray through the [`super::Renderer::posed_geometry`].
+
+use cgmath::{InnerSpace, Matrix4, SquareMatrix, Vector3, Vector4};
+
+use crate::camera::Camera;
+
+use super::posed::{PosedMesh, PosedSource};
+
+/// What is under a pixel of a render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pick {
+    /// The mesh hit; its texture is the skin (or a part override) for body parts.
+    pub source: PosedSource,
+    /// World-space point hit.
+    pub position: [f32; 3],
+    /// Unit world-space normal of the triangle hit, facing the camera.
+    pub normal: [f32; 3],
+    /// Texture coordinates at the point hit.
+    pub uv: [f32; 2],
+    /// Texel of the mesh's texture at `uv`, in pixels of that texture (HD skins give HD texels).
+    pub texel: (u32, u32),
+}
+
+/// A ray hit, nearest first when sorted by `distance`.
+pub(crate) struct Hit {
+    pub distance: f32,
+    pub mesh: usize,
+    pub position: Vector3<f32>,
+    pub normal: Vector3<f32>,
+    pub uv: [f32; 2],
+}
+
+/// Eye position and unit direction of the ray through the center of pixel `(x, y)` of a
+/// `width`×`height` render through `camera`, or `None` for a degenerate camera (zero or
+/// non-finite scale) whose matrices cannot be inverted.
+pub(crate) fn pixel_ray(
+    camera: &Camera,
+    fov: f32,
+    width: u32,
+    height: u32,
+    (x, y): (u32, u32),
+) -> Option<(Vector3<f32>, Vector3<f32>)> {
+    let view = Matrix4::from(camera.view_matrix_with_fov(fov));
+    let projection = Matrix4::from(camera.projection_matrix_with_fov(width, height, fov));
+    let inverse = (projection * view).invert()?;
+    let ndc_x = (x as f32 + 0.5) / width as f32 * 2.0 - 1.0;
+    let ndc_y = 1.0 - (y as f32 + 0.5) / height as f32 * 2.0;
+    // Depth 0.5 is inside the frustum whether clip depth runs from -1 or from 0.
+    let point = inverse * Vector4::new(ndc_x, ndc_y, 0.5, 1.0);
+    let point = point.truncate() / point.w;
+    let eye = camera.eye_position(fov);
+    let eye = Vector3::new(eye.x, eye.y, eye.z);
+    Some((eye, (point - eye).normalize()))
+}
+
+/// Every triangle of `meshes` the ray from `origin` along `direction` crosses, nearest first.
+/// Triangles are hit from both sides, as renders draw them.
+pub(crate) fn cast(
+    meshes: &[PosedMesh],
+    origin: Vector3<f32>,
+    direction: Vector3<f32>,
+) -> Vec<Hit> {
+    const EPSILON: f32 = 1e-7;
+    let mut hits = Vec::new();
+    for (mesh_index, mesh) in meshes.iter().enumerate() {
+        for [a, b, c] in mesh.triangles() {
+            // Möller–Trumbore.
+            let p0 = Vector3::from(a.position);
+            let edge1 = Vector3::from(b.position) - p0;
+            let edge2 = Vector3::from(c.position) - p0;
+            let p = direction.cross(edge2);
+            let det = edge1.dot(p);
+            if det.abs() < EPSILON {
+                continue;
+            }
+            let t_vec = origin - p0;
+            let u = t_vec.dot(p) / det;
+            if !(0.0..=1.0).contains(&u) {
+                continue;
+            }
+            let q = t_vec.cross(edge1);
+            let v = direction.dot(q) / det;
+            if v < 0.0 || u + v > 1.0 {
+                continue;
+            }
+            let distance = edge2.dot(q) / det;
+            if distance <= 0.0 {
+                continue;
+            }
+            let w = 1.0 - u - v;
+            let uv = [0, 1].map(|i| w * a.texture[i] + u * b.texture[i] + v * c.texture[i]);
+            let normal = edge1.cross(edge2).normalize();
+            hits.push(Hit {
+                distance,
+                mesh: mesh_index,
+                position: origin + direction * distance,
+                normal: if normal.dot(direction) > 0.0 { -normal } else { normal },
+                uv,
+            });
+        }
+    }
+    hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    hits
+}
+
+/// Texel of a `width`×`height` texture that nearest sampling reads at `uv`.
+pub(crate) fn texel_at(uv: [f32; 2], width: u32, height: u32) -> (u32, u32) {
+    let texel = |coordinate: f32, size: u32| {
+        ((coordinate * size as f32).floor().max(0.0) as u32).min(size.saturating_sub(1))
+    };
+    (texel(uv[0], width), texel(uv[1], height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::DEFAULT_FOV;
+    use crate::character::Bone;
+    use crate::model::TexturedVertex;
+    use crate::skin_layout::Layer;
+
+    fn vertex(position: [f32; 3], texture: [f32; 2]) -> TexturedVertex {
+        TexturedVertex {
+            position,
+            normal: [0.0, 0.0, 1.0],
+            texture,
+        }
+    }
+
+    /// A unit square at depth `z`, facing +Z, textured with the whole texture.
+    fn square(z: f32, layer: Layer) -> PosedMesh {
+        let corner = |x: f32, y: f32| vertex([x, y, z], [x, 1.0 - y]);
+        PosedMesh {
+            source: PosedSource::Part {
+                bone: Bone::Body,
+                layer,
+            },
+            vertices: vec![
+                corner(0.0, 0.0),
+                corner(1.0, 0.0),
+                corner(1.0, 1.0),
+                corner(0.0, 0.0),
+                corner(1.0, 1.0),
+                corner(0.0, 1.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn rays_hit_the_nearest_triangle_first() {
+        let meshes = [square(0.0, Layer::Base), square(0.5, Layer::Overlay)];
+        let origin = Vector3::new(0.25, 0.75, 2.0);
+        let hits = cast(&meshes, origin, Vector3::new(0.0, 0.0, -1.0));
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].mesh, 1);
+        assert!((hits[0].distance - 1.5).abs() < 1e-5);
+        assert!((hits[0].uv[0] - 0.25).abs() < 1e-5 && (hits[0].uv[1] - 0.25).abs() < 1e-5);
+        assert_eq!(texel_at(hits[0].uv, 64, 64), (16, 16));
+        assert_eq!(hits[1].mesh, 0);
+        // Seen from behind, the normal still faces the ray.
+        let hits = cast(&meshes, Vector3::new(0.5, 0.5, -1.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(hits[0].mesh, 0);
+        assert!(hits[0].normal.z < 0.0);
+        assert!(cast(&meshes, origin, Vector3::new(0.0, 0.0, 1.0)).is_empty());
+    }
+
+    #[test]
+    fn pixel_rays_go_through_the_look_at_target() {
+        let camera = Camera::default();
+        let (origin, direction) = pixel_ray(&camera, DEFAULT_FOV, 101, 101, (50, 50)).unwrap();
+        // The center pixel looks at the orbit target, (0, 1, 0).
+        let to_target = (Vector3::new(0.0, 1.0, 0.0) - origin).normalize();
+        assert!(direction.dot(to_target) > 0.9999);
+        assert_eq!(texel_at([1.0, -0.5], 64, 64), (63, 0));
+    }
+}
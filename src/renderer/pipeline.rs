@@ -1,27 +1,221 @@
-use crate::constants::SHADER;
+use crate::constants::{BLOOM_SHADER, POST_SHADER, SHADER, SPRITE_SHADER};
 use crate::model::TexturedVertex;
 
+use std::cell::RefCell;
+
+use super::bloom::BLOOM_FORMAT;
+#[cfg(not(target_arch = "wasm32"))]
+use super::crowd::InstanceData;
+use super::shadow::SHADOW_FORMAT;
+use super::sprites::SpriteInstance;
+use super::variants::VariantCache;
+
 pub(crate) const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24Plus;
 pub(crate) const RENDER_TARGET_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+/// Intermediate scene target when post-processing runs: float, so lighting above 1.0 survives
+/// until tone mapping.
+pub(crate) const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+/// Output target of float renders ([`super::Renderer::render_hdr`], 16-bit PNG). Half float:
+/// 32-bit float targets are not renderable on downlevel (GL) adapters.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) const FLOAT_TARGET_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+/// Output target of the linear depth written alongside float renders (depth in `r`).
+pub(crate) const DEPTH_OUTPUT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Pipelines that draw the scene into a color target of one format.
+pub(crate) struct ScenePipelines {
+    pub color_format: wgpu::TextureFormat,
+    /// Lit, textured skin mesh, one pipeline per shader feature set and sample count.
+    pub skin: VariantCache,
+    /// Shadow-catcher ground quad: blends the shadow over the background, no depth write.
+    pub ground: SampledPipeline,
+    /// Billboarded status-effect sprites, depth-tested, no depth write.
+    pub sprites: SampledPipeline,
+}
+
+/// Creates a pipeline from a shader, layout and color format at a sample count.
+type CreatePipeline = fn(
+    &wgpu::Device,
+    &wgpu::ShaderModule,
+    &wgpu::PipelineLayout,
+    wgpu::TextureFormat,
+    u32,
+) -> wgpu::RenderPipeline;
+
+/// One pipeline at every sample count the scene pass is asked for: single-sampled created up
+/// front, multisampled ones ([`super::RenderOptions::msaa`]) on first use.
+pub(crate) struct SampledPipeline {
+    create: CreatePipeline,
+    shader: wgpu::ShaderModule,
+    layout: wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+    pipelines: RefCell<Vec<(u32, wgpu::RenderPipeline)>>,
+}
+
+impl SampledPipeline {
+    fn new(
+        device: &wgpu::Device,
+        create: CreatePipeline,
+        shader: &wgpu::ShaderModule,
+        layout: &wgpu::PipelineLayout,
+        color_format: wgpu::TextureFormat,
+    ) -> Self {
+        let pipeline = create(device, shader, layout, color_format, 1);
+        Self {
+            create,
+            shader: shader.clone(),
+            layout: layout.clone(),
+            color_format,
+            pipelines: RefCell::new(vec![(1, pipeline)]),
+        }
+    }
+
+    /// The pipeline for `samples` samples per pixel, creating it on first request.
+    pub fn get(&self, device: &wgpu::Device, samples: u32) -> wgpu::RenderPipeline {
+        let mut pipelines = self.pipelines.borrow_mut();
+        if let Some((_, pipeline)) = pipelines.iter().find(|(s, _)| *s == samples) {
+            return pipeline.clone();
+        }
+        let pipeline = (self.create)(device, &self.shader, &self.layout, self.color_format, samples);
+        pipelines.push((samples, pipeline.clone()));
+        pipeline
+    }
+}
+
+/// Pipelines that write a final output target of one format: either the scene directly, or the
+/// post-processing composite of the HDR scene.
+pub(crate) struct TargetPipelines {
+    pub scene: ScenePipelines,
+    /// Fullscreen post-processing composite (reads the intermediate scene target).
+    pub composite: wgpu::RenderPipeline,
+}
+
+pub(crate) fn create_shader_module(device: &wgpu::Device) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Skin Shader"),
+        source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+    })
+}
+
+pub(crate) fn create_post_shader_module(device: &wgpu::Device) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Post Shader"),
+        source: wgpu::ShaderSource::Wgsl(POST_SHADER.into()),
+    })
+}
+
+pub(crate) fn create_sprite_shader_module(device: &wgpu::Device) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Sprite Shader"),
+        source: wgpu::ShaderSource::Wgsl(SPRITE_SHADER.into()),
+    })
+}
+
+pub(crate) fn create_bloom_shader_module(device: &wgpu::Device) -> wgpu::ShaderModule {
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Bloom Shader"),
+        source: wgpu::ShaderSource::Wgsl(BLOOM_SHADER.into()),
+    })
+}
+
+/// Shader modules and layouts shared by every [`ScenePipelines`] instance.
+pub(crate) struct PipelineSources<'a> {
+    pub shader: &'a wgpu::ShaderModule,
+    pub layout: &'a wgpu::PipelineLayout,
+    pub post_shader: &'a wgpu::ShaderModule,
+    pub post_layout: &'a wgpu::PipelineLayout,
+    pub sprite_shader: &'a wgpu::ShaderModule,
+    pub sprite_layout: &'a wgpu::PipelineLayout,
+}
+
+pub(crate) fn create_scene_pipelines(
+    device: &wgpu::Device,
+    sources: &PipelineSources,
+    color_format: wgpu::TextureFormat,
+) -> ScenePipelines {
+    ScenePipelines {
+        color_format,
+        skin: VariantCache::new(
+            sources.layout,
+            color_format,
+            "vs_main",
+            vec![TexturedVertex::desc()],
+            "fs_main",
+        ),
+        ground: SampledPipeline::new(
+            device,
+            create_ground_pipeline,
+            sources.shader,
+            sources.layout,
+            color_format,
+        ),
+        sprites: SampledPipeline::new(
+            device,
+            create_sprite_pipeline,
+            sources.sprite_shader,
+            sources.sprite_layout,
+            color_format,
+        ),
+    }
+}
+
+pub(crate) fn create_target_pipelines(
+    device: &wgpu::Device,
+    sources: &PipelineSources,
+    color_format: wgpu::TextureFormat,
+) -> TargetPipelines {
+    TargetPipelines {
+        scene: create_scene_pipelines(device, sources, color_format),
+        composite: create_composite_pipeline(
+            device,
+            sources.post_shader,
+            sources.post_layout,
+            color_format,
+        ),
+    }
+}
 
+/// Lit skin mesh for crowd renders: `vs_instanced` reads each member's part transform and skin
+/// layer from a per-instance [`InstanceData`] buffer in slot 1, `fs_crowd` samples the skin array.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn create_crowd_pipeline(
+    pipeline_layout: &wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+) -> VariantCache {
+    VariantCache::new(
+        pipeline_layout,
+        color_format,
+        "vs_instanced",
+        vec![TexturedVertex::desc(), InstanceData::desc()],
+        "fs_crowd",
+    )
+}
+
+/// Lit skin pipeline with the skin shader's feature overrides set to `constants`, drawing
+/// `samples` samples per pixel.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn create_pipeline(
     device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
     pipeline_layout: &wgpu::PipelineLayout,
     color_format: wgpu::TextureFormat,
+    vertex_entry: &str,
+    buffers: &[wgpu::VertexBufferLayout],
+    fragment_entry: &str,
+    constants: &[(&str, f64)],
+    samples: u32,
 ) -> wgpu::RenderPipeline {
-    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("Skin Shader"),
-        source: wgpu::ShaderSource::Wgsl(SHADER.into()),
-    });
-
     device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
         label: Some("Render Pipeline"),
         layout: Some(pipeline_layout),
         vertex: wgpu::VertexState {
-            module: &shader,
-            entry_point: Some("vs_main"),
-            compilation_options: Default::default(),
-            buffers: &[TexturedVertex::desc()],
+            module: shader,
+            entry_point: Some(vertex_entry),
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants,
+                ..Default::default()
+            },
+            buffers,
         },
         primitive: wgpu::PrimitiveState {
             topology: wgpu::PrimitiveTopology::TriangleList,
@@ -40,17 +234,69 @@ pub(crate) fn create_pipeline(
             bias: wgpu::DepthBiasState::default(),
         }),
         multisample: wgpu::MultisampleState {
-            count: 1,
+            count: samples,
             mask: !0,
             alpha_to_coverage_enabled: false,
         },
         fragment: Some(wgpu::FragmentState {
-            module: &shader,
-            entry_point: Some("fs_main"),
+            module: shader,
+            entry_point: Some(fragment_entry),
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants,
+                ..Default::default()
+            },
+            targets: &[Some(wgpu::ColorTargetState {
+                format: color_format,
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+        cache: None,
+    })
+}
+
+fn create_ground_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    pipeline_layout: &wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+    samples: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Ground Pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[TexturedVertex::desc()],
+        },
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: None,
+            ..Default::default()
+        },
+        // Tested against the character so the feet hide the plane, but never written: the
+        // plane is only a shadow catcher.
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: samples,
+            ..Default::default()
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_ground"),
             compilation_options: Default::default(),
             targets: &[Some(wgpu::ColorTargetState {
                 format: color_format,
-                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
                 write_mask: wgpu::ColorWrites::ALL,
             })],
         }),
@@ -58,3 +304,266 @@ pub(crate) fn create_pipeline(
         cache: None,
     })
 }
+
+fn create_sprite_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    pipeline_layout: &wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+    samples: u32,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Sprite Pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_sprite"),
+            compilation_options: Default::default(),
+            buffers: &[SpriteInstance::desc()],
+        },
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: None,
+            ..Default::default()
+        },
+        // Hidden behind the character, but not written: sprites overlap and blend.
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: samples,
+            ..Default::default()
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_sprite"),
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: color_format,
+                blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Depth-only pass from the key light. The fragment stage only alpha-tests the skin so
+/// transparent overlay texels do not cast shadows.
+pub(crate) fn create_shadow_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    pipeline_layout: &wgpu::PipelineLayout,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Shadow Pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_shadow"),
+            compilation_options: Default::default(),
+            buffers: &[TexturedVertex::desc()],
+        },
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: SHADOW_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState {
+                constant: 0,
+                slope_scale: 1.5,
+                clamp: 0.0,
+            },
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_shadow"),
+            compilation_options: Default::default(),
+            targets: &[],
+        }),
+        multiview: None,
+        cache: None,
+    })
+}
+
+pub(crate) fn create_composite_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    pipeline_layout: &wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    create_fullscreen_pipeline(
+        device,
+        "Composite Pipeline",
+        shader,
+        "fs_composite",
+        pipeline_layout,
+        color_format,
+    )
+}
+
+/// Eye distance per pixel from the scene depth buffer, into [`DEPTH_OUTPUT_FORMAT`].
+pub(crate) fn create_depth_output_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    pipeline_layout: &wgpu::PipelineLayout,
+) -> wgpu::RenderPipeline {
+    create_fullscreen_pipeline(
+        device,
+        "Depth Output Pipeline",
+        shader,
+        "fs_depth",
+        pipeline_layout,
+        DEPTH_OUTPUT_FORMAT,
+    )
+}
+
+fn create_fullscreen_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    shader: &wgpu::ShaderModule,
+    fragment_entry: &str,
+    pipeline_layout: &wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_fullscreen"),
+            compilation_options: Default::default(),
+            buffers: &[],
+        },
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some(fragment_entry),
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: color_format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Overlay layer, unlit, into the emissive target. Depth-tested (`LessEqual`, no write) against
+/// the finished scene depth so only visible overlay texels are written.
+pub(crate) fn create_emissive_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    pipeline_layout: &wgpu::PipelineLayout,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Emissive Pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[TexturedVertex::desc()],
+        },
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_emissive"),
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: BLOOM_FORMAT,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Segment ids of the meshes into [`RENDER_TARGET_FORMAT`]: alpha-tested and depth-tested like
+/// the skin, but unlit and unblended, so every pixel holds exactly one id.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn create_segmentation_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    pipeline_layout: &wgpu::PipelineLayout,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Segmentation Pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[TexturedVertex::desc()],
+        },
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            cull_mode: None,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState::default(),
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some("fs_segment"),
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: RENDER_TARGET_FORMAT,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+        cache: None,
+    })
+}
+
+pub(crate) fn create_blur_pipeline(
+    device: &wgpu::Device,
+    shader: &wgpu::ShaderModule,
+    pipeline_layout: &wgpu::PipelineLayout,
+) -> wgpu::RenderPipeline {
+    create_fullscreen_pipeline(
+        device,
+        "Bloom Blur Pipeline",
+        shader,
+        "fs_blur",
+        pipeline_layout,
+        BLOOM_FORMAT,
+    )
+}
@@ -0,0 +1,98 @@
+//! Posed geometry without rendering ([`super::Renderer::posed_geometry`]): the triangles a render
+//! draws, moved to world space, for crates that rasterize, ray cast or export on their own.
+
+use cgmath::{InnerSpace, Matrix4, Vector3};
+
+use crate::character::Bone;
+use crate::model::{inset_uvs, TexturedVertex};
+use crate::skin_layout::Layer;
+
+/// What a [`PosedMesh`] was built from, which also tells which texture its UVs index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PosedSource {
+    /// A layer of a body part, textured by the skin (or the part's
+    /// [`crate::character::PartTextureOverride`]).
+    Part { bone: Bone, layer: Layer },
+    /// The [`crate::character::Equipment::head`] model, textured by its own texture.
+    Headwear,
+    /// The [`crate::character::Character::attachments`] entry with this index.
+    Attachment(usize),
+    /// The [`crate::character::Equipment::cape`], textured by its own texture.
+    Cape,
+}
+
+/// One mesh of a posed character.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PosedMesh {
+    pub source: PosedSource,
+    /// Triangle list (every three vertices are one triangle) with world-space positions, unit
+    /// world-space normals and the texture coordinates the render samples.
+    pub vertices: Vec<TexturedVertex>,
+}
+
+impl PosedMesh {
+    /// The triangles of the mesh.
+    pub fn triangles(&self) -> impl Iterator<Item = [TexturedVertex; 3]> + '_ {
+        self.vertices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+    }
+}
+
+/// `vertices` moved by `model`, pushed `offset` along their normals first as the skin shader
+/// does, with their UVs inset by `uv_inset` ([`inset_uvs`]).
+pub(crate) fn pose_vertices(
+    vertices: &[TexturedVertex],
+    model: Matrix4<f32>,
+    offset: f32,
+    uv_inset: f32,
+) -> Vec<TexturedVertex> {
+    let mut posed: Vec<_> = vertices
+        .iter()
+        .map(|vertex| {
+            let normal = Vector3::from(vertex.normal);
+            let position = Vector3::from(vertex.position) + normal * offset;
+            let world = model * position.extend(1.0);
+            let world_normal = (model * normal.extend(0.0)).truncate();
+            let world_normal = if world_normal.magnitude2() > 0.0 {
+                world_normal.normalize()
+            } else {
+                world_normal
+            };
+            TexturedVertex {
+                position: world.truncate().into(),
+                normal: world_normal.into(),
+                texture: vertex.texture,
+            }
+        })
+        .collect();
+    if uv_inset > 0.0 {
+        inset_uvs(&mut posed, uv_inset);
+    }
+    posed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, Matrix4};
+
+    #[test]
+    fn vertices_are_moved_to_world_space() {
+        let vertices = [TexturedVertex {
+            position: [1.0, 0.0, 0.0],
+            normal: [1.0, 0.0, 0.0],
+            texture: [0.25, 0.5],
+        }; 3];
+        let model = Matrix4::from_translation(Vector3::new(0.0, 2.0, 0.0))
+            * Matrix4::from_angle_z(Deg(90.0))
+            * Matrix4::from_scale(2.0);
+        let posed = pose_vertices(&vertices, model, 0.5, 0.0);
+        let close = |a: [f32; 3], b: [f32; 3]| (0..3).all(|i| (a[i] - b[i]).abs() < 1e-5);
+        // Pushed out to x = 1.5, doubled, turned onto +Y and lifted.
+        assert!(close(posed[0].position, [0.0, 5.0, 0.0]), "{:?}", posed[0].position);
+        // Normals turn with the part but stay unit length.
+        assert!(close(posed[0].normal, [0.0, 1.0, 0.0]), "{:?}", posed[0].normal);
+        assert_eq!(posed[0].texture, [0.25, 0.5]);
+    }
+}
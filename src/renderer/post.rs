@@ -0,0 +1,234 @@
+//! Post-processing composite pass: the skin pass renders to an intermediate color target, then a
+//! fullscreen pass reads it (plus the depth buffer) and writes the output target.
+//!
+//! The emissive overlay adds two inputs — the unlit overlay color and its blurred bloom — built
+//! by [`super::bloom`] before the composite.
+//!
+//! Only used when [`RenderOptions::uses_post_processing`] is true, so renders without post
+//! effects keep drawing straight into the output.
+
+use crate::camera::Camera;
+
+use super::options::{AlphaMode, RenderOptions};
+
+/// Post-effect parameters (group 0, binding 0 of the post shader).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct PostUniforms {
+    /// `x` = enabled (0/1), `y` = focus distance, `z` = max blur radius in pixels.
+    pub dof: [f32; 4],
+    /// `x` = near plane, `y` = far plane of the camera projection.
+    pub projection: [f32; 4],
+    /// `x` = emissive overlay enabled (0/1), `y` = bloom intensity.
+    pub bloom: [f32; 4],
+    /// `x` = tone mapper ([`super::options::ToneMapping::shader_index`]), `y` = skip tone mapping
+    /// (0/1), `z` = premultiplied alpha output (0/1).
+    pub output: [f32; 4],
+}
+
+pub(crate) fn compute_post_uniforms(options: &RenderOptions, camera: &Camera) -> PostUniforms {
+    let (near, far) = Camera::clip_planes(options.fov);
+    let dof = match &options.depth_of_field {
+        Some(d) => [
+            1.0,
+            d.focus_distance
+                .unwrap_or_else(|| camera.orbit_distance_with_fov(options.fov))
+                .max(near),
+            d.blur_strength.max(0.0),
+            0.0,
+        ],
+        None => [0.0; 4],
+    };
+    let bloom = match &options.emissive_overlay {
+        Some(e) => [1.0, e.bloom_intensity.max(0.0), 0.0, 0.0],
+        None => [0.0; 4],
+    };
+    PostUniforms {
+        dof,
+        projection: [near, far, 0.0, 0.0],
+        bloom,
+        output: [
+            options.tone_mapping.shader_index(),
+            0.0,
+            f32::from(u8::from(options.alpha_mode == AlphaMode::Premultiplied)),
+            0.0,
+        ],
+    }
+}
+
+pub(crate) fn create_post_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Post Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: wgpu::BufferSize::new(
+                        std::mem::size_of::<PostUniforms>() as u64,
+                    ),
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 3,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 4,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                },
+                count: None,
+            },
+        ],
+    })
+}
+
+pub(crate) fn create_post_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    uniforms: &wgpu::Buffer,
+    color: &wgpu::TextureView,
+    depth: &wgpu::TextureView,
+    emissive: &wgpu::TextureView,
+    bloom: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Post Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniforms.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(color),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(depth),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(emissive),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::TextureView(bloom),
+            },
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::options::DepthOfField;
+
+    #[test]
+    fn dof_disabled_by_default() {
+        let u = compute_post_uniforms(&RenderOptions::default(), &Camera::new());
+        assert_eq!(u.dof[0], 0.0);
+        assert_eq!(u.bloom[0], 0.0);
+        assert_eq!(u.output, [0.0; 4]);
+    }
+
+    #[test]
+    fn tone_mapping_selects_shader_curve() {
+        use crate::renderer::options::ToneMapping;
+
+        let options = RenderOptions {
+            tone_mapping: ToneMapping::Reinhard,
+            ..RenderOptions::default()
+        };
+        let u = compute_post_uniforms(&options, &Camera::new());
+        assert_eq!(u.output[0], 1.0);
+    }
+
+    #[test]
+    fn premultiplied_alpha_mode_sets_output_flag() {
+        let options = RenderOptions {
+            alpha_mode: AlphaMode::Premultiplied,
+            ..RenderOptions::default()
+        };
+        let u = compute_post_uniforms(&options, &Camera::new());
+        assert_eq!(u.output[2], 1.0);
+    }
+
+    #[test]
+    fn emissive_overlay_sets_bloom_intensity() {
+        use crate::renderer::options::EmissiveOverlay;
+
+        let options = RenderOptions {
+            emissive_overlay: Some(EmissiveOverlay {
+                bloom_intensity: 2.0,
+                ..EmissiveOverlay::default()
+            }),
+            ..RenderOptions::default()
+        };
+        let u = compute_post_uniforms(&options, &Camera::new());
+        assert_eq!(u.bloom[..2], [1.0, 2.0]);
+    }
+
+    #[test]
+    fn dof_focuses_on_orbit_target_by_default() {
+        let options = RenderOptions {
+            depth_of_field: Some(DepthOfField::default()),
+            ..RenderOptions::default()
+        };
+        let camera = Camera {
+            scale: 2.0,
+            ..Camera::new()
+        };
+        let u = compute_post_uniforms(&options, &camera);
+        assert_eq!(u.dof[0], 1.0);
+        assert_eq!(u.dof[1], camera.orbit_distance());
+    }
+
+    #[test]
+    fn dof_explicit_focus_and_negative_blur() {
+        let options = RenderOptions {
+            depth_of_field: Some(DepthOfField {
+                focus_distance: Some(3.0),
+                blur_strength: -1.0,
+            }),
+            ..RenderOptions::default()
+        };
+        let u = compute_post_uniforms(&options, &Camera::new());
+        assert_eq!(u.dof[1], 3.0);
+        assert_eq!(u.dof[2], 0.0);
+    }
+}
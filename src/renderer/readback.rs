@@ -1,13 +1,47 @@
 use crate::error::EidolonError;
 use image::{ImageBuffer, Rgba};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc::TryRecvError;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{Duration, Instant};
+
+/// Pause between device polls while a readback with a timeout waits.
+#[cfg(not(target_arch = "wasm32"))]
+const MAP_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Largest width and height of a render read back at `bytes_per_pixel`: within the device's
+/// texture dimension limit, and small enough that a square image's padded readback buffer fits
+/// `max_buffer_size`.
+pub(crate) fn max_output_side(limits: &wgpu::Limits, bytes_per_pixel: u32) -> u32 {
+    let buffer_side = ((limits.max_buffer_size / bytes_per_pixel as u64) as f64).sqrt() as u64;
+    let mut side = buffer_side.min(limits.max_texture_dimension_2d as u64);
+    let fits = |side: u64| {
+        let row = (side * bytes_per_pixel as u64)
+            .next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64);
+        row * side <= limits.max_buffer_size
+    };
+    while side > 0 && !fits(side) {
+        side -= 1;
+    }
+    side as u32
+}
+
+/// Reject sizes outside `1..=max` on either axis.
+pub(crate) fn validate_size(width: u32, height: u32, max: u32) -> Result<(), EidolonError> {
+    if (1..=max).contains(&width) && (1..=max).contains(&height) {
+        Ok(())
+    } else {
+        Err(EidolonError::InvalidDimensions { width, height, max })
+    }
+}
 
 /// Allocates a mappable buffer sized for row-copy alignment; returns `(buffer, padded_bytes_per_row)`.
 pub(crate) fn create_output_buffer(
     device: &wgpu::Device,
     width: u32,
     height: u32,
+    bytes_per_pixel: u32,
 ) -> Result<(wgpu::Buffer, u32), EidolonError> {
-    let bytes_per_pixel = 4u32;
     let unpadded_bytes_per_row = bytes_per_pixel
         .checked_mul(width)
         .ok_or_else(|| EidolonError::gpu("buffer size overflow: width too large"))?;
@@ -68,21 +102,12 @@ pub(crate) fn map_output_buffer_to_rgba(
     width: u32,
     height: u32,
     padded_bytes_per_row: u32,
+    timeout: Option<Duration>,
 ) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, EidolonError> {
     let bytes_per_pixel = 4u32;
 
     let buffer_slice = output_buffer.slice(..);
-    let (tx, rx) = std::sync::mpsc::channel();
-    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-        // Avoid panicking if the receiver is dropped; just ignore the error.
-        let _ = tx.send(result);
-    });
-    device.poll(wgpu::PollType::Wait).ok();
-    let map_result = rx
-        .recv()
-        .map_err(|e| EidolonError::gpu(format!("failed to receive buffer map result: {e}")))?;
-    map_result
-        .map_err(|e| EidolonError::gpu(format!("buffer map failed: {e:?}")))?;
+    wait_for_map(device, &buffer_slice, timeout)?;
 
     let data = buffer_slice.get_mapped_range();
     let mut img_buf = ImageBuffer::new(width, height);
@@ -105,6 +130,80 @@ pub(crate) fn map_output_buffer_to_rgba(
     Ok(img_buf)
 }
 
+/// Map `buffer_slice` for reading and block until the GPU work writing it is done. With a
+/// `timeout`, give up after that long with [`EidolonError::Timeout`]; the map request is
+/// abandoned and the device may still be busy.
+#[cfg(not(target_arch = "wasm32"))]
+fn wait_for_map(
+    device: &wgpu::Device,
+    buffer_slice: &wgpu::BufferSlice<'_>,
+    timeout: Option<Duration>,
+) -> Result<(), EidolonError> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+        // Avoid panicking if the receiver is dropped; just ignore the error.
+        let _ = tx.send(result);
+    });
+    let map_result = match timeout {
+        None => {
+            device.poll(wgpu::PollType::Wait).ok();
+            rx.recv().map_err(|e| {
+                EidolonError::gpu(format!("failed to receive buffer map result: {e}"))
+            })?
+        }
+        // `PollType::Wait` cannot be interrupted, so poll without blocking until the map
+        // completes or time is up.
+        Some(timeout) => {
+            let deadline = Instant::now() + timeout;
+            loop {
+                device.poll(wgpu::PollType::Poll).ok();
+                match rx.try_recv() {
+                    Ok(result) => break result,
+                    Err(TryRecvError::Disconnected) => {
+                        return Err(EidolonError::gpu("buffer map callback was dropped"))
+                    }
+                    Err(TryRecvError::Empty) if Instant::now() >= deadline => {
+                        return Err(EidolonError::Timeout { after: timeout })
+                    }
+                    Err(TryRecvError::Empty) => std::thread::sleep(MAP_POLL_INTERVAL),
+                }
+            }
+        }
+    };
+    map_result.map_err(|e| EidolonError::gpu(format!("buffer map failed: {e:?}")))
+}
+
+/// Blocking readback of an `Rgba16Float` target as packed `f32` RGBA.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn map_output_buffer_to_f32(
+    device: &wgpu::Device,
+    output_buffer: &wgpu::Buffer,
+    width: u32,
+    height: u32,
+    padded_bytes_per_row: u32,
+    timeout: Option<Duration>,
+) -> Result<Vec<f32>, EidolonError> {
+    let buffer_slice = output_buffer.slice(..);
+    wait_for_map(device, &buffer_slice, timeout)?;
+
+    let data = buffer_slice.get_mapped_range();
+    let row_bytes = (width * 8) as usize;
+    let mut values = Vec::with_capacity((width * height * 4) as usize);
+    for y in 0..height {
+        let row_start = (y * padded_bytes_per_row) as usize;
+        values.extend(
+            data[row_start..row_start + row_bytes]
+                .chunks_exact(2)
+                .map(|b| half::f16::from_le_bytes([b[0], b[1]]).to_f32()),
+        );
+    }
+
+    drop(data);
+    output_buffer.unmap();
+
+    Ok(values)
+}
+
 #[cfg(target_arch = "wasm32")]
 pub(crate) async fn map_output_buffer_to_rgba_async(
     device: &wgpu::Device,
@@ -150,10 +249,37 @@ pub(crate) async fn map_output_buffer_to_rgba_async(
     Ok(img_buf)
 }
 
+/// Convert premultiplied RGBA8 to straight alpha in place.
+pub(crate) fn unpremultiply(image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>) {
+    for pixel in image.pixels_mut() {
+        let a = pixel[3] as u32;
+        if a == 0 || a == 255 {
+            continue;
+        }
+        for c in 0..3 {
+            pixel[c] = ((pixel[c] as u32 * 255 + a / 2) / a).min(255) as u8;
+        }
+    }
+}
+
 #[cfg(all(test, not(target_arch = "wasm32")))]
 mod tests {
     use super::*;
 
+    #[test]
+    fn unpremultiply_restores_straight_color() {
+        let mut image = ImageBuffer::from_raw(3, 1, vec![
+            100, 50, 0, 128, // half-covered
+            10, 20, 30, 255, // opaque: untouched
+            0, 0, 0, 0, // empty: untouched
+        ])
+        .unwrap();
+        unpremultiply(&mut image);
+        assert_eq!(image.get_pixel(0, 0).0, [199, 100, 0, 128]);
+        assert_eq!(image.get_pixel(1, 0).0, [10, 20, 30, 255]);
+        assert_eq!(image.get_pixel(2, 0).0, [0, 0, 0, 0]);
+    }
+
     fn make_device() -> (wgpu::Device, wgpu::Queue) {
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
         let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
@@ -166,10 +292,38 @@ mod tests {
             .expect("No wgpu device")
     }
 
+    #[test]
+    fn max_output_side_respects_both_limits() {
+        let limits = wgpu::Limits::downlevel_defaults();
+        assert_eq!(limits.max_texture_dimension_2d, 2048);
+        assert_eq!(max_output_side(&limits, 4), 2048);
+
+        let small_buffer = wgpu::Limits {
+            max_buffer_size: 1 << 20,
+            ..limits
+        };
+        let side = max_output_side(&small_buffer, 4);
+        assert_eq!(side, 512);
+        assert!(max_output_side(&small_buffer, 8) < side);
+    }
+
+    #[test]
+    fn validate_size_rejects_zero_and_oversized() {
+        assert!(validate_size(1, 2048, 2048).is_ok());
+        for (width, height) in [(0, 10), (10, 0), (2049, 10), (10, u32::MAX)] {
+            let err = validate_size(width, height, 2048).unwrap_err();
+            assert!(matches!(
+                err,
+                EidolonError::InvalidDimensions { width: w, height: h, max: 2048 }
+                    if (w, h) == (width, height)
+            ));
+        }
+    }
+
     #[test]
     fn create_output_buffer_normal_dimensions() {
         let (device, _queue) = make_device();
-        let (buf, padded_bytes_per_row) = create_output_buffer(&device, 256, 256).unwrap();
+        let (buf, padded_bytes_per_row) = create_output_buffer(&device, 256, 256, 4).unwrap();
         // padded_bytes_per_row must be a multiple of COPY_BYTES_PER_ROW_ALIGNMENT (256)
         assert_eq!(padded_bytes_per_row % wgpu::COPY_BYTES_PER_ROW_ALIGNMENT, 0);
         assert!(padded_bytes_per_row >= 256 * 4);
@@ -180,7 +334,7 @@ mod tests {
     fn create_output_buffer_width_overflow() {
         // 4 * u32::MAX overflows → error before touching device
         let (device, _queue) = make_device();
-        let result = create_output_buffer(&device, u32::MAX, 100);
+        let result = create_output_buffer(&device, u32::MAX, 100, 4);
         assert!(result.is_err());
         assert!(result.err().unwrap().to_string().contains("width too large"));
     }
@@ -191,7 +345,7 @@ mod tests {
         // u32::MAX / 4 = 0x3FFFFFFF → 4*0x3FFFFFFF = 0xFFFFFFFC (fits u32).
         // next_multiple_of(256) = 0x100000000 → overflow u32.
         let (device, _queue) = make_device();
-        let result = create_output_buffer(&device, 0x3FFFFFFF, 100);
+        let result = create_output_buffer(&device, 0x3FFFFFFF, 100, 4);
         assert!(result.is_err());
         assert!(result.err().unwrap().to_string().contains("padded row size too large"));
     }
@@ -0,0 +1,204 @@
+//! Segmentation maps ([`super::Renderer::render_segmentation`]): which mesh covers each pixel of
+//! a render, for ML datasets, visibility checks and picking many pixels at once.
+//!
+//! The GPU writes a segment id per pixel (0 = background) into the red channel; ids map to the
+//! same [`PosedSource`]s as [`super::Renderer::posed_geometry`].
+
+use image::{Rgba, RgbaImage};
+
+use crate::attachment::MAX_ATTACHMENTS;
+use crate::character::Bone;
+use crate::skin_layout::Layer;
+
+use super::posed::PosedSource;
+
+/// Bones in `Bone as usize` order.
+const BONES: [Bone; 6] = [
+    Bone::Head,
+    Bone::Body,
+    Bone::RightArm,
+    Bone::LeftArm,
+    Bone::RightLeg,
+    Bone::LeftLeg,
+];
+
+/// Segment id of [`PosedSource::Headwear`]; base layers come before it, then overlays, and
+/// attachments after it.
+const HEADWEAR_ID: u8 = 1 + 2 * BONES.len() as u8;
+
+/// Segment id of [`PosedSource::Cape`], after the last attachment.
+const CAPE_ID: u8 = HEADWEAR_ID + 1 + MAX_ATTACHMENTS as u8;
+
+/// Flat colors of the base layers in `Bone as usize` order; overlays are lighter.
+const PART_COLORS: [[u8; 3]; 6] = [
+    [220, 60, 60],
+    [60, 170, 70],
+    [60, 90, 220],
+    [230, 200, 50],
+    [170, 70, 200],
+    [50, 200, 210],
+];
+
+const HEADWEAR_COLOR: [u8; 3] = [240, 140, 40];
+
+const CAPE_COLOR: [u8; 3] = [140, 30, 60];
+
+/// Segment id the shader writes for `source`.
+pub(crate) fn segment_id(source: PosedSource) -> u8 {
+    match source {
+        PosedSource::Part { bone, layer } => {
+            let layer = match layer {
+                Layer::Base => 0,
+                Layer::Overlay => BONES.len() as u8,
+            };
+            1 + layer + bone as u8
+        }
+        PosedSource::Headwear => HEADWEAR_ID,
+        PosedSource::Attachment(i) => HEADWEAR_ID + 1 + i as u8,
+        PosedSource::Cape => CAPE_ID,
+    }
+}
+
+/// The source of segment `id`, or `None` for the background and unused ids.
+pub(crate) fn segment_source(id: u8) -> Option<PosedSource> {
+    let parts = BONES.len() as u8;
+    match id {
+        0 => None,
+        id if id <= parts => Some(PosedSource::Part {
+            bone: BONES[usize::from(id - 1)],
+            layer: Layer::Base,
+        }),
+        id if id <= 2 * parts => Some(PosedSource::Part {
+            bone: BONES[usize::from(id - 1 - parts)],
+            layer: Layer::Overlay,
+        }),
+        HEADWEAR_ID => Some(PosedSource::Headwear),
+        CAPE_ID => Some(PosedSource::Cape),
+        id if usize::from(id - HEADWEAR_ID - 1) < MAX_ATTACHMENTS => {
+            Some(PosedSource::Attachment(usize::from(id - HEADWEAR_ID - 1)))
+        }
+        _ => None,
+    }
+}
+
+/// Flat color of `source` in [`SegmentationMap::to_image`]; every source has its own.
+pub fn segment_color(source: PosedSource) -> [u8; 3] {
+    match source {
+        PosedSource::Part { bone, layer } => {
+            let color = PART_COLORS[bone as usize];
+            match layer {
+                Layer::Base => color,
+                Layer::Overlay => color.map(|c| c / 2 + 128),
+            }
+        }
+        PosedSource::Headwear => HEADWEAR_COLOR,
+        PosedSource::Attachment(i) => {
+            let i = i as u32;
+            [64 + 37 * i, 192 + 101 * i, 128 + 59 * i].map(|c| (c % 256) as u8)
+        }
+        PosedSource::Cape => CAPE_COLOR,
+    }
+}
+
+/// Which mesh covers each pixel of a render: the nearest one whose texel passed the alpha test,
+/// without blending or anti-aliasing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentationMap {
+    width: u32,
+    height: u32,
+    /// Segment id of each pixel, row by row; 0 is the background.
+    ids: Vec<u8>,
+}
+
+impl SegmentationMap {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn from_ids(width: u32, height: u32, ids: Vec<u8>) -> Self {
+        debug_assert_eq!(ids.len(), (width * height) as usize);
+        Self { width, height, ids }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// What covers pixel `(x, y)`, or `None` for the background and pixels outside the map.
+    pub fn source_at(&self, x: u32, y: u32) -> Option<PosedSource> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        segment_source(self.ids[(y * self.width + x) as usize])
+    }
+
+    /// Number of pixels `source` covers; 0 when it is hidden, culled or behind other meshes.
+    pub fn coverage(&self, source: PosedSource) -> usize {
+        let id = segment_id(source);
+        self.ids.iter().filter(|&&pixel| pixel == id).count()
+    }
+
+    /// The map in flat [`segment_color`]s on a transparent background.
+    pub fn to_image(&self) -> RgbaImage {
+        RgbaImage::from_fn(self.width, self.height, |x, y| match self.source_at(x, y) {
+            Some(source) => {
+                let [r, g, b] = segment_color(source);
+                Rgba([r, g, b, 255])
+            }
+            None => Rgba([0; 4]),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn all_sources() -> Vec<PosedSource> {
+        let parts = [Layer::Base, Layer::Overlay]
+            .into_iter()
+            .flat_map(|layer| BONES.map(|bone| PosedSource::Part { bone, layer }));
+        parts
+            .chain([PosedSource::Headwear])
+            .chain((0..MAX_ATTACHMENTS).map(PosedSource::Attachment))
+            .chain([PosedSource::Cape])
+            .collect()
+    }
+
+    #[test]
+    fn every_source_has_its_own_id_and_color() {
+        let sources = all_sources();
+        let ids: HashSet<_> = sources.iter().map(|&source| segment_id(source)).collect();
+        let colors: HashSet<_> = sources.iter().map(|&source| segment_color(source)).collect();
+        assert_eq!(ids.len(), sources.len());
+        assert_eq!(colors.len(), sources.len());
+        assert!(!ids.contains(&0));
+        for source in sources {
+            assert_eq!(segment_source(segment_id(source)), Some(source));
+        }
+        assert_eq!(segment_source(0), None);
+        assert_eq!(segment_source(u8::MAX), None);
+    }
+
+    #[test]
+    fn maps_count_and_color_their_segments() {
+        let head = PosedSource::Part {
+            bone: Bone::Head,
+            layer: Layer::Base,
+        };
+        let map = SegmentationMap::from_ids(2, 2, vec![0, segment_id(head), segment_id(head), 0]);
+        assert_eq!(map.source_at(1, 0), Some(head));
+        assert_eq!(map.source_at(0, 0), None);
+        assert_eq!(map.source_at(2, 0), None);
+        assert_eq!(map.coverage(head), 2);
+        assert_eq!(map.coverage(PosedSource::Headwear), 0);
+        let image = map.to_image();
+        assert_eq!(image.get_pixel(0, 1).0, {
+            let [r, g, b] = segment_color(head);
+            [r, g, b, 255]
+        });
+        assert_eq!(image.get_pixel(0, 0).0, [0; 4]);
+    }
+}
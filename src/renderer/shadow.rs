@@ -0,0 +1,272 @@
+//! Key-light shadow map: light-space matrix, depth texture, and the shadow-catcher ground quad.
+//!
+//! The light looks at the character from [`Lighting::key_direction`] through an orthographic
+//! frustum sized to [`SCENE_RADIUS`] around the model center, so the whole character and the
+//! ground quad always fit, independent of pose. Depth is written in wgpu's `0..1` range.
+
+use cgmath::{InnerSpace, Matrix4, Point3, Vector3};
+use wgpu::util::DeviceExt;
+
+use crate::camera::Camera;
+use crate::character::Character;
+use crate::lighting::Lighting;
+use crate::model::{Bounds, ModelPart, TexturedVertex};
+
+use super::options::ShadowSettings;
+use super::uniforms::{Uniforms, BODY_PART_COUNT};
+
+pub(crate) const SHADOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Dynamic-offset slot of the ground quad in the per-part uniform buffer (after the body parts).
+pub(crate) const GROUND_SLOT: usize = BODY_PART_COUNT;
+
+/// Half-width of the square ground quad, in model units (the model is 1 unit per 16 pixels).
+const GROUND_HALF_EXTENT: f32 = 1.5;
+
+/// Radius (model units) of a sphere around `(0, 1, 0)` enclosing the character and ground quad.
+const SCENE_RADIUS: f32 = 2.4;
+
+/// Smallest accepted shadow-map resolution.
+const MIN_RESOLUTION: u32 = 16;
+
+/// cgmath produces OpenGL clip space (`z` in `-1..1`); remap to wgpu's `0..1`.
+#[rustfmt::skip]
+const OPENGL_TO_WGPU: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+/// Light-space transform and shadow parameters (group 0, binding 3).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ShadowUniforms {
+    pub light_view_proj: [[f32; 4]; 4],
+    /// `x` = enabled (0/1), `y` = depth bias, `z` = shadow-map texel size, `w` = ground opacity.
+    pub params: [f32; 4],
+}
+
+/// Orthographic view-projection of the key light, centered on the character.
+fn light_view_proj(character: &Character, camera: &Camera, lighting: &Lighting) -> Matrix4<f32> {
+    let direction = Vector3::from(lighting.key_direction);
+    let direction = if direction.magnitude2() > 1e-8 {
+        direction.normalize()
+    } else {
+        Vector3::unit_y()
+    };
+    let radius = SCENE_RADIUS * camera.scale;
+    let center = Point3::new(0.0, camera.scale, 0.0) + character.position;
+    let eye = center + direction * (2.0 * radius);
+    // look_at degenerates when the light is straight above or below.
+    let up = if direction.y.abs() > 0.99 {
+        Vector3::unit_z()
+    } else {
+        Vector3::unit_y()
+    };
+    let view = Matrix4::look_at_rh(eye, center, up);
+    let proj = cgmath::ortho(-radius, radius, -radius, radius, radius, 3.0 * radius);
+    OPENGL_TO_WGPU * proj * view
+}
+
+pub(crate) fn compute_shadow_uniforms(
+    character: &Character,
+    camera: &Camera,
+    lighting: &Lighting,
+    settings: Option<&ShadowSettings>,
+    resolution: u32,
+) -> ShadowUniforms {
+    match settings {
+        Some(s) => ShadowUniforms {
+            light_view_proj: light_view_proj(character, camera, lighting).into(),
+            params: [1.0, s.bias, 1.0 / resolution as f32, s.ground_opacity],
+        },
+        None => ShadowUniforms {
+            light_view_proj: Matrix4::from_scale(1.0).into(),
+            params: [0.0; 4],
+        },
+    }
+}
+
+/// Uniforms for the ground quad: level with the feet, follows position and scale but not the
+/// character's rotation.
+pub(crate) fn compute_ground_uniforms(
+    character: &Character,
+    camera: &Camera,
+    fov: f32,
+    width: u32,
+    height: u32,
+) -> Uniforms {
+    let model =
+        Matrix4::from_translation(character.position) * Matrix4::from_scale(camera.scale);
+    Uniforms {
+        perspective: camera.projection_matrix_with_fov(width, height, fov),
+        view: camera.view_matrix_with_fov(fov),
+        model: model.into(),
+        offset: 0.0,
+        part: GROUND_SLOT as u32,
+        glint: 0.0,
+        alpha_cutoff: 0.0,
+        cutout: 0,
+        segment: 0,
+        _padding: [0; 2],
+    }
+}
+
+/// Clamp a requested resolution to what the device can allocate.
+pub(crate) fn clamp_resolution(requested: u32, max_texture_dimension: u32) -> u32 {
+    requested.clamp(MIN_RESOLUTION, max_texture_dimension.max(MIN_RESOLUTION))
+}
+
+/// Two triangles spanning the ground quad at `y = 0`; texture coordinates run `0..1` across it.
+pub(crate) fn create_ground_mesh(device: &wgpu::Device) -> ModelPart {
+    let e = GROUND_HALF_EXTENT;
+    let corner = |x: f32, z: f32| TexturedVertex {
+        position: [x * e, 0.0, z * e],
+        normal: [0.0, 1.0, 0.0],
+        texture: [(x + 1.0) * 0.5, (z + 1.0) * 0.5],
+    };
+    let vertices = [
+        corner(-1.0, -1.0),
+        corner(-1.0, 1.0),
+        corner(1.0, 1.0),
+        corner(-1.0, -1.0),
+        corner(1.0, 1.0),
+        corner(1.0, -1.0),
+    ];
+    let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Ground Vertex Buffer"),
+        contents: bytemuck::cast_slice(&vertices),
+        usage: wgpu::BufferUsages::VERTEX,
+    });
+    ModelPart {
+        vertex_buffer,
+        vertex_count: vertices.len() as u32,
+        bounds: Bounds::from_vertices(&vertices),
+        vertices: vertices.to_vec(),
+    }
+}
+
+/// Shadow-map depth texture plus the group-2 bind group that samples it.
+pub(crate) struct ShadowMap {
+    pub view: wgpu::TextureView,
+    pub bind_group: wgpu::BindGroup,
+    pub resolution: u32,
+}
+
+impl ShadowMap {
+    pub(crate) fn new(
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+        sampler: &wgpu::Sampler,
+        resolution: u32,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Map"),
+            size: wgpu::Extent3d {
+                width: resolution,
+                height: resolution,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SHADOW_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Shadow Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+        Self {
+            view,
+            bind_group,
+            resolution,
+        }
+    }
+
+    /// Bytes of the depth texture.
+    pub(crate) fn bytes(&self) -> u64 {
+        super::memory::target_bytes(SHADOW_FORMAT, self.resolution, self.resolution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Vector4;
+
+    fn project(m: Matrix4<f32>, p: [f32; 3]) -> Vector4<f32> {
+        let v = m * Vector4::new(p[0], p[1], p[2], 1.0);
+        v / v.w
+    }
+
+    #[test]
+    fn light_frustum_contains_character_and_ground() {
+        let lighting = Lighting::default();
+        let m = light_view_proj(&Character::new(), &Camera::new(), &lighting);
+        let e = GROUND_HALF_EXTENT;
+        for p in [
+            [0.0, 2.0, 0.0],
+            [0.5, 1.5, 0.25],
+            [e, 0.0, e],
+            [-e, 0.0, -e],
+            [e, 0.0, -e],
+        ] {
+            let ndc = project(m, p);
+            assert!(ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0, "{:?} outside xy", p);
+            assert!((0.0..=1.0).contains(&ndc.z), "{:?} depth {} outside 0..1", p, ndc.z);
+        }
+    }
+
+    #[test]
+    fn closer_to_light_has_smaller_depth() {
+        let lighting = Lighting {
+            key_direction: [0.0, 1.0, 0.0],
+            ..Lighting::default()
+        };
+        let m = light_view_proj(&Character::new(), &Camera::new(), &lighting);
+        assert!(project(m, [0.0, 2.0, 0.0]).z < project(m, [0.0, 0.0, 0.0]).z);
+    }
+
+    #[test]
+    fn zero_key_direction_stays_finite() {
+        let lighting = Lighting {
+            key_direction: [0.0; 3],
+            ..Lighting::default()
+        };
+        let m: [[f32; 4]; 4] = light_view_proj(&Character::new(), &Camera::new(), &lighting).into();
+        assert!(m.iter().flatten().all(|v| v.is_finite()));
+    }
+
+    #[test]
+    fn disabled_shadow_uniforms_are_off() {
+        let u = compute_shadow_uniforms(
+            &Character::new(),
+            &Camera::new(),
+            &Lighting::default(),
+            None,
+            1,
+        );
+        assert_eq!(u.params[0], 0.0);
+    }
+
+    #[test]
+    fn resolution_is_clamped() {
+        assert_eq!(clamp_resolution(0, 8192), MIN_RESOLUTION);
+        assert_eq!(clamp_resolution(1024, 8192), 1024);
+        assert_eq!(clamp_resolution(100_000, 8192), 8192);
+    }
+}
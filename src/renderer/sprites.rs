@@ -0,0 +1,216 @@
+//! Billboarded sprites drawn after the character: flames and potion swirls of
+//! [`StatusEffects`], and the particles of [`super::Particles`].
+//!
+//! Sprites are camera-facing quads whose pixel-art pattern is generated in the sprite shader
+//! ([`crate::constants::SPRITE_SHADER`]), so no sprite textures ship with the crate. One instanced
+//! draw covers every sprite of a frame; they are depth-tested against the character but do not
+//! write depth, so overlapping sprites blend.
+
+use cgmath::{EuclideanSpace, InnerSpace, Matrix4, Point3, Transform, Vector3};
+
+use crate::animation::splitmix64;
+use crate::camera::Camera;
+use crate::character::Character;
+
+use super::options::StatusEffects;
+use super::uniforms::character_matrix;
+
+/// Pattern drawn on a sprite; the discriminant is the shader's `params.x`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SpriteKind {
+    /// 16×16 fire, scrolling upwards.
+    Flame = 0,
+    /// 8×8 potion swirl that shrinks, turns and fades with age.
+    Swirl = 1,
+    /// 8×8 love heart.
+    Heart = 2,
+    /// 5×5 crit spark that shrinks to a dot with age.
+    Spark = 3,
+    /// 6×6 enchanting-table glyph picked by the seed.
+    Glyph = 4,
+}
+
+/// Per-instance vertex data of the sprite pipeline (vertex buffer slot 0).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct SpriteInstance {
+    /// World-space center in `xyz`, width (= height) in `w`.
+    pub center: [f32; 4],
+    /// Tint, straight alpha.
+    pub color: [f32; 4],
+    /// `x` = [`SpriteKind`], `y` = seed, `z` = age (`0–1` of its lifetime), `w` unused.
+    pub params: [f32; 4],
+}
+
+impl SpriteInstance {
+    pub(crate) fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 3] = wgpu::vertex_attr_array![
+            0 => Float32x4,
+            1 => Float32x4,
+            2 => Float32x4,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<SpriteInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// Camera of the sprite pass (group 0, binding 0).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct SpriteUniforms {
+    pub view_proj: [[f32; 4]; 4],
+    /// Camera right in world space in `xyz`; effect time in seconds in `w`.
+    pub right: [f32; 4],
+    /// Camera up in world space in `xyz`.
+    pub up: [f32; 4],
+}
+
+pub(crate) fn compute_sprite_uniforms(
+    camera: &Camera,
+    fov: f32,
+    width: u32,
+    height: u32,
+    time: f32,
+) -> SpriteUniforms {
+    let view = Matrix4::from(camera.view_matrix_with_fov(fov));
+    let perspective = Matrix4::from(camera.projection_matrix_with_fov(width, height, fov));
+    // The rows of the view rotation are the camera axes in world space.
+    SpriteUniforms {
+        view_proj: (perspective * view).into(),
+        right: [view.x.x, view.y.x, view.z.x, time],
+        up: [view.x.y, view.y.y, view.z.y, 0.0],
+    }
+}
+
+pub(crate) fn create_sprite_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Sprite Bind Group Layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: wgpu::BufferSize::new(
+                    std::mem::size_of::<SpriteUniforms>() as u64
+                ),
+            },
+            count: None,
+        }],
+    })
+}
+
+/// Flame layers in character space: center height, size, and how far they sit in front of the
+/// character towards the camera (the game draws its fire quads the same way).
+const FLAMES: [(f32, f32, f32); 3] = [(0.55, 1.1, 0.4), (1.25, 1.0, 0.38), (1.9, 0.85, 0.36)];
+
+/// Potion swirls alive at any time, their size, and how far they rise over their life.
+const SWIRL_COUNT: u64 = 12;
+const SWIRL_SIZE: f32 = 0.25;
+const SWIRL_RISE: f32 = 0.3;
+
+/// Sprites of `status` around `character` as seen by `camera`, in draw order.
+pub(crate) fn status_sprites(
+    status: &StatusEffects,
+    character: &Character,
+    camera: &Camera,
+    fov: f32,
+) -> Vec<SpriteInstance> {
+    let model = character_matrix(character, camera);
+    let eye = camera.eye_position(fov);
+    let place = |local: Vector3<f32>, toward_eye: f32| -> Point3<f32> {
+        let world = model.transform_point(Point3::from_vec(local));
+        let to_eye = eye - world;
+        if to_eye.magnitude2() > 1e-8 {
+            world + to_eye.normalize() * toward_eye * camera.scale
+        } else {
+            world
+        }
+    };
+    let mut sprites = Vec::new();
+
+    if let Some([r, g, b]) = status.potion {
+        let unit = |bits: u64| (bits & 0xffff) as f32 / 65535.0;
+        for i in 0..SWIRL_COUNT {
+            let random = splitmix64(i);
+            let age = (status.time + i as f32 / SWIRL_COUNT as f32).rem_euclid(1.0);
+            let local = Vector3::new(
+                (unit(random) - 0.5) * 1.4,
+                0.2 + unit(random >> 16) * 1.6 + age * SWIRL_RISE,
+                (unit(random >> 32) - 0.5) * 0.8,
+            );
+            let center = place(local, 0.0);
+            sprites.push(SpriteInstance {
+                center: [center.x, center.y, center.z, SWIRL_SIZE * camera.scale],
+                color: [r, g, b, 1.0],
+                params: [SpriteKind::Swirl as u32 as f32, i as f32, age, 0.0],
+            });
+        }
+    }
+
+    if status.on_fire {
+        for (i, (height, size, toward_eye)) in FLAMES.into_iter().enumerate() {
+            let center = place(Vector3::new(0.0, height, 0.0), toward_eye);
+            sprites.push(SpriteInstance {
+                center: [center.x, center.y, center.z, size * camera.scale],
+                color: [1.0; 4],
+                params: [SpriteKind::Flame as u32 as f32, i as f32, 0.0, 0.0],
+            });
+        }
+    }
+    sprites
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::DEFAULT_FOV;
+
+    #[test]
+    fn no_effects_no_sprites() {
+        let sprites = status_sprites(&StatusEffects::default(), &Character::new(), &Camera::new(), DEFAULT_FOV);
+        assert!(sprites.is_empty());
+    }
+
+    #[test]
+    fn flames_sit_in_front_of_the_character() {
+        let status = StatusEffects {
+            on_fire: true,
+            ..StatusEffects::default()
+        };
+        let camera = Camera::new();
+        let sprites = status_sprites(&status, &Character::new(), &camera, DEFAULT_FOV);
+        assert_eq!(sprites.len(), FLAMES.len());
+        // Seen from above, every flame is closer to the eye than the character's axis is.
+        let eye = camera.eye_position(DEFAULT_FOV);
+        for sprite in &sprites {
+            let center = Point3::new(sprite.center[0], 0.0, sprite.center[2]);
+            let eye = Point3::new(eye.x, 0.0, eye.z);
+            assert!((eye - center).magnitude() < eye.to_vec().magnitude());
+        }
+    }
+
+    #[test]
+    fn swirls_loop_every_second() {
+        let at = |time| {
+            let status = StatusEffects {
+                potion: Some([0.5, 0.2, 0.8]),
+                time,
+                ..StatusEffects::default()
+            };
+            status_sprites(&status, &Character::new(), &Camera::new(), DEFAULT_FOV)
+        };
+        let start = at(0.25);
+        assert_eq!(start.len(), SWIRL_COUNT as usize);
+        assert_eq!(start[0].color, [0.5, 0.2, 0.8, 1.0]);
+        let later = at(1.25);
+        for (a, b) in start.iter().zip(&later) {
+            assert!((a.params[2] - b.params[2]).abs() < 1e-5);
+            assert!((a.center[1] - b.center[1]).abs() < 1e-4);
+        }
+        assert_ne!(at(0.5)[0].center, start[0].center);
+    }
+}
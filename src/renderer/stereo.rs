@@ -0,0 +1,129 @@
+//! Stereo renders: a left- and right-eye view of the same scene, combined side by side (VR and
+//! parallel viewing) or as a red–cyan anaglyph ([`super::Renderer::render_stereo`]).
+
+#[cfg(not(target_arch = "wasm32"))]
+use image::{Rgba, RgbaImage};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::camera::Camera;
+
+/// How [`super::Renderer::render_stereo`] combines the two eye views.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StereoLayout {
+    /// Left eye in the left half, right eye in the right half; the image is twice as wide.
+    #[default]
+    SideBySide,
+    /// Red channel from the left eye, green and blue from the right (red–cyan glasses).
+    Anaglyph,
+}
+
+/// Stereo parameters for [`super::Renderer::render_stereo`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StereoSettings {
+    /// Distance between the two eyes, in world units (the character is about 2 units tall).
+    /// Larger values exaggerate depth.
+    pub eye_separation: f32,
+    /// How the two views are combined.
+    pub layout: StereoLayout,
+}
+
+impl Default for StereoSettings {
+    fn default() -> Self {
+        Self {
+            eye_separation: 0.1,
+            layout: StereoLayout::SideBySide,
+        }
+    }
+}
+
+/// Left- and right-eye cameras for `camera`: both keep looking at the orbit target, rotated
+/// sideways so the eyes sit `eye_separation` apart (toe-in stereo).
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn eye_cameras(camera: &Camera, fov: f32, eye_separation: f32) -> [Camera; 2] {
+    // Yaw moves the eye along the horizontal orbit circle, whose radius shrinks with pitch.
+    let radius =
+        camera.orbit_distance_with_fov(fov) * (camera.pitch - 90.0).to_radians().cos().abs();
+    let half_angle = (eye_separation * 0.5).atan2(radius.max(1e-3)).to_degrees();
+    // Increasing yaw moves the eye towards the viewer's right.
+    [
+        Camera {
+            yaw: camera.yaw - half_angle,
+            ..*camera
+        },
+        Camera {
+            yaw: camera.yaw + half_angle,
+            ..*camera
+        },
+    ]
+}
+
+/// Combine straight-alpha eye views of equal size.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn combine(left: &RgbaImage, right: &RgbaImage, layout: StereoLayout) -> RgbaImage {
+    let (width, height) = left.dimensions();
+    match layout {
+        StereoLayout::SideBySide => {
+            let mut out = RgbaImage::new(width * 2, height);
+            for (x, y, p) in left.enumerate_pixels() {
+                out.put_pixel(x, y, *p);
+                out.put_pixel(x + width, y, *right.get_pixel(x, y));
+            }
+            out
+        }
+        StereoLayout::Anaglyph => RgbaImage::from_fn(width, height, |x, y| {
+            let (l, r) = (left.get_pixel(x, y), right.get_pixel(x, y));
+            Rgba([l[0], r[1], r[2], l[3].max(r[3])])
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::DEFAULT_FOV;
+
+    #[test]
+    fn eyes_straddle_the_camera() {
+        let camera = Camera::new();
+        let [left, right] = eye_cameras(&camera, DEFAULT_FOV, 0.2);
+        assert!(left.yaw < camera.yaw && right.yaw > camera.yaw);
+        assert!(((left.yaw + right.yaw) * 0.5 - camera.yaw).abs() < 1e-4);
+        assert_eq!(left.pitch, camera.pitch);
+        assert_eq!(left.scale, camera.scale);
+    }
+
+    #[test]
+    fn zero_separation_is_mono() {
+        let [left, right] = eye_cameras(&Camera::new(), DEFAULT_FOV, 0.0);
+        assert_eq!(left.yaw, right.yaw);
+    }
+
+    #[test]
+    fn closer_cameras_turn_further() {
+        let near = Camera {
+            scale: 2.0,
+            ..Camera::new()
+        };
+        let [far_left, _] = eye_cameras(&Camera::new(), DEFAULT_FOV, 0.2);
+        let [near_left, _] = eye_cameras(&near, DEFAULT_FOV, 0.2);
+        assert!(near_left.yaw < far_left.yaw);
+    }
+
+    #[test]
+    fn side_by_side_doubles_width() {
+        let left = RgbaImage::from_pixel(2, 1, Rgba([255, 0, 0, 255]));
+        let right = RgbaImage::from_pixel(2, 1, Rgba([0, 0, 255, 255]));
+        let out = combine(&left, &right, StereoLayout::SideBySide);
+        assert_eq!(out.dimensions(), (4, 1));
+        assert_eq!(out.get_pixel(1, 0).0, [255, 0, 0, 255]);
+        assert_eq!(out.get_pixel(2, 0).0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn anaglyph_takes_red_from_left_eye() {
+        let left = RgbaImage::from_pixel(1, 1, Rgba([200, 10, 20, 255]));
+        let right = RgbaImage::from_pixel(1, 1, Rgba([30, 40, 50, 0]));
+        let out = combine(&left, &right, StereoLayout::Anaglyph);
+        assert_eq!(out.get_pixel(0, 0).0, [200, 40, 50, 255]);
+    }
+}
@@ -11,15 +11,22 @@
 use cgmath::{Matrix4, Rad, Vector3};
 
 use crate::camera::Camera;
-use crate::character::Character;
+use crate::cape;
+use crate::character::{Bone, Character};
+use crate::lighting::Lighting;
 use crate::model::{BodyPart, Model};
+use crate::skin_layout::Layer;
+
+use super::options::{EnchantmentGlint, RenderOptions};
+use super::posed::PosedSource;
+use super::segmentation::segment_id;
 
 /// Number of body parts in the model and draw loop.
 pub(crate) const BODY_PART_COUNT: usize = 6;
 
 /// Identifies a body part in [`PART_CONFIGS`] — single source of truth
 /// for mapping config entries to [`Model`] fields.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub(crate) enum PartId {
     Head,
     RightArm,
@@ -36,36 +43,108 @@ pub(crate) struct Uniforms {
     pub view: [[f32; 4]; 4],
     pub model: [[f32; 4]; 4],
     pub offset: f32,
-    pub _padding: [f32; 3],
+    /// Index into [`PART_CONFIGS`]; lets the shader tell parts apart.
+    pub part: u32,
+    /// `1.0` when the part's bone is in [`super::EnchantmentGlint::bones`].
+    pub glint: f32,
+    /// Texels with alpha below this are discarded ([`super::AlphaTest::cutoff`]).
+    pub alpha_cutoff: f32,
+    /// `1` draws texels that pass the cutoff opaque ([`super::LayerAlpha::Cutout`]).
+    pub cutout: u32,
+    /// Segmentation id of the mesh drawn ([`super::segmentation::segment_id`]).
+    pub segment: u32,
+    pub _padding: [u32; 2],
+}
+
+impl Uniforms {
+    /// These uniforms with the alpha test of [`super::AlphaTest::layer`].
+    pub fn with_alpha(self, (alpha_cutoff, cutout): (f32, u32)) -> Self {
+        Self {
+            alpha_cutoff,
+            cutout,
+            ..self
+        }
+    }
+
+    /// These uniforms drawing the mesh of `source`.
+    pub fn with_segment(self, source: PosedSource) -> Self {
+        Self {
+            segment: segment_id(source).into(),
+            ..self
+        }
+    }
+}
+
+/// Scene-wide light rig, uploaded once per render pass (group 0, binding 1).
+///
+/// Every field is a `vec4` on the WGSL side; `w` is unused padding except in `rim`.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct LightUniforms {
+    pub key_direction: [f32; 4],
+    pub key_color: [f32; 4],
+    pub fill_direction: [f32; 4],
+    pub fill_color: [f32; 4],
+    pub ambient: [f32; 4],
+    /// Rim color in `xyz`, strength in `w` (0 = off).
+    pub rim: [f32; 4],
+}
+
+impl From<&Lighting> for LightUniforms {
+    fn from(lighting: &Lighting) -> Self {
+        let pad = |v: [f32; 3]| [v[0], v[1], v[2], 0.0];
+        Self {
+            key_direction: pad(lighting.key_direction),
+            key_color: pad(lighting.key_color),
+            fill_direction: pad(lighting.fill_direction),
+            fill_color: pad(lighting.fill_color),
+            ambient: pad(lighting.ambient),
+            rim: [
+                lighting.rim_color[0],
+                lighting.rim_color[1],
+                lighting.rim_color[2],
+                lighting.rim_strength,
+            ],
+        }
+    }
 }
 
+/// Half the torso's width (8 pixels of 1/16 unit); arms move out by this times
+/// [`Character::chest_expansion`].
+const BODY_HALF_WIDTH: f32 = 0.25;
+
 /// Canonical body part order — defines pivot, layer offset, and which model
 /// field each entry targets. Both uniform computation and the render pass
 /// draw loop consume this array, guaranteeing they stay in sync.
 ///
-/// Pivot points are joint positions from the Blockbench OBJ model.
+/// Pivot points are the joint positions of the Blockbench OBJ model ([`Bone::joint`]).
 /// Coordinate system: Y-up, character centered at X=0 Z=0, feet at Y≈0,
 /// head top at Y≈2.
 pub(crate) const PART_CONFIGS: &[(Vector3<f32>, f32, PartId); BODY_PART_COUNT] = &[
     // Head: pivot at neck joint
-    (Vector3::new(0.0, 1.5, 0.0), 0.0, PartId::Head),
+    (pivot(Bone::Head), 0.0, PartId::Head),
     // Right Arm: pivot at right shoulder joint
-    (Vector3::new(0.3125, 1.375, 0.0), 0.0, PartId::RightArm),
+    (pivot(Bone::RightArm), 0.0, PartId::RightArm),
     // Left Arm: pivot at left shoulder joint (X mirror)
-    (Vector3::new(-0.3125, 1.375, 0.0), 0.0, PartId::LeftArm),
+    (pivot(Bone::LeftArm), 0.0, PartId::LeftArm),
     // Right Leg: pivot at right hip joint
-    (Vector3::new(0.125, 0.75, 0.0), 0.0, PartId::RightLeg),
+    (pivot(Bone::RightLeg), 0.0, PartId::RightLeg),
     // Left Leg: pivot at left hip joint (X mirror)
-    (Vector3::new(-0.125, 0.75, 0.0), 0.0, PartId::LeftLeg),
+    (pivot(Bone::LeftLeg), 0.0, PartId::LeftLeg),
     // Body: root — no pivot. Offset 0.0001 prevents Z-fighting with jacket layer.
-    (Vector3::new(0.0, 0.0, 0.0), 0.0001, PartId::Body),
+    (pivot(Bone::Body), 0.0001, PartId::Body),
 ];
 
+const fn pivot(bone: Bone) -> Vector3<f32> {
+    let [x, y, z] = bone.joint();
+    Vector3::new(x, y, z)
+}
+
 /// Map a [`PART_CONFIGS`] index to the corresponding [`BodyPart`] in the model.
 ///
 /// The mapping is derived from the `PartId` in PART_CONFIGS — no
 /// separate hardcoded order to maintain.
-pub(crate) fn body_part_ref<'a>(i: usize, model: &'a Model) -> &'a BodyPart {
+pub(crate) fn body_part_ref(i: usize, model: &Model) -> &BodyPart {
     match PART_CONFIGS[i].2 {
         PartId::Head => &model.head,
         PartId::RightArm => &model.right_arm,
@@ -76,36 +155,83 @@ pub(crate) fn body_part_ref<'a>(i: usize, model: &'a Model) -> &'a BodyPart {
     }
 }
 
-pub(crate) fn compute_body_part_uniforms(
-    character: &Character,
-    camera: &Camera,
-    width: u32,
-    height: u32,
-) -> [Uniforms; BODY_PART_COUNT] {
-    let perspective: [[f32; 4]; 4] = camera.get_projection_matrix(width, height);
-    let view: [[f32; 4]; 4] = camera.get_view_matrix();
+/// The bone of [`PART_CONFIGS`] entry `i`.
+pub(crate) fn part_bone(i: usize) -> Bone {
+    match PART_CONFIGS[i].2 {
+        PartId::Head => Bone::Head,
+        PartId::RightArm => Bone::RightArm,
+        PartId::LeftArm => Bone::LeftArm,
+        PartId::RightLeg => Bone::RightLeg,
+        PartId::LeftLeg => Bone::LeftLeg,
+        PartId::Body => Bone::Body,
+    }
+}
+
+/// [`PART_CONFIGS`] index of `bone`, e.g. into [`part_model_matrices`].
+pub(crate) fn bone_index(bone: Bone) -> usize {
+    let id = match bone {
+        Bone::Head => PartId::Head,
+        Bone::Body => PartId::Body,
+        Bone::RightArm => PartId::RightArm,
+        Bone::LeftArm => PartId::LeftArm,
+        Bone::RightLeg => PartId::RightLeg,
+        Bone::LeftLeg => PartId::LeftLeg,
+    };
+    PART_CONFIGS
+        .iter()
+        .position(|config| config.2 == id)
+        .expect("every part is in PART_CONFIGS")
+}
+
+/// The main and overlay meshes of each part that are drawn at all: those in
+/// [`Character::visibility`], less overlays under attachments with
+/// [`crate::attachment::AttachmentFlags::hide_overlay`].
+pub(crate) fn shown_layers(character: &Character) -> [[bool; 2]; BODY_PART_COUNT] {
+    let mut shown: [[bool; 2]; BODY_PART_COUNT] = std::array::from_fn(|i| {
+        [Layer::Base, Layer::Overlay].map(|layer| character.visibility.is_shown(part_bone(i), layer))
+    });
+    for attachment in &character.attachments {
+        if attachment.flags.hide_overlay {
+            shown[bone_index(attachment.bone)][1] = false;
+        }
+    }
+    shown
+}
+
+/// Flag the parts of the glinting bones in `uniforms`.
+pub(crate) fn apply_glint(uniforms: &mut [Uniforms; BODY_PART_COUNT], glint: &EnchantmentGlint) {
+    for &bone in &glint.bones {
+        uniforms[bone_index(bone)].glint = 1.0;
+    }
+}
 
+/// Base transform of the whole character: `translate(position) × rotate(rotation) ×
+/// scale(camera.scale)`.
+pub(crate) fn character_matrix(character: &Character, camera: &Camera) -> Matrix4<f32> {
     let translation = Matrix4::from_translation(character.position);
     let rotation_matrix = Matrix4::from_angle_x(Rad(character.rotation.x.to_radians()))
         * Matrix4::from_angle_y(Rad(character.rotation.y.to_radians()))
         * Matrix4::from_angle_z(Rad(character.rotation.z.to_radians()));
-    let scale = Matrix4::from_scale(camera.scale);
-    let base_model_matrix = translation * rotation_matrix * scale;
+    translation * rotation_matrix * Matrix4::from_scale(camera.scale)
+}
 
-    let posture = &character.posture;
+/// World-space model matrix of every body part, in [`PART_CONFIGS`] order.
+///
+/// Base transform is `translate(position) × rotate(rotation) × scale(camera.scale)`; limbs add
+/// `translate(pivot) × joint rotation × translate(-pivot)` on top. The chest expansion scales the
+/// body in X and Z and shifts the arms sideways.
+pub(crate) fn part_model_matrices(
+    character: &Character,
+    camera: &Camera,
+) -> [Matrix4<f32>; BODY_PART_COUNT] {
+    let base_model_matrix = character_matrix(character, camera);
 
-    let make_uniforms = |model_matrix: Matrix4<f32>, offset: f32| -> Uniforms {
-        Uniforms {
-            perspective,
-            view,
-            model: model_matrix.into(),
-            offset,
-            _padding: [0.0; 3],
-        }
-    };
+    let posture = &character.posture;
+    let expansion = character.chest_expansion;
+    let chest = Matrix4::from_nonuniform_scale(1.0 + expansion, 1.0, 1.0 + expansion);
 
     std::array::from_fn(|i| {
-        let (pivot, offset, _part_id) = PART_CONFIGS[i];
+        let (pivot, _offset, _part_id) = PART_CONFIGS[i];
         let rotation = match i {
             0 => // Head: yaw(Y) then pitch(X)
                 Matrix4::from_angle_y(Rad(posture.head_yaw.to_radians()))
@@ -126,15 +252,240 @@ pub(crate) fn compute_body_part_uniforms(
         };
 
         // Body (index 5) has no pivot — applies base transform directly
-        let transform = if i == 5 {
+        if i == 5 {
+            base_model_matrix * chest
+        } else if matches!(i, 1 | 2) {
+            // Arms ride on the expanding chest; the shoulders are half a torso width out.
             base_model_matrix
+                * Matrix4::from_translation(Vector3::new(
+                    pivot.x.signum() * BODY_HALF_WIDTH * expansion,
+                    0.0,
+                    0.0,
+                ))
+                * Matrix4::from_translation(pivot)
+                * rotation
+                * Matrix4::from_translation(-pivot)
         } else {
             base_model_matrix
                 * Matrix4::from_translation(pivot)
                 * rotation
                 * Matrix4::from_translation(-pivot)
-        };
+        }
+    })
+}
 
-        make_uniforms(transform, offset)
+pub(crate) fn compute_body_part_uniforms(
+    character: &Character,
+    camera: &Camera,
+    fov: f32,
+    width: u32,
+    height: u32,
+) -> [Uniforms; BODY_PART_COUNT] {
+    let perspective: [[f32; 4]; 4] = camera.projection_matrix_with_fov(width, height, fov);
+    let view: [[f32; 4]; 4] = camera.view_matrix_with_fov(fov);
+    let matrices = part_model_matrices(character, camera);
+
+    std::array::from_fn(|i| Uniforms {
+        perspective,
+        view,
+        model: matrices[i].into(),
+        offset: PART_CONFIGS[i].1,
+        part: i as u32,
+        glint: 0.0,
+        alpha_cutoff: 0.0,
+        cutout: 0,
+        segment: 0,
+        _padding: [0; 2],
+    })
+}
+
+/// Uniforms of each of the character's attachments: the model matrix of its bone times its
+/// transform. `part` is the bone's, so ambient occlusion treats the attachment as part of it.
+pub(crate) fn compute_attachment_uniforms(
+    character: &Character,
+    camera: &Camera,
+    fov: f32,
+    width: u32,
+    height: u32,
+) -> Vec<Uniforms> {
+    let perspective: [[f32; 4]; 4] = camera.projection_matrix_with_fov(width, height, fov);
+    let view: [[f32; 4]; 4] = camera.view_matrix_with_fov(fov);
+    let matrices = part_model_matrices(character, camera);
+    character
+        .attachments
+        .iter()
+        .map(|attachment| {
+            let part = bone_index(attachment.bone);
+            Uniforms {
+                perspective,
+                view,
+                model: (matrices[part] * attachment.transform.matrix()).into(),
+                offset: 0.0,
+                part: part as u32,
+                glint: 0.0,
+                alpha_cutoff: 0.0,
+                cutout: 0,
+                segment: 0,
+                _padding: [0; 2],
+            }
+        })
+        .collect()
+}
+
+/// World-space model matrix of the character's cape, if it wears one: the body's, with the cape
+/// swung about its hinge.
+pub(crate) fn cape_model_matrix(character: &Character, camera: &Camera) -> Option<Matrix4<f32>> {
+    let cape = character.equipment.cape.as_ref()?;
+    let body = part_model_matrices(character, camera)[bone_index(Bone::Body)];
+    Some(body * cape::hinge_matrix(cape.angle))
+}
+
+/// Uniforms of the character's cape, if it wears one. It counts as part of the body for the
+/// shader.
+pub(crate) fn compute_cape_uniforms(
+    character: &Character,
+    camera: &Camera,
+    fov: f32,
+    width: u32,
+    height: u32,
+) -> Option<Uniforms> {
+    let model = cape_model_matrix(character, camera)?;
+    Some(Uniforms {
+        perspective: camera.projection_matrix_with_fov(width, height, fov),
+        view: camera.view_matrix_with_fov(fov),
+        model: model.into(),
+        offset: 0.0,
+        part: bone_index(Bone::Body) as u32,
+        glint: 0.0,
+        alpha_cutoff: 0.0,
+        cutout: 0,
+        segment: 0,
+        _padding: [0; 2],
+    })
+}
+
+/// Per-part uniforms for instanced crowd draws: the model matrix comes from the instance data,
+/// so `model` is the identity.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn compute_instanced_part_uniforms(
+    camera: &Camera,
+    fov: f32,
+    width: u32,
+    height: u32,
+) -> [Uniforms; BODY_PART_COUNT] {
+    let perspective: [[f32; 4]; 4] = camera.projection_matrix_with_fov(width, height, fov);
+    let view: [[f32; 4]; 4] = camera.view_matrix_with_fov(fov);
+    std::array::from_fn(|i| Uniforms {
+        perspective,
+        view,
+        model: Matrix4::from_scale(1.0).into(),
+        offset: PART_CONFIGS[i].1,
+        part: i as u32,
+        glint: 0.0,
+        alpha_cutoff: 0.0,
+        cutout: 0,
+        segment: 0,
+        _padding: [0; 2],
     })
 }
+
+/// Two bind-pose occluder spheres `(center, radius)` per body part, in [`PART_CONFIGS`] order.
+///
+/// Sized to sit inside both the classic and slim meshes; a zero radius marks an unused slot.
+const OCCLUDER_SPHERES: [[(Vector3<f32>, f32); 2]; BODY_PART_COUNT] = [
+    // Head: one sphere filling the 8×8×8 cube
+    [(Vector3::new(0.0, 1.75, 0.0), 0.25), (Vector3::new(0.0, 1.75, 0.0), 0.0)],
+    // Right Arm: upper + lower
+    [(Vector3::new(0.36, 1.3, 0.0), 0.11), (Vector3::new(0.36, 0.95, 0.0), 0.11)],
+    // Left Arm: upper + lower
+    [(Vector3::new(-0.36, 1.3, 0.0), 0.11), (Vector3::new(-0.36, 0.95, 0.0), 0.11)],
+    // Right Leg: thigh + shin
+    [(Vector3::new(0.125, 0.55, 0.0), 0.12), (Vector3::new(0.125, 0.2, 0.0), 0.12)],
+    // Left Leg: thigh + shin
+    [(Vector3::new(-0.125, 0.55, 0.0), 0.12), (Vector3::new(-0.125, 0.2, 0.0), 0.12)],
+    // Body: chest + belly
+    [(Vector3::new(0.0, 1.3, 0.0), 0.17), (Vector3::new(0.0, 0.95, 0.0), 0.17)],
+];
+
+/// Posed occluder spheres for the contact-darkening term (group 0, binding 2).
+///
+/// Sphere `i` belongs to part `i / 2`; the shader skips the spheres of the part being shaded.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct OcclusionUniforms {
+    /// World-space center in `xyz`, radius in `w`.
+    pub spheres: [[f32; 4]; BODY_PART_COUNT * 2],
+    /// `x` = strength (0 = off); `yzw` unused.
+    pub params: [f32; 4],
+}
+
+pub(crate) fn compute_occlusion_uniforms(
+    character: &Character,
+    camera: &Camera,
+    strength: f32,
+) -> OcclusionUniforms {
+    let matrices = part_model_matrices(character, camera);
+    let shown = shown_layers(character);
+    let mut spheres = [[0.0; 4]; BODY_PART_COUNT * 2];
+    for (part, pair) in OCCLUDER_SPHERES.iter().enumerate() {
+        // Parts that are not drawn do not occlude (a zero radius contributes nothing).
+        if shown[part] == [false; 2] {
+            continue;
+        }
+        for (j, (center, radius)) in pair.iter().enumerate() {
+            let c = matrices[part] * center.extend(1.0);
+            spheres[part * 2 + j] = [c.x, c.y, c.z, radius * camera.scale];
+        }
+    }
+    OcclusionUniforms {
+        spheres,
+        params: [strength, 0.0, 0.0, 0.0],
+    }
+}
+
+/// Output adjustments, hurt tint and glint for the skin shader (group 0, binding 4).
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct AdjustUniforms {
+    /// `x` = exposure multiplier, `y` = `1 / gamma`, `z` = saturation, `w` = enabled (0/1).
+    pub params: [f32; 4],
+    /// Hurt tint color in `xyz`, mix amount in `w` (0 = off).
+    pub hurt: [f32; 4],
+    /// Glint color in `xyz`, time in `w`.
+    pub glint: [f32; 4],
+}
+
+/// Red the game mixes into hurt entities, and how far at full strength.
+const HURT_COLOR: [f32; 3] = [1.0, 0.0, 0.0];
+const HURT_MIX: f32 = 0.3;
+
+pub(crate) fn compute_adjust_uniforms(options: &RenderOptions) -> AdjustUniforms {
+    let adjustments = &options.adjustments;
+    let hurt = [
+        HURT_COLOR[0],
+        HURT_COLOR[1],
+        HURT_COLOR[2],
+        HURT_MIX * options.status.hurt.clamp(0.0, 1.0 / HURT_MIX),
+    ];
+    let glint = options.glint.as_ref().map_or([0.0; 4], |glint| {
+        let [r, g, b] = glint.color;
+        [r, g, b, glint.time.rem_euclid(EnchantmentGlint::PERIOD)]
+    });
+    if adjustments.is_identity() {
+        return AdjustUniforms {
+            params: [1.0, 1.0, 1.0, 0.0],
+            hurt,
+            glint,
+        };
+    }
+    AdjustUniforms {
+        params: [
+            adjustments.exposure.exp2(),
+            1.0 / adjustments.gamma.max(0.01),
+            adjustments.saturation.max(0.0),
+            1.0,
+        ],
+        hurt,
+        glint,
+    }
+}
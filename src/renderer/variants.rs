@@ -0,0 +1,194 @@
+//! Skin pipeline permutations: one pipeline per set of enabled shader features, compiled on first
+//! use and kept for the renderer's lifetime.
+//!
+//! The skin shader declares a `bool` override constant per optional lighting term (see
+//! [`crate::constants::SHADER`]). Compiling with the disabled ones set to `false` lets the shader
+//! compiler drop their code, so a plain render does not pay for shadows or occlusion it never
+//! uses, and a new effect adds a bit here instead of another branch every render takes.
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+
+use crate::constants::SHADER;
+use crate::lighting::Lighting;
+
+use super::options::RenderOptions;
+use super::pipeline::create_pipeline;
+
+/// Enabled optional terms of the skin shader, as bits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub(crate) struct ShaderFeatures(u32);
+
+impl ShaderFeatures {
+    pub const SHADOWS: Self = Self(1 << 0);
+    pub const AMBIENT_OCCLUSION: Self = Self(1 << 1);
+    pub const RIM_LIGHT: Self = Self(1 << 2);
+    pub const COLOR_ADJUST: Self = Self(1 << 3);
+    pub const HURT_TINT: Self = Self(1 << 4);
+    pub const GLINT: Self = Self(1 << 5);
+
+    /// Override constant of each feature in the skin shader.
+    const OVERRIDES: [(Self, &'static str); 6] = [
+        (Self::SHADOWS, "SHADOWS"),
+        (Self::AMBIENT_OCCLUSION, "AMBIENT_OCCLUSION"),
+        (Self::RIM_LIGHT, "RIM_LIGHT"),
+        (Self::COLOR_ADJUST, "COLOR_ADJUST"),
+        (Self::HURT_TINT, "HURT_TINT"),
+        (Self::GLINT, "GLINT"),
+    ];
+
+    /// Features a render with `options` and `lighting` needs.
+    pub fn for_render(options: &RenderOptions, lighting: &Lighting) -> Self {
+        let mut features = Self::default();
+        for (feature, enabled) in [
+            (Self::SHADOWS, options.shadow.is_some()),
+            (Self::AMBIENT_OCCLUSION, options.ambient_occlusion > 0.0),
+            (Self::RIM_LIGHT, lighting.rim_strength > 0.0),
+            (Self::COLOR_ADJUST, !options.adjustments.is_identity()),
+            (Self::HURT_TINT, options.status.hurt > 0.0),
+            (Self::GLINT, options.glint.as_ref().is_some_and(|g| !g.bones.is_empty())),
+        ] {
+            if enabled {
+                features.0 |= feature.0;
+            }
+        }
+        features
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Pipeline constants selecting this variant.
+    fn constants(self) -> [(&'static str, f64); 6] {
+        Self::OVERRIDES.map(|(feature, name)| (name, f64::from(u8::from(self.contains(feature)))))
+    }
+}
+
+/// Pipelines of one shader entry point pair and color format, by feature set and sample count.
+pub(crate) struct VariantCache {
+    layout: wgpu::PipelineLayout,
+    color_format: wgpu::TextureFormat,
+    vertex_entry: &'static str,
+    buffers: Vec<wgpu::VertexBufferLayout<'static>>,
+    fragment_entry: &'static str,
+    /// WGSL source of every variant: [`SHADER`] unless replaced in development.
+    source: Cow<'static, str>,
+    variants: RefCell<Vec<((ShaderFeatures, u32), wgpu::RenderPipeline)>>,
+}
+
+impl VariantCache {
+    pub fn new(
+        layout: &wgpu::PipelineLayout,
+        color_format: wgpu::TextureFormat,
+        vertex_entry: &'static str,
+        buffers: Vec<wgpu::VertexBufferLayout<'static>>,
+        fragment_entry: &'static str,
+    ) -> Self {
+        Self {
+            layout: layout.clone(),
+            color_format,
+            vertex_entry,
+            buffers,
+            fragment_entry,
+            source: Cow::Borrowed(SHADER),
+            variants: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The pipeline for `features` drawing `samples` samples per pixel, compiling it on first
+    /// request.
+    pub fn get(
+        &self,
+        device: &wgpu::Device,
+        features: ShaderFeatures,
+        samples: u32,
+    ) -> wgpu::RenderPipeline {
+        let key = (features, samples);
+        let mut variants = self.variants.borrow_mut();
+        if let Some((_, pipeline)) = variants.iter().find(|(k, _)| *k == key) {
+            return pipeline.clone();
+        }
+        // A module per variant: the GL backend caches linked programs by module and entry point,
+        // ignoring override values, so variants sharing a module would share one program.
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Skin Shader"),
+            source: wgpu::ShaderSource::Wgsl(self.source.clone()),
+        });
+        let pipeline = create_pipeline(
+            device,
+            &shader,
+            &self.layout,
+            self.color_format,
+            self.vertex_entry,
+            &self.buffers,
+            self.fragment_entry,
+            &features.constants(),
+            samples,
+        );
+        variants.push((key, pipeline.clone()));
+        pipeline
+    }
+
+    /// A cache for the same entry points and format whose variants compile from `source`.
+    #[cfg(all(feature = "dev", not(target_arch = "wasm32")))]
+    pub fn with_source(&self, source: &str) -> Self {
+        Self {
+            layout: self.layout.clone(),
+            color_format: self.color_format,
+            vertex_entry: self.vertex_entry,
+            buffers: self.buffers.clone(),
+            fragment_entry: self.fragment_entry,
+            source: Cow::Owned(source.to_owned()),
+            variants: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Number of compiled variants.
+    pub fn len(&self) -> usize {
+        self.variants.borrow().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::options::ShadowSettings;
+
+    #[test]
+    fn default_render_needs_no_features() {
+        let features = ShaderFeatures::for_render(&RenderOptions::default(), &Lighting::default());
+        assert_eq!(features, ShaderFeatures::default());
+        assert!(features.constants().iter().all(|(_, v)| *v == 0.0));
+    }
+
+    #[test]
+    fn enabled_effects_set_their_bits() {
+        let options = RenderOptions {
+            shadow: Some(ShadowSettings::default()),
+            ambient_occlusion: 0.5,
+            ..RenderOptions::default()
+        };
+        let lighting = Lighting {
+            rim_strength: 1.0,
+            ..Lighting::default()
+        };
+        let features = ShaderFeatures::for_render(&options, &lighting);
+        assert!(features.contains(ShaderFeatures::SHADOWS));
+        assert!(features.contains(ShaderFeatures::AMBIENT_OCCLUSION));
+        assert!(features.contains(ShaderFeatures::RIM_LIGHT));
+        assert!(!features.contains(ShaderFeatures::COLOR_ADJUST));
+        assert!(!features.contains(ShaderFeatures::HURT_TINT));
+        assert!(!features.contains(ShaderFeatures::GLINT));
+    }
+
+    #[test]
+    fn constants_name_every_override() {
+        for (feature, name) in ShaderFeatures::OVERRIDES {
+            assert!(crate::constants::SHADER.contains(&format!("override {name}: bool")));
+            let constants = feature.constants();
+            let enabled: Vec<_> = constants.iter().filter(|(_, v)| *v == 1.0).collect();
+            assert_eq!(enabled, [&(name, 1.0)]);
+        }
+    }
+}
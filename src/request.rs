@@ -0,0 +1,191 @@
+//! One-call rendering: [`render_skin_image`] turns skin PNG bytes and a [`RenderRequest`] into an
+//! image, without managing a [`Renderer`] or skin texture.
+//!
+//! Calls share one renderer per process (also used by [`crate::thumbnail`]), so only the first
+//! call pays for device creation. [`self_test`] checks that renderer for health probes.
+//!
+//! Panics inside a call are returned as [`EidolonError::Panicked`] ([`catch_panic`]), so a host
+//! process serving many requests survives a skin that hits a bug; the shared renderer is then
+//! replaced, like after a GPU timeout.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use image::RgbaImage;
+
+use crate::camera::Camera;
+use crate::cancel::CancelToken;
+use crate::character::Character;
+use crate::error::{catch_panic, EidolonError};
+use crate::lighting::Lighting;
+use crate::renderer::{RenderOptions, Renderer};
+use crate::texture::SkinImage;
+use crate::theme::ThemePalette;
+use crate::trace::TraceContext;
+
+/// Everything one render needs. Start from [`RenderRequest::new`] and override fields.
+#[derive(Debug, Clone)]
+pub struct RenderRequest<'a> {
+    /// Skin PNG bytes; single-layer (64×32) skins are converted.
+    pub skin: &'a [u8],
+    pub character: Character,
+    pub camera: Camera,
+    pub width: u32,
+    pub height: u32,
+    pub options: RenderOptions,
+    pub lighting: Lighting,
+    /// Straight RGBA background; `[0, 0, 0, 0]` is transparent.
+    pub background: [u8; 4],
+    /// Trace of the request this render serves: the render runs in its
+    /// [`span`](TraceContext::span) and logs its duration there.
+    pub trace: Option<TraceContext>,
+    /// Stops the render with [`EidolonError::Cancelled`] if cancelled before it reaches the GPU,
+    /// e.g. when the client disconnects while the request waits for the shared renderer.
+    pub cancel: Option<CancelToken>,
+    /// Longest to wait for the GPU to draw and read back the image
+    /// ([`Renderer::set_gpu_timeout`]). On [`EidolonError::Timeout`] the shared renderer is
+    /// discarded, and the next call builds a fresh one.
+    pub gpu_timeout: Option<Duration>,
+}
+
+impl<'a> RenderRequest<'a> {
+    /// The CLI's defaults: standing classic character, front camera, 800×600, default options and
+    /// light rig, transparent background.
+    pub fn new(skin: &'a [u8]) -> Self {
+        Self {
+            skin,
+            character: Character::new(),
+            camera: Camera::new(),
+            width: 800,
+            height: 600,
+            options: RenderOptions::default(),
+            lighting: Lighting::default(),
+            background: [0, 0, 0, 0],
+            trace: None,
+            cancel: None,
+            gpu_timeout: None,
+        }
+    }
+}
+
+/// Render `request` and return the straight- or premultiplied-alpha image selected by
+/// [`RenderOptions::alpha_mode`].
+pub fn render_skin_image(request: RenderRequest) -> Result<RgbaImage, EidolonError> {
+    catch_panic(|| {
+        // Decode before taking the shared renderer, so concurrent callers only queue for the GPU.
+        let skin = SkinImage::from_memory(request.skin)?;
+        render_decoded(&request, &skin)
+    })
+}
+
+/// [`render_skin_image`], plus the skin's [`ThemePalette`] so a page can be styled to match the
+/// render from one call.
+pub fn render_skin_image_with_theme(
+    request: RenderRequest,
+) -> Result<(RgbaImage, ThemePalette), EidolonError> {
+    catch_panic(|| {
+        let skin = SkinImage::from_memory(request.skin)?;
+        let palette = ThemePalette::from_skin(&skin, request.character.skin_type);
+        Ok((render_decoded(&request, &skin)?, palette))
+    })
+}
+
+fn render_decoded(request: &RenderRequest, skin: &SkinImage) -> Result<RgbaImage, EidolonError> {
+    let _span = request.trace.map(|trace| trace.span().entered());
+    let start = Instant::now();
+    let image = with_shared_renderer(|renderer| {
+        renderer.set_lighting(request.lighting);
+        renderer.set_options(request.options.clone());
+        let [r, g, b, a] = request.background.map(|c| c as f64 / 255.0);
+        renderer.set_clear_color(r, g, b, a);
+        // The token belongs to this request only; the next caller of the shared renderer must
+        // not inherit it.
+        renderer.set_cancel_token(request.cancel.clone());
+        renderer.set_gpu_timeout(request.gpu_timeout);
+
+        let skin = renderer.upload_skin(skin);
+        let image = renderer.render(
+            &request.character,
+            &skin,
+            &request.camera,
+            request.width,
+            request.height,
+        );
+        renderer.set_cancel_token(None);
+        renderer.set_gpu_timeout(None);
+        image
+    })?;
+    tracing::info!(
+        width = request.width,
+        height = request.height,
+        elapsed_ms = start.elapsed().as_millis() as u64,
+        "rendered"
+    );
+    Ok(image)
+}
+
+/// Render a 16×16 test image on the shared renderer and check it: the skin's color in the middle,
+/// the transparent background in the corners. Returns how long it took.
+///
+/// Meant for health and readiness probes: unlike a check that a device exists, it fails when the
+/// GL/Vulkan context is lost, the pipeline cannot be built or readback returns garbage. It waits
+/// its turn behind in-flight renders, so a slow result also signals a saturated renderer.
+pub fn self_test() -> Result<Duration, EidolonError> {
+    const RED: [u8; 4] = [220, 30, 30, 255];
+    let start = Instant::now();
+    let skin = SkinImage::from_rgba(RgbaImage::from_pixel(64, 64, image::Rgba(RED)))?;
+    let image = with_shared_renderer(|renderer| {
+        renderer.set_lighting(Lighting::default());
+        renderer.set_options(RenderOptions::default());
+        renderer.set_clear_color(0.0, 0.0, 0.0, 0.0);
+        let skin = renderer.upload_skin(&skin);
+        renderer.render(&Character::new(), &skin, &Camera::new(), 16, 16)
+    })?;
+
+    let [r, g, b, a] = image.get_pixel(8, 8).0;
+    if a < 128 || r <= g || r <= b {
+        return Err(EidolonError::gpu(format!(
+            "self-test render is wrong: center pixel is {:?}",
+            [r, g, b, a]
+        )));
+    }
+    if image.get_pixel(0, 0)[3] != 0 || image.get_pixel(15, 0)[3] != 0 {
+        return Err(EidolonError::gpu(
+            "self-test render is wrong: background is not transparent",
+        ));
+    }
+    Ok(start.elapsed())
+}
+
+/// Process-wide renderer of the one-call APIs. A static is never dropped, so the GPU device is
+/// not torn down during thread or process exit.
+static RENDERER: Mutex<Option<Renderer>> = Mutex::new(None);
+
+/// Run `f` on the shared renderer, creating it on first use. Concurrent callers take turns.
+///
+/// A renderer whose GPU timed out may still be busy with the abandoned work, or hung; it is
+/// replaced on the next call and torn down on another thread, since dropping it can block. A
+/// panic in `f` is returned as [`EidolonError::Panicked`] and replaces the renderer too, since it
+/// may have stopped halfway through changing its state.
+pub(crate) fn with_shared_renderer<T>(
+    f: impl FnOnce(&mut Renderer) -> Result<T, EidolonError>,
+) -> Result<T, EidolonError> {
+    let mut cached = RENDERER.lock().unwrap_or_else(|e| e.into_inner());
+    let renderer = match cached.as_mut() {
+        Some(renderer) => renderer,
+        None => cached.insert(Renderer::new()?),
+    };
+    let result = catch_panic(|| f(renderer));
+    match &result {
+        Err(EidolonError::Timeout { after }) => {
+            tracing::warn!("Discarding the shared renderer after a {after:?} GPU timeout");
+        }
+        Err(EidolonError::Panicked(msg)) => {
+            tracing::warn!("Discarding the shared renderer after a panic: {msg}");
+        }
+        _ => return result,
+    }
+    let stale = cached.take();
+    std::thread::spawn(move || drop(stale));
+    result
+}
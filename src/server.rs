@@ -0,0 +1,412 @@
+//! Server list ping: ask a Java Edition server for its status (the request behind the
+//! multiplayer screen) and render the players it reports online as a lineup banner.
+//!
+//! Servers only list a sample of their players (vanilla sends up to 12), and plugins often fill
+//! the sample with text lines instead; [`ServerStatus::players`] skips those.
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::camera::Camera;
+use crate::character::{Character, SkinType};
+use crate::error::EidolonError;
+use crate::provider::{PlayerId, SkinData, SkinProvider};
+use crate::renderer::{CrowdMember, Renderer};
+use crate::texture::Texture;
+
+/// Port servers listen on when the address names none.
+pub const DEFAULT_PORT: u16 = 25565;
+/// Upper bound on a status response; favicons make them a few kilobytes.
+const MAX_RESPONSE: usize = 1 << 20;
+/// Distance between the feet of neighbours in a lineup, in world units.
+const LINEUP_SPACING: f32 = 1.2;
+
+/// A server's answer to a status request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerStatus {
+    /// Version name, e.g. `"1.21.1"` or a proxy's `"Velocity 3.3.0"`.
+    pub version: String,
+    pub protocol: i64,
+    pub online: u64,
+    pub max: u64,
+    /// Message of the day as plain text, formatting dropped.
+    pub motd: String,
+    /// Players as listed, including any text lines a plugin put there.
+    pub sample: Vec<OnlinePlayer>,
+}
+
+/// An entry of the player sample.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OnlinePlayer {
+    pub name: String,
+    /// Dashed UUID as served.
+    pub id: String,
+}
+
+impl OnlinePlayer {
+    /// Whether this is a real player rather than a line of text: text lines use the nil UUID or
+    /// `§` formatting codes in the name.
+    pub fn is_player(&self) -> bool {
+        !self.name.is_empty()
+            && !self.name.contains('§')
+            && self.id.chars().any(|c| c != '0' && c != '-')
+    }
+
+    /// Lookup key for skin providers: the UUID, since names change.
+    pub fn player_id(&self) -> PlayerId {
+        match PlayerId::parse(&self.id) {
+            uuid @ PlayerId::Uuid(_) => uuid,
+            PlayerId::Name(_) => PlayerId::Name(self.name.clone()),
+        }
+    }
+}
+
+impl ServerStatus {
+    /// Parse the status JSON: `{"version": {"name", "protocol"}, "players": {"max", "online",
+    /// "sample": [{"name", "id"}]}, "description": text or chat component}`.
+    pub fn from_json(json: &[u8]) -> Result<Self, EidolonError> {
+        let status: Value = serde_json::from_slice(json)
+            .map_err(|e| EidolonError::fetch(format!("malformed server status: {e}")))?;
+        let players = &status["players"];
+        let sample = players["sample"]
+            .as_array()
+            .map(|sample| {
+                sample
+                    .iter()
+                    .filter_map(|entry| {
+                        Some(OnlinePlayer {
+                            name: entry["name"].as_str()?.to_string(),
+                            id: entry["id"].as_str()?.to_string(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut motd = String::new();
+        chat_text(&status["description"], &mut motd);
+        Ok(Self {
+            version: status["version"]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            protocol: status["version"]["protocol"].as_i64().unwrap_or(-1),
+            online: players["online"].as_u64().unwrap_or(0),
+            max: players["max"].as_u64().unwrap_or(0),
+            motd: strip_formatting(&motd),
+            sample,
+        })
+    }
+
+    /// The sample without text lines ([`OnlinePlayer::is_player`]).
+    pub fn players(&self) -> impl Iterator<Item = &OnlinePlayer> {
+        self.sample.iter().filter(|player| player.is_player())
+    }
+}
+
+/// Ping `address` (`host` or `host:port`, IPv6 as `[addr]:port`) and read its status. SRV records
+/// are not looked up: give the port of servers that rely on one.
+pub fn ping(address: &str, timeout: Duration) -> Result<ServerStatus, EidolonError> {
+    let (host, port) = split_address(address)?;
+    let failed = |e: std::io::Error| EidolonError::fetch(format!("ping {address}: {e}"));
+    let socket = (host, port)
+        .to_socket_addrs()
+        .map_err(failed)?
+        .next()
+        .ok_or_else(|| EidolonError::fetch(format!("ping {address}: host has no address")))?;
+    let mut stream = TcpStream::connect_timeout(&socket, timeout).map_err(failed)?;
+    stream.set_read_timeout(Some(timeout)).map_err(failed)?;
+    stream.set_write_timeout(Some(timeout)).map_err(failed)?;
+    debug!(%socket, "Pinging {address}");
+
+    // Handshake: protocol -1 (any), the address as typed, next state 1 (status).
+    let mut handshake = vec![0x00];
+    write_varint(&mut handshake, -1);
+    write_varint(&mut handshake, host.len() as i32);
+    handshake.extend_from_slice(host.as_bytes());
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_varint(&mut handshake, 1);
+    let mut packets = Vec::new();
+    write_varint(&mut packets, handshake.len() as i32);
+    packets.extend_from_slice(&handshake);
+    // Status request: length 1, packet 0.
+    packets.extend_from_slice(&[0x01, 0x00]);
+    stream.write_all(&packets).map_err(failed)?;
+
+    let json = read_status_response(&mut stream).map_err(failed)?;
+    ServerStatus::from_json(&json)
+}
+
+/// Split `host[:port]`; bracketed IPv6 hosts lose their brackets.
+fn split_address(address: &str) -> Result<(&str, u16), EidolonError> {
+    let invalid = || EidolonError::fetch(format!("invalid server address '{address}'"));
+    let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']').ok_or_else(invalid)?;
+        (host, rest.strip_prefix(':'))
+    } else {
+        match address.split_once(':') {
+            Some((host, port)) if !port.contains(':') => (host, Some(port)),
+            // A bare IPv6 address.
+            Some(_) => (address, None),
+            None => (address, None),
+        }
+    };
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| invalid())?,
+        None => DEFAULT_PORT,
+    };
+    if host.is_empty() {
+        return Err(invalid());
+    }
+    Ok((host, port))
+}
+
+/// Read a status response packet (length, id 0, JSON string) and return the JSON.
+fn read_status_response(stream: &mut impl Read) -> std::io::Result<Vec<u8>> {
+    let invalid = |msg: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, msg);
+    let length = usize::try_from(read_varint(stream)?).map_err(|_| invalid("negative length"))?;
+    if length > MAX_RESPONSE {
+        return Err(invalid("status response too large"));
+    }
+    let mut packet = vec![0; length];
+    stream.read_exact(&mut packet)?;
+    let mut packet = packet.as_slice();
+    if read_varint(&mut packet)? != 0 {
+        return Err(invalid("not a status response"));
+    }
+    let len = usize::try_from(read_varint(&mut packet)?).map_err(|_| invalid("negative length"))?;
+    packet
+        .get(..len)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| invalid("truncated status response"))
+}
+
+fn write_varint(out: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value < 0x80 {
+            out.push(value as u8);
+            return;
+        }
+        out.push(value as u8 & 0x7f | 0x80);
+        value >>= 7;
+    }
+}
+
+fn read_varint(input: &mut impl Read) -> std::io::Result<i32> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let mut byte = [0];
+        input.read_exact(&mut byte)?;
+        value |= u32::from(byte[0] & 0x7f) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        "varint too long",
+    ))
+}
+
+/// Append the text of a chat component (a string, or `{"text", "extra": [...]}`).
+fn chat_text(component: &Value, out: &mut String) {
+    match component {
+        Value::String(text) => out.push_str(text),
+        Value::Array(parts) => parts.iter().for_each(|part| chat_text(part, out)),
+        Value::Object(object) => {
+            if let Some(text) = object.get("text").and_then(Value::as_str) {
+                out.push_str(text);
+            }
+            if let Some(extra) = object.get("extra") {
+                chat_text(extra, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Drop `§x` formatting codes.
+fn strip_formatting(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// A player's skin, ready for [`render_lineup`].
+pub struct LineupSkin {
+    pub name: String,
+    pub texture: Texture,
+    pub skin_type: SkinType,
+}
+
+/// Look up the skins of `players` in order. Players the provider does not know get `fallback`,
+/// or are left out without one; other provider errors are logged and the player left out.
+pub fn fetch_lineup<'a>(
+    renderer: &Renderer,
+    players: impl IntoIterator<Item = &'a OnlinePlayer>,
+    provider: &dyn SkinProvider,
+    fallback: Option<&SkinData>,
+) -> Result<Vec<LineupSkin>, EidolonError> {
+    let mut skins = Vec::new();
+    for player in players {
+        renderer.check_cancelled()?;
+        let data = match (provider.fetch(&player.player_id()), fallback) {
+            (Ok(data), _) => data,
+            (Err(EidolonError::NotFound(_)), Some(fallback)) => fallback.clone(),
+            (Err(e), _) => {
+                warn!("Leaving {} out of the lineup: {e}", player.name);
+                continue;
+            }
+        };
+        skins.push(LineupSkin {
+            name: player.name.clone(),
+            texture: renderer.load_texture_from_memory(&data.bytes)?,
+            skin_type: data.skin_type.unwrap_or(SkinType::Classic),
+        });
+    }
+    Ok(skins)
+}
+
+/// Camera that fits `count` lineup members into a `width`×`height` image.
+///
+/// [`Camera::scale`] both shrinks the characters and moves the eye in, so a lineup drawn at
+/// scale `s` is `s²` times smaller on screen; members stand at `s` times their unscaled
+/// positions, centred on the look-at target.
+fn lineup_camera(count: usize, width: u32, height: u32) -> Camera {
+    // Visible half-height at the default orbit distance.
+    let half_view = 4.0 * 30f32.to_radians().tan();
+    let aspect = width as f32 / height as f32;
+    // Half the lineup's unscaled extent, with a margin.
+    let half_width = count.saturating_sub(1) as f32 * LINEUP_SPACING / 2.0 + 0.6;
+    let half_height: f32 = 1.1;
+    Camera {
+        scale: (half_view / half_height.max(half_width / aspect)).sqrt(),
+        ..Camera::default()
+    }
+}
+
+/// Render `skins` standing side by side, first on the left, in one image.
+pub fn render_lineup(
+    renderer: &Renderer,
+    skins: &[LineupSkin],
+    width: u32,
+    height: u32,
+) -> Result<image::RgbaImage, EidolonError> {
+    let camera = lineup_camera(skins.len(), width, height);
+    let center = (skins.len() as f32 - 1.0) / 2.0;
+    let members: Vec<_> = skins
+        .iter()
+        .enumerate()
+        .map(|(i, skin)| {
+            let mut character = Character {
+                skin_type: skin.skin_type,
+                ..Character::default()
+            };
+            // The camera faces the players, whose right (+X) is the image's left. Characters are
+            // two units tall; lift them so their middle is at the look-at target (y = 1).
+            character.position.x = (center - i as f32) * LINEUP_SPACING * camera.scale;
+            character.position.y = 1.0 - camera.scale;
+            CrowdMember {
+                character,
+                skin: &skin.texture,
+            }
+        })
+        .collect();
+    renderer.render_crowd(&members, &camera, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    const STATUS: &str = r#"{
+        "version": {"name": "1.21.1", "protocol": 767},
+        "players": {"max": 20, "online": 3, "sample": [
+            {"name": "Notch", "id": "069a79f4-44e9-4726-a5be-fca90e38aaf5"},
+            {"name": "§6Welcome!", "id": "00000000-0000-0000-0000-000000000000"},
+            {"name": "jeb_", "id": "853c80ef-3c37-49fd-aa49-938b674adae6"}
+        ]},
+        "description": {"text": "A §aMinecraft", "extra": [{"text": " Server"}]},
+        "favicon": "data:image/png;base64,"
+    }"#;
+
+    #[test]
+    fn status_json_parses_and_skips_text_lines() {
+        let status = ServerStatus::from_json(STATUS.as_bytes()).unwrap();
+        assert_eq!(status.version, "1.21.1");
+        assert_eq!(status.protocol, 767);
+        assert_eq!((status.online, status.max), (3, 20));
+        assert_eq!(status.motd, "A Minecraft Server");
+        assert_eq!(status.sample.len(), 3);
+        let names: Vec<_> = status.players().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Notch", "jeb_"]);
+        assert!(matches!(
+            status.sample[0].player_id(),
+            PlayerId::Uuid(uuid) if uuid == "069a79f4-44e9-4726-a5be-fca90e38aaf5"
+        ));
+
+        let bare = ServerStatus::from_json(br#"{"description": "hi"}"#).unwrap();
+        assert_eq!((bare.motd.as_str(), bare.sample.len()), ("hi", 0));
+        assert!(ServerStatus::from_json(b"<html>").is_err());
+    }
+
+    #[test]
+    fn addresses_default_to_the_game_port() {
+        assert_eq!(
+            split_address("mc.example.org").unwrap(),
+            ("mc.example.org", 25565)
+        );
+        assert_eq!(
+            split_address("localhost:25570").unwrap(),
+            ("localhost", 25570)
+        );
+        assert_eq!(split_address("[::1]:25570").unwrap(), ("::1", 25570));
+        assert_eq!(split_address("::1").unwrap(), ("::1", 25565));
+        assert!(split_address("host:port").is_err());
+        assert!(split_address(":25565").is_err());
+    }
+
+    #[test]
+    fn ping_reads_the_status_response() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let handshake_len = read_varint(&mut stream).unwrap() as usize;
+            let mut handshake = vec![0; handshake_len];
+            stream.read_exact(&mut handshake).unwrap();
+            let mut request = [0; 2];
+            stream.read_exact(&mut request).unwrap();
+            assert_eq!(request, [1, 0]);
+
+            let mut response = vec![0x00];
+            write_varint(&mut response, STATUS.len() as i32);
+            response.extend_from_slice(STATUS.as_bytes());
+            let mut packet = Vec::new();
+            write_varint(&mut packet, response.len() as i32);
+            packet.extend_from_slice(&response);
+            stream.write_all(&packet).unwrap();
+            handshake
+        });
+
+        let status = ping(&format!("127.0.0.1:{port}"), Duration::from_secs(5)).unwrap();
+        assert_eq!(status.players().count(), 2);
+        let handshake = server.join().unwrap();
+        // Packet 0, protocol -1, "127.0.0.1", the port, next state 1.
+        assert_eq!(&handshake[..7], &[0x00, 0xff, 0xff, 0xff, 0xff, 0x0f, 9]);
+        assert_eq!(&handshake[7..16], b"127.0.0.1");
+        assert_eq!(&handshake[16..], &[&port.to_be_bytes()[..], &[1]].concat());
+    }
+}
@@ -0,0 +1,198 @@
+//! Shot presets: a camera, pose, light rig and background set together to reproduce a familiar
+//! framing, so UIs showing the render next to the game do not hand-tune a dozen parameters.
+//!
+//! [`ShotPreset::Inventory`] follows the game's inventory screen, which draws the player in a box
+//! 49×70 GUI pixels wide at 30 GUI pixels per block, nearly orthographic and turned towards the
+//! mouse cursor. Render at 49×70 times the GUI scale for a pixel-for-pixel match.
+//! [`ShotPreset::CapeBack`] shows off capes for marketplaces: a three-quarter view from behind,
+//! arms held slightly out of the way and the head turned to show the face's profile.
+
+use cgmath::Vector3;
+
+use crate::camera::{Camera, DEFAULT_FOV};
+use crate::character::Character;
+use crate::lighting::Lighting;
+
+/// Size of the inventory's player box in GUI pixels (width, height).
+pub const INVENTORY_BOX: (u32, u32) = (49, 70);
+
+/// GUI pixels per block in the inventory's player box.
+const INVENTORY_BLOCK_PIXELS: f32 = 30.0;
+
+/// Height above the feet (blocks) drawn at the middle of the inventory box: half the player's
+/// 1.8-block hitbox plus the game's 1/16-block offset.
+const INVENTORY_CENTER_HEIGHT: f32 = 0.9 + 0.0625;
+
+/// Narrowest [`RenderOptions::fov`](crate::renderer::RenderOptions::fov): as close to the game's orthographic GUI projection as the camera
+/// goes.
+const INVENTORY_FOV: f32 = 1.0;
+
+/// Half the player's extent in model units (height, width), with a margin, for full-body shots.
+const BODY_HALF_EXTENT: (f32, f32) = (1.4, 0.8);
+
+/// Named shots; see the [module docs](self).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShotPreset {
+    /// The inventory screen's player model. `cursor` is the mouse position in GUI pixels
+    /// relative to the middle of the box (x right, y down); the body, head and tilt follow it
+    /// as in game. `[0.0, 0.0]` faces the viewer.
+    Inventory { cursor: [f32; 2] },
+    /// A three-quarter view from behind and slightly above for capes and back designs: arms
+    /// rolled a little out from the body and the head turned towards the camera, the whole
+    /// player fitted into the image.
+    CapeBack,
+}
+
+impl ShotPreset {
+    /// Pose and place `character` for this shot in a `width`×`height` render and return the
+    /// camera, to be rendered at [`ShotPreset::fov`]. Replaces the character's position, rotation
+    /// and head angles (and the arm rolls for [`ShotPreset::CapeBack`]); other joints, skin type
+    /// and attachments are kept.
+    pub fn frame(&self, character: &mut Character, width: u32, height: u32) -> Camera {
+        match *self {
+            ShotPreset::Inventory { cursor } => {
+                // The game's mouse follow: atan of the cursor offset over 40 GUI pixels, in
+                // radians, times 20 read as degrees.
+                let turn = (cursor[0] / 40.0).atan() * 20.0;
+                let tilt = (cursor[1] / 40.0).atan() * 20.0;
+                character.rotation = Vector3::new(-tilt, turn, 0.0);
+                character.posture.head_yaw = turn;
+                character.posture.head_pitch = -tilt;
+
+                // Fit the box into the image and keep 30 GUI pixels per block. The zoom scales
+                // the model and divides the orbit distance, so a block's on-screen height grows
+                // with its square: `scale² · height / (2 · 4 · tan(fov / 2))` pixels at any fov.
+                let gui_pixel = (width as f32 / INVENTORY_BOX.0 as f32)
+                    .min(height as f32 / INVENTORY_BOX.1 as f32);
+                let scale = (INVENTORY_BLOCK_PIXELS * gui_pixel * 2.0 * half_view()
+                    / height.max(1) as f32)
+                    .sqrt();
+                // The game tilts the model about its feet, which stay put.
+                character.position =
+                    Vector3::new(0.0, 1.0 - INVENTORY_CENTER_HEIGHT * scale, 0.0);
+                Camera {
+                    yaw: 180.0,
+                    pitch: 90.0,
+                    scale,
+                }
+            }
+            ShotPreset::CapeBack => {
+                character.rotation = Vector3::new(0.0, 0.0, 0.0);
+                character.posture.head_yaw = -45.0;
+                character.posture.head_pitch = 0.0;
+                character.posture.left_arm_roll = 12.0;
+                character.posture.right_arm_roll = 12.0;
+
+                // Fit the body's bounding box, as seen from the front, with the margin covering
+                // the turn. The model scales about its feet, so lift its middle onto the target.
+                let aspect = width as f32 / height.max(1) as f32;
+                let (half_height, half_width) = BODY_HALF_EXTENT;
+                let scale = (half_view() / half_height.max(half_width / aspect)).sqrt();
+                character.position = Vector3::new(0.0, 1.0 - scale, 0.0);
+                // Yaw 0° looks at the back; the eye swings to the player's right, where the head
+                // turns.
+                Camera {
+                    yaw: 20.0,
+                    pitch: 100.0,
+                    scale,
+                }
+            }
+        }
+    }
+
+    /// Vertical field of view in degrees for this shot's camera.
+    pub fn fov(&self) -> f32 {
+        match self {
+            ShotPreset::Inventory { .. } => INVENTORY_FOV,
+            ShotPreset::CapeBack => DEFAULT_FOV,
+        }
+    }
+
+    /// Light rig for this shot.
+    pub fn lighting(&self) -> Lighting {
+        match self {
+            // The game's inventory entity lights: two lights from above, one in front, at 0.6
+            // each over 0.4 ambient.
+            ShotPreset::Inventory { .. } => Lighting {
+                key_direction: [-0.2, 1.0, -1.0],
+                key_color: [0.6; 3],
+                fill_direction: [0.2, 1.0, 0.0],
+                fill_color: [0.6; 3],
+                ambient: [0.4; 3],
+                rim_color: [1.0; 3],
+                rim_strength: 0.0,
+            },
+            // The default key light already shines from behind, over the right shoulder; a
+            // faint rim separates a dark cape from the background.
+            ShotPreset::CapeBack => Lighting {
+                rim_strength: 0.3,
+                ..Lighting::default()
+            },
+        }
+    }
+
+    /// Background clear color (RGBA, 0.0–1.0) for this shot, or `None` to keep the renderer's.
+    pub fn background(&self) -> Option<[f64; 4]> {
+        match self {
+            // The inventory panel's gray, #c6c6c6.
+            ShotPreset::Inventory { .. } => {
+                Some([198.0 / 255.0, 198.0 / 255.0, 198.0 / 255.0, 1.0])
+            }
+            ShotPreset::CapeBack => None,
+        }
+    }
+}
+
+/// Visible half-height at the look-at target of an unzoomed camera, in world units. The same at
+/// field of view.
+fn half_view() -> f32 {
+    Camera::default().orbit_distance() * (DEFAULT_FOV / 2.0).to_radians().tan()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inventory_keeps_thirty_gui_pixels_per_block() {
+        let mut character = Character::new();
+        let (width, height) = (INVENTORY_BOX.0 * 2, INVENTORY_BOX.1 * 2);
+        let preset = ShotPreset::Inventory { cursor: [0.0, 0.0] };
+        let camera = preset.frame(&mut character, width, height);
+        // World units per pixel at the target's distance.
+        let world_per_pixel = 2.0
+            * camera.orbit_distance_with_fov(preset.fov())
+            * (preset.fov() / 2.0).to_radians().tan()
+            / height as f32;
+        let block_pixels = camera.scale / world_per_pixel;
+        assert!((block_pixels - 60.0).abs() < 1e-2, "{block_pixels}");
+        assert_eq!(character.rotation, Vector3::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn inventory_turns_towards_the_cursor() {
+        let mut character = Character::new();
+        ShotPreset::Inventory {
+            cursor: [40.0, -40.0],
+        }
+        .frame(&mut character, 49, 70);
+        // atan(1) · 20 = 15.7°: the body and then the head turn right, and the head looks up.
+        assert!((character.rotation.y - 15.708).abs() < 1e-3);
+        assert!((character.posture.head_yaw - 15.708).abs() < 1e-3);
+        assert!((character.posture.head_pitch - 15.708).abs() < 1e-3);
+        assert!((character.rotation.x - 15.708).abs() < 1e-3);
+    }
+
+    #[test]
+    fn cape_back_looks_at_the_back() {
+        let mut character = Character::new();
+        let camera = ShotPreset::CapeBack.frame(&mut character, 300, 400);
+        // The player faces -Z; the eye is behind, on +Z, and above the target.
+        let eye = camera.eye_position(DEFAULT_FOV);
+        assert!(eye.z > 0.0 && eye.z.abs() > eye.x.abs() && eye.y > 1.0, "{eye:?}");
+        // The model's middle sits on the target.
+        assert!((character.position.y + camera.scale - 1.0).abs() < 1e-6);
+        assert!(character.posture.left_arm_roll > 0.0 && character.posture.right_arm_roll > 0.0);
+        assert_eq!(ShotPreset::CapeBack.background(), None);
+    }
+}
@@ -0,0 +1,99 @@
+//! Yggdrasil signature checks: session servers sign a profile's `textures` property with
+//! SHA1withRSA (RSASSA-PKCS1-v1_5), so a skin URL can be traced back to the server that issued it.
+//!
+//! The crate bundles no keys. Mojang's key ships with the game's authlib as
+//! `yggdrasil_session_pubkey.der`; authlib-injector servers (Blessing Skin) publish theirs as
+//! `signaturePublickey` in their API root metadata ([`SignatureKey::from_authlib_injector_metadata`]).
+
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use serde_json::Value;
+use sha1::Sha1;
+
+use crate::error::EidolonError;
+use crate::profile::decode_base64;
+
+/// An RSA public key that signs `textures` properties.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureKey {
+    key: RsaPublicKey,
+}
+
+impl SignatureKey {
+    /// A DER `SubjectPublicKeyInfo` (the format of `yggdrasil_session_pubkey.der`).
+    pub fn from_der(der: &[u8]) -> Result<Self, EidolonError> {
+        let key = RsaPublicKey::from_public_key_der(der)
+            .map_err(|e| EidolonError::fetch(format!("not a DER RSA public key: {e}")))?;
+        Ok(Self { key })
+    }
+
+    /// A PEM `-----BEGIN PUBLIC KEY-----` block.
+    pub fn from_pem(pem: &str) -> Result<Self, EidolonError> {
+        let body = pem
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect::<String>();
+        let der = decode_base64(&body)
+            .ok_or_else(|| EidolonError::fetch("PEM public key is not base64"))?;
+        Self::from_der(&der)
+    }
+
+    /// The key in an authlib-injector API root response (`GET <root>`), e.g. Blessing Skin's
+    /// `https://skin.example.org/api/yggdrasil`.
+    pub fn from_authlib_injector_metadata(json: &[u8]) -> Result<Self, EidolonError> {
+        let metadata: Value = serde_json::from_slice(json)
+            .map_err(|e| EidolonError::fetch(format!("malformed server metadata: {e}")))?;
+        let pem = metadata["signaturePublickey"]
+            .as_str()
+            .ok_or_else(|| EidolonError::fetch("server metadata has no 'signaturePublickey'"))?;
+        Self::from_pem(pem)
+    }
+
+    /// Whether `signature` (raw bytes) is this key's SHA1withRSA signature of `data`.
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> bool {
+        let Ok(signature) = Signature::try_from(signature) else {
+            return false;
+        };
+        VerifyingKey::<Sha1>::new(self.key.clone())
+            .verify(data, &signature)
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A 1024-bit test key.
+    pub(crate) const PUBLIC_KEY: &str = "-----BEGIN PUBLIC KEY-----
+MIGfMA0GCSqGSIb3DQEBAQUAA4GNADCBiQKBgQDKwlgAaFsXu0+XzkoWQ0Lb/wUe
+60FRaNVKgz/dTkW3dqJuB4afLTp0rxEnv/LJp1jVsKR+6B6zHVnLtamilKJkPwQN
+vsqz2UWJeoOWE+RDtfsIPKi3ZubIEShe9HcxloRwUsFCVliAkXZmAea1PlZWhUox
+itThSPk5YMeJRiteCwIDAQAB
+-----END PUBLIC KEY-----";
+    /// A `textures` payload and its signature under [`PUBLIC_KEY`].
+    const TEXTURES: &str = "eyJ0aW1lc3RhbXAiOjAsInByb2ZpbGVJZCI6IjA2OWE3OWY0NDRlOTQ3MjZhNWJlZmNhOTBlMzhhYWY1IiwicHJvZmlsZU5hbWUiOiJOb3RjaCIsInRleHR1cmVzIjp7IlNLSU4iOnsidXJsIjoiaHR0cDovL3RleHR1cmVzLmV4YW1wbGUvc2tpbiIsIm1ldGFkYXRhIjp7Im1vZGVsIjoic2xpbSJ9fX19";
+    pub(crate) const SIGNATURE: &str = "fkr+Q3JGNLWhYN/me7OoJaCNgknZu2Z2jhuChAPEfe3ZZsHsxH0KiwqF7aIv9jZBOpOAB4KF/Wvo1r5Vqa/rjkIy0qc3gTMknllttJIk+6H7gqLwwUpgWTCbqCqOj1bOBGJWq9TTJgp5Qk0xWb/LRee88ii5Pzt0Jrh7fJh9A6I=";
+
+    #[test]
+    fn signatures_verify_against_the_signing_key_only() {
+        let key = SignatureKey::from_pem(PUBLIC_KEY).unwrap();
+        let signature = decode_base64(SIGNATURE).unwrap();
+        assert!(key.verify(TEXTURES.as_bytes(), &signature));
+        assert!(!key.verify(b"tampered", &signature));
+        let mut forged = signature.clone();
+        forged[10] ^= 1;
+        assert!(!key.verify(TEXTURES.as_bytes(), &forged));
+    }
+
+    #[test]
+    fn keys_load_from_authlib_injector_metadata() {
+        let metadata = serde_json::json!({ "meta": {}, "signaturePublickey": PUBLIC_KEY });
+        let key = SignatureKey::from_authlib_injector_metadata(metadata.to_string().as_bytes());
+        assert_eq!(key.unwrap(), SignatureKey::from_pem(PUBLIC_KEY).unwrap());
+        assert!(SignatureKey::from_der(b"\x30\x00").is_err());
+        assert!(SignatureKey::from_authlib_injector_metadata(b"{}").is_err());
+    }
+}
@@ -0,0 +1,282 @@
+//! Output sinks: where encoded renders go. [`Renderer::render_to_sink`] and the CLI hand every
+//! finished image to an [`OutputSink`], so a new destination is one trait impl instead of a change
+//! to the renderer.
+//!
+//! Built in are files ([`FileSink`]), standard output ([`StdoutSink`]), memory for HTTP response
+//! bodies ([`MemorySink`]) and Discord webhooks ([`WebhookSink`]). Like the downloading skin
+//! providers, the crate ships no network clients: object stores such as S3 are a closure around
+//! the caller's SDK.
+//!
+//! [`Renderer::render_to_sink`]: crate::renderer::Renderer::render_to_sink
+
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::error::EidolonError;
+use crate::webhook::{DiscordWebhook, HttpPost, WebhookMessage};
+
+/// One encoded render on its way to a sink.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Output {
+    /// File name or relative path, e.g. `"steve.png"`; sinks use it as a path, key or attachment
+    /// name.
+    pub name: String,
+    /// MIME type of `bytes`, e.g. `"image/png"`.
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+impl Output {
+    /// An output whose content type follows the extension of `name` (PNG, WebP, EXR, GIF; other
+    /// names are `application/octet-stream`).
+    pub fn new(name: impl Into<String>, bytes: Vec<u8>) -> Self {
+        let name = name.into();
+        Self {
+            content_type: content_type(&name),
+            name,
+            bytes,
+        }
+    }
+}
+
+/// MIME type for a file name's extension.
+pub(crate) fn content_type(name: &str) -> &'static str {
+    match name.rsplit_once('.').map(|(_, ext)| ext) {
+        Some(ext) if ext.eq_ignore_ascii_case("png") => "image/png",
+        Some(ext) if ext.eq_ignore_ascii_case("webp") => "image/webp",
+        Some(ext) if ext.eq_ignore_ascii_case("gif") => "image/gif",
+        Some(ext) if ext.eq_ignore_ascii_case("exr") => "image/x-exr",
+        _ => "application/octet-stream",
+    }
+}
+
+/// A destination for encoded renders.
+pub trait OutputSink: Send + Sync {
+    fn write(&self, output: &Output) -> Result<(), EidolonError>;
+}
+
+impl<F> OutputSink for F
+where
+    F: Fn(&Output) -> Result<(), EidolonError> + Send + Sync,
+{
+    fn write(&self, output: &Output) -> Result<(), EidolonError> {
+        self(output)
+    }
+}
+
+impl<S: OutputSink + ?Sized> OutputSink for Arc<S> {
+    fn write(&self, output: &Output) -> Result<(), EidolonError> {
+        (**self).write(output)
+    }
+}
+
+/// Writes outputs as files named by [`Output::name`]. Write failures are
+/// [`EidolonError::Texture`], as for every image the renderer saves.
+///
+/// [`FileSink::default`] takes names as paths, like the CLI. With a directory
+/// ([`FileSink::new`]), names must be relative and must not contain `..`, so callers cannot write
+/// outside it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileSink {
+    dir: Option<PathBuf>,
+}
+
+impl FileSink {
+    /// Files under `dir`, which is created on the first write.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: Some(dir.into()),
+        }
+    }
+
+    /// Where `name` is written.
+    pub fn path(&self, name: &str) -> Result<PathBuf, EidolonError> {
+        let Some(dir) = &self.dir else {
+            return Ok(PathBuf::from(name));
+        };
+        let relative = Path::new(name);
+        if name.is_empty()
+            || !relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(EidolonError::invalid_path(format!(
+                "output name '{name}' must be a relative path without '..'"
+            )));
+        }
+        Ok(dir.join(relative))
+    }
+}
+
+impl OutputSink for FileSink {
+    fn write(&self, output: &Output) -> Result<(), EidolonError> {
+        let path = self.path(&output.name)?;
+        if self.dir.is_some() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        std::fs::write(&path, &output.bytes).map_err(|e| {
+            EidolonError::texture(format!("failed to save image {}: {e}", path.display()))
+        })
+    }
+}
+
+/// Writes output bytes to standard output, for piping (`eidolon render skin.png - | ...`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StdoutSink;
+
+impl OutputSink for StdoutSink {
+    fn write(&self, output: &Output) -> Result<(), EidolonError> {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&output.bytes)?;
+        Ok(stdout.flush()?)
+    }
+}
+
+/// Keeps outputs in memory, e.g. to answer an HTTP request with the rendered bytes.
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    outputs: Mutex<Vec<Output>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The outputs written so far, oldest first; the sink is empty afterwards.
+    pub fn take(&self) -> Vec<Output> {
+        std::mem::take(&mut *self.outputs.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl OutputSink for MemorySink {
+    fn write(&self, output: &Output) -> Result<(), EidolonError> {
+        self.outputs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(output.clone());
+        Ok(())
+    }
+}
+
+/// Posts each output to a Discord webhook as an attachment named after the last component of
+/// [`Output::name`].
+#[derive(Debug, Clone)]
+pub struct WebhookSink<H> {
+    webhook: DiscordWebhook,
+    http: H,
+    /// Message text posted with every image.
+    pub content: Option<String>,
+}
+
+impl<H: HttpPost> WebhookSink<H> {
+    pub fn new(webhook: DiscordWebhook, http: H) -> Self {
+        Self {
+            webhook,
+            http,
+            content: None,
+        }
+    }
+}
+
+impl<H: HttpPost> OutputSink for WebhookSink<H> {
+    fn write(&self, output: &Output) -> Result<(), EidolonError> {
+        let file_name = Path::new(&output.name)
+            .file_name()
+            .map_or_else(|| output.name.clone(), |n| n.to_string_lossy().into_owned());
+        let mut message = WebhookMessage::new(output.bytes.clone(), file_name);
+        message.content = self.content.clone();
+        self.webhook.post(&message, &self.http)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("eidolon-sink-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn content_types_follow_the_extension() {
+        assert_eq!(Output::new("a.PNG", vec![]).content_type, "image/png");
+        assert_eq!(Output::new("a/b.webp", vec![]).content_type, "image/webp");
+        assert_eq!(Output::new("a.exr", vec![]).content_type, "image/x-exr");
+        assert_eq!(Output::new("a.gif", vec![]).content_type, "image/gif");
+        assert_eq!(
+            Output::new("-", vec![]).content_type,
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn file_sink_stays_inside_its_directory() {
+        let dir = temp_dir("files");
+        let sink = FileSink::new(&dir);
+        sink.write(&Output::new("renders/steve.png", b"png".to_vec()))
+            .unwrap();
+        assert_eq!(std::fs::read(dir.join("renders/steve.png")).unwrap(), b"png");
+        for name in ["", "../escape.png", "/tmp/abs.png", "a/../../b.png"] {
+            assert!(
+                matches!(
+                    sink.write(&Output::new(name, vec![])),
+                    Err(EidolonError::InvalidPath(_))
+                ),
+                "{name}"
+            );
+        }
+        assert_eq!(
+            FileSink::default().path("/tmp/abs.png").unwrap(),
+            PathBuf::from("/tmp/abs.png")
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn memory_and_closure_sinks_see_every_output() {
+        let memory = Arc::new(MemorySink::new());
+        let outputs = [
+            Output::new("a.png", b"a".to_vec()),
+            Output::new("b.webp", b"b".to_vec()),
+        ];
+        let forward = {
+            let memory = Arc::clone(&memory);
+            move |output: &Output| memory.write(output)
+        };
+        let sinks: Vec<Box<dyn OutputSink>> = vec![Box::new(forward), Box::new(Arc::clone(&memory))];
+        for output in &outputs {
+            for sink in &sinks {
+                sink.write(output).unwrap();
+            }
+        }
+        let names: Vec<_> = memory.take().into_iter().map(|o| o.name).collect();
+        assert_eq!(names, ["a.png", "a.png", "b.webp", "b.webp"]);
+        assert!(memory.take().is_empty());
+    }
+
+    #[test]
+    fn webhook_sink_attaches_the_file_name() {
+        let sent = Mutex::new(Vec::new());
+        let http = |_: &str, content_type: &str, body: &[u8]| {
+            sent.lock()
+                .unwrap()
+                .push((content_type.to_string(), body.to_vec()));
+            Ok(Vec::new())
+        };
+        let webhook = DiscordWebhook::new("https://discord.com/api/webhooks/1/token").unwrap();
+        let sink = WebhookSink::new(webhook, &http);
+        sink.write(&Output::new("renders/steve.webp", b"RIFF".to_vec()))
+            .unwrap();
+        let sent = sent.into_inner().unwrap();
+        let body = String::from_utf8_lossy(&sent[0].1);
+        assert!(sent[0].0.starts_with("multipart/form-data"));
+        assert!(body.contains("filename=\"steve.webp\""));
+        assert!(body.contains("Content-Type: image/webp"));
+    }
+}
@@ -0,0 +1,615 @@
+//! Where each body part lives in the skin atlas: the box unwrap of the six parts, for both the
+//! base layer and the overlay (hat, jacket, sleeves, pants).
+//!
+//! Coordinates are in 64px atlas texels; HD skins scale them by `width / 64`. Texels outside the
+//! parts' faces are never drawn, so statistics over a skin should only look at these.
+//! [`region_stats`] summarizes the texels of a [`Region`] (average color, color histogram,
+//! overlay usage) for moderation heuristics and UI colors.
+
+use std::collections::VecDeque;
+
+use image::{imageops, Rgba, RgbaImage};
+
+use crate::character::{Bone, SkinType};
+use crate::texture::SkinImage;
+
+/// Atlas rectangle `(x, y, width, height)` in 64px coordinates.
+pub type Rect = (u32, u32, u32, u32);
+
+/// Skin layer: the base layer, or the overlay drawn slightly outside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layer {
+    Base,
+    Overlay,
+}
+
+/// Atlas placement of one body part's box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PartLayout {
+    pub bone: Bone,
+    /// Top-left corner of the base layer's unwrap.
+    pub base: (u32, u32),
+    /// Top-left corner of the overlay's unwrap.
+    pub overlay: (u32, u32),
+    /// Box width, height and depth in texels (classic arms; slim arms are one narrower).
+    pub size: (u32, u32, u32),
+}
+
+/// Every body part, in the order of [`Bone`].
+pub const PARTS: [PartLayout; 6] = [
+    PartLayout {
+        bone: Bone::Head,
+        base: (0, 0),
+        overlay: (32, 0),
+        size: (8, 8, 8),
+    },
+    PartLayout {
+        bone: Bone::Body,
+        base: (16, 16),
+        overlay: (16, 32),
+        size: (8, 12, 4),
+    },
+    PartLayout {
+        bone: Bone::RightArm,
+        base: (40, 16),
+        overlay: (40, 32),
+        size: (4, 12, 4),
+    },
+    PartLayout {
+        bone: Bone::LeftArm,
+        base: (32, 48),
+        overlay: (48, 48),
+        size: (4, 12, 4),
+    },
+    PartLayout {
+        bone: Bone::RightLeg,
+        base: (0, 16),
+        overlay: (0, 32),
+        size: (4, 12, 4),
+    },
+    PartLayout {
+        bone: Bone::LeftLeg,
+        base: (16, 48),
+        overlay: (0, 48),
+        size: (4, 12, 4),
+    },
+];
+
+/// Layout of `bone`'s part.
+pub fn part(bone: Bone) -> &'static PartLayout {
+    PARTS
+        .iter()
+        .find(|part| part.bone == bone)
+        .expect("every bone has a part")
+}
+
+impl PartLayout {
+    /// Box size for `skin_type`: slim arms are 3 wide.
+    pub fn size_for(&self, skin_type: SkinType) -> (u32, u32, u32) {
+        let (w, h, d) = self.size;
+        let arm = matches!(self.bone, Bone::RightArm | Bone::LeftArm);
+        if arm && skin_type == SkinType::Slim {
+            (w - 1, h, d)
+        } else {
+            (w, h, d)
+        }
+    }
+
+    /// The six faces of `layer` in the unwrap: top, bottom, right, front, left, back. The faces
+    /// of both layers line up, so texel `(x, y)` of a base face sits under the same texel of the
+    /// overlay face.
+    pub fn faces(&self, layer: Layer, skin_type: SkinType) -> [Rect; 6] {
+        let (x, y) = match layer {
+            Layer::Base => self.base,
+            Layer::Overlay => self.overlay,
+        };
+        let (w, h, d) = self.size_for(skin_type);
+        [
+            (x + d, y, w, d),
+            (x + d + w, y, w, d),
+            (x, y + d, d, h),
+            (x + d, y + d, w, h),
+            (x + d + w, y + d, d, h),
+            (x + 2 * d + w, y + d, w, h),
+        ]
+    }
+}
+
+/// Every texel of `bone`'s part as seen from outside: the overlay where it is mostly opaque
+/// (alpha ≥ 128), the base layer under it otherwise. HD skins give `scale²` texels per 64px
+/// texel; skins smaller than 64px have none.
+pub fn visible_texels(skin: &SkinImage, bone: Bone, skin_type: SkinType) -> Vec<Rgba<u8>> {
+    let mut texels = Vec::new();
+    for_each_texel(
+        skin,
+        bone,
+        &[0, 1, 2, 3, 4, 5],
+        skin_type,
+        |base, overlay| {
+            texels.push(if overlay[3] >= 128 { overlay } else { base });
+        },
+    );
+    texels
+}
+
+/// Whether the base layer of `bone`'s part draws nothing: every texel is transparent (the shader
+/// discards alpha below 0.01) or lies outside `rgba`.
+pub(crate) fn base_is_blank(rgba: &RgbaImage, bone: Bone, skin_type: SkinType) -> bool {
+    let scale = rgba.width() / 64;
+    !part(bone)
+        .faces(Layer::Base, skin_type)
+        .iter()
+        .any(|&(x, y, w, h)| {
+            (y * scale..(y + h) * scale).any(|py| {
+                (x * scale..(x + w) * scale)
+                    .any(|px| rgba.get_pixel_checked(px, py).is_some_and(|p| p[3] >= 3))
+            })
+        })
+}
+
+/// A 64px skin whose base layer is `color` on every part (classic arms, which cover slim ones)
+/// and whose overlay is empty.
+pub(crate) fn mannequin(color: [u8; 4]) -> RgbaImage {
+    let mut rgba = RgbaImage::new(64, 64);
+    for part in &PARTS {
+        for (x, y, w, h) in part.faces(Layer::Base, SkinType::Classic) {
+            for py in y..y + h {
+                for px in x..x + w {
+                    rgba.put_pixel(px, py, Rgba(color));
+                }
+            }
+        }
+    }
+    rgba
+}
+
+/// Fill the texels outside every face (of the classic layout, which covers slim arms) with a
+/// copy of the nearest face texel, alpha included.
+///
+/// Filtered or mipmapped sampling near a face's edge reaches into its neighbours; with the
+/// unused texels usually transparent black, the faces get dark, see-through seams. Padding makes
+/// those samples repeat the face's own border instead. Face texels are left unchanged, so renders
+/// with nearest sampling stay the same. Textures narrower than 64 pixels or not square are left
+/// alone.
+pub(crate) fn pad_edges(rgba: &mut RgbaImage) {
+    let (width, height) = rgba.dimensions();
+    let scale = width / 64;
+    if scale == 0 || width != height {
+        return;
+    }
+    // Breadth-first from every face texel: each unused texel takes the texel that reached it.
+    let mut source: Vec<Option<u32>> = vec![None; (width * height) as usize];
+    let mut queue = VecDeque::new();
+    for part in &PARTS {
+        for layer in [Layer::Base, Layer::Overlay] {
+            for (x, y, w, h) in part.faces(layer, SkinType::Classic) {
+                for py in y * scale..(y + h) * scale {
+                    for px in x * scale..(x + w) * scale {
+                        let index = py * width + px;
+                        source[index as usize] = Some(index);
+                        queue.push_back(index);
+                    }
+                }
+            }
+        }
+    }
+    while let Some(index) = queue.pop_front() {
+        let (x, y) = (index % width, index / width);
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for (nx, ny) in neighbours {
+            if nx < width && ny < height {
+                let neighbour = (ny * width + nx) as usize;
+                if source[neighbour].is_none() {
+                    source[neighbour] = source[index as usize];
+                    queue.push_back(neighbour as u32);
+                }
+            }
+        }
+    }
+    for (index, from) in source.into_iter().enumerate() {
+        let index = index as u32;
+        match from {
+            Some(from) if from != index => {
+                let texel = *rgba.get_pixel(from % width, from / width);
+                rgba.put_pixel(index % width, index / width, texel);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Call `f(base, overlay)` for every texel of the listed faces (indices into
+/// [`PartLayout::faces`]) of `bone`'s part.
+fn for_each_texel(
+    skin: &SkinImage,
+    bone: Bone,
+    faces: &[usize],
+    skin_type: SkinType,
+    mut f: impl FnMut(Rgba<u8>, Rgba<u8>),
+) {
+    let part = part(bone);
+    let rgba = skin.rgba();
+    let scale = rgba.width() / 64;
+    let base = part.faces(Layer::Base, skin_type);
+    let overlay = part.faces(Layer::Overlay, skin_type);
+    for &face in faces {
+        let (bx, by, w, h) = base[face];
+        let (ox, oy, _, _) = overlay[face];
+        for y in 0..h * scale {
+            for x in 0..w * scale {
+                f(
+                    *rgba.get_pixel(bx * scale + x, by * scale + y),
+                    *rgba.get_pixel(ox * scale + x, oy * scale + y),
+                );
+            }
+        }
+    }
+}
+
+/// A group of atlas faces that services look at together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Region {
+    /// Front of the head.
+    Face,
+    /// All six faces of the head.
+    Head,
+    /// All faces of the body.
+    Torso,
+    /// Front of the body.
+    TorsoFront,
+    /// Both arms.
+    Arms,
+    /// Both legs.
+    Legs,
+}
+
+impl Region {
+    pub const ALL: [Region; 6] = [
+        Region::Face,
+        Region::Head,
+        Region::Torso,
+        Region::TorsoFront,
+        Region::Arms,
+        Region::Legs,
+    ];
+
+    /// Parts and face indices (see [`PartLayout::faces`]) the region covers.
+    fn faces(self) -> &'static [(Bone, &'static [usize])] {
+        const ALL: &[usize] = &[0, 1, 2, 3, 4, 5];
+        const FRONT: &[usize] = &[3];
+        match self {
+            Region::Face => &[(Bone::Head, FRONT)],
+            Region::Head => &[(Bone::Head, ALL)],
+            Region::Torso => &[(Bone::Body, ALL)],
+            Region::TorsoFront => &[(Bone::Body, FRONT)],
+            Region::Arms => &[(Bone::RightArm, ALL), (Bone::LeftArm, ALL)],
+            Region::Legs => &[(Bone::RightLeg, ALL), (Bone::LeftLeg, ALL)],
+        }
+    }
+}
+
+/// The faces of `region` cut from the atlas side by side (top-aligned, parts in region order, faces
+/// in [`PartLayout::faces`] order) with the overlay composited over the base layer. Single-face
+/// regions give just that face, e.g. the 8×8 front of the head for [`Region::Face`]. HD skins give
+/// HD crops.
+pub fn region_crop(skin: &SkinImage, region: Region, skin_type: SkinType) -> RgbaImage {
+    let rgba = skin.rgba();
+    let scale = (rgba.width() / 64).max(1);
+    let rects: Vec<(Rect, Rect)> = region
+        .faces()
+        .iter()
+        .flat_map(|&(bone, faces)| {
+            let part = part(bone);
+            let base = part.faces(Layer::Base, skin_type);
+            let overlay = part.faces(Layer::Overlay, skin_type);
+            faces.iter().map(move |&face| (base[face], overlay[face]))
+        })
+        .collect();
+    let width = rects.iter().map(|(rect, _)| rect.2).sum::<u32>();
+    let height = rects.iter().map(|(rect, _)| rect.3).max().unwrap_or(0);
+    let mut crop = RgbaImage::new(width * scale, height * scale);
+    if rgba.width() < 64 {
+        return crop;
+    }
+    let mut left = 0;
+    for ((bx, by, w, h), (ox, oy, _, _)) in rects {
+        let base = imageops::crop_imm(rgba, bx * scale, by * scale, w * scale, h * scale);
+        let overlay = imageops::crop_imm(rgba, ox * scale, oy * scale, w * scale, h * scale);
+        let mut face = base.to_image();
+        imageops::overlay(&mut face, &*overlay, 0, 0);
+        imageops::replace(&mut crop, &face, i64::from(left * scale), 0);
+        left += w;
+    }
+    crop
+}
+
+/// Color counts in 64 bins, 4 levels per RGB channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    pub bins: [u32; 64],
+}
+
+impl Histogram {
+    /// Bin of `color`: `r / 64 * 16 + g / 64 * 4 + b / 64`.
+    pub fn bin([r, g, b]: [u8; 3]) -> usize {
+        (r as usize / 64) * 16 + (g as usize / 64) * 4 + b as usize / 64
+    }
+
+    /// Center color of bin `index`.
+    pub fn bin_color(index: usize) -> [u8; 3] {
+        [index / 16, index / 4 % 4, index % 4].map(|level| (level * 64 + 32) as u8)
+    }
+
+    /// Number of counted texels.
+    pub fn total(&self) -> u32 {
+        self.bins.iter().sum()
+    }
+
+    /// Fullest bin (the first one on ties), or `None` when nothing was counted.
+    pub fn dominant(&self) -> Option<usize> {
+        let max = *self.bins.iter().max()?;
+        (max > 0).then(|| self.bins.iter().position(|&count| count == max).unwrap())
+    }
+}
+
+/// Color statistics of one [`Region`], over the texels as seen from outside (see
+/// [`visible_texels`]). Fully transparent texels are left out of the colors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionStats {
+    /// Mean RGB of the visible opaque texels; black when there are none.
+    pub average: [u8; 3],
+    /// Number of visible texels with any opacity.
+    pub opaque: u32,
+    /// Number of texels in the region (HD skins count every pixel).
+    pub texels: u32,
+    /// Share of overlay texels with any opacity, `0.0–1.0`.
+    pub overlay_used: f32,
+    /// Share of overlay texels opaque enough (alpha ≥ 128) to hide the base layer.
+    pub overlay_covering: f32,
+    /// Histogram of the visible opaque texels.
+    pub histogram: Histogram,
+}
+
+/// Statistics of `region` in `skin` as drawn with `skin_type` arms.
+pub fn region_stats(skin: &SkinImage, region: Region, skin_type: SkinType) -> RegionStats {
+    let mut sum = [0u64; 3];
+    let mut opaque = 0;
+    let mut texels = 0;
+    let mut used = 0;
+    let mut covering = 0;
+    let mut histogram = Histogram { bins: [0; 64] };
+    for &(bone, faces) in region.faces() {
+        for_each_texel(skin, bone, faces, skin_type, |base, overlay| {
+            texels += 1;
+            used += u32::from(overlay[3] > 0);
+            covering += u32::from(overlay[3] >= 128);
+            let visible = if overlay[3] >= 128 { overlay } else { base };
+            if visible[3] > 0 {
+                opaque += 1;
+                let rgb = [visible[0], visible[1], visible[2]];
+                for (sum, c) in sum.iter_mut().zip(rgb) {
+                    *sum += u64::from(c);
+                }
+                histogram.bins[Histogram::bin(rgb)] += 1;
+            }
+        });
+    }
+    let share = |count: u32| {
+        if texels > 0 {
+            count as f32 / texels as f32
+        } else {
+            0.0
+        }
+    };
+    RegionStats {
+        average: sum.map(|c| {
+            (c + u64::from(opaque) / 2)
+                .checked_div(u64::from(opaque))
+                .unwrap_or(0) as u8
+        }),
+        opaque,
+        texels,
+        overlay_used: share(used),
+        overlay_covering: share(covering),
+        histogram,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn faces_tile_the_classic_atlas_without_overlap() {
+        let mut owner = vec![None; 64 * 64];
+        for part in &PARTS {
+            for layer in [Layer::Base, Layer::Overlay] {
+                for (x, y, w, h) in part.faces(layer, SkinType::Classic) {
+                    assert!(x + w <= 64 && y + h <= 64, "{part:?} {layer:?}");
+                    for ty in y..y + h {
+                        for tx in x..x + w {
+                            let slot = &mut owner[(ty * 64 + tx) as usize];
+                            assert_eq!(*slot, None, "texel {tx},{ty} used twice");
+                            *slot = Some((part.bone, layer));
+                        }
+                    }
+                }
+            }
+        }
+        // Each part covers 2(wh + wd + hd) texels per layer.
+        let used = owner.iter().filter(|o| o.is_some()).count();
+        assert_eq!(used, 2 * (384 + 352 + 4 * 224));
+    }
+
+    #[test]
+    fn head_front_is_the_face() {
+        let front = part(Bone::Head).faces(Layer::Base, SkinType::Classic)[3];
+        assert_eq!(front, (8, 8, 8, 8));
+        let hat = part(Bone::Head).faces(Layer::Overlay, SkinType::Classic)[3];
+        assert_eq!(hat, (40, 8, 8, 8));
+    }
+
+    #[test]
+    fn slim_arms_are_narrower() {
+        let arm = part(Bone::LeftArm);
+        let classic = arm.faces(Layer::Base, SkinType::Classic);
+        let slim = arm.faces(Layer::Base, SkinType::Slim);
+        assert_eq!(classic[3].2, 4);
+        assert_eq!(slim[3].2, 3);
+        // The back moves one texel in with the narrower front.
+        assert_eq!(slim[5].0 + 1, classic[5].0);
+    }
+
+    #[test]
+    fn overlay_covers_the_base_where_opaque() {
+        let mut rgba = RgbaImage::from_pixel(64, 64, Rgba([10, 20, 30, 255]));
+        // Half-transparent hat everywhere, one opaque hat texel on the face.
+        for (x, y, w, h) in part(Bone::Head).faces(Layer::Overlay, SkinType::Classic) {
+            for ty in y..y + h {
+                for tx in x..x + w {
+                    rgba.put_pixel(tx, ty, Rgba([200, 0, 0, 100]));
+                }
+            }
+        }
+        rgba.put_pixel(40, 8, Rgba([0, 200, 0, 255]));
+        let skin = SkinImage::from_rgba(rgba).unwrap();
+        let texels = visible_texels(&skin, Bone::Head, SkinType::Classic);
+        assert_eq!(texels.len(), 6 * 64);
+        let green = texels.iter().filter(|t| t.0 == [0, 200, 0, 255]).count();
+        assert_eq!(green, 1);
+        assert!(texels.iter().all(|t| t[0] != 200));
+    }
+
+    #[test]
+    fn region_stats_average_and_overlay_usage() {
+        let mut rgba = RgbaImage::new(64, 64);
+        let fill = |rgba: &mut RgbaImage, (x, y, w, h): Rect, color: [u8; 4]| {
+            for ty in y..y + h {
+                for tx in x..x + w {
+                    rgba.put_pixel(tx, ty, Rgba(color));
+                }
+            }
+        };
+        let head = part(Bone::Head);
+        // Face half red, half blue; the hat covers a quarter of the face.
+        let (x, y, _, _) = head.faces(Layer::Base, SkinType::Classic)[3];
+        fill(&mut rgba, (x, y, 4, 8), [200, 0, 0, 255]);
+        fill(&mut rgba, (x + 4, y, 4, 8), [0, 0, 200, 255]);
+        let (hx, hy, _, _) = head.faces(Layer::Overlay, SkinType::Classic)[3];
+        fill(&mut rgba, (hx, hy, 8, 2), [0, 200, 0, 255]);
+        // A faint overlay texel is used but does not cover.
+        rgba.put_pixel(hx, hy + 7, Rgba([255, 255, 255, 40]));
+        let skin = SkinImage::from_rgba(rgba).unwrap();
+
+        let face = region_stats(&skin, Region::Face, SkinType::Classic);
+        assert_eq!(face.texels, 64);
+        assert_eq!(face.opaque, 64);
+        assert_eq!(face.overlay_covering, 16.0 / 64.0);
+        assert_eq!(face.overlay_used, 17.0 / 64.0);
+        // 24 red, 24 blue and 16 green texels.
+        assert_eq!(face.average, [75, 50, 75]);
+        assert_eq!(face.histogram.total(), 64);
+        assert_eq!(face.histogram.bins[Histogram::bin([200, 0, 0])], 24);
+        assert_eq!(face.histogram.bins[Histogram::bin([0, 200, 0])], 16);
+
+        // Nothing painted on the legs.
+        let legs = region_stats(&skin, Region::Legs, SkinType::Classic);
+        assert_eq!(legs.texels, 2 * 224);
+        assert_eq!(legs.opaque, 0);
+        assert_eq!(legs.average, [0, 0, 0]);
+        assert_eq!(legs.histogram.dominant(), None);
+    }
+
+    #[test]
+    fn region_crops_place_faces_side_by_side() {
+        let mut rgba = RgbaImage::new(64, 64);
+        rgba.put_pixel(8, 8, Rgba([255, 0, 0, 255]));
+        rgba.put_pixel(9, 9, Rgba([10, 20, 30, 255]));
+        // A hat texel over the face's bottom-left corner.
+        rgba.put_pixel(40, 15, Rgba([0, 255, 0, 255]));
+        // The left leg's front starts at (20, 52).
+        rgba.put_pixel(20, 52, Rgba([0, 0, 255, 255]));
+        let skin = SkinImage::from_rgba(rgba).unwrap();
+
+        let face = region_crop(&skin, Region::Face, SkinType::Classic);
+        assert_eq!(face.dimensions(), (8, 8));
+        assert_eq!(face.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(face.get_pixel(0, 7).0, [0, 255, 0, 255]);
+        assert_eq!(face.get_pixel(1, 1).0, [10, 20, 30, 255]);
+
+        let legs = region_crop(&skin, Region::Legs, SkinType::Classic);
+        assert_eq!(legs.dimensions(), (2 * (4 + 4 + 4 + 4 + 4 + 4), 12));
+        assert_eq!(legs.get_pixel(24 + 4 + 4 + 4, 0).0, [0, 0, 255, 255]);
+        let arms = region_crop(&skin, Region::Arms, SkinType::Slim);
+        assert_eq!(arms.width(), 2 * (3 + 3 + 4 + 3 + 4 + 3));
+    }
+
+    #[test]
+    fn histogram_bins_round_trip() {
+        for index in 0..64 {
+            assert_eq!(Histogram::bin(Histogram::bin_color(index)), index);
+        }
+        assert_eq!(Histogram::bin([255, 255, 255]), 63);
+    }
+
+    #[test]
+    fn padding_extends_faces_into_unused_texels() {
+        // A 2× HD skin with only the body's base layer painted.
+        let mut rgba = RgbaImage::new(128, 128);
+        for (x, y, w, h) in part(Bone::Body).faces(Layer::Base, SkinType::Classic) {
+            for ty in 2 * y..2 * (y + h) {
+                for tx in 2 * x..2 * (x + w) {
+                    rgba.put_pixel(tx, ty, Rgba([10, 200, 10, 255]));
+                }
+            }
+        }
+        let faces = rgba.clone();
+        pad_edges(&mut rgba);
+        // The unused block left of the body's top face repeats the face's border...
+        assert_eq!(rgba.get_pixel(39, 34), &Rgba([10, 200, 10, 255]));
+        // ...while the unused corner next to the empty head stays empty.
+        assert_eq!(rgba.get_pixel(2, 2)[3], 0);
+        // Face texels never change.
+        for part in &PARTS {
+            for layer in [Layer::Base, Layer::Overlay] {
+                for (x, y, w, h) in part.faces(layer, SkinType::Classic) {
+                    for ty in 2 * y..2 * (y + h) {
+                        for tx in 2 * x..2 * (x + w) {
+                            assert_eq!(rgba.get_pixel(tx, ty), faces.get_pixel(tx, ty));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blank_parts_are_transparent_or_missing() {
+        let gray = [150, 150, 150, 255];
+        let mannequin = mannequin(gray);
+        for part in &PARTS {
+            for skin_type in [SkinType::Classic, SkinType::Slim] {
+                assert!(!base_is_blank(&mannequin, part.bone, skin_type));
+            }
+            let (x, y, _, _) = part.faces(Layer::Overlay, SkinType::Classic)[3];
+            assert_eq!(mannequin.get_pixel(x, y)[3], 0);
+        }
+
+        // Only the head painted; faint texels are discarded by the shader.
+        let mut rgba = RgbaImage::new(64, 64);
+        rgba.put_pixel(8, 8, Rgba(gray));
+        rgba.put_pixel(20, 20, Rgba([255, 255, 255, 2]));
+        assert!(!base_is_blank(&rgba, Bone::Head, SkinType::Classic));
+        assert!(base_is_blank(&rgba, Bone::Body, SkinType::Classic));
+        // Textures too small for a part have nothing there.
+        let small = RgbaImage::from_pixel(64, 32, Rgba(gray));
+        assert!(!base_is_blank(&small, Bone::RightLeg, SkinType::Classic));
+        assert!(base_is_blank(&small, Bone::LeftLeg, SkinType::Classic));
+    }
+}
@@ -0,0 +1,313 @@
+//! Terminal previews of rendered images, for checking poses and camera settings on headless
+//! machines (over SSH) without copying files around.
+//!
+//! [`to_ansi`] works in any terminal with 24-bit color; the image is downsampled to fit.
+//! Terminals with inline graphics show the render at full resolution: [`to_kitty`] for the Kitty
+//! graphics protocol (kitty, WezTerm, Ghostty, Konsole) and [`to_sixel`] for Sixel (xterm
+//! `-ti vt340`, foot, mlterm, Windows Terminal).
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::io::Write as _;
+
+use flate2::{write::ZlibEncoder, Compression};
+use image::{imageops, Rgba, RgbaImage};
+
+use crate::profile::encode_base64;
+
+/// Pixels at or above this alpha are drawn; the rest show the terminal background.
+const OPAQUE: u8 = 128;
+
+/// Largest base64 payload per Kitty escape sequence, as the protocol requires.
+const KITTY_CHUNK: usize = 4096;
+
+/// Sixel palette size most terminals support.
+const SIXEL_COLORS: usize = 256;
+
+/// Levels per channel of the fallback Sixel palette (6 × 7 × 6 = 252 colors).
+const CUBE: [u32; 3] = [6, 7, 6];
+
+/// Draw `image` with `▀` half blocks in 24-bit ANSI color, two pixel rows per text line, at most
+/// `columns` characters wide (images are never enlarged). Every line ends with a color reset.
+pub fn to_ansi(image: &RgbaImage, columns: u32) -> String {
+    let image = fit_width(image, columns);
+    let mut out = String::new();
+    for y in (0..image.height()).step_by(2) {
+        // Whether colors are reset, as at the start of each line.
+        let mut reset = true;
+        for x in 0..image.width() {
+            let top = image.get_pixel(x, y);
+            let bottom = (y + 1 < image.height()).then(|| image.get_pixel(x, y + 1));
+            let visible = |p: &Rgba<u8>| p[3] >= OPAQUE;
+            match (visible(top), bottom.filter(|p| visible(p))) {
+                (true, Some(bottom)) => {
+                    let _ = write!(out, "{}{}▀", fg(top), bg(bottom));
+                }
+                (true, None) => {
+                    let _ = write!(out, "\x1b[49m{}▀", fg(top));
+                }
+                (false, Some(bottom)) => {
+                    let _ = write!(out, "\x1b[49m{}▄", fg(bottom));
+                }
+                (false, None) => {
+                    if !reset {
+                        out.push_str("\x1b[0m");
+                    }
+                    out.push(' ');
+                    reset = true;
+                    continue;
+                }
+            }
+            reset = false;
+        }
+        out.push_str("\x1b[0m\n");
+    }
+    out
+}
+
+/// Display `image` inline with the Kitty graphics protocol: zlib-compressed RGBA, split into
+/// chunks, with terminal replies suppressed. Ends with a newline so the cursor moves below it.
+pub fn to_kitty(image: &RgbaImage) -> String {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    // Writing into a Vec cannot fail.
+    let _ = encoder.write_all(image.as_raw());
+    let payload = encode_base64(&encoder.finish().unwrap_or_default());
+
+    let mut out = String::with_capacity(payload.len() + payload.len() / KITTY_CHUNK * 16 + 64);
+    let chunks: Vec<_> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        out.push_str("\x1b_G");
+        if i == 0 {
+            let _ = write!(
+                out,
+                "a=T,q=2,f=32,o=z,s={},v={},",
+                image.width(),
+                image.height()
+            );
+        }
+        let _ = write!(out, "m={more};");
+        // Base64 is ASCII, so every chunk is valid UTF-8.
+        out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        out.push_str("\x1b\\");
+    }
+    out.push('\n');
+    out
+}
+
+/// Encode `image` as Sixel graphics with at most 256 colors: the image's own colors when it has
+/// few enough (typical for skin renders without effects), otherwise a fixed color cube.
+/// Transparent pixels show the terminal background.
+pub fn to_sixel(image: &RgbaImage) -> String {
+    let (width, height) = image.dimensions();
+    let palette = SixelPalette::new(image);
+
+    // P2 = 1: pixels left unset keep the background.
+    let mut out = format!("\x1bP0;1;0q\"1;1;{width};{height}");
+    for (i, [r, g, b]) in palette.colors().iter().enumerate() {
+        let percent = |c: u8| (u32::from(c) * 100 + 127) / 255;
+        let _ = write!(
+            out,
+            "#{i};2;{};{};{}",
+            percent(*r),
+            percent(*g),
+            percent(*b)
+        );
+    }
+    for band in (0..height).step_by(6) {
+        // Sixel bits per column, for each color in this band of six rows.
+        let mut rows: BTreeMap<usize, Vec<u8>> = BTreeMap::new();
+        for dy in 0..6.min(height - band) {
+            for x in 0..width {
+                let p = image.get_pixel(x, band + dy);
+                if p[3] >= OPAQUE {
+                    let bits = rows
+                        .entry(palette.index(p))
+                        .or_insert_with(|| vec![0; width as usize]);
+                    bits[x as usize] |= 1 << dy;
+                }
+            }
+        }
+        for (n, (color, bits)) in rows.iter().enumerate() {
+            if n > 0 {
+                // Back to the start of the band for the next color.
+                out.push('$');
+            }
+            let _ = write!(out, "#{color}");
+            let end = bits.iter().rposition(|&b| b != 0).map_or(0, |x| x + 1);
+            let mut x = 0;
+            while x < end {
+                let run = bits[x..end].iter().take_while(|&&b| b == bits[x]).count();
+                let c = char::from(63 + bits[x]);
+                if run > 3 {
+                    let _ = write!(out, "!{run}{c}");
+                } else {
+                    out.extend(std::iter::repeat_n(c, run));
+                }
+                x += run;
+            }
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\\n");
+    out
+}
+
+/// Sixel palette colors and how pixels map onto them.
+enum SixelPalette {
+    /// The image's own colors, indexed in order of appearance.
+    Exact(HashMap<[u8; 3], usize>),
+    /// A [`CUBE`] of evenly spaced levels per channel.
+    Cube,
+}
+
+impl SixelPalette {
+    fn new(image: &RgbaImage) -> Self {
+        let mut exact = HashMap::new();
+        for p in image.pixels().filter(|p| p[3] >= OPAQUE) {
+            let len = exact.len();
+            exact.entry([p[0], p[1], p[2]]).or_insert(len);
+            if exact.len() > SIXEL_COLORS {
+                return Self::Cube;
+            }
+        }
+        Self::Exact(exact)
+    }
+
+    fn colors(&self) -> Vec<[u8; 3]> {
+        match self {
+            Self::Exact(exact) => {
+                let mut colors = vec![[0; 3]; exact.len()];
+                for (&color, &i) in exact {
+                    colors[i] = color;
+                }
+                colors
+            }
+            Self::Cube => (0..CUBE[0])
+                .flat_map(|r| (0..CUBE[1]).flat_map(move |g| (0..CUBE[2]).map(move |b| [r, g, b])))
+                .map(|levels| [0, 1, 2].map(|c| (levels[c] * 255 / (CUBE[c] - 1)) as u8))
+                .collect(),
+        }
+    }
+
+    fn index(&self, p: &Rgba<u8>) -> usize {
+        match self {
+            Self::Exact(exact) => exact[&[p[0], p[1], p[2]]],
+            Self::Cube => {
+                let [r, g, b] = [0, 1, 2].map(|c| (u32::from(p[c]) * (CUBE[c] - 1) + 127) / 255);
+                ((r * CUBE[1] + g) * CUBE[2] + b) as usize
+            }
+        }
+    }
+}
+
+/// `image` scaled down to at most `columns` pixels wide, keeping its aspect ratio.
+fn fit_width(image: &RgbaImage, columns: u32) -> RgbaImage {
+    let columns = columns.max(1);
+    if image.width() <= columns {
+        return image.clone();
+    }
+    let height = (u64::from(image.height()) * u64::from(columns) / u64::from(image.width())).max(1);
+    imageops::resize(
+        image,
+        columns,
+        height as u32,
+        imageops::FilterType::Triangle,
+    )
+}
+
+fn fg(p: &Rgba<u8>) -> String {
+    format!("\x1b[38;2;{};{};{}m", p[0], p[1], p[2])
+}
+
+fn bg(p: &Rgba<u8>) -> String {
+    format!("\x1b[48;2;{};{};{}m", p[0], p[1], p[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_blocks_carry_both_rows() {
+        let mut image = RgbaImage::new(3, 3);
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        image.put_pixel(0, 1, Rgba([0, 0, 255, 255]));
+        image.put_pixel(1, 1, Rgba([0, 255, 0, 255]));
+        image.put_pixel(2, 2, Rgba([1, 2, 3, 255]));
+        let ansi = to_ansi(&image, 80);
+        let lines: Vec<_> = ansi.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m▀\x1b[49m\x1b[38;2;0;255;0m▄\x1b[0m \x1b[0m"
+        );
+        // The odd last row has no lower half.
+        assert_eq!(lines[1], "  \x1b[49m\x1b[38;2;1;2;3m▀\x1b[0m");
+    }
+
+    #[test]
+    fn kitty_sends_compressed_rgba_in_chunks() {
+        let small = to_kitty(&RgbaImage::from_pixel(2, 1, Rgba([1, 2, 3, 4])));
+        assert!(small.starts_with("\x1b_Ga=T,q=2,f=32,o=z,s=2,v=1,m=0;"));
+        assert!(small.ends_with("\x1b\\\n"));
+        let payload = &small["\x1b_Ga=T,q=2,f=32,o=z,s=2,v=1,m=0;".len()..small.len() - 3];
+        let compressed = crate::profile::decode_base64(payload).unwrap();
+        let mut rgba = Vec::new();
+        std::io::Read::read_to_end(
+            &mut flate2::read::ZlibDecoder::new(&compressed[..]),
+            &mut rgba,
+        )
+        .unwrap();
+        assert_eq!(rgba, [1, 2, 3, 4, 1, 2, 3, 4]);
+
+        // Noise compresses badly, so this needs several chunks; only the last has m=0.
+        let noise = RgbaImage::from_fn(64, 64, |x, y| {
+            let n = (x * 7919 + y * 104_729) ^ (x * y * 31);
+            Rgba([n as u8, (n >> 8) as u8, (n >> 3) as u8, 255])
+        });
+        let kitty = to_kitty(&noise);
+        let sequences: Vec<_> = kitty
+            .trim_end()
+            .split("\x1b\\")
+            .filter(|s| !s.is_empty())
+            .collect();
+        assert!(sequences.len() > 1);
+        assert!(sequences[1..sequences.len() - 1]
+            .iter()
+            .all(|s| s.starts_with("\x1b_Gm=1;")));
+        assert!(sequences.last().unwrap().starts_with("\x1b_Gm=0;"));
+    }
+
+    #[test]
+    fn sixel_bands_use_the_image_palette() {
+        let mut image = RgbaImage::new(5, 7);
+        for x in 0..5 {
+            image.put_pixel(x, 0, Rgba([255, 0, 0, 255]));
+        }
+        image.put_pixel(1, 1, Rgba([255, 0, 0, 255]));
+        image.put_pixel(0, 6, Rgba([0, 0, 255, 255]));
+        assert_eq!(
+            to_sixel(&image),
+            "\x1bP0;1;0q\"1;1;5;7#0;2;100;0;0#1;2;0;0;100#0@B@@@-#1@-\x1b\\\n"
+        );
+
+        // Too many colors for the palette: falls back to the color cube.
+        let gradient =
+            RgbaImage::from_fn(300, 1, |x, _| Rgba([x as u8, (x / 256 * 99) as u8, 0, 255]));
+        let sixel = to_sixel(&gradient);
+        assert_eq!(sixel.matches(";2;").count(), 252);
+        assert!(sixel.contains("#251;2;100;100;100"));
+    }
+
+    #[test]
+    fn wide_images_shrink_to_the_column_count() {
+        let image = RgbaImage::from_pixel(400, 200, Rgba([9, 9, 9, 255]));
+        let fitted = fit_width(&image, 80);
+        assert_eq!(fitted.dimensions(), (80, 40));
+        assert_eq!(fit_width(&fitted, 200).dimensions(), (80, 40));
+        let ansi = to_ansi(&image, 80);
+        assert_eq!(ansi.lines().count(), 20);
+        assert!(ansi.lines().all(|line| line.matches('▀').count() == 80));
+    }
+}
@@ -1,21 +1,248 @@
-//! Skin texture loading for GPU rendering.
+//! Skin loading: CPU-side decoding into a [`SkinImage`], then GPU upload into a [`Texture`].
 //!
-//! Loads a PNG from disk, uploads RGBA8 data to a `wgpu` texture, and builds a bind group.
-//! If the image has single-layer layout (width = 2 × height), it is converted to a
-//! double-layer skin via [`crate::converter::single2double`] before upload.
+//! Decoding reads a PNG, converts single-layer layouts (width = 2 × height) to double-layer via
+//! [`crate::converter::single2double`] and validates the result. PNGs tagged with a color space
+//! other than sRGB are converted to sRGB ([`crate::color`]). It needs no device, so skins can
+//! be prepared on any thread; uploading creates the `wgpu` texture and bind group. Skins are
+//! uploaded with the texels outside their faces padded ([`skin_layout::pad_edges`]), and HD
+//! skins (at least 128 pixels wide) with mipmaps down to the standard 64×64, for
+//! [`crate::renderer::TextureFilter`]s that use them. Capes are decoded into the game's 2:1 cape
+//! layout instead ([`SkinImage::from_cape_image`]).
 
-use crate::converter::single2double;
+use crate::character::{Bone, SkinType};
+use crate::color;
+use crate::converter::single2double_in_place;
 use crate::error::EidolonError;
-use image::{DynamicImage, GenericImageView};
+use crate::metadata::{fnv1a, fnv1a_start};
+use crate::renderer::TextureFilter;
+use crate::skin_layout;
+use image::{DynamicImage, RgbaImage};
 #[cfg(not(target_arch = "wasm32"))]
 use image::ImageFormat;
-use log::info;
+use tracing::{debug, info};
 #[cfg(not(target_arch = "wasm32"))]
-use std::fs::File;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Width of the smallest mipmap level: the game's own skin resolution. Narrower skins get none.
+const MIN_MIP_WIDTH: u32 = 64;
+
+/// [`Texture::content_hash`] as it appears in logs and render metadata (16 hex digits).
+pub(crate) struct SkinId(pub u64);
+
+impl std::fmt::Display for SkinId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Decoded skin pixels, ready for upload with [`crate::renderer::Renderer::upload_skin`].
+///
+/// Pure CPU data (`Send + Sync`): decode skins on worker threads and hand them to the thread that
+/// owns the renderer.
+#[derive(Debug, Clone)]
+pub struct SkinImage {
+    rgba: RgbaImage,
+    content_hash: u64,
+    /// Whether `rgba` is laid out as a skin atlas (rather than a raw texture), so its unused
+    /// texels are padded on upload ([`skin_layout::pad_edges`]).
+    skin_layout: bool,
+}
+
+impl SkinImage {
+    /// Decode skin PNG bytes, converting single-layer skins. See [`SkinImage::from_image`].
+    pub fn from_memory(bytes: &[u8]) -> Result<Self, EidolonError> {
+        info!(bytes = bytes.len(), "decoding skin from memory");
+        Self::from_image(&DynamicImage::ImageRgba8(decode(bytes)?))
+    }
+
+    /// Decode image bytes as-is, without single→double layer conversion or layout checks: for
+    /// textures that are not skins, such as [`crate::attachment`] textures.
+    pub fn from_memory_raw(bytes: &[u8]) -> Result<Self, EidolonError> {
+        Self::from_rgba(decode(bytes)?)
+    }
+
+    /// Decode a skin PNG file, converting single-layer skins. See [`SkinImage::from_image`].
+    ///
+    /// The path is canonicalized before use to resolve symlinks and `..` components.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file(path: &str) -> Result<Self, EidolonError> {
+        info!(path, "decoding skin");
+        Self::from_image(&DynamicImage::ImageRgba8(read_png(path)?))
+    }
+
+    /// [`SkinImage::from_file`], decoding straight from a memory map of the file instead of a copy
+    /// read into memory: for batch jobs decoding tens of thousands of skins.
+    ///
+    /// The file must not be truncated or rewritten while it is decoded; on most platforms that
+    /// crashes the process instead of returning an error. Use [`SkinImage::from_file`] for files
+    /// other processes may be writing.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file_mapped(path: &str) -> Result<Self, EidolonError> {
+        info!(path, "decoding mapped skin");
+        let file = std::fs::File::open(canonicalize(path)?)?;
+        // SAFETY: the caller guarantees the file is not modified while mapped (see above).
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Self::from_image(&DynamicImage::ImageRgba8(decode_png(&map)?))
+    }
+
+    /// Decode a skin PNG file as-is, without single→double layer conversion or layout checks.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn from_file_raw(path: &str) -> Result<Self, EidolonError> {
+        info!(path, "decoding skin without layer conversion");
+        Self::from_rgba(read_png(path)?)
+    }
+
+    /// Decode cape PNG bytes. See [`SkinImage::from_cape_image`].
+    pub fn cape_from_memory(bytes: &[u8]) -> Result<Self, EidolonError> {
+        info!(bytes = bytes.len(), "decoding cape from memory");
+        Self::from_cape_image(decode(bytes)?)
+    }
+
+    /// Decode a cape PNG file. See [`SkinImage::from_cape_image`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn cape_from_file(path: &str) -> Result<Self, EidolonError> {
+        info!(path, "decoding cape");
+        Self::from_cape_image(read_png(path)?)
+    }
+
+    /// A [`crate::cape::Cape`] texture in the game's 2:1 layout (64×32, or a multiple for HD
+    /// capes). Other sizes, such as legacy 22×17 capes or 46×22 capes with an elytra, are placed
+    /// in the top-left corner of the smallest such canvas that holds them.
+    pub fn from_cape_image(rgba: RgbaImage) -> Result<Self, EidolonError> {
+        let (width, height) = rgba.dimensions();
+        if width == 0 || height == 0 {
+            return Err(EidolonError::texture("cape image is empty"));
+        }
+        let scale = width.div_ceil(64).max(height.div_ceil(32));
+        if (width, height) == (64 * scale, 32 * scale) {
+            return Self::from_rgba(rgba);
+        }
+        debug!(width, height, scale, "padding cape to the cape layout");
+        let mut canvas = RgbaImage::new(64 * scale, 32 * scale);
+        image::imageops::replace(&mut canvas, &rgba, 0, 0);
+        Self::from_rgba(canvas)
+    }
+
+    /// Convert `image` to a double-layer skin: 2:1 single-layer skins are expanded, square ones
+    /// kept. Any other shape is a [`EidolonError::Texture`].
+    pub fn from_image(image: &DynamicImage) -> Result<Self, EidolonError> {
+        let (width, height) = (image.width(), image.height());
+        debug!(width, height, "decoded skin");
+        if width != height && width != height * 2 {
+            return Err(EidolonError::texture(format!(
+                "skin must be square (double-layer) or 2:1 (single-layer), got {width}x{height}"
+            )));
+        }
+        let mut rgba = image.to_rgba8();
+        if width == height * 2 {
+            debug!("converting single-layer skin to double-layer");
+            single2double_in_place(&mut rgba)?;
+        }
+        Ok(Self {
+            skin_layout: true,
+            ..Self::from_rgba(rgba)?
+        })
+    }
+
+    pub(crate) fn from_rgba(rgba: RgbaImage) -> Result<Self, EidolonError> {
+        let (width, height) = rgba.dimensions();
+        if width == 0 || height == 0 {
+            return Err(EidolonError::texture("skin image is empty"));
+        }
+        let content_hash = fnv1a(
+            fnv1a(fnv1a_start(), &[width.to_le_bytes(), height.to_le_bytes()].concat()),
+            rgba.as_raw(),
+        );
+        Ok(Self {
+            rgba,
+            content_hash,
+            skin_layout: false,
+        })
+    }
+
+    /// The double-layer RGBA pixels.
+    pub fn rgba(&self) -> &RgbaImage {
+        &self.rgba
+    }
+
+    /// Same value as [`Texture::content_hash`] after upload.
+    pub fn content_hash(&self) -> u64 {
+        self.content_hash
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
-use std::io::BufReader;
+fn read_png(path: &str) -> Result<RgbaImage, EidolonError> {
+    decode_png(&std::fs::read(canonicalize(path)?)?)
+}
+
+/// `path` with symlinks and `..` components resolved.
 #[cfg(not(target_arch = "wasm32"))]
-use std::path::Path;
+fn canonicalize(path: &str) -> Result<std::path::PathBuf, EidolonError> {
+    if path.contains('\0') {
+        return Err(EidolonError::invalid_path("texture path contains null bytes"));
+    }
+    Path::new(path)
+        .canonicalize()
+        .map_err(|e| EidolonError::invalid_path(format!("failed to resolve '{}': {}", path, e)))
+}
+
+/// The pixels of PNG file contents, in sRGB.
+#[cfg(not(target_arch = "wasm32"))]
+fn decode_png(bytes: &[u8]) -> Result<RgbaImage, EidolonError> {
+    let mut rgba = image::load_from_memory_with_format(bytes, ImageFormat::Png)
+        .map_err(|e| EidolonError::texture(format!("failed to decode PNG: {e}")))?
+        .to_rgba8();
+    color::normalize_png(bytes, &mut rgba);
+    Ok(rgba)
+}
+
+fn decode(bytes: &[u8]) -> Result<RgbaImage, EidolonError> {
+    let mut rgba = image::load_from_memory(bytes)
+        .map_err(|e| EidolonError::texture(format!("failed to decode PNG from memory: {e}")))?
+        .to_rgba8();
+    color::normalize_png(bytes, &mut rgba);
+    Ok(rgba)
+}
+
+/// The mipmap levels below `rgba`, each half the size of the one before, down to
+/// [`MIN_MIP_WIDTH`]; empty for standard skins.
+///
+/// Each texel averages its 2×2 parents weighted by their alpha, so the color of transparent
+/// texels does not bleed into the edges of the overlay.
+fn mip_chain(rgba: &RgbaImage) -> Vec<RgbaImage> {
+    let mut levels: Vec<RgbaImage> = Vec::new();
+    loop {
+        let level = levels.last().unwrap_or(rgba);
+        if level.width() / 2 < MIN_MIP_WIDTH {
+            break levels;
+        }
+        let (width, height) = (level.width() / 2, (level.height() / 2).max(1));
+        let next = RgbaImage::from_fn(width, height, |x, y| {
+            let parents = [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| {
+                level.get_pixel(
+                    (2 * x + dx).min(level.width() - 1),
+                    (2 * y + dy).min(level.height() - 1),
+                )
+            });
+            let alpha: u32 = parents.iter().map(|p| u32::from(p[3])).sum();
+            let channel = |c: usize| {
+                let weighted: u32 = parents
+                    .iter()
+                    .map(|p| u32::from(p[c]) * u32::from(p[3]))
+                    .sum();
+                // Fully transparent texels keep their plain average.
+                let value = (weighted + alpha / 2).checked_div(alpha).unwrap_or_else(|| {
+                    parents.iter().map(|p| u32::from(p[c])).sum::<u32>().div_ceil(4)
+                });
+                value as u8
+            };
+            image::Rgba([channel(0), channel(1), channel(2), ((alpha + 2) / 4) as u8])
+        });
+        levels.push(next);
+    }
+}
 
 /// GPU skin texture.
 ///
@@ -27,11 +254,65 @@ pub struct Texture {
     #[allow(dead_code)]
     pub(crate) view: wgpu::TextureView,
     pub(crate) bind_group: wgpu::BindGroup,
+    /// Bind groups with the samplers of [`TextureFilter::NearestMipmapped`] and
+    /// [`TextureFilter::Linear`], created on first use by [`Texture::filtered_bind_group`].
+    filtered_bind_groups: [OnceLock<wgpu::BindGroup>; 2],
+    /// FNV-1a hash of the uploaded RGBA pixels and their size.
+    content_hash: u64,
+    /// [`skin_layout::base_is_blank`] of each part, in [`Bone`] order, for classic then slim arms.
+    blank_parts: [[bool; 6]; 2],
+    /// Size and per-texel alpha (row by row) of the uploaded pixels, for picking.
+    size: (u32, u32),
+    alpha: Vec<u8>,
 }
 
 impl Texture {
-    /// Load a skin from in-memory PNG bytes, decode as RGBA, optionally convert single-layer
-    /// skins, then create the GPU texture and bind group.
+    /// Hash of the uploaded skin pixels (after single→double layer conversion). Equal for the same
+    /// skin however the PNG was compressed; recorded as `skin-hash` in render metadata.
+    pub fn content_hash(&self) -> u64 {
+        self.content_hash
+    }
+
+    /// The bind group that samples this texture with `filter`; `samplers` are the samplers of
+    /// the filters other than [`TextureFilter::Nearest`], in declaration order.
+    pub(crate) fn filtered_bind_group(
+        &self,
+        filter: TextureFilter,
+        device: &wgpu::Device,
+        bind_group_layout: &wgpu::BindGroupLayout,
+        samplers: &[wgpu::Sampler; 2],
+    ) -> &wgpu::BindGroup {
+        let index = match filter {
+            TextureFilter::Nearest => return &self.bind_group,
+            TextureFilter::NearestMipmapped => 0,
+            TextureFilter::Linear => 1,
+        };
+        self.filtered_bind_groups[index].get_or_init(|| {
+            create_bind_group(device, bind_group_layout, &self.view, &samplers[index])
+        })
+    }
+
+    /// Whether `bone`'s base layer is fully transparent or outside this texture, so the part
+    /// would be invisible.
+    pub(crate) fn is_blank(&self, bone: Bone, skin_type: SkinType) -> bool {
+        self.blank_parts[skin_type as usize][bone as usize]
+    }
+
+    /// Width and height of the texture in texels.
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Alpha of the uploaded texel at `(x, y)`, or 0 outside the texture.
+    pub(crate) fn alpha_at(&self, (x, y): (u32, u32)) -> u8 {
+        let (width, height) = self.size;
+        if x >= width || y >= height {
+            return 0;
+        }
+        self.alpha[(y * width + x) as usize]
+    }
+
+    /// Decode skin PNG bytes ([`SkinImage::from_memory`]) and upload them.
     ///
     /// Available on all platforms. Preferred for WASM where file I/O is not available.
     pub fn load_from_memory(
@@ -41,16 +322,11 @@ impl Texture {
         sampler: &wgpu::Sampler,
         bytes: &[u8],
     ) -> Result<Self, EidolonError> {
-        info!("Loading texture from memory ({} bytes)", bytes.len());
-        let image = image::load_from_memory(bytes)
-            .map_err(|e| EidolonError::texture(format!("failed to decode PNG from memory: {e}")))?;
-        Self::load_texture(device, queue, bind_group_layout, sampler, &image)
+        let skin = SkinImage::from_memory(bytes)?;
+        Ok(Self::upload(device, queue, bind_group_layout, sampler, &skin))
     }
 
-    /// Load a skin from a PNG file path, decode as RGBA, optionally convert single-layer skins,
-    /// then create the GPU texture and bind group.
-    ///
-    /// The path is canonicalized before use to resolve symlinks and `..` components.
+    /// Decode a skin PNG file ([`SkinImage::from_file`]) and upload it.
     #[cfg(not(target_arch = "wasm32"))]
     pub fn load_from_file(
         device: &wgpu::Device,
@@ -59,25 +335,8 @@ impl Texture {
         sampler: &wgpu::Sampler,
         path: &str,
     ) -> Result<Self, EidolonError> {
-        info!("Loading texture: {}", path);
-        if path.contains('\0') {
-            return Err(EidolonError::invalid_path("texture path contains null bytes"));
-        }
-        let canonical = Path::new(path)
-            .canonicalize()
-            .map_err(|e| EidolonError::invalid_path(format!("failed to resolve '{}': {}", path, e)))?;
-        let file = File::open(&canonical)?;
-        let reader = BufReader::new(file);
-        let image = image::load(reader, ImageFormat::Png)
-            .map_err(|e| EidolonError::texture(format!("failed to decode PNG: {e}")))?
-            .to_rgba8();
-        Self::load_texture(
-            device,
-            queue,
-            bind_group_layout,
-            sampler,
-            &DynamicImage::ImageRgba8(image),
-        )
+        let skin = SkinImage::from_file(path)?;
+        Ok(Self::upload(device, queue, bind_group_layout, sampler, &skin))
     }
 
     /// Load a skin without auto-converting single-layer to double-layer.
@@ -91,124 +350,202 @@ impl Texture {
         sampler: &wgpu::Sampler,
         path: &str,
     ) -> Result<Self, EidolonError> {
-        info!("Loading texture (raw, no conversion): {}", path);
-        if path.contains('\0') {
-            return Err(EidolonError::invalid_path("texture path contains null bytes"));
-        }
-        let canonical = Path::new(path)
-            .canonicalize()
-            .map_err(|e| EidolonError::invalid_path(format!("failed to resolve '{}': {}", path, e)))?;
-        let file = File::open(&canonical)?;
-        let reader = BufReader::new(file);
-        let image = image::load(reader, ImageFormat::Png)
-            .map_err(|e| EidolonError::texture(format!("failed to decode PNG: {e}")))?
-            .to_rgba8();
-        let dimensions = image.dimensions();
-        Self::upload_raw(
-            device,
-            queue,
-            bind_group_layout,
-            sampler,
-            image.as_raw(),
-            dimensions.0,
-            dimensions.1,
-        )
-    }
-
-    fn load_texture(
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        bind_group_layout: &wgpu::BindGroupLayout,
-        sampler: &wgpu::Sampler,
-        image: &DynamicImage,
-    ) -> Result<Texture, EidolonError> {
-        let (width, height) = image.dimensions();
-        info!("Texture dimensions: {}x{}", width, height);
-
-        let image = if width == height * 2 {
-            info!("Single-layer skin detected, converting to double-layer...");
-            single2double(image)?
-        } else {
-            image.clone()
-        };
-
-        let image_dimensions = image.dimensions();
-        let image_rgba = image.to_rgba8();
-        Self::upload_raw(
-            device,
-            queue,
-            bind_group_layout,
-            sampler,
-            &image_rgba,
-            image_dimensions.0,
-            image_dimensions.1,
-        )
+        let skin = SkinImage::from_file_raw(path)?;
+        Ok(Self::upload(device, queue, bind_group_layout, sampler, &skin))
     }
 
-    fn upload_raw(
+    /// Create the GPU texture and bind group for `skin`.
+    pub(crate) fn upload(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         bind_group_layout: &wgpu::BindGroupLayout,
         sampler: &wgpu::Sampler,
-        rgba: &[u8],
-        width: u32,
-        height: u32,
-    ) -> Result<Texture, EidolonError> {
+        skin: &SkinImage,
+    ) -> Self {
+        let (width, height) = skin.rgba.dimensions();
         let size = wgpu::Extent3d {
             width,
             height,
             depth_or_array_layers: 1,
         };
+        let padded;
+        let rgba = if skin.skin_layout {
+            let mut rgba = skin.rgba.clone();
+            skin_layout::pad_edges(&mut rgba);
+            padded = rgba;
+            &padded
+        } else {
+            &skin.rgba
+        };
+        let mips = mip_chain(rgba);
 
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("Skin Texture"),
             size,
-            mip_level_count: 1,
+            mip_level_count: 1 + mips.len() as u32,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Rgba8Unorm,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            // COPY_SRC: crowd renders copy skins into a texture array.
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         });
 
-        queue.write_texture(
-            wgpu::TexelCopyTextureInfo {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            rgba,
-            wgpu::TexelCopyBufferLayout {
-                offset: 0,
-                bytes_per_row: Some(4 * width),
-                rows_per_image: Some(height),
-            },
-            size,
-        );
+        for (mip_level, level) in std::iter::once(rgba).chain(&mips).enumerate() {
+            let (width, height) = level.dimensions();
+            queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: mip_level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                level.as_raw(),
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = create_bind_group(device, bind_group_layout, &view, sampler);
+        let alpha = rgba.pixels().map(|pixel| pixel[3]).collect();
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Skin Texture Bind Group"),
-            layout: bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(sampler),
-                },
-            ],
-        });
+        debug!(skin = %SkinId(skin.content_hash), mip_levels = 1 + mips.len(), "texture uploaded");
 
-        info!("Texture loaded into GPU");
-        Ok(Texture {
+        let blank_parts = [SkinType::Classic, SkinType::Slim].map(|skin_type| {
+            skin_layout::PARTS.map(|part| skin_layout::base_is_blank(&skin.rgba, part.bone, skin_type))
+        });
+        Texture {
             texture,
             view,
             bind_group,
-        })
+            filtered_bind_groups: Default::default(),
+            content_hash: skin.content_hash,
+            blank_parts,
+            size: (width, height),
+            alpha,
+        }
+    }
+}
+
+/// Group 1 bind group of the skin pipelines: `view` sampled with `sampler`.
+fn create_bind_group(
+    device: &wgpu::Device,
+    bind_group_layout: &wgpu::BindGroupLayout,
+    view: &wgpu::TextureView,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Skin Texture Bind Group"),
+        layout: bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_layer_skins_are_converted() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(64, 32));
+        let skin = SkinImage::from_image(&image).unwrap();
+        assert_eq!(skin.rgba().dimensions(), (64, 64));
+    }
+
+    #[test]
+    fn other_shapes_are_rejected() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(64, 48));
+        let err = SkinImage::from_image(&image).unwrap_err();
+        assert!(err.to_string().contains("64x48"), "{err}");
+        assert!(SkinImage::from_rgba(RgbaImage::new(0, 0)).is_err());
+    }
+
+    #[test]
+    fn legacy_capes_are_padded_to_the_cape_layout() {
+        let cape = |width, height| {
+            SkinImage::from_cape_image(RgbaImage::from_pixel(width, height, image::Rgba([9; 4])))
+        };
+        assert_eq!(cape(64, 32).unwrap().rgba().dimensions(), (64, 32));
+        assert_eq!(cape(128, 64).unwrap().rgba().dimensions(), (128, 64));
+        for (size, padded) in [((22, 17), (64, 32)), ((46, 22), (64, 32)), ((92, 44), (128, 64))] {
+            let image = cape(size.0, size.1).unwrap();
+            assert_eq!(image.rgba().dimensions(), padded);
+            assert_eq!(image.rgba().get_pixel(size.0 - 1, size.1 - 1)[3], 9);
+            assert_eq!(image.rgba().get_pixel(size.0, 0)[3], 0);
+        }
+        assert!(cape(0, 0).is_err());
+    }
+
+    #[test]
+    fn mapped_files_decode_like_read_files() {
+        for path in ["resources/bingling_sama.png", "resources/SSSSSteven.png"] {
+            let mapped = SkinImage::from_file_mapped(path).unwrap();
+            let read = SkinImage::from_file(path).unwrap();
+            assert_eq!(mapped.content_hash(), read.content_hash());
+        }
+        let empty = std::env::temp_dir().join(format!("eidolon-empty-{}.png", std::process::id()));
+        std::fs::write(&empty, b"").unwrap();
+        let result = SkinImage::from_file_mapped(empty.to_str().unwrap());
+        std::fs::remove_file(&empty).unwrap();
+        assert!(matches!(result, Err(EidolonError::Texture(_))));
+        assert!(matches!(
+            SkinImage::from_file_mapped("resources/missing.png"),
+            Err(EidolonError::InvalidPath(_))
+        ));
+    }
+
+    #[test]
+    fn content_hash_depends_on_pixels() {
+        let skin =
+            |value| SkinImage::from_rgba(RgbaImage::from_pixel(4, 4, image::Rgba([value; 4])));
+        assert_eq!(skin(1).unwrap().content_hash(), skin(1).unwrap().content_hash());
+        assert_ne!(skin(1).unwrap().content_hash(), skin(2).unwrap().content_hash());
+    }
+
+    #[test]
+    fn hd_skins_get_mipmaps_down_to_standard_size() {
+        assert!(mip_chain(&RgbaImage::new(64, 64)).is_empty());
+        let sizes = |size| {
+            mip_chain(&RgbaImage::new(size, size))
+                .iter()
+                .map(|level| level.width())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sizes(512), [256, 128, 64]);
+        assert_eq!(sizes(128), [64]);
+
+        // Transparent texels do not darken the average.
+        let mut hd = RgbaImage::new(128, 128);
+        hd.put_pixel(0, 0, image::Rgba([200, 100, 0, 255]));
+        hd.put_pixel(1, 1, image::Rgba([200, 100, 0, 255]));
+        let levels = mip_chain(&hd);
+        assert_eq!(levels[0].get_pixel(0, 0), &image::Rgba([200, 100, 0, 128]));
+        assert_eq!(levels[0].get_pixel(1, 0), &image::Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn skin_images_cross_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SkinImage>();
     }
 }
@@ -0,0 +1,235 @@
+//! Theme colors from a skin: a small palette (primary, secondary, accent) so web UIs can style a
+//! profile page after the player's skin.
+//!
+//! Only texels that show on the model count (see [`crate::skin_layout::visible_texels`]).
+//! Similar colors are merged into clusters; the primary is the most common cluster, the secondary
+//! the most common clearly different one, and the accent a vivid color that stands out from both.
+
+use serde_json::json;
+
+use crate::character::SkinType;
+use crate::metadata::RenderMetadata;
+use crate::skin_layout::{visible_texels, PARTS};
+use crate::texture::SkinImage;
+
+/// Largest RGB distance between a color and the cluster it joins.
+const CLUSTER_RADIUS: f32 = 40.0;
+/// Smallest RGB distance between palette colors picked from the skin.
+const DISTINCT: f32 = 64.0;
+/// Smallest share of the visible texels a cluster needs to become the accent.
+const ACCENT_MIN_SHARE: f32 = 0.01;
+
+/// Three theme colors, RGB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThemePalette {
+    /// The skin's dominant color, e.g. for page backgrounds and headers.
+    pub primary: [u8; 3],
+    /// The most common color clearly different from the primary, e.g. for panels.
+    pub secondary: [u8; 3],
+    /// A vivid color that stands out from both, e.g. for buttons and links.
+    pub accent: [u8; 3],
+}
+
+/// Colors merged by similarity: running RGB sum and texel count.
+struct Cluster {
+    sum: [f32; 3],
+    count: u32,
+}
+
+impl Cluster {
+    fn mean(&self) -> [f32; 3] {
+        self.sum.map(|c| c / self.count as f32)
+    }
+}
+
+impl ThemePalette {
+    /// Palette of `skin` as drawn with `skin_type` arms. A skin with no visible texels gives
+    /// black, gray and white.
+    pub fn from_skin(skin: &SkinImage, skin_type: SkinType) -> Self {
+        let texels: Vec<[f32; 3]> = PARTS
+            .iter()
+            .flat_map(|part| visible_texels(skin, part.bone, skin_type))
+            .filter(|texel| texel[3] >= 128)
+            .map(|texel| [texel[0], texel[1], texel[2]].map(f32::from))
+            .collect();
+        if texels.is_empty() {
+            return Self {
+                primary: [0; 3],
+                secondary: [128; 3],
+                accent: [255; 3],
+            };
+        }
+
+        let mut clusters: Vec<Cluster> = Vec::new();
+        for texel in &texels {
+            let nearest = clusters
+                .iter_mut()
+                .map(|cluster| (distance(cluster.mean(), *texel), cluster))
+                .filter(|(d, _)| *d <= CLUSTER_RADIUS)
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            match nearest {
+                Some((_, cluster)) => {
+                    for (sum, c) in cluster.sum.iter_mut().zip(texel) {
+                        *sum += c;
+                    }
+                    cluster.count += 1;
+                }
+                None => clusters.push(Cluster {
+                    sum: *texel,
+                    count: 1,
+                }),
+            }
+        }
+        // Stable sort: ties keep the order clusters were first seen in.
+        clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.count));
+
+        let primary = clusters[0].mean();
+        let secondary = clusters
+            .iter()
+            .map(Cluster::mean)
+            .find(|&mean| distance(mean, primary) >= DISTINCT)
+            .unwrap_or_else(|| shade(primary));
+        let total = texels.len() as f32;
+        let accent = clusters
+            .iter()
+            .filter(|cluster| cluster.count as f32 >= total * ACCENT_MIN_SHARE)
+            .map(|cluster| (cluster.mean(), cluster.count as f32 / total))
+            .filter(|&(mean, _)| {
+                distance(mean, primary) >= DISTINCT && distance(mean, secondary) >= DISTINCT
+            })
+            .map(|(mean, share)| (mean, saturation(mean) * share.sqrt()))
+            .filter(|&(_, score)| score > 0.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(mean, _)| mean)
+            .unwrap_or_else(|| primary.map(|c| 255.0 - c));
+
+        let to_rgb = |color: [f32; 3]| color.map(|c| c.round().clamp(0.0, 255.0) as u8);
+        Self {
+            primary: to_rgb(primary),
+            secondary: to_rgb(secondary),
+            accent: to_rgb(accent),
+        }
+    }
+
+    /// `{"primary": "#rrggbb", "secondary": "#rrggbb", "accent": "#rrggbb"}`.
+    pub fn to_json(&self) -> String {
+        json!({
+            "primary": hex(self.primary),
+            "secondary": hex(self.secondary),
+            "accent": hex(self.accent),
+        })
+        .to_string()
+    }
+
+    /// Record the palette as `theme-primary`, `theme-secondary` and `theme-accent` (`#rrggbb`)
+    /// entries, so it travels inside the rendered image.
+    pub fn insert_into(&self, metadata: &mut RenderMetadata) {
+        metadata.insert("theme-primary", hex(self.primary));
+        metadata.insert("theme-secondary", hex(self.secondary));
+        metadata.insert("theme-accent", hex(self.accent));
+    }
+}
+
+/// CSS hex notation, `#rrggbb`.
+pub fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a.iter()
+        .zip(&b)
+        .map(|(x, y)| (x - y) * (x - y))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// HSV saturation, `0.0–1.0`.
+fn saturation(color: [f32; 3]) -> f32 {
+    let max = color.iter().copied().fold(0.0, f32::max);
+    let min = color.iter().copied().fold(255.0, f32::min);
+    if max > 0.0 {
+        (max - min) / max
+    } else {
+        0.0
+    }
+}
+
+/// A lighter version of a dark color or a darker version of a light one, for single-color skins.
+fn shade(color: [f32; 3]) -> [f32; 3] {
+    let luma = 0.2126 * color[0] + 0.7152 * color[1] + 0.0722 * color[2];
+    if luma < 128.0 {
+        color.map(|c| c + (255.0 - c) * 0.4)
+    } else {
+        color.map(|c| c * 0.6)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character::Bone;
+    use crate::skin_layout::{part, Layer};
+    use image::{Rgba, RgbaImage};
+
+    /// Paint every base face of `bone` with `color`.
+    fn paint(rgba: &mut RgbaImage, bone: Bone, color: [u8; 3]) {
+        let [r, g, b] = color;
+        for (x, y, w, h) in part(bone).faces(Layer::Base, SkinType::Classic) {
+            for ty in y..y + h {
+                for tx in x..x + w {
+                    rgba.put_pixel(tx, ty, Rgba([r, g, b, 255]));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dominant_colors_become_primary_and_secondary() {
+        let mut rgba = RgbaImage::new(64, 64);
+        // Blue clothes dominate, tan skin on the head, one small red arm.
+        for bone in [Bone::Body, Bone::LeftArm, Bone::RightLeg, Bone::LeftLeg] {
+            paint(&mut rgba, bone, [30, 60, 200]);
+        }
+        paint(&mut rgba, Bone::Head, [200, 160, 120]);
+        paint(&mut rgba, Bone::RightArm, [220, 20, 20]);
+        // Unused atlas texels do not count.
+        for x in 56..64 {
+            for y in 0..16 {
+                rgba.put_pixel(x, y, Rgba([0, 255, 0, 255]));
+            }
+        }
+        let palette = ThemePalette::from_skin(&SkinImage::from_rgba(rgba).unwrap(), SkinType::Classic);
+        assert_eq!(palette.primary, [30, 60, 200]);
+        assert_eq!(palette.secondary, [200, 160, 120]);
+        assert_eq!(palette.accent, [220, 20, 20]);
+    }
+
+    #[test]
+    fn single_color_skins_still_get_three_colors() {
+        let mut rgba = RgbaImage::new(64, 64);
+        for part in &PARTS {
+            paint(&mut rgba, part.bone, [40, 40, 40]);
+        }
+        let palette = ThemePalette::from_skin(&SkinImage::from_rgba(rgba).unwrap(), SkinType::Classic);
+        assert_eq!(palette.primary, [40, 40, 40]);
+        assert_ne!(palette.secondary, palette.primary);
+        assert_eq!(palette.accent, [215, 215, 215]);
+    }
+
+    #[test]
+    fn json_and_metadata_use_hex() {
+        let palette = ThemePalette {
+            primary: [0x12, 0x34, 0x56],
+            secondary: [0, 0, 0],
+            accent: [255, 128, 0],
+        };
+        assert_eq!(
+            palette.to_json(),
+            r##"{"accent":"#ff8000","primary":"#123456","secondary":"#000000"}"##
+        );
+        let mut metadata = RenderMetadata::default();
+        palette.insert_into(&mut metadata);
+        assert_eq!(metadata.get("theme-primary"), Some("#123456"));
+        assert_eq!(metadata.get("theme-accent"), Some("#ff8000"));
+    }
+}
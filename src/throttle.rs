@@ -0,0 +1,431 @@
+//! A polite HTTP client for skin APIs: [`ThrottledHttp`] wraps any [`HttpGet`] with per-host
+//! request spacing, exponential backoff on HTTP 429 ([`EidolonError::RateLimited`]) and request
+//! coalescing, so a traffic spike does not get a service's IP banned by Mojang.
+//!
+//! Concurrent requests for the same URL share one upstream request: a hundred renders of a newly
+//! popular player cost one name lookup, one profile lookup and one skin download. Time comes from
+//! a [`Clock`], so tests can run the backoff with a [`ManualClock`] instead of sleeping.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use tracing::{debug, warn};
+
+use crate::error::EidolonError;
+use crate::provider::HttpGet;
+
+/// Time source for [`ThrottledHttp`].
+pub trait Clock: Send + Sync {
+    /// Time since an arbitrary, fixed origin.
+    fn now(&self) -> Duration;
+    fn sleep(&self, duration: Duration);
+}
+
+/// The monotonic system clock; sleeps block the calling thread.
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self {
+            origin: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// A clock for tests: starts at zero, and sleeping advances it at once instead of blocking.
+/// Every sleep is recorded.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    state: Mutex<(Duration, Vec<Duration>)>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move the clock forward without recording a sleep.
+    pub fn advance(&self, duration: Duration) {
+        self.state.lock().unwrap().0 += duration;
+    }
+
+    /// Every sleep so far, in order.
+    pub fn sleeps(&self) -> Vec<Duration> {
+        self.state.lock().unwrap().1.clone()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.state.lock().unwrap().0
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.0 += duration;
+        state.1.push(duration);
+    }
+}
+
+impl<C: Clock> Clock for Arc<C> {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        (**self).sleep(duration)
+    }
+}
+
+/// Retries after [`EidolonError::RateLimited`]: wait `Retry-After` when the server sent one,
+/// otherwise `initial`, doubling per retry up to `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+    /// Retries before the rate-limit error is returned; 0 never retries.
+    pub max_retries: u32,
+}
+
+impl Default for Backoff {
+    /// 1 s, doubling up to 60 s, 5 retries.
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+            max_retries: 5,
+        }
+    }
+}
+
+impl Backoff {
+    /// Wait before retry number `retry` (0-based).
+    fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        retry_after
+            .unwrap_or_else(|| self.initial.saturating_mul(1 << retry.min(16)))
+            .min(self.max)
+    }
+}
+
+type Response = Result<Option<Vec<u8>>, EidolonError>;
+
+/// One upstream request that later callers for the same URL wait on.
+#[derive(Default)]
+struct Flight {
+    response: Mutex<Option<Response>>,
+    done: Condvar,
+}
+
+/// Ends the leader's [`Flight`] when dropped: removes it from `in_flight` and wakes the callers
+/// waiting on it. Should the upstream request panic, the flight is filled with an error, so
+/// waiting callers and later requests for the URL do not hang.
+struct Landing<'a> {
+    flight: &'a Flight,
+    in_flight: &'a Mutex<HashMap<String, Arc<Flight>>>,
+    url: &'a str,
+}
+
+impl Drop for Landing<'_> {
+    fn drop(&mut self) {
+        {
+            let mut response = self
+                .flight
+                .response
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            response.get_or_insert_with(|| Err(EidolonError::fetch("upstream request panicked")));
+        }
+        self.in_flight
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(self.url);
+        self.flight.done.notify_all();
+    }
+}
+
+/// An [`HttpGet`] that spaces, retries and coalesces requests; see the [module docs](self).
+pub struct ThrottledHttp<H, C = SystemClock> {
+    inner: H,
+    clock: C,
+    backoff: Backoff,
+    /// Minimum time between requests to a host.
+    intervals: HashMap<String, Duration>,
+    /// Earliest time of the next request to each spaced host.
+    next_slot: Mutex<HashMap<String, Duration>>,
+    in_flight: Mutex<HashMap<String, Arc<Flight>>>,
+}
+
+impl<H: HttpGet> ThrottledHttp<H> {
+    /// No spacing, [`Backoff::default`], the system clock.
+    pub fn new(inner: H) -> Self {
+        Self::with_clock(inner, SystemClock::default())
+    }
+
+    /// Spaced for Mojang's documented limit of 600 requests per 10 minutes: one request per
+    /// second to `api.mojang.com` and to `sessionserver.mojang.com`. Texture downloads are not
+    /// spaced.
+    pub fn mojang(inner: H) -> Self {
+        Self::new(inner)
+            .limit_host("api.mojang.com", Duration::from_secs(1))
+            .limit_host("sessionserver.mojang.com", Duration::from_secs(1))
+    }
+}
+
+impl<H: HttpGet, C: Clock> ThrottledHttp<H, C> {
+    pub fn with_clock(inner: H, clock: C) -> Self {
+        Self {
+            inner,
+            clock,
+            backoff: Backoff::default(),
+            intervals: HashMap::new(),
+            next_slot: Mutex::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start requests to `host` (e.g. `api.mojang.com`) at least `interval` apart.
+    pub fn limit_host(mut self, host: impl Into<String>, interval: Duration) -> Self {
+        self.intervals.insert(host.into(), interval);
+        self
+    }
+
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Wait for the next free slot of `url`'s host, if it is spaced.
+    fn wait_turn(&self, url: &str) {
+        let host = host(url);
+        let Some(&interval) = self.intervals.get(host) else {
+            return;
+        };
+        let now = self.clock.now();
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let next = next_slot.entry(host.to_string()).or_default();
+            let slot = (*next).max(now);
+            *next = slot + interval;
+            slot
+        };
+        if slot > now {
+            self.clock.sleep(slot - now);
+        }
+    }
+
+    /// Push `url`'s host back by `delay`, so other requests do not run into the same limit.
+    fn hold_host(&self, url: &str, delay: Duration) {
+        let until = self.clock.now() + delay;
+        let mut next_slot = self.next_slot.lock().unwrap();
+        let next = next_slot.entry(host(url).to_string()).or_default();
+        *next = (*next).max(until);
+    }
+
+    fn get_with_retries(&self, url: &str) -> Response {
+        let mut retry = 0;
+        loop {
+            self.wait_turn(url);
+            match self.inner.get(url) {
+                Err(EidolonError::RateLimited { retry_after })
+                    if retry < self.backoff.max_retries =>
+                {
+                    let delay = self.backoff.delay(retry, retry_after);
+                    warn!(url, ?delay, retry, "rate limited, backing off");
+                    self.hold_host(url, delay);
+                    self.clock.sleep(delay);
+                    retry += 1;
+                }
+                response => return response,
+            }
+        }
+    }
+}
+
+impl<H: HttpGet, C: Clock> HttpGet for ThrottledHttp<H, C> {
+    fn get(&self, url: &str) -> Result<Option<Vec<u8>>, EidolonError> {
+        let (flight, leader) = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(url) {
+                Some(flight) => (Arc::clone(flight), false),
+                None => {
+                    let flight = Arc::new(Flight::default());
+                    in_flight.insert(url.to_string(), Arc::clone(&flight));
+                    (flight, true)
+                }
+            }
+        };
+        if !leader {
+            debug!(url, "joining in-flight request");
+            let mut response = flight.response.lock().unwrap();
+            while response.is_none() {
+                response = flight.done.wait(response).unwrap();
+            }
+            return share(response.as_ref().unwrap());
+        }
+
+        let landing = Landing {
+            flight: &flight,
+            in_flight: &self.in_flight,
+            url,
+        };
+        let response = self.get_with_retries(url);
+        *flight.response.lock().unwrap() = Some(share(&response));
+        drop(landing);
+        response
+    }
+}
+
+/// A copy of `response` for a coalesced caller. Errors keep their variant where it matters to
+/// callers (not found, rate limited); the rest become [`EidolonError::Fetch`].
+fn share(response: &Response) -> Response {
+    match response {
+        Ok(body) => Ok(body.clone()),
+        Err(EidolonError::NotFound(msg)) => Err(EidolonError::not_found(msg.clone())),
+        Err(EidolonError::RateLimited { retry_after }) => {
+            Err(EidolonError::rate_limited(*retry_after))
+        }
+        Err(EidolonError::Fetch(msg)) => Err(EidolonError::fetch(msg.clone())),
+        Err(e) => Err(EidolonError::fetch(e.to_string())),
+    }
+}
+
+/// Host of `url`: between `://` and the next `/`, `?` or `#`.
+fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?', '#']).next().unwrap_or(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Barrier;
+
+    #[test]
+    fn backs_off_exponentially_on_429() {
+        let clock = Arc::new(ManualClock::new());
+        let calls = AtomicUsize::new(0);
+        let http = ThrottledHttp::with_clock(
+            |_: &str| match calls.fetch_add(1, Ordering::SeqCst) {
+                0..=2 => Err(EidolonError::rate_limited(None)),
+                _ => Ok(Some(b"ok".to_vec())),
+            },
+            Arc::clone(&clock),
+        );
+        assert_eq!(http.get("https://api.example/a").unwrap().unwrap(), b"ok");
+        assert_eq!(clock.sleeps(), [1, 2, 4].map(Duration::from_secs).to_vec());
+    }
+
+    #[test]
+    fn honors_retry_after_and_gives_up() {
+        let clock = Arc::new(ManualClock::new());
+        let http = ThrottledHttp::with_clock(
+            |_: &str| Err(EidolonError::rate_limited(Some(Duration::from_secs(90)))),
+            Arc::clone(&clock),
+        )
+        .backoff(Backoff {
+            max_retries: 2,
+            ..Backoff::default()
+        });
+        assert!(matches!(
+            http.get("https://api.example/a"),
+            Err(EidolonError::RateLimited { .. })
+        ));
+        // Retry-After is capped at `max`.
+        assert_eq!(clock.sleeps(), vec![Duration::from_secs(60); 2]);
+    }
+
+    #[test]
+    fn spaces_requests_per_host() {
+        let clock = Arc::new(ManualClock::new());
+        let http = ThrottledHttp::with_clock(|_: &str| Ok(None), Arc::clone(&clock))
+            .limit_host("api.example", Duration::from_secs(1));
+        for _ in 0..3 {
+            http.get("https://api.example/users/notch").unwrap();
+            http.get("https://textures.example/skin").unwrap();
+        }
+        assert_eq!(clock.sleeps(), vec![Duration::from_secs(1); 2]);
+        // A quiet host needs no wait.
+        clock.advance(Duration::from_secs(5));
+        http.get("https://api.example/users/jeb_").unwrap();
+        assert_eq!(clock.sleeps().len(), 2);
+    }
+
+    #[test]
+    fn concurrent_lookups_of_one_url_share_a_request() {
+        let calls = AtomicUsize::new(0);
+        let http = ThrottledHttp::new(|url: &str| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(200));
+            Err(EidolonError::not_found(url))
+        });
+        let barrier = Barrier::new(4);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    barrier.wait();
+                    let response = http.get("https://session.example/profile/069a79f4");
+                    assert!(matches!(response, Err(EidolonError::NotFound(_))));
+                });
+            }
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        // Finished requests are not cached.
+        http.get("https://session.example/profile/069a79f4")
+            .unwrap_err();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn a_panicking_request_fails_its_waiters() {
+        let calls = AtomicUsize::new(0);
+        let http = ThrottledHttp::new(|_: &str| {
+            if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                std::thread::sleep(Duration::from_millis(200));
+                panic!("upstream client bug");
+            }
+            Ok(Some(b"ok".to_vec()))
+        });
+        let url = "https://textures.example/skin";
+        let barrier = Barrier::new(4);
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    barrier.wait();
+                    let response =
+                        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| http.get(url)));
+                    // The leader panics; the callers waiting on it get an error.
+                    if let Ok(response) = response {
+                        assert!(matches!(response, Err(EidolonError::Fetch(_))));
+                    }
+                });
+            }
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(http.get(url).unwrap().unwrap(), b"ok");
+    }
+
+    #[test]
+    fn hosts_are_cut_from_urls() {
+        assert_eq!(host("https://api.mojang.com/users/x"), "api.mojang.com");
+        assert_eq!(host("http://localhost:8080?q"), "localhost:8080");
+        assert_eq!(
+            host("sessionserver.mojang.com/session"),
+            "sessionserver.mojang.com"
+        );
+    }
+}
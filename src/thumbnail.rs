@@ -0,0 +1,282 @@
+//! One-call thumbnails for skin databases: [`thumbnail`] turns skin PNG bytes into encoded image
+//! bytes from a small, versioned [`ThumbnailSpec`].
+//!
+//! Every render parameter the spec does not expose (light rig, render options, camera presets)
+//! is pinned by [`ThumbnailVersion`], so a stored spec keeps producing the same thumbnails when
+//! renderer defaults change. [`ThumbnailSpec::cache_key`] identifies a thumbnail for caching.
+//!
+//! Refresh jobs use [`thumbnail_if_changed`]: it fetches a player's skin and renders only when
+//! the skin differs from the one behind the caller's last thumbnail.
+
+use std::io::Cursor;
+
+use crate::camera::Camera;
+use crate::character::{Character, DefaultPostures, Posture, SkinType};
+use crate::error::EidolonError;
+use crate::lighting::Lighting;
+use crate::metadata::{fnv1a, fnv1a_start};
+use crate::provider::{PlayerId, SkinProvider};
+use crate::renderer::{OutputFormat, RenderOptions, Renderer};
+use crate::request::with_shared_renderer;
+use crate::texture::{SkinId, SkinImage};
+
+/// Revision of the fixed thumbnail parameters. New versions are added instead of changing old ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThumbnailVersion {
+    /// Default light rig, default render options, transparent or flat background.
+    #[default]
+    V1,
+}
+
+/// Pose preset for [`ThumbnailSpec::pose`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThumbnailPose {
+    #[default]
+    Stand,
+    Wave,
+    Walking,
+    Running,
+}
+
+impl ThumbnailPose {
+    pub(crate) fn posture(self) -> Posture {
+        match self {
+            ThumbnailPose::Stand => DefaultPostures::STAND,
+            ThumbnailPose::Wave => DefaultPostures::WAVE,
+            ThumbnailPose::Walking => DefaultPostures::WALKING,
+            ThumbnailPose::Running => DefaultPostures::RUNNING,
+        }
+    }
+}
+
+/// Camera preset for [`ThumbnailSpec::view`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThumbnailView {
+    /// Straight on, facing the camera.
+    #[default]
+    Front,
+    /// From behind.
+    Back,
+    /// Three-quarter view from the front-left, slightly above.
+    Isometric,
+}
+
+impl ThumbnailView {
+    pub(crate) fn camera(self, version: ThumbnailVersion) -> Camera {
+        match (version, self) {
+            (ThumbnailVersion::V1, ThumbnailView::Front) => Camera {
+                yaw: 180.0,
+                pitch: 90.0,
+                scale: 1.0,
+            },
+            (ThumbnailVersion::V1, ThumbnailView::Back) => Camera {
+                yaw: 0.0,
+                pitch: 90.0,
+                scale: 1.0,
+            },
+            (ThumbnailVersion::V1, ThumbnailView::Isometric) => Camera {
+                yaw: 215.0,
+                pitch: 75.0,
+                scale: 1.0,
+            },
+        }
+    }
+}
+
+/// Reuse of the GPU renderer between [`thumbnail`] calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CachePolicy {
+    /// Keep one renderer for the process and reuse it (device creation and model loading dominate
+    /// a single thumbnail). Concurrent calls take turns on it; [`crate::render_skin_image`] shares
+    /// it too.
+    #[default]
+    ReuseRenderer,
+    /// Create and drop a renderer for this call.
+    Fresh,
+}
+
+/// Everything that determines a thumbnail besides the skin itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThumbnailSpec {
+    pub version: ThumbnailVersion,
+    pub pose: ThumbnailPose,
+    pub view: ThumbnailView,
+    pub skin_type: SkinType,
+    /// Width and height in pixels (thumbnails are square).
+    pub size: u32,
+    /// [`OutputFormat::Png`] or [`OutputFormat::WebP`].
+    pub format: OutputFormat,
+    /// Straight RGBA background; `[0, 0, 0, 0]` is transparent.
+    pub background: [u8; 4],
+    /// Does not affect the output, so it is not part of [`ThumbnailSpec::cache_key`].
+    pub cache: CachePolicy,
+}
+
+impl Default for ThumbnailSpec {
+    fn default() -> Self {
+        Self {
+            version: ThumbnailVersion::V1,
+            pose: ThumbnailPose::Stand,
+            view: ThumbnailView::Front,
+            skin_type: SkinType::Classic,
+            size: 256,
+            format: OutputFormat::Png,
+            background: [0, 0, 0, 0],
+            cache: CachePolicy::ReuseRenderer,
+        }
+    }
+}
+
+impl ThumbnailSpec {
+    /// Stable key for the thumbnail of `skin_bytes` under this spec, e.g.
+    /// `"v1-0123456789abcdef"`. Equal keys mean equal output parameters; the hash (FNV-1a) does
+    /// not depend on the Rust version or platform.
+    pub fn cache_key(&self, skin_bytes: &[u8]) -> String {
+        let params = format!(
+            "{:?}|{:?}|{:?}|{:?}|{}|{}|{:?}",
+            self.version,
+            self.pose,
+            self.view,
+            self.skin_type,
+            self.size,
+            self.format.extension(),
+            self.background
+        );
+        let mut hash = fnv1a(fnv1a_start(), params.as_bytes());
+        hash = fnv1a(hash, &[0]);
+        hash = fnv1a(hash, skin_bytes);
+        let version = match self.version {
+            ThumbnailVersion::V1 => "v1",
+        };
+        format!("{version}-{hash:016x}")
+    }
+}
+
+/// Render `skin_bytes` (a skin PNG) per `spec` and return the encoded image.
+pub fn thumbnail(skin_bytes: &[u8], spec: &ThumbnailSpec) -> Result<Vec<u8>, EidolonError> {
+    check_format(spec)?;
+    thumbnail_decoded(&SkinImage::from_memory(skin_bytes)?, spec)
+}
+
+/// Outcome of [`thumbnail_if_changed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Refresh {
+    /// The skin is the one the caller already has a thumbnail of; nothing was rendered. Services
+    /// answer `304 Not Modified`.
+    Unchanged,
+    /// The skin changed (or the caller had none): the new thumbnail and the hash to pass next
+    /// time.
+    Rendered { skin_hash: String, image: Vec<u8> },
+}
+
+/// Fetch `id` from `provider` and render its thumbnail only if the skin changed since
+/// `last_hash`, a `skin_hash` from an earlier [`Refresh::Rendered`] (or the `skin-hash` of the
+/// render's [`crate::metadata::RenderMetadata`]); matched case-insensitively.
+///
+/// The hash covers the decoded pixels, so a re-encoded PNG of the same skin is still
+/// [`Refresh::Unchanged`]. It does not cover `spec`: when the spec changes, pass `None`.
+pub fn thumbnail_if_changed(
+    provider: &impl SkinProvider,
+    id: &PlayerId,
+    last_hash: Option<&str>,
+    spec: &ThumbnailSpec,
+) -> Result<Refresh, EidolonError> {
+    check_format(spec)?;
+    let data = provider.fetch(id)?;
+    let skin = SkinImage::from_memory(&data.bytes)?;
+    let skin_hash = SkinId(skin.content_hash()).to_string();
+    if last_hash.is_some_and(|last| last.trim().eq_ignore_ascii_case(&skin_hash)) {
+        return Ok(Refresh::Unchanged);
+    }
+    let image = thumbnail_decoded(&skin, spec)?;
+    Ok(Refresh::Rendered { skin_hash, image })
+}
+
+fn check_format(spec: &ThumbnailSpec) -> Result<(), EidolonError> {
+    if !matches!(spec.format, OutputFormat::Png | OutputFormat::WebP) {
+        return Err(EidolonError::texture(
+            "thumbnails support PNG and WebP output only",
+        ));
+    }
+    Ok(())
+}
+
+fn thumbnail_decoded(skin: &SkinImage, spec: &ThumbnailSpec) -> Result<Vec<u8>, EidolonError> {
+    match spec.cache {
+        CachePolicy::Fresh => render_thumbnail(&mut Renderer::new()?, skin, spec),
+        CachePolicy::ReuseRenderer => {
+            with_shared_renderer(|renderer| render_thumbnail(renderer, skin, spec))
+        }
+    }
+}
+
+fn render_thumbnail(
+    renderer: &mut Renderer,
+    skin: &SkinImage,
+    spec: &ThumbnailSpec,
+) -> Result<Vec<u8>, EidolonError> {
+    match spec.version {
+        ThumbnailVersion::V1 => {
+            renderer.set_lighting(Lighting::default());
+            renderer.set_options(RenderOptions::default());
+        }
+    }
+    let [r, g, b, a] = spec.background.map(|c| c as f64 / 255.0);
+    renderer.set_clear_color(r, g, b, a);
+
+    let skin = renderer.upload_skin(skin);
+    let character = Character {
+        skin_type: spec.skin_type,
+        posture: spec.pose.posture(),
+        ..Character::new()
+    };
+    let camera = spec.view.camera(spec.version);
+    let image = renderer.render(&character, &skin, &camera, spec.size, spec.size)?;
+
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut bytes), spec.format.as_image_format())
+        .map_err(|e| EidolonError::texture(format!("failed to encode thumbnail: {e}")))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_key_is_pinned() {
+        // Changing this value invalidates every stored thumbnail; only do so with a new version.
+        let key = ThumbnailSpec::default().cache_key(b"skin");
+        assert_eq!(key, "v1-5f50f18db87cd238");
+    }
+
+    #[test]
+    fn cache_key_tracks_output_parameters() {
+        let spec = ThumbnailSpec::default();
+        let key = spec.cache_key(b"skin");
+        assert_ne!(key, spec.cache_key(b"other skin"));
+        let back = ThumbnailSpec {
+            view: ThumbnailView::Back,
+            ..spec
+        };
+        assert_ne!(key, back.cache_key(b"skin"));
+        let webp = ThumbnailSpec {
+            format: OutputFormat::WebP,
+            ..spec
+        };
+        assert_ne!(key, webp.cache_key(b"skin"));
+        let fresh = ThumbnailSpec {
+            cache: CachePolicy::Fresh,
+            ..spec
+        };
+        assert_eq!(key, fresh.cache_key(b"skin"));
+    }
+
+    #[test]
+    fn views_differ() {
+        let front = ThumbnailView::Front.camera(ThumbnailVersion::V1);
+        let back = ThumbnailView::Back.camera(ThumbnailVersion::V1);
+        assert_ne!(front.yaw, back.yaw);
+    }
+}
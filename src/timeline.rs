@@ -0,0 +1,247 @@
+//! Skin history strips for profile pages: every past skin of a player rendered with one
+//! consistent preset, left to right in the order given, above a time axis with a label (usually
+//! the date the skin was worn from) under each.
+//!
+//! Labels use Cantarell, embedded in the library, unless [`TimelineStrip::with_font`] is given
+//! another font.
+
+use ab_glyph::FontArc;
+use image::{imageops, Rgba, RgbaImage};
+
+use crate::card::{draw_text, TextAlign, TextField};
+use crate::character::{Character, SkinType};
+use crate::error::EidolonError;
+use crate::provider::HttpGet;
+use crate::renderer::Renderer;
+use crate::texture::SkinImage;
+use crate::thumbnail::{ThumbnailPose, ThumbnailVersion, ThumbnailView};
+
+/// Cantarell Regular (SIL Open Font License 1.1).
+const DEFAULT_FONT: &[u8] = include_bytes!("../resources/Cantarell-Regular.ttf");
+
+/// Thickness of the time axis in pixels.
+const AXIS_THICKNESS: u32 = 2;
+/// Edge of the square marking each skin on the axis, in pixels.
+const MARKER_SIZE: u32 = 6;
+
+/// One skin on a timeline.
+#[derive(Debug, Clone)]
+pub struct TimelineEntry {
+    /// Text under the render, e.g. `2021-06-14`.
+    pub label: String,
+    pub skin: SkinImage,
+    pub skin_type: SkinType,
+}
+
+impl TimelineEntry {
+    /// A classic-armed entry.
+    pub fn new(label: impl Into<String>, skin: SkinImage) -> Self {
+        Self {
+            label: label.into(),
+            skin,
+            skin_type: SkinType::Classic,
+        }
+    }
+
+    /// An entry labelled with the UTC date of `unix_seconds`, as `YYYY-MM-DD`.
+    pub fn dated(unix_seconds: i64, skin: SkinImage) -> Self {
+        Self::new(date_label(unix_seconds), skin)
+    }
+
+    /// Download the skin at `url` with `http`. Missing skins are [`EidolonError::NotFound`].
+    pub fn fetch(
+        label: impl Into<String>,
+        url: &str,
+        http: &impl HttpGet,
+    ) -> Result<Self, EidolonError> {
+        let bytes = http
+            .get(url)?
+            .ok_or_else(|| EidolonError::not_found(format!("no skin at {url}")))?;
+        Ok(Self::new(label, SkinImage::from_memory(&bytes)?))
+    }
+}
+
+/// Look of a [`TimelineStrip`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimelineStyle {
+    pub pose: ThumbnailPose,
+    /// Camera of every render, pinned like [`ThumbnailVersion::V1`] thumbnails.
+    pub view: ThumbnailView,
+    /// Size of each render in pixels; the strip is `cell_width` times the number of entries wide.
+    pub cell_width: u32,
+    pub cell_height: u32,
+    /// Pixel height of the labels.
+    pub label_size: f32,
+    /// Straight RGBA of the axis and labels.
+    pub ink: [u8; 4],
+    /// Straight RGBA background; `[0, 0, 0, 0]` is transparent.
+    pub background: [u8; 4],
+}
+
+impl Default for TimelineStyle {
+    fn default() -> Self {
+        Self {
+            pose: ThumbnailPose::Stand,
+            view: ThumbnailView::Isometric,
+            cell_width: 160,
+            cell_height: 240,
+            label_size: 20.0,
+            ink: [90, 90, 90, 255],
+            background: [0, 0, 0, 0],
+        }
+    }
+}
+
+/// Renders skin histories in one [`TimelineStyle`]; see the [module docs](self).
+#[derive(Debug, Clone)]
+pub struct TimelineStrip {
+    pub style: TimelineStyle,
+    font: FontArc,
+}
+
+impl TimelineStrip {
+    /// A strip with labels in the embedded font.
+    pub fn new(style: TimelineStyle) -> Self {
+        let font = FontArc::try_from_slice(DEFAULT_FONT).expect("embedded font is valid");
+        Self { style, font }
+    }
+
+    /// A strip with labels in `font` (TrueType or OpenType bytes). Unreadable fonts are
+    /// [`EidolonError::Template`].
+    pub fn with_font(style: TimelineStyle, font: Vec<u8>) -> Result<Self, EidolonError> {
+        let font = FontArc::try_from_vec(font)
+            .map_err(|e| EidolonError::template(format!("unreadable label font: {e}")))?;
+        Ok(Self { style, font })
+    }
+
+    /// Render `entries` left to right. The renderer's light rig and options apply to the
+    /// characters; its clear color shows behind each render, so leave it transparent. An empty
+    /// list is [`EidolonError::NotFound`].
+    pub fn render(
+        &self,
+        renderer: &Renderer,
+        entries: &[TimelineEntry],
+    ) -> Result<RgbaImage, EidolonError> {
+        if entries.is_empty() {
+            return Err(EidolonError::not_found("no skins to put on the timeline"));
+        }
+        let style = &self.style;
+        let (cell_width, cell_height) = (style.cell_width.max(1), style.cell_height.max(1));
+        let band = (style.label_size * 2.0).ceil() as u32 + MARKER_SIZE;
+        let mut strip = RgbaImage::from_pixel(
+            cell_width * entries.len() as u32,
+            cell_height + band,
+            Rgba(style.background),
+        );
+
+        let camera = style.view.camera(ThumbnailVersion::V1);
+        for (i, entry) in entries.iter().enumerate() {
+            let character = Character {
+                skin_type: entry.skin_type,
+                posture: style.pose.posture(),
+                ..Character::new()
+            };
+            let skin = renderer.upload_skin(&entry.skin);
+            let render = renderer.render(&character, &skin, &camera, cell_width, cell_height)?;
+            imageops::overlay(&mut strip, &render, i64::from(cell_width) * i as i64, 0);
+        }
+
+        // The axis runs between the first and last markers, one under each render's middle.
+        let axis_y = cell_height + MARKER_SIZE / 2 - AXIS_THICKNESS / 2;
+        let first = cell_width / 2;
+        let last = first + cell_width * (entries.len() as u32 - 1);
+        fill(
+            &mut strip,
+            first,
+            axis_y,
+            last - first + 1,
+            AXIS_THICKNESS,
+            style.ink,
+        );
+        for (i, entry) in entries.iter().enumerate() {
+            let center = first + cell_width * i as u32;
+            fill(
+                &mut strip,
+                center.saturating_sub(MARKER_SIZE / 2),
+                cell_height,
+                MARKER_SIZE,
+                MARKER_SIZE,
+                style.ink,
+            );
+            let label = TextField {
+                text: String::new(),
+                x: center as f32,
+                y: (cell_height + MARKER_SIZE) as f32 + style.label_size * 0.25,
+                size: style.label_size,
+                font: String::new(),
+                color: style.ink,
+                align: TextAlign::Center,
+            };
+            draw_text(&mut strip, &self.font, &label, &entry.label);
+        }
+        Ok(strip)
+    }
+}
+
+impl Default for TimelineStrip {
+    fn default() -> Self {
+        Self::new(TimelineStyle::default())
+    }
+}
+
+/// Paint a `width`×`height` rectangle, clipped to `image`.
+fn fill(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: [u8; 4]) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, Rgba(color));
+        }
+    }
+}
+
+/// The UTC date of `unix_seconds` as `YYYY-MM-DD` (proleptic Gregorian calendar).
+pub fn date_label(unix_seconds: i64) -> String {
+    // Howard Hinnant's days-to-civil algorithm, with eras of 400 years starting on 0000-03-01.
+    let days = unix_seconds.div_euclid(86_400) + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_are_utc_days() {
+        assert_eq!(date_label(0), "1970-01-01");
+        assert_eq!(date_label(86_399), "1970-01-01");
+        assert_eq!(date_label(-1), "1969-12-31");
+        // Minecraft 1.8's release, with the double-layer skins.
+        assert_eq!(date_label(1_409_598_000), "2014-09-01");
+        assert_eq!(date_label(951_782_400), "2000-02-29");
+        assert_eq!(date_label(4_107_542_400), "2100-03-01");
+    }
+
+    #[test]
+    fn fetch_reports_missing_skins() {
+        let http = |_: &str| -> Result<Option<Vec<u8>>, EidolonError> { Ok(None) };
+        assert!(matches!(
+            TimelineEntry::fetch("2020", "https://skins.example.org/a.png", &http),
+            Err(EidolonError::NotFound(_))
+        ));
+        assert!(
+            TimelineStrip::with_font(TimelineStyle::default(), b"not a font".to_vec()).is_err()
+        );
+    }
+}
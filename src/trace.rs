@@ -0,0 +1,199 @@
+//! Request correlation: a [`TraceContext`] carries a W3C Trace Context (`traceparent`) through a
+//! render, so operators can follow one request across their gateway, Eidolon and the skin
+//! fetcher.
+//!
+//! Services parse the incoming `traceparent` header (or start a new trace), run the fetch and
+//! render inside [`TraceContext::span`], and answer with [`TraceContext::request_id`] and a child
+//! `traceparent`. Every log line Eidolon emits inside the span, including the renderer's own
+//! spans and [`crate::throttle`] backoff warnings, then carries the trace and span IDs.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A W3C trace context: the trace a request belongs to and the span of the current hop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TraceContext {
+    pub trace_id: [u8; 16],
+    /// ID of the span that sent the request (the `parent-id` of the header).
+    pub span_id: [u8; 8],
+    /// The caller's sampling decision (`trace-flags` bit 0).
+    pub sampled: bool,
+}
+
+impl TraceContext {
+    /// A new trace with random IDs, sampled.
+    pub fn new() -> Self {
+        let [a, b, c] = [next_id(), next_id(), next_id()];
+        let mut trace_id = [0; 16];
+        trace_id[..8].copy_from_slice(&a.to_be_bytes());
+        trace_id[8..].copy_from_slice(&b.to_be_bytes());
+        Self {
+            trace_id,
+            span_id: c.to_be_bytes(),
+            sampled: true,
+        }
+    }
+
+    /// Parse a `traceparent` header, e.g.
+    /// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`. `None` if it is malformed or
+    /// has all-zero IDs; headers of later versions are read by their first four fields, as the
+    /// specification asks.
+    pub fn from_traceparent(header: &str) -> Option<Self> {
+        let mut fields = header.trim().split('-');
+        let version = fields.next()?;
+        let trace_id = fields.next()?;
+        let span_id = fields.next()?;
+        let flags = fields.next()?;
+        if version.len() != 2 || version.eq_ignore_ascii_case("ff") {
+            return None;
+        }
+        u8::from_str_radix(version, 16).ok()?;
+        if version == "00" && fields.next().is_some() {
+            return None;
+        }
+        let trace_id: [u8; 16] = hex_bytes(trace_id)?;
+        let span_id: [u8; 8] = hex_bytes(span_id)?;
+        let [flags]: [u8; 1] = hex_bytes(flags)?;
+        if trace_id == [0; 16] || span_id == [0; 8] {
+            return None;
+        }
+        Some(Self {
+            trace_id,
+            span_id,
+            sampled: flags & 1 == 1,
+        })
+    }
+
+    /// The context parsed from `header`, or a new trace if it is missing or malformed.
+    pub fn from_traceparent_or_new(header: Option<&str>) -> Self {
+        header.and_then(Self::from_traceparent).unwrap_or_default()
+    }
+
+    /// The same trace with a new span ID: the context to send downstream or back to the caller.
+    pub fn child(&self) -> Self {
+        Self {
+            span_id: next_id().to_be_bytes(),
+            ..*self
+        }
+    }
+
+    /// Version-00 `traceparent` header value.
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            hex(&self.trace_id),
+            hex(&self.span_id),
+            u8::from(self.sampled)
+        )
+    }
+
+    /// The trace ID as 32 lowercase hex digits, for `X-Request-Id` headers and log search.
+    pub fn request_id(&self) -> String {
+        hex(&self.trace_id)
+    }
+
+    /// An `INFO` span named `request` with `request_id` and `span_id` fields. Enter it around the
+    /// work for one request.
+    pub fn span(&self) -> tracing::Span {
+        tracing::info_span!(
+            "request",
+            request_id = %self.request_id(),
+            span_id = %hex(&self.span_id),
+        )
+    }
+}
+
+impl Default for TraceContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut s, b| {
+        let _ = write!(s, "{b:02x}");
+        s
+    })
+}
+
+/// Exactly `N` bytes from `2N` hex digits.
+fn hex_bytes<const N: usize>(s: &str) -> Option<[u8; N]> {
+    if s.len() != 2 * N || !s.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+/// A non-zero pseudo-random ID: splitmix64 over the clock and a process-wide counter. Unique
+/// enough to correlate logs; not for anything secret.
+fn next_id() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos() as u64);
+    let mut z = nanos
+        ^ COUNTER
+            .fetch_add(1, Ordering::Relaxed)
+            .wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        ^ u64::from(std::process::id()) << 32;
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (z ^ (z >> 31)).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn traceparent_round_trips() {
+        let context = TraceContext::from_traceparent(HEADER).unwrap();
+        assert_eq!(context.request_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(
+            context.span_id,
+            [0x00, 0xf0, 0x67, 0xaa, 0x0b, 0xa9, 0x02, 0xb7]
+        );
+        assert!(context.sampled);
+        assert_eq!(context.traceparent(), HEADER);
+    }
+
+    #[test]
+    fn malformed_headers_are_rejected() {
+        for header in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e473-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "00-4bf92f3577b34da6a3ce929d0e0e473g-00f067aa0ba902b7-01",
+        ] {
+            assert_eq!(TraceContext::from_traceparent(header), None, "{header}");
+        }
+        // Later versions may append fields.
+        let future = "cc-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00-extra";
+        assert!(!TraceContext::from_traceparent(future).unwrap().sampled);
+    }
+
+    #[test]
+    fn children_keep_the_trace() {
+        let parent = TraceContext::from_traceparent_or_new(Some(HEADER));
+        let child = parent.child();
+        assert_eq!(child.trace_id, parent.trace_id);
+        assert_ne!(child.span_id, parent.span_id);
+
+        let fresh = TraceContext::from_traceparent_or_new(Some("garbage"));
+        assert_ne!(fresh.trace_id, parent.trace_id);
+        assert_ne!(TraceContext::new().trace_id, TraceContext::new().trace_id);
+        assert!(TraceContext::from_traceparent(&fresh.traceparent()).is_some());
+    }
+}
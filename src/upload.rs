@@ -0,0 +1,422 @@
+//! Sanitizing user-submitted skins: [`process_upload`] checks, repairs and normalizes skin PNG
+//! bytes under an [`UploadPolicy`] before a service stores or renders them.
+//!
+//! The steps run in order: byte-size and PNG header checks (before any pixel is decoded),
+//! decoding, single→double layer conversion, downscaling to the HD cap, arm-width detection,
+//! repair, and re-encoding as a plain RGBA8 PNG. Uploads that look the same in game therefore come
+//! out as the same bytes. [`process_upload_moderated`] also runs a
+//! [`crate::moderation::ModerationHook`] on the result.
+
+use std::io::Cursor;
+
+use image::{imageops, ImageFormat, RgbaImage};
+
+use crate::character::SkinType;
+use crate::color;
+use crate::converter::single2double_in_place;
+use crate::error::EidolonError;
+use crate::moderation::{moderate, ModerationHook, ModerationReport};
+use crate::texture::SkinImage;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Widest upload that is decoded at all (HD scale 64), whatever the policy's cap.
+pub const MAX_UPLOAD_WIDTH: u32 = 4096;
+
+/// What [`process_upload`] accepts and how it normalizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadPolicy {
+    /// Largest accepted upload in bytes.
+    pub max_bytes: usize,
+    /// Largest stored HD scale (width / 64). Wider skins are downscaled to `64 * max_hd_scale`
+    /// pixels; 1 stores every skin at vanilla resolution.
+    pub max_hd_scale: u32,
+    /// Accept skins whose atlas only fits slim (3px) arms; otherwise they are rejected.
+    pub allow_slim: bool,
+    /// Drop the upload's PNG text chunks (signatures, comments, tool metadata) instead of copying
+    /// them into the output. Other ancillary chunks (color profiles, timestamps) are always
+    /// dropped.
+    pub strip_signatures: bool,
+}
+
+impl Default for UploadPolicy {
+    /// 1 MiB, vanilla resolution, slim allowed, text chunks stripped.
+    fn default() -> Self {
+        Self {
+            max_bytes: 1 << 20,
+            max_hd_scale: 1,
+            allow_slim: true,
+            strip_signatures: true,
+        }
+    }
+}
+
+/// A sanitized skin: normalized PNG bytes plus what was found and changed on the way.
+#[derive(Debug, Clone)]
+pub struct ProcessedSkin {
+    /// Square RGBA8 PNG, `64 * hd_scale` pixels wide.
+    pub png: Vec<u8>,
+    /// The same pixels, ready for [`crate::renderer::Renderer::upload_skin`].
+    pub skin: SkinImage,
+    /// Arm width found in the atlas. Slim skins painted out to classic width cannot be told apart
+    /// and are reported as classic (they render correctly with either model); single-layer skins
+    /// are always classic.
+    pub skin_type: SkinType,
+    pub hd_scale: u32,
+    /// Width and height as uploaded.
+    pub original_size: (u32, u32),
+    /// Whether the upload was a single-layer (2:1) skin.
+    pub converted: bool,
+    /// Pixels changed by repair: see [`process_upload`].
+    pub repaired_pixels: u32,
+}
+
+/// Check, repair and normalize the skin PNG in `bytes`.
+///
+/// Rejected with [`EidolonError::Texture`]: uploads over [`UploadPolicy::max_bytes`], non-PNG
+/// data, sizes other than 64·k × 64·k or 64·k × 32·k (up to [`MAX_UPLOAD_WIDTH`]), and slim skins
+/// when [`UploadPolicy::allow_slim`] is off.
+///
+/// Repair matches what the game shows: base-layer pixels become opaque, pixels outside every
+/// body-part face are cleared, and fully transparent pixels lose their color.
+pub fn process_upload(bytes: &[u8], policy: &UploadPolicy) -> Result<ProcessedSkin, EidolonError> {
+    if bytes.len() > policy.max_bytes {
+        return Err(EidolonError::texture(format!(
+            "upload is {} bytes, the limit is {}",
+            bytes.len(),
+            policy.max_bytes
+        )));
+    }
+    if !bytes.starts_with(PNG_SIGNATURE) {
+        return Err(EidolonError::texture("upload is not a PNG"));
+    }
+    let reader = png::Decoder::new(Cursor::new(bytes))
+        .read_info()
+        .map_err(|e| EidolonError::texture(format!("failed to read PNG: {e}")))?;
+    let (width, height) = (reader.info().width, reader.info().height);
+    check_size(width, height)?;
+    let text = if policy.strip_signatures {
+        Vec::new()
+    } else {
+        text_chunks(reader.info())
+    };
+    drop(reader);
+
+    let image = image::load_from_memory_with_format(bytes, ImageFormat::Png)
+        .map_err(|e| EidolonError::texture(format!("failed to decode PNG: {e}")))?;
+    let converted = width == height * 2;
+    let mut rgba = image.into_rgba8();
+    if converted {
+        single2double_in_place(&mut rgba)?;
+    }
+    // The re-encoded skin carries no color chunks, so its pixels must already be sRGB.
+    color::normalize_png(bytes, &mut rgba);
+
+    let mut hd_scale = width / 64;
+    let max_hd_scale = policy.max_hd_scale.max(1);
+    if hd_scale > max_hd_scale {
+        hd_scale = max_hd_scale;
+        let side = 64 * hd_scale;
+        rgba = imageops::resize(&rgba, side, side, imageops::FilterType::Nearest);
+    }
+
+    let skin_type = if !converted && is_slim(&rgba, hd_scale) {
+        SkinType::Slim
+    } else {
+        SkinType::Classic
+    };
+    if skin_type == SkinType::Slim && !policy.allow_slim {
+        return Err(EidolonError::texture("slim skins are not accepted"));
+    }
+    let repaired_pixels = repair(&mut rgba, skin_type, hd_scale);
+
+    let png = encode(&rgba, &text)?;
+    Ok(ProcessedSkin {
+        png,
+        skin: SkinImage::from_rgba(rgba)?,
+        skin_type,
+        hd_scale,
+        original_size: (width, height),
+        converted,
+        repaired_pixels,
+    })
+}
+
+fn check_size(width: u32, height: u32) -> Result<(), EidolonError> {
+    let layout_ok =
+        width > 0 && width.is_multiple_of(64) && (height == width || height * 2 == width);
+    if !layout_ok || width > MAX_UPLOAD_WIDTH {
+        return Err(EidolonError::texture(format!(
+            "skin must be 64·k×64·k or 64·k×32·k pixels, at most {MAX_UPLOAD_WIDTH} wide; got \
+             {width}x{height}"
+        )));
+    }
+    Ok(())
+}
+
+/// A body-part box in 64px atlas coordinates: texture origin `(u, v)` and size `(w, h, d)`.
+type UvBox = (u32, u32, u32, u32, u32);
+
+/// Base-layer boxes; the two arms come last and are one pixel narrower on slim skins.
+const BASE_BOXES: [UvBox; 6] = [
+    (0, 0, 8, 8, 8),    // head
+    (16, 16, 8, 12, 4), // body
+    (0, 16, 4, 12, 4),  // right leg
+    (16, 48, 4, 12, 4), // left leg
+    (40, 16, 4, 12, 4), // right arm
+    (32, 48, 4, 12, 4), // left arm
+];
+
+/// Overlay-layer boxes, in the same order as [`BASE_BOXES`].
+const OVERLAY_BOXES: [UvBox; 6] = [
+    (32, 0, 8, 8, 8),   // hat
+    (16, 32, 8, 12, 4), // jacket
+    (0, 32, 4, 12, 4),  // right pants
+    (0, 48, 4, 12, 4),  // left pants
+    (40, 32, 4, 12, 4), // right sleeve
+    (48, 48, 4, 12, 4), // left sleeve
+];
+
+/// The two face rectangles `(x0, y0, x1, y1)` of a box: top and bottom in one strip, the four
+/// sides in the strip below.
+fn face_rects((u, v, w, h, d): UvBox) -> [(u32, u32, u32, u32); 2] {
+    [
+        (u + d, v, u + d + 2 * w, v + d),
+        (u, v + d, u + 2 * d + 2 * w, v + d + h),
+    ]
+}
+
+/// Boxes of one layer for `skin_type`.
+fn layer_boxes(boxes: [UvBox; 6], skin_type: SkinType) -> [UvBox; 6] {
+    let mut boxes = boxes;
+    if skin_type == SkinType::Slim {
+        for arm in &mut boxes[4..] {
+            arm.2 = 3;
+        }
+    }
+    boxes
+}
+
+/// Per-pixel layer of a `64 * scale` atlas: `Some(true)` base, `Some(false)` overlay, `None`
+/// unused.
+fn layer_mask(skin_type: SkinType, scale: u32) -> Vec<Option<bool>> {
+    let side = 64 * scale;
+    let mut mask = vec![None; (side * side) as usize];
+    for (boxes, base) in [(BASE_BOXES, true), (OVERLAY_BOXES, false)] {
+        for uv_box in layer_boxes(boxes, skin_type) {
+            for (x0, y0, x1, y1) in face_rects(uv_box) {
+                for y in y0 * scale..y1 * scale {
+                    for x in x0 * scale..x1 * scale {
+                        mask[(y * side + x) as usize] = Some(base);
+                    }
+                }
+            }
+        }
+    }
+    mask
+}
+
+/// Slim skins leave the last two columns of the classic right-arm faces unused; an atlas that is
+/// transparent there is slim.
+fn is_slim(rgba: &RgbaImage, scale: u32) -> bool {
+    (20 * scale..32 * scale).all(|y| (54 * scale..56 * scale).all(|x| rgba.get_pixel(x, y)[3] == 0))
+}
+
+/// Apply the repairs of [`process_upload`] and return the number of pixels changed.
+fn repair(rgba: &mut RgbaImage, skin_type: SkinType, scale: u32) -> u32 {
+    let mask = layer_mask(skin_type, scale);
+    let mut changed = 0;
+    for (pixel, layer) in rgba.pixels_mut().zip(mask) {
+        let repaired = match layer {
+            Some(true) => [pixel[0], pixel[1], pixel[2], 255],
+            Some(false) if pixel[3] > 0 => pixel.0,
+            _ => [0; 4],
+        };
+        if repaired != pixel.0 {
+            pixel.0 = repaired;
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Text chunk of the upload: keyword, text and whether it was Latin-1 (`tEXt`/`zTXt`).
+type TextChunk = (String, String, bool);
+
+fn text_chunks(info: &png::Info) -> Vec<TextChunk> {
+    let latin1 = info
+        .uncompressed_latin1_text
+        .iter()
+        .map(|c| (c.keyword.clone(), c.text.clone()))
+        .chain(
+            info.compressed_latin1_text
+                .iter()
+                .filter_map(|c| Some((c.keyword.clone(), c.get_text().ok()?))),
+        )
+        .map(|(keyword, text)| (keyword, text, true));
+    let utf8 = info
+        .utf8_text
+        .iter()
+        .filter_map(|c| Some((c.keyword.clone(), c.get_text().ok()?, false)));
+    latin1.chain(utf8).collect()
+}
+
+pub(crate) fn encode(rgba: &RgbaImage, text: &[TextChunk]) -> Result<Vec<u8>, EidolonError> {
+    let err = |e: png::EncodingError| EidolonError::texture(format!("failed to encode PNG: {e}"));
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, rgba.width(), rgba.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    for (keyword, text, latin1) in text {
+        if *latin1 {
+            encoder.add_text_chunk(keyword.clone(), text.clone())
+        } else {
+            encoder.add_itxt_chunk(keyword.clone(), text.clone())
+        }
+        .map_err(err)?;
+    }
+    let mut writer = encoder.write_header().map_err(err)?;
+    writer.write_image_data(rgba.as_raw()).map_err(err)?;
+    writer.finish().map_err(err)?;
+    Ok(out)
+}
+
+/// [`process_upload`], then `hook` on the sanitized skin's region crops. The report comes back
+/// alongside the skin; acting on its verdict is up to the caller. Hook errors fail the call.
+pub fn process_upload_moderated(
+    bytes: &[u8],
+    policy: &UploadPolicy,
+    hook: &impl ModerationHook,
+) -> Result<(ProcessedSkin, ModerationReport), EidolonError> {
+    let processed = process_upload(bytes, policy)?;
+    let report = moderate(&processed.skin, processed.skin_type, hook)?;
+    Ok((processed, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn png_bytes(image: &RgbaImage, text: &[TextChunk]) -> Vec<u8> {
+        encode(image, text).unwrap()
+    }
+
+    fn opaque(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_pixel(width, height, Rgba([200, 100, 50, 255]))
+    }
+
+    #[test]
+    fn rejects_oversized_and_foreign_uploads() {
+        let bytes = png_bytes(&opaque(64, 64), &[]);
+        let small = UploadPolicy {
+            max_bytes: bytes.len() - 1,
+            ..UploadPolicy::default()
+        };
+        assert!(process_upload(&bytes, &small).is_err());
+        assert!(process_upload(b"GIF89a", &UploadPolicy::default()).is_err());
+        for (w, h) in [(64, 48), (100, 100), (0, 0), (8192, 8192)] {
+            assert!(check_size(w, h).is_err(), "{w}x{h}");
+        }
+    }
+
+    #[test]
+    fn single_layer_uploads_are_converted_to_classic() {
+        let processed =
+            process_upload(&png_bytes(&opaque(64, 32), &[]), &UploadPolicy::default()).unwrap();
+        assert!(processed.converted);
+        assert_eq!(processed.original_size, (64, 32));
+        assert_eq!(processed.skin.rgba().dimensions(), (64, 64));
+        assert_eq!(processed.skin_type, SkinType::Classic);
+    }
+
+    #[test]
+    fn hd_uploads_are_capped() {
+        let bytes = png_bytes(&opaque(256, 256), &[]);
+        let capped = process_upload(&bytes, &UploadPolicy::default()).unwrap();
+        assert_eq!((capped.hd_scale, capped.skin.rgba().width()), (1, 64));
+        let policy = UploadPolicy {
+            max_hd_scale: 8,
+            ..UploadPolicy::default()
+        };
+        let kept = process_upload(&bytes, &policy).unwrap();
+        assert_eq!((kept.hd_scale, kept.skin.rgba().width()), (4, 256));
+    }
+
+    #[test]
+    fn slim_skins_follow_policy() {
+        let mut image = opaque(64, 64);
+        for y in 20..32 {
+            for x in 54..56 {
+                image.put_pixel(x, y, Rgba([0; 4]));
+            }
+        }
+        let bytes = png_bytes(&image, &[]);
+        let processed = process_upload(&bytes, &UploadPolicy::default()).unwrap();
+        assert_eq!(processed.skin_type, SkinType::Slim);
+        let classic_only = UploadPolicy {
+            allow_slim: false,
+            ..UploadPolicy::default()
+        };
+        assert!(process_upload(&bytes, &classic_only).is_err());
+    }
+
+    #[test]
+    fn repair_opaques_base_and_clears_unused_areas() {
+        let mut image = RgbaImage::from_pixel(64, 64, Rgba([10, 20, 30, 0]));
+        // Head front (base layer), hat front (overlay) and the unused top-left corner.
+        image.put_pixel(8, 8, Rgba([1, 2, 3, 40]));
+        image.put_pixel(40, 8, Rgba([4, 5, 6, 40]));
+        image.put_pixel(0, 0, Rgba([7, 8, 9, 255]));
+        let processed = process_upload(&png_bytes(&image, &[]), &UploadPolicy::default()).unwrap();
+        let rgba = processed.skin.rgba();
+        assert_eq!(rgba.get_pixel(8, 8).0, [1, 2, 3, 255]);
+        assert_eq!(rgba.get_pixel(40, 8).0, [4, 5, 6, 40]);
+        assert_eq!(rgba.get_pixel(0, 0).0, [0; 4]);
+        assert_eq!(rgba.get_pixel(33, 8).0, [0; 4]);
+        assert!(processed.repaired_pixels > 0);
+
+        let again = process_upload(&processed.png, &UploadPolicy::default()).unwrap();
+        assert_eq!(again.repaired_pixels, 0);
+        assert_eq!(again.png, processed.png);
+    }
+
+    #[test]
+    fn moderation_runs_on_the_sanitized_skin() {
+        use crate::moderation::{RegionCrop, Verdict};
+        // Single-layer upload: the hook sees the converted, repaired atlas.
+        let bytes = png_bytes(&opaque(64, 32), &[]);
+        let hook = |crops: &[RegionCrop]| {
+            assert_eq!(crops[0].image.get_pixel(0, 0).0, [200, 100, 50, 255]);
+            Ok(ModerationReport {
+                verdict: Verdict::Review,
+                findings: Vec::new(),
+            })
+        };
+        let (processed, report) =
+            process_upload_moderated(&bytes, &UploadPolicy::default(), &hook).unwrap();
+        assert!(processed.converted);
+        assert_eq!(report.verdict, Verdict::Review);
+
+        let failing = |_: &[RegionCrop]| Err(EidolonError::texture("classifier offline"));
+        assert!(process_upload_moderated(&bytes, &UploadPolicy::default(), &failing).is_err());
+    }
+
+    #[test]
+    fn text_chunks_are_stripped_unless_kept() {
+        let text = [("Comment".to_string(), "signed by someone".to_string(), true)];
+        let bytes = png_bytes(&opaque(64, 64), &text);
+        let stripped = process_upload(&bytes, &UploadPolicy::default()).unwrap();
+        let keep = UploadPolicy {
+            strip_signatures: false,
+            ..UploadPolicy::default()
+        };
+        let kept = process_upload(&bytes, &keep).unwrap();
+        let read = |png: &[u8]| {
+            let reader = png::Decoder::new(Cursor::new(png)).read_info().unwrap();
+            text_chunks(reader.info())
+        };
+        assert!(read(&stripped.png).is_empty());
+        assert_eq!(read(&kept.png), text);
+    }
+}
@@ -0,0 +1,273 @@
+//! Publishing renders to a Discord webhook: the encoded image is attached to a message, with an
+//! optional embed that shows it and lists the render's [`RenderMetadata`].
+//!
+//! Like the downloading skin providers, the crate ships no HTTP client: [`DiscordWebhook::post`]
+//! builds the `multipart/form-data` request and sends it through a caller-supplied [`HttpPost`].
+
+use crate::error::EidolonError;
+use crate::metadata::RenderMetadata;
+use crate::sink::content_type;
+
+/// Discord's limit on fields per embed.
+const MAX_EMBED_FIELDS: usize = 25;
+
+/// Blocking HTTP POST for publishing: the response body of a 2xx response. Other statuses and
+/// transport failures are [`EidolonError::Publish`].
+pub trait HttpPost: Send + Sync {
+    fn post(&self, url: &str, content_type: &str, body: &[u8]) -> Result<Vec<u8>, EidolonError>;
+}
+
+impl<F> HttpPost for F
+where
+    F: Fn(&str, &str, &[u8]) -> Result<Vec<u8>, EidolonError> + Send + Sync,
+{
+    fn post(&self, url: &str, content_type: &str, body: &[u8]) -> Result<Vec<u8>, EidolonError> {
+        self(url, content_type, body)
+    }
+}
+
+/// A webhook URL, `https://discord.com/api/webhooks/<id>/<token>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscordWebhook {
+    url: String,
+}
+
+impl DiscordWebhook {
+    /// Checks the URL's shape only: HTTPS and an `/api/webhooks/<id>/<token>` path, so
+    /// Discord-compatible services work too.
+    pub fn new(url: impl Into<String>) -> Result<Self, EidolonError> {
+        let url = url.into();
+        let valid = url.strip_prefix("https://").is_some_and(|rest| {
+            rest.split_once("/api/webhooks/")
+                .is_some_and(|(host, path)| {
+                    !host.is_empty()
+                        && !host.contains('/')
+                        && path
+                            .split('?')
+                            .next()
+                            .unwrap_or_default()
+                            .split('/')
+                            .count()
+                            == 2
+                })
+        });
+        if !valid {
+            return Err(EidolonError::publish(
+                "webhook URL must look like https://discord.com/api/webhooks/<id>/<token>",
+            ));
+        }
+        Ok(Self { url })
+    }
+
+    /// Send `message`. Asks Discord to confirm delivery (`wait=true`), so a rejected message
+    /// fails here rather than silently.
+    pub fn post(&self, message: &WebhookMessage, http: &impl HttpPost) -> Result<(), EidolonError> {
+        let separator = if self.url.contains('?') { '&' } else { '?' };
+        let url = format!("{}{separator}wait=true", self.url);
+        let (content_type, body) = message.multipart();
+        http.post(&url, &content_type, &body)?;
+        Ok(())
+    }
+}
+
+/// A message carrying one rendered image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookMessage {
+    /// Encoded image as written to disk (PNG, WebP or GIF).
+    pub image: Vec<u8>,
+    /// Attachment name; its extension sets the content type.
+    pub file_name: String,
+    /// Plain message text above the image.
+    pub content: Option<String>,
+    /// Overrides the webhook's display name.
+    pub username: Option<String>,
+    /// When set, the image is shown inside an embed instead of as a bare attachment.
+    pub embed: Option<Embed>,
+}
+
+/// Embed around the image.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Embed {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// `(name, value)` pairs, shown inline; Discord shows at most 25.
+    pub fields: Vec<(String, String)>,
+}
+
+impl Embed {
+    /// An embed listing the render metadata entries (camera, posture, skin hash, ...).
+    pub fn from_metadata(metadata: &RenderMetadata) -> Self {
+        Self {
+            fields: metadata
+                .entries
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            ..Self::default()
+        }
+    }
+}
+
+impl WebhookMessage {
+    pub fn new(image: Vec<u8>, file_name: impl Into<String>) -> Self {
+        Self {
+            image,
+            file_name: file_name.into(),
+            content: None,
+            username: None,
+            embed: None,
+        }
+    }
+
+    /// The Discord `payload_json` part.
+    fn payload(&self) -> serde_json::Value {
+        let mut payload = serde_json::json!({
+            "attachments": [{ "id": 0, "filename": self.file_name }],
+        });
+        if let Some(content) = &self.content {
+            payload["content"] = content.as_str().into();
+        }
+        if let Some(username) = &self.username {
+            payload["username"] = username.as_str().into();
+        }
+        if let Some(embed) = &self.embed {
+            let fields: Vec<_> = embed
+                .fields
+                .iter()
+                .take(MAX_EMBED_FIELDS)
+                .map(|(name, value)| serde_json::json!({ "name": name, "value": value, "inline": true }))
+                .collect();
+            let mut json = serde_json::json!({
+                "image": { "url": format!("attachment://{}", self.file_name) },
+                "fields": fields,
+            });
+            if let Some(title) = &embed.title {
+                json["title"] = title.as_str().into();
+            }
+            if let Some(description) = &embed.description {
+                json["description"] = description.as_str().into();
+            }
+            payload["embeds"] = serde_json::json!([json]);
+        }
+        payload
+    }
+
+    /// Content type and body of the `multipart/form-data` request.
+    fn multipart(&self) -> (String, Vec<u8>) {
+        let payload = self.payload().to_string();
+        let boundary = (0..)
+            .map(|n| format!("eidolon-boundary-{n}"))
+            .find(|b| {
+                !payload.contains(b.as_str())
+                    && !self.image.windows(b.len()).any(|w| w == b.as_bytes())
+            })
+            .expect("some boundary is absent from the body");
+        let image_type = content_type(&self.file_name);
+        // Quotes would end the header's filename early.
+        let file_name = self.file_name.replace('"', "_");
+
+        let mut body = Vec::with_capacity(self.image.len() + payload.len() + 512);
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"payload_json\"\r\n\
+                 Content-Type: application/json\r\n\r\n{payload}\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"files[0]\"; \
+                 filename=\"{file_name}\"\r\nContent-Type: {image_type}\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&self.image);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+        (format!("multipart/form-data; boundary={boundary}"), body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    const URL: &str = "https://discord.com/api/webhooks/123/token";
+
+    #[test]
+    fn webhook_urls_are_checked() {
+        assert!(DiscordWebhook::new(URL).is_ok());
+        assert!(
+            DiscordWebhook::new("https://ptb.discord.com/api/webhooks/1/t?thread_id=2").is_ok()
+        );
+        for url in [
+            "http://discord.com/api/webhooks/123/token",
+            "https://discord.com/api/webhooks/123",
+            "https://discord.com/channels/123/456",
+            "discord.com/api/webhooks/123/token",
+        ] {
+            assert!(
+                matches!(DiscordWebhook::new(url), Err(EidolonError::Publish(_))),
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn messages_post_as_multipart_with_an_embed() {
+        let mut metadata = RenderMetadata::default();
+        metadata.insert("skin-type", "slim");
+        let mut message = WebhookMessage::new(b"\x89PNG eidolon-boundary-0".to_vec(), "steve.png");
+        message.content = Some("New skin".into());
+        message.embed = Some(Embed {
+            title: Some("Steve".into()),
+            ..Embed::from_metadata(&metadata)
+        });
+
+        let sent = Mutex::new(None);
+        let http = |url: &str, content_type: &str, body: &[u8]| {
+            *sent.lock().unwrap() =
+                Some((url.to_string(), content_type.to_string(), body.to_vec()));
+            Ok(b"{}".to_vec())
+        };
+        DiscordWebhook::new(URL)
+            .unwrap()
+            .post(&message, &http)
+            .unwrap();
+        let (url, content_type, body) = sent.into_inner().unwrap().unwrap();
+        assert_eq!(url, format!("{URL}?wait=true"));
+        // The image contains the first candidate boundary, so the next one is used.
+        assert_eq!(
+            content_type,
+            "multipart/form-data; boundary=eidolon-boundary-1"
+        );
+
+        let body = String::from_utf8_lossy(&body);
+        let parts: Vec<_> = body.split("--eidolon-boundary-1").collect();
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[3], "--\r\n");
+        let (headers, json) = parts[1].split_once("\r\n\r\n").unwrap();
+        assert!(headers.contains("name=\"payload_json\""));
+        let payload: serde_json::Value = serde_json::from_str(json.trim_end()).unwrap();
+        assert_eq!(payload["content"], "New skin");
+        assert_eq!(payload["attachments"][0]["filename"], "steve.png");
+        let embed = &payload["embeds"][0];
+        assert_eq!(embed["title"], "Steve");
+        assert_eq!(embed["image"]["url"], "attachment://steve.png");
+        assert_eq!(embed["fields"][0]["name"], "skin-type");
+        assert_eq!(embed["fields"][0]["value"], "slim");
+        let (headers, image) = parts[2].split_once("\r\n\r\n").unwrap();
+        assert!(headers.contains("filename=\"steve.png\"\r\nContent-Type: image/png"));
+        assert_eq!(image, "\u{fffd}PNG eidolon-boundary-0\r\n");
+    }
+
+    #[test]
+    fn rejected_posts_are_errors() {
+        let http = |_: &str, _: &str, _: &[u8]| -> Result<Vec<u8>, EidolonError> {
+            Err(EidolonError::publish("HTTP 413"))
+        };
+        let message = WebhookMessage::new(vec![0; 4], "render.webp");
+        let result = DiscordWebhook::new(URL).unwrap().post(&message, &http);
+        assert!(matches!(result, Err(EidolonError::Publish(_))));
+    }
+}
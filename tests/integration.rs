@@ -7,9 +7,10 @@
 
 use eidolon::camera::Camera;
 use eidolon::character::{Character, DefaultPostures, Posture, SkinType};
+use eidolon::error::EidolonError;
 use eidolon::model::Model;
 use eidolon::renderer::{OutputFormat, Renderer};
-use eidolon::texture::Texture;
+use eidolon::texture::{SkinImage, Texture};
 
 fn make_renderer() -> Renderer {
     Renderer::new().expect("Failed to create Renderer")
@@ -105,6 +106,274 @@ fn render_to_image_saves_to_disk() {
     std::fs::remove_file(&tmp).ok();
 }
 
+#[test]
+fn render_to_sink_matches_render_to_image() {
+    use eidolon::sink::MemorySink;
+
+    let renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let sink = MemorySink::new();
+    renderer
+        .render_to_sink(&character, &skin, &camera_default(), "front", (64, 48), OutputFormat::WebP, &sink)
+        .expect("render_to_sink failed");
+    let outputs = sink.take();
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(outputs[0].name, "front.webp");
+    assert_eq!(outputs[0].content_type, "image/webp");
+    let decoded = image::load_from_memory_with_format(&outputs[0].bytes, image::ImageFormat::WebP)
+        .expect("decode WebP output");
+    assert_eq!((decoded.width(), decoded.height()), (64, 48));
+
+    let tmp = std::env::temp_dir().join(format!("eidolon_test_sink_{}.webp", std::process::id()));
+    renderer
+        .render_to_image(&character, &skin, &camera_default(), tmp.to_str().unwrap(), (64, 48), OutputFormat::WebP)
+        .expect("render_to_image failed");
+    assert_eq!(std::fs::read(&tmp).unwrap(), outputs[0].bytes);
+    std::fs::remove_file(&tmp).ok();
+}
+
+#[test]
+fn part_texture_overrides_replace_single_parts() {
+    use eidolon::character::{Bone, PartTextureOverride};
+    use eidolon::skin_layout::Layer;
+    use std::sync::Arc;
+
+    let renderer = make_renderer();
+    let solid = |rgba: [u8; 4]| {
+        let image = image::RgbaImage::from_pixel(64, 64, image::Rgba(rgba));
+        let mut png = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        renderer.upload_skin(&SkinImage::from_memory(&png).unwrap())
+    };
+    let red = solid([220, 30, 30, 255]);
+    let blue = Arc::new(solid([30, 30, 220, 255]));
+    let count = |character: &Character| {
+        let image = renderer
+            .render(character, &red, &camera_default(), 64, 64)
+            .expect("render failed");
+        let mut counts = [0; 2];
+        for p in image.pixels().filter(|p| p[3] > 0) {
+            counts[usize::from(p[2] > p[0])] += 1;
+        }
+        counts
+    };
+
+    let mut character = Character::new();
+    let [reds, blues] = count(&character);
+    assert!(reds > 0 && blues == 0);
+
+    character.part_texture_overrides.push(PartTextureOverride {
+        bone: Bone::Head,
+        layer: None,
+        texture: Arc::clone(&blue),
+    });
+    let [head_reds, head_blues] = count(&character);
+    assert!(head_blues > 0 && head_reds > 0 && head_reds < reds);
+
+    // The solid skin's opaque overlay hides a base-layer override.
+    character.part_texture_overrides[0].layer = Some(Layer::Base);
+    assert_eq!(count(&character), [reds, 0]);
+    character.part_texture_overrides[0].layer = Some(Layer::Overlay);
+    assert!(count(&character)[1] > 0);
+
+    let meta = renderer.render_metadata(&character, &red, &camera_default(), (64, 64));
+    assert_eq!(
+        meta.get("part-textures"),
+        Some(format!("head/overlay={:016x}", blue.content_hash()).as_str())
+    );
+}
+
+#[test]
+fn headwear_is_drawn_over_the_head() {
+    use eidolon::headwear::{block_texture, Headwear};
+    use std::sync::Arc;
+
+    let renderer = make_renderer();
+    let solid = |rgba: [u8; 4]| {
+        let image = image::RgbaImage::from_pixel(64, 64, image::Rgba(rgba));
+        renderer.upload_skin(&SkinImage::from_image(&image::DynamicImage::ImageRgba8(image)).unwrap())
+    };
+    let red = solid([220, 30, 30, 255]);
+    let count = |character: &Character| {
+        let image = renderer
+            .render(character, &red, &camera_default(), 64, 64)
+            .expect("render failed");
+        let mut counts = [0; 3];
+        for p in image.pixels().filter(|p| p[3] > 0) {
+            let channel = (0..3).max_by_key(|&c| p[c]).unwrap();
+            counts[channel] += 1;
+        }
+        counts
+    };
+
+    let mut character = Character::new();
+    let bare = count(&character);
+    assert_eq!(bare[1..], [0, 0]);
+
+    let skull = Arc::new(solid([30, 30, 220, 255]));
+    character.equipment.head = Some(Headwear::skull(Arc::clone(&skull)));
+    let [reds, _, blues] = count(&character);
+    assert!(blues > 0 && reds < bare[0]);
+    // The skull is larger than the head it covers.
+    assert!(reds + blues > bare[0]);
+
+    let face = image::RgbaImage::from_pixel(16, 16, image::Rgba([30, 220, 30, 255]));
+    let pumpkin = block_texture(&face, &face, &face, &face).unwrap();
+    character.equipment.head = Some(Headwear::block(Arc::new(renderer.upload_skin(&pumpkin))));
+    let [_, greens, blues] = count(&character);
+    assert!(greens > 0 && blues == 0);
+
+    let meta = renderer.render_metadata(&character, &red, &camera_default(), (64, 64));
+    assert_eq!(
+        meta.get("headwear"),
+        Some(format!("block={:016x}", pumpkin.content_hash()).as_str())
+    );
+
+    character.equipment.head = None;
+    assert_eq!(count(&character), bare);
+}
+
+#[test]
+fn headwear_is_drawn_over_a_hidden_head() {
+    use eidolon::character::Bone;
+    use eidolon::headwear::Headwear;
+    use eidolon::skin_layout::Layer;
+    use std::sync::Arc;
+
+    let renderer = make_renderer();
+    let solid = |rgba: [u8; 4]| {
+        let image = image::RgbaImage::from_pixel(64, 64, image::Rgba(rgba));
+        renderer.upload_skin(&SkinImage::from_image(&image::DynamicImage::ImageRgba8(image)).unwrap())
+    };
+    let red = solid([220, 30, 30, 255]);
+    let mut character = Character::new();
+    character.equipment.head = Some(Headwear::skull(Arc::new(solid([30, 30, 220, 255]))));
+    // Both of the head's layers hidden: the skull still shows.
+    character.visibility.set(Bone::Head, Layer::Base, false);
+    character.visibility.set(Bone::Head, Layer::Overlay, false);
+    let image = renderer
+        .render(&character, &red, &camera_default(), 64, 64)
+        .expect("render failed");
+    let blues = image
+        .pixels()
+        .filter(|p| p[3] > 0 && p[2] > p[0] && p[2] > p[1])
+        .count();
+    assert!(blues > 0);
+}
+
+#[test]
+fn attachments_follow_their_bone() {
+    use eidolon::attachment::{Attachment, AttachmentModel, MAX_ATTACHMENTS};
+    use eidolon::character::Bone;
+    use std::sync::Arc;
+
+    let renderer = make_renderer();
+    let solid = |rgba: [u8; 4]| {
+        let image = image::RgbaImage::from_pixel(16, 16, image::Rgba(rgba));
+        let mut png = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        renderer.upload_skin(&SkinImage::from_memory_raw(&png).unwrap())
+    };
+    let skin = solid([220, 30, 30, 255]);
+    // A visor in front of the face.
+    let bbmodel = r#"{"elements": [{"from": [-3, 27, -6], "to": [3, 30, -5], "box_uv": true}]}"#;
+    let mesh = Arc::new(renderer.upload_attachment(&AttachmentModel::from_bbmodel(bbmodel.as_bytes()).unwrap()));
+    let visor = Attachment::new("visor", Bone::Head, mesh, Arc::new(solid([30, 30, 220, 255])));
+    let render = |character: &Character| {
+        renderer
+            .render(character, &skin, &camera_default(), 64, 64)
+            .expect("render failed")
+    };
+    // Topmost row with a blue pixel.
+    let top_blue = |image: &image::RgbaImage| {
+        image
+            .enumerate_pixels()
+            .filter(|(_, _, p)| p[3] > 0 && p[2] > p[0])
+            .map(|(_, y, _)| y)
+            .min()
+    };
+
+    let mut character = Character::new();
+    assert_eq!(top_blue(&render(&character)), None);
+    character.attachments.push(visor.clone());
+    let upright = top_blue(&render(&character)).expect("the visor is drawn");
+    character.posture.head_pitch = -45.0;
+    let tilted = top_blue(&render(&character)).expect("the visor is drawn");
+    assert!(tilted > upright, "{tilted} <= {upright}");
+
+    let meta = renderer.render_metadata(&character, &skin, &camera_default(), (64, 64));
+    assert_eq!(meta.get("attachments"), Some("visor@head"));
+
+    character.attachments = vec![visor; MAX_ATTACHMENTS + 1];
+    assert!(renderer.render(&character, &skin, &camera_default(), 64, 64).is_err());
+}
+
+#[test]
+fn cosmetics_catalogs_render_every_item() {
+    use eidolon::cosmetics::CosmeticsCatalog;
+
+    let renderer = make_renderer();
+    let dir = std::env::temp_dir().join(format!("eidolon-cosmetics-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("models")).unwrap();
+    let bbmodel = r#"{"elements": [{"from": [-3, 27, -6], "to": [3, 30, -5], "box_uv": true}]}"#;
+    std::fs::write(dir.join("models/visor.bbmodel"), bbmodel).unwrap();
+    image::RgbaImage::from_pixel(16, 16, image::Rgba([30, 30, 220, 255]))
+        .save(dir.join("visor.png"))
+        .unwrap();
+    let manifest = r#"{
+        "name": "Test pack",
+        "attachments": {
+            "visor": { "model": "models/visor.bbmodel", "texture": "visor.png", "bone": "head" },
+            "visor_no_hat": {
+                "model": "models/visor.bbmodel",
+                "texture": "visor.png",
+                "bone": "head",
+                "render": { "hide_overlay": true }
+            },
+            "missing": { "model": "models/missing.obj", "texture": "visor.png", "bone": "head" }
+        }
+    }"#;
+    std::fs::write(dir.join("pack.json"), manifest).unwrap();
+    let path = dir.join("pack.json");
+    // Entries must all load.
+    assert!(CosmeticsCatalog::load(&renderer, path.to_str().unwrap()).is_err());
+    std::fs::write(&path, manifest.replace("missing.obj", "visor.bbmodel")).unwrap();
+    let catalog = CosmeticsCatalog::load(&renderer, path.to_str().unwrap()).unwrap();
+    assert_eq!(catalog.names().collect::<Vec<_>>(), ["missing", "visor", "visor_no_hat"]);
+    // The shared model is uploaded once.
+    assert!(std::sync::Arc::ptr_eq(
+        &catalog.get("visor").unwrap().mesh,
+        &catalog.get("visor_no_hat").unwrap().mesh
+    ));
+
+    // A skin whose overlay is opaque everywhere, so the hat layer widens the head.
+    let mut png = Vec::new();
+    image::RgbaImage::from_pixel(64, 64, image::Rgba([220, 30, 30, 255]))
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    let skin = renderer.upload_skin(&SkinImage::from_memory_raw(&png).unwrap());
+    let character = Character::new();
+    let previews = catalog
+        .render_each(&renderer, &character, &skin, &camera_default(), (64, 64))
+        .unwrap();
+    assert_eq!(previews.len(), 3);
+    let blue = |image: &image::RgbaImage| image.pixels().filter(|p| p[3] > 0 && p[2] > p[0]).count();
+    let opaque = |image: &image::RgbaImage| image.pixels().filter(|p| p[3] > 0).count();
+    let (with_hat, without_hat) = (&previews[1].1, &previews[2].1);
+    assert!(blue(with_hat) > 0 && blue(without_hat) > 0);
+    assert!(opaque(without_hat) < opaque(with_hat));
+
+    let mut wearing = Character::new();
+    catalog.wear(&mut wearing, "visor").unwrap();
+    assert_eq!(wearing.attachments.len(), 1);
+    assert!(matches!(catalog.wear(&mut wearing, "cape"), Err(eidolon::error::EidolonError::NotFound(_))));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
 #[test]
 fn webp_output_format_works() {
     let renderer = make_renderer();
@@ -190,6 +459,24 @@ fn load_model_from_bytes_invalid_data_errors() {
     assert!(msg.contains("Model error"), "expected Model error, got: {msg}");
 }
 
+#[test]
+fn load_model_from_obj_bytes_with_mtllib_errors() {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        compatible_surface: None,
+        force_fallback_adapter: false,
+    }))
+    .expect("No wgpu adapter");
+    let (device, _queue) =
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default()))
+            .expect("No wgpu device");
+
+    // Material libraries cannot be opened from bytes; naming one is an error, not a panic.
+    let result = Model::load_from_obj_bytes(&device, b"mtllib skin.mtl\no Head\n", "mtl.obj");
+    assert!(matches!(result, Err(EidolonError::Model(_))));
+}
+
 #[test]
 fn load_texture_raw_no_conversion() {
     let renderer = make_renderer();
@@ -409,3 +696,2326 @@ fn render_all_posture_presets_with_both_skin_types() {
         }
     }
 }
+
+#[test]
+fn lighting_preset_sets_opaque_background() {
+    use eidolon::lighting::LightingPreset;
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    renderer.apply_lighting_preset(LightingPreset::Nether);
+    let image = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Render with lighting preset failed");
+
+    // Corner pixel is background: opaque and tinted with the preset color.
+    let corner = image.get_pixel(0, 0);
+    let bg = LightingPreset::Nether.background();
+    assert_eq!(corner[3], 255, "preset background should be opaque");
+    assert_eq!(corner[0], (bg[0] * 255.0).round() as u8);
+}
+
+#[test]
+fn lighting_preset_changes_character_shading() {
+    use eidolon::lighting::LightingPreset;
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let neutral = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Neutral render failed");
+    renderer.apply_lighting_preset(LightingPreset::Moonlight);
+    renderer.set_clear_color(0.0, 0.0, 0.0, 0.0);
+    let moonlit = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Moonlight render failed");
+
+    // Same silhouette (background stays transparent), different shading.
+    let coverage = |img: &image::RgbaImage| img.pixels().filter(|p| p[3] > 0).count();
+    assert_eq!(coverage(&neutral), coverage(&moonlit));
+    assert_ne!(neutral.as_raw(), moonlit.as_raw());
+}
+
+#[test]
+fn rim_light_brightens_render() {
+    use eidolon::lighting::Lighting;
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let plain = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Plain render failed");
+    renderer.set_lighting(Lighting {
+        rim_strength: 1.0,
+        ..Lighting::default()
+    });
+    let rimmed = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Rim-lit render failed");
+
+    let brightness = |img: &image::RgbaImage| -> u64 {
+        img.pixels().map(|p| p[0] as u64 + p[1] as u64 + p[2] as u64).sum()
+    };
+    assert!(brightness(&rimmed) > brightness(&plain), "rim light should add brightness");
+}
+
+#[test]
+fn ambient_occlusion_darkens_render() {
+    use eidolon::renderer::RenderOptions;
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let plain = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Plain render failed");
+    renderer.set_options(RenderOptions {
+        ambient_occlusion: 1.0,
+        ..RenderOptions::default()
+    });
+    let occluded = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Occluded render failed");
+
+    let brightness = |img: &image::RgbaImage| -> u64 {
+        img.pixels().map(|p| p[0] as u64 + p[1] as u64 + p[2] as u64).sum()
+    };
+    let coverage = |img: &image::RgbaImage| img.pixels().filter(|p| p[3] > 0).count();
+    assert_eq!(coverage(&plain), coverage(&occluded), "AO must not change the silhouette");
+    assert!(brightness(&occluded) < brightness(&plain), "AO should darken contact areas");
+}
+
+#[test]
+fn mannequin_fallback_fills_blank_parts() {
+    use eidolon::character::Bone;
+    use eidolon::renderer::RenderOptions;
+    use eidolon::skin_layout::{part, Layer};
+
+    let mut renderer = make_renderer();
+    let (character, full_skin) = character_with_skin(&renderer);
+    // Only the head's base layer is painted.
+    let mut rgba = image::RgbaImage::new(64, 64);
+    for (x, y, w, h) in part(Bone::Head).faces(Layer::Base, SkinType::Classic) {
+        for py in y..y + h {
+            for px in x..x + w {
+                rgba.put_pixel(px, py, image::Rgba([220, 30, 30, 255]));
+            }
+        }
+    }
+    let mut png = Vec::new();
+    rgba.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    let head_only = renderer.upload_skin(&SkinImage::from_memory(&png).unwrap());
+    let render = |renderer: &Renderer, skin: &Texture| {
+        renderer
+            .render(&character, skin, &camera_default(), 80, 80)
+            .expect("render failed")
+    };
+    let coverage = |img: &image::RgbaImage| img.pixels().filter(|p| p[3] > 0).count();
+    let gray = |img: &image::RgbaImage| {
+        img.pixels()
+            .filter(|p| p[3] > 0 && p[0].abs_diff(p[1]) < 4 && p[1].abs_diff(p[2]) < 4)
+            .count()
+    };
+
+    let plain = render(&renderer, &head_only);
+    let full_plain = render(&renderer, &full_skin);
+    assert_eq!(gray(&plain), 0);
+    renderer.set_options(RenderOptions {
+        mannequin_fallback: true,
+        ..RenderOptions::default()
+    });
+    let mannequin = render(&renderer, &head_only);
+    assert!(coverage(&mannequin) > 3 * coverage(&plain));
+    assert!(gray(&mannequin) > 2 * coverage(&plain));
+    // The painted head is kept.
+    assert!(mannequin.pixels().any(|p| p[3] > 0 && p[0] > 2 * p[1]));
+    // Complete skins are unaffected.
+    assert_eq!(render(&renderer, &full_skin), full_plain);
+}
+
+#[test]
+fn alpha_test_cuts_out_translucent_overlay_texels() {
+    use eidolon::character::Bone;
+    use eidolon::renderer::{AlphaTest, LayerAlpha, RenderOptions};
+    use eidolon::skin_layout::{part, Layer};
+
+    let mut renderer = make_renderer();
+    let (character, _) = character_with_skin(&renderer);
+    // Only the hat is painted, at 40% alpha like an antialiased edge.
+    let mut rgba = image::RgbaImage::new(64, 64);
+    for (x, y, w, h) in part(Bone::Head).faces(Layer::Overlay, SkinType::Classic) {
+        for py in y..y + h {
+            for px in x..x + w {
+                rgba.put_pixel(px, py, image::Rgba([200, 200, 200, 102]));
+            }
+        }
+    }
+    let mut png = Vec::new();
+    rgba.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    let skin = renderer.upload_skin(&SkinImage::from_memory(&png).unwrap());
+    let mut render = |alpha_test: AlphaTest| {
+        renderer.set_options(RenderOptions {
+            alpha_test,
+            ..RenderOptions::default()
+        });
+        renderer
+            .render(&character, &skin, &camera_default(), 64, 64)
+            .expect("render failed")
+    };
+    let alphas = |img: &image::RgbaImage| {
+        let mut alphas: Vec<u8> = img.pixels().map(|p| p[3]).filter(|&a| a > 0).collect();
+        alphas.dedup();
+        alphas
+    };
+
+    let blended = render(AlphaTest::default());
+    assert!(!alphas(&blended).is_empty());
+    assert!(alphas(&blended).iter().all(|&a| a < 255), "blended texels stay translucent");
+    let opaque = render(AlphaTest::cutout(0.2));
+    assert_eq!(alphas(&opaque), [255]);
+    // The cutout mode is per layer: cutting out only the base layer keeps the hat blended.
+    let base_only = render(AlphaTest {
+        cutoff: 0.2,
+        base: LayerAlpha::Cutout,
+        overlay: LayerAlpha::Blend,
+    });
+    assert_eq!(base_only, blended);
+    // Above the texels' alpha, nothing is drawn.
+    assert!(alphas(&render(AlphaTest::cutout(0.5))).is_empty());
+}
+
+#[test]
+fn mipmapped_filtering_calms_small_hd_skins() {
+    use eidolon::renderer::{RenderOptions, TextureFilter};
+
+    let mut renderer = make_renderer();
+    let (character, standard) = character_with_skin(&renderer);
+    // A 512× skin of single-texel checks: noise when sampled texel by texel at a small size.
+    let checks = image::RgbaImage::from_fn(512, 512, |x, y| {
+        image::Rgba(if (x + y) % 2 == 0 { [255; 4] } else { [0, 0, 0, 255] })
+    });
+    let mut png = Vec::new();
+    checks
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    let hd = renderer.upload_skin(&SkinImage::from_memory(&png).unwrap());
+    let mut render = |filter: TextureFilter, skin: &Texture| {
+        renderer.set_options(RenderOptions {
+            texture_filter: filter,
+            ..RenderOptions::default()
+        });
+        renderer
+            .render(&character, skin, &camera_default(), 48, 48)
+            .expect("render failed")
+    };
+    // Mean difference between horizontally adjacent opaque pixels.
+    let roughness = |img: &image::RgbaImage| {
+        let mut total = 0u64;
+        let mut count = 0u64;
+        for y in 0..img.height() {
+            for x in 1..img.width() {
+                let (a, b) = (img.get_pixel(x - 1, y), img.get_pixel(x, y));
+                if a[3] == 255 && b[3] == 255 {
+                    total += u64::from(a[0].abs_diff(b[0]));
+                    count += 1;
+                }
+            }
+        }
+        total / count.max(1)
+    };
+
+    let nearest = render(TextureFilter::Nearest, &hd);
+    let mipmapped = render(TextureFilter::NearestMipmapped, &hd);
+    let linear = render(TextureFilter::Linear, &hd);
+    assert!(roughness(&nearest) > 4 * roughness(&mipmapped).max(1));
+    assert!(roughness(&nearest) > 4 * roughness(&linear).max(1));
+    // Standard skins have no mipmaps and keep their pixels.
+    assert_eq!(
+        render(TextureFilter::NearestMipmapped, &standard),
+        render(TextureFilter::Nearest, &standard)
+    );
+}
+
+#[test]
+fn padded_skins_have_no_seams_when_filtered() {
+    use eidolon::renderer::{RenderOptions, TextureFilter};
+    use eidolon::skin_layout::{Layer, PARTS};
+
+    let mut renderer = make_renderer();
+    let (character, _) = character_with_skin(&renderer);
+    // A 2× HD skin with every face of both layers opaque and the unused texels transparent.
+    let mut rgba = image::RgbaImage::new(128, 128);
+    for part in &PARTS {
+        for layer in [Layer::Base, Layer::Overlay] {
+            for (x, y, w, h) in part.faces(layer, SkinType::Classic) {
+                for py in 2 * y..2 * (y + h) {
+                    for px in 2 * x..2 * (x + w) {
+                        rgba.put_pixel(px, py, image::Rgba([90, 140, 200, 255]));
+                    }
+                }
+            }
+        }
+    }
+    let mut png = Vec::new();
+    rgba.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    renderer.set_options(RenderOptions {
+        texture_filter: TextureFilter::Linear,
+        ..RenderOptions::default()
+    });
+    let translucent = |skin: &Texture| {
+        renderer
+            .render(&character, skin, &camera_default(), 128, 128)
+            .expect("render failed")
+            .pixels()
+            .filter(|p| p[3] > 0 && p[3] < 255)
+            .count()
+    };
+    // Raw textures are not padded: filtering blends the transparent texels into the edges.
+    let raw = renderer.upload_skin(&SkinImage::from_memory_raw(&png).unwrap());
+    assert!(translucent(&raw) > 0);
+    let skin = renderer.upload_skin(&SkinImage::from_memory(&png).unwrap());
+    assert_eq!(translucent(&skin), 0);
+}
+
+#[test]
+fn msaa_smooths_silhouette_edges() {
+    use eidolon::renderer::{AlphaTest, DepthOfField, Msaa, RenderOptions, ToneMapping};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    // Cut out every texel, so partial alpha can only come from edge coverage.
+    let cutout = RenderOptions {
+        alpha_test: AlphaTest::cutout(0.5),
+        ..RenderOptions::default()
+    };
+    let mut render = |options: RenderOptions| {
+        renderer.set_options(options);
+        renderer
+            .render(&character, &skin, &camera_default(), 96, 96)
+            .expect("render failed")
+    };
+    let edges = |img: &image::RgbaImage| img.pixels().filter(|p| p[3] > 0 && p[3] < 255).count();
+    let coverage = |img: &image::RgbaImage| img.pixels().map(|p| u64::from(p[3])).sum::<u64>();
+
+    let hard = render(cutout.clone());
+    assert_eq!(edges(&hard), 0);
+    // Drawn straight into the output, and through the tone-mapping post pass.
+    for tone_mapping in [ToneMapping::Linear, ToneMapping::Aces] {
+        let smooth = render(RenderOptions {
+            msaa: Msaa::X4,
+            tone_mapping,
+            ..cutout.clone()
+        });
+        assert!(edges(&smooth) > 40, "{tone_mapping:?}: {}", edges(&smooth));
+        // The same silhouette, only softened.
+        let (a, b) = (coverage(&hard) as f64, coverage(&smooth) as f64);
+        assert!((a - b).abs() < 0.05 * a, "{tone_mapping:?}: {a} vs {b}");
+    }
+    // Counts the adapter lacks fall back to fewer samples instead of failing.
+    for msaa in [Msaa::X2, Msaa::X8] {
+        let smooth = render(RenderOptions {
+            msaa,
+            ..cutout.clone()
+        });
+        assert!(coverage(&smooth) > 0, "{msaa:?}");
+    }
+    // Depth of field reads the resolved depth.
+    let focused = render(RenderOptions {
+        msaa: Msaa::X4,
+        depth_of_field: Some(DepthOfField::default()),
+        ..cutout.clone()
+    });
+    assert!(coverage(&focused) > coverage(&hard) / 2);
+    assert_eq!(render(cutout), hard);
+
+    let memory = renderer.gpu_memory();
+    // 96² multisampled color and depth, 4 bytes each, at 4 samples.
+    assert!(memory.framebuffers >= 96 * 96 * 8 * 4, "{memory:?}");
+    assert_eq!(renderer.trim(0).framebuffers, 0);
+}
+
+#[test]
+fn uv_inset_reuploads_models_and_is_reversible() {
+    use eidolon::renderer::RenderOptions;
+
+    let inset = RenderOptions {
+        uv_inset: 1.0,
+        ..RenderOptions::default()
+    };
+    let render = |renderer: &mut Renderer, options: &RenderOptions| {
+        let (character, skin) = character_with_skin(renderer);
+        renderer.set_options(options.clone());
+        renderer
+            .render(&character, &skin, &camera_default(), 96, 96)
+            .expect("render failed")
+    };
+    let mut renderer = make_renderer();
+    let exact = render(&mut renderer, &RenderOptions::default());
+    // A whole texel of inset drops the outer texels of each face.
+    let inset_render = render(&mut renderer, &inset);
+    assert_ne!(inset_render, exact);
+    assert_eq!(render(&mut renderer, &RenderOptions::default()), exact);
+
+    // Models loaded after the option is set get the same inset.
+    let mut fresh = make_renderer();
+    assert_eq!(render(&mut fresh, &inset), inset_render);
+}
+
+#[test]
+fn posed_geometry_follows_the_pose() {
+    use eidolon::character::Bone;
+    use eidolon::renderer::{PosedMesh, PosedSource};
+    use eidolon::skin_layout::Layer;
+
+    let renderer = make_renderer();
+    let part = |meshes: &[PosedMesh], bone, layer| {
+        meshes
+            .iter()
+            .find(|mesh| mesh.source == PosedSource::Part { bone, layer })
+            .map(|mesh| mesh.vertices.clone())
+    };
+    let y_range = |vertices: &[eidolon::model::TexturedVertex]| {
+        vertices.iter().fold((f32::MAX, f32::MIN), |(lo, hi), v| {
+            (lo.min(v.position[1]), hi.max(v.position[1]))
+        })
+    };
+
+    let mut character = Character::new();
+    character.posture.head_yaw = 0.0;
+    character.posture.head_pitch = 0.0;
+    let meshes = renderer
+        .posed_geometry(&character, &camera_default())
+        .expect("posing failed");
+    assert_eq!(meshes.len(), 12);
+    assert!(meshes.iter().all(|mesh| mesh.vertices.len() % 3 == 0));
+    let head = part(&meshes, Bone::Head, Layer::Base).expect("head is posed");
+    let (bottom, top) = y_range(&head);
+    assert!(bottom > 1.4 && top < 2.1, "{bottom}..{top}");
+    assert!(head.iter().all(|v| {
+        let length: f32 = v.normal.iter().map(|c| c * c).sum();
+        (length - 1.0).abs() < 1e-4
+    }));
+
+    // Pitching the head moves its vertices but leaves the legs alone; hidden layers are dropped.
+    character.posture.head_pitch = 45.0;
+    character.visibility.set(Bone::Head, Layer::Overlay, false);
+    let posed = renderer
+        .posed_geometry(&character, &camera_default())
+        .expect("posing failed");
+    assert_eq!(posed.len(), 11);
+    assert!(part(&posed, Bone::Head, Layer::Overlay).is_none());
+    assert_eq!(
+        part(&posed, Bone::LeftLeg, Layer::Base),
+        part(&meshes, Bone::LeftLeg, Layer::Base)
+    );
+    // The head tips over the neck: its center swings a good way along Z.
+    let mean_z = |vertices: &[eidolon::model::TexturedVertex]| {
+        vertices.iter().map(|v| v.position[2]).sum::<f32>() / vertices.len() as f32
+    };
+    let tilted = part(&posed, Bone::Head, Layer::Base).unwrap();
+    let swing = (mean_z(&tilted) - mean_z(&head)).abs();
+    assert!(swing > 0.1, "{swing}");
+}
+
+#[test]
+fn picks_match_the_rendered_pixels() {
+    use eidolon::character::Bone;
+    use eidolon::renderer::PosedSource;
+
+    let renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let camera = camera_default();
+    let (width, height) = (64, 96);
+    let image = renderer
+        .render(&character, &skin, &camera, width, height)
+        .expect("render failed");
+
+    let mut mismatches = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let pick = renderer
+                .pick(&character, &skin, &camera, width, height, (x, y))
+                .expect("pick failed");
+            if pick.is_some() != (image.get_pixel(x, y)[3] > 0) {
+                mismatches += 1;
+            }
+        }
+    }
+    // Only edge pixels, where the rasterizer and the ray disagree on coverage, may differ.
+    assert!(mismatches < (width * height) / 50, "{mismatches} mismatches");
+
+    // The middle of the image is the torso, on the front face of the body in the atlas.
+    let pick = renderer
+        .pick(&character, &skin, &camera, width, height, (width / 2, height / 2))
+        .expect("pick failed")
+        .expect("the torso is under the center");
+    assert!(matches!(pick.source, PosedSource::Part { bone: Bone::Body, .. }), "{pick:?}");
+    let (u, v) = pick.texel;
+    assert!((20..28).contains(&u) && ((20..32).contains(&v) || (36..48).contains(&v)), "{pick:?}");
+    assert!(pick.normal[2].abs() > 0.99);
+    assert_eq!(
+        renderer
+            .pick(&character, &skin, &camera, width, height, (0, 0))
+            .expect("pick failed"),
+        None
+    );
+}
+
+#[test]
+fn segmentation_covers_the_rendered_pixels() {
+    use eidolon::character::Bone;
+    use eidolon::renderer::PosedSource;
+    use eidolon::skin_layout::Layer;
+
+    let renderer = make_renderer();
+    let (mut character, skin) = character_with_skin(&renderer);
+    let camera = camera_default();
+    let (width, height) = (64, 96);
+    let image = renderer
+        .render(&character, &skin, &camera, width, height)
+        .expect("render failed");
+    let map = renderer
+        .render_segmentation(&character, &skin, &camera, width, height)
+        .expect("segmentation failed");
+    assert_eq!((map.width(), map.height()), (width, height));
+    for (x, y, pixel) in image.enumerate_pixels() {
+        assert_eq!(map.source_at(x, y).is_some(), pixel[3] > 0, "pixel ({x}, {y})");
+    }
+    // Segments agree with picking, away from the edges of the parts.
+    let mut agree = 0;
+    for (x, y) in (0..width).step_by(4).flat_map(|x| (0..height).step_by(4).map(move |y| (x, y))) {
+        let pick = renderer
+            .pick(&character, &skin, &camera, width, height, (x, y))
+            .expect("pick failed");
+        if pick.map(|pick| pick.source) == map.source_at(x, y) {
+            agree += 1;
+        }
+    }
+    assert!(agree >= 370, "{agree} of 384 pixels agree");
+
+    let jacket = PosedSource::Part {
+        bone: Bone::Body,
+        layer: Layer::Overlay,
+    };
+    let torso = PosedSource::Part {
+        bone: Bone::Body,
+        layer: Layer::Base,
+    };
+    let shown = map.coverage(jacket) + map.coverage(torso);
+    assert!(shown > 0);
+    character.visibility.set(Bone::Body, Layer::Overlay, false);
+    let hidden = renderer
+        .render_segmentation(&character, &skin, &camera, width, height)
+        .expect("segmentation failed");
+    assert_eq!(hidden.coverage(jacket), 0);
+    // The torso shows where the jacket was, less the jacket's rim around it.
+    assert!((map.coverage(torso)..shown).contains(&hidden.coverage(torso)));
+    assert_eq!(hidden.to_image().get_pixel(width / 2, height / 2)[3], 255);
+}
+
+#[test]
+fn capes_show_from_behind() {
+    use eidolon::cape::{Cape, DEFAULT_CAPE_ANGLE};
+    use eidolon::renderer::PosedSource;
+    use eidolon::shot::ShotPreset;
+    use std::sync::Arc;
+
+    let renderer = make_renderer();
+    let (mut character, skin) = character_with_skin(&renderer);
+    let (width, height) = (64, 96);
+    let camera = ShotPreset::CapeBack.frame(&mut character, width, height);
+    let bare = renderer
+        .render(&character, &skin, &camera, width, height)
+        .expect("render failed");
+
+    // A legacy 22x17 cape, padded to the 64x32 layout.
+    let pixels = image::RgbaImage::from_pixel(22, 17, image::Rgba([200, 20, 20, 255]));
+    let texture = renderer.upload_skin(&SkinImage::from_cape_image(pixels).unwrap());
+    assert_eq!(texture.size(), (64, 32));
+    character.equipment.cape = Some(Cape::new(Arc::new(texture)));
+    let caped = renderer
+        .render(&character, &skin, &camera, width, height)
+        .expect("render failed");
+    assert_ne!(bare, caped);
+    let map = renderer
+        .render_segmentation(&character, &skin, &camera, width, height)
+        .expect("segmentation failed");
+    let cape_pixels: Vec<_> = (0..width)
+        .flat_map(|x| (0..height).map(move |y| (x, y)))
+        .filter(|&(x, y)| map.source_at(x, y) == Some(PosedSource::Cape))
+        .collect();
+    assert!(cape_pixels.len() > 100, "{} cape pixels", cape_pixels.len());
+    // Its pixels are red and picked as the cape.
+    let mut picked = 0;
+    for &(x, y) in &cape_pixels {
+        let pixel = caped.get_pixel(x, y);
+        assert!(pixel[0] > pixel[1] && pixel[0] > pixel[2], "{pixel:?} at ({x}, {y})");
+        let pick = renderer
+            .pick(&character, &skin, &camera, width, height, (x, y))
+            .expect("pick failed");
+        if pick.is_some_and(|pick| pick.source == PosedSource::Cape) {
+            picked += 1;
+        }
+    }
+    assert!(picked * 10 >= cape_pixels.len() * 9, "{picked} of {}", cape_pixels.len());
+
+    // Larger angles swing the hanging edge further back.
+    let back = |character: &Character| {
+        let meshes = renderer.posed_geometry(character, &camera).unwrap();
+        let cape = meshes.iter().find(|mesh| mesh.source == PosedSource::Cape).unwrap();
+        let z = cape.vertices.iter().map(|vertex| vertex.position[2]);
+        z.fold(f32::MIN, f32::max)
+    };
+    let hanging = back(&character);
+    let cape = character.equipment.cape.take().unwrap();
+    character.equipment.cape = Some(cape.with_angle(DEFAULT_CAPE_ANGLE + 40.0));
+    assert!(back(&character) > hanging + 0.2);
+    let meta = renderer.render_metadata(&character, &skin, &camera, (width, height));
+    assert!(meta.get("cape").unwrap().ends_with(",angle=46"));
+
+    character.equipment.cape = None;
+    let bare_again = renderer
+        .render(&character, &skin, &camera, width, height)
+        .expect("render failed");
+    assert_eq!(bare, bare_again);
+}
+
+#[test]
+fn hair_only_frames_the_head() {
+    use eidolon::character::PartVisibility;
+
+    let renderer = make_renderer();
+    let (mut character, skin) = character_with_skin(&renderer);
+    let render = |character: &Character, camera: &Camera| {
+        renderer
+            .render(character, &skin, camera, 64, 64)
+            .expect("render failed")
+    };
+    let opaque = |img: &image::RgbaImage| {
+        img.enumerate_pixels()
+            .filter(|(_, _, p)| p[3] > 0)
+            .map(|(x, y, _)| (x, y))
+            .collect::<Vec<_>>()
+    };
+
+    character.visibility = PartVisibility::NONE;
+    assert!(opaque(&render(&character, &camera_default())).is_empty());
+
+    for yaw in [180.0, 135.0] {
+        let camera = character.frame_hair_only(&Camera { yaw, ..camera_default() }, 64, 64);
+        let pixels = opaque(&render(&character, &camera));
+        // The head fills much of the frame without touching its edges.
+        assert!(pixels.len() > 64 * 64 / 5, "{} pixels", pixels.len());
+        assert!(pixels.iter().all(|&(x, y)| (1..63).contains(&x) && (1..63).contains(&y)));
+        let mean_y = pixels.iter().map(|&(_, y)| y as f32).sum::<f32>() / pixels.len() as f32;
+        assert!((mean_y - 32.0).abs() < 6.0, "{mean_y}");
+    }
+}
+
+#[test]
+fn inventory_shot_matches_the_game_box() {
+    use eidolon::renderer::RenderOptions;
+    use eidolon::shot::{ShotPreset, INVENTORY_BOX};
+
+    let mut renderer = make_renderer();
+    let (mut character, skin) = character_with_skin(&renderer);
+    let (width, height) = INVENTORY_BOX;
+    let preset = ShotPreset::Inventory { cursor: [0.0, 0.0] };
+    let camera = preset.frame(&mut character, width, height);
+    renderer.set_options(RenderOptions {
+        fov: preset.fov(),
+        ..RenderOptions::default()
+    });
+    let img = renderer
+        .render(&character, &skin, &camera, width, height)
+        .expect("render failed");
+    let rows: Vec<u32> = img
+        .enumerate_pixels()
+        .filter(|(_, _, p)| p[3] > 0)
+        .map(|(_, y, _)| y)
+        .collect();
+    let (top, bottom) = (*rows.iter().min().unwrap(), *rows.iter().max().unwrap());
+    // 32 skin pixels tall at 30 GUI pixels per block, feet 29 pixels below the middle.
+    assert!((59..=63).contains(&(bottom - top + 1)), "{top}..={bottom}");
+    assert!((62..=65).contains(&bottom), "{bottom}");
+}
+
+#[test]
+fn cape_back_shot_fits_the_player() {
+    use eidolon::shot::ShotPreset;
+
+    let renderer = make_renderer();
+    let (mut character, skin) = character_with_skin(&renderer);
+    for (width, height) in [(48, 64), (64, 48)] {
+        let camera = ShotPreset::CapeBack.frame(&mut character, width, height);
+        let img = renderer
+            .render(&character, &skin, &camera, width, height)
+            .expect("render failed");
+        let pixels: Vec<(u32, u32)> = img
+            .enumerate_pixels()
+            .filter(|(_, _, p)| p[3] > 0)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        // The whole player is in the frame and fills most of its height.
+        assert!(pixels
+            .iter()
+            .all(|&(x, y)| (1..width - 1).contains(&x) && (1..height - 1).contains(&y)));
+        let rows = pixels.iter().map(|&(_, y)| y);
+        let extent = rows.clone().max().unwrap() - rows.min().unwrap();
+        assert!(extent > height * 2 / 3, "{extent}");
+    }
+}
+
+#[test]
+fn compare_renders_revisions_with_one_camera() {
+    use eidolon::compare::{self, TextureDiff};
+    use eidolon::texture::SkinImage;
+
+    let renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let revised_image = SkinImage::from_file("resources/SSSSSteven.png").unwrap();
+    let revised = renderer.upload_skin(&revised_image);
+    let camera = camera_default();
+
+    let [left, right] =
+        compare::render_pair(&renderer, &character, &skin, &skin, &camera, 48, 40).unwrap();
+    assert_eq!(left, right);
+    let [left, right] =
+        compare::render_pair(&renderer, &character, &skin, &revised, &camera, 48, 40).unwrap();
+    assert_ne!(left, right);
+    let pair = compare::side_by_side(&left, &right);
+    assert_eq!(pair.dimensions(), (96, 40));
+    assert_eq!(pair.get_pixel(60, 20), right.get_pixel(12, 20));
+    let gif = compare::blink_gif(&left, &right, 2).unwrap();
+    assert!(gif.starts_with(b"GIF89a"));
+
+    let original = SkinImage::from_file("resources/bingling_sama.png").unwrap();
+    let unchanged = TextureDiff::between(&original, &original).unwrap();
+    assert_eq!((unchanged.changed, unchanged.max_difference), (0, 0));
+    let diff = TextureDiff::between(&original, &revised_image).unwrap();
+    assert!(diff.changed > 1000, "{}", diff.changed);
+    assert_eq!(diff.heatmap.dimensions(), original.rgba().dimensions());
+}
+
+#[test]
+fn cancel_token_stops_renders_and_animations() {
+    use eidolon::animation::{render_animation, Animation, CameraPath, Easing};
+    use eidolon::cancel::CancelToken;
+    use eidolon::error::EidolonError;
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let token = CancelToken::new();
+    renderer.set_cancel_token(Some(token.clone()));
+    assert!(renderer.render(&character, &skin, &camera_default(), 16, 16).is_ok());
+
+    token.cancel();
+    assert!(matches!(
+        renderer.render(&character, &skin, &camera_default(), 16, 16),
+        Err(EidolonError::Cancelled)
+    ));
+    let animation = Animation::new(CameraPath::orbit(camera_default(), 1.0, 1.0, Easing::Linear), 4);
+    let image = SkinImage::from_file("resources/bingling_sama.png").unwrap();
+    assert!(matches!(
+        render_animation(&mut renderer, &character, &image, &animation, (16, 16)),
+        Err(EidolonError::Cancelled)
+    ));
+
+    renderer.set_cancel_token(None);
+    assert!(renderer.render(&character, &skin, &camera_default(), 16, 16).is_ok());
+}
+
+#[test]
+fn timeline_strip_lays_out_skins_over_labels() {
+    use eidolon::timeline::{TimelineEntry, TimelineStrip, TimelineStyle};
+
+    let renderer = make_renderer();
+    let strip = TimelineStrip::new(TimelineStyle {
+        cell_width: 48,
+        cell_height: 64,
+        label_size: 12.0,
+        ..TimelineStyle::default()
+    });
+    let entries = [
+        TimelineEntry::dated(
+            1_409_598_000,
+            SkinImage::from_file("resources/bingling_sama.png").unwrap(),
+        ),
+        TimelineEntry::new("Now", SkinImage::from_file("resources/SSSSSteven.png").unwrap()),
+    ];
+    let image = strip.render(&renderer, &entries).unwrap();
+    // Two cells, with a band of twice the label size plus the markers underneath.
+    assert_eq!(image.dimensions(), (96, 64 + 24 + 6));
+    let opaque = |x0: u32, x1: u32, y0: u32, y1: u32| {
+        (y0..y1)
+            .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+            .filter(|&(x, y)| image.get_pixel(x, y)[3] > 0)
+            .count()
+    };
+    assert!(opaque(0, 48, 0, 64) > 100);
+    assert!(opaque(48, 96, 0, 64) > 100);
+    // Ink under both renders: the axis, markers and labels.
+    assert!(opaque(0, 48, 70, 94) > 20);
+    assert!(opaque(48, 96, 70, 94) > 20);
+    assert!(strip.render(&renderer, &[]).is_err());
+}
+
+#[test]
+fn trim_frees_cached_targets_and_models() {
+    use eidolon::renderer::{EmissiveOverlay, RenderOptions, ShadowSettings};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let fresh = renderer.gpu_memory();
+    assert_eq!(fresh.framebuffers, 0);
+    assert!(fresh.textures > 0 && fresh.buffers > 0);
+
+    renderer.set_options(RenderOptions {
+        shadow: Some(ShadowSettings {
+            resolution: 256,
+            ..ShadowSettings::default()
+        }),
+        emissive_overlay: Some(EmissiveOverlay::default()),
+        ..RenderOptions::default()
+    });
+    let before = renderer
+        .render(&character, &skin, &camera_default(), 64, 48)
+        .unwrap();
+    let used = renderer.gpu_memory();
+    // Depth (4 bytes per texel) and a 256² shadow map at the least.
+    assert!(used.framebuffers >= 64 * 48 * 4 + 256 * 256 * 4, "{used:?}");
+    assert!(used.geometry > fresh.geometry);
+
+    // A budget above the current use frees nothing.
+    assert_eq!(renderer.trim(used.total()), used);
+    let trimmed = renderer.trim(0);
+    assert_eq!(trimmed.framebuffers, 0);
+    assert_eq!(trimmed.geometry, fresh.geometry);
+    assert!(!renderer.is_model_loaded(SkinType::Classic));
+
+    // Everything comes back on the next render.
+    let after = renderer
+        .render(&character, &skin, &camera_default(), 64, 48)
+        .unwrap();
+    assert_eq!(before, after);
+    assert_eq!(renderer.gpu_memory(), used);
+}
+
+#[test]
+fn shadows_add_ground_shadow_and_darken_character() {
+    use eidolon::renderer::{RenderOptions, ShadowSettings};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let camera = Camera {
+        pitch: 70.0,
+        ..camera_default()
+    };
+    let plain = renderer
+        .render(&character, &skin, &camera, 160, 120)
+        .expect("Plain render failed");
+    renderer.set_options(RenderOptions {
+        shadow: Some(ShadowSettings {
+            resolution: 256,
+            ..ShadowSettings::default()
+        }),
+        ..RenderOptions::default()
+    });
+    let shadowed = renderer
+        .render(&character, &skin, &camera, 160, 120)
+        .expect("Shadowed render failed");
+
+    // Ground shadow: black, semi-transparent pixels where the background was empty.
+    let ground = plain
+        .pixels()
+        .zip(shadowed.pixels())
+        .filter(|(a, b)| a[3] == 0 && b[3] > 0 && b[3] < 255 && b[0] == 0)
+        .count();
+    assert!(ground > 0, "expected a ground shadow");
+
+    let brightness = |img: &image::RgbaImage| -> u64 {
+        img.pixels()
+            .filter(|p| p[3] == 255)
+            .map(|p| p[0] as u64 + p[1] as u64 + p[2] as u64)
+            .sum()
+    };
+    assert!(brightness(&shadowed) < brightness(&plain), "self-shadowing should darken");
+}
+
+#[test]
+fn shadows_without_ground_keep_background_empty() {
+    use eidolon::renderer::{RenderOptions, ShadowSettings};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let plain = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Plain render failed");
+    renderer.set_options(RenderOptions {
+        shadow: Some(ShadowSettings {
+            ground_opacity: 0.0,
+            ..ShadowSettings::default()
+        }),
+        ..RenderOptions::default()
+    });
+    let shadowed = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Shadowed render failed");
+
+    let coverage = |img: &image::RgbaImage| img.pixels().filter(|p| p[3] > 0).count();
+    assert_eq!(coverage(&plain), coverage(&shadowed));
+}
+
+#[test]
+fn depth_of_field_without_blur_matches_direct_render() {
+    use eidolon::renderer::{DepthOfField, RenderOptions};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let direct = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Direct render failed");
+    renderer.set_options(RenderOptions {
+        depth_of_field: Some(DepthOfField {
+            blur_strength: 0.0,
+            ..DepthOfField::default()
+        }),
+        ..RenderOptions::default()
+    });
+    let composited = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Composited render failed");
+
+    // The composite reads a half-float scene, so channels may round differently by one step.
+    let max_diff = direct
+        .as_raw()
+        .iter()
+        .zip(composited.as_raw())
+        .map(|(a, b)| a.abs_diff(*b))
+        .max()
+        .unwrap_or(0);
+    assert!(max_diff <= 1, "composite differs by {} from the direct render", max_diff);
+}
+
+#[test]
+fn depth_of_field_blurs_out_of_focus_character() {
+    use eidolon::renderer::{DepthOfField, RenderOptions};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let sharp = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Sharp render failed");
+    renderer.set_options(RenderOptions {
+        depth_of_field: Some(DepthOfField {
+            focus_distance: Some(100.0),
+            blur_strength: 4.0,
+        }),
+        ..RenderOptions::default()
+    });
+    let blurred = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Blurred render failed");
+
+    // Blur spreads the silhouette: more partially transparent pixels than the hard-edged render.
+    let soft = |img: &image::RgbaImage| img.pixels().filter(|p| p[3] > 0 && p[3] < 255).count();
+    assert!(soft(&blurred) > soft(&sharp), "expected soft silhouette edges");
+}
+
+#[test]
+fn emissive_overlay_brightens_and_blooms() {
+    use eidolon::lighting::LightingPreset;
+    use eidolon::renderer::{EmissiveOverlay, RenderOptions};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    renderer.set_lighting(LightingPreset::Moonlight.lighting());
+    let plain = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Plain render failed");
+
+    renderer.set_options(RenderOptions {
+        emissive_overlay: Some(EmissiveOverlay {
+            bloom_intensity: 0.0,
+            ..EmissiveOverlay::default()
+        }),
+        ..RenderOptions::default()
+    });
+    let full_bright = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Emissive render failed");
+
+    renderer.set_options(RenderOptions {
+        emissive_overlay: Some(EmissiveOverlay {
+            bloom_threshold: 0.0,
+            bloom_intensity: 2.0,
+        }),
+        ..RenderOptions::default()
+    });
+    let glowing = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Bloom render failed");
+
+    let brightness = |img: &image::RgbaImage| -> u64 {
+        img.pixels().map(|p| p[0] as u64 + p[1] as u64 + p[2] as u64).sum()
+    };
+    let coverage = |img: &image::RgbaImage| img.pixels().filter(|p| p[3] > 0).count();
+    assert!(brightness(&full_bright) > brightness(&plain), "unlit overlay should be brighter");
+    assert_eq!(coverage(&plain), coverage(&full_bright), "no bloom, no glow outside");
+    assert!(coverage(&glowing) > coverage(&plain), "bloom should spill onto the background");
+}
+
+#[test]
+fn tone_mapping_compresses_highlights() {
+    use eidolon::renderer::{RenderOptions, ToneMapping};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let linear = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Linear render failed");
+    renderer.set_options(RenderOptions {
+        tone_mapping: ToneMapping::Reinhard,
+        ..RenderOptions::default()
+    });
+    let reinhard = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Tone-mapped render failed");
+
+    let brightness = |img: &image::RgbaImage| -> u64 {
+        img.pixels().map(|p| p[0] as u64 + p[1] as u64 + p[2] as u64).sum()
+    };
+    let coverage = |img: &image::RgbaImage| img.pixels().filter(|p| p[3] > 0).count();
+    assert!(brightness(&reinhard) < brightness(&linear), "Reinhard maps 1.0 to 0.5");
+    assert_eq!(coverage(&linear), coverage(&reinhard));
+}
+
+#[test]
+fn render_16bit_matches_8bit_render() {
+    let renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let img8 = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("8-bit render failed");
+    let img16 = renderer
+        .render_16bit(&character, &skin, &camera_default(), 160, 120)
+        .expect("16-bit render failed");
+
+    assert_eq!(img16.dimensions(), img8.dimensions());
+    let max_diff = img8
+        .pixels()
+        .zip(img16.pixels())
+        .flat_map(|(a, b)| (0..4).map(move |c| (a[c] as i32 - (b[c] >> 8) as i32).abs()))
+        .max()
+        .unwrap_or(0);
+    assert!(max_diff <= 1, "16-bit render differs by {} steps", max_diff);
+}
+
+#[test]
+fn render_hdr_has_linear_color_and_depth() {
+    let renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let camera = camera_default();
+    let hdr = renderer
+        .render_hdr(&character, &skin, &camera, 160, 120)
+        .expect("HDR render failed");
+
+    let (x, y) = (80, 60);
+    assert_eq!(hdr.color.get_pixel(x, y)[3], 1.0, "center pixel is on the character");
+    let z = hdr.depth.get_pixel(x, y)[0];
+    let orbit = camera.orbit_distance();
+    assert!(z > orbit * 0.5 && z < orbit * 1.5, "center depth {} near orbit distance {}", z, orbit);
+    assert_eq!(hdr.depth.get_pixel(0, 0)[0], f32::INFINITY);
+    assert_eq!(hdr.color.get_pixel(0, 0)[3], 0.0);
+}
+
+#[test]
+fn render_to_image_exr_and_png16() {
+    use eidolon::renderer::ExrChannels;
+
+    let renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let dir = std::env::temp_dir();
+    let exr = dir.join("eidolon_test_render.exr");
+    let png16 = dir.join("eidolon_test_render16.png");
+    renderer
+        .render_to_image(
+            &character,
+            &skin,
+            &camera_default(),
+            exr.to_str().unwrap(),
+            (64, 48),
+            OutputFormat::Exr(ExrChannels::default()),
+        )
+        .expect("EXR render_to_image failed");
+    renderer
+        .render_to_image(
+            &character,
+            &skin,
+            &camera_default(),
+            png16.to_str().unwrap(),
+            (64, 48),
+            OutputFormat::Png16,
+        )
+        .expect("16-bit PNG render_to_image failed");
+
+    let exr_img = image::open(&exr).expect("EXR unreadable");
+    assert_eq!(exr_img.color(), image::ColorType::Rgba32F);
+    let png_img = image::open(&png16).expect("16-bit PNG unreadable");
+    assert_eq!(png_img.color(), image::ColorType::Rgba16);
+    let _ = std::fs::remove_file(exr);
+    let _ = std::fs::remove_file(png16);
+}
+
+#[test]
+fn translucent_skin_blends_without_fringes() {
+    use eidolon::renderer::{AlphaMode, RenderOptions};
+
+    let mut renderer = make_renderer();
+    let (character, _) = character_with_skin(&renderer);
+    // Every texel half-transparent white: base layer and overlay both blend.
+    let atlas = image::RgbaImage::from_pixel(64, 64, image::Rgba([255, 255, 255, 128]));
+    let mut png = Vec::new();
+    atlas
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .expect("encode skin");
+    let skin = renderer
+        .load_texture_from_memory(&png)
+        .expect("Failed to load translucent skin");
+
+    let straight = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Straight render failed");
+    renderer.set_options(RenderOptions {
+        alpha_mode: AlphaMode::Premultiplied,
+        ..RenderOptions::default()
+    });
+    let premultiplied = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Premultiplied render failed");
+
+    // Two half-transparent layers cover 75%; blending alpha by itself would give 37.5%.
+    let max_alpha = straight.pixels().map(|p| p[3]).max().unwrap();
+    assert!(max_alpha >= 190, "coverage alpha {} too low", max_alpha);
+    for (s, p) in straight.pixels().zip(premultiplied.pixels()) {
+        assert_eq!(s[3], p[3]);
+        for c in 0..3 {
+            assert!(p[c] <= p[3], "premultiplied color exceeds alpha");
+            let expected = (p[c] as u32 * 255 + p[3] as u32 / 2) / (p[3] as u32).max(1);
+            assert!((s[c] as i32 - expected.min(255) as i32).abs() <= 1);
+        }
+    }
+    // Straight color of the lit white skin must not be darkened towards black at low alpha.
+    let center = straight.get_pixel(80, 60);
+    assert!(center[0] > 100, "straight color darkened: {:?}", center);
+}
+
+#[test]
+fn color_adjustments_change_skin_not_background() {
+    use eidolon::renderer::{ColorAdjustments, RenderOptions};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let base = renderer
+        .render(&character, &skin, &camera_default(), 160, 120)
+        .expect("Base render failed");
+
+    let luma = |img: &image::RgbaImage| -> u64 {
+        img.pixels()
+            .filter(|p| p[3] == 255)
+            .map(|p| p[0] as u64 + p[1] as u64 + p[2] as u64)
+            .sum()
+    };
+    let render_with = |renderer: &mut eidolon::renderer::Renderer, adjustments| {
+        renderer.set_options(RenderOptions {
+            adjustments,
+            ..RenderOptions::default()
+        });
+        renderer
+            .render(&character, &skin, &camera_default(), 160, 120)
+            .expect("Adjusted render failed")
+    };
+
+    let brighter = render_with(
+        &mut renderer,
+        ColorAdjustments {
+            exposure: 1.0,
+            ..ColorAdjustments::default()
+        },
+    );
+    assert!(luma(&brighter) > luma(&base), "exposure did not brighten");
+
+    let gray = render_with(
+        &mut renderer,
+        ColorAdjustments {
+            saturation: 0.0,
+            ..ColorAdjustments::default()
+        },
+    );
+    for (b, g) in base.pixels().zip(gray.pixels()) {
+        assert_eq!(b[3], g[3], "adjustments must not change coverage");
+        if g[3] == 255 {
+            assert!((g[0] as i32 - g[1] as i32).abs() <= 1);
+            assert!((g[1] as i32 - g[2] as i32).abs() <= 1);
+        }
+    }
+
+    let identity = render_with(&mut renderer, ColorAdjustments::default());
+    assert_eq!(identity.as_raw(), base.as_raw());
+}
+
+#[test]
+fn normalize_brightness_moves_skin_towards_target() {
+    use eidolon::renderer::BrightnessStats;
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    renderer.set_clear_color(0.1, 0.1, 0.1, 1.0);
+
+    let before = renderer
+        .normalize_brightness(&character, &skin, &camera_default(), 0.7)
+        .expect("Normalize failed")
+        .expect("Skin not visible");
+    let exposure = renderer.options().adjustments.exposure;
+    assert!(before.mean_luminance < 0.7 && exposure > 0.0);
+    assert!(before.coverage > 0.0 && before.coverage < 1.0, "background counted as skin");
+
+    renderer.set_clear_color(0.0, 0.0, 0.0, 0.0);
+    let after = BrightnessStats::from_image(
+        &renderer
+            .render(&character, &skin, &camera_default(), 128, 128)
+            .expect("Adjusted render failed"),
+    )
+    .expect("Skin not visible");
+    assert!(
+        (after.mean_luminance - 0.7).abs() < (before.mean_luminance - 0.7).abs(),
+        "{:?} -> {:?}",
+        before,
+        after
+    );
+}
+
+#[test]
+fn render_views_matches_individual_renders() {
+    let renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let cameras = [
+        camera_default(),
+        Camera {
+            yaw: 0.0,
+            ..camera_default()
+        },
+        Camera {
+            yaw: 225.0,
+            pitch: 60.0,
+            scale: 1.5,
+        },
+    ];
+
+    let views = renderer
+        .render_views(&character, &skin, &cameras, (96, 72))
+        .expect("render_views failed");
+    assert_eq!(views.len(), cameras.len());
+    for (view, camera) in views.iter().zip(&cameras) {
+        let single = renderer
+            .render(&character, &skin, camera, 96, 72)
+            .expect("Render failed");
+        assert_eq!(view.as_raw(), single.as_raw());
+    }
+    assert_ne!(views[0].as_raw(), views[1].as_raw(), "front and back must differ");
+    assert!(renderer
+        .render_views(&character, &skin, &[], (96, 72))
+        .expect("empty render_views failed")
+        .is_empty());
+}
+
+#[test]
+fn camera_path_renders_a_looping_gif() {
+    use eidolon::animation::{self, Animation, CameraPath, Easing};
+    use image::AnimationDecoder;
+
+    let mut renderer = make_renderer();
+    let (character, _) = character_with_skin(&renderer);
+    let skin = SkinImage::from_file("resources/bingling_sama.png").expect("Failed to load skin");
+    let path = CameraPath::orbit(camera_default(), 1.0, 1.0, Easing::Linear);
+    let animation = Animation::new(path, 4);
+    let frames = animation::render_animation(&mut renderer, &character, &skin, &animation, (48, 48))
+        .expect("render_animation failed");
+    assert_eq!(frames.len(), 4);
+    // Quarter turns: the first frame is the front, the third the back.
+    assert_ne!(frames[0].as_raw(), frames[2].as_raw());
+
+    let gif = animation::encode_gif(&frames, 4).expect("GIF encoding failed");
+    let decoded = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(gif))
+        .expect("GIF decode failed")
+        .into_frames()
+        .collect_frames()
+        .expect("GIF frames failed");
+    assert_eq!(decoded.len(), 4);
+    assert_eq!(decoded[0].buffer().dimensions(), (48, 48));
+
+    let apng = animation::encode_apng(&frames, 4, &eidolon::cancel::CancelToken::new())
+        .expect("APNG encoding failed");
+    let decoded = image::codecs::png::PngDecoder::new(std::io::Cursor::new(apng))
+        .expect("APNG decode failed")
+        .apng()
+        .expect("not an APNG")
+        .into_frames()
+        .collect_frames()
+        .expect("APNG frames failed");
+    assert_eq!(decoded.len(), 4);
+    assert_eq!(decoded[2].buffer(), &frames[2]);
+}
+
+#[test]
+fn idle_animation_moves_a_still_pose() {
+    use eidolon::animation::{self, Animation, CameraPath, Idle};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let skin_image =
+        SkinImage::from_file("resources/bingling_sama.png").expect("Failed to load skin");
+    let animation = Animation {
+        idle: Some(Idle::new(3)),
+        duration: 2.0,
+        ..Animation::new(CameraPath::new(camera_default()), 2)
+    };
+    let frames =
+        animation::render_animation(&mut renderer, &character, &skin_image, &animation, (64, 64))
+            .expect("render_animation failed");
+    assert_eq!(frames.len(), 4);
+    assert_ne!(frames[0].as_raw(), frames[2].as_raw(), "idle frames must differ");
+
+    // Breathing widens the torso.
+    let still = renderer
+        .render(&character, &skin, &camera_default(), 64, 64)
+        .expect("Render failed");
+    let breathing = Character {
+        chest_expansion: 0.2,
+        ..character.clone()
+    };
+    let wider = renderer
+        .render(&breathing, &skin, &camera_default(), 64, 64)
+        .expect("Render failed");
+    assert_ne!(still.as_raw(), wider.as_raw(), "chest expansion must show");
+}
+
+#[test]
+fn streamed_frames_match_rendered_animation() {
+    use eidolon::animation::{self, Animation, CameraPath, Easing, Idle};
+    use eidolon::cancel::CancelToken;
+    use eidolon::error::EidolonError;
+
+    let mut renderer = make_renderer();
+    let (character, _) = character_with_skin(&renderer);
+    let skin = SkinImage::from_file("resources/bingling_sama.png").expect("Failed to load skin");
+    for idle in [None, Some(Idle::new(3))] {
+        let animation = Animation {
+            idle,
+            ..Animation::new(CameraPath::orbit(camera_default(), 1.0, 1.0, Easing::Linear), 3)
+        };
+        let frames =
+            animation::render_animation(&mut renderer, &character, &skin, &animation, (32, 32))
+                .expect("render_animation failed");
+        let stream =
+            animation::stream_animation(&mut renderer, &character, &skin, &animation, (32, 32));
+        assert_eq!(stream.size_hint(), (3, Some(3)));
+        let streamed: Vec<_> = stream
+            .map(|frame| frame.expect("stream frame failed"))
+            .collect();
+        assert_eq!(streamed.len(), 3);
+        for (i, (streamed, frame)) in streamed.iter().zip(&frames).enumerate() {
+            assert_eq!((streamed.index, &streamed.image), (i, frame));
+        }
+        let jpeg = streamed[0]
+            .encode(image::ImageFormat::Jpeg)
+            .expect("JPEG encode failed");
+        assert_eq!(
+            image::guess_format(&jpeg).unwrap(),
+            image::ImageFormat::Jpeg
+        );
+    }
+
+    // Looping streams go on until cancelled.
+    let animation = Animation::new(
+        CameraPath::orbit(camera_default(), 1.0, 1.0, Easing::Linear),
+        2,
+    );
+    let cancel = CancelToken::new();
+    renderer.set_cancel_token(Some(cancel.clone()));
+    let mut looping =
+        animation::stream_animation(&mut renderer, &character, &skin, &animation, (16, 16))
+            .looping();
+    for i in 0..5 {
+        let frame = looping.next().expect("looping stream ended").unwrap();
+        assert_eq!(frame.index, i);
+        assert!(frame.time < animation.duration);
+    }
+    cancel.cancel();
+    assert!(matches!(looping.next(), Some(Err(EidolonError::Cancelled))));
+    assert!(looping.next().is_none());
+}
+
+#[test]
+fn expressions_change_the_face_mid_animation() {
+    use eidolon::animation::{self, Animation, CameraPath, ExpressionKey};
+    use eidolon::expression::Expression;
+
+    let mut renderer = make_renderer();
+    let (character, _) = character_with_skin(&renderer);
+    let skin = SkinImage::from_file("resources/bingling_sama.png").expect("Failed to load skin");
+    let animation = Animation {
+        duration: 1.0,
+        expressions: vec![ExpressionKey {
+            start: 0.5,
+            duration: 0.25,
+            expression: Expression::Blink,
+        }],
+        ..Animation::new(CameraPath::new(camera_default()), 4)
+    };
+    let frames = animation::render_animation(&mut renderer, &character, &skin, &animation, (96, 96))
+        .expect("render_animation failed");
+    assert_eq!(frames[0].as_raw(), frames[1].as_raw());
+    assert_eq!(frames[0].as_raw(), frames[3].as_raw());
+    assert_ne!(frames[0].as_raw(), frames[2].as_raw(), "the blink must show");
+}
+
+#[test]
+fn status_effects_tint_and_decorate_the_character() {
+    use eidolon::animation::{self, Animation, CameraPath};
+    use eidolon::renderer::{RenderOptions, StatusEffects};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let mut render_with = |status: StatusEffects| {
+        renderer.set_options(RenderOptions {
+            status,
+            ..RenderOptions::default()
+        });
+        renderer
+            .render(&character, &skin, &camera_default(), 64, 64)
+            .expect("render failed")
+    };
+    let plain = render_with(StatusEffects::default());
+    let red = |image: &image::RgbaImage| -> f64 {
+        let visible = image.pixels().filter(|p| p[3] > 0);
+        visible
+            .map(|p| f64::from(p[0]) - f64::from(p[1]))
+            .sum::<f64>()
+    };
+
+    let hurt = render_with(StatusEffects {
+        hurt: 1.0,
+        ..StatusEffects::default()
+    });
+    assert!(red(&hurt) > red(&plain));
+    // The tint changes color, not coverage.
+    let coverage = |image: &image::RgbaImage| image.pixels().filter(|p| p[3] > 0).count();
+    assert_eq!(coverage(&hurt), coverage(&plain));
+
+    let burning = |time| StatusEffects {
+        on_fire: true,
+        time,
+        ..StatusEffects::default()
+    };
+    let fire = render_with(burning(0.0));
+    assert!(coverage(&fire) > coverage(&plain));
+    assert_ne!(fire.as_raw(), render_with(burning(0.5)).as_raw());
+    assert_eq!(fire.as_raw(), render_with(burning(1.0)).as_raw());
+
+    let potion = render_with(StatusEffects {
+        potion: Some([0.5, 0.2, 0.9]),
+        ..StatusEffects::default()
+    });
+    assert_ne!(potion.as_raw(), plain.as_raw());
+
+    // Animations advance the flames and leave the renderer's options as they were.
+    renderer.set_options(RenderOptions {
+        status: burning(0.0),
+        ..RenderOptions::default()
+    });
+    let skin_image = SkinImage::from_file("resources/bingling_sama.png").expect("Failed to load skin");
+    let clip = Animation {
+        duration: 1.0,
+        ..Animation::new(CameraPath::new(camera_default()), 4)
+    };
+    let frames = animation::render_animation(&mut renderer, &character, &skin_image, &clip, (48, 48))
+        .expect("render_animation failed");
+    assert_ne!(frames[0].as_raw(), frames[2].as_raw());
+    assert_eq!(renderer.options().status, burning(0.0));
+}
+
+#[test]
+fn particles_decorate_and_animate() {
+    use eidolon::animation::{self, Animation, CameraPath};
+    use eidolon::character::Bone;
+    use eidolon::renderer::{ParticleEmitter, ParticlePreset, Particles, RenderOptions};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let mut render_with = |emitters: Vec<ParticleEmitter>, time: f32| {
+        renderer.set_options(RenderOptions {
+            particles: Particles { emitters, time },
+            ..RenderOptions::default()
+        });
+        renderer
+            .render(&character, &skin, &camera_default(), 64, 64)
+            .expect("render failed")
+    };
+    let coverage = |image: &image::RgbaImage| image.pixels().filter(|p| p[3] > 0).count();
+    let plain = render_with(Vec::new(), 0.0);
+    for preset in [
+        ParticlePreset::Hearts,
+        ParticlePreset::CritSparks,
+        ParticlePreset::EnchantGlyphs,
+    ] {
+        let decorated = render_with(vec![ParticleEmitter::preset(preset)], 0.0);
+        assert!(coverage(&decorated) > coverage(&plain), "{preset:?}");
+    }
+
+    let hearts = ParticleEmitter::preset(ParticlePreset::Hearts);
+    let at_head = render_with(vec![hearts.clone()], 0.3);
+    assert_eq!(at_head.as_raw(), render_with(vec![hearts.clone()], 0.3).as_raw());
+    assert_ne!(at_head.as_raw(), render_with(vec![hearts.clone()], 0.6).as_raw());
+    let at_leg = render_with(vec![hearts.clone().attached_to(Bone::LeftLeg)], 0.3);
+    assert_ne!(at_head.as_raw(), at_leg.as_raw());
+
+    // Animations advance the particles and leave the renderer's options as they were.
+    let options = RenderOptions {
+        particles: Particles {
+            emitters: vec![hearts],
+            time: 0.0,
+        },
+        ..RenderOptions::default()
+    };
+    renderer.set_options(options.clone());
+    let skin_image = SkinImage::from_file("resources/bingling_sama.png").expect("Failed to load skin");
+    let clip = Animation {
+        duration: 1.0,
+        ..Animation::new(CameraPath::new(camera_default()), 4)
+    };
+    let frames = animation::render_animation(&mut renderer, &character, &skin_image, &clip, (48, 48))
+        .expect("render_animation failed");
+    assert_ne!(frames[0].as_raw(), frames[2].as_raw());
+    assert_eq!(renderer.options(), &options);
+}
+
+#[test]
+fn enchantment_glint_shimmers_on_chosen_bones() {
+    use eidolon::character::Bone;
+    use eidolon::renderer::{EnchantmentGlint, RenderOptions};
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let mut render_with = |glint: Option<EnchantmentGlint>| {
+        renderer.set_options(RenderOptions {
+            glint,
+            ..RenderOptions::default()
+        });
+        renderer
+            .render(&character, &skin, &camera_default(), 64, 64)
+            .expect("render failed")
+    };
+    let plain = render_with(None);
+    let brightness = |image: &image::RgbaImage| -> u64 {
+        image.pixels().map(|p| u64::from(p[0]) + u64::from(p[1]) + u64::from(p[2])).sum()
+    };
+
+    let enchanted = |time| EnchantmentGlint {
+        time,
+        ..EnchantmentGlint::all()
+    };
+    let glinting = render_with(Some(enchanted(0.0)));
+    assert!(brightness(&glinting) > brightness(&plain));
+    assert_ne!(glinting.as_raw(), render_with(Some(enchanted(1.0))).as_raw());
+    assert_eq!(
+        glinting.as_raw(),
+        render_with(Some(enchanted(EnchantmentGlint::PERIOD))).as_raw()
+    );
+
+    // Only the chosen bones glint: the legs are in the lower part of the frame.
+    let helmet = render_with(Some(EnchantmentGlint {
+        bones: vec![Bone::Head],
+        ..EnchantmentGlint::default()
+    }));
+    let lower = |image: &image::RgbaImage| image.as_raw()[image.as_raw().len() * 3 / 4..].to_vec();
+    assert_eq!(lower(&helmet), lower(&plain));
+    assert_ne!(helmet.as_raw(), plain.as_raw());
+    assert_eq!(
+        render_with(Some(EnchantmentGlint::default())).as_raw(),
+        plain.as_raw()
+    );
+}
+
+#[test]
+fn render_stereo_layouts() {
+    use eidolon::renderer::{StereoLayout, StereoSettings};
+
+    let renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let settings = StereoSettings {
+        eye_separation: 0.3,
+        layout: StereoLayout::SideBySide,
+    };
+    let pair = renderer
+        .render_stereo(&character, &skin, &camera_default(), &settings, 80, 60)
+        .expect("Side-by-side render failed");
+    assert_eq!(pair.dimensions(), (160, 60));
+    let left = image::imageops::crop_imm(&pair, 0, 0, 80, 60).to_image();
+    let right = image::imageops::crop_imm(&pair, 80, 0, 80, 60).to_image();
+    assert_ne!(left.as_raw(), right.as_raw(), "eyes must see different views");
+
+    let anaglyph = renderer
+        .render_stereo(
+            &character,
+            &skin,
+            &camera_default(),
+            &StereoSettings {
+                layout: StereoLayout::Anaglyph,
+                ..settings
+            },
+            80,
+            60,
+        )
+        .expect("Anaglyph render failed");
+    assert_eq!(anaglyph.dimensions(), (80, 60));
+    for ((a, l), r) in anaglyph.pixels().zip(left.pixels()).zip(right.pixels()) {
+        assert_eq!(a[0], l[0]);
+        assert_eq!(a.0[1..3], r.0[1..3]);
+    }
+}
+
+#[test]
+fn presets_render_like_their_thumbnail_spec() {
+    use eidolon::presets::PresetRegistry;
+    use eidolon::thumbnail::thumbnail;
+
+    let registry =
+        PresetRegistry::from_json(br#"{"iso_48": {"view": "isometric", "size": 48}}"#).unwrap();
+    let skin = std::fs::read("resources/SSSSSteven.png").expect("read skin");
+    let png = registry.render("iso_48", &skin).expect("preset render failed");
+    assert_eq!(png, thumbnail(&skin, registry.get("iso_48").unwrap()).unwrap());
+    let decoded = image::load_from_memory(&png).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), (48, 48));
+
+    let dir = std::env::temp_dir().join(format!("eidolon-preset-results-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let cache = eidolon::cache::ResultCache::new(&dir, 1 << 20).unwrap();
+    assert_eq!(registry.render_cached("iso_48", &skin, &cache).unwrap(), png);
+    let key = registry.cache_key("iso_48", &skin).unwrap();
+    assert_eq!(cache.get(&key).unwrap().unwrap(), png);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn thumbnail_if_changed_skips_known_skins() {
+    use eidolon::provider::{DirectoryProvider, PlayerId};
+    use eidolon::thumbnail::{thumbnail, thumbnail_if_changed, Refresh, ThumbnailSpec};
+
+    let dir = std::env::temp_dir().join(format!("eidolon-refresh-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy("resources/SSSSSteven.png", dir.join("steven.png")).unwrap();
+    let provider = DirectoryProvider::new(&dir);
+    let id = PlayerId::parse("Steven");
+    let spec = ThumbnailSpec {
+        size: 32,
+        ..ThumbnailSpec::default()
+    };
+
+    let Refresh::Rendered { skin_hash, image } =
+        thumbnail_if_changed(&provider, &id, None, &spec).expect("first refresh failed")
+    else {
+        panic!("first refresh must render");
+    };
+    assert_eq!(skin_hash.len(), 16);
+    let skin = std::fs::read("resources/SSSSSteven.png").unwrap();
+    assert_eq!(image, thumbnail(&skin, &spec).unwrap());
+    let upper = skin_hash.to_uppercase();
+    assert_eq!(
+        thumbnail_if_changed(&provider, &id, Some(&upper), &spec).unwrap(),
+        Refresh::Unchanged
+    );
+
+    std::fs::copy("resources/bingling_sama.png", dir.join("steven.png")).unwrap();
+    match thumbnail_if_changed(&provider, &id, Some(&skin_hash), &spec).unwrap() {
+        Refresh::Rendered { skin_hash: new, .. } => assert_ne!(new, skin_hash),
+        Refresh::Unchanged => panic!("a new skin must render"),
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn thumbnail_encodes_requested_format_and_size() {
+    use eidolon::renderer::OutputFormat;
+    use eidolon::thumbnail::{thumbnail, CachePolicy, ThumbnailSpec, ThumbnailView};
+
+    let skin = std::fs::read("resources/bingling_sama.png").expect("read skin");
+    let spec = ThumbnailSpec {
+        size: 64,
+        view: ThumbnailView::Isometric,
+        ..ThumbnailSpec::default()
+    };
+    let png = thumbnail(&skin, &spec).expect("PNG thumbnail failed");
+    let decoded = image::load_from_memory_with_format(&png, image::ImageFormat::Png)
+        .expect("decode PNG thumbnail");
+    assert_eq!((decoded.width(), decoded.height()), (64, 64));
+    assert_eq!(thumbnail(&skin, &spec).expect("repeat thumbnail failed"), png);
+
+    let fresh = ThumbnailSpec {
+        cache: CachePolicy::Fresh,
+        ..spec
+    };
+    assert_eq!(thumbnail(&skin, &fresh).expect("fresh thumbnail failed"), png);
+
+    let webp = ThumbnailSpec {
+        format: OutputFormat::WebP,
+        background: [255, 255, 255, 255],
+        ..spec
+    };
+    let bytes = thumbnail(&skin, &webp).expect("WebP thumbnail failed");
+    let decoded = image::load_from_memory_with_format(&bytes, image::ImageFormat::WebP)
+        .expect("decode WebP thumbnail")
+        .to_rgba8();
+    assert_eq!(decoded.get_pixel(0, 0).0, [255, 255, 255, 255]);
+
+    let exr = ThumbnailSpec {
+        format: OutputFormat::Png16,
+        ..spec
+    };
+    assert!(thumbnail(&skin, &exr).is_err());
+    assert!(thumbnail(b"not a png", &spec).is_err());
+}
+
+#[test]
+fn render_to_image_embeds_metadata() {
+    use eidolon::metadata::RenderMetadata;
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let dir = std::env::temp_dir();
+    let plain = dir.join("eidolon_test_plain.png");
+    renderer
+        .render_to_image(
+            &character,
+            &skin,
+            &camera_default(),
+            plain.to_str().unwrap(),
+            (64, 48),
+            OutputFormat::Png,
+        )
+        .expect("PNG render_to_image failed");
+    assert_eq!(RenderMetadata::read_file(&plain).expect("read plain"), None);
+
+    renderer.set_embed_metadata(true);
+    for (name, format) in [
+        ("eidolon_test_meta.png", OutputFormat::Png),
+        ("eidolon_test_meta.webp", OutputFormat::WebP),
+        ("eidolon_test_meta16.png", OutputFormat::Png16),
+    ] {
+        let path = dir.join(name);
+        renderer
+            .render_to_image(
+                &character,
+                &skin,
+                &camera_default(),
+                path.to_str().unwrap(),
+                (64, 48),
+                format,
+            )
+            .expect("render_to_image with metadata failed");
+        let meta = RenderMetadata::read_file(&path)
+            .expect("read metadata")
+            .expect("metadata missing");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            meta,
+            renderer.render_metadata(&character, &skin, &camera_default(), (64, 48))
+        );
+        assert_eq!(
+            meta.get("skin-hash"),
+            Some(format!("{:016x}", skin.content_hash()).as_str())
+        );
+    }
+    let embedded = image::open(dir.join("eidolon_test_plain.png")).expect("plain unreadable");
+    assert_eq!((embedded.width(), embedded.height()), (64, 48));
+    let _ = std::fs::remove_file(&plain);
+}
+
+#[test]
+fn color_tagged_skins_load_as_srgb() {
+    // A linear-light (gAMA 1.0) skin: stored 128 is half intensity, sRGB 188.
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, 64, 64);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_source_gamma(png::ScaledFloat::new(1.0));
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(&[128, 128, 128, 255].repeat(64 * 64))
+        .unwrap();
+    let skin = SkinImage::from_memory(&png).expect("decode tagged skin");
+    assert_eq!(skin.rgba().get_pixel(0, 0).0, [188, 188, 188, 255]);
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let encode = |renderer: &Renderer| {
+        renderer
+            .encode(&character, &skin, &camera_default(), (64, 48), OutputFormat::Png)
+            .expect("encode failed")
+    };
+    let plain = encode(&renderer);
+    renderer.set_embed_srgb(true);
+    let tagged = encode(&renderer);
+    let reader = png::Decoder::new(std::io::Cursor::new(&tagged))
+        .read_info()
+        .unwrap();
+    assert!(reader.info().srgb.is_some());
+    assert_eq!(
+        image::load_from_memory(&tagged).unwrap().to_rgba8(),
+        image::load_from_memory(&plain).unwrap().to_rgba8()
+    );
+}
+
+#[test]
+fn deterministic_renders_are_bit_identical() {
+    use eidolon::renderer::ExrChannels;
+
+    let renderer = Renderer::new_deterministic().expect("no software adapter");
+    assert!(renderer.is_deterministic());
+    assert!(!make_renderer().is_deterministic());
+    let (character, skin) = character_with_skin(&renderer);
+    let dir = std::env::temp_dir();
+    for (name, format) in [
+        ("eidolon_test_det.png", OutputFormat::Png),
+        ("eidolon_test_det.exr", OutputFormat::Exr(ExrChannels::default())),
+    ] {
+        let path = dir.join(name);
+        let mut runs = Vec::new();
+        for _ in 0..2 {
+            renderer
+                .render_to_image(
+                    &character,
+                    &skin,
+                    &camera_default(),
+                    path.to_str().unwrap(),
+                    (64, 96),
+                    format,
+                )
+                .expect("deterministic render_to_image failed");
+            runs.push(std::fs::read(&path).expect("read output"));
+        }
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(runs[0], runs[1], "{name} differs between runs");
+    }
+}
+
+#[test]
+fn render_crowd_single_member_matches_render() {
+    use eidolon::renderer::CrowdMember;
+
+    let renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let single = renderer
+        .render(&character, &skin, &camera_default(), 96, 72)
+        .expect("render failed");
+    let member = CrowdMember {
+        character: character.clone(),
+        skin: &skin,
+    };
+    let crowd = renderer
+        .render_crowd(&[member], &camera_default(), 96, 72)
+        .expect("render_crowd failed");
+    assert_eq!(single, crowd);
+
+    let empty = renderer
+        .render_crowd(&[], &camera_default(), 8, 8)
+        .expect("empty render_crowd failed");
+    assert!(empty.pixels().all(|p| p[3] == 0));
+}
+
+#[test]
+fn render_crowd_draws_every_member() {
+    use cgmath::Vector3;
+    use eidolon::renderer::CrowdMember;
+
+    let renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let other = renderer
+        .load_texture("resources/SSSSSteven.png")
+        .expect("Failed to load skin");
+    let members: Vec<_> = (0..5)
+        .map(|i| CrowdMember {
+            character: Character {
+                position: Vector3::new((i as f32 - 2.0) * 1.5, 0.0, 0.0),
+                skin_type: if i % 2 == 0 { SkinType::Classic } else { SkinType::Slim },
+                posture: DefaultPostures::STAND,
+                ..character.clone()
+            },
+            skin: if i < 3 { &skin } else { &other },
+        })
+        .collect();
+    let image = renderer
+        .render_crowd(&members, &camera_default(), 400, 200)
+        .expect("render_crowd failed");
+    // Members stand apart, so the covered columns form one run per member.
+    let covered: Vec<bool> = (0..image.width())
+        .map(|x| (0..image.height()).any(|y| image.get_pixel(x, y)[3] > 0))
+        .collect();
+    let runs = covered.windows(2).filter(|w| !w[0] && w[1]).count();
+    assert_eq!(runs, 5);
+}
+
+#[test]
+fn models_load_on_first_render_of_their_skin_type() {
+    let renderer = make_renderer();
+    assert!(!renderer.is_model_loaded(SkinType::Classic));
+    assert!(!renderer.is_model_loaded(SkinType::Slim));
+
+    let (character, skin) = character_with_skin(&renderer);
+    renderer
+        .render(&character, &skin, &camera_default(), 32, 32)
+        .expect("classic render failed");
+    assert!(renderer.is_model_loaded(SkinType::Classic));
+    assert!(!renderer.is_model_loaded(SkinType::Slim));
+}
+
+#[test]
+fn custom_model_replaces_bundled_one() {
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let bundled = renderer
+        .render(&character, &skin, &camera_default(), 64, 64)
+        .expect("bundled render failed");
+
+    // The slim mesh under the classic skin type: thinner arms, different image.
+    let slim = std::fs::read("resources/slim.obj").expect("read slim.obj");
+    renderer
+        .set_model_from_obj_bytes(SkinType::Classic, &slim, "slim.obj")
+        .expect("custom model failed to load");
+    assert!(renderer.is_model_loaded(SkinType::Classic));
+    let custom = renderer
+        .render(&character, &skin, &camera_default(), 64, 64)
+        .expect("custom render failed");
+    assert_ne!(bundled.as_raw(), custom.as_raw());
+
+    assert!(renderer
+        .set_model_from_obj_bytes(SkinType::Slim, b"not an obj", "junk.obj")
+        .is_err());
+}
+
+#[test]
+fn shader_variants_compile_once_per_feature_set() {
+    use eidolon::renderer::{RenderOptions, ShadowSettings};
+
+    let mut renderer = make_renderer();
+    assert_eq!(renderer.shader_variant_count(), 0);
+    let (character, skin) = character_with_skin(&renderer);
+    let render = |renderer: &Renderer| {
+        renderer
+            .render(&character, &skin, &camera_default(), 32, 32)
+            .expect("render failed")
+    };
+
+    let plain = render(&renderer);
+    render(&renderer);
+    assert_eq!(renderer.shader_variant_count(), 1);
+
+    renderer.set_options(RenderOptions {
+        shadow: Some(ShadowSettings::default()),
+        ..RenderOptions::default()
+    });
+    render(&renderer);
+    assert_eq!(renderer.shader_variant_count(), 2);
+
+    renderer.set_options(RenderOptions::default());
+    assert_eq!(render(&renderer).as_raw(), plain.as_raw());
+    assert_eq!(renderer.shader_variant_count(), 2);
+}
+
+#[test]
+fn warm_up_prepares_first_render() {
+    use eidolon::renderer::{RenderOptions, ShadowSettings};
+
+    let mut renderer = make_renderer();
+    let shadowed = RenderOptions {
+        shadow: Some(ShadowSettings::default()),
+        ..RenderOptions::default()
+    };
+    renderer
+        .warm_up(64, 64, std::slice::from_ref(&shadowed))
+        .expect("warm-up failed");
+    assert!(renderer.is_model_loaded(SkinType::Classic));
+    assert!(renderer.is_model_loaded(SkinType::Slim));
+    let variants = renderer.shader_variant_count();
+    assert_eq!(variants, 2);
+
+    let (character, skin) = character_with_skin(&renderer);
+    renderer
+        .render(&character, &skin, &camera_default(), 64, 64)
+        .expect("render after warm-up failed");
+    assert_eq!(renderer.shader_variant_count(), variants);
+    // A warmed-up feature set does not compile on its first render either.
+    renderer.set_options(shadowed);
+    renderer
+        .render(&character, &skin, &camera_default(), 64, 64)
+        .expect("shadowed render after warm-up failed");
+    assert_eq!(renderer.shader_variant_count(), variants);
+}
+
+#[test]
+fn invalid_dimensions_are_rejected_before_rendering() {
+    use eidolon::error::EidolonError;
+
+    let renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let max = renderer.max_output_size();
+    assert!(max >= 1024, "device limit unexpectedly small: {max}");
+    for (width, height) in [(0, 64), (64, 0), (max + 1, 64), (20000, 20000)] {
+        let err = renderer
+            .render(&character, &skin, &camera_default(), width, height)
+            .unwrap_err();
+        assert!(
+            matches!(err, EidolonError::InvalidDimensions { width: w, height: h, max: m }
+                if (w, h, m) == (width, height, max)),
+            "{width}x{height}: {err}"
+        );
+    }
+    assert!(matches!(
+        renderer.render_hdr(&character, &skin, &camera_default(), 0, 0),
+        Err(EidolonError::InvalidDimensions { .. })
+    ));
+    // The renderer is still usable afterwards.
+    renderer
+        .render(&character, &skin, &camera_default(), 32, 32)
+        .expect("render after rejected size failed");
+}
+
+#[test]
+fn render_skin_image_matches_renderer() {
+    use eidolon::{render_skin_image, RenderRequest};
+
+    let bytes = std::fs::read("resources/bingling_sama.png").expect("read skin");
+    let renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let expected = renderer
+        .render(&character, &skin, &camera_default(), 96, 64)
+        .expect("render failed");
+
+    let image = render_skin_image(RenderRequest {
+        character: character.clone(),
+        camera: camera_default(),
+        width: 96,
+        height: 64,
+        ..RenderRequest::new(&bytes)
+    })
+    .expect("render_skin_image failed");
+    assert_eq!(image.dimensions(), (96, 64));
+    assert_eq!(image.as_raw(), expected.as_raw());
+
+    // Waiting with a timeout polls the device instead of blocking; the result is the same.
+    let image = render_skin_image(RenderRequest {
+        character: character.clone(),
+        camera: camera_default(),
+        width: 96,
+        height: 64,
+        gpu_timeout: Some(std::time::Duration::from_secs(60)),
+        ..RenderRequest::new(&bytes)
+    })
+    .expect("render_skin_image with a GPU timeout failed");
+    assert_eq!(image.as_raw(), expected.as_raw());
+
+    assert!(render_skin_image(RenderRequest::new(b"not a png")).is_err());
+}
+
+#[test]
+fn render_skin_image_with_theme_returns_the_palette() {
+    use eidolon::theme::ThemePalette;
+    use eidolon::{render_skin_image, render_skin_image_with_theme, RenderRequest};
+
+    let bytes = std::fs::read("resources/bingling_sama.png").expect("read skin");
+    let request = RenderRequest {
+        width: 64,
+        height: 64,
+        ..RenderRequest::new(&bytes)
+    };
+    let (image, palette) =
+        render_skin_image_with_theme(request.clone()).expect("render_skin_image_with_theme failed");
+    assert_eq!(image.as_raw(), render_skin_image(request).unwrap().as_raw());
+    let skin = SkinImage::from_memory(&bytes).unwrap();
+    assert_eq!(palette, ThemePalette::from_skin(&skin, SkinType::Classic));
+    assert_ne!(palette.primary, palette.secondary);
+    assert_ne!(palette.primary, palette.accent);
+}
+
+#[test]
+fn self_test_passes_and_leaves_requests_unaffected() {
+    use eidolon::request::self_test;
+    use eidolon::{render_skin_image, RenderRequest};
+
+    let bytes = std::fs::read("resources/SSSSSteven.png").expect("read skin");
+    let request = RenderRequest {
+        width: 32,
+        height: 32,
+        ..RenderRequest::new(&bytes)
+    };
+    let before = render_skin_image(request.clone()).unwrap();
+    self_test().expect("self-test failed");
+    assert_eq!(render_skin_image(request).unwrap().as_raw(), before.as_raw());
+}
+
+#[test]
+fn skin_image_decoded_off_thread_uploads_like_load_texture() {
+    use eidolon::texture::SkinImage;
+
+    let decoded = std::thread::spawn(|| SkinImage::from_file("resources/bingling_sama.png"))
+        .join()
+        .unwrap()
+        .expect("decode failed");
+    let renderer = make_renderer();
+    let (character, loaded) = character_with_skin(&renderer);
+    let uploaded = renderer.upload_skin(&decoded);
+    assert_eq!(uploaded.content_hash(), loaded.content_hash());
+    assert_eq!(decoded.content_hash(), loaded.content_hash());
+
+    let a = renderer
+        .render(&character, &loaded, &camera_default(), 48, 48)
+        .expect("render failed");
+    let b = renderer
+        .render(&character, &uploaded, &camera_default(), 48, 48)
+        .expect("render failed");
+    assert_eq!(a.as_raw(), b.as_raw());
+}
+
+#[cfg(feature = "dev")]
+#[test]
+fn watched_shader_reloads_and_keeps_last_good_one() {
+    use eidolon::renderer::AssetOverrides;
+    use std::time::{Duration, SystemTime};
+
+    let dir = std::env::temp_dir().join(format!("eidolon-watch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("skin.wgsl");
+    // Stamp each save a second apart: back-to-back writes can share a modification time.
+    let save = |source: &str, second: u64| {
+        std::fs::write(&path, source).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(second))
+            .unwrap();
+    };
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let render = |renderer: &Renderer| {
+        renderer
+            .render(&character, &skin, &camera_default(), 32, 32)
+            .expect("render failed")
+    };
+    let plain = render(&renderer);
+
+    let red = eidolon::constants::SHADER.replacen(
+        "return shade(in, tex_color);",
+        "return vec4<f32>(1.0, 0.0, 0.0, 1.0);",
+        1,
+    );
+    save(&red, 1);
+    renderer
+        .watch_assets(&AssetOverrides {
+            shader: Some(path.clone()),
+            ..AssetOverrides::default()
+        })
+        .expect("override shader should load");
+    let tinted = render(&renderer);
+    assert_ne!(tinted.as_raw(), plain.as_raw());
+    assert!(tinted.pixels().any(|p| p.0 == [255, 0, 0, 255]));
+    assert!(!renderer.reload_changed_assets().unwrap(), "nothing changed");
+
+    save("fn broken(", 2);
+    assert!(renderer.reload_changed_assets().is_err());
+    assert_eq!(render(&renderer).as_raw(), tinted.as_raw());
+
+    save(eidolon::constants::SHADER, 3);
+    assert!(renderer.reload_changed_assets().unwrap());
+    assert_eq!(render(&renderer).as_raw(), plain.as_raw());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn processed_uploads_are_stable_and_render() {
+    use eidolon::upload::{process_upload, UploadPolicy};
+
+    let renderer = make_renderer();
+    for file in [
+        "resources/bingling_sama.png",
+        "resources/undefinedR2.png",
+        "resources/SSSSSteven.png",
+    ] {
+        let bytes = std::fs::read(file).unwrap();
+        let processed = process_upload(&bytes, &UploadPolicy::default()).expect(file);
+        let again = process_upload(&processed.png, &UploadPolicy::default()).unwrap();
+        assert_eq!(again.png, processed.png, "{file}: processing is not idempotent");
+
+        let character = Character {
+            skin_type: processed.skin_type,
+            ..Character::new()
+        };
+        let skin = renderer.upload_skin(&processed.skin);
+        let image = renderer
+            .render(&character, &skin, &camera_default(), 32, 32)
+            .expect("render failed");
+        assert!(image.pixels().any(|p| p[3] > 0), "{file}");
+    }
+}
+
+#[test]
+fn bedrock_geometry_replaces_the_player_model() {
+    use eidolon::bedrock::BedrockGeometry;
+
+    // Head and body only, with a horn on the head and no hat layer.
+    let geometry = br#"{
+        "format_version": "1.12.0",
+        "minecraft:geometry": [{
+            "description": {"identifier": "geometry.test", "texture_width": 64, "texture_height": 64},
+            "bones": [
+                {"name": "body", "pivot": [0, 24, 0],
+                 "cubes": [{"origin": [-4, 12, -2], "size": [8, 12, 4], "uv": [16, 16]}]},
+                {"name": "head", "parent": "body", "pivot": [0, 24, 0],
+                 "cubes": [{"origin": [-4, 24, -4], "size": [8, 8, 8], "uv": [0, 0]}]},
+                {"name": "horn", "parent": "head", "pivot": [0, 32, 0],
+                 "cubes": [{"origin": [-1, 32, -1], "size": [2, 6, 2], "uv": [0, 0]}]}
+            ]
+        }]
+    }"#;
+    let geometry = BedrockGeometry::find(geometry, "geometry.test").unwrap();
+
+    let mut renderer = make_renderer();
+    let (character, skin) = character_with_skin(&renderer);
+    let camera = Camera {
+        scale: 0.5,
+        ..camera_default()
+    };
+    let bundled = renderer.render(&character, &skin, &camera, 64, 64).unwrap();
+    renderer
+        .set_model_from_bedrock(character.skin_type, &geometry)
+        .expect("geometry should build");
+    let custom = renderer.render(&character, &skin, &camera, 64, 64).unwrap();
+
+    let covered = |image: &image::RgbaImage| image.pixels().filter(|p| p[3] > 0).count();
+    assert!(covered(&custom) > 0);
+    assert_ne!(custom.as_raw(), bundled.as_raw());
+    // No arms or legs, so less of the frame is covered.
+    assert!(covered(&custom) < covered(&bundled));
+}
+
+#[test]
+fn server_lineup_renders_listed_players() {
+    use eidolon::provider::{DirectoryProvider, SkinData};
+    use eidolon::server::{fetch_lineup, render_lineup, ServerStatus};
+
+    let status = ServerStatus::from_json(
+        br#"{"players": {"online": 4, "max": 10, "sample": [
+            {"name": "Steven", "id": "069a79f4-44e9-4726-a5be-fca90e38aaf5"},
+            {"name": "Line of text", "id": "00000000-0000-0000-0000-000000000000"},
+            {"name": "bingling_sama", "id": "not-a-uuid"},
+            {"name": "Unknown", "id": "853c80ef-3c37-49fd-aa49-938b674adae6"}
+        ]}}"#,
+    )
+    .expect("status parse failed");
+    let dir = std::env::temp_dir().join("eidolon_test_lineup");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy(
+        "resources/SSSSSteven.png",
+        dir.join("069a79f444e94726a5befca90e38aaf5.png"),
+    )
+    .unwrap();
+    std::fs::copy("resources/bingling_sama.png", dir.join("bingling_sama.png")).unwrap();
+
+    let renderer = make_renderer();
+    let provider = DirectoryProvider::new(&dir);
+    let skins = fetch_lineup(&renderer, status.players(), &provider, None).unwrap();
+    let names: Vec<_> = skins.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["Steven", "bingling_sama"]);
+
+    let fallback = SkinData {
+        bytes: std::fs::read("resources/undefinedR2.png").unwrap(),
+        skin_type: Some(SkinType::Slim),
+        signature: Default::default(),
+    };
+    let skins = fetch_lineup(&renderer, status.players(), &provider, Some(&fallback)).unwrap();
+    assert_eq!(skins.len(), 3);
+    assert_eq!(skins[2].skin_type, SkinType::Slim);
+
+    let image = render_lineup(&renderer, &skins, 300, 100).expect("render_lineup failed");
+    let covered: Vec<bool> = (0..image.width())
+        .map(|x| (0..image.height()).any(|y| image.get_pixel(x, y)[3] > 0))
+        .collect();
+    let runs = covered.windows(2).filter(|w| !w[0] && w[1]).count();
+    assert_eq!(runs, 3);
+    // The lineup fits: nobody touches the image border.
+    assert!(!covered[0] && !covered[covered.len() - 1]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn card_template_composes_character_and_text() {
+    use eidolon::card::{CardData, CardTemplate};
+
+    let template = CardTemplate::from_json(
+        br##"{
+            "size": [320, 120],
+            "background": "#202020",
+            "fonts": {"regular": "Cantarell-Regular.ttf"},
+            "character": {"x": 0, "y": 0, "width": 100, "height": 120},
+            "texts": [
+                {"text": "{username}", "x": 110, "y": 10, "size": 32, "font": "regular", "color": "#ff0000"}
+            ]
+        }"##,
+        |file| Ok(std::fs::read(format!("resources/{file}"))?),
+    )
+    .expect("template parse failed");
+    let renderer = make_renderer();
+    let (_, skin) = character_with_skin(&renderer);
+    let data = CardData {
+        skin: &skin,
+        skin_type: SkinType::Classic,
+        values: [("username".to_string(), "Notch".to_string())].into(),
+    };
+    let card = template.render(&renderer, &data).expect("card render failed");
+    assert_eq!(card.dimensions(), (320, 120));
+
+    let background = image::Rgba([32, 32, 32, 255]);
+    assert_eq!(*card.get_pixel(319, 119), background);
+    // The character covers the middle of its slot.
+    assert_ne!(*card.get_pixel(50, 60), background);
+    let red = |x0: u32, x1: u32| {
+        (x0..x1).any(|x| (0..60).any(|y| card.get_pixel(x, y).0 == [255, 0, 0, 255]))
+    };
+    assert!(red(110, 320));
+    assert!(!red(0, 100));
+}
+
+#[test]
+fn shipped_skins_pass_the_skin_tone_heuristic() {
+    use eidolon::moderation::{SkinToneHeuristic, Verdict};
+    use eidolon::upload::{process_upload_moderated, UploadPolicy};
+
+    for file in [
+        "resources/bingling_sama.png",
+        "resources/undefinedR2.png",
+        "resources/SSSSSteven.png",
+    ] {
+        let bytes = std::fs::read(file).unwrap();
+        let (_, report) = process_upload_moderated(
+            &bytes,
+            &UploadPolicy::default(),
+            &SkinToneHeuristic::default(),
+        )
+        .expect(file);
+        assert_eq!(report.verdict, Verdict::Allow, "{file}: {:?}", report.findings);
+    }
+}